@@ -1,6 +1,6 @@
-// Example usage of @codeloom/node
+// Example usage of @infiniloom/node
 
-const { pack, scan, countTokens, CodeLoom } = require('./index');
+const { pack, scan, countTokens, Infiniloom, Severity } = require('./index');
 
 // Example 1: Simple packing
 console.log('=== Example 1: Simple Packing ===');
@@ -20,14 +20,14 @@ try {
 // Example 2: Repository scanning
 console.log('\n=== Example 2: Repository Scanning ===');
 try {
-  const stats = scan('.', 'claude');
-  console.log(`Repository: ${stats.name}`);
-  console.log(`Total files: ${stats.totalFiles}`);
-  console.log(`Total lines: ${stats.totalLines}`);
-  console.log(`Total tokens: ${stats.totalTokens}`);
-  console.log(`Primary language: ${stats.primaryLanguage || 'N/A'}`);
-  console.log(`Languages:`, stats.languages);
-  console.log(`Security findings: ${stats.securityFindings}`);
+  const report = scan('.', 'claude');
+  console.log(`Repository: ${report.repository}`);
+  console.log(`Total files: ${report.files}`);
+  console.log(`Total lines: ${report.total_lines}`);
+  console.log(`Total tokens: ${report.tokens.claude}`);
+  console.log(`Primary language: ${report.languages[0]?.language || 'N/A'}`);
+  console.log(`Languages:`, report.languages);
+  console.log(`Largest files:`, report.largest_files);
 } catch (error) {
   console.error('Error:', error.message);
 }
@@ -40,18 +40,19 @@ console.log(`Tokens (claude): ${countTokens(text, 'claude')}`);
 console.log(`Tokens (gpt-4o): ${countTokens(text, 'gpt-4o')}`);
 console.log(`Tokens (gemini): ${countTokens(text, 'gemini')}`);
 
-// Example 4: Advanced usage with CodeLoom class
-console.log('\n=== Example 4: CodeLoom Class ===');
+// Example 4: Advanced usage with Infiniloom class
+console.log('\n=== Example 4: Infiniloom Class ===');
 try {
-  const loom = new CodeLoom('.', 'claude');
+  const loom = new Infiniloom('.', 'claude');
 
   // Get statistics
   const stats = loom.getStats();
   console.log(`Stats:`, stats);
 
   // Generate map
-  const map = loom.generateMap(1000, 20);
-  console.log(`Map generated, length: ${map.length}`);
+  const map = loom.getMap(1000, 20);
+  console.log(`Map generated, ${map.keySymbols.length} key symbols`);
+  map.keySymbols.slice(0, 5).forEach(s => console.log(`  ${s.kind} ${s.name} (${s.file}:${s.line})`));
 
   // Pack with options
   const context = loom.pack({
@@ -61,10 +62,13 @@ try {
   console.log(`Packed context length: ${context.length}`);
 
   // Security scan
-  const findings = loom.securityScan();
+  const findings = loom.securityFindings();
   if (findings.length > 0) {
     console.warn('Security issues found:');
-    findings.forEach(finding => console.warn(`  - ${finding}`));
+    findings.forEach(f => console.warn(`  - ${f.kind} in ${f.file}:${f.line}`));
+    if (findings.some(f => f.severity === Severity.Critical)) {
+      console.warn('  (includes critical findings)');
+    }
   } else {
     console.log('No security issues found');
   }
@@ -5,46 +5,212 @@
 
 /** Options for packing a repository */
 export interface PackOptions {
-  /** Output format: "xml", "markdown", or "json" */
-  format?: string
-  /** Target model: "claude", "gpt-4o", "gpt-4", "gemini", or "llama" */
-  model?: string
-  /** Compression level: "none", "minimal", "balanced", "aggressive", "extreme", "semantic" */
-  compression?: string
+  /** Output format (default: "xml") */
+  format?: 'xml' | 'markdown' | 'md' | 'json' | 'yaml' | 'toon' | 'plain' | 'signatures'
+  /** Target model (default: "claude") */
+  model?: 'claude' | 'gpt-4o' | 'gpt4o' | 'gpt-4' | 'gpt4' | 'gemini' | 'llama'
+  /** Compression level (default: "balanced") */
+  compression?: 'none' | 'minimal' | 'balanced' | 'aggressive' | 'extreme' | 'semantic'
   /** Token budget for repository map */
   mapBudget?: number
   /** Maximum number of symbols in map */
   maxSymbols?: number
   /** Skip security scanning */
   skipSecurity?: boolean
+  /**
+   * Only pack files changed since this branch or commit, plus the files
+   * they import or are imported by
+   */
+  diffBase?: string
+  /** Only pack the files relevant to this question, most relevant first */
+  query?: string
 }
-/** Statistics from scanning a repository */
-export interface ScanStats {
+/** Report from scanning a repository, the same layout as `infiniloom scan --json` */
+export interface ScanReport {
+  /** Report layout version */
+  schema_version: number
   /** Repository name */
-  name: string
-  /** Total number of files */
-  totalFiles: number
-  /** Total lines of code */
-  totalLines: number
-  /** Total tokens for target model */
-  totalTokens: number
-  /** Primary language */
-  primaryLanguage?: string
-  /** Language breakdown */
-  languages: Array<LanguageStat>
-  /** Number of security findings */
-  securityFindings: number
-}
-/** Statistics for a single language */
-export interface LanguageStat {
-  /** Language name */
-  language: string
+  repository: string
+  /** Scanned path */
+  path: string
   /** Number of files */
   files: number
+  /** Total size in bytes */
+  total_bytes: number
   /** Total lines */
-  lines: number
-  /** Percentage of codebase */
-  percentage: number
+  total_lines: number
+  /** Total tokens for each model */
+  tokens: { claude: number; gpt4o: number; gpt4: number; gemini: number; llama: number }
+  /** Per-language breakdown, most files first */
+  languages: Array<{ language: string; files: number; lines: number; bytes: number; percentage: number }>
+  /** Largest files by size, largest first */
+  largest_files: Array<{ path: string; language?: string; bytes: number; tokens: number }>
+  /** Import graph summary */
+  dependencies: {
+    internal_edges: number
+    unresolved_imports: number
+    circular_groups: number
+    external: Array<{ package: string; files: number }>
+  }
+  /** Branch and commit, for git repositories */
+  git?: { branch?: string; commit?: string }
+  /** Detected framework */
+  framework?: string
+  /** Inferred kinds of project */
+  topics: Array<string>
+}
+/** Severity of a security finding */
+export enum Severity {
+  Low = 'low',
+  Medium = 'medium',
+  High = 'high',
+  Critical = 'critical'
+}
+/** Kind of a code symbol */
+export enum SymbolKind {
+  Function = 'function',
+  Method = 'method',
+  Class = 'class',
+  Interface = 'interface',
+  Struct = 'struct',
+  Enum = 'enum',
+  Constant = 'constant',
+  Variable = 'variable',
+  Import = 'import',
+  Export = 'export',
+  TypeAlias = 'type',
+  Module = 'module',
+  Trait = 'trait',
+  Macro = 'macro'
+}
+/** A potential secret found by the security scanner */
+export interface SecurityFinding {
+  /** Kind of secret, e.g. "AWS Credential" */
+  kind: string
+  /** File the secret was found in */
+  file: string
+  /** Line number (1-based) */
+  line: number
+  /** Matched text, redacted */
+  pattern: string
+  /** Severity level */
+  severity: Severity
+}
+/** A chunk budget for one model */
+export interface ModelBudget {
+  /** Model name, e.g. "gpt-4o" */
+  model: 'claude' | 'gpt-4o' | 'gpt4o' | 'gpt-4' | 'gpt4' | 'gemini' | 'llama'
+  /** Token budget per chunk */
+  maxTokens: number
+}
+/** A file within a chunk */
+export interface ChunkFile {
+  /** Stable file ID, the same in every pack of the repository */
+  id: string
+  /** Relative file path */
+  path: string
+  /** File content */
+  content: string
+  /** Token count for the plan's model */
+  tokens: number
+}
+/** A chunk of the repository */
+export interface Chunk {
+  /** Chunk index (0-based) */
+  index: number
+  /** Total number of chunks in the plan */
+  total: number
+  /** Focus/theme of this chunk */
+  focus: string
+  /** Token count for the plan's model */
+  tokens: number
+  /** Files in this chunk */
+  files: Array<ChunkFile>
+}
+/** Chunks of the repository for one model */
+export interface ChunkPlan {
+  /** Model name */
+  model: string
+  /** Token budget per chunk */
+  maxTokens: number
+  /** The chunks */
+  chunks: Array<Chunk>
+}
+/** A ranked symbol in the repository map */
+export interface MapSymbol {
+  /**
+   * Stable symbol ID, the same in every pack of the repository; imports
+   * have none
+   */
+  id?: string
+  /** Symbol name */
+  name: string
+  /** Symbol kind */
+  kind: SymbolKind
+  /** File containing the symbol */
+  file: string
+  /** Line number */
+  line: number
+  /** Function/method signature */
+  signature?: string
+  /** Number of references */
+  references: number
+  /** Rank (1 = most important) */
+  rank: number
+  /** Importance score (0.0 - 1.0) */
+  importance: number
+}
+/** A symbol and the region of its file it covers */
+export interface SymbolRange {
+  /** Symbol name */
+  name: string
+  /** Symbol kind */
+  kind: SymbolKind
+  /** Parent symbol name, for methods */
+  parent?: string
+  /** File containing the symbol, relative to the repository root */
+  file: string
+  /** First line (1-based) */
+  startLine: number
+  /** Last line (1-based, inclusive) */
+  endLine: number
+  /** UTF-8 byte offset of the first line in the file */
+  startByte?: number
+  /** UTF-8 byte offset just past the last line, without its line break */
+  endByte?: number
+}
+/** A module (directory) in the repository map */
+export interface MapModule {
+  /** Module name */
+  name: string
+  /** Number of files in the module */
+  files: number
+  /** Total tokens in the module */
+  tokens: number
+}
+/** A file in the repository map index */
+export interface MapFile {
+  /** Relative file path */
+  path: string
+  /** Token count */
+  tokens: number
+  /** Importance level */
+  importance: 'critical' | 'high' | 'normal' | 'low'
+  /** Brief summary */
+  summary?: string
+}
+/** Repository map with the most important symbols and files */
+export interface RepoMap {
+  /** Text summary of the repository */
+  summary: string
+  /** Most important symbols, best first */
+  keySymbols: Array<MapSymbol>
+  /** Modules (directories) with their sizes */
+  modules: Array<MapModule>
+  /** Index of all files */
+  fileIndex: Array<MapFile>
+  /** Total token count for this map */
+  tokenCount: number
 }
 /**
  * Pack a repository into optimized LLM context
@@ -58,7 +224,7 @@ export interface LanguageStat {
  *
  * # Example
  * ```javascript
- * const { pack } = require('@codeloom/node');
+ * const { pack } = require('@infiniloom/node');
  *
  * const context = pack('./my-repo', {
  *   format: 'xml',
@@ -70,56 +236,145 @@ export interface LanguageStat {
  */
 export declare function pack(path: string, options?: PackOptions | undefined | null): string
 /**
- * Scan a repository and return statistics
+ * Pack a tar, tar.gz or zip archive held in memory
+ *
+ * Nothing is written to disk, so uploaded archives can be packed where the
+ * filesystem is read-only, such as in serverless functions. A single
+ * top-level directory, as in GitHub tarballs, is stripped and names the
+ * repository. `.gitignore` files inside the archive are not applied, and
+ * `diffBase` is not supported since an archive has no git history.
+ *
+ * # Arguments
+ * * `data` - Archive bytes
+ * * `options` - Optional packing options
+ *
+ * # Returns
+ * Formatted repository context as a string
+ *
+ * # Example
+ * ```javascript
+ * const { packArchive } = require('@infiniloom/node');
+ *
+ * const context = packArchive(fs.readFileSync('repo.tar.gz'), { format: 'xml' });
+ * ```
+ */
+export declare function packArchive(data: Buffer, options?: PackOptions | undefined | null): string
+/**
+ * Scan a repository and return its scan report
+ *
+ * The report has the same keys as `infiniloom scan --json` and the Python
+ * `scan()`, versioned by `schema_version`.
  *
  * # Arguments
  * * `path` - Path to repository root
  * * `model` - Optional target model (default: "claude")
  *
  * # Returns
- * Repository statistics
+ * The scan report
  *
  * # Example
  * ```javascript
- * const { scan } = require('@codeloom/node');
+ * const { scan } = require('@infiniloom/node');
  *
- * const stats = scan('./my-repo', 'claude');
- * console.log(`Total files: ${stats.total_files}`);
- * console.log(`Total tokens: ${stats.total_tokens}`);
+ * const report = scan('./my-repo');
+ * console.log(`Total files: ${report.files}`);
+ * console.log(`Claude tokens: ${report.tokens.claude}`);
  * ```
  */
-export declare function scan(path: string, model?: string | undefined | null): ScanStats
+export declare function scan(path: string, model?: 'claude' | 'gpt-4o' | 'gpt4o' | 'gpt-4' | 'gpt4' | 'gemini' | 'llama' | undefined | null): ScanReport
+/** Progress of `packAsync` or `scanAsync` */
+export interface Progress {
+  /**
+   * "scan" while files are read, then "pack" while the context is
+   * compressed, mapped and formatted
+   */
+  phase: 'scan' | 'pack'
+  /** Files scanned so far */
+  filesDone: number
+}
+/**
+ * Pack a repository without blocking the event loop
+ *
+ * Like `pack()`, but scanning and formatting run on the libuv thread pool
+ * and the result comes back as a promise. `onProgress` is called with the
+ * phase and the number of files scanned so far. Aborting `signal` stops the
+ * work between files and rejects the promise with an error whose `code` is
+ * "Cancelled".
+ *
+ * # Example
+ * ```javascript
+ * const { packAsync } = require('@infiniloom/node');
+ *
+ * const controller = new AbortController();
+ * setTimeout(() => controller.abort(), 10_000);
+ * const context = await packAsync('./my-repo', { format: 'xml' }, (p) => {
+ *   console.log(`${p.phase}: ${p.filesDone} files`);
+ * }, controller.signal);
+ * ```
+ */
+export declare function packAsync(path: string, options?: PackOptions | undefined | null, onProgress?: ((progress: Progress) => void) | undefined | null, signal?: AbortSignal | undefined | null): Promise<string>
+/**
+ * Scan a repository without blocking the event loop
+ *
+ * Like `scan()`, but the scan runs on the libuv thread pool and the report
+ * comes back as a promise. `onProgress` is called with the number of files
+ * scanned so far. Aborting `signal` stops the scan and rejects the promise
+ * as for `packAsync`.
+ *
+ * # Example
+ * ```javascript
+ * const { scanAsync } = require('@infiniloom/node');
+ *
+ * const report = await scanAsync('./my-repo', 'claude', (p) => console.log(p.filesDone));
+ * ```
+ */
+export declare function scanAsync(path: string, model?: 'claude' | 'gpt-4o' | 'gpt4o' | 'gpt-4' | 'gpt4' | 'gemini' | 'llama' | undefined | null, onProgress?: ((progress: Progress) => void) | undefined | null, signal?: AbortSignal | undefined | null): Promise<ScanReport>
 /**
  * Count tokens in text for a specific model
  *
+ * GPT-4 and GPT-4o counts come from their BPE tokenizers; other models are
+ * estimated.
+ *
  * # Arguments
  * * `text` - Text to tokenize
  * * `model` - Optional model name (default: "claude")
+ * * `exact` - Use the exact tokenizer where one exists (default: true);
+ *   `false` always estimates, which is faster on large inputs
  *
  * # Returns
  * Token count
  *
  * # Example
  * ```javascript
- * const { countTokens } = require('@codeloom/node');
+ * const { countTokens } = require('@infiniloom/node');
  *
- * const count = countTokens('Hello, world!', 'claude');
+ * const count = countTokens('Hello, world!', 'gpt-4o');
  * console.log(`Tokens: ${count}`);
  * ```
  */
-export declare function countTokens(text: string, model?: string | undefined | null): number
-/** CodeLoom class for advanced usage */
-export declare class CodeLoom {
+export declare function countTokens(text: string, model?: 'claude' | 'gpt-4o' | 'gpt4o' | 'gpt-4' | 'gpt4' | 'gemini' | 'llama' | undefined | null, exact?: boolean | undefined | null): number
+/**
+ * Infiniloom class for advanced usage
+ *
+ * Generated repository maps are cached per (budget, max symbols), so calling
+ * `pack()` repeatedly with different formats only pays for formatting. Call
+ * `invalidate()` after the files on disk change.
+ */
+export declare class Infiniloom {
   /**
-   * Create a new CodeLoom instance
+   * Create a new Infiniloom instance
    *
    * # Arguments
    * * `path` - Path to repository root
    * * `model` - Optional model name (default: "claude")
    */
-  constructor(path: string, model?: string | undefined | null)
-  /** Get repository statistics */
-  getStats(): ScanStats
+  constructor(path: string, model?: 'claude' | 'gpt-4o' | 'gpt4o' | 'gpt-4' | 'gpt4' | 'gemini' | 'llama' | undefined | null)
+  /** Rescan the repository and drop all cached maps */
+  invalidate(): void
+  /** Number of repository maps currently cached */
+  get cachedMaps(): number
+  /** Get the scan report, as returned by `scan()` */
+  getStats(): ScanReport
   /**
    * Generate a repository map
    *
@@ -128,8 +383,42 @@ export declare class CodeLoom {
    * * `max_symbols` - Maximum symbols (default: 50)
    */
   generateMap(budget?: number | undefined | null, maxSymbols?: number | undefined | null): string
+  /**
+   * Get the repository map as an object
+   *
+   * # Arguments
+   * * `budget` - Token budget (default: 2000)
+   * * `max_symbols` - Maximum symbols (default: 50)
+   */
+  getMap(budget?: number | undefined | null, maxSymbols?: number | undefined | null): RepoMap
   /** Pack repository with specific options */
   pack(options?: PackOptions | undefined | null): string
+  /**
+   * Innermost symbol containing a line, with its exact line and byte range
+   *
+   * # Arguments
+   * * `path` - File path, relative to the repository root or absolute
+   * * `line` - Line number (1-based)
+   */
+  symbolAt(path: string, line: number): SymbolRange | null
+  /**
+   * First symbol in a file with this name, with its exact line and byte range
+   *
+   * # Arguments
+   * * `path` - File path, relative to the repository root or absolute
+   * * `name` - Symbol name, optionally qualified as `Parent.name`
+   */
+  findSymbol(path: string, name: string): SymbolRange | null
   /** Check for security issues */
   securityScan(): Array<string>
+  /** Check for security issues, returning structured findings */
+  securityFindings(): Array<SecurityFinding>
+  /**
+   * Chunk the repository for several models at once, each with its own budget
+   *
+   * # Arguments
+   * * `budgets` - Models and their per-chunk token budgets
+   * * `strategy` - "fixed", "file", "module", "semantic" or "dependency" (default: "semantic")
+   */
+  chunkPlans(budgets: ModelBudget[], strategy?: 'fixed' | 'file' | 'module' | 'semantic' | 'dependency' | undefined | null): Array<ChunkPlan>
 }
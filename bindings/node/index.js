@@ -32,24 +32,24 @@ switch (platform) {
   case 'android':
     switch (arch) {
       case 'arm64':
-        localFileExisted = existsSync(join(__dirname, 'codeloom.android-arm64.node'))
+        localFileExisted = existsSync(join(__dirname, 'infiniloom.android-arm64.node'))
         try {
           if (localFileExisted) {
-            nativeBinding = require('./codeloom.android-arm64.node')
+            nativeBinding = require('./infiniloom.android-arm64.node')
           } else {
-            nativeBinding = require('@codeloom/node-android-arm64')
+            nativeBinding = require('@infiniloom/node-android-arm64')
           }
         } catch (e) {
           loadError = e
         }
         break
       case 'arm':
-        localFileExisted = existsSync(join(__dirname, 'codeloom.android-arm-eabi.node'))
+        localFileExisted = existsSync(join(__dirname, 'infiniloom.android-arm-eabi.node'))
         try {
           if (localFileExisted) {
-            nativeBinding = require('./codeloom.android-arm-eabi.node')
+            nativeBinding = require('./infiniloom.android-arm-eabi.node')
           } else {
-            nativeBinding = require('@codeloom/node-android-arm-eabi')
+            nativeBinding = require('@infiniloom/node-android-arm-eabi')
           }
         } catch (e) {
           loadError = e
@@ -63,13 +63,13 @@ switch (platform) {
     switch (arch) {
       case 'x64':
         localFileExisted = existsSync(
-          join(__dirname, 'codeloom.win32-x64-msvc.node')
+          join(__dirname, 'infiniloom.win32-x64-msvc.node')
         )
         try {
           if (localFileExisted) {
-            nativeBinding = require('./codeloom.win32-x64-msvc.node')
+            nativeBinding = require('./infiniloom.win32-x64-msvc.node')
           } else {
-            nativeBinding = require('@codeloom/node-win32-x64-msvc')
+            nativeBinding = require('@infiniloom/node-win32-x64-msvc')
           }
         } catch (e) {
           loadError = e
@@ -77,13 +77,13 @@ switch (platform) {
         break
       case 'ia32':
         localFileExisted = existsSync(
-          join(__dirname, 'codeloom.win32-ia32-msvc.node')
+          join(__dirname, 'infiniloom.win32-ia32-msvc.node')
         )
         try {
           if (localFileExisted) {
-            nativeBinding = require('./codeloom.win32-ia32-msvc.node')
+            nativeBinding = require('./infiniloom.win32-ia32-msvc.node')
           } else {
-            nativeBinding = require('@codeloom/node-win32-ia32-msvc')
+            nativeBinding = require('@infiniloom/node-win32-ia32-msvc')
           }
         } catch (e) {
           loadError = e
@@ -91,13 +91,13 @@ switch (platform) {
         break
       case 'arm64':
         localFileExisted = existsSync(
-          join(__dirname, 'codeloom.win32-arm64-msvc.node')
+          join(__dirname, 'infiniloom.win32-arm64-msvc.node')
         )
         try {
           if (localFileExisted) {
-            nativeBinding = require('./codeloom.win32-arm64-msvc.node')
+            nativeBinding = require('./infiniloom.win32-arm64-msvc.node')
           } else {
-            nativeBinding = require('@codeloom/node-win32-arm64-msvc')
+            nativeBinding = require('@infiniloom/node-win32-arm64-msvc')
           }
         } catch (e) {
           loadError = e
@@ -108,23 +108,23 @@ switch (platform) {
     }
     break
   case 'darwin':
-    localFileExisted = existsSync(join(__dirname, 'codeloom.darwin-universal.node'))
+    localFileExisted = existsSync(join(__dirname, 'infiniloom.darwin-universal.node'))
     try {
       if (localFileExisted) {
-        nativeBinding = require('./codeloom.darwin-universal.node')
+        nativeBinding = require('./infiniloom.darwin-universal.node')
       } else {
-        nativeBinding = require('@codeloom/node-darwin-universal')
+        nativeBinding = require('@infiniloom/node-darwin-universal')
       }
       break
     } catch {}
     switch (arch) {
       case 'x64':
-        localFileExisted = existsSync(join(__dirname, 'codeloom.darwin-x64.node'))
+        localFileExisted = existsSync(join(__dirname, 'infiniloom.darwin-x64.node'))
         try {
           if (localFileExisted) {
-            nativeBinding = require('./codeloom.darwin-x64.node')
+            nativeBinding = require('./infiniloom.darwin-x64.node')
           } else {
-            nativeBinding = require('@codeloom/node-darwin-x64')
+            nativeBinding = require('@infiniloom/node-darwin-x64')
           }
         } catch (e) {
           loadError = e
@@ -132,13 +132,13 @@ switch (platform) {
         break
       case 'arm64':
         localFileExisted = existsSync(
-          join(__dirname, 'codeloom.darwin-arm64.node')
+          join(__dirname, 'infiniloom.darwin-arm64.node')
         )
         try {
           if (localFileExisted) {
-            nativeBinding = require('./codeloom.darwin-arm64.node')
+            nativeBinding = require('./infiniloom.darwin-arm64.node')
           } else {
-            nativeBinding = require('@codeloom/node-darwin-arm64')
+            nativeBinding = require('@infiniloom/node-darwin-arm64')
           }
         } catch (e) {
           loadError = e
@@ -152,12 +152,12 @@ switch (platform) {
     if (arch !== 'x64') {
       throw new Error(`Unsupported architecture on FreeBSD: ${arch}`)
     }
-    localFileExisted = existsSync(join(__dirname, 'codeloom.freebsd-x64.node'))
+    localFileExisted = existsSync(join(__dirname, 'infiniloom.freebsd-x64.node'))
     try {
       if (localFileExisted) {
-        nativeBinding = require('./codeloom.freebsd-x64.node')
+        nativeBinding = require('./infiniloom.freebsd-x64.node')
       } else {
-        nativeBinding = require('@codeloom/node-freebsd-x64')
+        nativeBinding = require('@infiniloom/node-freebsd-x64')
       }
     } catch (e) {
       loadError = e
@@ -168,26 +168,26 @@ switch (platform) {
       case 'x64':
         if (isMusl()) {
           localFileExisted = existsSync(
-            join(__dirname, 'codeloom.linux-x64-musl.node')
+            join(__dirname, 'infiniloom.linux-x64-musl.node')
           )
           try {
             if (localFileExisted) {
-              nativeBinding = require('./codeloom.linux-x64-musl.node')
+              nativeBinding = require('./infiniloom.linux-x64-musl.node')
             } else {
-              nativeBinding = require('@codeloom/node-linux-x64-musl')
+              nativeBinding = require('@infiniloom/node-linux-x64-musl')
             }
           } catch (e) {
             loadError = e
           }
         } else {
           localFileExisted = existsSync(
-            join(__dirname, 'codeloom.linux-x64-gnu.node')
+            join(__dirname, 'infiniloom.linux-x64-gnu.node')
           )
           try {
             if (localFileExisted) {
-              nativeBinding = require('./codeloom.linux-x64-gnu.node')
+              nativeBinding = require('./infiniloom.linux-x64-gnu.node')
             } else {
-              nativeBinding = require('@codeloom/node-linux-x64-gnu')
+              nativeBinding = require('@infiniloom/node-linux-x64-gnu')
             }
           } catch (e) {
             loadError = e
@@ -197,26 +197,26 @@ switch (platform) {
       case 'arm64':
         if (isMusl()) {
           localFileExisted = existsSync(
-            join(__dirname, 'codeloom.linux-arm64-musl.node')
+            join(__dirname, 'infiniloom.linux-arm64-musl.node')
           )
           try {
             if (localFileExisted) {
-              nativeBinding = require('./codeloom.linux-arm64-musl.node')
+              nativeBinding = require('./infiniloom.linux-arm64-musl.node')
             } else {
-              nativeBinding = require('@codeloom/node-linux-arm64-musl')
+              nativeBinding = require('@infiniloom/node-linux-arm64-musl')
             }
           } catch (e) {
             loadError = e
           }
         } else {
           localFileExisted = existsSync(
-            join(__dirname, 'codeloom.linux-arm64-gnu.node')
+            join(__dirname, 'infiniloom.linux-arm64-gnu.node')
           )
           try {
             if (localFileExisted) {
-              nativeBinding = require('./codeloom.linux-arm64-gnu.node')
+              nativeBinding = require('./infiniloom.linux-arm64-gnu.node')
             } else {
-              nativeBinding = require('@codeloom/node-linux-arm64-gnu')
+              nativeBinding = require('@infiniloom/node-linux-arm64-gnu')
             }
           } catch (e) {
             loadError = e
@@ -226,26 +226,26 @@ switch (platform) {
       case 'arm':
         if (isMusl()) {
           localFileExisted = existsSync(
-            join(__dirname, 'codeloom.linux-arm-musleabihf.node')
+            join(__dirname, 'infiniloom.linux-arm-musleabihf.node')
           )
           try {
             if (localFileExisted) {
-              nativeBinding = require('./codeloom.linux-arm-musleabihf.node')
+              nativeBinding = require('./infiniloom.linux-arm-musleabihf.node')
             } else {
-              nativeBinding = require('@codeloom/node-linux-arm-musleabihf')
+              nativeBinding = require('@infiniloom/node-linux-arm-musleabihf')
             }
           } catch (e) {
             loadError = e
           }
         } else {
           localFileExisted = existsSync(
-            join(__dirname, 'codeloom.linux-arm-gnueabihf.node')
+            join(__dirname, 'infiniloom.linux-arm-gnueabihf.node')
           )
           try {
             if (localFileExisted) {
-              nativeBinding = require('./codeloom.linux-arm-gnueabihf.node')
+              nativeBinding = require('./infiniloom.linux-arm-gnueabihf.node')
             } else {
-              nativeBinding = require('@codeloom/node-linux-arm-gnueabihf')
+              nativeBinding = require('@infiniloom/node-linux-arm-gnueabihf')
             }
           } catch (e) {
             loadError = e
@@ -255,26 +255,26 @@ switch (platform) {
       case 'riscv64':
         if (isMusl()) {
           localFileExisted = existsSync(
-            join(__dirname, 'codeloom.linux-riscv64-musl.node')
+            join(__dirname, 'infiniloom.linux-riscv64-musl.node')
           )
           try {
             if (localFileExisted) {
-              nativeBinding = require('./codeloom.linux-riscv64-musl.node')
+              nativeBinding = require('./infiniloom.linux-riscv64-musl.node')
             } else {
-              nativeBinding = require('@codeloom/node-linux-riscv64-musl')
+              nativeBinding = require('@infiniloom/node-linux-riscv64-musl')
             }
           } catch (e) {
             loadError = e
           }
         } else {
           localFileExisted = existsSync(
-            join(__dirname, 'codeloom.linux-riscv64-gnu.node')
+            join(__dirname, 'infiniloom.linux-riscv64-gnu.node')
           )
           try {
             if (localFileExisted) {
-              nativeBinding = require('./codeloom.linux-riscv64-gnu.node')
+              nativeBinding = require('./infiniloom.linux-riscv64-gnu.node')
             } else {
-              nativeBinding = require('@codeloom/node-linux-riscv64-gnu')
+              nativeBinding = require('@infiniloom/node-linux-riscv64-gnu')
             }
           } catch (e) {
             loadError = e
@@ -283,13 +283,13 @@ switch (platform) {
         break
       case 's390x':
         localFileExisted = existsSync(
-          join(__dirname, 'codeloom.linux-s390x-gnu.node')
+          join(__dirname, 'infiniloom.linux-s390x-gnu.node')
         )
         try {
           if (localFileExisted) {
-            nativeBinding = require('./codeloom.linux-s390x-gnu.node')
+            nativeBinding = require('./infiniloom.linux-s390x-gnu.node')
           } else {
-            nativeBinding = require('@codeloom/node-linux-s390x-gnu')
+            nativeBinding = require('@infiniloom/node-linux-s390x-gnu')
           }
         } catch (e) {
           loadError = e
@@ -310,9 +310,14 @@ if (!nativeBinding) {
   throw new Error(`Failed to load native binding`)
 }
 
-const { pack, scan, countTokens, CodeLoom } = nativeBinding
+const { Severity, SymbolKind, pack, packArchive, scan, packAsync, scanAsync, countTokens, Infiniloom } = nativeBinding
 
+module.exports.Severity = Severity
+module.exports.SymbolKind = SymbolKind
 module.exports.pack = pack
+module.exports.packArchive = packArchive
 module.exports.scan = scan
+module.exports.packAsync = packAsync
+module.exports.scanAsync = scanAsync
 module.exports.countTokens = countTokens
-module.exports.CodeLoom = CodeLoom
+module.exports.Infiniloom = Infiniloom
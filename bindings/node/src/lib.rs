@@ -1,24 +1,32 @@
 #![deny(clippy::all)]
 
+use infiniloom_engine::pipeline::{self, PackError, PackPipeline};
+use infiniloom_engine::scanner::{self, ScanConfig, ScanProgress};
 use infiniloom_engine::{
-    CompressionLevel, OutputFormat, OutputFormatter, RepoMap, RepoMapGenerator, Repository,
-    SecurityScanner, TokenizerModel,
+    CancellationToken, Cancelled, ChunkStrategy, Chunker, CompressionLevel, Config, OutputFormat, RepoMap, RepoMapGenerator, Repository,
+    ScanError, ScanOptions, ScanReport, SecurityScanner, Tokenizer, TokenizerModel,
 };
 use napi::bindgen_prelude::*;
+use napi::threadsafe_function::{
+    ErrorStrategy, ThreadSafeCallContext, ThreadsafeFunction, ThreadsafeFunctionCallMode,
+};
+use napi::{Env, JsFunction, JsObject, JsUnknown, Task};
 use napi_derive::napi;
-use std::path::PathBuf;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 
-mod scanner;
-use scanner::{scan_repository as do_scan, ScanConfig};
 
 /// Options for packing a repository
 #[napi(object)]
 pub struct PackOptions {
-    /// Output format: "xml", "markdown", or "json"
+    /// Output format (default: "xml")
+    #[napi(ts_type = "'xml' | 'markdown' | 'md' | 'json' | 'yaml' | 'toon' | 'plain' | 'signatures'")]
     pub format: Option<String>,
-    /// Target model: "claude", "gpt-4o", "gpt-4", "gemini", or "llama"
+    /// Target model (default: "claude")
+    #[napi(ts_type = "'claude' | 'gpt-4o' | 'gpt4o' | 'gpt-4' | 'gpt4' | 'gemini' | 'llama'")]
     pub model: Option<String>,
-    /// Compression level: "none", "minimal", "balanced", "aggressive", "extreme", "semantic"
+    /// Compression level (default: "balanced")
+    #[napi(ts_type = "'none' | 'minimal' | 'balanced' | 'aggressive' | 'extreme' | 'semantic'")]
     pub compression: Option<String>,
     /// Token budget for repository map
     pub map_budget: Option<u32>,
@@ -26,38 +34,294 @@ pub struct PackOptions {
     pub max_symbols: Option<u32>,
     /// Skip security scanning
     pub skip_security: Option<bool>,
+    /// Only pack files changed since this branch or commit, plus the files
+    /// they import or are imported by
+    pub diff_base: Option<String>,
+    /// Only pack the files relevant to this question, most relevant first
+    pub query: Option<String>,
+}
+
+/// Severity of a security finding
+#[napi(string_enum = "lowercase")]
+pub enum Severity {
+    Low,
+    Medium,
+    High,
+    Critical,
+}
+
+impl From<infiniloom_engine::security::Severity> for Severity {
+    fn from(severity: infiniloom_engine::security::Severity) -> Self {
+        use infiniloom_engine::security::Severity as S;
+        match severity {
+            S::Low => Self::Low,
+            S::Medium => Self::Medium,
+            S::High => Self::High,
+            S::Critical => Self::Critical,
+        }
+    }
+}
+
+/// Kind of a code symbol
+#[napi(string_enum = "lowercase")]
+pub enum SymbolKind {
+    Function,
+    Method,
+    Class,
+    Interface,
+    Struct,
+    Enum,
+    Constant,
+    Variable,
+    Import,
+    Export,
+    #[napi(value = "type")]
+    TypeAlias,
+    Module,
+    Trait,
+    Macro,
+}
+
+impl SymbolKind {
+    /// Inverse of the engine's `SymbolKind::name`
+    fn from_name(name: &str) -> Self {
+        match name {
+            "method" => Self::Method,
+            "class" => Self::Class,
+            "interface" => Self::Interface,
+            "struct" => Self::Struct,
+            "enum" => Self::Enum,
+            "constant" => Self::Constant,
+            "variable" => Self::Variable,
+            "import" => Self::Import,
+            "export" => Self::Export,
+            "type" => Self::TypeAlias,
+            "module" => Self::Module,
+            "trait" => Self::Trait,
+            "macro" => Self::Macro,
+            _ => Self::Function,
+        }
+    }
+}
+
+/// A potential secret found by the security scanner
+#[napi(object)]
+pub struct SecurityFinding {
+    /// Kind of secret, e.g. "AWS Credential"
+    pub kind: String,
+    /// File the secret was found in
+    pub file: String,
+    /// Line number (1-based)
+    pub line: u32,
+    /// Matched text, redacted
+    pub pattern: String,
+    /// Severity level
+    pub severity: Severity,
+}
+
+/// A chunk budget for one model
+#[napi(object)]
+pub struct ModelBudget {
+    /// Model name, e.g. "gpt-4o"
+    #[napi(ts_type = "'claude' | 'gpt-4o' | 'gpt4o' | 'gpt-4' | 'gpt4' | 'gemini' | 'llama'")]
+    pub model: String,
+    /// Token budget per chunk
+    pub max_tokens: u32,
+}
+
+/// A file within a chunk
+#[napi(object)]
+pub struct ChunkFile {
+    /// Stable file ID, the same in every pack of the repository
+    pub id: String,
+    /// Relative file path
+    pub path: String,
+    /// File content
+    pub content: String,
+    /// Token count for the plan's model
+    pub tokens: u32,
+}
+
+/// A chunk of the repository
+#[napi(object)]
+pub struct Chunk {
+    /// Chunk index (0-based)
+    pub index: u32,
+    /// Total number of chunks in the plan
+    pub total: u32,
+    /// Focus/theme of this chunk
+    pub focus: String,
+    /// Token count for the plan's model
+    pub tokens: u32,
+    /// Files in this chunk
+    pub files: Vec<ChunkFile>,
+}
+
+/// Chunks of the repository for one model
+#[napi(object)]
+pub struct ChunkPlan {
+    /// Model name
+    pub model: String,
+    /// Token budget per chunk
+    pub max_tokens: u32,
+    /// The chunks
+    pub chunks: Vec<Chunk>,
+}
+
+/// A ranked symbol in the repository map
+#[napi(object)]
+pub struct MapSymbol {
+    /// Stable symbol ID, the same in every pack of the repository; imports
+    /// have none
+    pub id: Option<String>,
+    /// Symbol name
+    pub name: String,
+    /// Symbol kind
+    pub kind: SymbolKind,
+    /// File containing the symbol
+    pub file: String,
+    /// Line number
+    pub line: u32,
+    /// Function/method signature
+    pub signature: Option<String>,
+    /// Number of references
+    pub references: u32,
+    /// Rank (1 = most important)
+    pub rank: u32,
+    /// Importance score (0.0 - 1.0)
+    pub importance: f64,
 }
 
-/// Statistics from scanning a repository
+/// A symbol and the region of its file it covers
 #[napi(object)]
-pub struct ScanStats {
-    /// Repository name
+pub struct SymbolRange {
+    /// Symbol name
     pub name: String,
-    /// Total number of files
-    pub total_files: u32,
-    /// Total lines of code
-    pub total_lines: u32,
-    /// Total tokens for target model
-    pub total_tokens: u32,
-    /// Primary language
-    pub primary_language: Option<String>,
-    /// Language breakdown
-    pub languages: Vec<LanguageStat>,
-    /// Number of security findings
-    pub security_findings: u32,
-}
-
-/// Statistics for a single language
+    /// Symbol kind
+    pub kind: SymbolKind,
+    /// Parent symbol name, for methods
+    pub parent: Option<String>,
+    /// File containing the symbol, relative to the repository root
+    pub file: String,
+    /// First line (1-based)
+    pub start_line: u32,
+    /// Last line (1-based, inclusive)
+    pub end_line: u32,
+    /// UTF-8 byte offset of the first line in the file
+    pub start_byte: Option<u32>,
+    /// UTF-8 byte offset just past the last line, without its line break
+    pub end_byte: Option<u32>,
+}
+
+impl From<infiniloom_engine::SymbolSpan<'_>> for SymbolRange {
+    fn from(span: infiniloom_engine::SymbolSpan<'_>) -> Self {
+        Self {
+            name: span.symbol.name.clone(),
+            kind: SymbolKind::from_name(span.symbol.kind.name()),
+            parent: span.symbol.parent.clone(),
+            file: span.file.relative_path.clone(),
+            start_line: span.start_line,
+            end_line: span.end_line,
+            start_byte: span.bytes.as_ref().map(|b| b.start as u32),
+            end_byte: span.bytes.as_ref().map(|b| b.end as u32),
+        }
+    }
+}
+
+/// A module (directory) in the repository map
 #[napi(object)]
-pub struct LanguageStat {
-    /// Language name
-    pub language: String,
-    /// Number of files
+pub struct MapModule {
+    /// Module name
+    pub name: String,
+    /// Number of files in the module
     pub files: u32,
-    /// Total lines
-    pub lines: u32,
-    /// Percentage of codebase
-    pub percentage: f64,
+    /// Total tokens in the module
+    pub tokens: u32,
+}
+
+/// A file in the repository map index
+#[napi(object)]
+pub struct MapFile {
+    /// Relative file path
+    pub path: String,
+    /// Token count
+    pub tokens: u32,
+    /// Importance level
+    #[napi(ts_type = "'critical' | 'high' | 'normal' | 'low'")]
+    pub importance: String,
+    /// Brief summary
+    pub summary: Option<String>,
+}
+
+/// An external package and the files that import it
+#[napi(object)]
+pub struct MapDependencyUsage {
+    /// Package name as imported
+    pub package: String,
+    /// Relative paths of the importing files
+    pub files: Vec<String>,
+}
+
+/// Repository map with the most important symbols and files
+#[napi(object, js_name = "RepoMap")]
+pub struct JsRepoMap {
+    /// Text summary of the repository
+    pub summary: String,
+    /// Most important symbols, best first
+    pub key_symbols: Vec<MapSymbol>,
+    /// Modules (directories) with their sizes
+    pub modules: Vec<MapModule>,
+    /// Index of all files
+    pub file_index: Vec<MapFile>,
+    /// Most used external dependencies with their importers
+    pub dependency_usage: Vec<MapDependencyUsage>,
+    /// Total token count for this map
+    pub token_count: u32,
+}
+
+impl From<&RepoMap> for JsRepoMap {
+    fn from(map: &RepoMap) -> Self {
+        Self {
+            summary: map.summary.clone(),
+            key_symbols: map
+                .key_symbols
+                .iter()
+                .map(|s| MapSymbol {
+                    id: s.id.clone(),
+                    name: s.name.clone(),
+                    kind: SymbolKind::from_name(&s.kind),
+                    file: s.file.clone(),
+                    line: s.line,
+                    signature: s.signature.clone(),
+                    references: s.references,
+                    rank: s.rank,
+                    importance: s.importance as f64,
+                })
+                .collect(),
+            modules: map
+                .module_graph
+                .nodes
+                .iter()
+                .map(|m| MapModule { name: m.name.clone(), files: m.files, tokens: m.tokens })
+                .collect(),
+            file_index: map
+                .file_index
+                .iter()
+                .map(|f| MapFile {
+                    path: f.path.clone(),
+                    tokens: f.tokens,
+                    importance: f.importance.clone(),
+                    summary: f.summary.clone(),
+                })
+                .collect(),
+            dependency_usage: map
+                .external_usage
+                .iter()
+                .map(|u| MapDependencyUsage { package: u.package.clone(), files: u.files.clone() })
+                .collect(),
+            token_count: map.token_count,
+        }
+    }
 }
 
 /// Pack a repository into optimized LLM context
@@ -82,125 +346,350 @@ pub struct LanguageStat {
 /// ```
 #[napi]
 pub fn pack(path: String, options: Option<PackOptions>) -> Result<String> {
-    let opts = options.unwrap_or(PackOptions {
+    let opts = options.unwrap_or_else(default_pack_options);
+    let model = parse_model(opts.model.as_deref())?;
+
+    // Scan repository (with contents for packing)
+    let repo = scan_repository(&path, model, true)?;
+    pack_repository(repo, &opts, &CancellationToken::new())
+}
+
+/// Pack a tar, tar.gz or zip archive held in memory
+///
+/// Nothing is written to disk, so uploaded archives can be packed where the
+/// filesystem is read-only, such as in serverless functions. A single
+/// top-level directory, as in GitHub tarballs, is stripped and names the
+/// repository. `.gitignore` files inside the archive are not applied, and
+/// `diffBase` is not supported since an archive has no git history.
+///
+/// # Arguments
+/// * `data` - Archive bytes
+/// * `options` - Optional packing options
+///
+/// # Returns
+/// Formatted repository context as a string
+///
+/// # Example
+/// ```javascript
+/// const { packArchive } = require('@infiniloom/node');
+///
+/// const context = packArchive(fs.readFileSync('repo.tar.gz'), { format: 'xml' });
+/// ```
+#[napi]
+pub fn pack_archive(data: Buffer, options: Option<PackOptions>) -> Result<String> {
+    let opts = options.unwrap_or_else(default_pack_options);
+    if opts.diff_base.is_some() {
+        return Err(Error::new(
+            Status::InvalidArg,
+            "diffBase is not supported for archives".to_string(),
+        ));
+    }
+    pack_pipeline(&opts, &CancellationToken::new())?
+        .pack_archive(&data, "repository")
+        .map_err(|e| match e {
+            PackError::Scan(e) => Error::new(Status::InvalidArg, e.to_string()),
+            e => pack_error(e),
+        })
+}
+
+fn default_pack_options() -> PackOptions {
+    PackOptions {
         format: None,
         model: None,
         compression: None,
         map_budget: None,
         max_symbols: None,
         skip_security: None,
-    });
+        diff_base: None,
+        query: None,
+    }
+}
 
-    // Parse options
-    let format = parse_format(opts.format.as_deref())?;
-    let model = parse_model(opts.model.as_deref())?;
-    let compression = parse_compression(opts.compression.as_deref())?;
-    let map_budget = opts.map_budget.unwrap_or(2000);
-    let max_symbols = opts.max_symbols.unwrap_or(50);
-    let skip_security = opts.skip_security.unwrap_or(false);
+/// Filter, compress, check and format a scanned repository as `opts` and
+/// its config say, stopping once `cancel` is cancelled
+fn pack_repository(
+    repo: Repository,
+    opts: &PackOptions,
+    cancel: &CancellationToken,
+) -> Result<String> {
+    let config = discover_config(&repo.path)?;
+    let options = pack_options(opts)?.with_scan(ScanOptions::default().with_config(&config.scan));
+    PackPipeline::new(options)
+        .with_cancellation(cancel.clone())
+        .pack_repository(repo)
+        .map_err(pack_error)
+}
 
-    // Scan repository (with contents for packing)
-    let repo = scan_repository(&path, model, true)?;
+/// The engine pipeline for `opts`
+fn pack_pipeline(opts: &PackOptions, cancel: &CancellationToken) -> Result<PackPipeline> {
+    Ok(PackPipeline::new(pack_options(opts)?).with_cancellation(cancel.clone()))
+}
 
-    // Security check
-    if !skip_security {
-        let scanner = SecurityScanner::new();
-        for file in &repo.files {
-            if let Some(content) = &file.content {
-                let findings = scanner.scan(content, &file.relative_path);
-                if findings.iter().any(|f| {
-                    matches!(
-                        f.severity,
-                        infiniloom_engine::security::Severity::Critical
-                    )
-                }) {
-                    return Err(Error::new(
-                        Status::GenericFailure,
-                        format!(
-                            "Critical security issues found in {}. Use skip_security: true to override.",
-                            file.relative_path
-                        ),
-                    ));
-                }
-            }
-        }
+/// `opts` with defaults filled in, as the engine takes them
+fn pack_options(opts: &PackOptions) -> Result<pipeline::PackOptions> {
+    let mut options = pipeline::PackOptions::default()
+        .with_format(parse_format(opts.format.as_deref())?)
+        .with_model(parse_model(opts.model.as_deref())?)
+        .with_compression(parse_compression(opts.compression.as_deref())?)
+        .with_map_budget(opts.map_budget.unwrap_or(2000))
+        .with_max_symbols(opts.max_symbols.unwrap_or(50) as usize)
+        .with_secret_blocking(!opts.skip_security.unwrap_or(false));
+    if let Some(base) = &opts.diff_base {
+        options = options.with_diff_base(base);
+    }
+    if let Some(query) = &opts.query {
+        options = options.with_query(query);
     }
+    Ok(options)
+}
 
-    // Generate repository map
-    let generator = RepoMapGenerator::new(map_budget)
-        .with_max_symbols(max_symbols as usize)
-        .with_model(model);
-    let map = generator.generate(&repo);
+fn pack_error(err: PackError) -> Error {
+    match err {
+        PackError::Cancelled(c) => cancelled(c),
+        PackError::Secrets(path) => Error::new(
+            Status::GenericFailure,
+            format!(
+                "Critical security issues found in {}. Use skip_security: true to override.",
+                path
+            ),
+        ),
+        e => Error::new(Status::GenericFailure, e.to_string()),
+    }
+}
+
+/// The error a cancelled `packAsync` or `scanAsync` rejects with
+fn cancelled(_: Cancelled) -> Error {
+    Error::new(Status::Cancelled, "Operation cancelled".to_string())
+}
 
-    // Format output
-    let formatter = OutputFormatter::by_format(format);
-    let output = formatter.format(&repo, &map);
+/// A token cancelled when the JS `AbortSignal` fires
+fn abort_token(env: &Env, signal: Option<JsObject>) -> Result<CancellationToken> {
+    let cancel = CancellationToken::new();
+    let Some(signal) = signal else {
+        return Ok(cancel);
+    };
+    if signal.get_named_property_unchecked::<bool>("aborted")? {
+        cancel.cancel();
+        return Ok(cancel);
+    }
 
-    Ok(output)
+    let token = cancel.clone();
+    let on_abort = env.create_function_from_closure("onabort", move |_ctx| {
+        token.cancel();
+        Ok(())
+    })?;
+    let add_listener: JsFunction = signal.get_named_property_unchecked("addEventListener")?;
+    let args: [JsUnknown; 2] =
+        [env.create_string("abort")?.into_unknown(), on_abort.into_unknown()];
+    add_listener.call(Some(&signal), &args)?;
+    Ok(cancel)
 }
 
-/// Scan a repository and return statistics
+/// Scan a repository and return its scan report
+///
+/// The report has the same keys as `infiniloom scan --json` and the Python
+/// `scan()`, versioned by `schema_version`.
 ///
 /// # Arguments
 /// * `path` - Path to repository root
 /// * `model` - Optional target model (default: "claude")
 ///
 /// # Returns
-/// Repository statistics
+/// The scan report
 ///
 /// # Example
 /// ```javascript
 /// const { scan } = require('@infiniloom/node');
 ///
-/// const stats = scan('./my-repo', 'claude');
-/// console.log(`Total files: ${stats.total_files}`);
-/// console.log(`Total tokens: ${stats.total_tokens}`);
+/// const report = scan('./my-repo');
+/// console.log(`Total files: ${report.files}`);
+/// console.log(`Claude tokens: ${report.tokens.claude}`);
 /// ```
-#[napi]
-pub fn scan(path: String, model: Option<String>) -> Result<ScanStats> {
+#[napi(
+    ts_args_type = "path: string, model?: 'claude' | 'gpt-4o' | 'gpt4o' | 'gpt-4' | 'gpt4' | 'gemini' | 'llama' | undefined | null",
+    ts_return_type = "ScanReport"
+)]
+pub fn scan(path: String, model: Option<String>) -> Result<serde_json::Value> {
     let tokenizer_model = parse_model(model.as_deref())?;
-    let repo = scan_repository(&path, tokenizer_model, false)?;
-
-    // Security scan
-    let scanner = SecurityScanner::new();
-    let mut total_findings = 0;
-    for file in &repo.files {
-        if let Some(content) = &file.content {
-            let findings = scanner.scan(content, &file.relative_path);
-            total_findings += findings.len();
+    let repo = scan_repository(&path, tokenizer_model, true)?;
+    scan_report(&repo)
+}
+
+/// Progress of `packAsync` or `scanAsync`
+#[napi(object)]
+pub struct Progress {
+    /// "scan" while files are read, then "pack" while the context is
+    /// compressed, mapped and formatted
+    #[napi(ts_type = "'scan' | 'pack'")]
+    pub phase: String,
+    /// Files scanned so far
+    pub files_done: u32,
+}
+
+/// Files scanned between two progress callbacks
+const PROGRESS_INTERVAL: u32 = 100;
+
+/// A JS progress callback that the worker thread can call
+type ProgressCallback = ThreadsafeFunction<Progress, ErrorStrategy::Fatal>;
+
+fn progress_callback(on_progress: Option<JsFunction>) -> Result<Option<ProgressCallback>> {
+    on_progress
+        .map(|f| {
+            f.create_threadsafe_function(0, |ctx: ThreadSafeCallContext<Progress>| {
+                Ok(vec![ctx.value])
+            })
+        })
+        .transpose()
+}
+
+/// Queue a progress callback; it runs on the JS thread once that is free
+fn report_progress(progress: Option<&ProgressCallback>, phase: &str, files_done: u32) {
+    if let Some(callback) = progress {
+        callback.call(
+            Progress { phase: phase.to_string(), files_done },
+            ThreadsafeFunctionCallMode::NonBlocking,
+        );
+    }
+}
+
+/// Scan with contents, reporting every [`PROGRESS_INTERVAL`] files and once
+/// at the end
+fn scan_with_progress(
+    path: &str,
+    progress: Option<&ProgressCallback>,
+    cancel: &CancellationToken,
+) -> Result<Repository> {
+    let on_file = |files_done| {
+        if files_done % PROGRESS_INTERVAL == 0 {
+            report_progress(progress, "scan", files_done);
         }
+    };
+    let repo = scan_repository_with_progress(path, true, &on_file, cancel)?;
+    report_progress(progress, "scan", repo.files.len() as u32);
+    Ok(repo)
+}
+
+/// Background work of `scanAsync`
+pub struct ScanTask {
+    path: String,
+    progress: Option<ProgressCallback>,
+    cancel: CancellationToken,
+}
+
+impl Task for ScanTask {
+    type Output = serde_json::Value;
+    type JsValue = serde_json::Value;
+
+    fn compute(&mut self) -> Result<Self::Output> {
+        let repo = scan_with_progress(&self.path, self.progress.as_ref(), &self.cancel)?;
+        scan_report(&repo)
     }
 
-    Ok(ScanStats {
-        name: repo.name.clone(),
-        total_files: repo.metadata.total_files,
-        total_lines: repo.metadata.total_lines as u32,
-        total_tokens: repo.total_tokens(tokenizer_model),
-        primary_language: repo
-            .metadata
-            .languages
-            .first()
-            .map(|l| l.language.clone()),
-        languages: repo
-            .metadata
-            .languages
-            .iter()
-            .map(|l| LanguageStat {
-                language: l.language.clone(),
-                files: l.files,
-                lines: l.lines as u32,
-                percentage: l.percentage as f64,
-            })
-            .collect(),
-        security_findings: total_findings as u32,
-    })
+    fn resolve(&mut self, _env: Env, report: Self::Output) -> Result<Self::JsValue> {
+        Ok(report)
+    }
+}
+
+/// Background work of `packAsync`
+pub struct PackTask {
+    path: String,
+    options: PackOptions,
+    progress: Option<ProgressCallback>,
+    cancel: CancellationToken,
+}
+
+impl Task for PackTask {
+    type Output = String;
+    type JsValue = String;
+
+    fn compute(&mut self) -> Result<Self::Output> {
+        let repo = scan_with_progress(&self.path, self.progress.as_ref(), &self.cancel)?;
+        report_progress(self.progress.as_ref(), "pack", repo.files.len() as u32);
+        pack_repository(repo, &self.options, &self.cancel)
+    }
+
+    fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
+        Ok(output)
+    }
+}
+
+/// Pack a repository without blocking the event loop
+///
+/// Like `pack()`, but scanning and formatting run on the libuv thread pool
+/// and the result comes back as a promise. `onProgress` is called with the
+/// phase and the number of files scanned so far. Aborting `signal` stops the
+/// work between files and rejects the promise with an error whose `code` is
+/// "Cancelled".
+///
+/// # Example
+/// ```javascript
+/// const { packAsync } = require('@infiniloom/node');
+///
+/// const controller = new AbortController();
+/// setTimeout(() => controller.abort(), 10_000);
+/// const context = await packAsync('./my-repo', { format: 'xml' }, (p) => {
+///   console.log(`${p.phase}: ${p.filesDone} files`);
+/// }, controller.signal);
+/// ```
+#[napi(
+    ts_args_type = "path: string, options?: PackOptions | undefined | null, onProgress?: ((progress: Progress) => void) | undefined | null, signal?: AbortSignal | undefined | null",
+    ts_return_type = "Promise<string>"
+)]
+pub fn pack_async(
+    env: Env,
+    path: String,
+    options: Option<PackOptions>,
+    on_progress: Option<JsFunction>,
+    signal: Option<JsObject>,
+) -> Result<AsyncTask<PackTask>> {
+    let options = options.unwrap_or_else(default_pack_options);
+    // Reject bad options before any work is queued
+    parse_model(options.model.as_deref())?;
+    let progress = progress_callback(on_progress)?;
+    let cancel = abort_token(&env, signal)?;
+    Ok(AsyncTask::new(PackTask { path, options, progress, cancel }))
+}
+
+/// Scan a repository without blocking the event loop
+///
+/// Like `scan()`, but the scan runs on the libuv thread pool and the report
+/// comes back as a promise. `onProgress` is called with the number of files
+/// scanned so far. Aborting `signal` stops the scan and rejects the promise
+/// as for `packAsync`.
+///
+/// # Example
+/// ```javascript
+/// const { scanAsync } = require('@infiniloom/node');
+///
+/// const report = await scanAsync('./my-repo', 'claude', (p) => console.log(p.filesDone));
+/// ```
+#[napi(
+    ts_args_type = "path: string, model?: 'claude' | 'gpt-4o' | 'gpt4o' | 'gpt-4' | 'gpt4' | 'gemini' | 'llama' | undefined | null, onProgress?: ((progress: Progress) => void) | undefined | null, signal?: AbortSignal | undefined | null",
+    ts_return_type = "Promise<ScanReport>"
+)]
+pub fn scan_async(
+    env: Env,
+    path: String,
+    model: Option<String>,
+    on_progress: Option<JsFunction>,
+    signal: Option<JsObject>,
+) -> Result<AsyncTask<ScanTask>> {
+    parse_model(model.as_deref())?;
+    let progress = progress_callback(on_progress)?;
+    let cancel = abort_token(&env, signal)?;
+    Ok(AsyncTask::new(ScanTask { path, progress, cancel }))
 }
 
 /// Count tokens in text for a specific model
 ///
+/// GPT-4 and GPT-4o counts come from their BPE tokenizers; other models are
+/// estimated.
+///
 /// # Arguments
 /// * `text` - Text to tokenize
 /// * `model` - Optional model name (default: "claude")
+/// * `exact` - Use the exact tokenizer where one exists (default: true);
+///   `false` always estimates, which is faster on large inputs
 ///
 /// # Returns
 /// Token count
@@ -209,30 +698,35 @@ pub fn scan(path: String, model: Option<String>) -> Result<ScanStats> {
 /// ```javascript
 /// const { countTokens } = require('@infiniloom/node');
 ///
-/// const count = countTokens('Hello, world!', 'claude');
+/// const count = countTokens('Hello, world!', 'gpt-4o');
 /// console.log(`Tokens: ${count}`);
 /// ```
-#[napi]
-pub fn count_tokens(text: String, model: Option<String>) -> Result<u32> {
+#[napi(
+    ts_args_type = "text: string, model?: 'claude' | 'gpt-4o' | 'gpt4o' | 'gpt-4' | 'gpt4' | 'gemini' | 'llama' | undefined | null, exact?: boolean | undefined | null"
+)]
+pub fn count_tokens(text: String, model: Option<String>, exact: Option<bool>) -> Result<u32> {
     let tokenizer_model = parse_model(model.as_deref())?;
+    let tokenizer =
+        if exact.unwrap_or(true) { Tokenizer::new() } else { Tokenizer::estimation_only() };
 
-    // Simple approximation: ~4 chars per token
-    // In a real implementation, use tiktoken or similar
-    let approx_tokens = match tokenizer_model {
-        TokenizerModel::Claude => (text.len() as f32 / 3.5) as u32,
-        TokenizerModel::Gpt4o | TokenizerModel::Gpt4 => (text.len() as f32 / 4.0) as u32,
-        TokenizerModel::Gemini => (text.len() as f32 / 4.2) as u32,
-        TokenizerModel::Llama => (text.len() as f32 / 3.8) as u32,
-    };
-
-    Ok(approx_tokens)
+    Ok(tokenizer.count(&text, tokenizer_model.into()))
 }
 
 /// Infiniloom class for advanced usage
+///
+/// Generated repository maps are cached per (budget, max symbols), so calling
+/// `pack()` repeatedly with different formats only pays for formatting. Call
+/// `invalidate()` after the files on disk change.
 #[napi]
 pub struct Infiniloom {
+    path: String,
+    /// Discovered for `path` when the instance was created
+    config: Config,
     repo: Repository,
     model: TokenizerModel,
+    /// Maps keyed by (budget, max symbols, of the packed files rather than
+    /// all scanned files)
+    maps: HashMap<(u32, u32, bool), RepoMap>,
 }
 
 #[napi]
@@ -242,54 +736,36 @@ impl Infiniloom {
     /// # Arguments
     /// * `path` - Path to repository root
     /// * `model` - Optional model name (default: "claude")
-    #[napi(constructor)]
+    #[napi(
+        constructor,
+        ts_args_type = "path: string, model?: 'claude' | 'gpt-4o' | 'gpt4o' | 'gpt-4' | 'gpt4' | 'gemini' | 'llama' | undefined | null"
+    )]
     pub fn new(path: String, model: Option<String>) -> Result<Self> {
         let tokenizer_model = parse_model(model.as_deref())?;
         let repo = scan_repository(&path, tokenizer_model, true)?;
+        let config = discover_config(Path::new(&path))?;
 
-        Ok(Self {
-            repo,
-            model: tokenizer_model,
-        })
+        Ok(Self { path, config, repo, model: tokenizer_model, maps: HashMap::new() })
     }
 
-    /// Get repository statistics
+    /// Rescan the repository and drop all cached maps
     #[napi]
-    pub fn get_stats(&self) -> ScanStats {
-        let scanner = SecurityScanner::new();
-        let mut total_findings = 0;
-        for file in &self.repo.files {
-            if let Some(content) = &file.content {
-                let findings = scanner.scan(content, &file.relative_path);
-                total_findings += findings.len();
-            }
-        }
+    pub fn invalidate(&mut self) -> Result<()> {
+        self.repo = scan_repository(&self.path, self.model, true)?;
+        self.maps.clear();
+        Ok(())
+    }
 
-        ScanStats {
-            name: self.repo.name.clone(),
-            total_files: self.repo.metadata.total_files,
-            total_lines: self.repo.metadata.total_lines as u32,
-            total_tokens: self.repo.total_tokens(self.model),
-            primary_language: self
-                .repo
-                .metadata
-                .languages
-                .first()
-                .map(|l| l.language.clone()),
-            languages: self
-                .repo
-                .metadata
-                .languages
-                .iter()
-                .map(|l| LanguageStat {
-                    language: l.language.clone(),
-                    files: l.files,
-                    lines: l.lines as u32,
-                    percentage: l.percentage as f64,
-                })
-                .collect(),
-            security_findings: total_findings as u32,
-        }
+    /// Number of repository maps currently cached
+    #[napi(getter)]
+    pub fn cached_maps(&self) -> u32 {
+        self.maps.len() as u32
+    }
+
+    /// Get the scan report, as returned by `scan()`
+    #[napi(ts_return_type = "ScanReport")]
+    pub fn get_stats(&self) -> Result<serde_json::Value> {
+        scan_report(&self.repo)
     }
 
     /// Generate a repository map
@@ -298,45 +774,73 @@ impl Infiniloom {
     /// * `budget` - Token budget (default: 2000)
     /// * `max_symbols` - Maximum symbols (default: 50)
     #[napi]
-    pub fn generate_map(&self, budget: Option<u32>, max_symbols: Option<u32>) -> Result<String> {
-        let token_budget = budget.unwrap_or(2000);
-        let max_syms = max_symbols.unwrap_or(50);
+    pub fn generate_map(
+        &mut self,
+        budget: Option<u32>,
+        max_symbols: Option<u32>,
+    ) -> Result<String> {
+        let (_, map) = self.cached_map(budget.unwrap_or(2000), max_symbols.unwrap_or(50));
 
-        let generator = RepoMapGenerator::new(token_budget)
-            .with_max_symbols(max_syms as usize)
-            .with_model(self.model);
-
-        let map = generator.generate(&self.repo);
-
-        serde_json::to_string_pretty(&map)
+        serde_json::to_string_pretty(map)
             .map_err(|e| Error::new(Status::GenericFailure, e.to_string()))
     }
 
+    /// Get the repository map as an object
+    ///
+    /// # Arguments
+    /// * `budget` - Token budget (default: 2000)
+    /// * `max_symbols` - Maximum symbols (default: 50)
+    #[napi]
+    pub fn get_map(&mut self, budget: Option<u32>, max_symbols: Option<u32>) -> JsRepoMap {
+        let (_, map) = self.cached_map(budget.unwrap_or(2000), max_symbols.unwrap_or(50));
+        map.into()
+    }
+
     /// Pack repository with specific options
     #[napi]
-    pub fn pack(&self, options: Option<PackOptions>) -> Result<String> {
-        let opts = options.unwrap_or(PackOptions {
-            format: None,
-            model: None,
-            compression: None,
-            map_budget: None,
-            max_symbols: None,
-            skip_security: None,
-        });
+    pub fn pack(&mut self, options: Option<PackOptions>) -> Result<String> {
+        let opts = options.unwrap_or_else(default_pack_options);
+        // The scan was counted with the instance's model
+        let options = pack_options(&opts)?
+            .with_model(self.model)
+            .with_scan(ScanOptions::default().with_config(&self.config.scan));
+        let pipeline = PackPipeline::new(options);
 
-        let format = parse_format(opts.format.as_deref())?;
-        let compression = parse_compression(opts.compression.as_deref())?;
-        let map_budget = opts.map_budget.unwrap_or(2000);
-        let max_symbols = opts.max_symbols.unwrap_or(50);
+        let mut repo = self.repo.clone();
+        pipeline.prepare(&mut repo).map_err(pack_error)?;
 
-        let generator = RepoMapGenerator::new(map_budget)
-            .with_max_symbols(max_symbols as usize)
-            .with_model(self.model);
+        // Diff and query packs cover a different file set, so their maps are
+        // not cached; compression only rewrites contents, so the map of the
+        // packed files serves every format and compression level
+        if opts.diff_base.is_some() || opts.query.is_some() {
+            return pipeline.render(&repo).map_err(pack_error);
+        }
+        let key = (opts.map_budget.unwrap_or(2000), opts.max_symbols.unwrap_or(50), true);
+        if !self.maps.contains_key(&key) {
+            let map = pipeline.map(&repo).map_err(pack_error)?;
+            self.maps.insert(key, map);
+        }
+        pipeline.format(&repo, &self.maps[&key]).map_err(pack_error)
+    }
 
-        let map = generator.generate(&self.repo);
-        let formatter = OutputFormatter::by_format(format);
+    /// Innermost symbol containing a line, with its exact line and byte range
+    ///
+    /// # Arguments
+    /// * `path` - File path, relative to the repository root or absolute
+    /// * `line` - Line number (1-based)
+    #[napi]
+    pub fn symbol_at(&self, path: String, line: u32) -> Option<SymbolRange> {
+        self.repo.file(&path)?.symbol_at(line).map(Into::into)
+    }
 
-        Ok(formatter.format(&self.repo, &map))
+    /// First symbol in a file with this name, with its exact line and byte range
+    ///
+    /// # Arguments
+    /// * `path` - File path, relative to the repository root or absolute
+    /// * `name` - Symbol name, optionally qualified as `Parent.name`
+    #[napi]
+    pub fn find_symbol(&self, path: String, name: String) -> Option<SymbolRange> {
+        self.repo.find_symbol(&path, &name).map(Into::into)
     }
 
     /// Check for security issues
@@ -362,6 +866,104 @@ impl Infiniloom {
 
         Ok(findings)
     }
+
+    /// Check for security issues, returning structured findings
+    #[napi]
+    pub fn security_findings(&self) -> Vec<SecurityFinding> {
+        let scanner = SecurityScanner::new();
+        self.repo
+            .files
+            .iter()
+            .filter_map(|file| Some(scanner.scan(file.content.as_deref()?, &file.relative_path)))
+            .flatten()
+            .map(|finding| SecurityFinding {
+                kind: finding.kind.name().to_owned(),
+                file: finding.file,
+                line: finding.line,
+                pattern: finding.pattern,
+                severity: finding.severity.into(),
+            })
+            .collect()
+    }
+
+    /// Chunk the repository for several models at once, each with its own budget
+    ///
+    /// # Arguments
+    /// * `budgets` - Models and their per-chunk token budgets
+    /// * `strategy` - "fixed", "file", "module", "semantic" or "dependency" (default: "semantic")
+    #[napi(
+        ts_args_type = "budgets: ModelBudget[], strategy?: 'fixed' | 'file' | 'module' | 'semantic' | 'dependency' | undefined | null"
+    )]
+    pub fn chunk_plans(
+        &self,
+        budgets: Vec<ModelBudget>,
+        strategy: Option<String>,
+    ) -> Result<Vec<ChunkPlan>> {
+        let strategy = match strategy.as_deref().unwrap_or("semantic") {
+            "fixed" => ChunkStrategy::Fixed { size: 8000 },
+            "file" => ChunkStrategy::File,
+            "module" => ChunkStrategy::Module,
+            "semantic" => ChunkStrategy::Semantic,
+            "dependency" => ChunkStrategy::Dependency,
+            other => {
+                return Err(Error::new(
+                    Status::InvalidArg,
+                    format!("Unknown strategy: {}", other),
+                ))
+            },
+        };
+
+        let mut chunker = Chunker::new(strategy, 8000);
+        let mut models = Vec::with_capacity(budgets.len());
+        for budget in &budgets {
+            let model = parse_model(Some(&budget.model))?;
+            chunker = chunker.with_model_budget(model, budget.max_tokens);
+            models.push(model);
+        }
+
+        Ok(chunker
+            .plan_for_models(&self.repo, &models)
+            .into_iter()
+            .map(|plan| ChunkPlan {
+                model: plan.model.name().to_owned(),
+                max_tokens: plan.max_tokens,
+                chunks: plan
+                    .chunks
+                    .into_iter()
+                    .map(|chunk| Chunk {
+                        index: chunk.index as u32,
+                        total: chunk.total as u32,
+                        focus: chunk.focus,
+                        tokens: chunk.tokens,
+                        files: chunk
+                            .files
+                            .into_iter()
+                            .map(|file| ChunkFile {
+                                id: file.id,
+                                path: file.path,
+                                content: file.content,
+                                tokens: file.tokens,
+                            })
+                            .collect(),
+                    })
+                    .collect(),
+            })
+            .collect())
+    }
+}
+
+impl Infiniloom {
+    /// The repository and its map for these options, generating the map on first use
+    fn cached_map(&mut self, budget: u32, max_symbols: u32) -> (&Repository, &RepoMap) {
+        let (repo, model) = (&self.repo, self.model);
+        let map = self.maps.entry((budget, max_symbols, false)).or_insert_with(|| {
+            RepoMapGenerator::new(budget)
+                .with_max_symbols(max_symbols as usize)
+                .with_model(model)
+                .generate(repo)
+        });
+        (repo, map)
+    }
 }
 
 // Helper functions
@@ -371,9 +973,16 @@ fn parse_format(format: Option<&str>) -> Result<OutputFormat> {
         "xml" => Ok(OutputFormat::Xml),
         "markdown" | "md" => Ok(OutputFormat::Markdown),
         "json" => Ok(OutputFormat::Json),
+        "yaml" => Ok(OutputFormat::Yaml),
+        "toon" => Ok(OutputFormat::Toon),
+        "plain" => Ok(OutputFormat::Plain),
+        "signatures" => Ok(OutputFormat::Signatures),
         other => Err(Error::new(
             Status::InvalidArg,
-            format!("Unknown format: {}. Use 'xml', 'markdown', or 'json'", other),
+            format!(
+                "Unknown format: {}. Use 'xml', 'markdown', 'json', 'yaml', 'toon', 'plain', or 'signatures'",
+                other
+            ),
         )),
     }
 }
@@ -413,22 +1022,51 @@ fn parse_compression(compression: Option<&str>) -> Result<CompressionLevel> {
     }
 }
 
+/// The [`ScanReport`] of `repo`, serialized with the CLI's keys
+fn scan_report(repo: &Repository) -> Result<serde_json::Value> {
+    serde_json::to_value(ScanReport::new(repo))
+        .map_err(|e| Error::new(Status::GenericFailure, e.to_string()))
+}
+
+/// The configuration of the repository at `path`, failing on a config file
+/// that does not parse
+fn discover_config(path: &Path) -> Result<Config> {
+    Config::discover(path).map_err(|e| Error::new(Status::InvalidArg, e.to_string()))
+}
+
 fn scan_repository(path: &str, _model: TokenizerModel, read_contents: bool) -> Result<Repository> {
+    scan_repository_with_progress(path, read_contents, &|_| {}, &CancellationToken::new())
+}
+
+/// Scan like [`scan_repository`], calling `on_file` with the number of files
+/// scanned so far and stopping once `cancel` is cancelled
+///
+/// The repository's config file and `.infiniloomignore` decide which files
+/// are kept.
+fn scan_repository_with_progress(
+    path: &str,
+    read_contents: bool,
+    on_file: &(dyn Fn(u32) + Sync),
+    cancel: &CancellationToken,
+) -> Result<Repository> {
     let path_buf = PathBuf::from(path);
 
     if !path_buf.exists() {
-        return Err(Error::new(
-            Status::InvalidArg,
-            format!("Path does not exist: {}", path),
-        ));
+        return Err(Error::new(Status::InvalidArg, format!("Path does not exist: {}", path)));
     }
 
+    let repo_config = discover_config(&path_buf)?;
     let config = ScanConfig {
-        include_hidden: false,
-        respect_gitignore: true,
         read_contents,
-        max_file_size: 50 * 1024 * 1024, // 50MB
+        cancel: cancel.clone(),
+        ..ScanConfig::from_config(&repo_config.scan)
     };
-
-    do_scan(&path_buf, config).map_err(|e| Error::new(Status::GenericFailure, e.to_string()))
+    let on_progress = |p: ScanProgress| on_file(p.files_done as u32);
+    let mut repo = scanner::scan_repository_with_progress(&path_buf, config, &on_progress)
+        .map_err(|e| match e {
+            ScanError::Cancelled(c) => cancelled(c),
+            e => Error::new(Status::GenericFailure, e.to_string()),
+        })?;
+    repo_config.scan.select_files(&mut repo);
+    Ok(repo)
 }
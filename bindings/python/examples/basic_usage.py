@@ -25,10 +25,10 @@ def functional_api_examples():
     # Example 2: Scan repository
     print("2. Scanning repository...")
     stats = infiniloom.scan("../../", respect_gitignore=True)
-    print(f"Name: {stats['name']}")
-    print(f"Files: {stats['total_files']}")
+    print(f"Name: {stats['repository']}")
+    print(f"Files: {stats['files']}")
     print(f"Lines: {stats['total_lines']}")
-    print(f"Claude tokens: {stats['total_tokens']['claude']}")
+    print(f"Claude tokens: {stats['tokens']['claude']}")
     print(f"Languages: {[lang['language'] for lang in stats['languages'][:3]]}")
     print()
 
@@ -60,8 +60,8 @@ def oo_api_examples():
     # Example 1: Get stats
     print("1. Getting statistics...")
     stats = loom.stats()
-    print(f"Repository: {stats['name']}")
-    print(f"Total files: {stats['total_files']}")
+    print(f"Repository: {stats['repository']}")
+    print(f"Total files: {stats['files']}")
     print(f"Total tokens: {stats['tokens']['claude']}\n")
 
     # Example 2: Get repository map
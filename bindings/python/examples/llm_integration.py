@@ -179,8 +179,8 @@ def multi_model_comparison():
     loom = Infiniloom("../../")
     stats = loom.stats()
 
-    print(f"Repository: {stats['name']}")
-    print(f"Files: {stats['total_files']}, Lines: {stats['total_lines']}\n")
+    print(f"Repository: {stats['repository']}")
+    print(f"Files: {stats['files']}, Lines: {stats['total_lines']}\n")
 
     # Generate contexts for different models
     models_config = [
@@ -17,9 +17,12 @@ Functional API (quick and simple):
     >>>
     >>> # Scan a repository and get statistics
     >>> stats = infiniloom.scan("/path/to/repo")
-    >>> print(f"Files: {stats['total_files']}")
+    >>> print(f"Files: {stats['files']}")
     >>> print(f"Lines: {stats['total_lines']}")
     >>>
+    >>> # Pack an uploaded archive without writing it to disk
+    >>> context = infiniloom.pack_archive(archive_bytes, format="xml")
+    >>>
     >>> # Count tokens in text
     >>> tokens = infiniloom.count_tokens("Hello, world!", model="claude")
     >>> print(f"Tokens: {tokens}")
@@ -43,6 +46,10 @@ Object-Oriented API (more control):
     >>> for symbol in repo_map['key_symbols']:
     ...     print(f"{symbol['name']} ({symbol['kind']}) - {symbol['file']}")
     >>>
+    >>> # Save the map and reload it in a later session without rescanning
+    >>> loom.map_object().save("repo-map.json")
+    >>> repo_map = infiniloom.RepoMap.load("repo-map.json")
+    >>>
     >>> # Scan for security issues
     >>> findings = loom.scan_security()
     >>> for finding in findings:
@@ -108,8 +115,8 @@ Advanced repository analysis:
     >>>
     >>> # Get detailed statistics
     >>> stats = loom.stats()
-    >>> print(f"Repository: {stats['name']}")
-    >>> print(f"Total files: {stats['total_files']}")
+    >>> print(f"Repository: {stats['repository']}")
+    >>> print(f"Total files: {stats['files']}")
     >>> print(f"Total lines: {stats['total_lines']}")
     >>> print(f"Claude tokens: {stats['tokens']['claude']}")
     >>>
@@ -150,10 +157,12 @@ Integration with LLM APIs:
 
 from ._infiniloom import (
     pack,
+    pack_archive,
     scan,
     count_tokens,
     scan_security,
     Infiniloom,
+    RepoMap,
     InfiniloomError,
     __version__,
 )
@@ -161,12 +170,14 @@ from ._infiniloom import (
 __all__ = [
     # Functions
     "pack",
+    "pack_archive",
     "scan",
     "count_tokens",
     "scan_security",
 
     # Classes
     "Infiniloom",
+    "RepoMap",
 
     # Exceptions
     "InfiniloomError",
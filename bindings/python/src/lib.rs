@@ -7,15 +7,20 @@ use pyo3::exceptions::{PyIOError, PyValueError};
 use pyo3::types::{PyDict, PyList};
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
 
 // Import from infiniloom-engine
+use infiniloom_engine::cache::write_atomic;
+use infiniloom_engine::output::MarkdownFormatter;
+use infiniloom_engine::pipeline::{PackError, PackOptions, PackPipeline};
 use infiniloom_engine::{
-    CompressionLevel, OutputFormat, OutputFormatter, RepoMap, RepoMapGenerator,
-    Repository, TokenizerModel, SecurityScanner,
+    CancellationToken, HiddenPolicy, Chunk, ChunkStrategy, Chunker, CompressionLevel, Config, OutputFormat, RepoMap, RepoMapGenerator,
+    RepoFile, Repository, ScanOptions, ScanReport, SymbolSpan, TokenizerModel, Tokenizer, SecurityScanner, rank_files, sort_files_by_importance,
 };
 
-mod scanner;
-use scanner::{scan_repository, ScanConfig};
+use infiniloom_engine::scanner::{
+    scan_repository, scan_repository_with_progress, ScanConfig, ScanProgress,
+};
 
 /// Python exception for Infiniloom errors
 pyo3::create_exception!(infiniloom, InfiniloomError, pyo3::exceptions::PyException);
@@ -25,193 +30,404 @@ fn to_py_err(err: impl std::fmt::Display) -> PyErr {
     InfiniloomError::new_err(format!("{}", err))
 }
 
+/// `pathlib.Path` for `path`, decoded like `os.fsdecode` so names that are
+/// not valid UTF-8 survive the round trip
+fn to_py_path(py: Python, path: &Path) -> PyResult<PyObject> {
+    let pathlib = py.import("pathlib")?;
+    Ok(pathlib.getattr("Path")?.call1((path.as_os_str(),))?.into())
+}
+
+/// A file's path relative to the repository root as a `str`, decoded like
+/// `os.fsdecode` rather than lossily
+fn relative_py_path(py: Python, repo: &Repository, file: &RepoFile) -> PyObject {
+    file.path
+        .strip_prefix(&repo.path)
+        .map(|relative| relative.as_os_str().to_object(py))
+        .unwrap_or_else(|_| file.relative_path.to_object(py))
+}
+
+/// How often scanning reports progress, in files
+const PROGRESS_INTERVAL: usize = 100;
+
+/// Forwards progress to an optional Python callable while the GIL is released
+///
+/// The callable gets `(phase, files_done, files_total)`. An exception it raises
+/// stops the work and is raised again by the function that was called.
+struct Progress {
+    callback: Option<PyObject>,
+    error: Mutex<Option<PyErr>>,
+}
+
+impl Progress {
+    fn new(callback: Option<PyObject>) -> Self {
+        Self { callback, error: Mutex::new(None) }
+    }
+
+    /// Call the callable, returning false once it has raised
+    fn report(&self, phase: &str, files_done: usize, files_total: usize) -> bool {
+        let Some(callback) = &self.callback else {
+            return true;
+        };
+        let mut error = self.error.lock().unwrap();
+        if error.is_some() {
+            return false;
+        }
+        Python::with_gil(|py| match callback.call1(py, (phase, files_done, files_total)) {
+            Ok(_) => true,
+            Err(err) => {
+                *error = Some(err);
+                false
+            },
+        })
+    }
+
+    /// Report scanning every [`PROGRESS_INTERVAL`] files and after the last one
+    fn scanned(&self, files_done: usize, files_total: usize) -> bool {
+        if files_done % PROGRESS_INTERVAL != 0 && files_done != files_total {
+            return true;
+        }
+        self.report("scan", files_done, files_total)
+    }
+
+    /// Scan with `config`, cancelling its token once the callable has raised
+    fn scan(&self, path: &Path, config: ScanConfig) -> PyResult<Repository> {
+        let cancel = config.cancel.clone();
+        let on_progress = |p: ScanProgress| {
+            if !self.scanned(p.files_done, p.files_total) {
+                cancel.cancel();
+            }
+        };
+        scan_repository_with_progress(path, config, &on_progress).map_err(to_py_err)
+    }
+
+    /// `result`, or the exception the callable raised
+    fn result<T>(&self, result: PyResult<T>) -> PyResult<T> {
+        match self.error.lock().unwrap().take() {
+            Some(err) => Err(err),
+            None => result,
+        }
+    }
+}
+
 /// Pack a repository into an LLM-optimized format
 ///
 /// Args:
 ///     path: Path to the repository
-///     format: Output format ("xml", "markdown", "json", "yaml")
+///     format: Output format ("xml", "markdown", "json", "yaml", "signatures")
 ///     model: Target LLM model ("claude", "gpt", "gemini")
 ///     compression: Compression level ("none", "minimal", "balanced", "aggressive", "extreme")
 ///     map_budget: Token budget for repository map (default: 2000)
 ///     max_symbols: Maximum number of symbols to include (default: 50)
+///     diff_base: Only pack files changed since this branch or commit, plus the
+///         files they import or are imported by (default: None)
+///     query: Only pack the files relevant to this question, most relevant
+///         first (default: None)
+///     skip_security: Pack files with critical secrets instead of raising
+///         (default: False)
+///     progress: Callable invoked with (phase, files_done, files_total) while
+///         packing; phases are "scan", "compress", "map" and "format". Raising
+///         an exception from it cancels the pack (default: None)
 ///
 /// Returns:
 ///     Formatted repository context as a string
 ///
+/// The repository's config file and ``.infiniloomignore`` decide which files
+/// are scanned, as for the CLI. Other Python threads keep running while the
+/// repository is packed.
+///
 /// Example:
 ///     >>> import infiniloom
 ///     >>> context = infiniloom.pack("/path/to/repo", format="xml", model="claude")
 ///     >>> print(context)
 #[pyfunction]
-#[pyo3(signature = (path, format="xml", model="claude", compression="balanced", map_budget=2000, max_symbols=50))]
+#[pyo3(signature = (path, format="xml", model="claude", compression="balanced", map_budget=2000, max_symbols=50, diff_base=None, query=None, skip_security=false, progress=None))]
 fn pack(
-    path: &str,
+    py: Python,
+    path: PathBuf,
     format: &str,
     model: &str,
     compression: &str,
     map_budget: u32,
     max_symbols: usize,
+    diff_base: Option<&str>,
+    query: Option<&str>,
+    skip_security: bool,
+    progress: Option<PyObject>,
 ) -> PyResult<String> {
-    // Parse format
-    let output_format = match format.to_lowercase().as_str() {
-        "xml" => OutputFormat::Xml,
-        "markdown" | "md" => OutputFormat::Markdown,
-        "json" => OutputFormat::Json,
-        "yaml" | "yml" => OutputFormat::Yaml,
-        _ => return Err(PyValueError::new_err(format!("Invalid format: {}", format))),
-    };
-
-    // Parse model
-    let tokenizer_model = match model.to_lowercase().as_str() {
-        "claude" => TokenizerModel::Claude,
-        "gpt" | "gpt-4" => TokenizerModel::Gpt4,
-        "gpt-4o" | "gpt4o" => TokenizerModel::Gpt4o,
-        "gemini" => TokenizerModel::Gemini,
-        "llama" => TokenizerModel::Llama,
-        _ => return Err(PyValueError::new_err(format!("Invalid model: {}", model))),
-    };
-
-    // Parse compression level
-    let _compression_level = match compression.to_lowercase().as_str() {
-        "none" => CompressionLevel::None,
-        "minimal" => CompressionLevel::Minimal,
-        "balanced" => CompressionLevel::Balanced,
-        "aggressive" => CompressionLevel::Aggressive,
-        "extreme" => CompressionLevel::Extreme,
-        "semantic" => CompressionLevel::Semantic,
-        _ => return Err(PyValueError::new_err(format!("Invalid compression: {}", compression))),
-    };
-
-    // Scan repository
-    let path_buf = PathBuf::from(path);
+    let repo_config = discover_config(&path)?;
+    let options = pack_options(
+        format,
+        model,
+        compression,
+        map_budget,
+        max_symbols,
+        diff_base,
+        query,
+        skip_security,
+    )?
+    .with_scan(ScanOptions::default().with_config(&repo_config.scan));
+
+    // The scan stops at the next file and the pipeline at the next phase
+    // once the callable has raised
+    let progress = Arc::new(Progress::new(progress));
+    let cancel = CancellationToken::new();
     let config = ScanConfig {
-        include_hidden: false,
-        respect_gitignore: true,
         read_contents: true,
-        max_file_size: 50 * 1024 * 1024, // 50MB
+        cancel: cancel.clone(),
+        ..ScanConfig::from_config(&repo_config.scan)
     };
+    let reporter = Arc::clone(&progress);
+    let stop = cancel.clone();
+    let pipeline = PackPipeline::new(options)
+        .with_cancellation(cancel)
+        .with_progress(move |phase, files| {
+            if !reporter.report(phase.name(), files, files) {
+                stop.cancel();
+            }
+        });
 
-    let repo = scan_repository(&path_buf, config).map_err(to_py_err)?;
+    let result = py.allow_threads(|| {
+        let repo = progress.scan(&path, config)?;
+        pipeline.pack_repository(repo).map_err(pack_error)
+    });
+    progress.result(result)
+}
+
+/// Pack a tar, tar.gz or zip archive held in memory
+///
+/// Nothing is written to disk, so uploaded archives can be packed where the
+/// filesystem is read-only, such as in serverless functions. A single
+/// top-level directory, as in GitHub tarballs, is stripped and names the
+/// repository. `.gitignore` files inside the archive are not applied.
+///
+/// Args:
+///     data: Archive bytes
+///     format: Output format ("xml", "markdown", "json", "yaml", "signatures")
+///     model: Target LLM model ("claude", "gpt", "gemini")
+///     compression: Compression level ("none", "minimal", "balanced", "aggressive", "extreme")
+///     map_budget: Token budget for repository map (default: 2000)
+///     max_symbols: Maximum number of symbols to include (default: 50)
+///     query: Only pack the files relevant to this question, most relevant
+///         first (default: None)
+///     name: Repository name if the archive has no top-level directory
+///         (default: "repository")
+///     skip_security: Pack files with critical secrets instead of raising
+///         (default: False)
+///
+/// Returns:
+///     Formatted repository context as a string
+///
+/// Example:
+///     >>> import infiniloom
+///     >>> with open("repo.tar.gz", "rb") as f:
+///     ...     context = infiniloom.pack_archive(f.read(), format="xml")
+#[pyfunction]
+#[pyo3(signature = (data, format="xml", model="claude", compression="balanced", map_budget=2000, max_symbols=50, query=None, name="repository", skip_security=false))]
+fn pack_archive(
+    py: Python,
+    data: &[u8],
+    format: &str,
+    model: &str,
+    compression: &str,
+    map_budget: u32,
+    max_symbols: usize,
+    query: Option<&str>,
+    name: &str,
+    skip_security: bool,
+) -> PyResult<String> {
+    let options = pack_options(
+        format,
+        model,
+        compression,
+        map_budget,
+        max_symbols,
+        None,
+        query,
+        skip_security,
+    )?;
+
+    py.allow_threads(|| {
+        PackPipeline::new(options)
+            .pack_archive(data, name)
+            .map_err(pack_error)
+    })
+}
 
-    // Generate repository map
-    let generator = RepoMapGenerator::new(map_budget)
+/// Engine pack options from the arguments of the `pack` functions
+fn pack_options(
+    format: &str,
+    model: &str,
+    compression: &str,
+    map_budget: u32,
+    max_symbols: usize,
+    diff_base: Option<&str>,
+    query: Option<&str>,
+    skip_security: bool,
+) -> PyResult<PackOptions> {
+    let mut options = PackOptions::default()
+        .with_format(parse_format(format)?)
+        .with_model(parse_model(model)?)
+        .with_compression(parse_compression(compression)?)
+        .with_map_budget(map_budget)
         .with_max_symbols(max_symbols)
-        .with_model(tokenizer_model);
-    let map = generator.generate(&repo);
+        .with_secret_blocking(!skip_security);
+    if let Some(base) = diff_base {
+        options = options.with_diff_base(base);
+    }
+    if let Some(query) = query {
+        options = options.with_query(query);
+    }
+    Ok(options)
+}
 
-    // Format output
-    let formatter = OutputFormatter::by_format(output_format);
-    let output = formatter.format(&repo, &map);
+/// Convert pack errors, pointing at `skip_security` when secrets stopped the pack
+fn pack_error(err: PackError) -> PyErr {
+    match err {
+        PackError::Secrets(path) => to_py_err(format!(
+            "Critical security issues found in {}; pass skip_security=True to pack anyway",
+            path
+        )),
+        err => to_py_err(err),
+    }
+}
+
+/// The configuration of the repository at `path`, raising on a config file
+/// that does not parse
+fn discover_config(path: &Path) -> PyResult<Config> {
+    Config::discover(path).map_err(to_py_err)
+}
 
-    Ok(output)
+fn parse_format(format: &str) -> PyResult<OutputFormat> {
+    match format.to_lowercase().as_str() {
+        "xml" => Ok(OutputFormat::Xml),
+        "markdown" | "md" => Ok(OutputFormat::Markdown),
+        "json" => Ok(OutputFormat::Json),
+        "yaml" | "yml" => Ok(OutputFormat::Yaml),
+        "signatures" => Ok(OutputFormat::Signatures),
+        _ => Err(PyValueError::new_err(format!("Invalid format: {}", format))),
+    }
 }
 
-/// Scan a repository and return statistics
+/// Scan a repository and return its scan report
+///
+/// The report has the same layout as `infiniloom scan --json`, versioned by
+/// its `schema_version` key.
 ///
 /// Args:
 ///     path: Path to the repository
-///     include_hidden: Include hidden files (default: False)
-///     respect_gitignore: Respect .gitignore files (default: True)
+///     include_hidden: Include hidden files (default: ``scan.include_hidden``
+///         from the config, else False)
+///     respect_gitignore: Respect .gitignore files (default:
+///         ``scan.respect_gitignore`` from the config, else True)
+///     hidden_allowlist: Extra hidden paths to include, on top of defaults
+///         such as .github and .env.example and ``scan.hidden_allowlist``
+///         from the config (default: None)
+///     progress: Callable invoked with ("scan", files_done, files_total) while
+///         scanning. Raising an exception from it cancels the scan (default: None)
 ///
 /// Returns:
-///     Dictionary with repository statistics
+///     Dictionary with the scan report: repository, path, files, total_bytes,
+///     total_lines, tokens, languages, largest_files, dependencies, and
+///     optionally git and framework
+///
+/// Files left out by ``scan.include``, ``scan.exclude``, the language lists
+/// or ``.infiniloomignore`` are not counted.
 ///
 /// Example:
 ///     >>> import infiniloom
 ///     >>> stats = infiniloom.scan("/path/to/repo")
-///     >>> print(stats["total_files"])
+///     >>> print(stats["files"], stats["tokens"]["claude"])
 #[pyfunction]
-#[pyo3(signature = (path, include_hidden=false, respect_gitignore=true))]
+#[pyo3(signature = (path, include_hidden=None, respect_gitignore=None, hidden_allowlist=None, progress=None))]
 fn scan(
     py: Python,
-    path: &str,
-    include_hidden: bool,
-    respect_gitignore: bool,
+    path: PathBuf,
+    include_hidden: Option<bool>,
+    respect_gitignore: Option<bool>,
+    hidden_allowlist: Option<Vec<String>>,
+    progress: Option<PyObject>,
 ) -> PyResult<PyObject> {
-    let path_buf = PathBuf::from(path);
+    let repo_config = discover_config(&path)?;
     let config = ScanConfig {
-        include_hidden,
-        respect_gitignore,
-        read_contents: false,
-        max_file_size: 50 * 1024 * 1024,
+        hidden: HiddenPolicy::new(include_hidden.unwrap_or(repo_config.scan.include_hidden))
+            .with_allowed(&repo_config.scan.hidden_allowlist)
+            .with_allowed(hidden_allowlist.unwrap_or_default()),
+        respect_gitignore: respect_gitignore.unwrap_or(repo_config.scan.respect_gitignore),
+        read_contents: true,
+        ..ScanConfig::from_config(&repo_config.scan)
     };
 
-    let repo = scan_repository(&path_buf, config).map_err(to_py_err)?;
+    let progress = Progress::new(progress);
+    let result = py.allow_threads(|| {
+        let mut repo = progress.scan(&path, config)?;
+        repo_config.scan.select_files(&mut repo);
+        Ok((ScanReport::new(&repo), repo.path))
+    });
+    let (report, path) = progress.result(result)?;
 
-    // Convert to Python dict
-    let dict = PyDict::new(py);
-    dict.set_item("name", repo.name)?;
-    dict.set_item("path", repo.path.to_string_lossy().to_string())?;
-    dict.set_item("total_files", repo.metadata.total_files)?;
-    dict.set_item("total_lines", repo.metadata.total_lines)?;
-
-    // Token counts
-    let tokens = PyDict::new(py);
-    tokens.set_item("claude", repo.metadata.total_tokens.claude)?;
-    tokens.set_item("gpt4o", repo.metadata.total_tokens.gpt4o)?;
-    tokens.set_item("gpt4", repo.metadata.total_tokens.gpt4)?;
-    tokens.set_item("gemini", repo.metadata.total_tokens.gemini)?;
-    tokens.set_item("llama", repo.metadata.total_tokens.llama)?;
-    dict.set_item("total_tokens", tokens)?;
-
-    // Languages
-    let languages = PyList::new(
-        py,
-        repo.metadata.languages.iter().map(|lang| {
-            let lang_dict = PyDict::new(py);
-            lang_dict.set_item("language", &lang.language).unwrap();
-            lang_dict.set_item("files", lang.files).unwrap();
-            lang_dict.set_item("lines", lang.lines).unwrap();
-            lang_dict.set_item("percentage", lang.percentage).unwrap();
-            lang_dict
-        }),
-    );
-    dict.set_item("languages", languages)?;
+    report_to_py(py, &report, &path)
+}
 
-    // Optional metadata
-    if let Some(branch) = repo.metadata.branch {
-        dict.set_item("branch", branch)?;
-    }
-    if let Some(commit) = repo.metadata.commit {
-        dict.set_item("commit", commit)?;
-    }
-    if let Some(framework) = repo.metadata.framework {
-        dict.set_item("framework", framework)?;
-    }
+/// The [`ScanReport`] of `repo` as a dict, with `path` as a `pathlib.Path`
+fn scan_report_to_py(py: Python, repo: &Repository) -> PyResult<PyObject> {
+    report_to_py(py, &ScanReport::new(repo), &repo.path)
+}
 
+/// `report` as a dict, with `path` as a `pathlib.Path`
+fn report_to_py(py: Python, report: &ScanReport, path: &Path) -> PyResult<PyObject> {
+    // Going through JSON keeps the dict in step with the CLI's report
+    let dict = py.import("json")?.call_method1("loads", (report.to_json(),))?;
+    dict.set_item("path", to_py_path(py, path)?)?;
     Ok(dict.into())
 }
 
 /// Count tokens in text for a specific model
 ///
+/// GPT-4 and GPT-4o counts come from their BPE tokenizers; other models are
+/// estimated.
+///
 /// Args:
 ///     text: Text to count tokens for
-///     model: Target LLM model ("claude", "gpt", "gemini")
+///     model: Target LLM model ("claude", "gpt-4o", "gpt-4", "gemini", "llama")
+///     exact: Use the exact tokenizer where one exists (default: True); False
+///         always estimates, which is faster on large inputs
 ///
 /// Returns:
 ///     Number of tokens
 ///
 /// Example:
 ///     >>> import infiniloom
-///     >>> tokens = infiniloom.count_tokens("Hello, world!", model="claude")
+///     >>> tokens = infiniloom.count_tokens("Hello, world!", model="gpt-4o")
 ///     >>> print(tokens)
 #[pyfunction]
-#[pyo3(signature = (text, model="claude"))]
-fn count_tokens(text: &str, model: &str) -> PyResult<u32> {
-    // Simple estimation based on character count
-    // Real implementation would use actual tokenizers
-    let len = text.len() as f32;
-
-    let tokens = match model.to_lowercase().as_str() {
-        "claude" => (len / 3.5) as u32,
-        "gpt" | "gpt-4" => (len / 3.7) as u32,
-        "gpt-4o" | "gpt4o" => (len / 4.0) as u32,
-        "gemini" => (len / 3.8) as u32,
-        "llama" => (len / 3.5) as u32,
-        _ => return Err(PyValueError::new_err(format!("Invalid model: {}", model))),
-    };
+#[pyo3(signature = (text, model="claude", exact=true))]
+fn count_tokens(text: &str, model: &str, exact: bool) -> PyResult<u32> {
+    let tokenizer = if exact { Tokenizer::new() } else { Tokenizer::estimation_only() };
+    Ok(tokenizer.count(text, parse_model(model)?.into()))
+}
+
+/// Parse a model name as accepted by `pack` and `count_tokens`
+fn parse_model(model: &str) -> PyResult<TokenizerModel> {
+    match model.to_lowercase().as_str() {
+        "claude" => Ok(TokenizerModel::Claude),
+        "gpt" | "gpt-4" | "gpt4" => Ok(TokenizerModel::Gpt4),
+        "gpt-4o" | "gpt4o" => Ok(TokenizerModel::Gpt4o),
+        "gemini" => Ok(TokenizerModel::Gemini),
+        "llama" => Ok(TokenizerModel::Llama),
+        _ => Err(PyValueError::new_err(format!("Invalid model: {}", model))),
+    }
+}
 
-    Ok(tokens)
+fn parse_compression(compression: &str) -> PyResult<CompressionLevel> {
+    match compression.to_lowercase().as_str() {
+        "none" => Ok(CompressionLevel::None),
+        "minimal" => Ok(CompressionLevel::Minimal),
+        "balanced" => Ok(CompressionLevel::Balanced),
+        "aggressive" => Ok(CompressionLevel::Aggressive),
+        "extreme" => Ok(CompressionLevel::Extreme),
+        "semantic" => Ok(CompressionLevel::Semantic),
+        _ => Err(PyValueError::new_err(format!("Invalid compression: {}", compression))),
+    }
 }
 
 /// Scan repository for security issues
@@ -228,17 +444,22 @@ fn count_tokens(text: &str, model: &str) -> PyResult<u32> {
 ///     >>> for finding in findings:
 ///     ...     print(finding["severity"], finding["message"])
 #[pyfunction]
-fn scan_security(py: Python, path: &str) -> PyResult<PyObject> {
-    let path_buf = PathBuf::from(path);
+fn scan_security(py: Python, path: PathBuf) -> PyResult<PyObject> {
+    let repo_config = discover_config(&path)?;
     let config = ScanConfig {
-        include_hidden: false,
-        respect_gitignore: true,
         read_contents: true,
-        max_file_size: 10 * 1024 * 1024, // 10MB for security scan
+        skip_symbols: true,
+        ..ScanConfig::from_config(&repo_config.scan)
     };
 
-    let repo = scan_repository(&path_buf, config).map_err(to_py_err)?;
+    let mut repo = scan_repository(&path, config).map_err(to_py_err)?;
+    repo_config.scan.select_files(&mut repo);
+
+    security_findings(py, &repo)
+}
 
+/// Security findings for every file in `repo` as a list of dicts
+fn security_findings(py: Python, repo: &Repository) -> PyResult<PyObject> {
     let scanner = SecurityScanner::new();
     let mut all_findings = Vec::new();
 
@@ -246,16 +467,16 @@ fn scan_security(py: Python, path: &str) -> PyResult<PyObject> {
     for file in &repo.files {
         if let Some(content) = &file.content {
             let findings = scanner.scan(content, &file.relative_path);
-            all_findings.extend(findings);
+            all_findings.extend(findings.into_iter().map(|finding| (file, finding)));
         }
     }
 
     // Convert findings to Python list
     let results = PyList::new(
         py,
-        all_findings.iter().map(|finding| {
+        all_findings.iter().map(|(file, finding)| {
             let dict = PyDict::new(py);
-            dict.set_item("file", &finding.file).unwrap();
+            dict.set_item("file", relative_py_path(py, repo, file)).unwrap();
             dict.set_item("line", finding.line).unwrap();
             dict.set_item("severity", format!("{:?}", finding.severity)).unwrap();
             dict.set_item("kind", finding.kind.name()).unwrap();
@@ -274,112 +495,174 @@ fn scan_security(py: Python, path: &str) -> PyResult<PyObject> {
 ///     >>> loom = Infiniloom("/path/to/repo")
 ///     >>> stats = loom.stats()
 ///     >>> context = loom.pack(format="xml", model="claude")
+///
+/// The scanned repository and every generated map are cached on the
+/// object, so repeated ``pack()`` calls with different formats only pay for
+/// formatting. Call ``invalidate()`` after the files on disk change.
+///
+/// ``load()`` and ``pack()`` release the GIL, so another thread can stop
+/// them with ``cancel()``.
+///
+/// The repository's config file is read once, when the object is created.
 #[pyclass]
 struct Infiniloom {
     path: PathBuf,
+    /// Discovered for `path` by `Config::discover`
+    config: Config,
     repo: Option<Repository>,
+    /// Generated maps keyed by (map budget, max symbols, model, of the
+    /// packed files rather than all scanned files)
+    maps: HashMap<MapKey, RepoMap>,
+    /// Cancelled by `cancel()`, replaced once the cancelled call returns
+    cancel: CancellationToken,
 }
 
+/// Options that affect map generation
+type MapKey = (u32, usize, &'static str, bool);
+
 #[pymethods]
 impl Infiniloom {
     /// Create a new Infiniloom instance
     ///
     /// Args:
     ///     path: Path to the repository
+    ///
+    /// Raises:
+    ///     InfiniloomError: If the repository's config file does not parse
     #[new]
-    fn new(path: &str) -> PyResult<Self> {
-        let path_buf = PathBuf::from(path);
-        if !path_buf.exists() {
-            return Err(PyIOError::new_err(format!("Path does not exist: {}", path)));
+    fn new(path: PathBuf) -> PyResult<Self> {
+        if !path.exists() {
+            return Err(PyIOError::new_err(format!("Path does not exist: {}", path.display())));
         }
 
         Ok(Infiniloom {
-            path: path_buf,
+            config: discover_config(&path)?,
+            path,
             repo: None,
+            maps: HashMap::new(),
+            cancel: CancellationToken::new(),
         })
     }
 
     /// Scan the repository and load it into memory
-    fn load(&mut self, include_hidden: bool, respect_gitignore: bool) -> PyResult<()> {
-        let config = ScanConfig {
-            include_hidden,
-            respect_gitignore,
-            read_contents: true,
-            max_file_size: 50 * 1024 * 1024,
+    ///
+    /// ``include_hidden`` and ``respect_gitignore`` default to the config's
+    /// ``scan`` settings.
+    #[pyo3(signature = (include_hidden=None, respect_gitignore=None))]
+    fn load(
+        slf: &PyCell<Self>,
+        py: Python,
+        include_hidden: Option<bool>,
+        respect_gitignore: Option<bool>,
+    ) -> PyResult<()> {
+        let (path, mut config, cancel) = {
+            let this = slf.borrow();
+            (this.path.clone(), this.config.scan.clone(), this.cancel.clone())
         };
-
-        let repo = scan_repository(&self.path, config).map_err(to_py_err)?;
-        self.repo = Some(repo);
+        config.include_hidden = include_hidden.unwrap_or(config.include_hidden);
+        config.respect_gitignore = respect_gitignore.unwrap_or(config.respect_gitignore);
+        let result = py.allow_threads(|| load_repository(&path, &config, &cancel));
+
+        let mut this = slf.borrow_mut();
+        this.reset_cancelled();
+        this.repo = Some(result?);
+        this.maps.clear();
         Ok(())
     }
 
-    /// Get repository statistics
+    /// Stop the ``load()`` or ``pack()`` running in another thread
+    ///
+    /// The stopped call raises ``InfiniloomError``. Called while nothing is
+    /// running, it stops the next call instead. Either way the object can
+    /// be used again afterwards.
+    fn cancel(&self) {
+        self.cancel.cancel();
+    }
+
+    /// Drop the cached scan and maps so the next call rescans the repository
+    fn invalidate(&mut self) {
+        self.repo = None;
+        self.maps.clear();
+    }
+
+    /// Get the scan report, as returned by `scan()`
     fn stats(&mut self, py: Python) -> PyResult<PyObject> {
         if self.repo.is_none() {
-            self.load(false, true)?;
+            self.scan_and_store()?;
         }
 
-        let repo = self.repo.as_ref().unwrap();
-
-        let dict = PyDict::new(py);
-        dict.set_item("name", &repo.name)?;
-        dict.set_item("path", repo.path.to_string_lossy().to_string())?;
-        dict.set_item("total_files", repo.metadata.total_files)?;
-        dict.set_item("total_lines", repo.metadata.total_lines)?;
-
-        let tokens = PyDict::new(py);
-        tokens.set_item("claude", repo.metadata.total_tokens.claude)?;
-        tokens.set_item("gpt4o", repo.metadata.total_tokens.gpt4o)?;
-        tokens.set_item("gpt4", repo.metadata.total_tokens.gpt4)?;
-        tokens.set_item("gemini", repo.metadata.total_tokens.gemini)?;
-        tokens.set_item("llama", repo.metadata.total_tokens.llama)?;
-        dict.set_item("tokens", tokens)?;
-
-        Ok(dict.into())
+        scan_report_to_py(py, self.repo.as_ref().unwrap())
     }
 
     /// Pack the repository into an LLM-optimized format
-    #[pyo3(signature = (format="xml", model="claude", compression="balanced", map_budget=2000))]
+    ///
+    /// With ``diff_base``, only the files changed since that branch or commit
+    /// and their dependency neighborhood are packed; with ``query``, only the
+    /// files relevant to that question, most relevant first. Neither map is
+    /// cached. Critical secrets raise ``InfiniloomError`` unless
+    /// ``skip_security`` is true.
+    #[pyo3(signature = (format="xml", model="claude", compression="balanced", map_budget=2000, diff_base=None, query=None, skip_security=false))]
     fn pack(
-        &mut self,
+        slf: &PyCell<Self>,
+        py: Python,
         format: &str,
         model: &str,
         compression: &str,
         map_budget: u32,
+        diff_base: Option<&str>,
+        query: Option<&str>,
+        skip_security: bool,
     ) -> PyResult<String> {
-        if self.repo.is_none() {
-            self.load(false, true)?;
+        let options = pack_options(
+            format,
+            model,
+            compression,
+            map_budget,
+            50,
+            diff_base,
+            query,
+            skip_security,
+        )?
+        .with_scan(ScanOptions::default().with_config(&slf.borrow().config.scan));
+
+        if slf.borrow().repo.is_none() {
+            Self::load(slf, py, None, None)?;
         }
 
-        let repo = self.repo.as_ref().unwrap();
-
-        // Parse format
-        let output_format = match format.to_lowercase().as_str() {
-            "xml" => OutputFormat::Xml,
-            "markdown" | "md" => OutputFormat::Markdown,
-            "json" => OutputFormat::Json,
-            "yaml" | "yml" => OutputFormat::Yaml,
-            _ => return Err(PyValueError::new_err(format!("Invalid format: {}", format))),
+        // Reuse the map of the packed files when only the format or the
+        // compression changed, since compression only rewrites contents
+        let key = (map_budget, 50, options.model.name(), true);
+        let cacheable = diff_base.is_none() && query.is_none();
+        let (mut repo, cached, cancel) = {
+            let this = slf.borrow();
+            let repo = this
+                .repo
+                .clone()
+                .ok_or_else(|| to_py_err("Repository was invalidated"))?;
+            let cached = if cacheable {
+                this.maps.get(&key).cloned()
+            } else {
+                None
+            };
+            (repo, cached, this.cancel.clone())
         };
 
-        // Parse model
-        let tokenizer_model = match model.to_lowercase().as_str() {
-            "claude" => TokenizerModel::Claude,
-            "gpt" | "gpt-4" => TokenizerModel::Gpt4,
-            "gpt-4o" | "gpt4o" => TokenizerModel::Gpt4o,
-            "gemini" => TokenizerModel::Gemini,
-            "llama" => TokenizerModel::Llama,
-            _ => return Err(PyValueError::new_err(format!("Invalid model: {}", model))),
-        };
-
-        // Generate repository map
-        let generator = RepoMapGenerator::new(map_budget).with_model(tokenizer_model);
-        let map = generator.generate(repo);
-
-        // Format output
-        let formatter = OutputFormatter::by_format(output_format);
-        let output = formatter.format(repo, &map);
-
+        let pipeline = PackPipeline::new(options).with_cancellation(cancel);
+        let result = py.allow_threads(|| -> Result<(String, RepoMap), PackError> {
+            pipeline.prepare(&mut repo)?;
+            let map = match cached {
+                Some(map) => map,
+                None => pipeline.map(&repo)?,
+            };
+            Ok((pipeline.format(&repo, &map)?, map))
+        });
+
+        let mut this = slf.borrow_mut();
+        this.reset_cancelled();
+        let (output, map) = result.map_err(pack_error)?;
+        if cacheable {
+            this.maps.entry(key).or_insert(map);
+        }
         Ok(output)
     }
 
@@ -387,78 +670,148 @@ impl Infiniloom {
     #[pyo3(signature = (map_budget=2000, max_symbols=50))]
     fn map(&mut self, py: Python, map_budget: u32, max_symbols: usize) -> PyResult<PyObject> {
         if self.repo.is_none() {
-            self.load(false, true)?;
+            self.scan_and_store()?;
         }
 
-        let repo = self.repo.as_ref().unwrap();
-        let generator = RepoMapGenerator::new(map_budget).with_max_symbols(max_symbols);
-        let map = generator.generate(repo);
+        let (_, map) = self.cached_map(map_budget, max_symbols, TokenizerModel::Claude)?;
+        map_to_dict(py, map)
+    }
 
-        // Convert to Python dict
-        let dict = PyDict::new(py);
-        dict.set_item("summary", &map.summary)?;
-        dict.set_item("token_count", map.token_count)?;
+    /// Get the repository map as a ``RepoMap`` that can be saved and reloaded
+    ///
+    /// Args:
+    ///     map_budget: Token budget for the map (default: 2000)
+    ///     max_symbols: Maximum key symbols (default: 50)
+    ///
+    /// Example:
+    ///     >>> loom.map_object().save("repo-map.json")
+    ///     >>> repo_map = RepoMap.load("repo-map.json")  # no rescan
+    #[pyo3(signature = (map_budget=2000, max_symbols=50))]
+    fn map_object(&mut self, map_budget: u32, max_symbols: usize) -> PyResult<PyRepoMap> {
+        let (_, map) = self.cached_map(map_budget, max_symbols, TokenizerModel::Claude)?;
+        Ok(PyRepoMap { map: map.clone() })
+    }
 
-        // Key symbols
-        let symbols = PyList::new(
-            py,
-            map.key_symbols.iter().map(|sym| {
-                let sym_dict = PyDict::new(py);
-                sym_dict.set_item("name", &sym.name).unwrap();
-                sym_dict.set_item("kind", &sym.kind).unwrap();
-                sym_dict.set_item("file", &sym.file).unwrap();
-                sym_dict.set_item("line", sym.line).unwrap();
-                sym_dict.set_item("rank", sym.rank).unwrap();
-                sym_dict.set_item("importance", sym.importance).unwrap();
-                if let Some(sig) = &sym.signature {
-                    sym_dict.set_item("signature", sig).unwrap();
-                }
-                sym_dict
-            }),
-        );
-        dict.set_item("key_symbols", symbols)?;
+    /// Split the repository into chunks for multi-turn ingestion
+    ///
+    /// Args:
+    ///     strategy: "fixed", "file", "module", "semantic" or "dependency"
+    ///     max_tokens: Token budget per chunk (default: 8000)
+    ///     overlap: Tokens of context repeated between chunks (default: 200)
+    ///     model: Model used for token counting (default: "claude")
+    ///
+    /// Returns:
+    ///     List of chunk dicts with "index", "total", "focus", "tokens",
+    ///     "fill_ratio", "files" and "context"
+    ///
+    /// Example:
+    ///     >>> for chunk in loom.chunks(max_tokens=4000):
+    ///     ...     send(chunk["context"]["current_focus"], chunk["files"])
+    #[pyo3(signature = (strategy="semantic", max_tokens=8000, overlap=200, model="claude"))]
+    fn chunks(
+        &mut self,
+        py: Python,
+        strategy: &str,
+        max_tokens: u32,
+        overlap: u32,
+        model: &str,
+    ) -> PyResult<PyObject> {
+        let strategy = match strategy.to_lowercase().as_str() {
+            "fixed" => ChunkStrategy::Fixed { size: max_tokens },
+            "file" => ChunkStrategy::File,
+            "module" => ChunkStrategy::Module,
+            "semantic" => ChunkStrategy::Semantic,
+            "dependency" => ChunkStrategy::Dependency,
+            _ => return Err(PyValueError::new_err(format!("Invalid strategy: {}", strategy))),
+        };
+        let tokenizer_model = parse_model(model)?;
+
+        if self.repo.is_none() {
+            self.scan_and_store()?;
+        }
+
+        let chunks = Chunker::new(strategy, max_tokens)
+            .with_overlap(overlap)
+            .with_model(tokenizer_model)
+            .chunk(self.repo.as_ref().unwrap());
 
-        Ok(dict.into())
+        let list = PyList::empty(py);
+        for chunk in &chunks {
+            list.append(chunk_to_dict(py, chunk)?)?;
+        }
+        Ok(list.into())
     }
 
-    /// Scan for security issues
-    fn scan_security(&mut self, py: Python) -> PyResult<PyObject> {
+    /// Chunk the repository for several models at once, each with its own budget
+    ///
+    /// Every model's chunks are counted with its own tokenizer from the one
+    /// scan, so targeting several context windows does not rescan.
+    ///
+    /// Args:
+    ///     budgets: Dict of model name to token budget per chunk
+    ///     strategy: "fixed", "file", "module", "semantic" or "dependency"
+    ///     overlap: Tokens of context repeated between chunks (default: 200)
+    ///
+    /// Returns:
+    ///     Dict of model name to a list of chunk dicts, as from ``chunks()``
+    ///
+    /// Example:
+    ///     >>> plans = loom.chunk_plans({"claude": 150000, "gpt-4": 6000})
+    ///     >>> len(plans["gpt-4"]) >= len(plans["claude"])
+    ///     True
+    #[pyo3(signature = (budgets, strategy="semantic", overlap=200))]
+    fn chunk_plans(
+        &mut self,
+        py: Python,
+        budgets: &PyDict,
+        strategy: &str,
+        overlap: u32,
+    ) -> PyResult<PyObject> {
+        let strategy = match strategy.to_lowercase().as_str() {
+            "fixed" => ChunkStrategy::Fixed { size: 8000 },
+            "file" => ChunkStrategy::File,
+            "module" => ChunkStrategy::Module,
+            "semantic" => ChunkStrategy::Semantic,
+            "dependency" => ChunkStrategy::Dependency,
+            _ => return Err(PyValueError::new_err(format!("Invalid strategy: {}", strategy))),
+        };
+
+        let mut chunker = Chunker::new(strategy, 8000).with_overlap(overlap);
+        let mut models = Vec::with_capacity(budgets.len());
+        for (name, budget) in budgets.iter() {
+            let model = parse_model(name.extract()?)?;
+            chunker = chunker.with_model_budget(model, budget.extract()?);
+            models.push(model);
+        }
+
         if self.repo.is_none() {
-            self.load(false, true)?;
+            self.scan_and_store()?;
         }
 
-        let repo = self.repo.as_ref().unwrap();
-        let scanner = SecurityScanner::new();
-        let mut all_findings = Vec::new();
-
-        // Scan each file's content
-        for file in &repo.files {
-            if let Some(content) = &file.content {
-                let findings = scanner.scan(content, &file.relative_path);
-                all_findings.extend(findings);
+        let result = PyDict::new(py);
+        for plan in chunker.plan_for_models(self.repo.as_ref().unwrap(), &models) {
+            let list = PyList::empty(py);
+            for chunk in &plan.chunks {
+                list.append(chunk_to_dict(py, chunk)?)?;
             }
+            result.set_item(plan.model.name(), list)?;
         }
+        Ok(result.into())
+    }
 
-        let results = PyList::new(
-            py,
-            all_findings.iter().map(|finding| {
-                let dict = PyDict::new(py);
-                dict.set_item("file", &finding.file).unwrap();
-                dict.set_item("line", finding.line).unwrap();
-                dict.set_item("severity", format!("{:?}", finding.severity)).unwrap();
-                dict.set_item("kind", finding.kind.name()).unwrap();
-                dict.set_item("pattern", &finding.pattern).unwrap();
-                dict
-            }),
-        );
+    /// Scan for security issues
+    fn scan_security(&mut self, py: Python) -> PyResult<PyObject> {
+        if self.repo.is_none() {
+            self.scan_and_store()?;
+        }
 
-        Ok(results.into())
+        security_findings(py, self.repo.as_ref().unwrap())
     }
 
     /// Get list of files in the repository
     fn files(&mut self, py: Python) -> PyResult<PyObject> {
         if self.repo.is_none() {
-            self.load(false, true)?;
+            self.scan_and_store()?;
         }
 
         let repo = self.repo.as_ref().unwrap();
@@ -467,7 +820,7 @@ impl Infiniloom {
             py,
             repo.files.iter().map(|file| {
                 let dict = PyDict::new(py);
-                dict.set_item("path", &file.relative_path).unwrap();
+                dict.set_item("path", relative_py_path(py, repo, file)).unwrap();
                 if let Some(lang) = &file.language {
                     dict.set_item("language", lang).unwrap();
                 }
@@ -481,6 +834,45 @@ impl Infiniloom {
         Ok(files.into())
     }
 
+    /// Innermost symbol containing `line` (1-based) in the file at `path`
+    ///
+    /// Returns a dict with the symbol's name, kind, parent, file,
+    /// start_line/end_line and UTF-8 start_byte/end_byte, or None.
+    fn symbol_at(&mut self, py: Python, path: PathBuf, line: u32) -> PyResult<PyObject> {
+        if self.repo.is_none() {
+            self.scan_and_store()?;
+        }
+
+        let repo = self.repo.as_ref().unwrap();
+        let span = repo.file(&path).and_then(|file| file.symbol_at(line));
+        span_to_py(py, repo, span)
+    }
+
+    /// First symbol called `name` in the file at `path`, as `symbol_at()`
+    /// returns it
+    ///
+    /// Methods can be qualified with their parent, as in `Parser.parse`.
+    fn find_symbol(&mut self, py: Python, path: PathBuf, name: &str) -> PyResult<PyObject> {
+        if self.repo.is_none() {
+            self.scan_and_store()?;
+        }
+
+        let repo = self.repo.as_ref().unwrap();
+        span_to_py(py, repo, repo.find_symbol(&path, name))
+    }
+
+    /// Repository path as a `pathlib.Path`
+    #[getter]
+    fn path(&self, py: Python) -> PyResult<PyObject> {
+        to_py_path(py, &self.path)
+    }
+
+    /// Number of repository maps currently cached
+    #[getter]
+    fn cached_maps(&self) -> usize {
+        self.maps.len()
+    }
+
     fn __repr__(&self) -> String {
         format!("Infiniloom('{}')", self.path.display())
     }
@@ -490,6 +882,248 @@ impl Infiniloom {
     }
 }
 
+impl Infiniloom {
+    /// Load the repository without releasing the GIL, for methods that need
+    /// it loaded
+    fn scan_and_store(&mut self) -> PyResult<()> {
+        let result = load_repository(&self.path, &self.config.scan, &self.cancel);
+        self.reset_cancelled();
+        self.repo = Some(result?);
+        self.maps.clear();
+        Ok(())
+    }
+
+    /// Give the next call a fresh token once `cancel()` has stopped one
+    fn reset_cancelled(&mut self) {
+        if self.cancel.is_cancelled() {
+            self.cancel = CancellationToken::new();
+        }
+    }
+
+    /// The loaded repository and its map for these options, generating the map once
+    fn cached_map(
+        &mut self,
+        map_budget: u32,
+        max_symbols: usize,
+        model: TokenizerModel,
+    ) -> PyResult<(&Repository, &RepoMap)> {
+        if self.repo.is_none() {
+            self.scan_and_store()?;
+        }
+
+        let key = (map_budget, max_symbols, model.name(), false);
+        if !self.maps.contains_key(&key) {
+            let result = RepoMapGenerator::new(map_budget)
+                .with_max_symbols(max_symbols)
+                .with_model(model)
+                .try_generate(self.repo.as_ref().unwrap(), &self.cancel);
+            self.reset_cancelled();
+            self.maps.insert(key, result.map_err(to_py_err)?);
+        }
+        Ok((self.repo.as_ref().unwrap(), &self.maps[&key]))
+    }
+}
+
+/// A symbol span as the dict returned by `Infiniloom.symbol_at()`, or None
+fn span_to_py(py: Python, repo: &Repository, span: Option<SymbolSpan<'_>>) -> PyResult<PyObject> {
+    let Some(span) = span else {
+        return Ok(py.None());
+    };
+    let dict = PyDict::new(py);
+    dict.set_item("name", &span.symbol.name)?;
+    dict.set_item("kind", span.symbol.kind.name())?;
+    dict.set_item("parent", &span.symbol.parent)?;
+    dict.set_item("file", relative_py_path(py, repo, span.file))?;
+    dict.set_item("start_line", span.start_line)?;
+    dict.set_item("end_line", span.end_line)?;
+    dict.set_item("start_byte", span.bytes.as_ref().map(|b| b.start))?;
+    dict.set_item("end_byte", span.bytes.as_ref().map(|b| b.end))?;
+    Ok(dict.into())
+}
+
+/// A repository map as the dict returned by `Infiniloom.map()`
+fn map_to_dict(py: Python, map: &RepoMap) -> PyResult<PyObject> {
+    let dict = PyDict::new(py);
+    dict.set_item("summary", &map.summary)?;
+    dict.set_item("token_count", map.token_count)?;
+
+    // Key symbols
+    let symbols = PyList::new(
+        py,
+        map.key_symbols.iter().map(|sym| {
+            let sym_dict = PyDict::new(py);
+            if let Some(id) = &sym.id {
+                sym_dict.set_item("id", id).unwrap();
+            }
+            sym_dict.set_item("name", &sym.name).unwrap();
+            sym_dict.set_item("kind", &sym.kind).unwrap();
+            sym_dict.set_item("file", &sym.file).unwrap();
+            sym_dict.set_item("line", sym.line).unwrap();
+            sym_dict.set_item("rank", sym.rank).unwrap();
+            sym_dict.set_item("importance", sym.importance).unwrap();
+            if let Some(sig) = &sym.signature {
+                sym_dict.set_item("signature", sig).unwrap();
+            }
+            sym_dict
+        }),
+    );
+    dict.set_item("key_symbols", symbols)?;
+
+    // External dependencies with the files importing them
+    let usage = PyList::new(
+        py,
+        map.external_usage.iter().map(|usage| {
+            let usage_dict = PyDict::new(py);
+            usage_dict.set_item("package", &usage.package).unwrap();
+            usage_dict.set_item("files", &usage.files).unwrap();
+            usage_dict
+        }),
+    );
+    dict.set_item("dependency_usage", usage)?;
+
+    Ok(dict.into())
+}
+
+/// A repository map that can be saved and reloaded without rescanning
+///
+///     >>> repo_map = loom.map_object()
+///     >>> repo_map.save("repo-map.json")
+///     >>> repo_map = RepoMap.load("repo-map.json")
+///     >>> prompt = repo_map.to_markdown()
+#[pyclass(name = "RepoMap")]
+struct PyRepoMap {
+    map: RepoMap,
+}
+
+#[pymethods]
+impl PyRepoMap {
+    /// Parse a map written by ``to_json()``
+    #[staticmethod]
+    fn from_json(json: &str) -> PyResult<Self> {
+        let map = serde_json::from_str(json)
+            .map_err(|e| PyValueError::new_err(format!("Invalid repository map: {}", e)))?;
+        Ok(PyRepoMap { map })
+    }
+
+    /// Read a map written by ``save()``
+    #[staticmethod]
+    fn load(path: PathBuf) -> PyResult<Self> {
+        let json = std::fs::read_to_string(&path)
+            .map_err(|e| PyIOError::new_err(format!("{}: {}", path.display(), e)))?;
+        Self::from_json(&json)
+    }
+
+    /// The map as JSON, indented when ``pretty`` is true
+    #[pyo3(signature = (pretty=false))]
+    fn to_json(&self, pretty: bool) -> PyResult<String> {
+        let json = if pretty {
+            serde_json::to_string_pretty(&self.map)
+        } else {
+            serde_json::to_string(&self.map)
+        };
+        json.map_err(to_py_err)
+    }
+
+    /// The map as the Markdown section ``pack(format="markdown")`` embeds
+    fn to_markdown(&self) -> String {
+        MarkdownFormatter::new().format_map(&self.map)
+    }
+
+    /// Write the map to ``path`` as JSON, replacing the file atomically
+    fn save(&self, path: PathBuf) -> PyResult<()> {
+        let json = self.to_json(true)?;
+        write_atomic(&path, json)
+            .map_err(|e| PyIOError::new_err(format!("{}: {}", path.display(), e)))
+    }
+
+    /// The map as the dict returned by ``Infiniloom.map()``
+    fn to_dict(&self, py: Python) -> PyResult<PyObject> {
+        map_to_dict(py, &self.map)
+    }
+
+    /// Text summary of the repository
+    #[getter]
+    fn summary(&self) -> &str {
+        &self.map.summary
+    }
+
+    /// Tokens the map takes up
+    #[getter]
+    fn token_count(&self) -> u32 {
+        self.map.token_count
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "RepoMap({} symbols, {} files, {} tokens)",
+            self.map.key_symbols.len(),
+            self.map.file_index.len(),
+            self.map.token_count
+        )
+    }
+}
+
+/// Scan and rank a repository for `Infiniloom` as `config` says, stopping
+/// once `cancel` is cancelled
+fn load_repository(
+    path: &Path,
+    config: &infiniloom_engine::config::ScanConfig,
+    cancel: &CancellationToken,
+) -> PyResult<Repository> {
+    let scan_config = ScanConfig {
+        read_contents: true,
+        cancel: cancel.clone(),
+        ..ScanConfig::from_config(config)
+    };
+
+    let mut repo = scan_repository(path, scan_config).map_err(to_py_err)?;
+    config.select_files(&mut repo);
+    rank_files(&mut repo);
+    sort_files_by_importance(&mut repo);
+    Ok(repo)
+}
+
+/// A chunk as a dict mirroring `Chunk`, `ChunkFile` and `ChunkContext`
+fn chunk_to_dict<'py>(py: Python<'py>, chunk: &Chunk) -> PyResult<&'py PyDict> {
+    let dict = PyDict::new(py);
+    dict.set_item("index", chunk.index)?;
+    dict.set_item("total", chunk.total)?;
+    dict.set_item("focus", &chunk.focus)?;
+    dict.set_item("tokens", chunk.tokens)?;
+    dict.set_item("fill_ratio", chunk.fill_ratio)?;
+
+    let files = PyList::empty(py);
+    for file in &chunk.files {
+        let file_dict = PyDict::new(py);
+        file_dict.set_item("id", &file.id)?;
+        file_dict.set_item("path", &file.path)?;
+        file_dict.set_item("content", &file.content)?;
+        file_dict.set_item("tokens", file.tokens)?;
+        file_dict.set_item("truncated", file.truncated)?;
+        files.append(file_dict)?;
+    }
+    dict.set_item("files", files)?;
+
+    let context = PyDict::new(py);
+    context.set_item("previous_summary", &chunk.context.previous_summary)?;
+    context.set_item("current_focus", &chunk.context.current_focus)?;
+    context.set_item("next_preview", &chunk.context.next_preview)?;
+    let references = PyList::empty(py);
+    for reference in &chunk.context.cross_references {
+        let ref_dict = PyDict::new(py);
+        ref_dict.set_item("symbol", &reference.symbol)?;
+        ref_dict.set_item("symbol_id", &reference.symbol_id)?;
+        ref_dict.set_item("chunk_index", reference.chunk_index)?;
+        ref_dict.set_item("file", &reference.file)?;
+        ref_dict.set_item("file_id", &reference.file_id)?;
+        references.append(ref_dict)?;
+    }
+    context.set_item("cross_references", references)?;
+    dict.set_item("context", context)?;
+
+    Ok(dict)
+}
+
 /// Python module definition
 #[pymodule]
 fn _infiniloom(_py: Python, m: &PyModule) -> PyResult<()> {
@@ -497,12 +1131,14 @@ fn _infiniloom(_py: Python, m: &PyModule) -> PyResult<()> {
 
     // Functions
     m.add_function(wrap_pyfunction!(pack, m)?)?;
+    m.add_function(wrap_pyfunction!(pack_archive, m)?)?;
     m.add_function(wrap_pyfunction!(scan, m)?)?;
     m.add_function(wrap_pyfunction!(count_tokens, m)?)?;
     m.add_function(wrap_pyfunction!(scan_security, m)?)?;
 
     // Classes
     m.add_class::<Infiniloom>()?;
+    m.add_class::<PyRepoMap>()?;
 
     // Exceptions
     m.add("InfiniloomError", _py.get_type::<InfiniloomError>())?;
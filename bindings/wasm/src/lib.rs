@@ -2,6 +2,8 @@
 //!
 //! This module exposes Infiniloom functionality to JavaScript environments.
 
+use infiniloom_engine::pipeline::{PackOptions, PackPipeline};
+use infiniloom_engine::{self as engine, TokenModel, Tokenizer};
 use wasm_bindgen::prelude::*;
 use serde::{Deserialize, Serialize};
 use std::panic;
@@ -54,59 +56,43 @@ impl TokenCounts {
 }
 
 /// Count tokens for a specific model
+///
+/// GPT-4 and GPT-4o counts come from their BPE tokenizers unless `exact` is
+/// `false`; other models are always estimated.
 #[wasm_bindgen]
-pub fn count_tokens(text: &str, model: &str) -> Result<u32, JsValue> {
-    // This would call into the Zig WASM module
-    // For now, we'll use a simple heuristic
-    let count = match model {
-        "claude" => estimate_tokens_claude(text),
-        "gpt4o" => estimate_tokens_gpt4o(text),
-        "gpt4" => estimate_tokens_gpt4(text),
-        "gemini" => estimate_tokens_gemini(text),
-        "llama" => estimate_tokens_llama(text),
+pub fn count_tokens(text: &str, model: &str, exact: Option<bool>) -> Result<u32, JsValue> {
+    let model = match model {
+        "claude" => TokenModel::Claude,
+        "gpt4o" | "gpt-4o" => TokenModel::Gpt4o,
+        "gpt4" | "gpt-4" => TokenModel::Gpt4,
+        "gemini" => TokenModel::Gemini,
+        "llama" => TokenModel::Llama,
         _ => return Err(JsValue::from_str(&format!("Unknown model: {}", model))),
     };
 
-    Ok(count)
+    Ok(tokenizer(exact).count(text, model))
 }
 
 /// Count tokens for all models at once
 #[wasm_bindgen]
-pub fn count_tokens_all(text: &str) -> TokenCounts {
+pub fn count_tokens_all(text: &str, exact: Option<bool>) -> TokenCounts {
+    let counts = tokenizer(exact).count_all(text);
     TokenCounts {
-        claude: estimate_tokens_claude(text),
-        gpt4o: estimate_tokens_gpt4o(text),
-        gpt4: estimate_tokens_gpt4(text),
-        gemini: estimate_tokens_gemini(text),
-        llama: estimate_tokens_llama(text),
+        claude: counts.claude,
+        gpt4o: counts.gpt4o,
+        gpt4: counts.gpt4,
+        gemini: counts.gemini,
+        llama: counts.llama,
     }
 }
 
-// Quick estimation algorithms (simplified versions)
-fn estimate_tokens_claude(text: &str) -> u32 {
-    // Claude's tokenizer is similar to GPT-4
-    // Roughly 4 chars per token for English
-    (text.len() as f32 / 4.0).ceil() as u32
-}
-
-fn estimate_tokens_gpt4o(text: &str) -> u32 {
-    // GPT-4o is slightly more efficient
-    (text.len() as f32 / 4.2).ceil() as u32
-}
-
-fn estimate_tokens_gpt4(text: &str) -> u32 {
-    // GPT-4 baseline
-    (text.len() as f32 / 4.0).ceil() as u32
-}
-
-fn estimate_tokens_gemini(text: &str) -> u32 {
-    // Gemini similar to GPT-4
-    (text.len() as f32 / 4.0).ceil() as u32
-}
-
-fn estimate_tokens_llama(text: &str) -> u32 {
-    // Llama tends to use more tokens
-    (text.len() as f32 / 3.5).ceil() as u32
+/// Exact tokenizer unless estimation was asked for
+fn tokenizer(exact: Option<bool>) -> Tokenizer {
+    if exact.unwrap_or(true) {
+        Tokenizer::new()
+    } else {
+        Tokenizer::estimation_only()
+    }
 }
 
 // ============================================================================
@@ -190,7 +176,7 @@ pub fn detect_language(filename: &str) -> Option<String> {
 #[wasm_bindgen]
 pub fn process_file(filename: &str, content: &str) -> FileInfo {
     let language = detect_language(filename);
-    let tokens = count_tokens_all(content);
+    let tokens = count_tokens_all(content, None);
     let size_bytes = content.len();
 
     FileInfo {
@@ -316,6 +302,9 @@ pub enum OutputFormat {
 }
 
 /// Generate context output from files
+///
+/// Runs the same pack pipeline as the CLI over the in-memory files, so
+/// default ignores, ranking and compression match `infiniloom pack`.
 #[wasm_bindgen]
 pub fn generate_context(
     files: JsValue,
@@ -326,86 +315,29 @@ pub fn generate_context(
     let files: Vec<(String, String)> = serde_wasm_bindgen::from_value(files)
         .map_err(|e| JsValue::from_str(&format!("Failed to parse files: {}", e)))?;
 
-    let mut output = String::new();
-
-    match format {
-        OutputFormat::Claude => {
-            output.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
-            output.push_str("<repository>\n");
-            output.push_str("  <context>\n");
-
-            for (filename, content) in &files {
-                let language = detect_language(filename).unwrap_or_else(|| "text".to_string());
-                let compressed = compress(content, compression, Some(language.clone()));
-
-                output.push_str(&format!("    <file path=\"{}\" language=\"{}\">\n", filename, language));
-                output.push_str("      <content>\n");
-
-                for line in compressed.lines() {
-                    output.push_str(&format!("        {}\n", escape_xml(line)));
-                }
-
-                output.push_str("      </content>\n");
-                output.push_str("    </file>\n");
-            }
-
-            output.push_str("  </context>\n");
-            output.push_str("</repository>");
-        }
-
-        OutputFormat::GPT => {
-            output.push_str("# Repository Context\n\n");
-
-            for (filename, content) in &files {
-                let language = detect_language(filename).unwrap_or_else(|| "text".to_string());
-                let compressed = compress(content, compression, Some(language.clone()));
-
-                output.push_str(&format!("## {}\n\n", filename));
-                output.push_str(&format!("```{}\n", language));
-                output.push_str(&compressed);
-                output.push_str("\n```\n\n");
-            }
-        }
-
-        OutputFormat::Gemini => {
-            output.push_str("repository:\n");
-            output.push_str("  files:\n");
-
-            for (filename, content) in &files {
-                let language = detect_language(filename).unwrap_or_else(|| "text".to_string());
-                let compressed = compress(content, compression, Some(language.clone()));
-
-                output.push_str(&format!("    - path: {}\n", filename));
-                output.push_str(&format!("      language: {}\n", language));
-                output.push_str("      content: |\n");
-
-                for line in compressed.lines() {
-                    output.push_str(&format!("        {}\n", line));
-                }
-            }
-        }
-
-        OutputFormat::Plain => {
-            for (filename, content) in &files {
-                let language = detect_language(filename).unwrap_or_else(|| "text".to_string());
-                let compressed = compress(content, compression, Some(language));
-
-                output.push_str(&format!("=== {} ===\n", filename));
-                output.push_str(&compressed);
-                output.push_str("\n\n");
-            }
-        }
-    }
-
-    Ok(output)
-}
-
-fn escape_xml(s: &str) -> String {
-    s.replace('&', "&amp;")
-        .replace('<', "&lt;")
-        .replace('>', "&gt;")
-        .replace('"', "&quot;")
-        .replace('\'', "&apos;")
+    let (format, model) = match format {
+        OutputFormat::Claude => (engine::OutputFormat::Xml, engine::TokenizerModel::Claude),
+        OutputFormat::GPT => (engine::OutputFormat::Markdown, engine::TokenizerModel::Gpt4o),
+        OutputFormat::Gemini => (engine::OutputFormat::Yaml, engine::TokenizerModel::Gemini),
+        OutputFormat::Plain => (engine::OutputFormat::Plain, engine::TokenizerModel::Claude),
+    };
+    let compression = match compression {
+        CompressionLevel::None => engine::CompressionLevel::None,
+        CompressionLevel::Minimal => engine::CompressionLevel::Minimal,
+        CompressionLevel::Balanced => engine::CompressionLevel::Balanced,
+        CompressionLevel::Aggressive => engine::CompressionLevel::Aggressive,
+    };
+    let options = PackOptions::default()
+        .with_format(format)
+        .with_model(model)
+        .with_compression(compression);
+
+    let files = files
+        .into_iter()
+        .map(|(path, content)| (path, content.into_bytes()));
+    PackPipeline::new(options)
+        .pack_files(files, "repository")
+        .map_err(|e| JsValue::from_str(&e.to_string()))
 }
 
 // ============================================================================
@@ -490,7 +422,7 @@ pub fn calculate_stats(files: JsValue) -> Result<RepoStats, JsValue> {
         stats.total_bytes += content.len();
         stats.total_lines += content.lines().count();
 
-        let tokens = count_tokens_all(content);
+        let tokens = count_tokens_all(content, None);
         stats.tokens_claude += tokens.claude;
         stats.tokens_gpt4o += tokens.gpt4o;
         stats.tokens_gpt4 += tokens.gpt4;
@@ -506,11 +438,15 @@ mod tests {
     use super::*;
 
     #[test]
-    fn test_token_estimation() {
+    fn test_token_counting() {
         let text = "Hello, world!";
-        let claude_count = estimate_tokens_claude(text);
+        let claude_count = count_tokens(text, "claude", None).unwrap();
         assert!(claude_count > 0);
         assert!(claude_count < 10);
+
+        // "Hello", ",", " world", "!" in o200k_base
+        assert_eq!(count_tokens(text, "gpt-4o", Some(true)).unwrap(), 4);
+        assert!(count_tokens(text, "gpt-4o", Some(false)).unwrap() > 0);
     }
 
     #[test]
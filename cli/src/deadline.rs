@@ -0,0 +1,133 @@
+//! Time budget tracking for `pack --deadline`
+//!
+//! The pack pipeline is split into phases that each get a share of the total
+//! budget. When a phase runs late, later phases degrade (skip symbols, fall back
+//! to fast ranking, pack fewer files) instead of failing, and every degradation
+//! is recorded so it can be reported alongside the output.
+
+use infiniloom_engine::output::Section;
+use serde_json::json;
+use std::time::{Duration, Instant};
+
+/// Share of the budget by which scanning (including symbol extraction) should finish
+pub(crate) const SCAN_SHARE: f64 = 0.5;
+/// Share of the budget by which ranking should start to afford PageRank
+pub(crate) const RANKING_SHARE: f64 = 0.6;
+/// Share of the budget by which optional enrichment (security, git) should start
+pub(crate) const ENRICH_SHARE: f64 = 0.7;
+/// Share of the budget by which output generation should start
+pub(crate) const OUTPUT_SHARE: f64 = 0.8;
+
+/// Wall-clock budget for a single pack run
+pub(crate) struct Deadline {
+    start: Instant,
+    budget: Duration,
+    degradations: Vec<String>,
+}
+
+impl Deadline {
+    /// Start a deadline that began at `start` and lasts `budget`
+    pub(crate) fn new(start: Instant, budget: Duration) -> Self {
+        Self { start, budget, degradations: Vec::new() }
+    }
+
+    /// Instant by which a phase ending at `share` of the budget should be done
+    pub(crate) fn phase_end(&self, share: f64) -> Instant {
+        self.start + self.budget.mul_f64(share)
+    }
+
+    /// Whether the point at `share` of the budget has already passed
+    pub(crate) fn past(&self, share: f64) -> bool {
+        Instant::now() >= self.phase_end(share)
+    }
+
+    /// Time left until the full budget is spent
+    pub(crate) fn remaining(&self) -> Duration {
+        self.budget.saturating_sub(self.start.elapsed())
+    }
+
+    /// Record a degradation applied to stay within budget
+    pub(crate) fn degrade(&mut self, what: impl Into<String>) {
+        self.degradations.push(what.into());
+    }
+
+    /// Degradations applied so far
+    pub(crate) fn degradations(&self) -> &[String] {
+        &self.degradations
+    }
+
+    /// Number of files to keep so that output generation fits in the remaining time
+    ///
+    /// Assumes output time scales with file count: the output phase is given
+    /// `1 - OUTPUT_SHARE` of the budget, and the file list shrinks in proportion
+    /// to how much of that window is still available.
+    pub(crate) fn files_to_keep(&self, total: usize) -> usize {
+        let window = self.budget.mul_f64(1.0 - OUTPUT_SHARE);
+        if window.is_zero() {
+            return total.min(1);
+        }
+        let ratio = (self.remaining().as_secs_f64() / window.as_secs_f64()).min(1.0);
+        ((total as f64 * ratio).ceil() as usize).clamp(total.min(1), total)
+    }
+
+    /// Report section describing the degradations, if any
+    pub(crate) fn section(&self) -> Option<Section> {
+        if self.degradations.is_empty() {
+            return None;
+        }
+        let budget = humantime::format_duration(self.budget).to_string();
+        let mut note =
+            format!("\n\n<!-- Deadline of {} applied; output is best-effort -->\n", budget);
+        for degradation in &self.degradations {
+            note.push_str(&format!("<!-- - {} -->\n", degradation));
+        }
+        let data = json!({ "budget": budget, "degradations": self.degradations });
+        Some(Section::new("deadline", note, &data))
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::str_to_string)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_phase_end_scales_budget() {
+        let start = Instant::now();
+        let deadline = Deadline::new(start, Duration::from_secs(10));
+        assert_eq!(deadline.phase_end(SCAN_SHARE), start + Duration::from_secs(5));
+        assert!(!deadline.past(SCAN_SHARE));
+    }
+
+    #[test]
+    fn test_expired_budget() {
+        let start = Instant::now() - Duration::from_secs(2);
+        let deadline = Deadline::new(start, Duration::from_secs(1));
+        assert!(deadline.past(OUTPUT_SHARE));
+        assert_eq!(deadline.remaining(), Duration::ZERO);
+        assert_eq!(deadline.files_to_keep(100), 1);
+        assert_eq!(deadline.files_to_keep(0), 0);
+    }
+
+    #[test]
+    fn test_files_to_keep_with_time_left() {
+        let deadline = Deadline::new(Instant::now(), Duration::from_secs(60));
+        assert_eq!(deadline.files_to_keep(40), 40);
+    }
+
+    #[test]
+    fn test_section_lists_degradations() {
+        let mut deadline = Deadline::new(Instant::now(), Duration::from_secs(10));
+        assert!(deadline.section().is_none());
+
+        deadline.degrade("used fast ranking instead of PageRank");
+        let section = deadline.section().unwrap();
+        assert!(section.text.contains("Deadline of 10s"));
+        assert!(section
+            .text
+            .contains("used fast ranking instead of PageRank"));
+        assert_eq!(section.data["budget"], "10s");
+        assert_eq!(section.data["degradations"][0], "used fast ranking instead of PageRank");
+        assert_eq!(deadline.degradations().len(), 1);
+    }
+}
@@ -0,0 +1,50 @@
+//! Ctrl-C handling
+//!
+//! The first Ctrl-C cancels [`token`]. Scanning, map generation and
+//! formatting check it between files, so the command stops with an error
+//! instead of writing partial output. A second Ctrl-C exits immediately.
+
+use infiniloom_engine::CancellationToken;
+use std::sync::OnceLock;
+
+/// Exit code for a run stopped by Ctrl-C (128 + SIGINT)
+pub(crate) const EXIT_INTERRUPTED: i32 = 130;
+
+static TOKEN: OnceLock<CancellationToken> = OnceLock::new();
+
+/// Token cancelled by Ctrl-C
+pub(crate) fn token() -> &'static CancellationToken {
+    TOKEN.get_or_init(CancellationToken::new)
+}
+
+/// Cancel [`token`] on Ctrl-C instead of killing the process
+#[cfg(unix)]
+#[allow(unsafe_code)]
+pub(crate) fn install() {
+    use std::ffi::c_int;
+
+    extern "C" {
+        fn signal(signum: c_int, handler: extern "C" fn(c_int)) -> usize;
+        fn _exit(status: c_int) -> !;
+    }
+    const SIGINT: c_int = 2;
+
+    // Only atomic loads and stores, which are async-signal-safe
+    extern "C" fn on_interrupt(_: c_int) {
+        match TOKEN.get() {
+            Some(token) if !token.is_cancelled() => token.cancel(),
+            // SAFETY: _exit is async-signal-safe
+            _ => unsafe { _exit(EXIT_INTERRUPTED) },
+        }
+    }
+
+    token();
+    // SAFETY: the handler only touches an initialized static and _exit
+    unsafe {
+        signal(SIGINT, on_interrupt);
+    }
+}
+
+/// Ctrl-C keeps its default behavior of ending the process
+#[cfg(not(unix))]
+pub(crate) fn install() {}
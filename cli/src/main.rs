@@ -9,20 +9,66 @@ use anyhow::{Context, Result};
 use clap::{Parser, Subcommand, ValueEnum};
 use colored::Colorize;
 use humansize::{format_size, BINARY};
-use indicatif::{ProgressBar, ProgressStyle};
 use std::path::PathBuf;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
-mod scanner;
+mod deadline;
+mod interrupt;
+mod progress;
+mod serve;
+mod ui;
+
+use deadline::Deadline;
+use progress::{Progress, ProgressFormat};
+use ui::{icon, rule, status, Icon, LogLevel};
 
 use infiniloom_engine::{
-    git::GitRepo,
-    output::{OutputFormat, OutputFormatter},
-    remote::RemoteRepo,
-    repomap::RepoMapGenerator,
-    security::SecurityScanner,
-    types::{CompressionLevel, TokenizerModel},
+    bisect::{BisectPack, DEFAULT_BISECT_STEPS},
+    budget::{BudgetAllocator, BudgetReport},
+    bundle::{Bundle, BundleError, BundlePart},
+    cache::{repo_cache_dir, write_atomic, CacheEntry, CacheGc, CacheLock},
+    cancel::Cancelled,
+    chunking::{Chunk, ChunkStrategy, Chunker},
+    compress::Compressor,
+    config::{Config, SecurityConfig},
+    contracts::{contracts_section, extract_contracts},
+    data::DataCache,
+    default_ignores::{keeps_by_default, HiddenPolicy},
+    delta::{apply_delta, PackManifest},
+    doctor::{self, Doctor},
+    export::{export_symbols, Dataset, ExportFormat},
+    federation::{self, FederationConfig, FederationError},
+    file_context::{FileContext, DEFAULT_FILE_CONTEXT_BUDGET},
+    filter::{FileFilter, FilterStack, GlobFilter, LanguageFilter},
+    focus::{retain_focus, DEFAULT_FOCUS_DEPTH},
+    git::{annotate_changes, annotate_symbol_changes, retain_changes, FileStatus, GitRepo},
+    incremental::IncrementalScanner,
+    index::RepoIndex,
+    output::{
+        lint_output, ExecFormatter, FormatVersion, IndexLimit, Locale, OutputFormat,
+        OutputFormatter, PathRewriter, Section, DEFAULT_EXEC_TIMEOUT,
+    },
+    packages::retain_packages,
+    query::retain_relevant,
+    remote::{CloneCache, RemoteRepo, RetryPolicy},
+    repomap::{RepoMapGenerator, SymbolFilter, ANY_LANGUAGE},
+    report::ReportGenerator,
+    sampling::Sampler,
+    scan::ScanOptions,
+    scan_report::ScanReport,
+    scanner,
+    search::SearchIndex,
+    security::{
+        PiiPolicy, SecretFinding, SecretKind, SecurityBaseline, SecurityScanner, Severity,
+        BASELINE_FILE,
+    },
+    topics::Topic,
+    transform::{LineLimiter, LongLineMode},
+    tuning::{parse_queries, TuningAdvisor},
+    types::{CompressionLevel, RepoFile, TokenizerModel},
+    watch::{DebouncedWatcher, WatchFilter},
 };
+use std::collections::HashSet;
 use std::io::{self, BufRead};
 
 /// Infiniloom - Repository context generator for LLMs
@@ -36,8 +82,19 @@ use std::io::{self, BufRead};
 struct Cli {
     #[command(subcommand)]
     command: Commands,
+
+    /// Print nothing but the command's output (no status lines, warnings or
+    /// progress)
+    #[arg(short, long, global = true)]
+    quiet: bool,
+
+    /// Log level for diagnostics on stderr (overrides RUST_LOG)
+    #[arg(long, value_enum, global = true, value_name = "LEVEL")]
+    log_level: Option<LogLevel>,
 }
 
+// Parsed once per process, so the size of `Pack` doesn't matter
+#[allow(clippy::large_enum_variant)]
 #[derive(Subcommand)]
 enum Commands {
     /// Pack a repository into LLM-friendly format
@@ -46,27 +103,29 @@ enum Commands {
         #[arg(default_value = ".")]
         path: PathBuf,
 
-        /// Output format
-        #[arg(short, long, value_enum, default_value = "xml")]
-        format: Format,
+        /// Output format [default: output.format from the config, else xml]
+        #[arg(short, long, value_enum)]
+        format: Option<Format>,
 
-        /// Target model for optimization
-        #[arg(short, long, value_enum, default_value = "claude")]
-        model: Model,
+        /// Target model for optimization [default: output.model, else claude]
+        #[arg(short, long, value_enum)]
+        model: Option<Model>,
 
-        /// Compression level
-        #[arg(short, long, value_enum, default_value = "balanced")]
-        compression: Compression,
+        /// Compression level [default: output.compression, else balanced]
+        #[arg(short, long, value_enum)]
+        compression: Option<Compression>,
 
-        /// Maximum output tokens (0 = no limit)
-        #[arg(short = 't', long, default_value = "100000")]
-        max_tokens: u32,
+        /// Maximum output tokens, 0 = no limit [default: output.token_budget,
+        /// else 100000]
+        #[arg(short = 't', long)]
+        max_tokens: Option<u32>,
 
-        /// Output file (default: stdout)
+        /// Output file (default: output.output_file, else stdout); repeat to write
+        /// several formats from one scan, each inferred from its extension
         #[arg(short, long)]
-        output: Option<PathBuf>,
+        output: Vec<PathBuf>,
 
-        /// Include hidden files
+        /// Include all hidden files, not just allowlisted ones like .github/
         #[arg(long)]
         hidden: bool,
 
@@ -94,6 +153,11 @@ enum Commands {
         #[arg(long)]
         no_default_ignores: bool,
 
+        /// Keep Infiniloom's own caches, manifests and earlier pack outputs
+        /// (skipped by default)
+        #[arg(long)]
+        include_artifacts: bool,
+
         /// Verbose output
         #[arg(short, long)]
         verbose: bool,
@@ -130,9 +194,20 @@ enum Commands {
         #[arg(long)]
         remove_comments: bool,
 
-        /// Limit number of files in summary (0 = all)
-        #[arg(long, default_value = "0")]
-        top_files: usize,
+        /// Limit number of files in summary, 0 = all [default:
+        /// output.top_files_length, else 0]
+        #[arg(long)]
+        top_files: Option<usize>,
+
+        /// List only the N most important files in the file index and project
+        /// structure, noting how many were left out (0 = all)
+        #[arg(long, value_name = "N", default_value_t = 0)]
+        index_max_files: usize,
+
+        /// Roll up a directory into one file index entry after its first N
+        /// files (0 = never)
+        #[arg(long, value_name = "N", default_value_t = 0)]
+        index_rollup: usize,
 
         /// Include git commit history in output
         #[arg(long)]
@@ -142,6 +217,10 @@ enum Commands {
         #[arg(long, default_value = "50")]
         logs_count: usize,
 
+        /// Only include commits newer than this, e.g. `90d`, `12w`, `6m` or a date
+        #[arg(long, value_name = "AGE")]
+        logs_since: Option<String>,
+
         /// Include git diffs in output
         #[arg(long)]
         include_diffs: bool,
@@ -158,6 +237,14 @@ enum Commands {
         #[arg(long)]
         truncate_base64: bool,
 
+        /// Maximum line length in characters before --long-lines applies (0 = no limit)
+        #[arg(long, default_value = "10000")]
+        max_line_length: usize,
+
+        /// How to handle lines over --max-line-length
+        #[arg(long, value_enum, default_value = "truncate")]
+        long_lines: LongLines,
+
         /// Include only files matching glob pattern (can be repeated)
         #[arg(long = "include", short = 'i')]
         include_patterns: Vec<String>,
@@ -166,25 +253,173 @@ enum Commands {
         #[arg(long = "exclude", short = 'e')]
         exclude_patterns: Vec<String>,
 
+        /// Keep only files in these languages, by detected language rather
+        /// than file name, e.g. `rust,python` (can be repeated)
+        #[arg(long = "only-lang", value_name = "LANG", value_delimiter = ',')]
+        only_lang: Vec<String>,
+
+        /// Leave out files in these languages, e.g. `markdown,json` (can be repeated)
+        #[arg(long = "exclude-lang", value_name = "LANG", value_delimiter = ',')]
+        exclude_lang: Vec<String>,
+
         /// Scan for security issues (secrets, API keys)
         #[arg(long)]
         security_check: bool,
 
+        /// Extra security rules (YAML with `rules` and `disabled_rules`, like the
+        /// `security` section of .infiniloom.yaml); implies --security-check
+        #[arg(long, value_name = "FILE")]
+        security_rules: Option<PathBuf>,
+
+        /// Accepted findings to leave out of the security report (default:
+        /// .infiniloom-security-baseline.json in the repository, if present);
+        /// implies --security-check
+        #[arg(long, value_name = "FILE")]
+        security_baseline: Option<PathBuf>,
+
+        /// Accept every current finding by writing it to the security baseline;
+        /// implies --security-check
+        #[arg(long)]
+        update_baseline: bool,
+
         /// Branch to checkout for remote repositories
         #[arg(long)]
         remote_branch: Option<String>,
 
+        /// Subdirectory of a remote repository to check out and pack (sparse checkout)
+        #[arg(long, value_name = "PATH")]
+        remote_path: Option<String>,
+
+        /// Commits of history to clone for remote repositories (0 = full history)
+        #[arg(long, default_value = "1")]
+        depth: u32,
+
+        /// Times to retry a remote clone after a network failure, with exponential backoff
+        #[arg(long, default_value = "3")]
+        retries: u32,
+
+        /// Pack a remote repository from the clone cache without any network access
+        #[arg(long)]
+        offline: bool,
+
+        /// Oldest cached clone --offline accepts (e.g. "12h", "7days")
+        #[arg(long, value_parser = humantime::parse_duration, default_value = "24h")]
+        clone_max_age: Duration,
+
         /// Disable line numbers in output
         #[arg(long)]
         no_line_numbers: bool,
 
-        /// Path to config file (default: .infiniloom.yaml)
+        /// Write XML, JSON and YAML output without checking that it parses
+        #[arg(long)]
+        no_lint: bool,
+
+        /// Write an older output layout, for parsers not yet updated (the
+        /// previous version is deprecated but still supported)
+        #[arg(long, value_name = "N")]
+        format_version: Option<u32>,
+
+        /// Config file to use instead of the repository's .infiniloom.yaml, .toml
+        /// or .json; flags override its settings
         #[arg(long)]
         config: Option<PathBuf>,
 
         /// Watch for file changes and regenerate output
         #[arg(long)]
         watch: bool,
+
+        /// Time budget for packing (e.g. "10s", "1500ms"); degrades gracefully to meet it
+        #[arg(long, value_parser = humantime::parse_duration)]
+        deadline: Option<Duration>,
+
+        /// Strip a leading path prefix from all paths in the output (can be repeated)
+        #[arg(long = "path-prefix-strip")]
+        path_prefix_strip: Vec<String>,
+
+        /// Remap a path prefix in the output, as OLD=NEW (can be repeated)
+        #[arg(long = "path-map", value_parser = parse_path_map)]
+        path_map: Vec<(String, String)>,
+
+        /// Only pack files changed since the pack that wrote this manifest; list
+        /// the rest by hash
+        #[arg(long, value_name = "MANIFEST")]
+        delta_from: Option<PathBuf>,
+
+        /// Write a manifest of the packed files for a later --delta-from
+        #[arg(long, value_name = "MANIFEST")]
+        write_manifest: Option<PathBuf>,
+
+        /// Progress reporting: `bar` for a spinner, `json` for newline-delimited
+        /// JSON events on stderr (default: spinner with --verbose)
+        #[arg(long, value_enum)]
+        progress: Option<ProgressFormat>,
+
+        /// Reuse token counts and symbols for unchanged files from the
+        /// repository's cache (under $INFINILOOM_CACHE_DIR or ~/.cache/infiniloom),
+        /// and update it
+        #[arg(long)]
+        incremental: bool,
+
+        /// Load the repository saved by `infiniloom index` instead of scanning
+        #[arg(long)]
+        use_index: bool,
+
+        /// Annotate each file in the output with the main factors behind its rank
+        #[arg(long)]
+        explain_ranking: bool,
+
+        /// For repositories far beyond --max-tokens: pack the top-ranked files in
+        /// full, the next tier as signatures and representative samples of every
+        /// module, followed by a sampling report
+        #[arg(long)]
+        sample: bool,
+
+        /// With --sample, keep an outline (symbols and line ranges) of files that
+        /// would otherwise be left out, while the budget allows
+        #[arg(long, requires = "sample")]
+        outlines: bool,
+
+        /// Replace Protobuf, OpenAPI/Swagger and JSON Schema files with compact
+        /// interface summaries in a contracts section
+        #[arg(long)]
+        contracts: bool,
+
+        /// Only pack files changed since this branch or commit, as a pull request
+        /// against it would show them, plus the files they import or are imported by
+        #[arg(long, value_name = "REF")]
+        diff_base: Option<String>,
+
+        /// Pack the repository as it was at this tag, branch or commit, read from
+        /// git objects without touching the working tree
+        #[arg(
+            long = "ref",
+            value_name = "REF",
+            conflicts_with_all = ["watch", "incremental", "use_index", "diff_base", "include_logs", "include_diffs"]
+        )]
+        git_ref: Option<String>,
+
+        /// Only pack files matching this path, glob or symbol name, plus the
+        /// files they import or are imported by (can be repeated)
+        #[arg(long, value_name = "PATH|GLOB|SYMBOL")]
+        focus: Vec<String>,
+
+        /// Import hops to follow from the --focus files
+        #[arg(long, default_value_t = DEFAULT_FOCUS_DEPTH)]
+        focus_depth: usize,
+
+        /// Only pack this Cargo, npm, yarn, pnpm or Go workspace member, by name
+        /// or directory, plus the members it depends on (can be repeated)
+        #[arg(long, value_name = "NAME")]
+        package: Vec<String>,
+
+        /// Only pack the files relevant to this question, most relevant first
+        /// (e.g. "how does authentication work")
+        #[arg(long, value_name = "TEXT")]
+        query: Option<String>,
+
+        /// Language of the titles, guidance and notes around the packed content
+        #[arg(long, value_enum, default_value = "en")]
+        lang: Lang,
     },
 
     /// Scan a repository and show statistics
@@ -197,7 +432,7 @@ enum Commands {
         #[arg(short, long, value_enum, default_value = "claude")]
         model: Model,
 
-        /// Include hidden files
+        /// Include all hidden files, not just allowlisted ones like .github/
         #[arg(long)]
         hidden: bool,
 
@@ -208,6 +443,25 @@ enum Commands {
         /// Output as JSON
         #[arg(long)]
         json: bool,
+
+        /// Output the complete scanned repository as JSON: every file with its
+        /// symbols and importance, the directory structure and external
+        /// dependencies. File contents are left out unless --with-content is given
+        #[arg(long, conflicts_with = "json")]
+        json_full: bool,
+
+        /// With --json-full, include file contents
+        #[arg(long, requires = "json_full")]
+        with_content: bool,
+
+        /// Keep only files in these languages, by detected language rather
+        /// than file name, e.g. `rust,python` (can be repeated)
+        #[arg(long = "only-lang", value_name = "LANG", value_delimiter = ',')]
+        only_lang: Vec<String>,
+
+        /// Leave out files in these languages, e.g. `markdown,json` (can be repeated)
+        #[arg(long = "exclude-lang", value_name = "LANG", value_delimiter = ',')]
+        exclude_lang: Vec<String>,
     },
 
     /// Generate a repository map (symbol index)
@@ -223,975 +477,3955 @@ enum Commands {
         /// Output file
         #[arg(short, long)]
         output: Option<PathBuf>,
+
+        /// Leave symbols matching this name out of the map, e.g. `test_*` or
+        /// `rust:new` for one language (can be repeated)
+        #[arg(long = "stop-symbol", value_name = "[LANG:]PATTERN")]
+        stop_symbols: Vec<String>,
+
+        /// Leave out symbols whose signature is shorter than this many characters
+        #[arg(long)]
+        min_signature_length: Option<usize>,
+
+        /// Don't apply the built-in per-language symbol stop-lists
+        #[arg(long)]
+        no_default_stop_lists: bool,
+
+        /// Target model for token counting
+        #[arg(short, long, value_enum, default_value = "claude")]
+        model: Model,
+
+        /// Maximum number of key symbols
+        #[arg(long, default_value = "50")]
+        max_symbols: usize,
+
+        /// Include only files matching glob pattern (can be repeated)
+        #[arg(long = "include", short = 'i')]
+        include_patterns: Vec<String>,
+
+        /// Exclude files matching glob pattern (can be repeated)
+        #[arg(long = "exclude", short = 'e')]
+        exclude_patterns: Vec<String>,
+
+        /// Keep only files in these languages, by detected language rather
+        /// than file name, e.g. `rust,python` (can be repeated)
+        #[arg(long = "only-lang", value_name = "LANG", value_delimiter = ',')]
+        only_lang: Vec<String>,
+
+        /// Leave out files in these languages, e.g. `markdown,json` (can be repeated)
+        #[arg(long = "exclude-lang", value_name = "LANG", value_delimiter = ',')]
+        exclude_lang: Vec<String>,
+
+        /// Output format
+        #[arg(short, long, value_enum, default_value = "text")]
+        format: MapFormat,
+
+        /// Rank symbols in this file or directory, and those it imports, first
+        /// (can be repeated)
+        #[arg(long)]
+        focus: Vec<String>,
+
+        /// Load the repository saved by `infiniloom index` instead of scanning
+        #[arg(long)]
+        use_index: bool,
     },
 
-    /// Show version and configuration info
-    Info,
+    /// List the most important files with their scores and token counts
+    Top {
+        /// Path to repository (default: current directory)
+        #[arg(default_value = ".")]
+        path: PathBuf,
 
-    /// Initialize a new configuration file
-    Init {
-        /// Configuration format
-        #[arg(short, long, value_enum, default_value = "yaml")]
-        format: ConfigFormat,
+        /// Number of files to list
+        #[arg(short, long, default_value = "20")]
+        n: usize,
 
-        /// Output path (default: .infiniloom.yaml in current directory)
+        /// What to rank files by
+        #[arg(long, value_enum, default_value = "rank")]
+        by: TopBy,
+
+        /// Target model for token counting
+        #[arg(short, long, value_enum, default_value = "claude")]
+        model: Model,
+
+        /// Include all hidden files, not just allowlisted ones like .github/
+        #[arg(long)]
+        hidden: bool,
+
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Pack one file with the signatures it imports, the types it uses and
+    /// its callers, for questions about that file
+    FileContext {
+        /// File to build the context for, relative to the repository
+        file: PathBuf,
+
+        /// Path to repository (default: current directory)
+        #[arg(default_value = ".")]
+        path: PathBuf,
+
+        /// Token budget for the file and its surroundings
+        #[arg(short, long, default_value_t = DEFAULT_FILE_CONTEXT_BUDGET)]
+        budget: u32,
+
+        /// Target model for token counting
+        #[arg(short, long, value_enum, default_value = "claude")]
+        model: Model,
+
+        /// Output file (default: stdout)
         #[arg(short, long)]
         output: Option<PathBuf>,
 
-        /// Overwrite existing config file
+        /// Include all hidden files, not just allowlisted ones like .github/
         #[arg(long)]
-        force: bool,
+        hidden: bool,
+
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
     },
-}
 
-#[derive(ValueEnum, Clone, Copy)]
-enum ConfigFormat {
-    /// YAML format
-    Yaml,
-    /// TOML format
-    Toml,
-    /// JSON format
-    Json,
-}
+    /// Search file contents and symbols, best matching files first
+    Search {
+        /// Words to search for
+        query: String,
 
-#[derive(ValueEnum, Clone, Copy)]
-enum Format {
-    /// XML format (Claude-optimized)
-    Xml,
-    /// Markdown format (GPT-optimized)
-    Markdown,
-    /// JSON format (generic)
-    Json,
-    /// YAML format (Gemini-optimized)
-    Yaml,
-    /// TOON format (most token-efficient, 40% smaller)
-    Toon,
-    /// Plain text format (simple, no formatting)
-    Plain,
-}
+        /// Path to repository (default: current directory)
+        #[arg(default_value = ".")]
+        path: PathBuf,
 
-impl From<Format> for OutputFormat {
-    fn from(f: Format) -> Self {
-        match f {
-            Format::Xml => OutputFormat::Xml,
-            Format::Markdown => OutputFormat::Markdown,
-            Format::Json => OutputFormat::Json,
-            Format::Yaml => OutputFormat::Yaml,
-            Format::Toon => OutputFormat::Toon,
-            Format::Plain => OutputFormat::Plain,
-        }
-    }
-}
+        /// Number of files to list
+        #[arg(short, long, default_value = "10")]
+        n: usize,
 
-#[derive(ValueEnum, Clone, Copy)]
-enum Model {
-    Claude,
-    Gpt4o,
-    Gpt4,
-    Gemini,
-    Llama,
-}
+        /// Include all hidden files, not just allowlisted ones like .github/
+        #[arg(long)]
+        hidden: bool,
 
-impl From<Model> for TokenizerModel {
-    fn from(m: Model) -> Self {
-        match m {
-            Model::Claude => TokenizerModel::Claude,
-            Model::Gpt4o => TokenizerModel::Gpt4o,
-            Model::Gpt4 => TokenizerModel::Gpt4,
-            Model::Gemini => TokenizerModel::Gemini,
-            Model::Llama => TokenizerModel::Llama,
-        }
-    }
-}
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
 
-#[derive(ValueEnum, Clone, Copy)]
-enum Compression {
-    /// No compression
-    None,
-    /// Minimal: remove empty lines
-    Minimal,
-    /// Balanced: remove comments
-    Balanced,
-    /// Aggressive: signatures only
-    Aggressive,
-    /// Extreme: key symbols only
-    Extreme,
-}
+        /// Pack the matching files, best match first, instead of listing them
+        #[arg(long)]
+        pack_results: bool,
 
-impl From<Compression> for CompressionLevel {
-    fn from(c: Compression) -> Self {
-        match c {
-            Compression::None => CompressionLevel::None,
-            Compression::Minimal => CompressionLevel::Minimal,
-            Compression::Balanced => CompressionLevel::Balanced,
-            Compression::Aggressive => CompressionLevel::Aggressive,
-            Compression::Extreme => CompressionLevel::Extreme,
-        }
-    }
-}
+        /// Output format for --pack-results
+        #[arg(short, long, value_enum, default_value = "xml")]
+        format: Format,
 
-fn main() -> Result<()> {
-    // Initialize logging
-    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("warn")).init();
+        /// Output file for --pack-results (default: stdout)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
 
-    let cli = Cli::parse();
+        /// Load the repository saved by `infiniloom index` instead of scanning
+        #[arg(long)]
+        use_index: bool,
+    },
 
-    match cli.command {
-        Commands::Pack {
-            path,
-            format,
-            model,
-            compression,
-            max_tokens,
-            output,
-            hidden,
-            no_gitignore,
-            symbols,
-            full,
-            include_tests,
-            include_docs,
-            no_default_ignores,
-            verbose,
-            header_text,
-            instruction_file,
-            copy_to_clipboard,
-            token_tree,
-            no_directory_structure,
-            no_file_summary,
-            remove_empty_lines,
-            remove_comments,
-            top_files,
-            include_logs,
-            logs_count,
-            include_diffs,
-            sort_by_changes,
-            stdin,
-            truncate_base64,
-            include_patterns,
-            exclude_patterns,
-            security_check,
-            remote_branch,
-            no_line_numbers,
-            config,
-            watch,
-        } => cmd_pack(
+    /// Scan a repository once and save it, with its symbol and dependency
+    /// graphs, to the repository's cache for `--use-index`
+    Index {
+        /// Path to repository (default: current directory)
+        #[arg(default_value = ".")]
+        path: PathBuf,
+
+        /// Include all hidden files, not just allowlisted ones like .github/
+        #[arg(long)]
+        hidden: bool,
+    },
+
+    /// Serve packs, maps, file lists, token counts and security scans over
+    /// HTTP from warm scans that follow file changes
+    Serve {
+        /// Repositories to serve (default: current directory)
+        #[arg(default_value = ".")]
+        paths: Vec<PathBuf>,
+
+        /// Address to listen on
+        #[arg(long, value_name = "ADDR", default_value = "127.0.0.1:7700")]
+        http: std::net::SocketAddr,
+
+        /// Include all hidden files, not just allowlisted ones like .github/
+        #[arg(long)]
+        hidden: bool,
+
+        /// Keep the startup scans instead of re-scanning on file changes
+        #[arg(long)]
+        no_watch: bool,
+    },
+
+    /// Split a repository into context-window-sized chunks, written as
+    /// chunk_001.xml, chunk_002.xml, ...
+    Chunk {
+        /// Path to repository (default: current directory)
+        #[arg(default_value = ".")]
+        path: PathBuf,
+
+        /// Maximum tokens per chunk
+        #[arg(short = 't', long, default_value = "8000")]
+        max_tokens: u32,
+
+        /// How files are grouped into chunks
+        #[arg(short, long, value_enum, default_value = "semantic")]
+        strategy: ChunkBy,
+
+        /// Tokens of a neighbouring file repeated at the start of a chunk
+        #[arg(long, default_value = "200")]
+        overlap: u32,
+
+        /// Target model for token counting
+        #[arg(short, long, value_enum, default_value = "claude")]
+        model: Model,
+
+        /// Also chunk for this model, as MODEL or MODEL=TOKENS (can be repeated);
+        /// each model's chunks go to a subdirectory named after it
+        #[arg(long = "for-model", value_name = "MODEL[=TOKENS]", value_parser = parse_model_budget)]
+        for_models: Vec<(Model, Option<u32>)>,
+
+        /// Directory to write the chunks to
+        #[arg(short, long, default_value = "chunks")]
+        output: PathBuf,
+
+        /// Include all hidden files, not just allowlisted ones like .github/
+        #[arg(long)]
+        hidden: bool,
+    },
+
+    /// Check the repository for conditions that degrade pack quality
+    Doctor {
+        /// Path to repository (default: current directory)
+        #[arg(default_value = ".")]
+        path: PathBuf,
+
+        /// Include all hidden files, not just allowlisted ones like .github/
+        #[arg(long)]
+        hidden: bool,
+
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Suggest include patterns, focus files and a token budget from a log
+    /// of past queries
+    Tune {
+        /// Path to repository (default: current directory)
+        #[arg(default_value = ".")]
+        path: PathBuf,
+
+        /// Plain text file with one past query or task per line
+        #[arg(long, value_name = "FILE")]
+        queries: PathBuf,
+
+        /// Fit the suggested budget split into this many tokens
+        #[arg(long)]
+        max_tokens: Option<u32>,
+
+        /// Target model for token counting
+        #[arg(short, long, value_enum, default_value = "claude")]
+        model: Model,
+
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Pack a subtree at a good revision, a bad one and commits between
+    /// them, read from git objects, to help localize a regression
+    BisectPack {
+        /// Path to repository (default: current directory)
+        #[arg(default_value = ".")]
+        path: PathBuf,
+
+        /// Revision known to behave correctly
+        #[arg(long)]
+        good: String,
+
+        /// Revision known to show the regression
+        #[arg(long)]
+        bad: String,
+
+        /// Limit the snapshots to this file or directory (can be repeated)
+        #[arg(long = "path", value_name = "PATH")]
+        paths: Vec<String>,
+
+        /// Number of intermediate commits to snapshot
+        #[arg(long, default_value_t = DEFAULT_BISECT_STEPS)]
+        steps: usize,
+
+        /// Output format of each snapshot
+        #[arg(short, long, value_enum, default_value = "xml")]
+        format: Format,
+
+        /// Target model for token counting
+        #[arg(short, long, value_enum, default_value = "claude")]
+        model: Model,
+
+        /// Directory to write the snapshots and index.md to
+        #[arg(short, long, default_value = "bisect-pack")]
+        output: PathBuf,
+
+        /// Pack every file of every snapshot, not just those changed since
+        /// the previous one
+        #[arg(long)]
+        full: bool,
+
+        /// Include all hidden files, not just allowlisted ones like .github/
+        #[arg(long)]
+        hidden: bool,
+    },
+
+    /// Scan for secrets, listing findings by severity; with --fail-on, exits
+    /// with status 1 when a finding is that severe, for CI gates
+    Security {
+        /// Path to repository (default: current directory)
+        #[arg(default_value = ".")]
+        path: PathBuf,
+
+        /// Exit with status 1 if any finding is at least this severe
+        #[arg(long, value_enum, value_name = "SEVERITY")]
+        fail_on: Option<FailOn>,
+
+        /// Extra security rules (YAML with `rules` and `disabled_rules`, like the
+        /// `security` section of .infiniloom.yaml)
+        #[arg(long, value_name = "FILE")]
+        rules: Option<PathBuf>,
+
+        /// Accepted findings to leave out (default:
+        /// .infiniloom-security-baseline.json in the repository, if present)
+        #[arg(long, value_name = "FILE")]
+        baseline: Option<PathBuf>,
+
+        /// Accept every current finding by writing it to the baseline
+        #[arg(long)]
+        update_baseline: bool,
+
+        /// Also scan files ignored by .gitignore, such as local .env files
+        #[arg(long)]
+        no_gitignore: bool,
+
+        /// Skip personal data (emails, phone numbers, national IDs), scanning
+        /// for credentials only
+        #[arg(long)]
+        no_pii: bool,
+
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Report packed files that changed since a manifest was written
+    Freshness {
+        /// Manifest written by pack --write-manifest
+        manifest: PathBuf,
+
+        /// Path to repository (default: current directory)
+        #[arg(default_value = ".")]
+        path: PathBuf,
+
+        /// Compare against a git ref instead of the working tree
+        #[arg(long, value_name = "REF")]
+        against: Option<String>,
+
+        /// Target model for the refresh estimate
+        #[arg(short, long, value_enum, default_value = "claude")]
+        model: Model,
+
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Bundle a pack with its manifest, instructions and session transcripts
+    /// into one archive, so the exact context sent to a model can be shared
+    Bundle {
+        #[command(subcommand)]
+        command: BundleCommand,
+    },
+
+    /// Generate review context for a commit range: its diffs, the touched
+    /// files and a map of the affected modules
+    Pr {
+        /// Commit range (`BASE..HEAD`, `BASE...HEAD`) or the revision to start from
+        range: String,
+
+        /// End of the range when RANGE is a single revision (default: HEAD)
+        to: Option<String>,
+
+        /// Path to repository (default: current directory)
+        #[arg(long, default_value = ".")]
+        path: PathBuf,
+
+        /// Output format
+        #[arg(short, long, value_enum, default_value = "xml")]
+        format: Format,
+
+        /// Target model for token counting
+        #[arg(short, long, value_enum, default_value = "claude")]
+        model: Model,
+
+        /// Token budget for the map of the affected modules
+        #[arg(long, default_value = "2000")]
+        map_budget: u32,
+
+        /// Output file (default: stdout)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+
+        /// Language of the titles and notes around the content
+        #[arg(long, value_enum, default_value = "en")]
+        lang: Lang,
+    },
+
+    /// Pack several repositories described by a federation config
+    Federate {
+        #[command(subcommand)]
+        command: FederateCommand,
+    },
+
+    /// Maintain the scan cache, downloaded data and federation clones
+    Cache {
+        #[command(subcommand)]
+        command: CacheCommand,
+    },
+
+    /// Generate a human-readable architecture report for onboarding
+    Analyze {
+        /// Path to repository (default: current directory)
+        #[arg(default_value = ".")]
+        path: PathBuf,
+
+        /// Write the Markdown report to this file (default: stdout)
+        #[arg(short, long)]
+        report: Option<PathBuf>,
+
+        /// Include all hidden files, not just allowlisted ones like .github/
+        #[arg(long)]
+        hidden: bool,
+
+        /// Maximum number of key symbols to describe
+        #[arg(long, default_value = "25")]
+        max_symbols: usize,
+    },
+
+    /// Export the symbol index for editors and code-intelligence tools
+    Export {
+        /// Path to repository (default: current directory)
+        #[arg(default_value = ".")]
+        path: PathBuf,
+
+        /// Index format
+        #[arg(short, long, value_enum, default_value = "ctags")]
+        format: IndexFormat,
+
+        /// Table to write for columnar formats (parquet)
+        #[arg(long, value_enum, default_value = "symbols")]
+        what: ExportDataset,
+
+        /// Output file (default: stdout)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+
+        /// Include all hidden files, not just allowlisted ones like .github/
+        #[arg(long)]
+        hidden: bool,
+    },
+
+    /// Explain why a file is included in or excluded from a pack, and how it ranks
+    Explain {
+        /// File to explain, relative to the repository root
+        file: String,
+
+        /// Path to repository (default: current directory)
+        #[arg(long, default_value = ".")]
+        path: PathBuf,
+
+        /// Target model for token counts
+        #[arg(short, long, value_enum, default_value = "claude")]
+        model: Model,
+
+        /// Explain full analysis mode ranking instead of the fast heuristic
+        #[arg(long)]
+        full: bool,
+
+        /// Include all hidden files, not just allowlisted ones like .github/
+        #[arg(long)]
+        hidden: bool,
+
+        /// Don't respect .gitignore
+        #[arg(long)]
+        no_gitignore: bool,
+
+        /// Include test files (excluded by default)
+        #[arg(long)]
+        include_tests: bool,
+
+        /// Include documentation files (excluded by default)
+        #[arg(long)]
+        include_docs: bool,
+
+        /// Disable default ignore patterns (node_modules, dist, etc.)
+        #[arg(long)]
+        no_default_ignores: bool,
+
+        /// Include only files matching glob pattern (can be repeated)
+        #[arg(long = "include", short = 'i')]
+        include_patterns: Vec<String>,
+
+        /// Exclude files matching glob pattern (can be repeated)
+        #[arg(long = "exclude", short = 'e')]
+        exclude_patterns: Vec<String>,
+
+        /// Config file to use instead of the repository's .infiniloom.yaml, .toml
+        /// or .json; flags override its settings
+        #[arg(long)]
+        config: Option<PathBuf>,
+    },
+
+    /// Measure repository map quality on built-in labeled fixtures (dev tool)
+    #[cfg(feature = "eval")]
+    Eval {
+        /// Number of runs per fixture (for churn)
+        #[arg(long, default_value = "3")]
+        runs: usize,
+
+        /// Maximum key symbols requested from the map generator
+        #[arg(long, default_value = "10")]
+        max_symbols: usize,
+    },
+
+    /// Show version and configuration info
+    Info,
+
+    /// Initialize a new configuration file
+    Init {
+        /// Configuration format
+        #[arg(short, long, value_enum, default_value = "yaml")]
+        format: ConfigFormat,
+
+        /// Output path (default: .infiniloom.yaml in current directory)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+
+        /// Overwrite existing config file
+        #[arg(long)]
+        force: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum FederateCommand {
+    /// Fetch or update each repository and pack them together
+    Pack {
+        /// Only repositories tagged with this service
+        #[arg(long)]
+        service: Option<String>,
+
+        /// Federation config
+        #[arg(short, long, default_value = "federation.yaml")]
+        config: PathBuf,
+
+        /// Output format
+        #[arg(short, long, value_enum, default_value = "xml")]
+        format: Format,
+
+        /// Target model for token budgets
+        #[arg(short, long, value_enum, default_value = "claude")]
+        model: Model,
+
+        /// Write one pack per repository plus an index.md into --output
+        #[arg(long)]
+        split: bool,
+
+        /// Output file, or directory with --split (default: stdout)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+
+        /// Use cached clones as they are instead of fetching
+        #[arg(long)]
+        no_fetch: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum CacheCommand {
+    /// Remove corrupt entries and evict the least recently used ones
+    Gc {
+        /// Repository whose caches to collect (default: current directory)
+        #[arg(default_value = ".")]
+        path: PathBuf,
+
+        /// Evict until the caches fit in this size (e.g. 500MB, 2GB)
+        #[arg(long, value_parser = parse_byte_size)]
+        max_size: Option<u64>,
+
+        /// Show what would be removed without removing it
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum BundleCommand {
+    /// Write a bundle from a pack and the rest of a session's inputs
+    Create {
+        /// Pack output sent to the model (repeat for several)
+        #[arg(long, required = true, value_name = "FILE")]
+        pack: Vec<PathBuf>,
+
+        /// Manifest written by pack --write-manifest
+        #[arg(long, value_name = "FILE")]
+        manifest: Option<PathBuf>,
+
+        /// Instruction file sent with the pack
+        #[arg(long, value_name = "FILE")]
+        instructions: Option<PathBuf>,
+
+        /// Prompt or response transcript, in the order they were exchanged
+        /// (repeat for several)
+        #[arg(long, value_name = "FILE")]
+        transcript: Vec<PathBuf>,
+
+        /// Bundle file to write
+        #[arg(short, long, default_value = "infiniloom-bundle.tar")]
+        output: PathBuf,
+    },
+
+    /// Check a bundle and write its files back out
+    Extract {
+        /// Bundle written by `bundle create`
+        bundle: PathBuf,
+
+        /// Directory to extract into (default: the bundle's file name without
+        /// its extension)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+
+        /// Overwrite files that already exist
+        #[arg(long)]
+        force: bool,
+    },
+}
+
+#[derive(ValueEnum, Clone, Copy)]
+enum ConfigFormat {
+    /// YAML format
+    Yaml,
+    /// TOML format
+    Toml,
+    /// JSON format
+    Json,
+}
+
+#[derive(ValueEnum, Clone, Copy)]
+enum Format {
+    /// XML format (Claude-optimized)
+    Xml,
+    /// Markdown format (GPT-optimized)
+    Markdown,
+    /// JSON format (generic)
+    Json,
+    /// YAML format (Gemini-optimized)
+    Yaml,
+    /// TOON format (most token-efficient, 40% smaller)
+    Toon,
+    /// Plain text format (simple, no formatting)
+    Plain,
+    /// Directory tree, exported signatures and dependencies only
+    Signatures,
+}
+
+impl From<Format> for OutputFormat {
+    fn from(f: Format) -> Self {
+        match f {
+            Format::Xml => OutputFormat::Xml,
+            Format::Markdown => OutputFormat::Markdown,
+            Format::Json => OutputFormat::Json,
+            Format::Yaml => OutputFormat::Yaml,
+            Format::Toon => OutputFormat::Toon,
+            Format::Plain => OutputFormat::Plain,
+            Format::Signatures => OutputFormat::Signatures,
+        }
+    }
+}
+
+#[derive(ValueEnum, Clone, Copy)]
+enum IndexFormat {
+    /// universal-ctags JSON lines
+    Ctags,
+    /// Language Server Index Format (LSIF) dump
+    Lsif,
+    /// Apache Parquet dataset (see --what; requires --output)
+    Parquet,
+}
+
+impl IndexFormat {
+    /// The engine's text export format, or `None` for columnar formats
+    fn text_format(self) -> Option<ExportFormat> {
+        match self {
+            IndexFormat::Ctags => Some(ExportFormat::Ctags),
+            IndexFormat::Lsif => Some(ExportFormat::Lsif),
+            IndexFormat::Parquet => None,
+        }
+    }
+}
+
+#[derive(ValueEnum, Clone, Copy)]
+enum ExportDataset {
+    /// One row per symbol
+    Symbols,
+    /// One row per import edge between files
+    Edges,
+    /// One row per file
+    Files,
+}
+
+impl From<ExportDataset> for Dataset {
+    fn from(d: ExportDataset) -> Self {
+        match d {
+            ExportDataset::Symbols => Dataset::Symbols,
+            ExportDataset::Edges => Dataset::Edges,
+            ExportDataset::Files => Dataset::Files,
+        }
+    }
+}
+
+#[derive(ValueEnum, Clone, Copy)]
+enum Model {
+    Claude,
+    Gpt4o,
+    Gpt4,
+    Gemini,
+    Llama,
+}
+
+impl From<Model> for TokenizerModel {
+    fn from(m: Model) -> Self {
+        match m {
+            Model::Claude => TokenizerModel::Claude,
+            Model::Gpt4o => TokenizerModel::Gpt4o,
+            Model::Gpt4 => TokenizerModel::Gpt4,
+            Model::Gemini => TokenizerModel::Gemini,
+            Model::Llama => TokenizerModel::Llama,
+        }
+    }
+}
+
+#[derive(ValueEnum, Clone, Copy)]
+enum Compression {
+    /// No compression
+    None,
+    /// Minimal: remove empty lines and trailing whitespace
+    Minimal,
+    /// Balanced: also remove comments
+    Balanced,
+    /// Aggressive: also replace function bodies with `...` and remove docstrings
+    Aggressive,
+    /// Extreme: symbol declarations only
+    Extreme,
+    /// Semantic: strip like aggressive but keep bodies, collapsing near-duplicate
+    /// functions across files (needs the `embeddings` feature)
+    Semantic,
+}
+
+#[derive(ValueEnum, Clone, Copy, PartialEq, Eq)]
+enum TopBy {
+    /// Heuristic importance score (entry points, core code, symbols)
+    Rank,
+    /// Token count for the target model
+    Tokens,
+    /// Number of commits touching the file in the last 90 days
+    Changes,
+}
+
+/// Lowest severity that fails `security --fail-on`
+#[derive(ValueEnum, Clone, Copy)]
+enum FailOn {
+    Low,
+    Medium,
+    High,
+    Critical,
+}
+
+impl From<FailOn> for Severity {
+    fn from(level: FailOn) -> Self {
+        match level {
+            FailOn::Low => Severity::Low,
+            FailOn::Medium => Severity::Medium,
+            FailOn::High => Severity::High,
+            FailOn::Critical => Severity::Critical,
+        }
+    }
+}
+
+#[derive(ValueEnum, Clone, Copy)]
+enum ChunkBy {
+    /// Fill chunks with files in rank order
+    Fixed,
+    /// One file per chunk
+    File,
+    /// One top-level directory per chunk
+    Module,
+    /// Keep directories together and balance chunk sizes
+    Semantic,
+    /// Follow import order
+    Dependency,
+}
+
+#[derive(ValueEnum, Clone, Copy)]
+enum MapFormat {
+    /// Human-readable summary
+    Text,
+    /// The full map as JSON
+    Json,
+    /// The full map as YAML
+    Yaml,
+}
+
+#[derive(ValueEnum, Clone, Copy)]
+enum LongLines {
+    /// Cut long lines and mark them `[LINE TRUNCATED: N chars]`
+    Truncate,
+    /// Split long lines (shifts later line numbers)
+    Wrap,
+}
+
+#[derive(ValueEnum, Clone, Copy)]
+enum Lang {
+    /// English
+    En,
+    /// Japanese
+    Ja,
+    /// German
+    De,
+    /// Spanish
+    Es,
+}
+
+impl From<Lang> for Locale {
+    fn from(l: Lang) -> Self {
+        match l {
+            Lang::En => Locale::En,
+            Lang::Ja => Locale::Ja,
+            Lang::De => Locale::De,
+            Lang::Es => Locale::Es,
+        }
+    }
+}
+
+impl From<LongLines> for LongLineMode {
+    fn from(l: LongLines) -> Self {
+        match l {
+            LongLines::Truncate => LongLineMode::Truncate,
+            LongLines::Wrap => LongLineMode::Wrap,
+        }
+    }
+}
+
+impl From<Compression> for CompressionLevel {
+    fn from(c: Compression) -> Self {
+        match c {
+            Compression::None => CompressionLevel::None,
+            Compression::Minimal => CompressionLevel::Minimal,
+            Compression::Balanced => CompressionLevel::Balanced,
+            Compression::Aggressive => CompressionLevel::Aggressive,
+            Compression::Extreme => CompressionLevel::Extreme,
+            Compression::Semantic => CompressionLevel::Semantic,
+        }
+    }
+}
+
+fn main() -> Result<()> {
+    let cli = Cli::parse();
+    ui::init(cli.quiet, cli.log_level);
+    interrupt::install();
+
+    let result = match cli.command {
+        Commands::Pack {
             path,
-            format.into(),
-            model.into(),
-            compression.into(),
+            format,
+            model,
+            compression,
             max_tokens,
             output,
             hidden,
-            !no_gitignore,
-            symbols || full, // Enable symbols if --symbols or --full
-            full,            // Full mode for PageRank ranking
+            no_gitignore,
+            symbols,
+            full,
             include_tests,
             include_docs,
-            !no_default_ignores,
+            no_default_ignores,
+            include_artifacts,
             verbose,
             header_text,
             instruction_file,
             copy_to_clipboard,
             token_tree,
-            !no_directory_structure,
-            !no_file_summary,
+            no_directory_structure,
+            no_file_summary,
             remove_empty_lines,
             remove_comments,
             top_files,
+            index_max_files,
+            index_rollup,
             include_logs,
             logs_count,
+            logs_since,
             include_diffs,
             sort_by_changes,
             stdin,
             truncate_base64,
+            max_line_length,
+            long_lines,
             include_patterns,
             exclude_patterns,
             security_check,
+            security_rules,
+            security_baseline,
+            update_baseline,
             remote_branch,
-            !no_line_numbers,
+            remote_path,
+            depth,
+            retries,
+            offline,
+            clone_max_age,
+            no_line_numbers,
+            no_lint,
+            format_version,
             config,
             watch,
+            deadline,
+            path_prefix_strip,
+            path_map,
+            delta_from,
+            write_manifest,
+            progress,
+            incremental,
+            use_index,
+            explain_ranking,
+            sample,
+            outlines,
+            contracts,
+            diff_base,
+            git_ref,
+            focus,
+            focus_depth,
+            package,
+            query,
+            lang,
+            only_lang,
+            exclude_lang,
+        } => {
+            // Flags win over the config file, which wins over the defaults
+            let loaded_config = load_config_file(config.as_deref(), &path)?;
+            let settings = &loaded_config.config;
+            let output_settings = &settings.output;
+            // An `exec:` format in the config hands the output to an external
+            // command, unless --format asks for a built-in one
+            let exec_formatter = match format {
+                Some(_) => None,
+                None => configured_formatter(&loaded_config)?,
+            };
+            let format = match exec_formatter {
+                Some(_) => Format::Xml,
+                None => flag_or_config(format, "output.format", &output_settings.format)?,
+            };
+            let model = flag_or_config(model, "output.model", &output_settings.model)?;
+            let compression =
+                flag_or_config(compression, "output.compression", &output_settings.compression)?;
+            let output = if output.is_empty() && output_settings.output_file != "-" {
+                vec![PathBuf::from(&output_settings.output_file)]
+            } else {
+                output
+            };
+            if settings.performance.threads > 0 {
+                let threads = rayon::ThreadPoolBuilder::new()
+                    .num_threads(settings.performance.threads)
+                    .build_global();
+                if let Err(e) = threads {
+                    status!("Warning: could not apply performance.threads: {}", e);
+                }
+            }
+
+            cmd_pack(
+                path.clone(),
+                format.into(),
+                model.into(),
+                compression.into(),
+                max_tokens.unwrap_or(output_settings.token_budget),
+                output,
+                hidden || settings.scan.include_hidden,
+                !no_gitignore && settings.scan.respect_gitignore,
+                // Symbols for --symbols, --full, --sample, --query or the --diff-base and
+                // --focus neighborhoods
+                symbols
+                    || full
+                    || sample
+                    || query.is_some()
+                    || diff_base.is_some()
+                    || !focus.is_empty(),
+                full, // Full mode for PageRank ranking
+                include_tests,
+                include_docs,
+                !no_default_ignores && settings.scan.default_ignores,
+                include_artifacts,
+                verbose,
+                header_text.or_else(|| output_settings.header_text.clone()),
+                instruction_file.or_else(|| {
+                    output_settings
+                        .instruction_file
+                        .as_ref()
+                        .map(|file| loaded_config.dir.join(file))
+                }),
+                copy_to_clipboard || output_settings.copy_to_clipboard,
+                token_tree || output_settings.show_token_tree,
+                !no_directory_structure && output_settings.show_directory_structure,
+                !no_file_summary && output_settings.show_file_summary,
+                IndexLimit::none()
+                    .with_max_files(index_max_files)
+                    .with_per_directory(index_rollup),
+                remove_empty_lines || output_settings.remove_empty_lines,
+                remove_comments || output_settings.remove_comments,
+                top_files.unwrap_or(output_settings.top_files_length),
+                include_logs,
+                logs_count,
+                logs_since,
+                include_diffs,
+                sort_by_changes,
+                stdin,
+                truncate_base64,
+                (max_line_length > 0)
+                    .then(|| LineLimiter::new(max_line_length).with_mode(long_lines.into())),
+                include_patterns,
+                exclude_patterns,
+                security_check
+                    || settings.security.scan_secrets
+                    || settings.security.fail_on_secrets,
+                security_rules,
+                security_baseline,
+                update_baseline,
+                remote_branch,
+                remote_path,
+                (depth > 0).then_some(depth),
+                RetryPolicy::with_retries(retries),
+                offline.then_some(clone_max_age),
+                !no_line_numbers && output_settings.line_numbers,
+                !no_lint,
+                format_version
+                    .map(FormatVersion::new)
+                    .transpose()?
+                    .unwrap_or_default(),
+                exec_formatter,
+                watch,
+                deadline,
+                path_map.into_iter().fold(
+                    path_prefix_strip
+                        .into_iter()
+                        .fold(PathRewriter::new(), PathRewriter::with_strip_prefix),
+                    |rewriter, (old, new)| rewriter.with_mapping(old, new),
+                ),
+                delta_from,
+                write_manifest,
+                progress,
+                incremental || settings.performance.incremental,
+                use_index,
+                explain_ranking,
+                sample,
+                outlines,
+                contracts,
+                diff_base,
+                git_ref,
+                focus,
+                focus_depth,
+                package,
+                language_filter(settings.scan.clone(), only_lang, exclude_lang),
+                query,
+                lang.into(),
+                loaded_config,
+            )
+        },
+        Commands::Scan {
+            path,
+            model,
+            hidden,
+            verbose,
+            json,
+            json_full,
+            with_content,
+            only_lang,
+            exclude_lang,
+        } => {
+            let mut scan = configured_scan(&path)?;
+            scan.include_hidden |= hidden;
+            apply_language_flags(&mut scan, only_lang, exclude_lang);
+            cmd_scan(path, model.into(), verbose, json, json_full, with_content, &scan)
+        },
+        Commands::Map {
+            path,
+            budget,
+            output,
+            stop_symbols,
+            min_signature_length,
+            no_default_stop_lists,
+            model,
+            max_symbols,
+            include_patterns,
+            exclude_patterns,
+            only_lang,
+            exclude_lang,
+            format,
+            focus,
+            use_index,
+        } => {
+            // `--include` replaces the config's patterns, `--exclude` adds to them
+            let mut scan = configured_scan(&path)?;
+            if !include_patterns.is_empty() {
+                scan.include = include_patterns;
+            }
+            scan.exclude.extend(exclude_patterns);
+            apply_language_flags(&mut scan, only_lang, exclude_lang);
+            let mut symbols = configured_symbols(&path);
+            if no_default_stop_lists {
+                symbols.default_stop_lists = false;
+            }
+            if let Some(len) = min_signature_length {
+                symbols.min_signature_length = len;
+            }
+            for stop_symbol in stop_symbols {
+                let (language, pattern) = match stop_symbol.split_once(':') {
+                    Some((language, pattern)) => (language.to_owned(), pattern.to_owned()),
+                    None => (ANY_LANGUAGE.to_owned(), stop_symbol),
+                };
+                symbols
+                    .stop_lists
+                    .entry(language)
+                    .or_default()
+                    .push(pattern);
+            }
+            let generator = RepoMapGenerator::new(budget)
+                .with_model(model.into())
+                .with_max_symbols(max_symbols)
+                .with_symbol_filter(SymbolFilter::from_config(&symbols))
+                .with_focus(focus);
+            cmd_map(path, generator, &scan, format, output, use_index)
+        },
+        Commands::Top { path, n, by, model, hidden, json } => {
+            cmd_top(path, n, by, model.into(), hidden, json)
+        },
+        Commands::FileContext { file, path, budget, model, output, hidden, json } => {
+            cmd_file_context(file, path, budget, model.into(), output, hidden, json)
+        },
+        Commands::Search {
+            query,
+            path,
+            n,
+            hidden,
+            json,
+            pack_results,
+            format,
+            output,
+            use_index,
+        } => {
+            let pack = pack_results.then(|| (format.into(), output));
+            cmd_search(path, &query, n, hidden, json, pack, use_index)
+        },
+        Commands::Index { path, hidden } => cmd_index(path, hidden),
+        Commands::Serve { paths, http, hidden, no_watch } => {
+            cmd_serve(paths, http, hidden, !no_watch)
+        },
+        Commands::Chunk {
+            path,
+            max_tokens,
+            strategy,
+            overlap,
+            model,
+            for_models,
+            output,
+            hidden,
+        } => {
+            cmd_chunk(path, max_tokens, strategy, overlap, model.into(), for_models, output, hidden)
+        },
+        Commands::Doctor { path, hidden, json } => cmd_doctor(path, hidden, json),
+        Commands::Tune { path, queries, max_tokens, model, json } => {
+            cmd_tune(path, queries, max_tokens, model.into(), json)
+        },
+        Commands::BisectPack {
+            path,
+            good,
+            bad,
+            paths,
+            steps,
+            format,
+            model,
+            output,
+            full,
+            hidden,
+        } => cmd_bisect_pack(
+            path,
+            &good,
+            &bad,
+            &paths,
+            steps,
+            format.into(),
+            model.into(),
+            output,
+            full,
+            hidden,
+        ),
+        Commands::Security {
+            path,
+            fail_on,
+            rules,
+            baseline,
+            update_baseline,
+            no_gitignore,
+            no_pii,
+            json,
+        } => cmd_security(
+            path,
+            fail_on.map(Severity::from),
+            rules,
+            baseline,
+            update_baseline,
+            !no_gitignore,
+            !no_pii,
+            json,
+        ),
+        Commands::Freshness { manifest, path, against, model, json } => {
+            cmd_freshness(manifest, path, against, model.into(), json)
+        },
+        Commands::Bundle {
+            command: BundleCommand::Create { pack, manifest, instructions, transcript, output },
+        } => cmd_bundle_create(pack, manifest, instructions, transcript, output),
+        Commands::Bundle { command: BundleCommand::Extract { bundle, output, force } } => {
+            cmd_bundle_extract(bundle, output, force)
+        },
+        Commands::Pr { range, to, path, format, model, map_budget, output, lang } => {
+            let range = match to {
+                Some(to) => format!("{}..{}", range, to),
+                None => range,
+            };
+            cmd_pr(path, &range, format.into(), model.into(), map_budget, output, lang.into())
+        },
+        Commands::Federate {
+            command:
+                FederateCommand::Pack { service, config, format, model, split, output, no_fetch },
+        } => cmd_federate_pack(
+            config,
+            service,
+            format.into(),
+            model.into(),
+            split,
+            output,
+            !no_fetch,
         ),
-        Commands::Scan { path, model, hidden, verbose, json } => {
-            cmd_scan(path, model.into(), hidden, verbose, json)
+        Commands::Cache { command: CacheCommand::Gc { path, max_size, dry_run, json } } => {
+            cmd_cache_gc(path, max_size, dry_run, json)
+        },
+        Commands::Analyze { path, report, hidden, max_symbols } => {
+            cmd_analyze(path, report, hidden, max_symbols)
+        },
+        Commands::Export { path, format, what, output, hidden } => {
+            cmd_export(path, format, what.into(), output, hidden)
+        },
+        Commands::Explain {
+            file,
+            path,
+            model,
+            full,
+            hidden,
+            no_gitignore,
+            include_tests,
+            include_docs,
+            no_default_ignores,
+            include_patterns,
+            exclude_patterns,
+            config,
+        } => {
+            let settings = load_config_file(config.as_deref(), &path)?.config;
+            let scan = &settings.scan;
+            let hidden = HiddenPolicy::new(hidden || scan.include_hidden)
+                .with_allowed(&scan.hidden_allowlist);
+            let respect_gitignore = !no_gitignore && scan.respect_gitignore;
+            let use_default_ignores = !no_default_ignores && scan.default_ignores;
+            cmd_explain(
+                path,
+                &file,
+                model.into(),
+                full,
+                hidden,
+                respect_gitignore,
+                &settings,
+                use_default_ignores,
+                include_tests,
+                include_docs,
+                &include_patterns,
+                exclude_patterns,
+            )
         },
-        Commands::Map { path, budget, output } => cmd_map(path, budget, output),
+        #[cfg(feature = "eval")]
+        Commands::Eval { runs, max_symbols } => cmd_eval(runs, max_symbols),
         Commands::Info => cmd_info(),
         Commands::Init { format, output, force } => cmd_init(format, output, force),
+    };
+    if result.is_err() && interrupt::token().is_cancelled() {
+        status!("Interrupted");
+        std::process::exit(interrupt::EXIT_INTERRUPTED);
+    }
+    result
+}
+
+#[allow(clippy::too_many_arguments)]
+fn cmd_pack(
+    path: PathBuf,
+    format: OutputFormat,
+    model: TokenizerModel,
+    compression: CompressionLevel,
+    max_tokens: u32,
+    output: Vec<PathBuf>,
+    include_hidden: bool,
+    respect_gitignore: bool,
+    enable_symbols: bool,
+    full_mode: bool,
+    include_tests: bool,
+    include_docs: bool,
+    use_default_ignores: bool,
+    include_artifacts: bool,
+    verbose: bool,
+    header_text: Option<String>,
+    instruction_file: Option<PathBuf>,
+    copy_to_clipboard: bool,
+    token_tree: bool,
+    show_directory_structure: bool,
+    show_file_summary: bool,
+    index_limit: IndexLimit,
+    remove_empty_lines: bool,
+    remove_comments: bool,
+    top_files: usize,
+    include_logs: bool,
+    logs_count: usize,
+    logs_since: Option<String>,
+    include_diffs: bool,
+    sort_by_changes: bool,
+    stdin: bool,
+    truncate_base64: bool,
+    line_limiter: Option<LineLimiter>,
+    include_patterns: Vec<String>,
+    exclude_patterns: Vec<String>,
+    security_check: bool,
+    security_rules: Option<PathBuf>,
+    security_baseline: Option<PathBuf>,
+    update_baseline: bool,
+    remote_branch: Option<String>,
+    remote_path: Option<String>,
+    clone_depth: Option<u32>,
+    retry: RetryPolicy,
+    offline: Option<Duration>,
+    show_line_numbers: bool,
+    lint: bool,
+    format_version: FormatVersion,
+    exec_formatter: Option<ExecFormatter>,
+    watch_mode: bool,
+    deadline: Option<Duration>,
+    path_rewriter: PathRewriter,
+    delta_from: Option<PathBuf>,
+    write_manifest: Option<PathBuf>,
+    progress_format: Option<ProgressFormat>,
+    incremental: bool,
+    use_index: bool,
+    explain_ranking: bool,
+    sample: bool,
+    outlines: bool,
+    summarize_contracts: bool,
+    diff_base: Option<String>,
+    git_ref: Option<String>,
+    focus: Vec<String>,
+    focus_depth: usize,
+    packages: Vec<String>,
+    languages: LanguageFilter,
+    query: Option<String>,
+    locale: Locale,
+    loaded_config: LoadedConfig,
+) -> Result<()> {
+    let settings = loaded_config.config;
+    if compression == CompressionLevel::Semantic && !cfg!(feature = "embeddings") {
+        anyhow::bail!(
+            "This build has no semantic compression; rebuild with `--features embeddings`"
+        );
+    }
+
+    let start = Instant::now();
+    let deadline_budget = deadline;
+    let mut deadline = deadline_budget.map(|budget| Deadline::new(start, budget));
+
+    // Render every requested output from the same scanned repository
+    let targets = output_targets(&output, format);
+    // Signature packs are made of symbols
+    let enable_symbols = enable_symbols
+        || targets
+            .iter()
+            .any(|(_, f)| matches!(f, OutputFormat::Signatures));
+
+    // Handle stdin mode - read file paths from stdin
+    let stdin_paths: Option<Vec<String>> = if stdin {
+        let stdin_handle = io::stdin();
+        let paths: Vec<String> = stdin_handle
+            .lock()
+            .lines()
+            .map_while(Result::ok)
+            .filter(|l| !l.trim().is_empty())
+            .collect();
+        if paths.is_empty() {
+            None
+        } else {
+            Some(paths)
+        }
+    } else {
+        None
+    };
+
+    if verbose {
+        status!("{}", "Infiniloom - Repository Context Generator".cyan().bold());
+        status!();
+    }
+
+    let progress = Progress::new(progress_format, verbose);
+
+    if let Some(warning) = format_version.deprecation() {
+        status!("{} {}", icon(Icon::Warn).yellow(), warning);
+    }
+
+    // Handle remote URL - clone if needed
+    let repo_path = if RemoteRepo::is_remote_url(path.to_string_lossy().as_ref()) {
+        progress.phase("clone", "Cloning remote repository...");
+        let mut remote = RemoteRepo::parse(path.to_string_lossy().as_ref())
+            .map_err(|e| anyhow::anyhow!("Invalid remote URL: {}", e))?;
+
+        // Override branch if specified via CLI
+        if let Some(ref branch) = remote_branch {
+            remote.branch = Some(branch.clone());
+        }
+        if remote_path.is_some() {
+            remote.subdir = remote_path;
+        }
+        remote.depth = clone_depth;
+
+        if verbose {
+            let branch_info = remote.branch.as_deref().unwrap_or("default");
+            status!(
+                "  Cloning {} from {:?} (branch: {})...",
+                remote.name,
+                remote.provider,
+                branch_info
+            );
+            if let Some(subdir) = &remote.subdir {
+                status!("  Checking out only {}", subdir);
+            }
+            if let Some(var) = remote
+                .token_var()
+                .filter(|var| std::env::var_os(var).is_some())
+            {
+                status!("  Authenticating with {}", var);
+            }
+        }
+
+        let clones = CloneCache::default_location()
+            .context("No clone cache directory; set INFINILOOM_CLONE_DIR")?;
+        match offline {
+            Some(max_age) => clones
+                .cached(&remote, max_age)
+                .map_err(|e| anyhow::anyhow!("Cannot pack offline: {}", e))?,
+            None => clones
+                .fetch(&remote, &retry, |e, delay| {
+                    status!("Warning: {}; retrying in {}", e, humantime::format_duration(delay));
+                })
+                .map_err(|e| anyhow::anyhow!("Failed to clone repository: {}", e))?,
+        }
+    } else {
+        path.clone()
+    };
+
+    let hidden = HiddenPolicy::new(include_hidden).with_allowed(&settings.scan.hidden_allowlist);
+
+    // Take turns with other runs on this checkout, such as a watch session
+    let repo_lock = lock_repo(&repo_path);
+
+    let own_files: Vec<PathBuf> = output
+        .iter()
+        .chain(&delta_from)
+        .chain(&write_manifest)
+        .cloned()
+        .collect();
+
+    // One pack, from the scan to the rendered outputs; watch mode runs it
+    // again on every change, so rebuilds select and transform the same way
+    let pack_once = |deadline: &mut Option<Deadline>| -> Result<(
+        infiniloom_engine::Repository,
+        Vec<(Option<PathBuf>, String)>,
+    )> {
+        // Scan repository
+        // Fast mode (default): skip symbols for speed
+        // Full mode: enable symbols for better ranking and repo map
+        let config = scanner::ScanConfig {
+            hidden: hidden.clone(),
+            respect_gitignore,
+            read_contents: true,
+            max_file_size: settings.scan.max_file_size_bytes(),
+            skip_symbols: !enable_symbols, // Skip by default unless --symbols or --full
+            symbol_deadline: deadline.as_ref().map(|d| d.phase_end(deadline::SCAN_SHARE)),
+            cancel: interrupt::token().clone(),
+        };
+
+        progress.phase("scan", "Scanning repository...");
+        let mut repo = if let Some(rev) = &git_ref {
+            // Blobs come straight from the object database, so the checkout stays as it is
+            let options = ScanOptions::default()
+                .with_hidden(include_hidden)
+                .with_hidden_allowlist(&settings.scan.hidden_allowlist)
+                .with_default_ignores(use_default_ignores)
+                .with_max_file_size(settings.scan.max_file_size_bytes())
+                .with_symbols(enable_symbols)
+                .with_ranking(false)
+                .with_cancellation(interrupt::token().clone());
+            let repo = infiniloom_engine::Repository::scan_revision(&repo_path, rev, &[], options)
+                .with_context(|| format!("Failed to read {} from git", rev))?;
+            if verbose {
+                if let Some(commit) = &repo.metadata.commit {
+                    status!("  Packing {} at commit {}", rev, commit);
+                }
+            }
+            repo
+        } else if use_index {
+            load_index(&repo_path)?
+        } else if incremental {
+            let mut cache = match &settings.performance.cache_dir {
+                Some(dir) => IncrementalScanner::with_cache_path(
+                    &repo_path,
+                    loaded_config.dir.join(dir).join("repo.cache"),
+                ),
+                None => IncrementalScanner::new(&repo_path),
+            };
+            let (repo, stats) =
+                scanner::scan_repository_incremental(&repo_path, config, &mut cache, &|p| {
+                    progress.scan(p)
+                })
+                .context("Failed to scan repository")?;
+            progress.message(format!(
+                "Incremental: {} files reused, {} processed, {} removed from cache",
+                stats.reused, stats.processed, stats.removed
+            ));
+            if let Err(e) = cache.save() {
+                status!("{} Could not update incremental cache: {}", icon(Icon::Warn).yellow(), e);
+            }
+            repo
+        } else {
+            scanner::scan_repository_with_progress(&repo_path, config, &|p| progress.scan(p))
+                .context("Failed to scan repository")?
+        };
+        progress.phase("filter", format!("Scanned {} files", repo.files.len()));
+
+        if let Some(d) = deadline.as_mut() {
+            if enable_symbols && d.past(deadline::SCAN_SHARE) {
+                d.degrade("symbol extraction skipped for files scanned after the scan budget");
+            }
+        }
+
+        // Never feed our own caches and earlier outputs back in
+        if !include_artifacts {
+            skip_own_artifacts(&mut repo, &own_files);
+        }
+
+        // Apply default ignores (test files, docs, node_modules, etc.)
+        if use_default_ignores {
+            let dropped = repo.retain_files(&|f: &RepoFile| {
+                keeps_by_default(&f.relative_path, include_tests, include_docs)
+            });
+
+            if verbose && dropped > 0 {
+                progress.message(format!(
+                    "Filtered {} -> {} files (default ignores)",
+                    repo.files.len() + dropped,
+                    repo.files.len()
+                ));
+            }
+        }
+
+        // Keep only the requested workspace members and the members they depend on
+        if !packages.is_empty() {
+            let known = repo.packages();
+            let selection = retain_packages(&mut repo, &packages);
+            if !selection.unmatched.is_empty() {
+                let names: Vec<&str> = known.iter().map(|p| p.name.as_str()).collect();
+                anyhow::bail!(
+                    "No workspace package named {} (found: {})",
+                    selection.unmatched.join(", "),
+                    if names.is_empty() {
+                        "none".to_owned()
+                    } else {
+                        names.join(", ")
+                    }
+                );
+            }
+            progress.message(format!(
+                "Packages: {}, {} dependencies, {} files",
+                selection.selected.join(", "),
+                selection.dependencies.len(),
+                repo.files.len()
+            ));
+        }
+
+        // Keep only what changed since the base ref and its dependency neighborhood
+        let change_selection = match &diff_base {
+            Some(base) => {
+                let changes = GitRepo::open(&repo_path)
+                    .and_then(|git| git.changed_since(base))
+                    .with_context(|| format!("Failed to diff against {}", base))?;
+                let selection = retain_changes(&mut repo, &changes);
+                progress.message(format!(
+                    "Diff against {}: {} changed files, {} neighbors",
+                    base,
+                    selection.changed.len(),
+                    selection.neighbors.len()
+                ));
+                Some(selection)
+            },
+            None => None,
+        };
+
+        // Keep only the focused subsystem and its import neighborhood
+        if !focus.is_empty() {
+            let selection = retain_focus(&mut repo, &focus, focus_depth);
+            for target in &selection.unmatched {
+                status!("{} --focus {} matched no files", icon(Icon::Warn).yellow(), target);
+            }
+            if selection.focused.is_empty() {
+                anyhow::bail!("No files match --focus {}", focus.join(", "));
+            }
+            progress.message(format!(
+                "Focus: {} files, {} related",
+                selection.focused.len(),
+                selection.related.len()
+            ));
+        }
+
+        // Narrow to the stdin paths, the include and exclude patterns and the
+        // languages
+        let mut selection = FilterStack::new();
+        if let Some(paths) = stdin_paths.clone() {
+            selection.push(move |f: &RepoFile| {
+                paths
+                    .iter()
+                    .any(|p| f.relative_path == *p || f.relative_path.ends_with(p.as_str()))
+            });
+        }
+        let globs = selection_globs(
+            &settings,
+            &include_patterns,
+            exclude_patterns.clone(),
+            &repo.metadata.topics,
+        );
+        if !globs.is_empty() {
+            selection.push(globs);
+        }
+        if !languages.is_empty() {
+            selection.push(languages.clone());
+        }
+        if !selection.is_empty() {
+            let dropped = repo.retain_files(&selection);
+            repo.refresh_metadata();
+            progress.message(format!("Selected {} files, {} filtered out", repo.files.len(), dropped));
+        }
+
+        // Limit to top N files if specified
+        if top_files > 0 && repo.files.len() > top_files {
+            repo.files.truncate(top_files);
+            progress.message(format!("Limited to top {} files", top_files));
+        }
+
+        progress.phase("rank", format!("Found {} files", repo.files.len()));
+
+        // Fall back to cheaper ranking when running late
+        let mut use_change_sort = sort_by_changes;
+        let mut use_pagerank = full_mode;
+        if let Some(d) = deadline.as_mut() {
+            if d.past(deadline::RANKING_SHARE) {
+                if use_change_sort {
+                    use_change_sort = false;
+                    d.degrade("skipped sorting by git change frequency");
+                }
+                if use_pagerank {
+                    use_pagerank = false;
+                    d.degrade("used heuristic ranking instead of PageRank");
+                }
+            }
+        }
+
+        // Sort by git change frequency if requested
+        if use_change_sort {
+            if let Ok(git_repo) = GitRepo::open(&path) {
+                // Calculate change frequency for each file (commits in last 90 days)
+                let mut file_changes: Vec<(String, u32)> = repo
+                    .files
+                    .iter()
+                    .map(|f| {
+                        let freq = git_repo
+                            .file_change_frequency(&f.relative_path, 90)
+                            .unwrap_or(0);
+                        (f.relative_path.clone(), freq)
+                    })
+                    .collect();
+
+                // Sort by frequency descending
+                file_changes.sort_by_key(|(_, freq)| std::cmp::Reverse(*freq));
+
+                // Reorder files based on change frequency
+                let order_map: std::collections::HashMap<String, usize> = file_changes
+                    .iter()
+                    .enumerate()
+                    .map(|(i, (path, _))| (path.clone(), i))
+                    .collect();
+
+                repo.files.sort_by_key(|f| {
+                    order_map
+                        .get(&f.relative_path)
+                        .copied()
+                        .unwrap_or(usize::MAX)
+                });
+
+                if explain_ranking {
+                    let frequencies: std::collections::HashMap<String, u32> =
+                        file_changes.into_iter().collect();
+                    for file in &mut repo.files {
+                        let commits = frequencies.get(&file.relative_path).copied().unwrap_or(0);
+                        file.rank_factors =
+                            vec![format!("change frequency ({} commits in 90 days)", commits)];
+                    }
+                }
+
+                progress.message("Sorted files by git change frequency");
+            }
+        } else if use_pagerank {
+            // Full mode: use PageRank-based ranking (slower, better quality)
+            infiniloom_engine::rank_files(&mut repo);
+            infiniloom_engine::sort_files_by_importance(&mut repo);
+            if explain_ranking {
+                annotate_rank_factors(&mut repo, true);
+            }
+        } else {
+            // Fast mode (default): use heuristic-based ranking
+            rank_files_fast(&mut repo);
+            if explain_ranking {
+                annotate_rank_factors(&mut repo, false);
+            }
+        }
+
+        // Keep the files relevant to the query, most relevant first
+        if let Some(query) = &query {
+            let selection = retain_relevant(&mut repo, query);
+            if selection.matches.is_empty() {
+                anyhow::bail!("No files match --query {:?}", query);
+            }
+            if explain_ranking {
+                for found in &selection.matches {
+                    if let Some(file) = repo
+                        .files
+                        .iter_mut()
+                        .find(|f| f.relative_path == found.path)
+                    {
+                        file.rank_factors = vec![found.describe()];
+                    }
+                }
+            }
+            progress.message(format!(
+                "Query: {} relevant files by {}, {} left out",
+                selection.matches.len(),
+                selection.scoring.name(),
+                selection.dropped
+            ));
+        }
+
+        // Files pinned by a directive come first whatever the ranking
+        repo.files.sort_by_key(|f| !f.pinned);
+
+        // Changed files come before their neighbors
+        if let Some(selection) = &change_selection {
+            repo.files
+                .sort_by_key(|f| !selection.is_changed(&f.relative_path));
+        }
+
+        // Apply content transformations based on compression level and flags
+        Compressor::new(compression)
+            .with_remove_comments(remove_comments)
+            .with_remove_empty_lines(remove_empty_lines)
+            .compress(&mut repo);
+        if truncate_base64 {
+            for file in &mut repo.files {
+                if let Some(ref mut content) = file.content {
+                    *content = truncate_base64_content(content);
+                }
+            }
+        }
+
+        // Keep minified or data-bearing lines from swamping the output
+        if let Some(limiter) = &line_limiter {
+            let changed = limiter.apply_to_repo(&mut repo);
+            if verbose && changed > 0 {
+                progress.message(format!("Limited long lines in {} files", changed));
+            }
+        }
+
+        // Skip optional enrichment when running late
+        let mut security_check = security_check
+            || security_rules.is_some()
+            || security_baseline.is_some()
+            || update_baseline;
+        let mut include_logs = include_logs;
+        let mut include_diffs = include_diffs;
+        if let Some(d) = deadline.as_mut() {
+            if d.past(deadline::ENRICH_SHARE) {
+                if security_check && !settings.security.fail_on_secrets {
+                    security_check = false;
+                    d.degrade("skipped security scan");
+                }
+                if include_logs || include_diffs {
+                    include_logs = false;
+                    include_diffs = false;
+                    d.degrade("skipped git history");
+                }
+            }
+        }
+
+        // Run security scan if requested
+        let mut security_issues = if security_check {
+            progress.phase("security", "Scanning for security issues...");
+            let scanner = security_scanner(settings.security.clone(), security_rules.as_deref())?;
+            let mut issues = Vec::new();
+            for file in &repo.files {
+                issues.extend(scanner.scan_file(file));
+            }
+
+            // Leave out accepted findings, or accept all of them
+            let baseline_path = security_baseline
+                .clone()
+                .unwrap_or_else(|| repo_path.join(BASELINE_FILE));
+            let known = apply_security_baseline(&mut issues, &baseline_path, update_baseline)?;
+            if verbose && !update_baseline && known > 0 {
+                progress.message(format!("Security baseline: {} known findings hidden", known));
+            }
+            if settings.security.fail_on_secrets && !update_baseline && !issues.is_empty() {
+                anyhow::bail!(
+                    "Found {} potential security issues, not packing (security.fail_on_secrets)",
+                    issues.len()
+                );
+            }
+            Some(issues)
+        } else {
+            None
+        };
+
+        // Populate git history in Repository struct (for structured output in formatters)
+        if include_logs || include_diffs {
+            if let Ok(git_repo) = GitRepo::open(&repo_path) {
+                use infiniloom_engine::types::{GitChangedFile, GitCommitInfo, GitHistory};
+
+                let git_repo = git_repo.with_local_time(settings.output.local_time);
+
+                let mut git_history = GitHistory::default();
+
+                // Get recent commits if requested
+                if include_logs {
+                    let commits = git_repo
+                        .log_stream(logs_count, logs_since.as_deref())
+                        .and_then(|stream| stream.collect::<Result<Vec<_>, _>>());
+                    if let Ok(commits) = commits {
+                        git_history.commits = commits
+                            .iter()
+                            .map(|c| GitCommitInfo {
+                                hash: c.hash.clone(),
+                                short_hash: c.short_hash.clone(),
+                                author: c.author.clone(),
+                                date: c.date.clone(),
+                                message: c.message.clone(),
+                                symbols: git_repo.commit_symbol_changes(&c.hash).unwrap_or_default(),
+                            })
+                            .collect();
+                    }
+                }
+
+                // Get uncommitted changes if requested, annotating each packed file
+                if include_diffs {
+                    if let Ok(changed_files) = git_repo.status() {
+                        annotate_changes(&mut repo, &changed_files);
+                        if let Ok(symbols) = git_repo.worktree_symbol_changes() {
+                            annotate_symbol_changes(&mut repo, &symbols);
+                        }
+                        git_history.changed_files = changed_files
+                            .iter()
+                            .map(|f| GitChangedFile {
+                                path: f.path.clone(),
+                                status: f.status.code().to_owned(),
+                            })
+                            .collect();
+                    }
+                }
+
+                // Set git history on repo metadata
+                repo.metadata.git_history = Some(git_history);
+
+                progress.message(format!(
+                    "Loaded {} commits, {} changes",
+                    repo.metadata
+                        .git_history
+                        .as_ref()
+                        .map(|h| h.commits.len())
+                        .unwrap_or(0),
+                    repo.metadata
+                        .git_history
+                        .as_ref()
+                        .map(|h| h.changed_files.len())
+                        .unwrap_or(0)
+                ));
+            } else if verbose {
+                status!("{} Not a git repository, skipping git history", icon(Icon::Warn).yellow());
+            }
+        }
+
+        // Rewrite paths before anything derived from them (tree, map) is built
+        if !path_rewriter.is_empty() {
+            path_rewriter.apply(&mut repo);
+            if let Some(issues) = security_issues.as_mut() {
+                for issue in issues {
+                    issue.file = path_rewriter.rewrite(&issue.file);
+                }
+            }
+            repo.metadata.directory_structure =
+                Some(scanner::generate_directory_structure(&repo.files));
+        }
+
+        // Clear directory structure if --no-directory-structure was passed
+        if !show_directory_structure {
+            repo.metadata.directory_structure = None;
+        }
+
+        // Summarize API contracts instead of packing them raw
+        let contracts = if summarize_contracts {
+            let contracts = extract_contracts(&mut repo);
+            progress.message(format!("Summarized {} contract files", contracts.len()));
+            contracts
+        } else {
+            Vec::new()
+        };
+
+        // Pack fewer files when too little time is left for output generation
+        if let Some(d) = deadline.as_mut() {
+            if d.past(deadline::OUTPUT_SHARE) {
+                let total = repo.files.len();
+                let keep = d.files_to_keep(total);
+                if keep < total {
+                    repo.files.truncate(keep);
+                    d.degrade(format!("packed only the top {} of {} files", keep, total));
+                }
+            }
+        }
+
+        // Reduce repositories beyond the budget to a ranked sample
+        let sampling = if !sample {
+            None
+        } else if max_tokens == 0 {
+            status!(
+                "{} --sample needs a --max-tokens budget, packing everything",
+                icon(Icon::Warn).yellow()
+            );
+            None
+        } else {
+            // Leave a fifth of the budget for the tree, map and the report itself
+            let report = Sampler::new(max_tokens / 5 * 4, model)
+                .with_outlines(outlines)
+                .sample(&mut repo);
+            if report.is_sampled() {
+                progress.message(format!(
+                    "Sampled: {} full, {} signatures, {} representative, {} outlined, {} omitted files",
+                    report.full.len(),
+                    report.signatures.len(),
+                    report.sampled_files(),
+                    report.outlines.len(),
+                    report.omitted
+                ));
+            }
+            Some(report)
+        };
+
+        // Leave out files the previous pack already sent, then record this one
+        let manifest = write_manifest
+            .as_ref()
+            .map(|_| PackManifest::from_repository(&repo).with_sources(&repo));
+        let delta = match &delta_from {
+            Some(manifest_path) if manifest_path.exists() => {
+                let previous = PackManifest::load(manifest_path)
+                    .with_context(|| format!("Failed to read manifest: {}", manifest_path.display()))?;
+                let delta = apply_delta(&mut repo, &previous);
+                progress.message(format!(
+                    "Delta: {} changed, {} unchanged files",
+                    repo.files.len(),
+                    delta.unchanged.len()
+                ));
+                Some(delta)
+            },
+            Some(manifest_path) => {
+                status!(
+                    "{} Manifest {} not found, packing everything",
+                    icon(Icon::Warn).yellow(),
+                    manifest_path.display()
+                );
+                None
+            },
+            None => None,
+        };
+        if let (Some(manifest_path), Some(manifest)) = (&write_manifest, &manifest) {
+            manifest
+                .save(manifest_path)
+                .with_context(|| format!("Failed to write manifest: {}", manifest_path.display()))?;
+        }
+
+        // Generate repo map
+        let map = RepoMapGenerator::new(2000)
+            .with_max_symbols(settings.symbols.max_symbols)
+            .with_symbol_filter(SymbolFilter::from_config(&settings.symbols))
+            .try_generate(&repo, interrupt::token())?;
+
+        progress.phase("output", "Generating output...");
+
+        // Reports that go inside the output, in whatever shape its format takes
+        let mut sections: Vec<Section> = [
+            contracts_section(&contracts, locale),
+            delta.as_ref().and_then(|d| d.section(locale)),
+            sampling.as_ref().and_then(|report| report.section(locale)),
+            deadline.as_ref().and_then(Deadline::section),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+
+        // Include custom instructions from file
+        if let Some(instr_path) = &instruction_file {
+            let instructions = std::fs::read_to_string(instr_path).with_context(|| {
+                format!("Failed to read instruction file: {}", instr_path.display())
+            })?;
+            sections.push(Section::new(
+                "instructions",
+                format!("\n\n<!-- Custom Instructions -->\n{}\n\n", instructions),
+                &instructions,
+            ));
+        }
+
+        // Add token tree if requested
+        if token_tree {
+            let mut text = String::from("\n\n<!-- Token Count by File -->\n");
+            text.push_str("| File | Tokens |\n|------|--------|\n");
+            let mut data = Vec::with_capacity(repo.files.len());
+            for file in &repo.files {
+                text.push_str(&format!("| {} | {} |\n", file.relative_path, file.token_count.claude));
+                data.push(serde_json::json!({ "path": file.relative_path, "tokens": file.token_count.claude }));
+            }
+            sections.push(Section::new("token_tree", text, &data));
+        }
+
+        // Add security issues if found
+        if let Some(ref issues) = security_issues {
+            if !issues.is_empty() {
+                let mut text = String::from("\n\n<!-- Security Scan Results -->\n");
+                text.push_str(&format!("⚠️ Found {} potential security issues:\n\n", issues.len()));
+                for issue in issues {
+                    text.push_str(&format!(
+                        "- [{:?}] {} in {} (line {})\n",
+                        issue.severity,
+                        issue.label(),
+                        issue.file,
+                        issue.line
+                    ));
+                }
+                sections.push(Section::new("security", text, issues));
+
+                if verbose {
+                    status!("{} Found {} security issues", icon(Icon::Warn).yellow(), issues.len());
+                }
+            } else if verbose {
+                status!("{} No security issues found", icon(Icon::Ok).green());
+            }
+        }
+
+        let mut rendered = Vec::with_capacity(targets.len());
+        for (target, target_format) in &targets {
+            // External formatters produce the final output as it is
+            if let Some(formatter) = &exec_formatter {
+                let output_text = formatter
+                    .format(&repo, &map)
+                    .with_context(|| format!("Formatter `{}` failed", formatter.command()))?;
+                rendered.push((target.clone(), output_text));
+                continue;
+            }
+
+            let formatter = OutputFormatter::by_format_version(
+                *target_format,
+                format_version,
+                show_line_numbers,
+                show_file_summary,
+                index_limit,
+                locale,
+            );
+            // The header leads text outputs; documents carry it as a section
+            let mut preamble = String::new();
+            let mut target_sections = sections.clone();
+            if let Some(header) = &header_text {
+                if target_format.is_structured() {
+                    target_sections.insert(0, Section::new("header", header.clone(), header));
+                } else {
+                    preamble = format!("{}\n\n", header);
+                }
+            }
+            let render = |repo: &infiniloom_engine::Repository, budget: Option<Section>| {
+                let mut sections = target_sections.clone();
+                sections.extend(budget);
+                formatter
+                    .format_with_sections_cancellable(repo, &map, &sections, interrupt::token())
+                    .map(|body| format!("{}{}", preamble, body))
+            };
+            let mut output_text = render(&repo, None)?;
+
+            // Enforce max tokens limit by trimming files, not the output
+            if max_tokens > 0 {
+                let current_tokens = estimate_tokens(&output_text, model);
+                if current_tokens > max_tokens as usize {
+                    if verbose {
+                        status!(
+                            "{} Output exceeds token limit ({} > {}), trimming files...",
+                            icon(Icon::Warn).yellow(),
+                            current_tokens,
+                            max_tokens
+                        );
+                    }
+                    let (fitted, report) =
+                        fit_to_max_tokens(&repo, output_text, max_tokens, model, locale, render)?;
+                    output_text = fitted;
+                    if let Some(report) = report {
+                        progress.message(format!(
+                            "Budget: {} files truncated, {} omitted",
+                            report.truncated.len(),
+                            report.omitted.len()
+                        ));
+                    }
+                }
+            }
+
+            // Check exactly what will be written
+            if lint {
+                lint_output(*target_format, &output_text).with_context(|| {
+                    format!(
+                        "Refusing to write malformed {} output (pass --no-lint to write it anyway)",
+                        formatter.name()
+                    )
+                })?;
+            }
+
+            rendered.push((target.clone(), output_text));
+        }
+        Ok((repo, rendered))
+    };
+    let (repo, rendered) = pack_once(&mut deadline)?;
+
+    if verbose {
+        if let Some(d) = &deadline {
+            for degradation in d.degradations() {
+                status!("{} Deadline: {}", icon(Icon::Warn).yellow(), degradation);
+            }
+        }
+    }
+
+    progress.phase("write", "Writing output...");
+
+    // Copy to clipboard if requested (the first output when there are several)
+    if copy_to_clipboard {
+        #[cfg(feature = "clipboard")]
+        {
+            use clipboard::{ClipboardContext, ClipboardProvider};
+            if let Ok(mut ctx) = ClipboardContext::new() {
+                let _ = ctx.set_contents(rendered[0].1.clone());
+                if verbose {
+                    status!("{} Copied to clipboard", icon(Icon::Ok).green());
+                }
+            }
+        }
+        #[cfg(not(feature = "clipboard"))]
+        {
+            status!(
+                "{} Clipboard support not enabled. Build with --features clipboard",
+                icon(Icon::Warn).yellow()
+            );
+        }
+    }
+
+    // Write outputs
+    for (target, output_text) in &rendered {
+        match target {
+            Some(output_path) => {
+                write_atomic(output_path, output_text).with_context(|| {
+                    format!("Failed to write output file: {}", output_path.display())
+                })?;
+            },
+            None => print!("{}", output_text),
+        }
+    }
+    progress.finish(repo.files.len());
+
+    if verbose && !output.is_empty() {
+        let elapsed = start.elapsed();
+        let total_lines: usize = repo
+            .files
+            .iter()
+            .filter_map(|f| f.content.as_ref())
+            .map(|c| c.lines().count())
+            .sum();
+
+        status!();
+        status!("{}", rule(50).dimmed());
+        for (target, output_text) in &rendered {
+            if let Some(output_path) = target {
+                status!(
+                    "{} Output written to: {} ({})",
+                    icon(Icon::Ok).green(),
+                    output_path.display(),
+                    format_size(output_text.len() as u64, BINARY)
+                );
+            }
+        }
+        status!("{}", rule(50).dimmed());
+        status!("  {} {} files", icon(Icon::Files).dimmed(), repo.files.len());
+        status!("  {} {} lines", icon(Icon::Lines).dimmed(), total_lines);
+        status!(
+            "  {} ~{} tokens ({})",
+            icon(Icon::Tokens).dimmed(),
+            repo.total_tokens(model),
+            model.name()
+        );
+        status!("  {} {:?}", icon(Icon::Time).dimmed(), elapsed);
+
+        // Show language breakdown if available
+        if !repo.metadata.languages.is_empty() {
+            status!();
+            status!("  {}:", "Languages".cyan());
+            for lang in repo.metadata.languages.iter().take(5) {
+                status!(
+                    "    {} {}: {} files ({:.1}%)",
+                    icon(Icon::Bullet).dimmed(),
+                    lang.language,
+                    lang.files,
+                    lang.percentage
+                );
+            }
+        }
+        status!();
+    }
+
+    // Handle watch mode
+    drop(repo_lock);
+    if watch_mode {
+        if output.is_empty() {
+            eprintln!("{} Watch mode requires --output to be specified", "Error:".red().bold());
+            std::process::exit(1);
+        }
+
+        // Skip everything the scanner would skip, plus the output files themselves,
+        // so builds and our own writes don't trigger rebuild loops
+        let watch_root = repo_path
+            .canonicalize()
+            .unwrap_or_else(|_| repo_path.clone());
+        let filter = WatchFilter::new(&watch_root)
+            .with_hidden_policy(hidden.clone())
+            .with_gitignore(respect_gitignore)
+            .with_default_ignores(use_default_ignores);
+        let filter = output.iter().fold(filter, |f, path| f.ignore_path(path));
+        let mut watcher = DebouncedWatcher::new(&watch_root, filter, Duration::from_millis(500))
+            .context("Failed to watch directory")?;
+        status!();
+        status!("{} Watching for file changes... (Ctrl+C to stop)", icon(Icon::Watch).cyan());
+
+        while let Some(changed) = watcher.next_batch() {
+            status!(
+                "{} {} file{} changed, regenerating...",
+                icon(Icon::Rebuild).yellow(),
+                changed.len(),
+                if changed.len() == 1 { "" } else { "s" }
+            );
+            if verbose {
+                for path in &changed {
+                    let shown = path.strip_prefix(&watch_root).unwrap_or(path);
+                    status!("   {}", shown.display());
+                }
+            }
+
+            let rebuild_start = Instant::now();
+            let _repo_lock = lock_repo(&repo_path);
+
+            let mut deadline = deadline_budget.map(|budget| Deadline::new(rebuild_start, budget));
+            let (new_repo, rendered) = match pack_once(&mut deadline) {
+                Ok(packed) => packed,
+                Err(e) if interrupt::token().is_cancelled() => return Err(e),
+                Err(e) => {
+                    eprintln!("{} Failed to regenerate: {:#}", "Error:".red(), e);
+                    continue;
+                },
+            };
+            let mut written = true;
+            for (target, output_text) in &rendered {
+                let Some(output_path) = target else { continue };
+                if let Err(e) = write_atomic(output_path, output_text) {
+                    eprintln!("{} Failed to write output: {}", "Error:".red(), e);
+                    written = false;
+                }
+            }
+            if written {
+                status!(
+                    "{} Regenerated in {:?} ({} files, ~{} tokens)",
+                    icon(Icon::Ok).green(),
+                    rebuild_start.elapsed(),
+                    new_repo.files.len(),
+                    new_repo.total_tokens(model)
+                );
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn cmd_scan(
+    path: PathBuf,
+    model: TokenizerModel,
+    verbose: bool,
+    json_output: bool,
+    json_full: bool,
+    with_content: bool,
+    scan: &infiniloom_engine::config::ScanConfig,
+) -> Result<()> {
+    let start = Instant::now();
+
+    // The JSON outputs count real lines and imports, which need contents and
+    // symbols; the human-readable summary gets by with estimates
+    let machine_output = json_output || json_full;
+    let mut repo = scan_selected(&path, scan, |walk| {
+        walk.read_contents = machine_output;
+        walk.skip_symbols = !machine_output;
+    })?;
+
+    let elapsed = start.elapsed();
+
+    if json_full {
+        infiniloom_engine::rank_files(&mut repo);
+        if !with_content {
+            for file in &mut repo.files {
+                file.content = None;
+            }
+        }
+        println!("{}", serde_json::to_string_pretty(&repo)?);
+    } else if json_output {
+        println!("{}", ScanReport::new(&repo).to_json());
+    } else {
+        // Human-readable output
+        println!();
+        println!("{}", rule(50).dimmed());
+        println!("  {}", "Scan Results".cyan().bold());
+        println!("{}", rule(50).dimmed());
+        println!();
+
+        println!("  Repository:   {}", repo.name.yellow());
+        println!("  Path:         {}", path.display());
+        println!("  Files:        {}", repo.files.len());
+        if !repo.metadata.topics.is_empty() {
+            let topics: Vec<&str> = repo.metadata.topics.iter().map(Topic::id).collect();
+            println!("  Topics:       {}", topics.join(", "));
+        }
+
+        let total_bytes: u64 = repo.files.iter().map(|f| f.size_bytes).sum();
+        println!("  Total Size:   {}", format_size(total_bytes, BINARY));
+        println!("  Scan Time:    {:?}", elapsed);
+        println!();
+
+        // Language breakdown
+        if !repo.metadata.languages.is_empty() {
+            println!("  {}:", "Languages".cyan());
+            for lang in &repo.metadata.languages {
+                println!("    {}: {} files ({:.1}%)", lang.language, lang.files, lang.percentage);
+            }
+            println!();
+        }
+
+        // Token estimates
+        println!("  {} ({}):", "Token Estimates".cyan(), model.name());
+        println!("    Total: ~{}", repo.total_tokens(model));
+        println!();
+
+        // Verbose file list
+        if verbose {
+            println!("  {}:", "Files".cyan());
+            let mut files: Vec<_> = repo.files.iter().collect();
+            files.sort_by_key(|f| std::cmp::Reverse(f.size_bytes));
+
+            for file in files.iter().take(20) {
+                let lang = file.language.as_deref().unwrap_or("?");
+                println!(
+                    "    {} ({}) - {}",
+                    file.relative_path,
+                    lang,
+                    format_size(file.size_bytes, BINARY)
+                );
+            }
+
+            if files.len() > 20 {
+                println!("    ... and {} more files", files.len() - 20);
+            }
+            println!();
+        }
+    }
+
+    Ok(())
+}
+
+fn cmd_map(
+    path: PathBuf,
+    generator: RepoMapGenerator,
+    scan: &infiniloom_engine::config::ScanConfig,
+    format: MapFormat,
+    output: Option<PathBuf>,
+    use_index: bool,
+) -> Result<()> {
+    let mut repo = if use_index {
+        let mut repo = load_index(&path)?;
+        scan.select_files(&mut repo);
+        repo
+    } else {
+        scan_selected(&path, scan, |_| {})?
+    };
+
+    // Rank files by importance
+    infiniloom_engine::rank_files(&mut repo);
+    infiniloom_engine::sort_files_by_importance(&mut repo);
+
+    let map = generator.try_generate(&repo, interrupt::token())?;
+
+    let output_text = match format {
+        MapFormat::Text => map.summary,
+        MapFormat::Json => serde_json::to_string_pretty(&map)?,
+        MapFormat::Yaml => serde_yaml::to_string(&map)?,
+    };
+
+    if let Some(output_path) = output {
+        write_atomic(&output_path, &output_text).context("Failed to write output file")?;
+        status!("Repository map written to: {}", output_path.display());
+    } else {
+        println!("{}", output_text);
+    }
+
+    Ok(())
+}
+
+fn cmd_top(
+    path: PathBuf,
+    n: usize,
+    by: TopBy,
+    model: TokenizerModel,
+    include_hidden: bool,
+    json_output: bool,
+) -> Result<()> {
+    // Importance scores weigh the symbols a file defines
+    let mut repo = scan_configured(&path, |scan| scan.include_hidden |= include_hidden)?;
+    infiniloom_engine::rank_files(&mut repo);
+
+    // Change counts cost one `git log` per file, so only gather them when asked
+    let changes: std::collections::HashMap<String, u32> = if by == TopBy::Changes {
+        let git_repo = GitRepo::open(&path).context("--by changes requires a git repository")?;
+        repo.files
+            .iter()
+            .map(|f| {
+                let freq = git_repo
+                    .file_change_frequency(&f.relative_path, 90)
+                    .unwrap_or(0);
+                (f.relative_path.clone(), freq)
+            })
+            .collect()
+    } else {
+        std::collections::HashMap::new()
+    };
+
+    let mut files: Vec<_> = repo.files.iter().collect();
+    match by {
+        TopBy::Rank => files.sort_by(|a, b| {
+            b.importance
+                .partial_cmp(&a.importance)
+                .unwrap_or(std::cmp::Ordering::Equal)
+                .then_with(|| a.relative_path.cmp(&b.relative_path))
+        }),
+        TopBy::Tokens => files.sort_by_key(|f| std::cmp::Reverse(f.token_count.get(model))),
+        TopBy::Changes => files.sort_by_key(|f| std::cmp::Reverse(changes[&f.relative_path])),
+    }
+    files.truncate(n);
+
+    if json_output {
+        let entries: Vec<_> = files
+            .iter()
+            .map(|f| {
+                serde_json::json!({
+                    "path": f.relative_path,
+                    "language": f.language,
+                    "importance": f.importance,
+                    "tokens": f.token_count.get(model),
+                    "changes": changes.get(&f.relative_path),
+                })
+            })
+            .collect();
+        println!("{}", serde_json::to_string_pretty(&entries)?);
+        return Ok(());
     }
+
+    let width = files
+        .iter()
+        .map(|f| f.relative_path.len())
+        .max()
+        .unwrap_or(0);
+    println!();
+    let changes_header = if by == TopBy::Changes {
+        "  Changes"
+    } else {
+        ""
+    };
+    println!(
+        "  {}",
+        format!(
+            "{:>3}  {:<width$}  {:>6}  {:>8}{}",
+            "#", "File", "Score", "Tokens", changes_header
+        )
+        .cyan()
+        .bold()
+    );
+    for (i, file) in files.iter().enumerate() {
+        let changes_column = changes
+            .get(&file.relative_path)
+            .map(|c| format!("  {:>7}", c))
+            .unwrap_or_default();
+        println!(
+            "  {:>3}  {:<width$}  {:>6.2}  {:>8}{}",
+            i + 1,
+            file.relative_path,
+            file.importance,
+            file.token_count.get(model),
+            changes_column
+        );
+    }
+    println!();
+
+    Ok(())
 }
 
-#[allow(clippy::too_many_arguments)]
-fn cmd_pack(
+fn cmd_file_context(
+    file: PathBuf,
     path: PathBuf,
-    format: OutputFormat,
+    budget: u32,
     model: TokenizerModel,
-    compression: CompressionLevel,
-    max_tokens: u32,
     output: Option<PathBuf>,
     include_hidden: bool,
-    respect_gitignore: bool,
-    enable_symbols: bool,
-    full_mode: bool,
-    include_tests: bool,
-    include_docs: bool,
-    use_default_ignores: bool,
-    verbose: bool,
-    header_text: Option<String>,
-    instruction_file: Option<PathBuf>,
-    copy_to_clipboard: bool,
-    token_tree: bool,
-    show_directory_structure: bool,
-    show_file_summary: bool,
-    remove_empty_lines: bool,
-    remove_comments: bool,
-    top_files: usize,
-    include_logs: bool,
-    logs_count: usize,
-    include_diffs: bool,
-    sort_by_changes: bool,
-    stdin: bool,
-    truncate_base64: bool,
-    include_patterns: Vec<String>,
-    exclude_patterns: Vec<String>,
-    security_check: bool,
-    remote_branch: Option<String>,
-    show_line_numbers: bool,
-    config_path: Option<PathBuf>,
-    watch_mode: bool,
+    json_output: bool,
 ) -> Result<()> {
-    let start = Instant::now();
+    // The halo is built from symbols
+    let mut repo = scan_configured(&path, |scan| scan.include_hidden |= include_hidden)?;
+
+    // Accept the file relative to the repository or to the working directory
+    let relative = match (file.canonicalize(), path.canonicalize()) {
+        (Ok(file_abs), Ok(root)) => file_abs
+            .strip_prefix(&root)
+            .map(|p| p.to_path_buf())
+            .unwrap_or_else(|_| file.clone()),
+        _ => file.clone(),
+    };
+    let relative = relative.to_string_lossy().replace('\\', "/");
+    let context = FileContext::build(&mut repo, &relative, budget, model)
+        .with_context(|| format!("No scanned file {}", file.display()))?;
 
-    // Handle stdin mode - read file paths from stdin
-    let stdin_paths: Option<Vec<String>> = if stdin {
-        let stdin_handle = io::stdin();
-        let paths: Vec<String> = stdin_handle
-            .lock()
-            .lines()
-            .map_while(Result::ok)
-            .filter(|l| !l.trim().is_empty())
+    let output_text = if json_output {
+        serde_json::to_string_pretty(&context)?
+    } else {
+        context.render()
+    };
+    if let Some(output_path) = output {
+        write_atomic(&output_path, &output_text).context("Failed to write output file")?;
+        status!(
+            "File context written to: {} ({} of {} tokens)",
+            output_path.display(),
+            context.tokens,
+            context.budget
+        );
+    } else {
+        println!("{}", output_text);
+    }
+    Ok(())
+}
+
+fn cmd_search(
+    path: PathBuf,
+    query: &str,
+    n: usize,
+    include_hidden: bool,
+    json_output: bool,
+    pack: Option<(OutputFormat, Option<PathBuf>)>,
+    use_index: bool,
+) -> Result<()> {
+    // Symbol names weigh in the ranking
+    let mut repo = if use_index {
+        load_index(&path)?
+    } else {
+        scan_configured(&path, |scan| scan.include_hidden |= include_hidden)?
+    };
+    let hits = SearchIndex::build(&repo).search(query, n);
+
+    if let Some((format, output)) = pack {
+        if hits.is_empty() {
+            anyhow::bail!("No files match {:?}", query);
+        }
+        let order: std::collections::HashMap<&str, usize> = hits
+            .iter()
+            .enumerate()
+            .map(|(i, hit)| (hit.path.as_str(), i))
             .collect();
-        if paths.is_empty() {
-            None
-        } else {
-            Some(paths)
+        repo.files
+            .retain(|f| order.contains_key(f.relative_path.as_str()));
+        repo.files.sort_by_key(|f| order[f.relative_path.as_str()]);
+        let map = RepoMapGenerator::new(2000).generate(&repo);
+        let output_text = OutputFormatter::by_format(format).format(&repo, &map);
+        match output {
+            Some(output_path) => {
+                write_atomic(&output_path, &output_text)
+                    .with_context(|| format!("Failed to write {}", output_path.display()))?;
+                status!(
+                    "{} Packed {} matching files to: {}",
+                    icon(Icon::Ok).green(),
+                    hits.len(),
+                    output_path.display()
+                );
+            },
+            None => print!("{}", output_text),
         }
-    } else {
-        None
+        return Ok(());
+    }
+
+    // The text of a matching line, from the content the index was built on
+    let line_text = |path: &str, number: usize| -> String {
+        repo.files
+            .iter()
+            .find(|f| f.relative_path == path)
+            .and_then(|f| f.numbered_lines().find(|&(n, _)| n == number))
+            .map(|(_, line)| line.trim().to_owned())
+            .unwrap_or_default()
     };
 
-    if verbose {
-        eprintln!("{}", "Infiniloom - Repository Context Generator".cyan().bold());
-        eprintln!();
+    if json_output {
+        let entries: Vec<_> = hits
+            .iter()
+            .map(|hit| {
+                let lines: Vec<_> = hit
+                    .lines
+                    .iter()
+                    .map(|&line| serde_json::json!({ "line": line, "text": line_text(&hit.path, line) }))
+                    .collect();
+                serde_json::json!({
+                    "path": hit.path,
+                    "score": hit.score,
+                    "terms": hit.terms,
+                    "symbols": hit.symbols,
+                    "lines": lines,
+                })
+            })
+            .collect();
+        println!("{}", serde_json::to_string_pretty(&entries)?);
+        return Ok(());
     }
 
-    // Create progress bar with better formatting
-    let pb = if verbose {
-        let pb = ProgressBar::new_spinner();
-        pb.set_style(
-            ProgressStyle::default_spinner()
-                .template("{spinner:.green} [{elapsed_precise}] {msg}")
-                .unwrap(),
+    if hits.is_empty() {
+        println!("No files match {:?}", query);
+        return Ok(());
+    }
+    println!();
+    for (i, hit) in hits.iter().enumerate() {
+        println!(
+            "  {:>3}  {}  {}",
+            i + 1,
+            hit.path.cyan().bold(),
+            format!("{:.2}  {}", hit.score, hit.terms.join(", ")).dimmed()
         );
-        pb.enable_steady_tick(std::time::Duration::from_millis(100));
-        pb.set_message("Scanning repository...");
-        Some(pb)
-    } else {
-        None
-    };
+        if !hit.symbols.is_empty() {
+            println!("       {} {}", "symbols:".dimmed(), hit.symbols.join(", "));
+        }
+        for &line in &hit.lines {
+            let text: String = line_text(&hit.path, line).chars().take(120).collect();
+            println!("       {:>5}  {}", line.to_string().dimmed(), text);
+        }
+    }
+    println!();
+
+    Ok(())
+}
 
-    // Load config file if specified or look for default
-    let loaded_config = load_config_file(config_path.as_ref(), &path);
+fn cmd_index(path: PathBuf, include_hidden: bool) -> Result<()> {
+    let mut repo = scan_configured(&path, |scan| scan.include_hidden |= include_hidden)?;
+    skip_own_artifacts(&mut repo, &[]);
+    let index = RepoIndex::build(repo);
+    let index_path = RepoIndex::default_path(&path);
+    index
+        .save(&index_path)
+        .with_context(|| format!("Failed to write {}", index_path.display()))?;
+
+    let symbols: usize = index.repository.files.iter().map(|f| f.symbols.len()).sum();
+    status!(
+        "{} Indexed {} files, {} symbols, {} symbol references and {} imports to: {}",
+        icon(Icon::Ok).green(),
+        index.repository.files.len(),
+        symbols,
+        index.symbol_edges.len(),
+        index.dependencies.len(),
+        index_path.display()
+    );
+    Ok(())
+}
 
-    // Handle remote URL - clone if needed
-    let (repo_path, _temp_dir) = if RemoteRepo::is_remote_url(path.to_string_lossy().as_ref()) {
-        if let Some(pb) = &pb {
-            pb.set_message("Cloning remote repository...");
+fn cmd_serve(
+    paths: Vec<PathBuf>,
+    addr: std::net::SocketAddr,
+    include_hidden: bool,
+    watch: bool,
+) -> Result<()> {
+    let workspaces = paths
+        .iter()
+        .map(|path| serve::Workspace::scan(path, include_hidden))
+        .collect::<Result<Vec<_>>>()?;
+    let server = serve::Server::new(workspaces)?;
+    if watch {
+        server.watch(include_hidden);
+    }
+
+    let listener = std::net::TcpListener::bind(addr)
+        .with_context(|| format!("Failed to listen on {}", addr))?;
+    status!(
+        "{} Serving {} repositor{} on http://{}",
+        icon(Icon::Ok).green(),
+        paths.len(),
+        if paths.len() == 1 { "y" } else { "ies" },
+        listener.local_addr()?
+    );
+    server.run(listener)
+}
+
+/// The repository saved by `infiniloom index`, with a warning if files
+/// changed since
+fn load_index(path: &std::path::Path) -> Result<infiniloom_engine::Repository> {
+    let index_path = RepoIndex::default_path(path);
+    let index = RepoIndex::load(&index_path).with_context(|| {
+        format!("Failed to load {} (run `infiniloom index` first)", index_path.display())
+    })?;
+    let changed = index.changed_files().len();
+    if changed > 0 {
+        status!(
+            "{} Index is out of date: {} files changed since it was built; run `infiniloom index` to refresh it",
+            icon(Icon::Warn).yellow(),
+            changed
+        );
+    }
+    Ok(index.repository)
+}
+
+fn cmd_chunk(
+    path: PathBuf,
+    max_tokens: u32,
+    strategy: ChunkBy,
+    overlap: u32,
+    model: TokenizerModel,
+    for_models: Vec<(Model, Option<u32>)>,
+    output: PathBuf,
+    include_hidden: bool,
+) -> Result<()> {
+    // Cross-references point at the chunk defining a symbol
+    let mut repo = scan_configured(&path, |scan| scan.include_hidden |= include_hidden)?;
+    infiniloom_engine::rank_files(&mut repo);
+    infiniloom_engine::sort_files_by_importance(&mut repo);
+
+    let strategy = match strategy {
+        ChunkBy::Fixed => ChunkStrategy::Fixed { size: max_tokens },
+        ChunkBy::File => ChunkStrategy::File,
+        ChunkBy::Module => ChunkStrategy::Module,
+        ChunkBy::Semantic => ChunkStrategy::Semantic,
+        ChunkBy::Dependency => ChunkStrategy::Dependency,
+    };
+    let mut chunker = Chunker::new(strategy, max_tokens).with_overlap(overlap);
+
+    // One scan, chunked again for every extra target model
+    if for_models.is_empty() {
+        let chunks = chunker.with_model(model).chunk(&repo);
+        write_chunks(&output, &chunks, &repo.name)?;
+        return Ok(());
+    }
+    let mut models = vec![model];
+    for (target, budget) in for_models {
+        let target = TokenizerModel::from(target);
+        if let Some(budget) = budget {
+            chunker = chunker.with_model_budget(target, budget);
         }
-        let mut remote = RemoteRepo::parse(path.to_string_lossy().as_ref())
-            .map_err(|e| anyhow::anyhow!("Invalid remote URL: {}", e))?;
+        if !models.contains(&target) {
+            models.push(target);
+        }
+    }
+    for plan in chunker.plan_for_models(&repo, &models) {
+        status!("{} ({} tokens per chunk):", plan.model.name(), plan.max_tokens);
+        write_chunks(&output.join(plan.model.name()), &plan.chunks, &repo.name)?;
+    }
 
-        // Override branch if specified via CLI
-        if let Some(ref branch) = remote_branch {
-            remote.branch = Some(branch.clone());
+    Ok(())
+}
+
+/// Write chunks to `output` as `chunk_NNN.xml`, replacing any earlier run's
+fn write_chunks(output: &std::path::Path, chunks: &[Chunk], repo_name: &str) -> Result<()> {
+    std::fs::create_dir_all(output)
+        .with_context(|| format!("Failed to create {}", output.display()))?;
+
+    // Chunks left over from an earlier, longer run would look like part of this one
+    for entry in std::fs::read_dir(output)?.flatten() {
+        let name = entry.file_name();
+        let name = name.to_string_lossy();
+        if name.starts_with("chunk_") && name.ends_with(".xml") {
+            std::fs::remove_file(entry.path())
+                .with_context(|| format!("Failed to remove {}", entry.path().display()))?;
         }
+    }
 
-        if verbose {
-            let branch_info = remote.branch.as_deref().unwrap_or("default");
-            eprintln!(
-                "  Cloning {} from {:?} (branch: {})...",
-                remote.name, remote.provider, branch_info
-            );
+    for chunk in chunks {
+        let name = format!("chunk_{:03}.xml", chunk.index + 1);
+        let chunk_path = output.join(&name);
+        write_atomic(&chunk_path, chunk.to_xml(repo_name))
+            .with_context(|| format!("Failed to write {}", chunk_path.display()))?;
+        status!(
+            "  {}  {:>7} tokens  {:>3} files  {}",
+            name,
+            chunk.tokens,
+            chunk.files.len(),
+            chunk.focus
+        );
+    }
+    status!("{} Wrote {} chunks to {}", icon(Icon::Ok).green(), chunks.len(), output.display());
+
+    Ok(())
+}
+
+fn cmd_pr(
+    path: PathBuf,
+    range: &str,
+    format: OutputFormat,
+    model: TokenizerModel,
+    map_budget: u32,
+    output: Option<PathBuf>,
+    locale: Locale,
+) -> Result<()> {
+    let git_repo = GitRepo::open(&path).context("Not a git repository")?;
+    let range_diff = git_repo
+        .range_diff(range)
+        .with_context(|| format!("Failed to diff {}", range))?;
+
+    // The map needs symbols for ranking
+    let mut repo = scan_configured(&path, |_| {})?;
+
+    // Touched files are shown as of the end of the range, not the working tree
+    let changes: Vec<_> = range_diff.files.iter().map(|d| d.file.clone()).collect();
+    let touched: HashSet<&str> = changes
+        .iter()
+        .filter(|c| c.status != FileStatus::Deleted)
+        .map(|c| c.path.as_str())
+        .collect();
+    for file in &mut repo.files {
+        if touched.contains(file.relative_path.as_str()) {
+            let content = git_repo.file_at(&range_diff.to, &file.relative_path)?;
+            if file.content.as_deref() != Some(content.as_str()) {
+                scanner::replace_content(file, content);
+            }
         }
+    }
+    annotate_changes(&mut repo, &changes);
+    let symbols: Vec<_> = range_diff
+        .files
+        .iter()
+        .flat_map(|d| d.symbols.iter().cloned())
+        .collect();
+    annotate_symbol_changes(&mut repo, &symbols);
+    infiniloom_engine::rank_files(&mut repo);
+    infiniloom_engine::sort_files_by_importance(&mut repo);
 
-        let cloned_path = remote
-            .clone(None)
-            .map_err(|e| anyhow::anyhow!("Failed to clone repository: {}", e))?;
+    // Map the directories holding the touched files, ranked around them
+    let module_of = |path: &str| path.rsplit_once('/').map_or("", |(dir, _)| dir).to_owned();
+    let modules: HashSet<String> = touched.iter().map(|p| module_of(p)).collect();
+    let mut scoped = repo.clone();
+    scoped
+        .files
+        .retain(|f| modules.contains(&module_of(&f.relative_path)));
+    let map = RepoMapGenerator::new(map_budget)
+        .with_model(model)
+        .with_focus(
+            changes
+                .iter()
+                .map(|c| c.path.as_str())
+                .filter(|p| touched.contains(p)),
+        )
+        .generate(&scoped);
 
-        // Keep temp dir alive by returning it
-        (cloned_path, Some(()))
+    repo.files
+        .retain(|f| touched.contains(f.relative_path.as_str()));
+    let formatter = OutputFormatter::by_format_with_all_options(format, true, true, locale);
+    let output_text = formatter.format_with_sections(&repo, &map, &[range_diff.section(locale)]);
+
+    if let Some(output_path) = output {
+        write_atomic(&output_path, &output_text)
+            .with_context(|| format!("Failed to write {}", output_path.display()))?;
+        status!(
+            "{} Wrote context for {} changed files in {} commits to {}",
+            icon(Icon::Ok).green(),
+            range_diff.files.len(),
+            range_diff.commits.len(),
+            output_path.display()
+        );
     } else {
-        (path.clone(), None)
-    };
+        println!("{}", output_text);
+    }
 
-    // Scan repository
-    // Fast mode (default): skip symbols for speed
-    // Full mode: enable symbols for better ranking and repo map
-    let config = scanner::ScanConfig {
-        include_hidden,
-        respect_gitignore,
-        read_contents: true,
-        max_file_size: 50 * 1024 * 1024, // 50MB
-        skip_symbols: !enable_symbols,   // Skip by default unless --symbols or --full
+    Ok(())
+}
+
+fn cmd_federate_pack(
+    config_path: PathBuf,
+    service: Option<String>,
+    format: OutputFormat,
+    model: TokenizerModel,
+    split: bool,
+    output: Option<PathBuf>,
+    fetch: bool,
+) -> Result<()> {
+    let config = FederationConfig::load(&config_path)
+        .with_context(|| format!("Failed to load {}", config_path.display()))?;
+    let selected = match config.select(service.as_deref()) {
+        Err(FederationError::UnknownService(name)) => {
+            let known: Vec<&str> = config.services().into_keys().collect();
+            anyhow::bail!(
+                "No repositories belong to service '{}' (known: {})",
+                name,
+                known.join(", ")
+            );
+        },
+        selected => selected?,
     };
+    if split && output.is_none() {
+        anyhow::bail!("--split writes one file per repository; pass --output <DIR>");
+    }
 
-    let mut repo =
-        scanner::scan_repository(&repo_path, config).context("Failed to scan repository")?;
+    let title = service.unwrap_or_else(|| "federation".to_owned());
+    let cache_dir = config.cache_dir();
+    let own_files: Vec<PathBuf> = output.iter().cloned().collect();
 
-    // Apply default ignores (test files, docs, node_modules, etc.)
-    if use_default_ignores {
-        use infiniloom_engine::default_ignores::{
-            matches_any, DEFAULT_IGNORES, DOC_IGNORES, TEST_IGNORES,
+    let mut entries = Vec::with_capacity(selected.len());
+    let mut parts = Vec::with_capacity(selected.len());
+    for member in &selected {
+        status!("{} {} ({})", icon(Icon::Arrow).cyan(), member.name, member.source());
+        let checkout = member.checkout(&cache_dir, fetch)?;
+
+        let mut repo = scan_configured(&checkout, |_| {})
+            .with_context(|| format!("Failed to scan {}", member.name))?;
+        repo.name = member.name.clone();
+        skip_own_artifacts(&mut repo, &own_files);
+        member.apply_filters(&mut repo);
+
+        infiniloom_engine::rank_files(&mut repo);
+        infiniloom_engine::sort_files_by_importance(&mut repo);
+        let dropped = config
+            .budget_for(member, selected.len())
+            .map_or(0, |budget| federation::trim_to_budget(&mut repo, budget, model));
+        repo.metadata.directory_structure =
+            Some(scanner::generate_directory_structure(&repo.files));
+
+        entries.push(federation::IndexEntry {
+            name: member.name.clone(),
+            source: member.source(),
+            branch: repo
+                .metadata
+                .branch
+                .clone()
+                .or_else(|| member.branch.clone()),
+            commit: repo.metadata.commit.clone(),
+            files: repo.files.len(),
+            tokens: repo.total_tokens(model),
+            dropped,
+            pack: split.then(|| format!("{}.{}", member.name, format.extension())),
+        });
+        parts.push((member.name.clone(), repo));
+    }
+
+    let index = federation::render_index(&title, &entries);
+    let formatter = OutputFormatter::by_format(format);
+
+    if let (true, Some(dir)) = (split, &output) {
+        std::fs::create_dir_all(dir)
+            .with_context(|| format!("Failed to create {}", dir.display()))?;
+        for ((_, repo), entry) in parts.iter().zip(&entries) {
+            let map = RepoMapGenerator::new(2000).generate(repo);
+            let pack_path = dir.join(entry.pack.as_deref().unwrap_or_default());
+            write_atomic(&pack_path, formatter.format(repo, &map))
+                .with_context(|| format!("Failed to write {}", pack_path.display()))?;
+        }
+        write_atomic(&dir.join("index.md"), &index).context("Failed to write index")?;
+        status!(
+            "{} Wrote {} packs and index.md to {}",
+            icon(Icon::Ok).green(),
+            entries.len(),
+            dir.display()
+        );
+        return Ok(());
+    }
+
+    let mut merged = federation::merge(&title, &cache_dir, parts);
+    merged.metadata.directory_structure =
+        Some(scanner::generate_directory_structure(&merged.files));
+    let map = RepoMapGenerator::new(2000).generate(&merged);
+    let output_text = format!(
+        "{}\n\n<!-- Cross-Repository Index -->\n{}",
+        formatter.format(&merged, &map),
+        index
+    );
+
+    match output {
+        Some(output_path) => {
+            write_atomic(&output_path, &output_text).with_context(|| {
+                format!("Failed to write output file: {}", output_path.display())
+            })?;
+            status!(
+                "{} Packed {} repositories into {}",
+                icon(Icon::Ok).green(),
+                entries.len(),
+                output_path.display()
+            );
+        },
+        None => print!("{}", output_text),
+    }
+    Ok(())
+}
+
+fn cmd_cache_gc(
+    path: PathBuf,
+    max_size: Option<u64>,
+    dry_run: bool,
+    json_output: bool,
+) -> Result<()> {
+    // The repository's caches, including those older versions kept in
+    // .infiniloom/, plus the shared data cache
+    let mut roots = vec![repo_cache_dir(&path), path.join(".infiniloom")];
+    roots.extend(DataCache::default_location().map(|cache| cache.dir().to_path_buf()));
+
+    let mut gc = CacheGc::new().with_dry_run(dry_run);
+    if let Some(max_size) = max_size {
+        gc = gc.with_max_size(max_size);
+    }
+    let report = gc.run(&roots).context("Failed to collect cache garbage")?;
+
+    if json_output {
+        let entries = |entries: &[CacheEntry]| -> Vec<serde_json::Value> {
+            entries
+                .iter()
+                .map(|e| {
+                    serde_json::json!({
+                        "path": e.path.display().to_string(),
+                        "kind": e.kind.name(),
+                        "size": e.size,
+                    })
+                })
+                .collect()
         };
+        let result = serde_json::json!({
+            "dry_run": dry_run,
+            "corrupt": entries(&report.corrupt),
+            "evicted": entries(&report.evicted),
+            "in_use": entries(&report.in_use),
+            "kept": report.kept.len(),
+            "freed_bytes": report.freed_bytes(),
+            "remaining_bytes": report.remaining_bytes(),
+        });
+        println!("{}", serde_json::to_string_pretty(&result)?);
+        return Ok(());
+    }
+
+    let verb = if dry_run { "Would remove" } else { "Removed" };
+    for entry in &report.corrupt {
+        println!(
+            "  {} {} {} {}",
+            icon(Icon::Fail).red(),
+            verb,
+            entry.path.display(),
+            "(corrupt)".dimmed()
+        );
+    }
+    for entry in &report.evicted {
+        println!(
+            "  {} {} {} {}",
+            "-".yellow(),
+            verb,
+            entry.path.display(),
+            format!("({})", format_size(entry.size, BINARY)).dimmed()
+        );
+    }
+    if !report.in_use.is_empty() {
+        println!(
+            "  {} {} entries in use by another process were skipped",
+            icon(Icon::Info).blue(),
+            report.in_use.len()
+        );
+    }
+    println!(
+        "  {} {} freed, {} in {} entries remain",
+        icon(Icon::Ok).green(),
+        format_size(report.freed_bytes(), BINARY),
+        format_size(report.remaining_bytes(), BINARY),
+        report.kept.len() + report.in_use.len()
+    );
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
+fn cmd_security(
+    path: PathBuf,
+    fail_on: Option<Severity>,
+    rules: Option<PathBuf>,
+    baseline: Option<PathBuf>,
+    update_baseline: bool,
+    respect_gitignore: bool,
+    pii: bool,
+    json_output: bool,
+) -> Result<()> {
+    // Secrets hide in dotfiles and in files a pack would skip, so neither the
+    // hidden allowlist nor the default ignores apply
+    let mut scan = configured_scan(&path)?;
+    scan.include_hidden = true;
+    scan.default_ignores = false;
+    scan.respect_gitignore &= respect_gitignore;
+    let repo = scan_selected(&path, &scan, |walk| walk.skip_symbols = true)?;
+
+    let mut scanner = security_scanner(configured_security(&path), rules.as_deref())?;
+    if !pii {
+        scanner.set_pii_policy(PiiPolicy { enabled: false, ..PiiPolicy::default() });
+    }
+    let mut findings = security_findings(&repo, &scanner);
+    let baseline_path = baseline.unwrap_or_else(|| path.join(BASELINE_FILE));
+    let accepted = apply_security_baseline(&mut findings, &baseline_path, update_baseline)?;
+
+    let failing =
+        fail_on.map_or(0, |level| findings.iter().filter(|f| f.severity >= level).count());
+    // Personal data is reported apart from credentials
+    let is_pii = |f: &&SecretFinding| f.kind == SecretKind::Pii;
+
+    if json_output {
+        let mut report = security_report(&findings, accepted);
+        report["fail_on"] = serde_json::json!(fail_on);
+        report["passed"] = serde_json::json!(failing == 0);
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else {
+        println!();
+        println!("{}", rule(50).dimmed());
+        println!("  {}", "Security Scan".cyan().bold());
+        println!("{}", rule(50).dimmed());
+        println!();
 
-        let before_count = repo.files.len();
-        repo.files.retain(|f| {
-            // Always apply default ignores
-            if matches_any(&f.relative_path, DEFAULT_IGNORES) {
-                return false;
+        for severity in [Severity::Critical, Severity::High, Severity::Medium, Severity::Low] {
+            let group: Vec<_> = findings
+                .iter()
+                .filter(|f| !is_pii(f) && f.severity == severity)
+                .collect();
+            if group.is_empty() {
+                continue;
             }
-            // Optionally filter tests
-            if !include_tests && matches_any(&f.relative_path, TEST_IGNORES) {
-                return false;
+            let title = format!("{:?} ({})", severity, group.len());
+            let title = match severity {
+                Severity::Critical => title.red().bold(),
+                Severity::High => title.red(),
+                Severity::Medium => title.yellow(),
+                Severity::Low => title.normal(),
+            };
+            println!("  {}", title);
+            for finding in group {
+                println!(
+                    "    {}:{}  {}  {}",
+                    finding.file,
+                    finding.line,
+                    finding.label(),
+                    finding.pattern.dimmed()
+                );
             }
-            // Optionally filter docs
-            if !include_docs && matches_any(&f.relative_path, DOC_IGNORES) {
-                return false;
+            println!();
+        }
+        let personal: Vec<_> = findings.iter().filter(is_pii).collect();
+        if !personal.is_empty() {
+            println!("  {}", format!("Personal Data ({})", personal.len()).magenta());
+            for finding in personal {
+                println!(
+                    "    {}:{}  {}  {}",
+                    finding.file,
+                    finding.line,
+                    finding.label(),
+                    finding.pattern.dimmed()
+                );
             }
-            true
-        });
+            println!();
+        }
 
-        if verbose && repo.files.len() < before_count {
-            if let Some(pb) = &pb {
-                pb.set_message(format!(
-                    "Filtered {} -> {} files (default ignores)",
-                    before_count,
-                    repo.files.len()
-                ));
-            }
+        let marker = if findings.is_empty() {
+            icon(Icon::Ok).green()
+        } else {
+            icon(Icon::Warn).yellow()
+        };
+        println!("  {} {}", marker, SecurityScanner::summarize(&findings));
+        if accepted > 0 && !update_baseline {
+            println!(
+                "  {}",
+                format!("{} known findings hidden by the baseline", accepted).dimmed()
+            );
         }
+        println!();
     }
 
-    // Filter to stdin paths if provided
-    if let Some(ref paths) = stdin_paths {
-        repo.files.retain(|f| {
-            paths
-                .iter()
-                .any(|p| f.relative_path == *p || f.relative_path.ends_with(p))
-        });
-        if verbose {
-            if let Some(pb) = &pb {
-                pb.set_message(format!("Filtered to {} files from stdin", repo.files.len()));
-            }
+    if let Some(level) = fail_on {
+        if failing > 0 {
+            eprintln!(
+                "{} {} findings at or above {:?} severity",
+                "Error:".red().bold(),
+                failing,
+                level
+            );
+            std::process::exit(1);
         }
     }
+    Ok(())
+}
 
-    // Apply include patterns
-    if !include_patterns.is_empty() {
-        let patterns: Vec<glob::Pattern> = include_patterns
+fn cmd_doctor(path: PathBuf, include_hidden: bool, json_output: bool) -> Result<()> {
+    // Default ignores stay off so committed dependencies and generated code
+    // show up; generated-file markers live in file headers, so contents are read
+    let mut scan = configured_scan(&path)?;
+    scan.include_hidden |= include_hidden;
+    scan.default_ignores = false;
+    let repo = scan_selected(&path, &scan, |walk| walk.skip_symbols = true)?;
+    let findings = Doctor::new().check(&repo);
+
+    if json_output {
+        let entries: Vec<_> = findings
             .iter()
-            .filter_map(|p| glob::Pattern::new(p).ok())
+            .map(|f| {
+                serde_json::json!({
+                    "check": f.check,
+                    "severity": f.severity.name(),
+                    "message": f.message,
+                    "paths": f.paths,
+                    "suggestion": f.suggestion,
+                })
+            })
             .collect();
-        if !patterns.is_empty() {
-            repo.files
-                .retain(|f| patterns.iter().any(|p| p.matches(&f.relative_path)));
-            if verbose {
-                if let Some(pb) = &pb {
-                    pb.set_message(format!(
-                        "Included {} files matching patterns",
-                        repo.files.len()
-                    ));
-                }
-            }
-        }
+        println!("{}", serde_json::to_string_pretty(&entries)?);
+        return Ok(());
     }
 
-    // Apply exclude patterns (combine CLI args with config file patterns)
-    let all_exclude_patterns: Vec<String> = exclude_patterns
-        .into_iter()
-        .chain(loaded_config.exclude_patterns)
-        .collect();
+    println!();
+    println!("{}", rule(50).dimmed());
+    println!("  {}", "Repository Health".cyan().bold());
+    println!("{}", rule(50).dimmed());
+    println!();
 
-    if !all_exclude_patterns.is_empty() {
-        let patterns: Vec<glob::Pattern> = all_exclude_patterns
-            .iter()
-            .filter_map(|p| glob::Pattern::new(p).ok())
-            .collect();
-        if !patterns.is_empty() {
-            repo.files
-                .retain(|f| !patterns.iter().any(|p| p.matches(&f.relative_path)));
-            if verbose {
-                if let Some(pb) = &pb {
-                    pb.set_message(format!("After exclusions: {} files", repo.files.len()));
-                }
-            }
-        }
+    if findings.is_empty() {
+        println!("  {} No issues found", icon(Icon::Ok).green());
+        println!();
+        return Ok(());
     }
 
-    // Limit to top N files if specified
-    if top_files > 0 && repo.files.len() > top_files {
-        repo.files.truncate(top_files);
-        if verbose {
-            if let Some(pb) = &pb {
-                pb.set_message(format!("Limited to top {} files", top_files));
+    for finding in &findings {
+        let marker = match finding.severity {
+            doctor::Severity::Warning => icon(Icon::Warn).yellow(),
+            doctor::Severity::Info => icon(Icon::Info).blue(),
+        };
+        println!("  {} {} ({})", marker, finding.message, finding.check.dimmed());
+        for path in finding.paths.iter().take(5) {
+            println!("      {}", path);
+        }
+        if finding.paths.len() > 5 {
+            println!("      {}", format!("...and {} more", finding.paths.len() - 5).dimmed());
+        }
+        if let Some(suggestion) = &finding.suggestion {
+            println!("    {}", "Suggested:".cyan());
+            for line in suggestion.lines() {
+                println!("      {}", line);
             }
         }
+        println!();
     }
 
-    if let Some(pb) = &pb {
-        pb.set_message(format!("Found {} files", repo.files.len()));
+    Ok(())
+}
+
+fn cmd_tune(
+    path: PathBuf,
+    queries_path: PathBuf,
+    max_tokens: Option<u32>,
+    model: TokenizerModel,
+    json_output: bool,
+) -> Result<()> {
+    let log = std::fs::read_to_string(&queries_path)
+        .with_context(|| format!("Failed to read queries: {}", queries_path.display()))?;
+    let queries = parse_queries(&log);
+    if queries.is_empty() {
+        anyhow::bail!("No queries in {}", queries_path.display());
     }
 
-    // Sort by git change frequency if requested
-    if sort_by_changes {
-        if let Ok(git_repo) = GitRepo::open(&path) {
-            // Calculate change frequency for each file (commits in last 90 days)
-            let mut file_changes: Vec<(String, u32)> = repo
-                .files
-                .iter()
-                .map(|f| {
-                    let freq = git_repo
-                        .file_change_frequency(&f.relative_path, 90)
-                        .unwrap_or(0);
-                    (f.relative_path.clone(), freq)
-                })
-                .collect();
+    // Symbol names carry most of the query vocabulary
+    let repo = scan_configured(&path, |_| {})?;
 
-            // Sort by frequency descending
-            file_changes.sort_by(|a, b| b.1.cmp(&a.1));
+    let mut advisor = TuningAdvisor::new().with_model(model);
+    if let Some(tokens) = max_tokens {
+        advisor = advisor.with_budget(tokens);
+    }
+    let report = advisor.advise(&repo, &queries);
 
-            // Reorder files based on change frequency
-            let order_map: std::collections::HashMap<String, usize> = file_changes
-                .iter()
-                .enumerate()
-                .map(|(i, (path, _))| (path.clone(), i))
-                .collect();
+    if json_output {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+        return Ok(());
+    }
 
-            repo.files.sort_by_key(|f| {
-                order_map
-                    .get(&f.relative_path)
-                    .copied()
-                    .unwrap_or(usize::MAX)
-            });
+    println!();
+    println!("{}", rule(50).dimmed());
+    println!(
+        "  {}",
+        format!("Tuning from {} queries", report.queries)
+            .cyan()
+            .bold()
+    );
+    println!("{}", rule(50).dimmed());
+    println!();
 
-            if verbose {
-                if let Some(pb) = &pb {
-                    pb.set_message("Sorted files by git change frequency");
-                }
-            }
-        }
-    } else if full_mode {
-        // Full mode: use PageRank-based ranking (slower, better quality)
-        infiniloom_engine::rank_files(&mut repo);
-        infiniloom_engine::sort_files_by_importance(&mut repo);
-    } else {
-        // Fast mode (default): use heuristic-based ranking
-        rank_files_fast(&mut repo);
+    if report.focus.is_empty() {
+        println!("  {} No file matched any query", icon(Icon::Warn).yellow());
+        println!();
+        return Ok(());
     }
 
-    // Apply content transformations based on compression level and flags
-    let should_remove_comments = remove_comments
-        || matches!(
-            compression,
-            CompressionLevel::Balanced | CompressionLevel::Aggressive | CompressionLevel::Extreme
+    println!("  {}", "Focus files:".cyan());
+    for file in &report.focus {
+        println!(
+            "    {} {} ({} queries, {} tokens)",
+            icon(Icon::Bullet),
+            file.path,
+            file.queries,
+            file.tokens
         );
-    let should_remove_empty = remove_empty_lines
-        || matches!(
-            compression,
-            CompressionLevel::Minimal
-                | CompressionLevel::Balanced
-                | CompressionLevel::Aggressive
-                | CompressionLevel::Extreme
-        );
-
-    for file in &mut repo.files {
-        if let Some(ref mut content) = file.content {
-            // Remove empty lines if requested
-            if should_remove_empty {
-                *content = remove_empty_lines_from_content(content);
-            }
-            // Remove comments if requested
-            if should_remove_comments {
-                if let Some(lang) = &file.language {
-                    *content = remove_comments_from_content(content, lang);
-                }
-            }
-            // Truncate base64 content if requested
-            if truncate_base64 {
-                *content = truncate_base64_content(content);
-            }
+        if !file.symbols.is_empty() {
+            println!("        {}", file.symbols.join(", ").dimmed());
         }
     }
+    println!();
 
-    // Run security scan if requested
-    let security_issues = if security_check {
-        if let Some(pb) = &pb {
-            pb.set_message("Scanning for security issues...");
-        }
-        let scanner = SecurityScanner::new();
-        let mut issues = Vec::new();
-        for file in &repo.files {
-            if let Some(content) = &file.content {
-                let file_issues = scanner.scan(content, &file.relative_path);
-                issues.extend(file_issues);
-            }
-        }
-        Some(issues)
-    } else {
-        None
-    };
-
-    // Populate git history in Repository struct (for structured output in formatters)
-    if include_logs || include_diffs {
-        if let Ok(git_repo) = GitRepo::open(&repo_path) {
-            use infiniloom_engine::types::{GitChangedFile, GitCommitInfo, GitHistory};
-
-            let mut git_history = GitHistory::default();
-
-            // Get recent commits if requested
-            if include_logs {
-                if let Ok(commits) = git_repo.log(logs_count) {
-                    git_history.commits = commits
-                        .iter()
-                        .map(|c| GitCommitInfo {
-                            hash: c.hash.clone(),
-                            short_hash: c.short_hash.clone(),
-                            author: c.author.clone(),
-                            date: c.date.clone(),
-                            message: c.message.clone(),
-                        })
-                        .collect();
-                }
-            }
-
-            // Get uncommitted changes if requested
-            if include_diffs {
-                if let Ok(changed_files) = git_repo.status() {
-                    git_history.changed_files = changed_files
-                        .iter()
-                        .map(|f| {
-                            let status = match f.status {
-                                infiniloom_engine::git::FileStatus::Added => "A",
-                                infiniloom_engine::git::FileStatus::Modified => "M",
-                                infiniloom_engine::git::FileStatus::Deleted => "D",
-                                infiniloom_engine::git::FileStatus::Renamed => "R",
-                                infiniloom_engine::git::FileStatus::Copied => "C",
-                                infiniloom_engine::git::FileStatus::Unknown => "?",
-                            };
-                            GitChangedFile { path: f.path.clone(), status: status.to_owned() }
-                        })
-                        .collect();
-                }
-            }
+    println!("  {}", "Include patterns:".cyan());
+    for pattern in &report.include {
+        println!(
+            "    {} {} ({} matches, {} tokens)",
+            icon(Icon::Bullet),
+            pattern.pattern,
+            pattern.queries,
+            pattern.tokens
+        );
+    }
+    println!();
 
-            // Set git history on repo metadata
-            repo.metadata.git_history = Some(git_history);
+    let budget = report.budget;
+    let share = |part: u32| part as f64 * 100.0 / budget.total().max(1) as f64;
+    println!("  {} {} tokens", "Budget:".cyan(), budget.total());
+    println!("    focus files  {:>8} ({:.0}%)", budget.focus, share(budget.focus));
+    println!("    context      {:>8} ({:.0}%)", budget.context, share(budget.context));
+    println!("    map          {:>8} ({:.0}%)", budget.map, share(budget.map));
+    println!();
 
-            if verbose {
-                if let Some(pb) = &pb {
-                    pb.set_message(format!(
-                        "Loaded {} commits, {} changes",
-                        repo.metadata
-                            .git_history
-                            .as_ref()
-                            .map(|h| h.commits.len())
-                            .unwrap_or(0),
-                        repo.metadata
-                            .git_history
-                            .as_ref()
-                            .map(|h| h.changed_files.len())
-                            .unwrap_or(0)
-                    ));
-                }
-            }
-        } else if verbose {
-            eprintln!("{} Not a git repository, skipping git history", "⚠".yellow());
+    if !report.unmatched.is_empty() {
+        println!(
+            "  {} {} queries matched no file:",
+            icon(Icon::Info).blue(),
+            report.unmatched.len()
+        );
+        for query in report.unmatched.iter().take(5) {
+            println!("      {}", query);
         }
+        println!();
     }
 
-    // Clear directory structure if --no-directory-structure was passed
-    if !show_directory_structure {
-        repo.metadata.directory_structure = None;
+    println!("  {}", "Suggested .infiniloom.yaml:".cyan());
+    for line in report.config_snippet().lines() {
+        println!("      {}", line);
     }
+    let focus: Vec<String> = report
+        .focus
+        .iter()
+        .map(|f| format!("--focus {}", f.path))
+        .collect();
+    println!("  {}", "Or for one pack:".cyan());
+    println!(
+        "      infiniloom pack {} {} --max-tokens {}",
+        path.display(),
+        focus.join(" "),
+        budget.total()
+    );
+    println!();
+
+    Ok(())
+}
 
-    // Generate repo map
-    let map = RepoMapGenerator::new(2000).generate(&repo);
+#[allow(clippy::too_many_arguments)]
+fn cmd_bisect_pack(
+    path: PathBuf,
+    good: &str,
+    bad: &str,
+    paths: &[String],
+    steps: usize,
+    format: OutputFormat,
+    model: TokenizerModel,
+    output: PathBuf,
+    full: bool,
+    include_hidden: bool,
+) -> Result<()> {
+    let options = ScanOptions { include_hidden, ..ScanOptions::default() };
+    let mut pack = BisectPack::collect(&path, good, bad, paths, steps, options)
+        .with_context(|| format!("Failed to read {}..{}", good, bad))?;
+    if !full {
+        pack.compact();
+    }
 
-    if let Some(pb) = &pb {
-        pb.set_message("Generating output...");
+    std::fs::create_dir_all(&output)
+        .with_context(|| format!("Failed to create {}", output.display()))?;
+    let formatter = OutputFormatter::by_format(format);
+    let mut tokens = 0;
+    for (i, snapshot) in pack.snapshots.iter_mut().enumerate() {
+        snapshot.repo.metadata.directory_structure =
+            Some(scanner::generate_directory_structure(&snapshot.repo.files));
+        tokens += snapshot.repo.total_tokens(model);
+        let map = RepoMapGenerator::new(2000)
+            .with_model(model)
+            .generate(&snapshot.repo);
+        let pack_path = output.join(format!("{}.{}", snapshot.file_stem(i), format.extension()));
+        write_atomic(&pack_path, formatter.format(&snapshot.repo, &map))
+            .with_context(|| format!("Failed to write {}", pack_path.display()))?;
     }
+    write_atomic(&output.join("index.md"), pack.render_index(format.extension()))
+        .context("Failed to write index")?;
+
+    status!(
+        "{} Wrote {} snapshots ({} of {} commits between, {} tokens) and index.md to {}",
+        icon(Icon::Ok).green(),
+        pack.snapshots.len(),
+        pack.snapshots.len().saturating_sub(2),
+        pack.candidates,
+        tokens,
+        output.display()
+    );
+    Ok(())
+}
+
+fn cmd_freshness(
+    manifest_path: PathBuf,
+    path: PathBuf,
+    against: Option<String>,
+    model: TokenizerModel,
+    json_output: bool,
+) -> Result<()> {
+    let manifest = PackManifest::load(&manifest_path)
+        .with_context(|| format!("Failed to read manifest: {}", manifest_path.display()))?;
+
+    let freshness = match &against {
+        Some(rev) => {
+            let repo = GitRepo::open(&path).context("Not a git repository")?;
+            repo.short_hash(rev)
+                .with_context(|| format!("Unknown ref: {}", rev))?;
+            manifest.freshness_at(&repo, rev, model)?
+        },
+        None => manifest.freshness(&path, model)?,
+    };
 
-    // Format output with options
-    let formatter =
-        OutputFormatter::by_format_with_all_options(format, show_line_numbers, show_file_summary);
-    let mut output_text = formatter.format(&repo, &map);
+    if json_output {
+        let changed: Vec<_> = freshness
+            .changed
+            .iter()
+            .map(|(path, tokens)| serde_json::json!({ "path": path, "tokens": tokens }))
+            .collect();
+        let result = serde_json::json!({
+            "fresh": freshness.is_fresh(),
+            "changed": changed,
+            "removed": freshness.removed,
+            "unchanged": freshness.unchanged,
+            "refresh_tokens": freshness.refresh_tokens,
+            "model": model.name(),
+        });
+        println!("{}", serde_json::to_string_pretty(&result)?);
+        return Ok(());
+    }
 
-    // Prepend custom header if specified
-    if let Some(header) = header_text {
-        output_text = format!("{}\n\n{}", header, output_text);
+    if freshness.is_fresh() {
+        println!(
+            "  {} All {} packed files are unchanged",
+            icon(Icon::Ok).green(),
+            freshness.unchanged
+        );
+        return Ok(());
     }
 
-    // Include custom instructions from file
-    if let Some(instr_path) = instruction_file {
-        let instructions = std::fs::read_to_string(&instr_path).with_context(|| {
-            format!("Failed to read instruction file: {}", instr_path.display())
-        })?;
-        output_text =
-            format!("{}\n\n<!-- Custom Instructions -->\n{}\n\n", output_text, instructions);
+    println!(
+        "  {} {} changed, {} removed, {} unchanged files",
+        icon(Icon::Warn).yellow(),
+        freshness.changed.len(),
+        freshness.removed.len(),
+        freshness.unchanged
+    );
+    for (path, tokens) in &freshness.changed {
+        println!("      {} {}", path, format!("(~{} tokens)", tokens).dimmed());
     }
+    for path in &freshness.removed {
+        println!("      {} {}", path, "(removed)".dimmed());
+    }
+    println!(
+        "  Delta refresh: ~{} {} tokens (pack --delta-from {})",
+        freshness.refresh_tokens,
+        model.name(),
+        manifest_path.display()
+    );
+    Ok(())
+}
 
-    // Add token tree if requested
-    if token_tree {
-        let mut tree = String::from("\n\n<!-- Token Count by File -->\n");
-        tree.push_str("| File | Tokens |\n|------|--------|\n");
-        for file in &repo.files {
-            tree.push_str(&format!("| {} | {} |\n", file.relative_path, file.token_count.claude));
-        }
-        output_text.push_str(&tree);
+fn cmd_bundle_create(
+    packs: Vec<PathBuf>,
+    manifest: Option<PathBuf>,
+    instructions: Option<PathBuf>,
+    transcripts: Vec<PathBuf>,
+    output: PathBuf,
+) -> Result<()> {
+    if let Some(manifest) = &manifest {
+        PackManifest::load(manifest)
+            .with_context(|| format!("Failed to read manifest: {}", manifest.display()))?;
     }
 
-    // Add security issues if found
-    if let Some(ref issues) = security_issues {
-        if !issues.is_empty() {
-            let mut sec_output = String::from("\n\n<!-- Security Scan Results -->\n");
-            sec_output
-                .push_str(&format!("⚠️ Found {} potential security issues:\n\n", issues.len()));
-            for issue in issues {
-                sec_output.push_str(&format!(
-                    "- [{:?}] {} in {} (line {})\n",
-                    issue.severity,
-                    issue.kind.name(),
-                    issue.file,
-                    issue.line
-                ));
-            }
-            output_text.push_str(&sec_output);
+    let mut bundle = Bundle::new();
+    let parts = packs
+        .iter()
+        .map(|path| (BundlePart::Pack, path))
+        .chain(manifest.iter().map(|path| (BundlePart::Manifest, path)))
+        .chain(
+            instructions
+                .iter()
+                .map(|path| (BundlePart::Instructions, path)),
+        )
+        .chain(
+            transcripts
+                .iter()
+                .map(|path| (BundlePart::Transcript, path)),
+        );
+    for (part, path) in parts {
+        let content = std::fs::read(path)
+            .with_context(|| format!("Failed to read {}: {}", part.name(), path.display()))?;
+        bundle.add(part, &path.to_string_lossy(), content);
+    }
 
-            if verbose {
-                eprintln!("{} Found {} security issues", "⚠".yellow(), issues.len());
-            }
-        } else if verbose {
-            eprintln!("{} No security issues found", "✓".green());
-        }
+    let bytes = bundle.to_bytes()?;
+    write_atomic(&output, &bytes)
+        .with_context(|| format!("Failed to write bundle: {}", output.display()))?;
+
+    println!(
+        "  {} Bundled {} files ({}) into {}",
+        icon(Icon::Ok).green(),
+        bundle.index.files.len(),
+        format_size(bytes.len(), BINARY),
+        output.display()
+    );
+    for file in &bundle.index.files {
+        println!("      {:<12} {}", file.part.name(), file.path);
     }
+    Ok(())
+}
 
-    // Enforce max tokens limit
-    if max_tokens > 0 {
-        let current_tokens = estimate_tokens(&output_text, model);
-        if current_tokens > max_tokens as usize {
-            if verbose {
-                eprintln!(
-                    "{} Output exceeds token limit ({} > {}), truncating...",
-                    "⚠".yellow(),
-                    current_tokens,
-                    max_tokens
-                );
-            }
-            output_text = truncate_to_tokens(&output_text, max_tokens as usize, model);
-        }
+fn cmd_bundle_extract(bundle_path: PathBuf, output: Option<PathBuf>, force: bool) -> Result<()> {
+    let bytes = std::fs::read(&bundle_path)
+        .with_context(|| format!("Failed to read bundle: {}", bundle_path.display()))?;
+    let bundle = Bundle::read(&bytes)
+        .with_context(|| format!("Failed to read bundle: {}", bundle_path.display()))?;
+    let dir = output.unwrap_or_else(|| {
+        PathBuf::from(
+            bundle_path
+                .file_stem()
+                .unwrap_or_else(|| std::ffi::OsStr::new("bundle")),
+        )
+    });
+    let written = match bundle.extract(&dir, force) {
+        Err(BundleError::Exists(path)) => {
+            anyhow::bail!("{} already exists; use --force to overwrite", path.display())
+        },
+        result => {
+            result.with_context(|| format!("Failed to extract bundle into {}", dir.display()))?
+        },
+    };
+
+    let created = std::time::UNIX_EPOCH + Duration::from_secs(bundle.index.created);
+    println!(
+        "  {} Extracted {} files into {} {}",
+        icon(Icon::Ok).green(),
+        written.len(),
+        dir.display(),
+        format!(
+            "(made {} by {})",
+            humantime::format_rfc3339_seconds(created),
+            bundle.index.generator
+        )
+        .dimmed()
+    );
+    for (file, path) in bundle.index.files.iter().zip(&written) {
+        println!("      {:<12} {}", file.part.name(), path.display());
     }
+    Ok(())
+}
+
+fn cmd_analyze(
+    path: PathBuf,
+    report: Option<PathBuf>,
+    include_hidden: bool,
+    max_symbols: usize,
+) -> Result<()> {
+    // Key symbols and entry points need symbols
+    let mut repo = scan_configured(&path, |scan| scan.include_hidden |= include_hidden)?;
 
-    if let Some(pb) = pb {
-        pb.finish_and_clear();
+    infiniloom_engine::rank_files(&mut repo);
+    infiniloom_engine::sort_files_by_importance(&mut repo);
+
+    // Over-fetch so the report still has enough symbols after imports are dropped
+    let map = RepoMapGenerator::new(2000)
+        .with_max_symbols(max_symbols * 2)
+        .generate(&repo);
+    let report_text = ReportGenerator::new()
+        .with_max_symbols(max_symbols)
+        .generate(&repo, &map);
+
+    if let Some(report_path) = report {
+        write_atomic(&report_path, &report_text).context("Failed to write report file")?;
+        status!("Onboarding report written to: {}", report_path.display());
+    } else {
+        print!("{}", report_text);
     }
 
-    // Copy to clipboard if requested
-    if copy_to_clipboard {
-        #[cfg(feature = "clipboard")]
-        {
-            use clipboard::{ClipboardContext, ClipboardProvider};
-            if let Ok(mut ctx) = ClipboardContext::new() {
-                let _ = ctx.set_contents(output_text.clone());
-                if verbose {
-                    eprintln!("{} Copied to clipboard", "✓".green());
-                }
-            }
-        }
-        #[cfg(not(feature = "clipboard"))]
-        {
-            eprintln!(
-                "{} Clipboard support not enabled. Build with --features clipboard",
-                "⚠".yellow()
-            );
-        }
+    Ok(())
+}
+
+/// Include and exclude globs of a pack: `--include` replaces the config's
+/// `scan.include`, while `--exclude` and the patterns conditional on the
+/// repository's topics add to its `scan.exclude`
+fn selection_globs(
+    settings: &Config,
+    include_patterns: &[String],
+    exclude_patterns: Vec<String>,
+    topics: &[Topic],
+) -> GlobFilter {
+    let include_patterns = if include_patterns.is_empty() {
+        &settings.scan.include
+    } else {
+        include_patterns
+    };
+    let exclude_patterns: Vec<String> = exclude_patterns
+        .into_iter()
+        .chain(settings.scan.exclude.iter().cloned())
+        .chain(settings.topic_excludes(topics))
+        .collect();
+    GlobFilter::lenient(include_patterns, &exclude_patterns)
+}
+
+/// The filters `pack` selects files with, in the order it applies them
+struct SelectionRules {
+    use_default_ignores: bool,
+    include_tests: bool,
+    include_docs: bool,
+    globs: GlobFilter,
+    languages: LanguageFilter,
+}
+
+impl FileFilter for SelectionRules {
+    fn keep(&self, file: &RepoFile) -> bool {
+        (!self.use_default_ignores
+            || keeps_by_default(&file.relative_path, self.include_tests, self.include_docs))
+            && self.globs.keep(file)
+            && self.languages.keep(file)
     }
+}
+
+impl SelectionRules {
+    /// Verdict of every rule for a file: `Ok(detail)` keeps it, `Err(detail)`
+    /// drops it
+    ///
+    /// Only explains [`keep`](FileFilter::keep), which decides.
+    fn evaluate(&self, file: &RepoFile) -> Vec<(&'static str, Result<String, String>)> {
+        use infiniloom_engine::default_ignores::{
+            is_default_ignored, matches_any, DEFAULT_IGNORES, DOC_IGNORES, TEST_IGNORES,
+        };
 
-    // Write output
-    if let Some(ref output_path) = output {
-        std::fs::write(output_path, &output_text).context("Failed to write output file")?;
+        let path = file.relative_path.as_str();
 
-        if verbose {
-            let elapsed = start.elapsed();
-            let total_lines: usize = repo
-                .files
+        let first_match = |patterns: &[&str]| {
+            patterns
                 .iter()
-                .filter_map(|f| f.content.as_ref())
-                .map(|c| c.lines().count())
-                .sum();
-
-            eprintln!();
-            eprintln!("{}", "━".repeat(50).dimmed());
-            eprintln!("{} Output written to: {}", "✓".green(), output_path.display());
-            eprintln!("{}", "━".repeat(50).dimmed());
-            eprintln!("  {} {} files", "📁".dimmed(), repo.files.len());
-            eprintln!("  {} {} lines", "📄".dimmed(), total_lines);
-            eprintln!("  {} {}", "📦".dimmed(), format_size(output_text.len() as u64, BINARY));
-            eprintln!(
-                "  {} ~{} tokens ({})",
-                "🔢".dimmed(),
-                repo.total_tokens(model),
-                model.name()
-            );
-            eprintln!("  {} {:?}", "⏱️ ".dimmed(), elapsed);
-
-            // Show language breakdown if available
-            if !repo.metadata.languages.is_empty() {
-                eprintln!();
-                eprintln!("  {}:", "Languages".cyan());
-                for lang in repo.metadata.languages.iter().take(5) {
-                    eprintln!(
-                        "    {} {}: {} files ({:.1}%)",
-                        "•".dimmed(),
-                        lang.language,
-                        lang.files,
-                        lang.percentage
-                    );
-                }
-            }
-            eprintln!();
+                .find(|p| matches_any(path, &[**p]))
+                .map(|p| (*p).to_owned())
+        };
+
+        let mut verdicts = Vec::new();
+
+        if self.use_default_ignores {
+            let default = match first_match(DEFAULT_IGNORES) {
+                Some(p) if is_default_ignored(path) => {
+                    Err(format!("matches `{}` (use --no-default-ignores)", p))
+                },
+                Some(p) => Ok(format!("matches `{}`, but env templates are kept", p)),
+                None => Ok("no match".to_owned()),
+            };
+            verdicts.push(("default ignores", default));
+
+            let tests = match first_match(TEST_IGNORES) {
+                Some(p) if !self.include_tests => {
+                    Err(format!("matches `{}` (use --include-tests)", p))
+                },
+                Some(p) => Ok(format!("matches `{}`, but tests are included", p)),
+                None => Ok("no match".to_owned()),
+            };
+            verdicts.push(("test files", tests));
+
+            let docs = match first_match(DOC_IGNORES) {
+                Some(p) if !self.include_docs => {
+                    Err(format!("matches `{}` (use --include-docs)", p))
+                },
+                Some(p) => Ok(format!("matches `{}`, but docs are included", p)),
+                None => Ok("no match".to_owned()),
+            };
+            verdicts.push(("documentation", docs));
+        } else {
+            verdicts.push(("default ignores", Ok("disabled".to_owned())));
         }
-    } else {
-        print!("{}", output_text);
-    }
 
-    // Handle watch mode
-    if watch_mode {
-        if output.is_none() {
-            eprintln!("{} Watch mode requires --output to be specified", "Error:".red().bold());
-            std::process::exit(1);
+        let include = self.globs.include_patterns();
+        if !include.is_empty() {
+            let verdict = match include.iter().find(|p| p.matches(path)) {
+                Some(p) => Ok(format!("matches `{}`", p)),
+                None => Err("matches no --include pattern".to_owned()),
+            };
+            verdicts.push(("include patterns", verdict));
         }
 
-        let output_path = output.as_ref().unwrap().clone();
-        eprintln!();
-        eprintln!("{} Watching for file changes... (Ctrl+C to stop)", "👀".cyan());
+        let exclude = self.globs.exclude_patterns();
+        if !exclude.is_empty() {
+            let verdict = match exclude.iter().find(|p| p.matches(path)) {
+                Some(p) => Err(format!("matches `{}`", p)),
+                None => Ok("no match".to_owned()),
+            };
+            verdicts.push(("exclude patterns", verdict));
+        }
 
-        use notify::{Config, Event, RecommendedWatcher, RecursiveMode, Watcher};
-        use std::sync::mpsc::channel;
-        use std::time::Duration;
+        if !self.languages.is_empty() {
+            let language = file.language.as_deref().unwrap_or("unknown");
+            let verdict = if self.languages.keep(file) {
+                Ok(format!("{} is selected", language))
+            } else {
+                Err(format!("{} is left out by scan.only_languages or exclude_languages", language))
+            };
+            verdicts.push(("languages", verdict));
+        }
 
-        let (tx, rx) = channel();
+        verdicts
+    }
+}
 
-        let mut watcher = RecommendedWatcher::new(
-            move |res: Result<Event, notify::Error>| {
-                if let Ok(event) = res {
-                    if event.kind.is_modify() || event.kind.is_create() || event.kind.is_remove() {
-                        let _ = tx.send(());
-                    }
-                }
-            },
-            Config::default().with_poll_interval(Duration::from_secs(1)),
-        )
-        .context("Failed to create file watcher")?;
+#[allow(clippy::too_many_arguments)]
+fn cmd_explain(
+    path: PathBuf,
+    file: &str,
+    model: TokenizerModel,
+    full_mode: bool,
+    hidden: HiddenPolicy,
+    respect_gitignore: bool,
+    settings: &Config,
+    use_default_ignores: bool,
+    include_tests: bool,
+    include_docs: bool,
+    include_patterns: &[String],
+    exclude_patterns: Vec<String>,
+) -> Result<()> {
+    let file = file.trim_start_matches("./").replace('\\', "/");
 
-        watcher
-            .watch(&repo_path, RecursiveMode::Recursive)
-            .context("Failed to watch directory")?;
+    // The walk of the config's `scan` section, but the selection rules are
+    // applied below, after each is explained
+    let config = scanner::ScanConfig {
+        hidden: hidden.clone(),
+        respect_gitignore,
+        read_contents: true,
+        skip_symbols: !full_mode, // Symbols only affect full mode ranking
+        cancel: interrupt::token().clone(),
+        ..scanner::ScanConfig::from_config(&settings.scan)
+    };
+    let mut repo = scanner::scan_repository(&path, config).context("Failed to scan repository")?;
+
+    println!("{}", file.bold());
+
+    let Some(scanned) = repo.files.iter().find(|f| f.relative_path == file).cloned() else {
+        let reason = if !path.join(&file).is_file() {
+            "no such file in the repository"
+        } else if !hidden.allows(std::path::Path::new(&file), false) {
+            "hidden file (use --hidden or add it to hidden_allowlist)"
+        } else {
+            "ignored by .gitignore, binary, or over the size limit"
+        };
+        println!("  Status: {} - {}", "not scanned".red(), reason);
+        return Ok(());
+    };
 
-        // Debounce: wait for changes to settle
-        let debounce_duration = Duration::from_millis(500);
-        let mut last_rebuild = Instant::now();
+    // The same filters as pack, in the same order
+    let rules = SelectionRules {
+        use_default_ignores,
+        include_tests,
+        include_docs,
+        globs: selection_globs(settings, include_patterns, exclude_patterns, &repo.metadata.topics),
+        languages: language_filter(settings.scan.clone(), Vec::new(), Vec::new()),
+    };
+    let verdicts = rules.evaluate(&scanned);
+    let selected = rules.keep(&scanned);
 
-        loop {
-            match rx.recv_timeout(Duration::from_secs(1)) {
-                Ok(()) => {
-                    // Debounce - wait for changes to settle
-                    if last_rebuild.elapsed() < debounce_duration {
-                        continue;
-                    }
+    repo.retain_files(&rules);
+    if full_mode {
+        infiniloom_engine::rank_files(&mut repo);
+        infiniloom_engine::sort_files_by_importance(&mut repo);
+    } else {
+        rank_files_fast(&mut repo);
+    }
 
-                    eprintln!("{} Change detected, regenerating...", "🔄".yellow());
-
-                    // Re-run the pack logic
-                    let rebuild_start = Instant::now();
-
-                    // Re-scan repository
-                    let scan_config = scanner::ScanConfig {
-                        include_hidden,
-                        respect_gitignore,
-                        read_contents: true,
-                        max_file_size: 50 * 1024 * 1024,
-                        skip_symbols: !enable_symbols,
-                    };
-
-                    if let Ok(mut new_repo) = scanner::scan_repository(&repo_path, scan_config) {
-                        // Re-apply transformations
-                        if full_mode {
-                            infiniloom_engine::rank_files(&mut new_repo);
-                            infiniloom_engine::sort_files_by_importance(&mut new_repo);
-                        } else {
-                            rank_files_fast(&mut new_repo);
-                        }
+    match repo.files.iter().position(|f| f.relative_path == file) {
+        Some(index) if selected => println!(
+            "  Status: {} (position {} of {})",
+            "included".green(),
+            index + 1,
+            repo.files.len()
+        ),
+        _ => println!("  Status: {}", "excluded".red()),
+    }
 
-                        let new_map = RepoMapGenerator::new(2000).generate(&new_repo);
-                        let new_formatter =
-                            OutputFormatter::by_format_with_options(format, show_line_numbers);
-                        let new_output = new_formatter.format(&new_repo, &new_map);
-
-                        if let Err(e) = std::fs::write(&output_path, &new_output) {
-                            eprintln!("{} Failed to write output: {}", "Error:".red(), e);
-                        } else {
-                            eprintln!(
-                                "{} Regenerated in {:?} ({} files, ~{} tokens)",
-                                "✓".green(),
-                                rebuild_start.elapsed(),
-                                new_repo.files.len(),
-                                new_repo.total_tokens(model)
-                            );
-                        }
-                    }
+    println!();
+    println!("  {}", "Selection rules".cyan());
+    for (rule, verdict) in &verdicts {
+        match verdict {
+            Ok(detail) => println!("    {} {}: {}", icon(Icon::Ok).green(), rule, detail),
+            Err(detail) => println!("    {} {}: {}", icon(Icon::Fail).red(), rule, detail),
+        }
+    }
 
-                    last_rebuild = Instant::now();
-                },
-                Err(std::sync::mpsc::RecvTimeoutError::Timeout) => {
-                    // Just keep watching
-                },
-                Err(std::sync::mpsc::RecvTimeoutError::Disconnected) => {
-                    break;
-                },
-            }
+    println!();
+    if full_mode {
+        let importance = infiniloom_engine::file_importance(&scanned);
+        println!("  {} (full mode, higher ranks first)", "Ranking".cyan());
+        println!("    {:<32} {:>8.2}", format!("{} (base)", importance.category), importance.base);
+        println!("    {:<32} {:>+8.2}", "symbol count", importance.symbol_boost);
+        println!("    {:<32} {:>+8.2}", "implementation name", importance.name_boost);
+        println!("    {:<32} {:>8.2}", "importance", importance.total());
+    } else {
+        println!("  {} (fast heuristic, lower ranks first)", "Ranking".cyan());
+        let entry_point = entry_point_paths(&repo).contains(&file);
+        for (reason, delta) in fast_rank_components(&file, entry_point) {
+            println!("    {:<32} {:>+8}", reason, delta);
         }
+        println!("    {:<32} {:>8}", "score", fast_rank_score(&file, entry_point));
     }
 
+    println!();
+    println!("  {}", "Tokens".cyan());
+    println!("    {:<32} {:>8}", model.name(), scanned.token_count.get(model));
+    println!("    {:<32} {:>8}", "size (bytes)", scanned.size_bytes);
+
     Ok(())
 }
 
-fn cmd_scan(
+fn cmd_export(
     path: PathBuf,
-    model: TokenizerModel,
+    format: IndexFormat,
+    dataset: Dataset,
+    output: Option<PathBuf>,
     include_hidden: bool,
-    verbose: bool,
-    json_output: bool,
 ) -> Result<()> {
-    let start = Instant::now();
+    if format.text_format().is_none() && output.is_none() {
+        anyhow::bail!("Parquet export writes a binary file; pass --output <FILE>");
+    }
 
-    let config = scanner::ScanConfig {
-        include_hidden,
-        respect_gitignore: true,
-        read_contents: false, // Don't need content for stats
-        max_file_size: 50 * 1024 * 1024,
-        skip_symbols: true, // No need for symbols in scan mode
-    };
+    // Contents are needed for ctags patterns, LSIF columns and line counts
+    let repo = scan_configured(&path, |scan| scan.include_hidden |= include_hidden)?;
 
-    let repo = scanner::scan_repository(&path, config).context("Failed to scan repository")?;
+    let Some(format) = format.text_format() else {
+        let output_path = output.unwrap_or_default();
+        let rows = export_parquet_file(&repo, dataset, &output_path)?;
+        status!("{} rows written to: {}", rows, output_path.display());
+        return Ok(());
+    };
 
-    let elapsed = start.elapsed();
+    let output_text = export_symbols(&repo, format);
 
-    if json_output {
-        // JSON output
-        let stats = serde_json::json!({
-            "repository": repo.name,
-            "files": repo.files.len(),
-            "total_bytes": repo.files.iter().map(|f| f.size_bytes).sum::<u64>(),
-            "total_tokens": {
-                "claude": repo.total_tokens(TokenizerModel::Claude),
-                "gpt4o": repo.total_tokens(TokenizerModel::Gpt4o),
-                "gemini": repo.total_tokens(TokenizerModel::Gemini),
-            },
-            "languages": repo.metadata.languages,
-            "scan_time_ms": elapsed.as_millis(),
-        });
-        println!("{}", serde_json::to_string_pretty(&stats)?);
+    if let Some(output_path) = output {
+        write_atomic(&output_path, &output_text).context("Failed to write output file")?;
+        status!("Symbol index written to: {}", output_path.display());
     } else {
-        // Human-readable output
-        println!();
-        println!("{}", "━".repeat(50).dimmed());
-        println!("  {}", "Scan Results".cyan().bold());
-        println!("{}", "━".repeat(50).dimmed());
-        println!();
-
-        println!("  Repository:   {}", repo.name.yellow());
-        println!("  Path:         {}", path.display());
-        println!("  Files:        {}", repo.files.len());
-
-        let total_bytes: u64 = repo.files.iter().map(|f| f.size_bytes).sum();
-        println!("  Total Size:   {}", format_size(total_bytes, BINARY));
-        println!("  Scan Time:    {:?}", elapsed);
-        println!();
-
-        // Language breakdown
-        if !repo.metadata.languages.is_empty() {
-            println!("  {}:", "Languages".cyan());
-            for lang in &repo.metadata.languages {
-                println!("    {}: {} files ({:.1}%)", lang.language, lang.files, lang.percentage);
-            }
-            println!();
-        }
-
-        // Token estimates
-        println!("  {} ({}):", "Token Estimates".cyan(), model.name());
-        println!("    Total: ~{}", repo.total_tokens(model));
-        println!();
-
-        // Verbose file list
-        if verbose {
-            println!("  {}:", "Files".cyan());
-            let mut files: Vec<_> = repo.files.iter().collect();
-            files.sort_by(|a, b| b.size_bytes.cmp(&a.size_bytes));
-
-            for file in files.iter().take(20) {
-                let lang = file.language.as_deref().unwrap_or("?");
-                println!(
-                    "    {} ({}) - {}",
-                    file.relative_path,
-                    lang,
-                    format_size(file.size_bytes, BINARY)
-                );
-            }
-
-            if files.len() > 20 {
-                println!("    ... and {} more files", files.len() - 20);
-            }
-            println!();
-        }
+        print!("{}", output_text);
     }
 
     Ok(())
 }
 
-fn cmd_map(path: PathBuf, budget: u32, output: Option<PathBuf>) -> Result<()> {
-    let config = scanner::ScanConfig {
-        include_hidden: false,
-        respect_gitignore: true,
-        read_contents: true,
-        max_file_size: 50 * 1024 * 1024,
-        skip_symbols: false, // Map command needs symbols for ranking
-    };
-
-    let mut repo = scanner::scan_repository(&path, config).context("Failed to scan repository")?;
-
-    // Rank files by importance
-    infiniloom_engine::rank_files(&mut repo);
-    infiniloom_engine::sort_files_by_importance(&mut repo);
+#[cfg(feature = "parquet")]
+fn export_parquet_file(
+    repo: &infiniloom_engine::Repository,
+    dataset: Dataset,
+    output_path: &std::path::Path,
+) -> Result<usize> {
+    let file = std::fs::File::create(output_path).context("Failed to create output file")?;
+    infiniloom_engine::export::export_parquet(repo, dataset, io::BufWriter::new(file))
+        .context("Failed to write Parquet file")
+}
 
-    let map = RepoMapGenerator::new(budget).generate(&repo);
+#[cfg(not(feature = "parquet"))]
+fn export_parquet_file(
+    _repo: &infiniloom_engine::Repository,
+    _dataset: Dataset,
+    _output_path: &std::path::Path,
+) -> Result<usize> {
+    anyhow::bail!("This build has no Parquet support; rebuild with `--features parquet`")
+}
 
-    let output_text = map.summary.clone();
+#[cfg(feature = "eval")]
+fn cmd_eval(runs: usize, max_symbols: usize) -> Result<()> {
+    use infiniloom_engine::eval::{builtin_fixtures, Evaluator};
 
-    if let Some(output_path) = output {
-        std::fs::write(&output_path, &output_text).context("Failed to write output file")?;
-        eprintln!("Repository map written to: {}", output_path.display());
-    } else {
-        println!("{}", output_text);
+    let reports = Evaluator::new()
+        .with_runs(runs)
+        .with_max_symbols(max_symbols)
+        .evaluate_all(&builtin_fixtures());
+    for report in &reports {
+        println!("{}", report);
     }
 
     Ok(())
@@ -1200,7 +4434,7 @@ fn cmd_map(path: PathBuf, budget: u32, output: Option<PathBuf>) -> Result<()> {
 fn cmd_info() -> Result<()> {
     println!();
     println!("{}", "Infiniloom - Repository Context Generator".cyan().bold());
-    println!("{}", "━".repeat(50).dimmed());
+    println!("{}", rule(50).dimmed());
     println!();
     println!("  Version:      {}", env!("CARGO_PKG_VERSION"));
     println!("  Engine:       {}", infiniloom_engine::VERSION);
@@ -1212,6 +4446,7 @@ fn cmd_info() -> Result<()> {
     println!("    yaml      - Gemini-optimized (query at end)");
     println!("    toon      - Most token-efficient (~40% smaller)");
     println!("    plain     - Simple plain text (no markup)");
+    println!("    signatures - Directory tree, exported signatures and dependencies");
     println!();
     println!("  {}:", "Supported Models".yellow());
     println!("    claude    - Anthropic Claude (default)");
@@ -1224,8 +4459,9 @@ fn cmd_info() -> Result<()> {
     println!("    none      - No compression (0%)");
     println!("    minimal   - Whitespace only (~15%)");
     println!("    balanced  - Remove comments (~35%)");
-    println!("    aggressive - Signatures only (~60%)");
-    println!("    extreme   - Key symbols only (~80%)");
+    println!("    aggressive - Remove docstrings too (~60%)");
+    println!("    extreme   - Symbol declarations only (~80%)");
+    println!("    semantic  - Aggressive, collapsing near-duplicate functions (embeddings)");
     println!();
 
     Ok(())
@@ -1252,13 +4488,13 @@ fn cmd_init(format: ConfigFormat, output: Option<PathBuf>, force: bool) -> Resul
     }
 
     // Generate default config
-    let config_content = infiniloom_engine::Config::generate_default(format_name);
+    let config_content = Config::generate_default(format_name);
 
     // Write config file
-    std::fs::write(&output_path, &config_content)
+    write_atomic(&output_path, &config_content)
         .with_context(|| format!("Failed to write config file: {}", output_path.display()))?;
 
-    println!("{} Created configuration file: {}", "✓".green(), output_path.display());
+    println!("{} Created configuration file: {}", icon(Icon::Ok).green(), output_path.display());
     println!();
     println!("Edit this file to customize Infiniloom behavior.");
     println!("See https://github.com/homotopylabs/infiniloom#configuration for options.");
@@ -1320,98 +4556,219 @@ fn truncate_base64_content(content: &str) -> String {
     }
 }
 
-/// Remove empty lines from content
-fn remove_empty_lines_from_content(content: &str) -> String {
-    content
-        .lines()
-        .filter(|line| !line.trim().is_empty())
-        .collect::<Vec<_>>()
-        .join("\n")
+/// Security scanner for a config's `security` section, extended by a rules file
+fn security_scanner(
+    mut security: SecurityConfig,
+    rules: Option<&std::path::Path>,
+) -> Result<SecurityScanner> {
+    if let Some(rules) = rules {
+        security.extend(
+            SecurityConfig::load_rules(rules)
+                .with_context(|| format!("Failed to read security rules: {}", rules.display()))?,
+        );
+    }
+    Ok(security.scanner()?)
+}
+
+/// Findings of `scanner` in `repo`, most severe first, leaving out build
+/// artifacts
+fn security_findings(
+    repo: &infiniloom_engine::Repository,
+    scanner: &SecurityScanner,
+) -> Vec<SecretFinding> {
+    use infiniloom_engine::default_ignores::{matches_any, ARTIFACT_IGNORES};
+
+    let mut findings: Vec<SecretFinding> = repo
+        .files
+        .iter()
+        .filter(|f| !matches_any(&f.relative_path, ARTIFACT_IGNORES))
+        .flat_map(|f| scanner.scan_file(f))
+        .collect();
+    findings.sort_by(|a, b| {
+        b.severity
+            .cmp(&a.severity)
+            .then_with(|| a.file.cmp(&b.file))
+            .then(a.line.cmp(&b.line))
+    });
+    findings
 }
 
-/// Remove comments from code based on language
-fn remove_comments_from_content(content: &str, language: &str) -> String {
-    let (line_comment, block_start, block_end) = match language.to_lowercase().as_str() {
-        "python" | "ruby" | "shell" | "bash" | "sh" | "yaml" | "yml" => ("#", "", ""),
-        "javascript" | "typescript" | "java" | "c" | "cpp" | "c++" | "rust" | "go" | "swift"
-        | "kotlin" | "scala" => ("//", "/*", "*/"),
-        "html" | "xml" => ("", "<!--", "-->"),
-        "css" | "scss" | "sass" => ("", "/*", "*/"),
-        "sql" => ("--", "/*", "*/"),
-        "lua" => ("--", "--[[", "]]"),
-        _ => ("//", "/*", "*/"), // Default to C-style
+/// JSON report of `findings`: the findings themselves and their counts, with
+/// personal data counted apart from credentials
+fn security_report(findings: &[SecretFinding], baselined: usize) -> serde_json::Value {
+    let count = |severity: Severity| {
+        findings
+            .iter()
+            .filter(|f| f.kind != SecretKind::Pii && f.severity == severity)
+            .count()
     };
+    serde_json::json!({
+        "findings": findings,
+        "counts": {
+            "critical": count(Severity::Critical),
+            "high": count(Severity::High),
+            "medium": count(Severity::Medium),
+            "low": count(Severity::Low),
+            "pii": findings.iter().filter(|f| f.kind == SecretKind::Pii).count(),
+        },
+        "baselined": baselined,
+    })
+}
 
-    let mut result = String::new();
-    let mut in_block_comment = false;
-
-    for line in content.lines() {
-        let trimmed = line.trim();
-
-        // Handle block comments
-        if !block_start.is_empty() && !block_end.is_empty() {
-            if in_block_comment {
-                if let Some(idx) = line.find(block_end) {
-                    in_block_comment = false;
-                    let after_block = &line[idx + block_end.len()..];
-                    if !after_block.trim().is_empty() {
-                        result.push_str(after_block);
-                        result.push('\n');
-                    }
-                }
-                continue;
-            }
+/// Drop findings recorded in the baseline at `path`, or with `update` record
+/// them all there and drop them; returns how many were dropped
+fn apply_security_baseline(
+    findings: &mut Vec<SecretFinding>,
+    path: &std::path::Path,
+    update: bool,
+) -> Result<usize> {
+    let before = findings.len();
+    if update {
+        SecurityBaseline::from_findings(findings)
+            .save(path)
+            .with_context(|| format!("Failed to write security baseline: {}", path.display()))?;
+        status!("{} Recorded {} findings in {}", icon(Icon::Ok).green(), before, path.display());
+        findings.clear();
+    } else {
+        SecurityBaseline::load(path)
+            .with_context(|| format!("Failed to read security baseline: {}", path.display()))?
+            .retain_new(findings);
+    }
+    Ok(before - findings.len())
+}
 
-            if let Some(idx) = line.find(block_start) {
-                // Check if block comment ends on same line
-                if let Some(end_idx) = line[idx + block_start.len()..].find(block_end) {
-                    let before = &line[..idx];
-                    let after = &line[idx + block_start.len() + end_idx + block_end.len()..];
-                    let combined = format!("{}{}", before.trim_end(), after);
-                    if !combined.trim().is_empty() {
-                        result.push_str(&combined);
-                        result.push('\n');
-                    }
-                    continue;
-                } else {
-                    in_block_comment = true;
-                    let before = &line[..idx];
-                    if !before.trim().is_empty() {
-                        result.push_str(before.trim_end());
-                        result.push('\n');
-                    }
-                    continue;
-                }
-            }
-        }
+/// Hold the repository lock for the rest of the run, waiting out other runs
+///
+/// Best effort: a lock that cannot be created (no writable temp directory) is
+/// not worth failing the pack for.
+fn lock_repo(repo_path: &std::path::Path) -> Option<CacheLock> {
+    match CacheLock::try_repo(repo_path) {
+        Ok(Some(lock)) => Some(lock),
+        Ok(None) => {
+            status!(
+                "{} Waiting for another infiniloom run on {}...",
+                icon(Icon::Time),
+                repo_path.display()
+            );
+            CacheLock::repo(repo_path).ok()
+        },
+        Err(_) => None,
+    }
+}
 
-        // Handle line comments (simple approach - may not handle strings perfectly)
-        if !line_comment.is_empty() && trimmed.starts_with(line_comment) {
-            continue;
-        }
+/// Parse a size like `2GB`, `500M` or `1024`; units are powers of 1024
+fn parse_byte_size(value: &str) -> Result<u64, String> {
+    let value = value.trim();
+    let split = value
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(value.len());
+    let (number, unit) = value.split_at(split);
+    let number: f64 = number
+        .parse()
+        .map_err(|_| format!("expected a size like 2GB, got '{}'", value))?;
+    let scale: u64 = match unit.trim().to_ascii_uppercase().as_str() {
+        "" | "B" => 1,
+        "K" | "KB" | "KIB" => 1 << 10,
+        "M" | "MB" | "MIB" => 1 << 20,
+        "G" | "GB" | "GIB" => 1 << 30,
+        "T" | "TB" | "TIB" => 1 << 40,
+        _ => return Err(format!("unknown size unit '{}'", unit)),
+    };
+    Ok((number * scale as f64) as u64)
+}
 
-        // Try to remove trailing line comments
-        if !line_comment.is_empty() {
-            if let Some(idx) = line.find(line_comment) {
-                // Simple heuristic: skip if inside a string
-                let before = &line[..idx];
-                let quote_count = before.matches('"').count() + before.matches('\'').count();
-                if quote_count % 2 == 0 {
-                    let cleaned = before.trim_end();
-                    if !cleaned.is_empty() {
-                        result.push_str(cleaned);
-                        result.push('\n');
-                    }
-                    continue;
-                }
-            }
-        }
+/// Parse a `--path-map` value of the form `OLD=NEW`
+fn parse_path_map(value: &str) -> Result<(String, String), String> {
+    match value.split_once('=') {
+        Some((old, new)) if !old.is_empty() => Ok((old.to_owned(), new.to_owned())),
+        _ => Err(format!("expected OLD=NEW, got '{}'", value)),
+    }
+}
+
+/// Parse a `--for-model` value of the form `MODEL` or `MODEL=TOKENS`
+fn parse_model_budget(value: &str) -> Result<(Model, Option<u32>), String> {
+    let (name, budget) = match value.split_once('=') {
+        Some((name, budget)) => {
+            let budget = budget
+                .parse()
+                .map_err(|_| format!("expected a token count after '=', got '{}'", budget))?;
+            (name, Some(budget))
+        },
+        None => (value, None),
+    };
+    let model = Model::from_str(name, true)?;
+    Ok((model, budget))
+}
+
+/// Pair each output path with the format to render into it
+///
+/// A single output (or stdout) uses `--format`; with several outputs each
+/// format is inferred from the file extension, falling back to `--format`.
+fn output_targets(
+    outputs: &[PathBuf],
+    format: OutputFormat,
+) -> Vec<(Option<PathBuf>, OutputFormat)> {
+    match outputs {
+        [] => vec![(None, format)],
+        [single] => vec![(Some(single.clone()), format)],
+        _ => outputs
+            .iter()
+            .map(|path| {
+                let inferred = path
+                    .extension()
+                    .and_then(|ext| ext.to_str())
+                    .and_then(OutputFormat::from_extension)
+                    .unwrap_or(format);
+                (Some(path.clone()), inferred)
+            })
+            .collect(),
+    }
+}
+
+/// Drop Infiniloom's caches and manifests, the files this run writes
+/// (`own_files`), and anything that looks like an earlier pack output
+///
+/// Earlier outputs are recognized by content and reported, since a stray
+/// `context.xml` left in the tree would otherwise double the next pack.
+fn skip_own_artifacts(repo: &mut infiniloom_engine::Repository, own_files: &[PathBuf]) {
+    use infiniloom_engine::default_ignores::{
+        looks_like_pack_output, matches_any, ARTIFACT_IGNORES,
+    };
+
+    // Outputs may not exist yet, so resolve their directory instead
+    let resolve = |path: &PathBuf| -> Option<PathBuf> {
+        let absolute = std::env::current_dir().ok()?.join(path);
+        let dir = absolute.parent()?.canonicalize().ok()?;
+        Some(dir.join(absolute.file_name()?))
+    };
+    let own_files: Vec<PathBuf> = own_files.iter().filter_map(resolve).collect();
 
-        result.push_str(line);
-        result.push('\n');
+    let before = repo.files.len();
+    let mut previous_outputs = Vec::new();
+    repo.files.retain(|f| {
+        if matches_any(&f.relative_path, ARTIFACT_IGNORES)
+            || own_files.contains(&repo.path.join(&f.relative_path))
+        {
+            return false;
+        }
+        if f.content.as_deref().is_some_and(looks_like_pack_output) {
+            previous_outputs.push(f.relative_path.clone());
+            return false;
+        }
+        true
+    });
+    if repo.files.len() < before && repo.metadata.directory_structure.is_some() {
+        repo.metadata.directory_structure =
+            Some(scanner::generate_directory_structure(&repo.files));
     }
 
-    result
+    for path in previous_outputs {
+        status!(
+            "{} Skipping {}: looks like an earlier pack output (use --include-artifacts to keep it)",
+            icon(Icon::Warn).yellow(),
+            path
+        );
+    }
 }
 
 /// Estimate token count for text using model-specific estimation
@@ -1427,8 +4784,87 @@ fn estimate_tokens(text: &str, model: TokenizerModel) -> usize {
     (text.len() as f64 / char_ratio) as usize
 }
 
+/// Trim file contents until `output`, rendered from `repo` by `render`, is
+/// within `max_tokens`, returning the new output and the last budget report
+///
+/// `render` is given the budget report's section to place in the output, and
+/// fails once the render is cancelled. Output already within the limit is
+/// returned as it is, without a report.
+fn fit_to_max_tokens(
+    repo: &infiniloom_engine::Repository,
+    output: String,
+    max_tokens: u32,
+    model: TokenizerModel,
+    locale: Locale,
+    render: impl Fn(&infiniloom_engine::Repository, Option<Section>) -> Result<String, Cancelled>,
+) -> Result<(String, Option<BudgetReport>), Cancelled> {
+    let current_tokens = estimate_tokens(&output, model);
+    if current_tokens <= max_tokens as usize {
+        return Ok((output, None));
+    }
+
+    // What the output costs without any file contents
+    let mut bare = repo.clone();
+    for file in &mut bare.files {
+        file.content = None;
+    }
+    let fixed = estimate_tokens(&render(&bare, None)?, model);
+    let mut budget = content_budget(repo, current_tokens, fixed, max_tokens, model);
+    // Headers of the kept files and the budget report are not in `fixed`,
+    // so refine the budget from the result a few times
+    let mut output = output;
+    let mut last_report = None;
+    for _ in 0..3 {
+        let mut trimmed = repo.clone();
+        let report = BudgetAllocator::new(budget, model).allocate(&mut trimmed);
+        output = render(&trimmed, report.section(locale))?;
+        last_report = Some(report);
+        let tokens = estimate_tokens(&output, model);
+        if tokens <= max_tokens as usize {
+            break;
+        }
+        // Aim a little low, as cuts land on whole symbols
+        let over = tokens.saturating_sub(fixed).max(1) as f64;
+        let room = max_tokens.saturating_sub(fixed as u32) as f64 * 0.95;
+        budget = (budget as f64 * room / over) as u32;
+    }
+    // Sections other than file contents can still be too large
+    if estimate_tokens(&output, model) > max_tokens as usize {
+        output = truncate_to_tokens(&output, max_tokens as usize, model, locale);
+    }
+    Ok((output, last_report))
+}
+
+/// Budget in file tokens that brings an output of `current` tokens down to
+/// `max_tokens`, when `fixed` of them are not file contents
+///
+/// Rendered contents shrink in proportion to the contents themselves, line
+/// numbers and all, so the budget is the same share of their token counts.
+fn content_budget(
+    repo: &infiniloom_engine::Repository,
+    current: usize,
+    fixed: usize,
+    max_tokens: u32,
+    model: TokenizerModel,
+) -> u32 {
+    let rendered = current.saturating_sub(fixed).max(1);
+    let share = (max_tokens as usize).saturating_sub(fixed) as f64 / rendered as f64;
+    let tokens: u64 = repo
+        .files
+        .iter()
+        .filter(|f| f.content.is_some())
+        .map(|f| f.token_count.get(model) as u64)
+        .sum();
+    (tokens as f64 * share.min(1.0)) as u32
+}
+
 /// Truncate text to fit within token limit
-fn truncate_to_tokens(text: &str, max_tokens: usize, model: TokenizerModel) -> String {
+fn truncate_to_tokens(
+    text: &str,
+    max_tokens: usize,
+    model: TokenizerModel,
+    locale: Locale,
+) -> String {
     let current = estimate_tokens(text, model);
     if current <= max_tokens {
         return text.to_owned();
@@ -1456,117 +4892,17 @@ fn truncate_to_tokens(text: &str, max_tokens: usize, model: TokenizerModel) -> S
     }
 
     let mut result = truncated[..best_end].to_string();
-    result.push_str("\n\n<!-- Output truncated to fit token limit -->\n");
+    result.push_str(&format!("\n\n<!-- {} -->\n", locale.strings().output_truncated));
     result
 }
 
 /// Fast heuristic-based file ranking (no symbol extraction needed)
 /// This is the default mode - much faster than PageRank-based ranking
 fn rank_files_fast(repo: &mut infiniloom_engine::Repository) {
-    repo.files.sort_by_key(|f| {
-        let path = &f.relative_path;
-        let mut score: i32 = 1000; // Base score
-
-        // === CRITICAL: Entry points (highest priority) ===
-        let entry_point_patterns = [
-            "main.rs",
-            "main.go",
-            "main.py",
-            "main.ts",
-            "main.js",
-            "main.c",
-            "main.cpp",
-            "index.ts",
-            "index.js",
-            "index.tsx",
-            "index.jsx",
-            "index.py",
-            "app.py",
-            "app.ts",
-            "app.js",
-            "app.tsx",
-            "app.jsx",
-            "app.go",
-            "server.py",
-            "server.ts",
-            "server.js",
-            "server.go",
-            "mod.rs",
-            "lib.rs",
-            "lib.py",
-            "__main__.py",
-            "__init__.py",
-        ];
-        if entry_point_patterns.iter().any(|p| path.ends_with(p)) {
-            score -= 5000;
-        }
-
-        // === HIGH: Config and manifest files ===
-        let config_patterns = [
-            "Cargo.toml",
-            "package.json",
-            "pyproject.toml",
-            "go.mod",
-            "pom.xml",
-            "build.gradle",
-            "Gemfile",
-            "requirements.txt",
-            "setup.py",
-            "setup.cfg",
-            "tsconfig.json",
-            "webpack.config",
-            "vite.config",
-            "next.config",
-            "Makefile",
-            "CMakeLists.txt",
-            "Dockerfile",
-            "docker-compose",
-            ".env.example",
-        ];
-        if config_patterns.iter().any(|p| path.contains(p)) {
-            score -= 3000;
-        }
-
-        // === MEDIUM-HIGH: Source directories ===
-        if path.starts_with("src/") || path.starts_with("lib/") || path.starts_with("pkg/") {
-            score -= 1000;
-        }
-
-        // === MEDIUM: API/Routes/Models ===
-        let important_patterns =
-            ["api/", "routes/", "models/", "controllers/", "services/", "handlers/"];
-        if important_patterns.iter().any(|p| path.contains(p)) {
-            score -= 500;
-        }
-
-        // === LOW: Tests (if included) ===
-        let test_patterns = ["/test", "_test.", ".test.", ".spec.", "tests/", "__tests__/"];
-        if test_patterns.iter().any(|p| path.contains(p)) {
-            score += 2000;
-        }
-
-        // === LOWER: Examples, benchmarks, scripts ===
-        let auxiliary_patterns =
-            ["examples/", "example/", "benchmarks/", "bench/", "scripts/", "tools/"];
-        if auxiliary_patterns.iter().any(|p| path.contains(p)) {
-            score += 1500;
-        }
-
-        // === LOWEST: Vendored, generated, docs ===
-        let low_priority_patterns = ["vendor/", "third_party/", "generated/", "docs/", "doc/"];
-        if low_priority_patterns.iter().any(|p| path.contains(p)) {
-            score += 3000;
-        }
-
-        // Prefer shallower paths (fewer slashes = more important)
-        score += (path.matches('/').count() as i32) * 50;
-
-        // Prefer shorter filenames
-        if let Some(name) = path.rsplit('/').next() {
-            score += (name.len() as i32) / 5;
-        }
-
-        score
+    let entry_points = entry_point_paths(repo);
+    repo.files.sort_by_cached_key(|f| {
+        let entry_point = entry_points.contains(&f.relative_path);
+        (!f.pinned, fast_rank_score(&f.relative_path, entry_point))
     });
 
     // Update importance field based on new order
@@ -1576,108 +4912,332 @@ fn rank_files_fast(repo: &mut infiniloom_engine::Repository) {
     }
 }
 
-/// Loaded configuration from file
-#[derive(Default)]
-struct LoadedConfig {
-    /// Additional exclude patterns from config
-    exclude_patterns: Vec<String>,
-    /// Additional include patterns from config
-    #[allow(dead_code)]
-    include_patterns: Vec<String>,
+/// Record the top factors behind each file's rank for `--explain-ranking`
+fn annotate_rank_factors(repo: &mut infiniloom_engine::Repository, full_mode: bool) {
+    let entry_points = entry_point_paths(repo);
+    for file in &mut repo.files {
+        file.rank_factors = if full_mode {
+            infiniloom_engine::file_importance(file).factors()
+        } else {
+            fast_rank_factors(&file.relative_path, entry_points.contains(&file.relative_path))
+        };
+        file.rank_factors.truncate(3);
+    }
 }
 
-/// Load config file (.infiniloom.yaml, .infiniloom.toml, .infiniloom.json)
-fn load_config_file(config_path: Option<&PathBuf>, repo_path: &std::path::Path) -> LoadedConfig {
-    let mut config = LoadedConfig::default();
+/// Paths of the entry points found in code or declared by manifests, which
+/// fast ranking treats like entry points matched by name
+fn entry_point_paths(repo: &infiniloom_engine::Repository) -> HashSet<String> {
+    infiniloom_engine::detect_entry_points(repo)
+        .into_iter()
+        .map(|e| e.path)
+        .collect()
+}
 
-    // Try to load specified config file
-    if let Some(path) = config_path {
-        if path.exists() {
-            if let Ok(content) = std::fs::read_to_string(path) {
-                parse_config_content(&content, path, &mut config);
-            }
-        }
-        return config;
+/// Fast ranking adjustments for a path, largest first, signed so that a
+/// positive number moves the file up
+fn fast_rank_factors(path: &str, entry_point: bool) -> Vec<String> {
+    let mut components: Vec<_> = fast_rank_components(path, entry_point)
+        .into_iter()
+        .filter(|(reason, _)| *reason != "base score")
+        .collect();
+    components.sort_by_key(|(_, delta)| std::cmp::Reverse(delta.abs()));
+    components
+        .into_iter()
+        .map(|(reason, delta)| format!("{} ({:+})", reason, -delta))
+        .collect()
+}
+
+/// Fast ranking score for a path (lower ranks first)
+fn fast_rank_score(path: &str, entry_point: bool) -> i32 {
+    fast_rank_components(path, entry_point)
+        .iter()
+        .map(|(_, delta)| delta)
+        .sum()
+}
+
+/// Score adjustments applied by fast ranking, as (reason, delta); `entry_point`
+/// marks files detected as entry points by their code or a manifest
+fn fast_rank_components(path: &str, entry_point: bool) -> Vec<(&'static str, i32)> {
+    let mut components = vec![("base score", 1000)];
+
+    // === CRITICAL: Entry points (highest priority) ===
+    let entry_point_patterns = [
+        "main.rs",
+        "main.go",
+        "main.py",
+        "main.ts",
+        "main.js",
+        "main.c",
+        "main.cpp",
+        "index.ts",
+        "index.js",
+        "index.tsx",
+        "index.jsx",
+        "index.py",
+        "app.py",
+        "app.ts",
+        "app.js",
+        "app.tsx",
+        "app.jsx",
+        "app.go",
+        "server.py",
+        "server.ts",
+        "server.js",
+        "server.go",
+        "mod.rs",
+        "lib.rs",
+        "lib.py",
+        "__main__.py",
+        "__init__.py",
+    ];
+    if entry_point || entry_point_patterns.iter().any(|p| path.ends_with(p)) {
+        components.push(("entry point", -5000));
     }
 
-    // Look for default config files
-    let config_files =
-        [".infiniloom.yaml", ".infiniloom.yml", ".infiniloom.toml", ".infiniloom.json"];
-    for name in config_files {
-        let path = repo_path.join(name);
-        if path.exists() {
-            if let Ok(content) = std::fs::read_to_string(&path) {
-                parse_config_content(&content, &path, &mut config);
-                break;
-            }
-        }
+    // === HIGH: Config and manifest files ===
+    let config_patterns = [
+        "Cargo.toml",
+        "package.json",
+        "pyproject.toml",
+        "go.mod",
+        "pom.xml",
+        "build.gradle",
+        "Gemfile",
+        "requirements.txt",
+        "setup.py",
+        "setup.cfg",
+        "tsconfig.json",
+        "webpack.config",
+        "vite.config",
+        "next.config",
+        "Makefile",
+        "CMakeLists.txt",
+        "Dockerfile",
+        "docker-compose",
+        ".env.example",
+    ];
+    if config_patterns.iter().any(|p| path.contains(p)) {
+        components.push(("config or manifest file", -3000));
     }
 
-    // Also load .infiniloomignore patterns
-    let ignore_path = repo_path.join(".infiniloomignore");
-    if ignore_path.exists() {
-        if let Ok(content) = std::fs::read_to_string(&ignore_path) {
-            for line in content.lines() {
-                let line = line.trim();
-                if !line.is_empty() && !line.starts_with('#') {
-                    config.exclude_patterns.push(line.to_owned());
-                }
-            }
+    // === MEDIUM-HIGH: Source directories ===
+    if path.starts_with("src/") || path.starts_with("lib/") || path.starts_with("pkg/") {
+        components.push(("source directory", -1000));
+    }
+
+    // === MEDIUM: API/Routes/Models ===
+    let important_patterns =
+        ["api/", "routes/", "models/", "controllers/", "services/", "handlers/"];
+    if important_patterns.iter().any(|p| path.contains(p)) {
+        components.push(("API/routes/models directory", -500));
+    }
+
+    // === LOW: Tests (if included) ===
+    let test_patterns = ["/test", "_test.", ".test.", ".spec.", "tests/", "__tests__/"];
+    if test_patterns.iter().any(|p| path.contains(p)) {
+        components.push(("test file", 2000));
+    }
+
+    // === LOWER: Examples, benchmarks, scripts ===
+    let auxiliary_patterns =
+        ["examples/", "example/", "benchmarks/", "bench/", "scripts/", "tools/"];
+    if auxiliary_patterns.iter().any(|p| path.contains(p)) {
+        components.push(("examples, benchmarks or scripts", 1500));
+    }
+
+    // === LOWEST: Vendored, generated, docs ===
+    let low_priority_patterns = ["vendor/", "third_party/", "generated/", "docs/", "doc/"];
+    if low_priority_patterns.iter().any(|p| path.contains(p)) {
+        components.push(("vendored, generated or docs", 3000));
+    }
+
+    // Prefer shallower paths (fewer slashes = more important)
+    let depth = path.matches('/').count() as i32;
+    if depth > 0 {
+        components.push(("path depth", depth * 50));
+    }
+
+    // Prefer shorter filenames
+    if let Some(name) = path.rsplit('/').next() {
+        if name.len() >= 5 {
+            components.push(("filename length", (name.len() as i32) / 5));
         }
     }
 
-    config
+    components
 }
 
-/// Parse config content based on file extension
-fn parse_config_content(content: &str, path: &std::path::Path, config: &mut LoadedConfig) {
-    let ext = path.extension().and_then(|e| e.to_str()).unwrap_or("");
+/// The `symbols` section of the repository's config, or defaults if it has none
+fn configured_symbols(repo_path: &std::path::Path) -> infiniloom_engine::config::SymbolConfig {
+    Config::load(repo_path)
+        .map(|config| config.symbols)
+        .unwrap_or_default()
+}
 
-    match ext {
-        "yaml" | "yml" => {
-            // Simple YAML parsing for ignore patterns
-            for line in content.lines() {
-                let line = line.trim();
-                if line.starts_with("- ") && !line.contains(':') {
-                    // This is likely an array item in ignore section
-                    let pattern = line.trim_start_matches("- ").trim();
-                    if !pattern.is_empty() {
-                        config.exclude_patterns.push(pattern.to_owned());
-                    }
-                }
-            }
-        },
-        "toml" => {
-            // Simple TOML parsing
-            let mut in_ignore_section = false;
-            for line in content.lines() {
-                let line = line.trim();
-                if line.starts_with("[ignore]") || line.starts_with("[exclude]") {
-                    in_ignore_section = true;
-                } else if line.starts_with('[') {
-                    in_ignore_section = false;
-                } else if in_ignore_section && line.starts_with('"') {
-                    let pattern = line.trim_matches(|c| c == '"' || c == ',' || c == ' ');
-                    if !pattern.is_empty() {
-                        config.exclude_patterns.push(pattern.to_owned());
-                    }
-                }
-            }
-        },
-        "json" => {
-            // Simple JSON parsing for ignore array
-            if let Ok(value) = serde_json::from_str::<serde_json::Value>(content) {
-                if let Some(ignore) = value.get("ignore").or_else(|| value.get("exclude")) {
-                    if let Some(arr) = ignore.as_array() {
-                        for item in arr {
-                            if let Some(s) = item.as_str() {
-                                config.exclude_patterns.push(s.to_owned());
-                            }
-                        }
-                    }
-                }
-            }
-        },
-        _ => {},
+/// The `security` section of the repository's config, or defaults if it has none
+fn configured_security(repo_path: &std::path::Path) -> SecurityConfig {
+    Config::load(repo_path)
+        .map(|config| config.security)
+        .unwrap_or_default()
+}
+
+/// The `scan` section of the config discovered for the repository, failing
+/// on a config file that does not parse
+fn configured_scan(repo_path: &std::path::Path) -> Result<infiniloom_engine::config::ScanConfig> {
+    Ok(load_config_file(None, repo_path)?.config.scan)
+}
+
+/// Scan a repository as its config file says, like `pack` and the bindings
+///
+/// `adjust` applies the command's flags to the discovered `scan` section,
+/// which then decides the walk and the files kept (see [`scan_selected`]).
+fn scan_configured(
+    repo_path: &std::path::Path,
+    adjust: impl FnOnce(&mut infiniloom_engine::config::ScanConfig),
+) -> Result<infiniloom_engine::Repository> {
+    let mut scan = configured_scan(repo_path)?;
+    adjust(&mut scan);
+    scan_selected(repo_path, &scan, |_| {})
+}
+
+/// Scan a repository with contents and symbols as `scan` says: its hidden
+/// allowlist, `.gitignore` and size limit decide the walk, then its include
+/// and exclude patterns, languages and default ignores the files kept
+///
+/// `walk` changes what the command needs from the walk, such as skipping
+/// symbols.
+fn scan_selected(
+    repo_path: &std::path::Path,
+    scan: &infiniloom_engine::config::ScanConfig,
+    walk: impl FnOnce(&mut scanner::ScanConfig),
+) -> Result<infiniloom_engine::Repository> {
+    let mut config = scanner::ScanConfig {
+        read_contents: true,
+        cancel: interrupt::token().clone(),
+        ..scanner::ScanConfig::from_config(scan)
+    };
+    walk(&mut config);
+    let mut repo =
+        scanner::scan_repository(repo_path, config).context("Failed to scan repository")?;
+    if scan.select_files(&mut repo) > 0 {
+        repo.metadata.directory_structure =
+            Some(scanner::generate_directory_structure(&repo.files));
+    }
+    Ok(repo)
+}
+
+/// Apply `--only-lang`, which replaces the config's `scan.only_languages`,
+/// and `--exclude-lang`, which adds to its `scan.exclude_languages`
+fn apply_language_flags(
+    scan: &mut infiniloom_engine::config::ScanConfig,
+    only: Vec<String>,
+    exclude: Vec<String>,
+) {
+    if !only.is_empty() {
+        scan.only_languages = only;
+    }
+    scan.exclude_languages.extend(exclude);
+    for name in scan.language_filter().unknown() {
+        status!("Warning: unknown language '{}'", name);
+    }
+}
+
+/// Languages to keep, `scan` with the `--only-lang` and `--exclude-lang` flags
+fn language_filter(
+    mut scan: infiniloom_engine::config::ScanConfig,
+    only: Vec<String>,
+    exclude: Vec<String>,
+) -> LanguageFilter {
+    apply_language_flags(&mut scan, only, exclude);
+    scan.language_filter()
+}
+
+/// Configuration a command runs with
+struct LoadedConfig {
+    config: Config,
+    /// Directory of the config file, where relative paths in it resolve
+    dir: PathBuf,
+    /// The config file when it was found in the repository rather than
+    /// passed with `--config`
+    discovered: Option<PathBuf>,
+}
+
+/// Load `--config`, or else the config file that applies to `repo_path`
+///
+/// Remote repositories get the global config and `INFINILOOM_*` variables
+/// only, as their files are not there yet. A config that does not parse is an
+/// error rather than silently ignored.
+fn load_config_file(
+    config_path: Option<&std::path::Path>,
+    repo_path: &std::path::Path,
+) -> Result<LoadedConfig> {
+    let file = match config_path {
+        Some(file) => Some(file.to_path_buf()),
+        None if RemoteRepo::is_remote_url(&repo_path.to_string_lossy()) => None,
+        None => Config::find_file(repo_path),
+    };
+    let config = match &file {
+        Some(file) => Config::load_file(file)
+            .with_context(|| format!("Failed to load config file {}", file.display()))?,
+        None => Config::load(repo_path).context("Failed to load config")?,
+    };
+    let dir = match file.as_deref().and_then(std::path::Path::parent) {
+        Some(dir) if !dir.as_os_str().is_empty() => dir.to_path_buf(),
+        Some(_) => PathBuf::from("."),
+        None => repo_path.to_path_buf(),
+    };
+    let discovered = if config_path.is_none() { file } else { None };
+    Ok(LoadedConfig { config, dir, discovered })
+}
+
+/// `flag` if given, else the config's `value` for `key`, parsed like the flag
+fn flag_or_config<T: ValueEnum>(flag: Option<T>, key: &str, value: &str) -> Result<T> {
+    match flag {
+        Some(flag) => Ok(flag),
+        None => T::from_str(value, true)
+            .map_err(|e| anyhow::anyhow!("Invalid {} in config: {}", key, e)),
     }
 }
+
+/// The external formatter of an `exec:` format in the `output` section
+///
+/// A formatter runs arbitrary commands, so it is only taken from the global
+/// config, `INFINILOOM_*` variables or a `--config` file, never from a config
+/// file that came with the repository being packed.
+fn configured_formatter(loaded: &LoadedConfig) -> Result<Option<ExecFormatter>> {
+    let output = &loaded.config.output;
+    let Some(formatter) = ExecFormatter::from_spec(&output.format) else {
+        return Ok(None);
+    };
+    let mut config_dir = loaded.dir.clone();
+    if let Some(file) = &loaded.discovered {
+        // The user's settings apply on top of the repository's, so the
+        // formatter is theirs if it survives without the repository's file
+        let user = Config::load_user().context("Failed to load config")?;
+        if user.output.format != output.format {
+            anyhow::bail!(
+                "Refusing to run external formatter `{}` from {}: formatters are only \
+                 taken from the global config or --config (pass --config {} to trust it)",
+                output.format.trim(),
+                file.display(),
+                file.display()
+            );
+        }
+        config_dir = match Config::global_file() {
+            Some(global) if global.exists() => global.parent().unwrap_or(&global).to_path_buf(),
+            _ => PathBuf::from("."),
+        };
+    }
+    let timeout = match &output.formatter_timeout {
+        Some(timeout) => humantime::parse_duration(timeout)
+            .with_context(|| format!("Invalid output.formatter_timeout in config: {}", timeout))?,
+        None => DEFAULT_EXEC_TIMEOUT,
+    };
+    Ok(Some(
+        formatter
+            .context("Invalid formatter in config")?
+            .with_working_dir(config_dir)
+            .with_timeout(timeout),
+    ))
+}
@@ -0,0 +1,210 @@
+//! Progress reporting for `pack`
+//!
+//! Interactive runs get an indicatif spinner. With `--progress json` the same
+//! updates are written to stderr as newline-delimited JSON events instead, so
+//! GUI wrappers and services can drive their own progress bars:
+//!
+//! ```text
+//! {"event":"phase","phase":"scan","message":"Scanning repository...","elapsed_ms":0}
+//! {"event":"progress","phase":"scan","files_done":40,"files_total":80,"bytes_done":51200,"bytes_total":98304,"elapsed_ms":120,"eta_ms":110}
+//! {"event":"done","files":80,"elapsed_ms":310}
+//! ```
+
+use clap::ValueEnum;
+use indicatif::{ProgressBar, ProgressStyle};
+use infiniloom_engine::scanner::ScanProgress;
+use serde_json::json;
+use std::io::Write;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Minimum time between file progress updates
+const UPDATE_INTERVAL: Duration = Duration::from_millis(100);
+
+/// How progress is reported
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub(crate) enum ProgressFormat {
+    /// Spinner on the terminal (shown with --verbose)
+    Bar,
+    /// Newline-delimited JSON events on stderr
+    Json,
+}
+
+enum Sink {
+    Hidden,
+    Bar(ProgressBar),
+    Json,
+}
+
+/// Reports pipeline phases and scan progress to the chosen sink
+pub(crate) struct Progress {
+    sink: Sink,
+    start: Instant,
+    phase: Mutex<&'static str>,
+    last_update: Mutex<Option<Instant>>,
+}
+
+impl Progress {
+    /// Pick the sink: JSON when requested, otherwise a spinner if verbose
+    /// and not `--quiet`
+    pub(crate) fn new(format: Option<ProgressFormat>, verbose: bool) -> Self {
+        let sink = match format {
+            Some(ProgressFormat::Json) => Sink::Json,
+            _ if crate::ui::is_quiet() => Sink::Hidden,
+            Some(ProgressFormat::Bar) => Sink::Bar(spinner()),
+            None if verbose => Sink::Bar(spinner()),
+            None => Sink::Hidden,
+        };
+        Self {
+            sink,
+            start: Instant::now(),
+            phase: Mutex::new("start"),
+            last_update: Mutex::new(None),
+        }
+    }
+
+    /// Enter a new pipeline phase
+    pub(crate) fn phase(&self, phase: &'static str, message: impl Into<String>) {
+        *self.phase.lock().unwrap() = phase;
+        let message = message.into();
+        match &self.sink {
+            Sink::Hidden => {},
+            Sink::Bar(pb) => pb.set_message(message),
+            Sink::Json => self.emit(json!({
+                "event": "phase",
+                "phase": phase,
+                "message": message,
+                "elapsed_ms": self.elapsed_ms(),
+            })),
+        }
+    }
+
+    /// Report a status update within the current phase
+    pub(crate) fn message(&self, message: impl Into<String>) {
+        let message = message.into();
+        match &self.sink {
+            Sink::Hidden => {},
+            Sink::Bar(pb) => pb.set_message(message),
+            Sink::Json => self.emit(json!({
+                "event": "message",
+                "phase": *self.phase.lock().unwrap(),
+                "message": message,
+                "elapsed_ms": self.elapsed_ms(),
+            })),
+        }
+    }
+
+    /// Report how far file processing has got; updates are rate limited
+    /// except for the final one
+    pub(crate) fn scan(&self, progress: ScanProgress) {
+        if matches!(self.sink, Sink::Hidden) {
+            return;
+        }
+        let finished = progress.files_done == progress.files_total;
+        {
+            let mut last = self.last_update.lock().unwrap();
+            let now = Instant::now();
+            if !finished && last.is_some_and(|t| now.duration_since(t) < UPDATE_INTERVAL) {
+                return;
+            }
+            *last = Some(now);
+        }
+
+        match &self.sink {
+            Sink::Hidden => {},
+            Sink::Bar(pb) => pb.set_message(format!(
+                "Scanning repository... {}/{} files",
+                progress.files_done, progress.files_total
+            )),
+            Sink::Json => {
+                let phase = *self.phase.lock().unwrap();
+                let phase_elapsed = progress.started.elapsed();
+                self.emit(json!({
+                    "event": "progress",
+                    "phase": phase,
+                    "files_done": progress.files_done,
+                    "files_total": progress.files_total,
+                    "bytes_done": progress.bytes_done,
+                    "bytes_total": progress.bytes_total,
+                    "elapsed_ms": self.elapsed_ms(),
+                    "eta_ms": eta(&progress, phase_elapsed).map(|d| d.as_millis() as u64),
+                }));
+            },
+        }
+    }
+
+    /// Clear the spinner or emit the final event
+    pub(crate) fn finish(&self, files: usize) {
+        match &self.sink {
+            Sink::Hidden => {},
+            Sink::Bar(pb) => pb.finish_and_clear(),
+            Sink::Json => self.emit(json!({
+                "event": "done",
+                "files": files,
+                "elapsed_ms": self.elapsed_ms(),
+            })),
+        }
+    }
+
+    fn elapsed_ms(&self) -> u64 {
+        self.start.elapsed().as_millis() as u64
+    }
+
+    fn emit(&self, event: serde_json::Value) {
+        // One locked write per event keeps lines whole across threads
+        let mut stderr = std::io::stderr().lock();
+        writeln!(stderr, "{}", event).ok();
+    }
+}
+
+fn spinner() -> ProgressBar {
+    let pb = ProgressBar::new_spinner();
+    let style = ProgressStyle::default_spinner()
+        .template("{spinner:.green} [{elapsed_precise}] {msg}")
+        .unwrap();
+    // Braille frames by default; ASCII where colour and symbols are off
+    let style = if crate::ui::is_fancy() {
+        style
+    } else {
+        style.tick_chars("|/-\\ ")
+    };
+    pb.set_style(style);
+    pb.enable_steady_tick(Duration::from_millis(100));
+    pb
+}
+
+/// Remaining time, extrapolated from bytes processed so far
+fn eta(progress: &ScanProgress, elapsed: Duration) -> Option<Duration> {
+    if progress.files_done == progress.files_total {
+        return Some(Duration::ZERO);
+    }
+    let (done, total) = if progress.bytes_total > 0 {
+        (progress.bytes_done as f64, progress.bytes_total as f64)
+    } else {
+        (progress.files_done as f64, progress.files_total as f64)
+    };
+    if done <= 0.0 {
+        return None;
+    }
+    Some(elapsed.mul_f64((total - done).max(0.0) / done))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_eta() {
+        let progress = ScanProgress {
+            files_done: 1,
+            files_total: 4,
+            bytes_done: 100,
+            bytes_total: 400,
+            started: Instant::now(),
+        };
+        assert_eq!(eta(&progress, Duration::from_secs(2)), Some(Duration::from_secs(6)));
+
+        let progress = ScanProgress { bytes_done: 0, files_done: 0, ..progress };
+        assert_eq!(eta(&progress, Duration::from_secs(2)), None);
+    }
+}
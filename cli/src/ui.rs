@@ -0,0 +1,139 @@
+//! Terminal output policy shared by every command
+//!
+//! Set once from the global flags in `main`:
+//!
+//! - `--quiet` silences everything but the command's output: status lines,
+//!   warnings and the spinner (errors still go to stderr)
+//! - `--log-level` sets the `env_logger` filter, overriding `RUST_LOG`
+//! - colour, emoji and box-drawing characters are only used when neither
+//!   stdout nor stderr is redirected and `NO_COLOR` is unset; otherwise the
+//!   output is plain ASCII that can be logged or parsed
+//!
+//! Status text goes through [`status!`] and symbols through [`icon`].
+
+use clap::ValueEnum;
+use std::io::IsTerminal;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static QUIET: AtomicBool = AtomicBool::new(false);
+static FANCY: AtomicBool = AtomicBool::new(false);
+
+/// Print a status line to stderr unless `--quiet` is set
+macro_rules! status {
+    ($($arg:tt)*) => {
+        if !$crate::ui::is_quiet() {
+            eprintln!($($arg)*);
+        }
+    };
+}
+pub(crate) use status;
+
+/// Filter for `env_logger`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub(crate) enum LogLevel {
+    Off,
+    Error,
+    Warn,
+    Info,
+    Debug,
+    Trace,
+}
+
+impl LogLevel {
+    fn filter(self) -> log::LevelFilter {
+        match self {
+            Self::Off => log::LevelFilter::Off,
+            Self::Error => log::LevelFilter::Error,
+            Self::Warn => log::LevelFilter::Warn,
+            Self::Info => log::LevelFilter::Info,
+            Self::Debug => log::LevelFilter::Debug,
+            Self::Trace => log::LevelFilter::Trace,
+        }
+    }
+}
+
+/// Apply the policy: colour overrides, logging and the quiet flag
+pub(crate) fn init(quiet: bool, log_level: Option<LogLevel>) {
+    let no_color = std::env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty());
+    let dumb = std::env::var("TERM").is_ok_and(|term| term == "dumb");
+    let fancy =
+        !no_color && !dumb && std::io::stdout().is_terminal() && std::io::stderr().is_terminal();
+
+    QUIET.store(quiet, Ordering::Relaxed);
+    FANCY.store(fancy, Ordering::Relaxed);
+    colored::control::set_override(fancy);
+    console::set_colors_enabled(fancy);
+    console::set_colors_enabled_stderr(fancy);
+
+    // --log-level beats RUST_LOG, which beats the default
+    let default = if quiet { "off" } else { "warn" };
+    let mut logger =
+        env_logger::Builder::from_env(env_logger::Env::default().default_filter_or(default));
+    if let Some(level) = log_level {
+        logger.filter_level(level.filter());
+    }
+    logger.write_style(if fancy {
+        env_logger::WriteStyle::Auto
+    } else {
+        env_logger::WriteStyle::Never
+    });
+    logger.init();
+}
+
+/// Whether `--quiet` is set
+pub(crate) fn is_quiet() -> bool {
+    QUIET.load(Ordering::Relaxed)
+}
+
+/// Whether colour and non-ASCII symbols may be used
+pub(crate) fn is_fancy() -> bool {
+    FANCY.load(Ordering::Relaxed)
+}
+
+/// Symbols used in status lines and reports
+#[derive(Clone, Copy, Debug)]
+pub(crate) enum Icon {
+    Ok,
+    Warn,
+    Fail,
+    Info,
+    Arrow,
+    Bullet,
+    /// Horizontal rule segment
+    Rule,
+    Files,
+    Lines,
+    Tokens,
+    Time,
+    Watch,
+    Rebuild,
+}
+
+/// The symbol, or its ASCII stand-in in plain output
+pub(crate) fn icon(icon: Icon) -> &'static str {
+    let (fancy, plain) = match icon {
+        Icon::Ok => ("✓", "ok"),
+        Icon::Warn => ("⚠", "warning:"),
+        Icon::Fail => ("✗", "x"),
+        Icon::Info => ("ℹ", "i"),
+        Icon::Arrow => ("→", "->"),
+        Icon::Bullet => ("•", "-"),
+        Icon::Rule => ("━", "-"),
+        Icon::Files => ("📁", "-"),
+        Icon::Lines => ("📄", "-"),
+        Icon::Tokens => ("🔢", "-"),
+        Icon::Time => ("⏱️ ", "-"),
+        Icon::Watch => ("👀", "*"),
+        Icon::Rebuild => ("🔄", "*"),
+    };
+    if is_fancy() {
+        fancy
+    } else {
+        plain
+    }
+}
+
+/// Horizontal rule of `width` segments
+pub(crate) fn rule(width: usize) -> String {
+    icon(Icon::Rule).repeat(width)
+}
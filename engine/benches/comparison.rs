@@ -137,8 +137,8 @@ fn bench_file_traversal(c: &mut Criterion) {
     let mut group = c.benchmark_group("file_traversal");
 
     for (num_files, name) in sizes.iter() {
-        let temp = create_test_repo(*num_files, 100);
-        let path = temp.path().to_path_buf();
+        let temp_dir = create_test_repo(*num_files, 100);
+        let path = temp_dir.path().to_path_buf();
 
         group.throughput(Throughput::Elements(*num_files as u64));
         group.bench_with_input(BenchmarkId::new("walkdir", name), &path, |b, path| {
@@ -179,8 +179,8 @@ fn bench_file_traversal(c: &mut Criterion) {
 
 /// Benchmark file reading speed
 fn bench_file_reading(c: &mut Criterion) {
-    let temp = create_test_repo(30, 500);
-    let path = temp.path().to_path_buf();
+    let temp_dir = create_test_repo(30, 500);
+    let path = temp_dir.path().to_path_buf();
 
     // Collect all file paths
     let files: Vec<_> = ignore::WalkBuilder::new(&path)
@@ -226,8 +226,8 @@ fn bench_file_reading(c: &mut Criterion) {
 /// Benchmark line counting methods
 fn bench_line_counting(c: &mut Criterion) {
     // Create a large file for benchmarking
-    let temp = TempDir::new().unwrap();
-    let large_file = temp.path().join("large.rs");
+    let temp_dir = TempDir::new().unwrap();
+    let large_file = temp_dir.path().join("large.rs");
 
     let content: String = (0..10000)
         .map(|i| format!("fn function_{}() {{ /* code */ }}\n", i))
@@ -2,6 +2,9 @@
 //!
 //! Run with: cargo run --example parser_demo
 
+// Examples print their results directly
+#![allow(clippy::print_stdout)]
+
 use infiniloom_engine::parser::{Language, Parser};
 
 fn main() {
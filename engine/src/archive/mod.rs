@@ -0,0 +1,410 @@
+//! Reading repositories from archive bytes
+//!
+//! [`read_archive`] unpacks a tar, gzip-compressed tar or zip archive held in
+//! memory, so an uploaded or piped archive can be scanned without writing it
+//! to disk (see [`Repository::scan_archive`](crate::Repository::scan_archive)).
+//! The format is detected from the content.
+//!
+//! Only regular files are returned. Links, devices and entries whose paths
+//! are absolute or climb out with `..` are skipped. When every entry sits
+//! under one top-level directory, as in GitHub and `git archive --prefix`
+//! tarballs, that directory is stripped and reported as the archive root.
+//!
+//! [`write_tar`] goes the other way, for bundles meant to be shared.
+//!
+//! Parsing is left to the `tar`, `flate2` and `zip` crates, which check
+//! checksums and CRCs; this module only decides which entries to keep.
+
+use flate2::read::MultiGzDecoder;
+use std::io::{self, Cursor, Read};
+use thiserror::Error;
+
+/// Most bytes an archive may expand to, against decompression bombs
+pub const MAX_EXPANDED_SIZE: usize = 1024 * 1024 * 1024;
+
+/// Unix file type bits, for telling symlinks apart in zip archives
+const UNIX_TYPE_MASK: u32 = 0o170_000;
+const UNIX_REGULAR: u32 = 0o100_000;
+
+/// Archive formats [`read_archive`] understands
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArchiveFormat {
+    /// Uncompressed tar
+    Tar,
+    /// Gzip-compressed tar (`.tar.gz`, `.tgz`)
+    TarGz,
+    /// Zip with stored or deflated entries
+    Zip,
+}
+
+impl ArchiveFormat {
+    /// Short name for messages
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Tar => "tar",
+            Self::TarGz => "tar.gz",
+            Self::Zip => "zip",
+        }
+    }
+}
+
+/// A regular file in an archive
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArchiveEntry {
+    /// Path relative to the archive root, with `/` separators
+    pub path: String,
+    /// File content
+    pub content: Vec<u8>,
+}
+
+/// The files of an archive
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Archive {
+    /// Detected format
+    pub format: ArchiveFormat,
+    /// Top-level directory stripped from every path, if there was one
+    pub root: Option<String>,
+    /// Regular files, in archive order
+    pub entries: Vec<ArchiveEntry>,
+}
+
+/// Errors reading an archive
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum ArchiveError {
+    /// The bytes are not a tar, gzip or zip archive
+    #[error("not a tar, tar.gz or zip archive")]
+    UnknownFormat,
+    /// The archive ends in the middle of an entry
+    #[error("archive is truncated")]
+    Truncated,
+    /// The archive is damaged
+    #[error("corrupt archive: {0}")]
+    Corrupt(String),
+    /// The archive uses a feature that is not supported
+    #[error("unsupported archive: {0}")]
+    Unsupported(String),
+    /// The archive expands to more than [`MAX_EXPANDED_SIZE`]
+    #[error("archive expands to more than {} MiB", MAX_EXPANDED_SIZE / (1024 * 1024))]
+    TooLarge,
+}
+
+/// Unpack a tar, tar.gz or zip archive held in memory
+pub fn read_archive(bytes: &[u8]) -> Result<Archive, ArchiveError> {
+    let (format, mut entries) = if bytes.starts_with(&[0x1f, 0x8b]) {
+        let tar = gunzip(bytes)?;
+        if !is_tar(&tar) {
+            return Err(ArchiveError::UnknownFormat);
+        }
+        (ArchiveFormat::TarGz, read_tar(&tar)?)
+    } else if bytes.starts_with(b"PK\x03\x04") || bytes.starts_with(b"PK\x05\x06") {
+        (ArchiveFormat::Zip, read_zip(bytes)?)
+    } else if is_tar(bytes) {
+        (ArchiveFormat::Tar, read_tar(bytes)?)
+    } else {
+        return Err(ArchiveError::UnknownFormat);
+    };
+
+    let root = strip_common_root(&mut entries);
+    Ok(Archive { format, root, entries })
+}
+
+/// Pack regular files into an uncompressed tar archive
+///
+/// Paths that don't fit the 100 bytes of a tar header get a GNU long name
+/// entry, which [`read_archive`] and common tar tools understand. Times and
+/// owners are zeroed so the same files always give the same bytes.
+pub fn write_tar(entries: &[ArchiveEntry]) -> io::Result<Vec<u8>> {
+    let mut builder = tar::Builder::new(Vec::new());
+    for entry in entries {
+        let mut header = tar::Header::new_gnu();
+        header.set_mode(0o644);
+        header.set_mtime(0);
+        header.set_uid(0);
+        header.set_gid(0);
+        header.set_size(entry.content.len() as u64);
+        header.set_entry_type(tar::EntryType::Regular);
+        builder.append_data(&mut header, &entry.path, entry.content.as_slice())?;
+    }
+    builder.into_inner()
+}
+
+/// Normalize an entry path, or `None` if it must not be extracted
+fn clean_path(raw: &str) -> Option<String> {
+    let raw = raw.replace('\\', "/");
+    if raw.starts_with('/') {
+        return None;
+    }
+    let mut parts = Vec::new();
+    for part in raw.split('/') {
+        match part {
+            "" | "." => {},
+            ".." => return None,
+            part => parts.push(part),
+        }
+    }
+    (!parts.is_empty()).then(|| parts.join("/"))
+}
+
+/// Remove the top-level directory all entries share, returning it
+fn strip_common_root(entries: &mut [ArchiveEntry]) -> Option<String> {
+    let first = entries.first()?.path.split_once('/')?.0.to_owned();
+    let prefix = format!("{first}/");
+    if !entries.iter().all(|e| e.path.starts_with(&prefix)) {
+        return None;
+    }
+    for entry in entries.iter_mut() {
+        entry.path.drain(..prefix.len());
+    }
+    Some(first)
+}
+
+/// Read an I/O error from a decoder as the archive problem it stands for
+fn archive_error(error: io::Error) -> ArchiveError {
+    match error.kind() {
+        io::ErrorKind::UnexpectedEof => ArchiveError::Truncated,
+        _ => ArchiveError::Corrupt(error.to_string()),
+    }
+}
+
+/// Read `reader` to the end, if it holds no more than `limit` bytes
+fn read_limited(reader: impl Read, limit: usize) -> Result<Vec<u8>, ArchiveError> {
+    let mut content = Vec::new();
+    reader
+        .take(limit as u64 + 1)
+        .read_to_end(&mut content)
+        .map_err(archive_error)?;
+    if content.len() > limit {
+        return Err(ArchiveError::TooLarge);
+    }
+    Ok(content)
+}
+
+/// Decompress every member of a gzip stream
+fn gunzip(bytes: &[u8]) -> Result<Vec<u8>, ArchiveError> {
+    read_limited(MultiGzDecoder::new(bytes), MAX_EXPANDED_SIZE)
+}
+
+/// Whether `bytes` start with a tar header with a valid checksum
+fn is_tar(bytes: &[u8]) -> bool {
+    let mut archive = tar::Archive::new(bytes);
+    archive
+        .entries()
+        .is_ok_and(|mut entries| matches!(entries.next(), Some(Ok(_))))
+}
+
+fn read_tar(bytes: &[u8]) -> Result<Vec<ArchiveEntry>, ArchiveError> {
+    let mut archive = tar::Archive::new(bytes);
+    let mut entries = Vec::new();
+    let mut expanded = 0usize;
+    for entry in archive.entries().map_err(archive_error)? {
+        let entry = entry.map_err(archive_error)?;
+        // Directories, links, devices and pax headers carry no file
+        if !matches!(
+            entry.header().entry_type(),
+            tar::EntryType::Regular | tar::EntryType::Continuous
+        ) {
+            continue;
+        }
+        let path = String::from_utf8_lossy(&entry.path_bytes()).into_owned();
+        let size = usize::try_from(entry.size()).map_err(|_| ArchiveError::TooLarge)?;
+        expanded = expanded.checked_add(size).ok_or(ArchiveError::TooLarge)?;
+        if expanded > MAX_EXPANDED_SIZE {
+            return Err(ArchiveError::TooLarge);
+        }
+        let content = read_limited(entry, size)?;
+        if content.len() != size {
+            return Err(ArchiveError::Truncated);
+        }
+        if let Some(path) = clean_path(&path) {
+            entries.push(ArchiveEntry { path, content });
+        }
+    }
+    Ok(entries)
+}
+
+fn read_zip(bytes: &[u8]) -> Result<Vec<ArchiveEntry>, ArchiveError> {
+    let mut archive = zip::ZipArchive::new(Cursor::new(bytes)).map_err(zip_error)?;
+    let mut entries = Vec::with_capacity(archive.len());
+    let mut expanded = 0usize;
+    for index in 0..archive.len() {
+        let file = archive.by_index(index).map_err(zip_error)?;
+        // Symlinks are stored as files holding their target; some tools
+        // leave the type bits of regular files unset
+        let regular = file
+            .unix_mode()
+            .is_none_or(|mode| matches!(mode & UNIX_TYPE_MASK, 0 | UNIX_REGULAR));
+        if file.is_dir() || !regular {
+            continue;
+        }
+        let path = file.name().to_owned();
+        // The declared size can lie, so the limit applies to what comes out
+        let content = read_limited(file, MAX_EXPANDED_SIZE - expanded)?;
+        expanded += content.len();
+        if let Some(path) = clean_path(&path) {
+            entries.push(ArchiveEntry { path, content });
+        }
+    }
+    Ok(entries)
+}
+
+fn zip_error(error: zip::result::ZipError) -> ArchiveError {
+    use zip::result::ZipError;
+    match error {
+        ZipError::Io(error) => archive_error(error),
+        ZipError::UnsupportedArchive(what) => ArchiveError::Unsupported(what.to_owned()),
+        error => ArchiveError::Corrupt(error.to_string()),
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::str_to_string)]
+mod tests {
+    use super::*;
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+    use std::io::Write;
+
+    const TAR_BLOCK: usize = 512;
+
+    /// A ustar archive of `(path, content)` regular files
+    fn tar(files: &[(&str, &str)]) -> Vec<u8> {
+        let mut out = Vec::new();
+        for (path, content) in files {
+            let mut header = [0u8; TAR_BLOCK];
+            header[..path.len()].copy_from_slice(path.as_bytes());
+            header[100..107].copy_from_slice(b"0000644");
+            header[124..135].copy_from_slice(format!("{:011o}", content.len()).as_bytes());
+            header[156] = b'0';
+            header[257..263].copy_from_slice(b"ustar\0");
+            header[148..156].fill(b' ');
+            let sum: u32 = header.iter().map(|&b| u32::from(b)).sum();
+            header[148..155].copy_from_slice(format!("{sum:06o}\0").as_bytes());
+            out.extend_from_slice(&header);
+            out.extend_from_slice(content.as_bytes());
+            out.resize(out.len().div_ceil(TAR_BLOCK) * TAR_BLOCK, 0);
+        }
+        out.resize(out.len() + 2 * TAR_BLOCK, 0);
+        out
+    }
+
+    #[test]
+    fn test_read_tar() {
+        let bytes = tar(&[
+            ("repo-main/src/lib.rs", "pub fn run() {}\n"),
+            ("repo-main/README.md", "# Repo\n"),
+            ("repo-main/../escape.txt", "no"),
+        ]);
+        let archive = read_archive(&bytes).unwrap();
+        assert_eq!(archive.format, ArchiveFormat::Tar);
+        assert_eq!(archive.root.as_deref(), Some("repo-main"));
+        let paths: Vec<&str> = archive.entries.iter().map(|e| e.path.as_str()).collect();
+        assert_eq!(paths, vec!["src/lib.rs", "README.md"]);
+        assert_eq!(archive.entries[0].content, b"pub fn run() {}\n");
+
+        let flat = read_archive(&tar(&[("a.py", "x = 1\n"), ("b/c.py", "")])).unwrap();
+        assert_eq!(flat.root, None);
+        assert_eq!(flat.entries[1].path, "b/c.py");
+    }
+
+    #[test]
+    fn test_write_tar_round_trip() {
+        let long = format!("{}/notes.md", "nested".repeat(20));
+        let entries = vec![
+            ArchiveEntry { path: "bundle.json".to_string(), content: b"{}".to_vec() },
+            ArchiveEntry { path: long.clone(), content: vec![b'x'; 700] },
+        ];
+        let bytes = write_tar(&entries).unwrap();
+        assert_eq!(bytes.len() % TAR_BLOCK, 0);
+        assert_eq!(bytes, write_tar(&entries).unwrap());
+
+        let archive = read_archive(&bytes).unwrap();
+        assert_eq!(archive.format, ArchiveFormat::Tar);
+        assert_eq!(archive.entries, entries);
+        assert_eq!(archive.entries[1].path, long);
+    }
+
+    #[test]
+    fn test_read_tar_gz() {
+        // demo/ with a.txt ("hello\n") and b.rs ("fn main() {}\n"), as ustar
+        let bytes = include_bytes!("testdata/demo.tar.gz");
+        let archive = read_archive(bytes).unwrap();
+        assert_eq!(archive.format, ArchiveFormat::TarGz);
+        assert_eq!(archive.root.as_deref(), Some("demo"));
+        let files: Vec<(&str, &[u8])> = archive
+            .entries
+            .iter()
+            .map(|e| (e.path.as_str(), e.content.as_slice()))
+            .collect();
+        assert_eq!(files, vec![("a.txt", &b"hello\n"[..]), ("b.rs", &b"fn main() {}\n"[..])]);
+    }
+
+    #[test]
+    fn test_read_zip() {
+        // demo/ with a stored entry and one deflated with dynamic Huffman codes
+        let bytes = include_bytes!("testdata/demo.zip");
+        let archive = read_archive(bytes).unwrap();
+        assert_eq!(archive.format, ArchiveFormat::Zip);
+        assert_eq!(archive.root.as_deref(), Some("demo"));
+        assert_eq!(archive.entries.len(), 2);
+        assert_eq!(archive.entries[0].path, "stored.txt");
+        assert_eq!(archive.entries[0].content, b"stored\n");
+        assert_eq!(archive.entries[1].path, "src/main.py");
+        let expected: String = (0..200)
+            .map(|i| format!("def f{i}(x):\n    return x * {i}\n"))
+            .collect();
+        assert_eq!(String::from_utf8(archive.entries[1].content.clone()).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_rejects_other_input() {
+        assert_eq!(read_archive(b"just some text"), Err(ArchiveError::UnknownFormat));
+        let mut bytes = tar(&[("a.txt", "content")]);
+        bytes.truncate(TAR_BLOCK + 3);
+        assert_eq!(read_archive(&bytes), Err(ArchiveError::Truncated));
+    }
+
+    fn gzip(bytes: &[u8]) -> Vec<u8> {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(bytes).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    #[test]
+    fn test_read_multi_member_gzip() {
+        // `cat a.gz b.gz` is a valid gzip stream of both members
+        let tar = tar(&[("a.txt", "first\n"), ("b.txt", "second\n")]);
+        let (start, end) = tar.split_at(TAR_BLOCK * 2);
+        let mut bytes = gzip(start);
+        bytes.extend(gzip(end));
+
+        let archive = read_archive(&bytes).unwrap();
+        assert_eq!(archive.format, ArchiveFormat::TarGz);
+        let paths: Vec<&str> = archive.entries.iter().map(|e| e.path.as_str()).collect();
+        assert_eq!(paths, ["a.txt", "b.txt"]);
+    }
+
+    #[test]
+    fn test_rejects_bad_gzip_crc() {
+        let mut bytes = gzip(&tar(&[("a.txt", "content")]));
+        // The CRC-32 is the first half of the 8-byte trailer
+        let crc = bytes.len() - 8;
+        bytes[crc] ^= 0xff;
+        assert!(matches!(read_archive(&bytes), Err(ArchiveError::Corrupt(_))));
+    }
+
+    #[test]
+    fn test_zip_skips_symlinks() {
+        let mut writer = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        let options = zip::write::SimpleFileOptions::default();
+        writer.start_file("notes.md", options).unwrap();
+        writer.write_all(b"# Notes\n").unwrap();
+        writer
+            .add_symlink("secret", "/etc/passwd", options)
+            .unwrap();
+        let bytes = writer.finish().unwrap().into_inner();
+
+        let archive = read_archive(&bytes).unwrap();
+        let paths: Vec<&str> = archive.entries.iter().map(|e| e.path.as_str()).collect();
+        assert_eq!(paths, ["notes.md"]);
+    }
+}
@@ -0,0 +1,336 @@
+//! Snapshots of a subtree across a commit range, for regression hunting
+//!
+//! [`BisectPack::collect`] scans the subtree at a known-good revision, a
+//! known-bad revision and a few evenly spaced commits between them that
+//! touched it. Every snapshot is read from git's object store, so the
+//! working tree is left alone. Consecutive snapshots are compared to show
+//! what each step changed, and [`BisectPack::compact`] drops the unchanged
+//! files from every snapshot after the first to keep the set small.
+
+use crate::git::{Commit, GitRepo};
+use crate::scan::{fill_metadata, ScanError, ScanOptions};
+use crate::types::Repository;
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write;
+use std::path::Path;
+
+/// Intermediate snapshots taken when none are requested
+pub const DEFAULT_BISECT_STEPS: usize = 3;
+
+/// Files added, modified and removed since the previous snapshot
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SnapshotChanges {
+    pub added: Vec<String>,
+    pub modified: Vec<String>,
+    pub removed: Vec<String>,
+}
+
+impl SnapshotChanges {
+    /// Compare the files of two scans of the same subtree
+    fn between(before: &Repository, after: &Repository) -> Self {
+        let old: HashMap<&str, Option<&str>> = before
+            .files
+            .iter()
+            .map(|f| (f.relative_path.as_str(), f.content.as_deref()))
+            .collect();
+        let new: HashSet<&str> = after
+            .files
+            .iter()
+            .map(|f| f.relative_path.as_str())
+            .collect();
+
+        let mut changes = Self::default();
+        for file in &after.files {
+            match old.get(file.relative_path.as_str()) {
+                None => changes.added.push(file.relative_path.clone()),
+                Some(content) if *content != file.content.as_deref() => {
+                    changes.modified.push(file.relative_path.clone());
+                },
+                Some(_) => {},
+            }
+        }
+        changes.removed = old
+            .keys()
+            .filter(|path| !new.contains(*path))
+            .map(|path| (*path).to_owned())
+            .collect();
+        changes.added.sort();
+        changes.modified.sort();
+        changes.removed.sort();
+        changes
+    }
+
+    /// Whether nothing changed
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.modified.is_empty() && self.removed.is_empty()
+    }
+
+    /// `+added ~modified -removed` summary
+    pub fn summary(&self) -> String {
+        format!("+{} ~{} -{}", self.added.len(), self.modified.len(), self.removed.len())
+    }
+}
+
+/// The subtree at one revision
+#[derive(Debug, Clone)]
+pub struct Snapshot {
+    /// `good`, `step-N` or `bad`
+    pub label: String,
+    /// The revision's commit
+    pub commit: Commit,
+    /// Files of the subtree at that commit
+    pub repo: Repository,
+    /// Changes since the previous snapshot; empty for the first
+    pub changes: SnapshotChanges,
+}
+
+impl Snapshot {
+    /// File name stem for this snapshot's pack, sortable by position
+    pub fn file_stem(&self, index: usize) -> String {
+        format!("{:02}-{}-{}", index, self.label, self.commit.short_hash)
+    }
+}
+
+/// Snapshots of a subtree from a good revision to a bad one
+#[derive(Debug, Clone)]
+pub struct BisectPack {
+    /// Known-good revision as given
+    pub good: String,
+    /// Known-bad revision as given
+    pub bad: String,
+    /// Subtree paths; empty for the whole repository
+    pub paths: Vec<String>,
+    /// Commits strictly between good and bad that touch the paths
+    pub candidates: usize,
+    /// Good, intermediate and bad snapshots, oldest first
+    pub snapshots: Vec<Snapshot>,
+    /// Whether later snapshots hold only changed files
+    pub compacted: bool,
+}
+
+impl BisectPack {
+    /// Scan `paths` at `good`, at `bad` and at up to `steps` commits between
+    /// them that touched `paths`
+    pub fn collect(
+        path: impl AsRef<Path>,
+        good: &str,
+        bad: &str,
+        paths: &[String],
+        steps: usize,
+        options: ScanOptions,
+    ) -> Result<Self, ScanError> {
+        let git = GitRepo::open(path.as_ref())?;
+        let bad_commit = git.commit(bad)?;
+        let mut candidates = git.commits_between(good, bad, paths)?;
+        candidates.retain(|c| c.hash != bad_commit.hash);
+
+        let mut revisions = vec![("good".to_owned(), git.commit(good)?)];
+        for (n, index) in pick_steps(candidates.len(), steps).into_iter().enumerate() {
+            revisions.push((format!("step-{}", n + 1), candidates[index].clone()));
+        }
+        revisions.push(("bad".to_owned(), bad_commit));
+
+        let mut snapshots: Vec<Snapshot> = Vec::with_capacity(revisions.len());
+        for (label, commit) in revisions {
+            let repo = Repository::scan_revision(&path, &commit.hash, paths, options.clone())?;
+            let changes = snapshots
+                .last()
+                .map(|previous| SnapshotChanges::between(&previous.repo, &repo))
+                .unwrap_or_default();
+            snapshots.push(Snapshot { label, commit, repo, changes });
+        }
+
+        Ok(Self {
+            good: good.to_owned(),
+            bad: bad.to_owned(),
+            paths: paths.to_vec(),
+            candidates: candidates.len(),
+            snapshots,
+            compacted: false,
+        })
+    }
+
+    /// Keep only added and modified files in every snapshot after the first
+    pub fn compact(&mut self) {
+        for snapshot in self.snapshots.iter_mut().skip(1) {
+            let changed: HashSet<&String> = snapshot
+                .changes
+                .added
+                .iter()
+                .chain(&snapshot.changes.modified)
+                .collect();
+            snapshot
+                .repo
+                .files
+                .retain(|f| changed.contains(&f.relative_path));
+            fill_metadata(&mut snapshot.repo);
+        }
+        self.compacted = true;
+    }
+
+    /// Markdown index of the snapshots, naming each pack `<stem>.<extension>`
+    pub fn render_index(&self, extension: &str) -> String {
+        let mut out = String::new();
+        writeln!(out, "# Bisect pack: {} (good) .. {} (bad)\n", self.good, self.bad).unwrap();
+        let paths = if self.paths.is_empty() {
+            "whole repository".to_owned()
+        } else {
+            self.paths.join(", ")
+        };
+        writeln!(out, "Paths: {}\n", paths).unwrap();
+        writeln!(
+            out,
+            "{} commits between good and bad touch these paths; {} snapshots are packed.",
+            self.candidates,
+            self.snapshots.len()
+        )
+        .unwrap();
+        if self.compacted {
+            writeln!(out, "Packs after the first hold only files changed since the previous one.")
+                .unwrap();
+        }
+        out.push('\n');
+
+        writeln!(out, "| # | Snapshot | Commit | Date | Subject | Files | Changes | Pack |")
+            .unwrap();
+        writeln!(out, "|---|----------|--------|------|---------|-------|---------|------|")
+            .unwrap();
+        for (i, snapshot) in self.snapshots.iter().enumerate() {
+            let changes = if i == 0 {
+                "-".to_owned()
+            } else {
+                snapshot.changes.summary()
+            };
+            writeln!(
+                out,
+                "| {} | {} | {} | {} | {} | {} | {} | {}.{} |",
+                i,
+                snapshot.label,
+                snapshot.commit.short_hash,
+                snapshot.commit.date,
+                snapshot.commit.message.replace('|', "\\|"),
+                snapshot.repo.files.len(),
+                changes,
+                snapshot.file_stem(i),
+                extension
+            )
+            .unwrap();
+        }
+
+        for (i, snapshot) in self.snapshots.iter().enumerate().skip(1) {
+            writeln!(out, "\n## {} {} ({})\n", i, snapshot.label, snapshot.commit.short_hash)
+                .unwrap();
+            if snapshot.changes.is_empty() {
+                writeln!(out, "No changes under the paths.").unwrap();
+            }
+            for (kind, files) in [
+                ("added", &snapshot.changes.added),
+                ("modified", &snapshot.changes.modified),
+                ("removed", &snapshot.changes.removed),
+            ] {
+                for file in files {
+                    writeln!(out, "- {}: {}", kind, file).unwrap();
+                }
+            }
+        }
+        out
+    }
+}
+
+/// Indices of `steps` evenly spaced picks among `count` candidates, or all
+/// of them when there are no more than `steps`
+fn pick_steps(count: usize, steps: usize) -> Vec<usize> {
+    if count <= steps {
+        return (0..count).collect();
+    }
+    let mut picks: Vec<usize> = (1..=steps).map(|k| k * count / (steps + 1)).collect();
+    picks.dedup();
+    picks
+}
+
+#[cfg(test)]
+#[allow(clippy::str_to_string)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::process::Command;
+    use tempfile::TempDir;
+
+    fn git(dir: &Path, args: &[&str]) -> String {
+        let output = Command::new("git")
+            .current_dir(dir)
+            .args(["-c", "user.email=t@t", "-c", "user.name=t"])
+            .args(args)
+            .output()
+            .unwrap();
+        String::from_utf8(output.stdout).unwrap().trim().to_owned()
+    }
+
+    fn commit(dir: &Path, path: &str, content: &str, message: &str) -> String {
+        let file = dir.join(path);
+        fs::create_dir_all(file.parent().unwrap()).unwrap();
+        fs::write(file, content).unwrap();
+        git(dir, &["add", "."]);
+        git(dir, &["commit", "-q", "-m", message]);
+        git(dir, &["rev-parse", "HEAD"])
+    }
+
+    #[test]
+    fn test_pick_steps() {
+        assert_eq!(pick_steps(2, 3), vec![0, 1]);
+        assert_eq!(pick_steps(7, 3), vec![1, 3, 5]);
+        assert_eq!(pick_steps(10, 1), vec![5]);
+        assert!(pick_steps(5, 0).is_empty());
+    }
+
+    #[test]
+    fn test_collect_snapshots() {
+        let temp = TempDir::new().unwrap();
+        let dir = temp.path();
+        git(dir, &["init", "-q"]);
+        let good = commit(dir, "src/pay.rs", "pub fn pay() -> u32 { 1 }\n", "v1");
+        commit(dir, "docs/notes.md", "# Notes\n", "docs only");
+        commit(dir, "src/pay.rs", "pub fn pay() -> u32 { 2 }\n", "tweak pay");
+        commit(dir, "src/refund.rs", "pub fn refund() {}\n", "add refund");
+        let bad = commit(dir, "src/pay.rs", "pub fn pay() -> u32 { 0 }\n", "break pay");
+        // Uncommitted edits are not part of any snapshot
+        fs::write(dir.join("src/pay.rs"), "dirty").unwrap();
+
+        let paths = vec!["src".to_string()];
+        let mut pack =
+            BisectPack::collect(dir, &good[..7], "HEAD", &paths, 1, ScanOptions::default())
+                .unwrap();
+        assert_eq!(pack.candidates, 2);
+        let labels: Vec<&str> = pack.snapshots.iter().map(|s| s.label.as_str()).collect();
+        assert_eq!(labels, ["good", "step-1", "bad"]);
+        assert_eq!(pack.snapshots[2].commit.hash, bad);
+        assert_eq!(pack.snapshots[1].commit.message, "add refund");
+        assert!(pack.snapshots.iter().all(|s| s
+            .repo
+            .files
+            .iter()
+            .all(|f| f.relative_path.starts_with("src/"))));
+
+        let step = &pack.snapshots[1];
+        assert_eq!(step.changes.added, ["src/refund.rs"]);
+        assert_eq!(step.changes.modified, ["src/pay.rs"]);
+        let last = &pack.snapshots[2];
+        assert_eq!(last.changes.modified, ["src/pay.rs"]);
+        let pay = last
+            .repo
+            .files
+            .iter()
+            .find(|f| f.relative_path == "src/pay.rs")
+            .unwrap();
+        assert_eq!(pay.content.as_deref(), Some("pub fn pay() -> u32 { 0 }\n"));
+        assert_eq!(pay.symbols[0].name, "pay");
+
+        pack.compact();
+        assert_eq!(pack.snapshots[0].repo.files.len(), 1);
+        assert_eq!(pack.snapshots[2].repo.files.len(), 1);
+        let index = pack.render_index("xml");
+        assert!(index.contains("| 1 | step-1 |"));
+        assert!(index.contains("- added: src/refund.rs"));
+        assert!(index.contains(&format!("02-bad-{}.xml", &bad[..7])));
+    }
+}
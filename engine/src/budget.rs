@@ -0,0 +1,407 @@
+//! Fitting file contents into a token budget
+//!
+//! A [`BudgetAllocator`] splits the budget between files in proportion to
+//! their importance. Files that need less than their share keep all of it
+//! and the rest is shared among the others, so small files are never cut to
+//! make room for large ones. A file over its share is cut after the last
+//! symbol that still fits, parsing it first if the scan skipped symbols, or
+//! at a line boundary when the symbols are too coarse. Files whose share
+//! would be too small to be useful are left out.
+//!
+//! The returned [`BudgetReport`] lists the truncated and omitted files, for
+//! appending to the pack.
+
+use crate::output::{Locale, Section};
+use crate::scan::parse_symbols;
+use crate::tokenizer::Tokenizer;
+use crate::types::{RepoFile, Repository, SymbolKind, TokenizerModel};
+use serde::Serialize;
+use std::path::Path;
+
+/// Smallest share worth keeping part of a file for
+pub const DEFAULT_MIN_FILE_TOKENS: u32 = 64;
+
+/// Assigns per-file token budgets and trims files to them
+#[derive(Debug, Clone)]
+pub struct BudgetAllocator {
+    budget: u32,
+    model: TokenizerModel,
+    min_file_tokens: u32,
+}
+
+impl BudgetAllocator {
+    /// Create an allocator for a content budget in tokens of `model`
+    pub fn new(budget: u32, model: TokenizerModel) -> Self {
+        Self { budget, model, min_file_tokens: DEFAULT_MIN_FILE_TOKENS }
+    }
+
+    /// Leave out files whose share is below this many tokens (default 64)
+    pub fn with_min_file_tokens(mut self, tokens: u32) -> Self {
+        self.min_file_tokens = tokens;
+        self
+    }
+
+    /// Token budget of every file, in order; 0 for files to leave out
+    ///
+    /// Files without content get 0 as well, but are not left out.
+    pub fn shares(&self, files: &[RepoFile]) -> Vec<u32> {
+        let need: Vec<u32> = files
+            .iter()
+            .map(|f| {
+                if f.content.is_some() {
+                    f.token_count.get(self.model)
+                } else {
+                    0
+                }
+            })
+            .collect();
+        let weight = |i: usize| files[i].importance.max(0.01) as f64;
+
+        let mut shares = vec![0u32; files.len()];
+        let mut active: Vec<usize> = (0..files.len()).filter(|&i| need[i] > 0).collect();
+        loop {
+            // Whoever needs less than a proportional share gets what it
+            // needs, and the remainder is split again among the others
+            let mut remaining = self.budget as f64;
+            let mut open = active.clone();
+            while !open.is_empty() {
+                let total: f64 = open.iter().map(|&i| weight(i)).sum();
+                let (fits, rest): (Vec<usize>, Vec<usize>) = open
+                    .iter()
+                    .partition(|&&i| need[i] as f64 <= remaining * weight(i) / total);
+                if fits.is_empty() {
+                    for &i in &rest {
+                        shares[i] = (remaining * weight(i) / total) as u32;
+                    }
+                    break;
+                }
+                for &i in &fits {
+                    shares[i] = need[i];
+                    remaining -= need[i] as f64;
+                }
+                open = rest;
+            }
+
+            // Leave out files cut below the minimum and share again
+            let before = active.len();
+            active.retain(|&i| {
+                let keep = shares[i] >= need[i] || shares[i] >= self.min_file_tokens;
+                if !keep {
+                    shares[i] = 0;
+                }
+                keep
+            });
+            if active.len() == before {
+                break;
+            }
+        }
+        shares
+    }
+
+    /// Trim `repo` to the budget, keeping file order
+    ///
+    /// If everything fits the repository is left as it is.
+    pub fn allocate(&self, repo: &mut Repository) -> BudgetReport {
+        let mut report = BudgetReport { budget: self.budget, ..Default::default() };
+        let total: u64 = repo
+            .files
+            .iter()
+            .filter(|f| f.content.is_some())
+            .map(|f| f.token_count.get(self.model) as u64)
+            .sum();
+        if total <= self.budget as u64 {
+            return report;
+        }
+
+        let shares = self.shares(&repo.files);
+        let tokenizer = Tokenizer::estimation_only();
+        let files = std::mem::take(&mut repo.files);
+        for (mut file, share) in files.into_iter().zip(shares) {
+            let Some(content) = file.content.as_deref() else {
+                repo.files.push(file);
+                continue;
+            };
+            let tokens = file.token_count.get(self.model);
+            if tokens <= share {
+                repo.files.push(file);
+                continue;
+            }
+
+            let total_lines = content.lines().count();
+            let kept = if share == 0 {
+                0
+            } else {
+                cut_line(&file, content, share, self.model, &tokenizer)
+            };
+            if kept == 0 {
+                report.omitted.push(file.relative_path);
+                continue;
+            }
+
+            let end = content.split_inclusive('\n').take(kept).map(str::len).sum();
+            let text = content[..end].to_owned();
+            file.token_count = tokenizer.count_all(&text).into();
+            file.content = Some(text);
+            if let Some(numbers) = file.line_numbers.as_mut() {
+                numbers.truncate(kept);
+            }
+            report.truncated.push(TruncatedFile {
+                path: file.relative_path.clone(),
+                kept_lines: kept,
+                total_lines,
+                original_tokens: tokens,
+                tokens: file.token_count.get(self.model),
+            });
+            repo.files.push(file);
+        }
+        report
+    }
+}
+
+/// Number of leading lines of `content` to keep within `budget` tokens
+///
+/// Prefers ending right after a symbol, unless that keeps less than half of
+/// what a plain line cut would.
+fn cut_line(
+    file: &RepoFile,
+    content: &str,
+    budget: u32,
+    model: TokenizerModel,
+    tokenizer: &Tokenizer,
+) -> usize {
+    let ends: Vec<usize> = content
+        .split_inclusive('\n')
+        .scan(0, |end, line| {
+            *end += line.len();
+            Some(*end)
+        })
+        .collect();
+    let fits = |lines: usize| {
+        lines == 0 || tokenizer.count(&content[..ends[lines - 1]], model.into()) <= budget
+    };
+
+    // Longest prefix that fits, by binary search over line ends
+    let (mut low, mut high) = (0, ends.len());
+    while low < high {
+        let mid = (low + high).div_ceil(2);
+        if fits(mid) {
+            low = mid;
+        } else {
+            high = mid - 1;
+        }
+    }
+    let line_cut = low;
+
+    // Scans that skipped symbols leave them to be parsed here, from the
+    // content itself, so only extracted symbols need their source lines
+    // mapped to content lines
+    let parsed;
+    let (symbols, numbers) = if file.symbols.is_empty() {
+        parsed = parse_symbols(content, Path::new(&file.relative_path));
+        (&parsed, None)
+    } else {
+        (&file.symbols, file.line_numbers.as_ref())
+    };
+    let mut boundaries: Vec<usize> = symbols
+        .iter()
+        .filter(|s| s.kind != SymbolKind::Import && s.end_line > 0)
+        .map(|s| match numbers {
+            Some(numbers) => numbers.partition_point(|&n| n <= s.end_line),
+            None => s.end_line as usize,
+        })
+        .filter(|&lines| lines <= line_cut)
+        .collect();
+    boundaries.sort_unstable();
+    match boundaries.last() {
+        Some(&lines) if lines * 2 >= line_cut => lines,
+        _ => line_cut,
+    }
+}
+
+/// A file cut to fit its share of the budget
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TruncatedFile {
+    pub path: String,
+    /// Leading lines kept
+    pub kept_lines: usize,
+    /// Lines before truncation
+    pub total_lines: usize,
+    /// Tokens before truncation
+    pub original_tokens: u32,
+    /// Tokens kept
+    pub tokens: u32,
+}
+
+/// What a [`BudgetAllocator`] cut, for appending to the pack
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct BudgetReport {
+    /// Content budget in tokens
+    pub budget: u32,
+    /// Files cut short, in pack order
+    pub truncated: Vec<TruncatedFile>,
+    /// Files left out entirely
+    pub omitted: Vec<String>,
+}
+
+impl BudgetReport {
+    /// Whether any file was cut or left out
+    pub fn is_trimmed(&self) -> bool {
+        !self.truncated.is_empty() || !self.omitted.is_empty()
+    }
+
+    /// List of the trimmed files to append to the pack
+    pub fn render(&self) -> String {
+        self.render_localized(Locale::default())
+    }
+
+    /// [`BudgetReport::render`] with the explanation in `locale`
+    pub fn render_localized(&self, locale: Locale) -> String {
+        if !self.is_trimmed() {
+            return String::new();
+        }
+
+        let text = locale.strings();
+        let mut out = format!("\n\n<!-- {} -->\n", text.budget_title);
+        out.push_str(
+            &text
+                .budget_intro
+                .replace("{budget}", &self.budget.to_string()),
+        );
+        out.push_str("\n\n");
+        out.push_str(&format!(
+            "- {}\n",
+            text.budget_truncated
+                .replace("{count}", &self.truncated.len().to_string())
+        ));
+        for file in &self.truncated {
+            out.push_str(&format!(
+                "  - {} ({})\n",
+                file.path,
+                text.budget_kept_lines
+                    .replace("{kept}", &file.kept_lines.to_string())
+                    .replace("{total}", &file.total_lines.to_string())
+            ));
+        }
+        out.push_str(&format!(
+            "- {}\n",
+            text.sampled_omitted
+                .replace("{count}", &self.omitted.len().to_string())
+        ));
+        for path in &self.omitted {
+            out.push_str(&format!("  - {}\n", path));
+        }
+        out
+    }
+
+    /// The trimmed files as a report section, `None` if nothing was trimmed
+    pub fn section(&self, locale: Locale) -> Option<Section> {
+        self.is_trimmed()
+            .then(|| Section::new("budget", self.render_localized(locale), self))
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::str_to_string)]
+mod tests {
+    use super::*;
+    use crate::types::{Symbol, TokenCounts};
+
+    fn file(path: &str, content: &str, importance: f32) -> RepoFile {
+        let mut file = RepoFile::new(path, path);
+        file.content = Some(content.to_string());
+        file.token_count = Tokenizer::estimation_only().count_all(content).into();
+        file.importance = importance;
+        file
+    }
+
+    fn functions(count: usize) -> (String, Vec<Symbol>) {
+        let mut content = String::new();
+        let mut symbols = Vec::new();
+        for i in 0..count {
+            let start = (i * 4 + 1) as u32;
+            content.push_str(&format!(
+                "fn function_{i}(value: u32) -> u32 {{\n    let doubled = value * 2;\n    doubled + {i}\n}}\n"
+            ));
+            let mut symbol = Symbol::new(format!("function_{i}"), SymbolKind::Function);
+            symbol.start_line = start;
+            symbol.end_line = start + 3;
+            symbols.push(symbol);
+        }
+        (content, symbols)
+    }
+
+    fn repo(files: Vec<RepoFile>) -> Repository {
+        let mut repo = Repository::new("demo", "/demo");
+        repo.files = files;
+        repo
+    }
+
+    #[test]
+    fn test_fits_untouched() {
+        let mut repo = repo(vec![file("a.rs", "fn a() {}\n", 0.5)]);
+        let report = BudgetAllocator::new(1000, TokenizerModel::Claude).allocate(&mut repo);
+        assert!(!report.is_trimmed());
+        assert!(report.render().is_empty());
+        assert!(report.section(Locale::En).is_none());
+        assert_eq!(repo.files[0].content.as_deref(), Some("fn a() {}\n"));
+    }
+
+    #[test]
+    fn test_shares_follow_importance() {
+        let mut small = file("small.rs", "", 0.1);
+        small.token_count = TokenCounts { claude: 50, ..Default::default() };
+        let mut big = file("big.rs", "", 0.75);
+        big.token_count = TokenCounts { claude: 5000, ..Default::default() };
+        let mut other = file("other.rs", "", 0.25);
+        other.token_count = TokenCounts { claude: 5000, ..Default::default() };
+        let mut tiny = file("tiny.rs", "", 0.01);
+        tiny.token_count = TokenCounts { claude: 5000, ..Default::default() };
+
+        let allocator = BudgetAllocator::new(1050, TokenizerModel::Claude);
+        let shares = allocator.shares(&[small, big, other, tiny]);
+        // The small file needs less than its share and keeps everything
+        assert_eq!(shares[0], 50);
+        // The rest is split 3:1 once the least important file is left out
+        assert_eq!(shares[3], 0);
+        assert_eq!(shares[1], 750);
+        assert_eq!(shares[2], 250);
+    }
+
+    #[test]
+    fn test_truncates_at_symbol_boundary() {
+        let (content, symbols) = functions(40);
+        let mut main = file("main.rs", &content, 0.9);
+        main.symbols = symbols;
+        let tokens = main.token_count.claude;
+        let mut repo = repo(vec![main, file("extra.rs", &content, 0.001)]);
+
+        let report = BudgetAllocator::new(tokens / 2, TokenizerModel::Claude).allocate(&mut repo);
+        assert_eq!(report.omitted, ["extra.rs"]);
+        assert_eq!(repo.files.len(), 1);
+        let truncated = &report.truncated[0];
+        assert_eq!(truncated.total_lines, 160);
+        assert!(truncated.kept_lines > 0 && truncated.kept_lines.is_multiple_of(4));
+        assert!(truncated.tokens <= tokens / 2);
+
+        let kept = repo.files[0].content.as_deref().unwrap();
+        assert!(kept.ends_with("}\n"));
+        assert_eq!(kept.lines().count(), truncated.kept_lines);
+        let rendered = report.render();
+        assert!(rendered.contains("main.rs (kept lines 1-"));
+        assert!(rendered.contains("  - extra.rs\n"));
+        let section = report.section(Locale::En).unwrap();
+        assert_eq!(section.data["omitted"][0], "extra.rs");
+        assert_eq!(section.data["truncated"][0]["path"], "main.rs");
+    }
+
+    #[test]
+    fn test_truncates_at_line_without_symbols() {
+        let content = "let x = 1;\n".repeat(200);
+        let mut repo = repo(vec![file("data.rs", &content, 0.5)]);
+        let report = BudgetAllocator::new(100, TokenizerModel::Claude).allocate(&mut repo);
+        let kept = repo.files[0].content.as_deref().unwrap();
+        assert!(kept.ends_with(";\n"));
+        assert!(repo.files[0].token_count.claude <= 100);
+        assert_eq!(report.truncated[0].kept_lines, kept.lines().count());
+    }
+}
@@ -0,0 +1,302 @@
+//! Shareable bundles of the inputs to an LLM session
+//!
+//! A [`Bundle`] is a tar archive holding a pack, the manifest written with it,
+//! the instruction file and any prompt or response transcripts, so the exact
+//! context a model was given can be handed to someone debugging its answer.
+//!
+//! `bundle.json` at the archive root lists every file with its part, size and
+//! SHA-256. [`Bundle::read`] checks each file against it, so a truncated or
+//! edited bundle is refused instead of silently reproducing a different
+//! session.
+
+use crate::archive::{read_archive, write_tar, ArchiveEntry, ArchiveError};
+use crate::data::sha256_hex;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use thiserror::Error;
+
+/// Current bundle format version
+pub const BUNDLE_VERSION: u32 = 1;
+
+/// Name of the file listing a bundle's contents
+pub const BUNDLE_INDEX: &str = "bundle.json";
+
+/// Errors writing, reading or extracting bundles
+#[derive(Debug, Error)]
+pub enum BundleError {
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("invalid bundle index: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error(transparent)]
+    Archive(#[from] ArchiveError),
+    #[error("not a bundle: {BUNDLE_INDEX} is missing")]
+    MissingIndex,
+    #[error("unsupported bundle version {0} (expected {BUNDLE_VERSION})")]
+    Version(u32),
+    #[error("{0} is listed in the bundle but missing from it")]
+    MissingFile(String),
+    #[error("{0} does not match its checksum in the bundle")]
+    Checksum(String),
+    #[error("{0} already exists")]
+    Exists(PathBuf),
+}
+
+/// What a bundled file is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BundlePart {
+    /// Pack output sent to the model
+    Pack,
+    /// Manifest written alongside the pack
+    Manifest,
+    /// Instruction file sent with the pack
+    Instructions,
+    /// Prompt or response from the session
+    Transcript,
+}
+
+impl BundlePart {
+    /// Short name for messages
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Pack => "pack",
+            Self::Manifest => "manifest",
+            Self::Instructions => "instructions",
+            Self::Transcript => "transcript",
+        }
+    }
+
+    /// Directory holding this part's files in the bundle
+    fn dir(self) -> &'static str {
+        match self {
+            Self::Pack => "pack",
+            Self::Manifest => "manifest",
+            Self::Instructions => "instructions",
+            Self::Transcript => "transcripts",
+        }
+    }
+}
+
+/// A file listed in `bundle.json`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BundleFile {
+    /// Path inside the bundle
+    pub path: String,
+    /// What the file is
+    pub part: BundlePart,
+    /// Size in bytes
+    pub size: u64,
+    /// SHA-256 of the content
+    pub sha256: String,
+}
+
+/// Contents of `bundle.json`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BundleIndex {
+    /// Bundle format version
+    pub version: u32,
+    /// Seconds since the Unix epoch when the bundle was made
+    pub created: u64,
+    /// Tool and version that made the bundle
+    pub generator: String,
+    /// Bundled files, transcripts in the order they were added
+    pub files: Vec<BundleFile>,
+}
+
+/// A pack and the rest of a session's inputs, ready to share
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Bundle {
+    /// What the bundle holds
+    pub index: BundleIndex,
+    /// Content of each file in `index.files`, in the same order
+    contents: Vec<Vec<u8>>,
+}
+
+impl Default for Bundle {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Bundle {
+    /// An empty bundle dated now
+    pub fn new() -> Self {
+        let created = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or_default();
+        Self {
+            index: BundleIndex {
+                version: BUNDLE_VERSION,
+                created,
+                generator: format!("infiniloom {}", crate::VERSION),
+                files: Vec::new(),
+            },
+            contents: Vec::new(),
+        }
+    }
+
+    /// Add a file named `name` as `part`, returning its path in the bundle
+    ///
+    /// Only the final component of `name` is kept, and a name already taken
+    /// by the part gets a numeric suffix.
+    pub fn add(&mut self, part: BundlePart, name: &str, content: Vec<u8>) -> &str {
+        let name = name
+            .rsplit(['/', '\\'])
+            .find(|s| !s.is_empty() && *s != "." && *s != "..")
+            .unwrap_or(part.name());
+        let taken = |path: &str| self.index.files.iter().any(|f| f.path == path);
+        let mut path = format!("{}/{}", part.dir(), name);
+        let mut n = 2;
+        while taken(&path) {
+            let (stem, ext) = match name.rsplit_once('.') {
+                Some((stem, ext)) if !stem.is_empty() => (stem, format!(".{}", ext)),
+                _ => (name, String::new()),
+            };
+            path = format!("{}/{}-{}{}", part.dir(), stem, n, ext);
+            n += 1;
+        }
+
+        self.index.files.push(BundleFile {
+            path,
+            part,
+            size: content.len() as u64,
+            sha256: sha256_hex(&content),
+        });
+        self.contents.push(content);
+        &self.index.files[self.index.files.len() - 1].path
+    }
+
+    /// Bundled files with their content
+    pub fn files(&self) -> impl Iterator<Item = (&BundleFile, &[u8])> {
+        self.index
+            .files
+            .iter()
+            .zip(self.contents.iter().map(Vec::as_slice))
+    }
+
+    /// The bundle as tar archive bytes, `bundle.json` first
+    pub fn to_bytes(&self) -> Result<Vec<u8>, BundleError> {
+        let mut entries = vec![ArchiveEntry {
+            path: BUNDLE_INDEX.to_owned(),
+            content: serde_json::to_vec_pretty(&self.index)?,
+        }];
+        entries.extend(self.files().map(|(file, content)| ArchiveEntry {
+            path: file.path.clone(),
+            content: content.to_vec(),
+        }));
+        Ok(write_tar(&entries)?)
+    }
+
+    /// Read a bundle, checking every file against `bundle.json`
+    pub fn read(bytes: &[u8]) -> Result<Self, BundleError> {
+        let mut archive = read_archive(bytes)?;
+        let index_entry = archive
+            .entries
+            .iter()
+            .position(|e| e.path == BUNDLE_INDEX)
+            .ok_or(BundleError::MissingIndex)?;
+        let index: BundleIndex =
+            serde_json::from_slice(&archive.entries.swap_remove(index_entry).content)?;
+        if index.version != BUNDLE_VERSION {
+            return Err(BundleError::Version(index.version));
+        }
+
+        let mut contents = Vec::with_capacity(index.files.len());
+        for file in &index.files {
+            let entry = archive
+                .entries
+                .iter_mut()
+                .find(|e| e.path == file.path)
+                .ok_or_else(|| BundleError::MissingFile(file.path.clone()))?;
+            let content = std::mem::take(&mut entry.content);
+            if content.len() as u64 != file.size || sha256_hex(&content) != file.sha256 {
+                return Err(BundleError::Checksum(file.path.clone()));
+            }
+            contents.push(content);
+        }
+        Ok(Self { index, contents })
+    }
+
+    /// Write the bundled files under `dir`, returning their paths
+    ///
+    /// Existing files are left alone and fail the extraction unless
+    /// `overwrite` is set; nothing is written in that case.
+    pub fn extract(&self, dir: &Path, overwrite: bool) -> Result<Vec<PathBuf>, BundleError> {
+        let targets: Vec<PathBuf> = self.index.files.iter().map(|f| dir.join(&f.path)).collect();
+        if !overwrite {
+            if let Some(existing) = targets.iter().find(|t| t.exists()) {
+                return Err(BundleError::Exists(existing.clone()));
+            }
+        }
+        for (target, (_, content)) in targets.iter().zip(self.files()) {
+            if let Some(parent) = target.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            std::fs::write(target, content)?;
+        }
+        Ok(targets)
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::str_to_string)]
+mod tests {
+    use super::*;
+
+    fn session() -> Bundle {
+        let mut bundle = Bundle::new();
+        bundle.add(BundlePart::Pack, "out/context.xml", b"<repository/>".to_vec());
+        bundle.add(BundlePart::Manifest, "pack-manifest.json", b"{}".to_vec());
+        bundle.add(BundlePart::Transcript, "prompt.md", b"Why does login fail?".to_vec());
+        bundle.add(BundlePart::Transcript, "logs/prompt.md", b"And on retry?".to_vec());
+        bundle
+    }
+
+    #[test]
+    fn test_bundle_round_trip() {
+        let bundle = session();
+        let paths: Vec<&str> = bundle.index.files.iter().map(|f| f.path.as_str()).collect();
+        assert_eq!(
+            paths,
+            vec![
+                "pack/context.xml",
+                "manifest/pack-manifest.json",
+                "transcripts/prompt.md",
+                "transcripts/prompt-2.md"
+            ]
+        );
+
+        let read = Bundle::read(&bundle.to_bytes().unwrap()).unwrap();
+        assert_eq!(read, bundle);
+
+        let dir = tempfile::tempdir().unwrap();
+        let written = read.extract(dir.path(), false).unwrap();
+        assert_eq!(written.len(), 4);
+        assert_eq!(
+            std::fs::read_to_string(dir.path().join("transcripts/prompt-2.md")).unwrap(),
+            "And on retry?"
+        );
+        assert!(matches!(read.extract(dir.path(), false), Err(BundleError::Exists(_))));
+        assert!(read.extract(dir.path(), true).is_ok());
+    }
+
+    #[test]
+    fn test_bundle_rejects_tampering() {
+        let bytes = session().to_bytes().unwrap();
+        let mut tampered = bytes.clone();
+        let at = bytes
+            .windows(13)
+            .position(|w| w == b"<repository/>")
+            .unwrap();
+        tampered[at + 1] = b'R';
+        assert!(matches!(Bundle::read(&tampered), Err(BundleError::Checksum(_))));
+
+        let plain =
+            write_tar(&[ArchiveEntry { path: "notes.md".to_string(), content: b"hi".to_vec() }])
+                .unwrap();
+        assert!(matches!(Bundle::read(&plain), Err(BundleError::MissingIndex)));
+    }
+}
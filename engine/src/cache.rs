@@ -0,0 +1,619 @@
+//! Locking and garbage collection for on-disk caches
+//!
+//! Parallel invocations (CI jobs on one runner, editors and agents on one
+//! checkout) share the incremental scan cache, the data cache and federation
+//! clones. Every cached entry has a sibling `<entry>.lock` file: readers take
+//! a shared lock and writers an exclusive one, and [`CacheGc`] only touches
+//! entries it can lock exclusively, so nothing in use is ever deleted.
+//!
+//! Locks are advisory and released when the [`CacheLock`] is dropped, which
+//! includes the process exiting or crashing. [`CacheLock::repo`] extends the
+//! same scheme to whole runs on one repository, and [`write_atomic`] gives
+//! outputs the partial-then-rename treatment cache entries get.
+//!
+//! Nothing is cached inside the scanned repository, so read-only checkouts
+//! and mounted volumes work. Per-repository caches live under
+//! [`repo_cache_dir`], which `INFINILOOM_CACHE_DIR` moves; where no cache
+//! directory is writable, callers keep their cache in memory for the run.
+
+use crate::data::sha256_hex;
+use crate::incremental::RepoCache;
+use std::fs::{self, File, OpenOptions, TryLockError};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+/// Extension of the lock file next to each entry
+pub const LOCK_EXTENSION: &str = "lock";
+
+/// Partial writes older than this belong to a process that died mid-write
+const STALE_PARTIAL: Duration = Duration::from_secs(60 * 60);
+
+/// Length of the digest prefix in data cache file names
+const ASSET_DIGEST_LEN: usize = 12;
+
+/// Environment variable moving every cache and lock under one directory
+pub const CACHE_DIR_ENV: &str = "INFINILOOM_CACHE_DIR";
+
+/// Root of infiniloom's caches
+///
+/// Uses `INFINILOOM_CACHE_DIR`, then `$XDG_CACHE_HOME/infiniloom`, then
+/// `~/.cache/infiniloom`, then `infiniloom` in the temp directory. Nothing
+/// is created.
+pub fn cache_root() -> PathBuf {
+    std::env::var_os(CACHE_DIR_ENV)
+        .map(PathBuf::from)
+        .or_else(|| {
+            std::env::var_os("XDG_CACHE_HOME").map(|cache| PathBuf::from(cache).join("infiniloom"))
+        })
+        .or_else(|| {
+            std::env::var_os("HOME")
+                .or_else(|| std::env::var_os("USERPROFILE"))
+                .map(|home| PathBuf::from(home).join(".cache/infiniloom"))
+        })
+        .unwrap_or_else(|| std::env::temp_dir().join("infiniloom"))
+}
+
+/// Cache directory of the repository at `repo_path`, outside the repository
+///
+/// Named after the repository and a digest of its canonical path, under
+/// `repos` in [`cache_root`]. Nothing is created.
+pub fn repo_cache_dir(repo_path: &Path) -> PathBuf {
+    let canonical = repo_path
+        .canonicalize()
+        .unwrap_or_else(|_| repo_path.to_path_buf());
+    let digest = sha256_hex(canonical.to_string_lossy().as_bytes());
+    let name = canonical
+        .file_name()
+        .map_or_else(|| "repo".into(), |n| n.to_string_lossy());
+    cache_root()
+        .join("repos")
+        .join(format!("{}-{}", name, &digest[..ASSET_DIGEST_LEN]))
+}
+
+/// Whether files can be written in `dir`, creating it if needed
+///
+/// Probes with a file that is removed again, since a directory on a
+/// read-only mount can exist and still refuse writes.
+pub fn is_writable_dir(dir: &Path) -> bool {
+    if fs::create_dir_all(dir).is_err() {
+        return false;
+    }
+    let probe = partial_path(&dir.join(".probe"));
+    let writable = File::create(&probe).is_ok();
+    fs::remove_file(&probe).ok();
+    writable
+}
+
+/// Advisory lock on a cache entry, released on drop
+#[derive(Debug)]
+pub struct CacheLock {
+    _file: File,
+}
+
+impl CacheLock {
+    /// Lock file guarding `entry`
+    pub fn path(entry: &Path) -> PathBuf {
+        let mut name = entry.file_name().unwrap_or_default().to_os_string();
+        name.push(".");
+        name.push(LOCK_EXTENSION);
+        entry.with_file_name(name)
+    }
+
+    /// Wait for a lock that other readers may share
+    ///
+    /// Does not create the cache directory, so reading a missing or read-only
+    /// cache fails here instead of leaving directories behind.
+    pub fn shared(entry: &Path) -> io::Result<Self> {
+        let file = Self::open(entry)?;
+        file.lock_shared()?;
+        Ok(Self { _file: file })
+    }
+
+    /// Wait for sole access to `entry`
+    pub fn exclusive(entry: &Path) -> io::Result<Self> {
+        if let Some(parent) = entry.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let file = Self::open(entry)?;
+        file.lock()?;
+        Ok(Self { _file: file })
+    }
+
+    /// Sole access to `entry`, or `None` if another process holds a lock
+    pub fn try_exclusive(entry: &Path) -> io::Result<Option<Self>> {
+        let file = Self::open(entry)?;
+        match file.try_lock() {
+            Ok(()) => Ok(Some(Self { _file: file })),
+            Err(TryLockError::WouldBlock) => Ok(None),
+            Err(TryLockError::Error(e)) => Err(e),
+        }
+    }
+
+    /// Wait for sole use of a repository
+    ///
+    /// Taken for a whole pack, so overlapping runs on one checkout (watch
+    /// mode plus a manual pack) take turns updating its cache and outputs.
+    /// The lock file lives in `INFINILOOM_CACHE_DIR`, if set, or else the
+    /// temp directory, keyed by the canonical path, so read-only checkouts
+    /// can be locked too.
+    pub fn repo(repo_path: &Path) -> io::Result<Self> {
+        Self::exclusive(&Self::repo_entry(repo_path))
+    }
+
+    /// Sole use of a repository, or `None` if another run holds it
+    pub fn try_repo(repo_path: &Path) -> io::Result<Option<Self>> {
+        let entry = Self::repo_entry(repo_path);
+        if let Some(parent) = entry.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        Self::try_exclusive(&entry)
+    }
+
+    fn repo_entry(repo_path: &Path) -> PathBuf {
+        let canonical = repo_path
+            .canonicalize()
+            .unwrap_or_else(|_| repo_path.to_path_buf());
+        let digest = sha256_hex(canonical.to_string_lossy().as_bytes());
+        std::env::var_os(CACHE_DIR_ENV)
+            .map(|dir| PathBuf::from(dir).join("locks"))
+            .unwrap_or_else(|| std::env::temp_dir().join("infiniloom-locks"))
+            .join(&digest[..ASSET_DIGEST_LEN])
+    }
+
+    fn open(entry: &Path) -> io::Result<File> {
+        OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(Self::path(entry))
+    }
+}
+
+/// Path a writer fills before renaming it over `entry`
+///
+/// Readers never see a half-written entry, and [`CacheGc`] recognizes
+/// leftovers from writers that died.
+pub fn partial_path(entry: &Path) -> PathBuf {
+    let mut name = entry.file_name().unwrap_or_default().to_os_string();
+    name.push(format!(".part{}", std::process::id()));
+    entry.with_file_name(name)
+}
+
+/// Replace the regular file at `path` with `contents` through a synced
+/// [`partial_path`]
+///
+/// Readers, concurrent writers and crashes only ever leave the old file or
+/// the new one in place, never a mix of the two. A symlink is followed and
+/// its target replaced, keeping the link, and a replaced file keeps its
+/// permissions. Anything that is not a regular file, such as `/dev/stdout`,
+/// a FIFO or a dangling symlink, is written to directly instead.
+pub fn write_atomic(path: &Path, contents: impl AsRef<[u8]>) -> io::Result<()> {
+    let (path, permissions) = match fs::metadata(path) {
+        Ok(meta) if meta.is_file() => (fs::canonicalize(path)?, Some(meta.permissions())),
+        Ok(_) => return File::create(path)?.write_all(contents.as_ref()),
+        Err(e) if e.kind() == io::ErrorKind::NotFound && path.is_symlink() => {
+            return File::create(path)?.write_all(contents.as_ref());
+        },
+        Err(_) => (path.to_path_buf(), None),
+    };
+    let partial = partial_path(&path);
+    let written = File::create(&partial).and_then(|mut file| {
+        file.write_all(contents.as_ref())?;
+        if let Some(permissions) = permissions {
+            file.set_permissions(permissions)?;
+        }
+        file.sync_all()
+    });
+    if let Err(e) = written.and_then(|()| fs::rename(&partial, &path)) {
+        fs::remove_file(&partial).ok();
+        return Err(e);
+    }
+    Ok(())
+}
+
+/// What a cache entry holds, which decides how it is verified
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EntryKind {
+    /// Incremental scan cache (`*.cache`)
+    RepoCache,
+    /// Downloaded data asset named after its digest
+    Asset,
+    /// Git clone of a remote repository
+    Clone,
+    /// Leftover of an interrupted write
+    Partial,
+    /// Anything else; kept unless evicted for size
+    Other,
+}
+
+impl EntryKind {
+    /// Lowercase name for display
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::RepoCache => "repo-cache",
+            Self::Asset => "asset",
+            Self::Clone => "clone",
+            Self::Partial => "partial",
+            Self::Other => "other",
+        }
+    }
+
+    /// Kind of a cached file from its name
+    fn classify(path: &Path) -> Self {
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        if let Some((_, pid)) = name.rsplit_once(".part") {
+            if !pid.is_empty() && pid.bytes().all(|b| b.is_ascii_digit()) {
+                return Self::Partial;
+            }
+        }
+        if path.extension().is_some_and(|ext| ext == "cache") {
+            return Self::RepoCache;
+        }
+        match name.rsplit_once('-') {
+            Some((_, digest))
+                if digest.len() == ASSET_DIGEST_LEN
+                    && digest.bytes().all(|b| b.is_ascii_hexdigit()) =>
+            {
+                Self::Asset
+            },
+            _ => Self::Other,
+        }
+    }
+}
+
+/// One unit of cached data: a file, or a whole clone
+#[derive(Debug, Clone)]
+pub struct CacheEntry {
+    /// Location on disk
+    pub path: PathBuf,
+    /// What the entry holds
+    pub kind: EntryKind,
+    /// Size in bytes, including everything below a clone
+    pub size: u64,
+    /// Last write or use, the eviction order
+    pub last_used: SystemTime,
+}
+
+impl CacheEntry {
+    /// Whether the entry is complete and readable
+    fn is_intact(&self) -> bool {
+        match self.kind {
+            EntryKind::RepoCache => fs::read(&self.path)
+                .ok()
+                .is_some_and(|bytes| RepoCache::from_bytes(&bytes).is_ok()),
+            EntryKind::Asset => {
+                let name = self.path.file_name().unwrap_or_default().to_string_lossy();
+                let digest = name.rsplit_once('-').map(|(_, d)| d).unwrap_or_default();
+                fs::read(&self.path)
+                    .ok()
+                    .is_some_and(|bytes| sha256_hex(&bytes).starts_with(digest))
+            },
+            EntryKind::Clone => self.path.join(".git/HEAD").is_file(),
+            EntryKind::Partial => self
+                .last_used
+                .elapsed()
+                .is_ok_and(|age| age < STALE_PARTIAL),
+            EntryKind::Other => true,
+        }
+    }
+
+    fn remove(&self) -> io::Result<()> {
+        match self.kind {
+            EntryKind::Clone => fs::remove_dir_all(&self.path),
+            _ => fs::remove_file(&self.path),
+        }
+    }
+}
+
+/// Every entry below `root`, skipping lock files
+///
+/// A directory containing `.git` counts as one entry. A missing root has no
+/// entries.
+pub fn list_entries(root: &Path) -> io::Result<Vec<CacheEntry>> {
+    let mut entries = Vec::new();
+    if root.is_dir() {
+        collect_entries(root, &mut entries)?;
+    }
+    Ok(entries)
+}
+
+fn collect_entries(dir: &Path, entries: &mut Vec<CacheEntry>) -> io::Result<()> {
+    for item in fs::read_dir(dir)? {
+        let item = item?;
+        let path = item.path();
+        let meta = item.metadata()?;
+        if meta.is_dir() {
+            if path.join(".git").exists() {
+                let (size, last_used) = tree_usage(&path)?;
+                entries.push(CacheEntry { path, kind: EntryKind::Clone, size, last_used });
+            } else {
+                collect_entries(&path, entries)?;
+            }
+        } else if path.extension().is_none_or(|ext| ext != LOCK_EXTENSION) {
+            entries.push(CacheEntry {
+                kind: EntryKind::classify(&path),
+                size: meta.len(),
+                last_used: meta.modified()?,
+                path,
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Total size and newest modification below `dir`
+fn tree_usage(dir: &Path) -> io::Result<(u64, SystemTime)> {
+    let mut size = 0;
+    let mut newest = fs::metadata(dir)?.modified()?;
+    for item in fs::read_dir(dir)? {
+        let item = item?;
+        let meta = item.metadata()?;
+        let (item_size, item_time) = if meta.is_dir() {
+            tree_usage(&item.path())?
+        } else {
+            (meta.len(), meta.modified()?)
+        };
+        size += item_size;
+        newest = newest.max(item_time);
+    }
+    Ok((size, newest))
+}
+
+/// Outcome of a [`CacheGc`] run
+#[derive(Debug, Clone, Default)]
+pub struct GcReport {
+    /// Entries that failed their integrity check
+    pub corrupt: Vec<CacheEntry>,
+    /// Intact entries evicted to get under the size limit, oldest first
+    pub evicted: Vec<CacheEntry>,
+    /// Entries left in place
+    pub kept: Vec<CacheEntry>,
+    /// Entries skipped because another process holds their lock
+    pub in_use: Vec<CacheEntry>,
+}
+
+impl GcReport {
+    /// Bytes removed (or that would be, on a dry run)
+    pub fn freed_bytes(&self) -> u64 {
+        self.corrupt
+            .iter()
+            .chain(&self.evicted)
+            .map(|e| e.size)
+            .sum()
+    }
+
+    /// Bytes still cached
+    pub fn remaining_bytes(&self) -> u64 {
+        self.kept.iter().chain(&self.in_use).map(|e| e.size).sum()
+    }
+}
+
+/// Removes corrupt cache entries and evicts the least recently used ones
+#[derive(Debug, Clone, Default)]
+pub struct CacheGc {
+    /// Upper bound on the combined size of all roots
+    max_size: Option<u64>,
+    /// Report what would be removed without removing it
+    dry_run: bool,
+}
+
+impl CacheGc {
+    /// Collector that only removes corrupt entries
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Evict entries until the roots together fit in `max_size` bytes
+    pub fn with_max_size(mut self, max_size: u64) -> Self {
+        self.max_size = Some(max_size);
+        self
+    }
+
+    /// Leave everything in place and only report
+    pub fn with_dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+
+    /// Collect garbage across `roots`, treating them as one cache
+    pub fn run(&self, roots: &[PathBuf]) -> io::Result<GcReport> {
+        let mut entries = Vec::new();
+        for root in roots {
+            entries.extend(list_entries(root)?);
+        }
+        entries.sort_by_key(|e| e.last_used);
+
+        let mut report = GcReport::default();
+        let mut intact = Vec::new();
+        for entry in entries {
+            let Some(_lock) = CacheLock::try_exclusive(&entry.path)? else {
+                report.in_use.push(entry);
+                continue;
+            };
+            if entry.is_intact() {
+                intact.push(entry);
+            } else {
+                if !self.dry_run {
+                    entry.remove()?;
+                }
+                report.corrupt.push(entry);
+            }
+        }
+
+        let mut total: u64 = intact.iter().chain(&report.in_use).map(|e| e.size).sum();
+        for entry in intact {
+            let over = self.max_size.is_some_and(|max| total > max);
+            // Partial writes are never evicted; they are either live or corrupt
+            if !over || entry.kind == EntryKind::Partial {
+                report.kept.push(entry);
+                continue;
+            }
+            match CacheLock::try_exclusive(&entry.path)? {
+                Some(_lock) => {
+                    if !self.dry_run {
+                        entry.remove()?;
+                    }
+                    total -= entry.size;
+                    report.evicted.push(entry);
+                },
+                None => report.in_use.push(entry),
+            }
+        }
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::str_to_string)]
+mod tests {
+    use super::*;
+
+    fn age(path: &Path, secs: u64) {
+        let file = File::options().write(true).open(path).unwrap();
+        file.set_modified(SystemTime::now() - Duration::from_secs(secs))
+            .unwrap();
+    }
+
+    #[test]
+    fn test_classify() {
+        let kind = |name: &str| EntryKind::classify(Path::new(name));
+        assert_eq!(kind("repo.cache"), EntryKind::RepoCache);
+        assert_eq!(kind("hello-2cf24dba5fb0"), EntryKind::Asset);
+        assert_eq!(kind("hello-2cf24dba5fb0.part4242"), EntryKind::Partial);
+        assert_eq!(kind("repo.cache.part7"), EntryKind::Partial);
+        assert_eq!(kind("notes.txt"), EntryKind::Other);
+        assert_eq!(CacheLock::path(Path::new("a/repo.cache")), Path::new("a/repo.cache.lock"));
+    }
+
+    #[test]
+    fn test_lock_excludes_other_holders() {
+        let dir = tempfile::tempdir().unwrap();
+        let entry = dir.path().join("cache/repo.cache");
+
+        let held = CacheLock::exclusive(&entry).unwrap();
+        assert!(CacheLock::try_exclusive(&entry).unwrap().is_none());
+        drop(held);
+
+        let shared = CacheLock::shared(&entry).unwrap();
+        assert!(CacheLock::try_exclusive(&entry).unwrap().is_none());
+        drop(shared);
+        assert!(CacheLock::try_exclusive(&entry).unwrap().is_some());
+    }
+
+    #[test]
+    fn test_repo_lock() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let held = CacheLock::repo(dir.path()).unwrap();
+        // Same repository through another path spelling
+        assert!(CacheLock::try_repo(&dir.path().join("."))
+            .unwrap()
+            .is_none());
+        drop(held);
+        assert!(CacheLock::try_repo(dir.path()).unwrap().is_some());
+    }
+
+    #[test]
+    fn test_write_atomic() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("pack.xml");
+
+        write_atomic(&path, "first").unwrap();
+        write_atomic(&path, "second").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "second");
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+
+        // A failed write leaves neither a partial file nor a changed target
+        assert!(write_atomic(&dir.path().join("missing/pack.xml"), "x").is_err());
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_write_atomic_keeps_links_and_permissions() {
+        use std::os::unix::fs::{symlink, PermissionsExt};
+
+        let dir = tempfile::tempdir().unwrap();
+        let target = dir.path().join("pack.xml");
+        fs::write(&target, "first").unwrap();
+        fs::set_permissions(&target, fs::Permissions::from_mode(0o640)).unwrap();
+        let link = dir.path().join("latest.xml");
+        symlink(&target, &link).unwrap();
+
+        write_atomic(&link, "second").unwrap();
+        assert!(link.is_symlink());
+        assert_eq!(fs::read_to_string(&target).unwrap(), "second");
+        let mode = fs::metadata(&target).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o640);
+
+        // A dangling link is written through, creating its target
+        let dangling = dir.path().join("dangling.xml");
+        symlink(dir.path().join("new.xml"), &dangling).unwrap();
+        write_atomic(&dangling, "third").unwrap();
+        assert!(dangling.is_symlink());
+        assert_eq!(fs::read_to_string(dir.path().join("new.xml")).unwrap(), "third");
+
+        // Devices aren't replaced
+        write_atomic(Path::new("/dev/null"), "gone").unwrap();
+        assert!(!fs::metadata("/dev/null").unwrap().is_file());
+    }
+
+    #[test]
+    fn test_gc_removes_corrupt_and_evicts_oldest() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().to_path_buf();
+
+        // Intact asset: "hello" hashes to 2cf24dba5fb0...
+        fs::write(root.join("hello-2cf24dba5fb0"), "hello").unwrap();
+        // Asset whose content no longer matches its digest
+        fs::write(root.join("bye-2cf24dba5fb0"), "bye").unwrap();
+        // Undecodable scan cache and an abandoned partial write
+        fs::create_dir_all(root.join("cache")).unwrap();
+        fs::write(root.join("cache/repo.cache"), "garbage").unwrap();
+        fs::write(root.join("cache/repo.cache.part1"), "half").unwrap();
+        age(&root.join("cache/repo.cache.part1"), 2 * 60 * 60);
+        // Two unrecognized files, the older one evicted first
+        fs::write(root.join("old.bin"), vec![0u8; 100]).unwrap();
+        age(&root.join("old.bin"), 600);
+        fs::write(root.join("new.bin"), vec![0u8; 100]).unwrap();
+
+        let report = CacheGc::new()
+            .with_max_size(150)
+            .with_dry_run(true)
+            .run(std::slice::from_ref(&root))
+            .unwrap();
+        assert_eq!(report.corrupt.len(), 3);
+        assert!(root.join("bye-2cf24dba5fb0").exists());
+
+        let report = CacheGc::new()
+            .with_max_size(150)
+            .run(&[root.clone(), root.join("missing")])
+            .unwrap();
+        let mut corrupt: Vec<_> = report.corrupt.iter().map(|e| e.kind).collect();
+        corrupt.sort_by_key(|k| k.name());
+        assert_eq!(corrupt, [EntryKind::Asset, EntryKind::Partial, EntryKind::RepoCache]);
+        assert_eq!(report.evicted.len(), 1);
+        assert_eq!(report.evicted[0].path, root.join("old.bin"));
+        assert_eq!(report.remaining_bytes(), 105);
+
+        assert!(!root.join("bye-2cf24dba5fb0").exists());
+        assert!(!root.join("old.bin").exists());
+        assert!(root.join("hello-2cf24dba5fb0").exists());
+        assert!(root.join("new.bin").exists());
+    }
+
+    #[test]
+    fn test_gc_skips_locked_entries() {
+        let dir = tempfile::tempdir().unwrap();
+        let entry = dir.path().join("bye-2cf24dba5fb0");
+        fs::write(&entry, "bye").unwrap();
+
+        let _lock = CacheLock::shared(&entry).unwrap();
+        let report = CacheGc::new().run(&[dir.path().to_path_buf()]).unwrap();
+        assert!(report.corrupt.is_empty());
+        assert_eq!(report.in_use.len(), 1);
+        assert!(entry.exists());
+    }
+}
@@ -0,0 +1,79 @@
+//! Cooperative cancellation of long scans and packs
+//!
+//! A [`CancellationToken`] is shared between the code doing the work and
+//! whoever may want to stop it: a Ctrl-C handler, an `AbortSignal`, a GUI
+//! button. Cancelling only sets a flag; scans, map generation and formatting
+//! check it between files and give up with [`Cancelled`], so nothing is
+//! interrupted halfway through a file.
+//!
+//! ```
+//! use infiniloom_engine::{CancellationToken, Repository, ScanError, ScanOptions};
+//! # let dir = tempfile::tempdir()?;
+//! # std::fs::write(dir.path().join("app.py"), "def run():\n    pass\n")?;
+//!
+//! let cancel = CancellationToken::new();
+//! cancel.cancel();
+//! let result = Repository::scan(dir.path(), ScanOptions::default().with_cancellation(cancel));
+//! assert!(matches!(result, Err(ScanError::Cancelled(_))));
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use thiserror::Error;
+
+/// The operation was cancelled through its [`CancellationToken`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Error)]
+#[error("operation cancelled")]
+pub struct Cancelled;
+
+/// Shared flag asking long-running work to stop
+///
+/// Clones share the flag, so one can be handed to the work and another kept
+/// to cancel it. A token cannot be reset once cancelled.
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl CancellationToken {
+    /// A token that has not been cancelled
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Ask the work holding this token, or a clone of it, to stop
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    /// Whether [`cancel`](Self::cancel) has been called
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+
+    /// `Err(Cancelled)` once cancelled, for use with `?` between steps
+    pub fn check(&self) -> Result<(), Cancelled> {
+        if self.is_cancelled() {
+            Err(Cancelled)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_clones_share_cancellation() {
+        let token = CancellationToken::new();
+        let worker = token.clone();
+        assert_eq!(worker.check(), Ok(()));
+
+        token.cancel();
+        assert!(worker.is_cancelled());
+        assert_eq!(worker.check(), Err(Cancelled));
+    }
+}
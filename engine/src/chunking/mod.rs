@@ -1,7 +1,18 @@
 //! Intelligent code chunking for LLM context windows
 
-use crate::types::{RepoFile, Repository, TokenizerModel};
+use crate::ids::IdTable;
+use crate::output::escape_xml;
+use crate::types::{RepoFile, Repository, Symbol, SymbolKind, TokenizerModel};
 use serde::Serialize;
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BTreeSet, BinaryHeap, HashMap, HashSet};
+use std::fmt::Write;
+
+/// Most cross-references listed per chunk
+const MAX_CROSS_REFERENCES: usize = 50;
+
+/// Shortest symbol name worth linking; shorter names match too much
+const MIN_LINKED_NAME_LEN: usize = 3;
 
 /// A chunk of repository content
 #[derive(Debug, Clone, Serialize)]
@@ -14,6 +25,8 @@ pub struct Chunk {
     pub focus: String,
     /// Token count for this chunk
     pub tokens: u32,
+    /// Share of the chunk token budget in use (can exceed 1.0 for oversized files)
+    pub fill_ratio: f32,
     /// Files included in this chunk
     pub files: Vec<ChunkFile>,
     /// Context information
@@ -23,6 +36,8 @@ pub struct Chunk {
 /// A file within a chunk
 #[derive(Debug, Clone, Serialize)]
 pub struct ChunkFile {
+    /// Stable file ID, see [`crate::ids`]
+    pub id: String,
     /// Relative file path
     pub path: String,
     /// File content (may be compressed)
@@ -51,10 +66,25 @@ pub struct ChunkContext {
 pub struct CrossReference {
     /// Symbol name
     pub symbol: String,
+    /// Stable ID of the symbol
+    pub symbol_id: String,
     /// Chunk containing the symbol
     pub chunk_index: usize,
     /// File containing the symbol
     pub file: String,
+    /// Stable ID of the file
+    pub file_id: String,
+}
+
+/// Chunks of a repository for one model's tokenizer and budget
+#[derive(Debug, Clone, Serialize)]
+pub struct ChunkPlan {
+    /// Model whose token counts the chunks use
+    pub model: TokenizerModel,
+    /// Token budget per chunk
+    pub max_tokens: u32,
+    /// The chunks
+    pub chunks: Vec<Chunk>,
 }
 
 /// Chunking strategy
@@ -86,12 +116,20 @@ pub struct Chunker {
     overlap_tokens: u32,
     /// Target model for token counting
     model: TokenizerModel,
+    /// Budgets replacing `max_tokens` for particular models
+    model_budgets: Vec<(TokenizerModel, u32)>,
 }
 
 impl Chunker {
     /// Create a new chunker
     pub fn new(strategy: ChunkStrategy, max_tokens: u32) -> Self {
-        Self { strategy, max_tokens, overlap_tokens: 200, model: TokenizerModel::Claude }
+        Self {
+            strategy,
+            max_tokens,
+            overlap_tokens: 200,
+            model: TokenizerModel::Claude,
+            model_budgets: Vec::new(),
+        }
     }
 
     /// Set overlap tokens
@@ -106,15 +144,59 @@ impl Chunker {
         self
     }
 
+    /// Set the chunk budget for one model in [`Chunker::plan_for_models`]
+    pub fn with_model_budget(mut self, model: TokenizerModel, max_tokens: u32) -> Self {
+        self.model_budgets.retain(|(m, _)| *m != model);
+        self.model_budgets.push((model, max_tokens));
+        self
+    }
+
+    /// Chunk budget for `model`: its own, or else the chunker's
+    pub fn budget_for(&self, model: TokenizerModel) -> u32 {
+        self.model_budgets
+            .iter()
+            .find(|(m, _)| *m == model)
+            .map(|&(_, budget)| budget)
+            .unwrap_or(match self.strategy {
+                ChunkStrategy::Fixed { size } => size,
+                _ => self.max_tokens,
+            })
+    }
+
+    /// Chunk the repository once per model, each plan using that model's token
+    /// counts and budget
+    ///
+    /// Every file already carries counts for all models, so one scan serves
+    /// any number of targets; models with smaller windows just get more,
+    /// smaller chunks.
+    pub fn plan_for_models(&self, repo: &Repository, models: &[TokenizerModel]) -> Vec<ChunkPlan> {
+        models
+            .iter()
+            .map(|&model| {
+                let max_tokens = self.budget_for(model);
+                let strategy = match self.strategy {
+                    ChunkStrategy::Fixed { .. } => ChunkStrategy::Fixed { size: max_tokens },
+                    strategy => strategy,
+                };
+                let chunker = Chunker::new(strategy, max_tokens)
+                    .with_overlap(self.overlap_tokens)
+                    .with_model(model);
+                ChunkPlan { model, max_tokens, chunks: chunker.chunk(repo) }
+            })
+            .collect()
+    }
+
     /// Chunk a repository
     pub fn chunk(&self, repo: &Repository) -> Vec<Chunk> {
-        match self.strategy {
+        let mut chunks = match self.strategy {
             ChunkStrategy::Fixed { size } => self.fixed_chunk(repo, size),
             ChunkStrategy::File => self.file_chunk(repo),
             ChunkStrategy::Module => self.module_chunk(repo),
             ChunkStrategy::Semantic => self.semantic_chunk(repo),
             ChunkStrategy::Dependency => self.dependency_chunk(repo),
-        }
+        };
+        link_chunks(&mut chunks, repo);
+        chunks
     }
 
     /// Fixed-size chunking
@@ -187,48 +269,75 @@ impl Chunker {
     }
 
     /// Semantic chunking (group related files)
+    ///
+    /// Files are grouped by directory and the groups bin-packed so chunks
+    /// come out evenly filled; a directory is only split across chunks when
+    /// it does not fit in one.
     fn semantic_chunk(&self, repo: &Repository) -> Vec<Chunk> {
-        let mut chunks = Vec::new();
-        let mut current_files = Vec::new();
-        let mut current_tokens = 0u32;
-        let mut current_module: Option<String> = None;
-
-        // Sort files by path for better grouping
-        let mut sorted_files: Vec<_> = repo.files.iter().collect();
-        sorted_files.sort_by(|a, b| a.relative_path.cmp(&b.relative_path));
-
-        for file in sorted_files {
-            let file_tokens = file.token_count.get(self.model);
-            let file_module = file.relative_path.split('/').next().map(String::from);
-
-            // Check if we should start a new chunk
-            let should_split = current_tokens + file_tokens > self.max_tokens
-                || (current_module.is_some()
-                    && file_module.is_some()
-                    && current_module != file_module
-                    && current_tokens > self.max_tokens / 2);
+        let groups = self.directory_groups(repo);
+        let mut bins = pack_balanced(groups, self.max_tokens);
 
-            if should_split && !current_files.is_empty() {
-                chunks.push(self.create_chunk(chunks.len(), &current_files, current_tokens));
-
-                // Keep some overlap for context
-                current_files = self.get_overlap_files(&current_files);
-                current_tokens = current_files
-                    .iter()
-                    .map(|f| f.token_count.get(self.model))
-                    .sum();
+        // Present bins in path order so related chunks stay adjacent
+        for (_, files) in &mut bins {
+            files.sort_by(|a, b| a.relative_path.cmp(&b.relative_path));
+        }
+        bins.sort_by(|a, b| a.1[0].relative_path.cmp(&b.1[0].relative_path));
+
+        let mut chunks: Vec<Chunk> = Vec::with_capacity(bins.len());
+        let mut previous_last: Option<&RepoFile> = None;
+        for (mut tokens, files) in bins {
+            let last = files.last().copied();
+            let mut chunk_files: Vec<RepoFile> = Vec::with_capacity(files.len() + 1);
+
+            // Keep some overlap for context when it still fits
+            if let Some(overlap) = previous_last.filter(|f| {
+                let overlap_tokens = f.token_count.get(self.model);
+                overlap_tokens < self.overlap_tokens && tokens + overlap_tokens <= self.max_tokens
+            }) {
+                tokens += overlap.token_count.get(self.model);
+                chunk_files.push(overlap.clone());
             }
+            chunk_files.extend(files.into_iter().cloned());
 
-            current_files.push(file.clone());
-            current_tokens += file_tokens;
-            current_module = file_module;
+            chunks.push(self.create_chunk(chunks.len(), &chunk_files, tokens));
+            previous_last = last;
         }
 
-        if !current_files.is_empty() {
-            chunks.push(self.create_chunk(chunks.len(), &current_files, current_tokens));
+        self.finalize_chunks(chunks)
+    }
+
+    /// Files grouped by parent directory, with groups larger than the chunk
+    /// budget split in path order
+    fn directory_groups<'a>(&self, repo: &'a Repository) -> Vec<(u32, Vec<&'a RepoFile>)> {
+        let mut directories: BTreeMap<&str, Vec<&RepoFile>> = BTreeMap::new();
+        for file in &repo.files {
+            let dir = file
+                .relative_path
+                .rsplit_once('/')
+                .map_or("", |(dir, _)| dir);
+            directories.entry(dir).or_default().push(file);
         }
 
-        self.finalize_chunks(chunks)
+        let mut groups = Vec::new();
+        for (_, mut files) in directories {
+            files.sort_by(|a, b| a.relative_path.cmp(&b.relative_path));
+
+            let mut current: Vec<&RepoFile> = Vec::new();
+            let mut current_tokens = 0u32;
+            for file in files {
+                let file_tokens = file.token_count.get(self.model);
+                if current_tokens + file_tokens > self.max_tokens && !current.is_empty() {
+                    groups.push((current_tokens, std::mem::take(&mut current)));
+                    current_tokens = 0;
+                }
+                current.push(file);
+                current_tokens += file_tokens;
+            }
+            if !current.is_empty() {
+                groups.push((current_tokens, current));
+            }
+        }
+        groups
     }
 
     /// Dependency-based chunking
@@ -246,9 +355,11 @@ impl Chunker {
             total: 0, // Updated in finalize
             focus: focus.clone(),
             tokens,
+            fill_ratio: 0.0, // Updated in finalize
             files: files
                 .iter()
                 .map(|f| ChunkFile {
+                    id: String::new(), // Filled in by link_chunks
                     path: f.relative_path.clone(),
                     content: f.content.clone().unwrap_or_default(),
                     tokens: f.token_count.get(self.model),
@@ -287,25 +398,23 @@ impl Chunker {
         "Mixed content".to_owned()
     }
 
-    fn get_overlap_files(&self, files: &[RepoFile]) -> Vec<RepoFile> {
-        // Keep files that might be needed for context
-        // For now, just keep the last file if it's small enough
-        files
-            .last()
-            .filter(|f| f.token_count.get(self.model) < self.overlap_tokens)
-            .cloned()
-            .into_iter()
-            .collect()
-    }
-
     fn finalize_chunks(&self, mut chunks: Vec<Chunk>) -> Vec<Chunk> {
         let total = chunks.len();
+        let capacity = match self.strategy {
+            ChunkStrategy::Fixed { size } => size,
+            _ => self.max_tokens,
+        };
 
         // First pass: collect the focus strings we need
         let focus_strs: Vec<String> = chunks.iter().map(|c| c.focus.clone()).collect();
 
         for (i, chunk) in chunks.iter_mut().enumerate() {
             chunk.total = total;
+            chunk.fill_ratio = if capacity > 0 {
+                chunk.tokens as f32 / capacity as f32
+            } else {
+                0.0
+            };
 
             // Add previous summary
             if i > 0 {
@@ -314,7 +423,8 @@ impl Chunker {
 
             // Add next preview
             if i + 1 < total {
-                chunk.context.next_preview = Some(format!("Next: Chunk {}", i + 2));
+                chunk.context.next_preview =
+                    Some(format!("Next: Chunk {} ({})", i + 2, focus_strs[i + 1]));
             }
         }
 
@@ -322,6 +432,200 @@ impl Chunker {
     }
 }
 
+impl Chunk {
+    /// Render the chunk as a standalone XML document
+    ///
+    /// Chunk numbers are 1-based, matching `chunk_001.xml`-style file names.
+    pub fn to_xml(&self, repository: &str) -> String {
+        let mut out = String::new();
+        writeln!(out, r#"<?xml version="1.0" encoding="UTF-8"?>"#).unwrap();
+        writeln!(
+            out,
+            r#"<chunk repository="{}" index="{}" total="{}" focus="{}" tokens="{}">"#,
+            escape_xml(repository),
+            self.index + 1,
+            self.total,
+            escape_xml(&self.focus),
+            self.tokens
+        )
+        .unwrap();
+
+        let context = &self.context;
+        writeln!(out, "  <context>").unwrap();
+        if let Some(previous) = &context.previous_summary {
+            writeln!(out, "    <previous>{}</previous>", escape_xml(previous)).unwrap();
+        }
+        writeln!(out, "    <current_focus>{}</current_focus>", escape_xml(&context.current_focus))
+            .unwrap();
+        if let Some(next) = &context.next_preview {
+            writeln!(out, "    <next_preview>{}</next_preview>", escape_xml(next)).unwrap();
+        }
+        if !context.cross_references.is_empty() {
+            writeln!(out, "    <cross_references>").unwrap();
+            for reference in &context.cross_references {
+                writeln!(
+                    out,
+                    r#"      <reference symbol="{}" chunk="{}" file="{}" symbol_id="{}" file_id="{}"/>"#,
+                    escape_xml(&reference.symbol),
+                    reference.chunk_index + 1,
+                    escape_xml(&reference.file),
+                    reference.symbol_id,
+                    reference.file_id
+                )
+                .unwrap();
+            }
+            writeln!(out, "    </cross_references>").unwrap();
+        }
+        writeln!(out, "  </context>").unwrap();
+
+        writeln!(out, "  <files>").unwrap();
+        for file in &self.files {
+            writeln!(
+                out,
+                r#"    <file path="{}" tokens="{}" id="{}"><![CDATA[{}]]></file>"#,
+                escape_xml(&file.path),
+                file.tokens,
+                file.id,
+                file.content.replace("]]>", "]]]]><![CDATA[>")
+            )
+            .unwrap();
+        }
+        writeln!(out, "  </files>").unwrap();
+        writeln!(out, "</chunk>").unwrap();
+        out
+    }
+}
+
+/// Fill in file IDs and cross-references: symbols used in a chunk but
+/// defined in another
+fn link_chunks(chunks: &mut [Chunk], repo: &Repository) {
+    let ids = IdTable::new(repo);
+    for file in chunks.iter_mut().flat_map(|c| c.files.iter_mut()) {
+        file.id = ids.file(&file.path).unwrap_or_default().to_owned();
+    }
+
+    let symbols: HashMap<&str, &[Symbol]> = repo
+        .files
+        .iter()
+        .map(|f| (f.relative_path.as_str(), f.symbols.as_slice()))
+        .collect();
+    // Names defined in several files (`new`, `from`, ...) can't be resolved by name
+    let mut defining_files: HashMap<&str, usize> = HashMap::new();
+    for file in &repo.files {
+        let names: HashSet<&str> = file.symbols.iter().map(|s| s.name.as_str()).collect();
+        for name in names {
+            *defining_files.entry(name).or_default() += 1;
+        }
+    }
+    let linkable = |path: &str| {
+        symbols
+            .get(path)
+            .copied()
+            .unwrap_or_default()
+            .iter()
+            .filter(|s| {
+                !matches!(s.kind, SymbolKind::Import | SymbolKind::Export)
+                    && !is_method(s)
+                    && s.name.chars().count() >= MIN_LINKED_NAME_LEN
+                    && defining_files.get(s.name.as_str()) == Some(&1)
+            })
+    };
+
+    // First chunk defining each symbol, and every symbol each chunk defines
+    let mut definitions: HashMap<&str, (usize, String)> = HashMap::new();
+    let mut defined: Vec<HashSet<&str>> = Vec::with_capacity(chunks.len());
+    for chunk in chunks.iter() {
+        let mut names = HashSet::new();
+        for file in &chunk.files {
+            for symbol in linkable(&file.path) {
+                definitions
+                    .entry(symbol.name.as_str())
+                    .or_insert_with(|| (chunk.index, file.path.clone()));
+                names.insert(symbol.name.as_str());
+            }
+        }
+        defined.push(names);
+    }
+
+    for (chunk, own) in chunks.iter_mut().zip(&defined) {
+        let used: BTreeSet<&str> = chunk
+            .files
+            .iter()
+            .flat_map(|f| {
+                f.content
+                    .split(|c: char| !(c.is_alphanumeric() || c == '_'))
+            })
+            .filter(|word| !word.is_empty() && !own.contains(word))
+            .collect();
+
+        let mut references: Vec<CrossReference> = used
+            .into_iter()
+            .filter_map(|word| {
+                let (chunk_index, file) = definitions.get(word)?;
+                Some(CrossReference {
+                    symbol: word.to_owned(),
+                    symbol_id: ids.symbol_named(file, word).unwrap_or_default().to_owned(),
+                    chunk_index: *chunk_index,
+                    file: file.clone(),
+                    file_id: ids.file(file).unwrap_or_default().to_owned(),
+                })
+            })
+            .collect();
+        references.sort_by_key(|r| r.chunk_index);
+        references.truncate(MAX_CROSS_REFERENCES);
+        chunk.context.cross_references = references;
+    }
+}
+
+/// Methods are called through a receiver, so a bare name match says little;
+/// parsers report some as functions, recognizable by a `self` parameter
+fn is_method(symbol: &Symbol) -> bool {
+    symbol.kind == SymbolKind::Method
+        || symbol.signature.as_deref().is_some_and(|signature| {
+            signature
+                .split(|c: char| !(c.is_alphanumeric() || c == '_'))
+                .any(|word| word == "self")
+        })
+}
+
+/// Balance `groups` of `(tokens, files)` across as few bins of `capacity`
+/// tokens as possible
+///
+/// Worst-fit decreasing: the largest groups are placed first, each into the
+/// least-filled bin, starting from the lower bound on the number of bins. A
+/// new bin is opened only when a group fits nowhere.
+fn pack_balanced<T>(mut groups: Vec<(u32, Vec<T>)>, capacity: u32) -> Vec<(u32, Vec<T>)> {
+    groups.sort_by_key(|(tokens, _)| Reverse(*tokens));
+
+    let total: u64 = groups.iter().map(|(tokens, _)| u64::from(*tokens)).sum();
+    let lower_bound = total.div_ceil(u64::from(capacity.max(1))) as usize;
+    let initial = lower_bound.clamp(1, groups.len().max(1));
+
+    let mut bins: Vec<(u32, Vec<T>)> = (0..initial).map(|_| (0, Vec::new())).collect();
+    let mut heap: BinaryHeap<Reverse<(u32, usize)>> =
+        (0..initial).map(|i| Reverse((0, i))).collect();
+
+    for (tokens, files) in groups {
+        let target = match heap.peek() {
+            // Empty bins always accept, so oversized groups still get placed
+            Some(&Reverse((load, i))) if load == 0 || load + tokens <= capacity => {
+                heap.pop();
+                i
+            },
+            _ => {
+                bins.push((0, Vec::new()));
+                bins.len() - 1
+            },
+        };
+        bins[target].0 += tokens;
+        bins[target].1.extend(files);
+        heap.push(Reverse((bins[target].0, target)));
+    }
+
+    bins.retain(|(_, files)| !files.is_empty());
+    bins
+}
+
 #[cfg(test)]
 #[allow(clippy::str_to_string)]
 mod tests {
@@ -344,9 +648,9 @@ mod tests {
                     gemini: 470,
                     llama: 460,
                 },
-                symbols: Vec::new(),
                 importance: 0.5,
                 content: Some(format!("# File {}\ndef func{}(): pass", i, i)),
+                ..Default::default()
             });
         }
 
@@ -365,6 +669,24 @@ mod tests {
             .all(|c| c.tokens <= 1000 || c.files.len() == 1));
     }
 
+    #[test]
+    fn test_plan_for_models() {
+        let repo = create_test_repo();
+        let plans = Chunker::new(ChunkStrategy::Fixed { size: 1000 }, 1000)
+            .with_model_budget(TokenizerModel::Gpt4o, 3000)
+            .plan_for_models(&repo, &[TokenizerModel::Claude, TokenizerModel::Gpt4o]);
+
+        assert_eq!(plans.len(), 2);
+        assert_eq!(plans[0].max_tokens, 1000);
+        assert_eq!(plans[0].chunks.len(), 3);
+        assert_eq!(plans[0].chunks[0].tokens, 1000);
+
+        // Counted with the other tokenizer against the other budget
+        assert_eq!(plans[1].max_tokens, 3000);
+        assert_eq!(plans[1].chunks.len(), 1);
+        assert_eq!(plans[1].chunks[0].tokens, 5 * 480);
+    }
+
     #[test]
     fn test_file_chunking() {
         let repo = create_test_repo();
@@ -384,4 +706,82 @@ mod tests {
         // All chunks should have correct total
         assert!(chunks.iter().all(|c| c.total == chunks.len()));
     }
+
+    #[test]
+    fn test_semantic_chunking_balances_modules() {
+        let mut repo = Repository::new("test", "/tmp/test");
+        for (path, tokens) in [
+            ("a/one.py", 600),
+            ("a/two.py", 600),
+            ("b/one.py", 900),
+            ("c/one.py", 300),
+            ("d/one.py", 350),
+            ("d/two.py", 350),
+        ] {
+            let mut file = create_test_repo().files.remove(0);
+            file.relative_path = path.to_string();
+            file.token_count.claude = tokens;
+            repo.files.push(file);
+        }
+
+        let chunker = Chunker::new(ChunkStrategy::Semantic, 1600).with_overlap(0);
+        let chunks = chunker.chunk(&repo);
+
+        // Greedy path-order splitting would need three chunks, one under half full
+        assert_eq!(chunks.len(), 2);
+        assert!(chunks
+            .iter()
+            .all(|c| c.fill_ratio > 0.9 && c.fill_ratio <= 1.0));
+
+        // Each directory lands in exactly one chunk
+        for dir in ["a/", "b/", "c/", "d/"] {
+            let holding = chunks
+                .iter()
+                .filter(|c| c.files.iter().any(|f| f.path.starts_with(dir)))
+                .count();
+            assert_eq!(holding, 1, "{} split across chunks", dir);
+        }
+    }
+
+    #[test]
+    fn test_cross_references_and_xml() {
+        let mut repo = create_test_repo();
+        repo.files.truncate(2);
+        repo.files[0].content = Some("def parse_config(): pass".to_string());
+        repo.files[0].symbols = vec![Symbol::new("parse_config", SymbolKind::Function)];
+        repo.files[1].content = Some("config = parse_config()  # ]]> here".to_string());
+
+        let chunks = Chunker::new(ChunkStrategy::File, 8000).chunk(&repo);
+        assert_eq!(chunks.len(), 2);
+        assert!(chunks[0].context.cross_references.is_empty());
+        let reference = &chunks[1].context.cross_references[0];
+        assert_eq!(reference.symbol, "parse_config");
+        assert_eq!(reference.chunk_index, 0);
+        assert_eq!(reference.file, "src/file0.py");
+        let ids = IdTable::new(&repo);
+        assert_eq!(Some(reference.file_id.as_str()), ids.file("src/file0.py"));
+        assert_eq!(chunks[0].files[0].id, reference.file_id);
+        assert_eq!(
+            Some(reference.symbol_id.as_str()),
+            ids.symbol_named("src/file0.py", "parse_config")
+        );
+
+        assert_eq!(chunks[0].context.next_preview.as_deref(), Some("Next: Chunk 2 (src module)"));
+
+        let xml = chunks[1].to_xml("test");
+        assert!(xml.contains(r#"<chunk repository="test" index="2" total="2""#));
+        assert!(xml.contains("<previous>Previous: src module</previous>"));
+        assert!(xml.contains(&format!(
+            r#"<reference symbol="parse_config" chunk="1" file="src/file0.py" symbol_id="{}" file_id="{}"/>"#,
+            reference.symbol_id, reference.file_id
+        )));
+        assert!(xml.contains("]]]]><![CDATA[> here"));
+    }
+
+    #[test]
+    fn test_pack_balanced_oversized_group() {
+        let bins = pack_balanced(vec![(3000, vec!["big"]), (200, vec!["small"])], 1000);
+        assert_eq!(bins.len(), 2);
+        assert_eq!(bins[0], (3000, vec!["big"]));
+    }
 }
@@ -0,0 +1,442 @@
+//! Build, test and run commands declared by a repository
+//!
+//! "How do I run the tests?" is usually the first question about an
+//! unfamiliar codebase, and the answer is already written down in the
+//! repository. [`detect_commands`] reads it from:
+//!
+//! - **Makefile**: every target, as `make <target>`
+//! - **package.json**: every script, run with the package manager whose
+//!   lockfile is present (npm, yarn, pnpm or bun)
+//! - **justfile**: every recipe, as `just <recipe>`
+//! - **CI configs**: `run:` steps of GitHub Actions workflows and `script:`
+//!   lines of `.gitlab-ci.yml`, when they build, test, lint or format
+//! - **Manifests**: the standard commands of Cargo, Go, Maven, Gradle and
+//!   Python projects, which need no declaring
+//!
+//! Each [`ProjectCommand`] is classified by [`CommandKind`] from its name, so
+//! formatters can list the canonical commands in the overview.
+
+use serde::{Deserialize, Serialize};
+use serde_yaml::Value as YamlValue;
+use std::collections::HashSet;
+use std::path::Path;
+
+/// What a command is for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CommandKind {
+    /// Installs dependencies or sets up the environment
+    Install,
+    /// Compiles or bundles the project
+    Build,
+    /// Runs the test suite
+    Test,
+    /// Runs linters or type checkers
+    Lint,
+    /// Formats the code
+    Format,
+    /// Starts the application or a dev server
+    Run,
+    /// Anything else
+    Other,
+}
+
+impl CommandKind {
+    /// Lowercase name
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Install => "install",
+            Self::Build => "build",
+            Self::Test => "test",
+            Self::Lint => "lint",
+            Self::Format => "format",
+            Self::Run => "run",
+            Self::Other => "other",
+        }
+    }
+
+    /// Classify a target, script or recipe name, or a command line
+    ///
+    /// Looks at the words of `name`, so `test:e2e` and `cargo test` are tests
+    /// but `inspect` is not.
+    pub fn classify(name: &str) -> Self {
+        let name = name.to_lowercase();
+        let words: Vec<&str> = name.split(|c: char| !c.is_ascii_alphanumeric()).collect();
+        let has = |keys: &[&str]| words.iter().any(|w| keys.iter().any(|k| w.starts_with(k)));
+        if has(&["test", "unittest", "spec", "pytest", "jest", "vitest", "coverage"]) {
+            Self::Test
+        } else if has(&["lint", "clippy", "eslint", "ruff", "mypy", "vet", "typecheck"]) {
+            Self::Lint
+        } else if has(&["fmt", "format", "prettier", "black"]) {
+            Self::Format
+        } else if has(&["install", "setup", "bootstrap", "deps"]) {
+            Self::Install
+        } else if has(&["build", "compile", "bundle", "dist", "release"]) {
+            Self::Build
+        } else if has(&["run", "start", "serve", "dev", "watch"]) {
+            Self::Run
+        } else {
+            Self::Other
+        }
+    }
+}
+
+/// A command found in the repository
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProjectCommand {
+    /// What the command is for
+    pub kind: CommandKind,
+    /// Command line to run from the repository root
+    pub command: String,
+    /// File that declares it, relative to the repository root
+    pub source: String,
+}
+
+/// Find the commands declared by the repository at `root`
+///
+/// Commands come in the order of their sources (Makefile, package.json,
+/// justfile, CI, manifests) and then of declaration; duplicates are dropped.
+pub fn detect_commands(root: &Path) -> Vec<ProjectCommand> {
+    let mut commands = Vec::new();
+    let read = |name: &str| std::fs::read_to_string(root.join(name)).ok();
+
+    for name in ["Makefile", "makefile", "GNUmakefile"] {
+        if let Some(content) = read(name) {
+            push_named(&mut commands, name, "make", makefile_targets(&content));
+            break;
+        }
+    }
+
+    if let Some(content) = read("package.json") {
+        let runner = package_runner(root);
+        let scripts = package_scripts(&content);
+        commands.extend(scripts.into_iter().map(|script| ProjectCommand {
+            kind: CommandKind::classify(&script),
+            command: match script.as_str() {
+                "test" | "start" => format!("{} {}", runner, script),
+                _ => format!("{} run {}", runner, script),
+            },
+            source: "package.json".to_owned(),
+        }));
+    }
+
+    for name in ["justfile", "Justfile", ".justfile"] {
+        if let Some(content) = read(name) {
+            push_named(&mut commands, name, "just", justfile_recipes(&content));
+            break;
+        }
+    }
+
+    if let Ok(entries) = std::fs::read_dir(root.join(".github/workflows")) {
+        let mut workflows: Vec<_> = entries
+            .filter_map(Result::ok)
+            .map(|entry| entry.file_name().to_string_lossy().into_owned())
+            .filter(|name| name.ends_with(".yml") || name.ends_with(".yaml"))
+            .collect();
+        workflows.sort();
+        for name in workflows {
+            let source = format!(".github/workflows/{}", name);
+            if let Some(content) = read(&source) {
+                push_ci(&mut commands, &source, github_run_steps(&content));
+            }
+        }
+    }
+
+    if let Some(content) = read(".gitlab-ci.yml") {
+        push_ci(&mut commands, ".gitlab-ci.yml", gitlab_scripts(&content));
+    }
+
+    commands.extend(manifest_commands(root));
+
+    let mut seen = HashSet::new();
+    commands.retain(|c| seen.insert(c.command.clone()));
+    commands
+}
+
+fn push_named(commands: &mut Vec<ProjectCommand>, source: &str, tool: &str, names: Vec<String>) {
+    commands.extend(names.into_iter().map(|name| ProjectCommand {
+        kind: CommandKind::classify(&name),
+        command: format!("{} {}", tool, name),
+        source: source.to_owned(),
+    }));
+}
+
+/// Conventional commands of the build manifests at `root`
+fn manifest_commands(root: &Path) -> Vec<ProjectCommand> {
+    let mut commands = Vec::new();
+    let mut push = |source: &str, kind: CommandKind, command: &str| {
+        commands.push(ProjectCommand {
+            kind,
+            command: command.to_owned(),
+            source: source.to_owned(),
+        });
+    };
+
+    if root.join("Cargo.toml").is_file() {
+        push("Cargo.toml", CommandKind::Build, "cargo build");
+        push("Cargo.toml", CommandKind::Test, "cargo test");
+        if root.join("src/main.rs").is_file() {
+            push("Cargo.toml", CommandKind::Run, "cargo run");
+        }
+    }
+    if root.join("go.mod").is_file() {
+        push("go.mod", CommandKind::Build, "go build ./...");
+        push("go.mod", CommandKind::Test, "go test ./...");
+    }
+    if root.join("pom.xml").is_file() {
+        push("pom.xml", CommandKind::Build, "mvn package");
+        push("pom.xml", CommandKind::Test, "mvn test");
+    }
+    if let Some(gradle) = ["build.gradle", "build.gradle.kts"]
+        .into_iter()
+        .find(|name| root.join(name).is_file())
+    {
+        push(gradle, CommandKind::Build, "gradle build");
+        push(gradle, CommandKind::Test, "gradle test");
+    }
+    if let Some(python) = ["pyproject.toml", "setup.py"]
+        .into_iter()
+        .find(|name| root.join(name).is_file())
+    {
+        push(python, CommandKind::Install, "pip install -e .");
+    }
+    if root.join("requirements.txt").is_file() {
+        push("requirements.txt", CommandKind::Install, "pip install -r requirements.txt");
+    }
+    commands
+}
+
+/// CI steps are mostly plumbing; only keep the ones that say what they do
+fn push_ci(commands: &mut Vec<ProjectCommand>, source: &str, lines: Vec<String>) {
+    for line in lines {
+        let kind = CommandKind::classify(&line);
+        if !matches!(kind, CommandKind::Other | CommandKind::Install | CommandKind::Run) {
+            commands.push(ProjectCommand { kind, command: line, source: source.to_owned() });
+        }
+    }
+}
+
+/// Targets of a Makefile, skipping special, pattern and variable lines
+fn makefile_targets(content: &str) -> Vec<String> {
+    let mut targets = Vec::new();
+    for line in content.lines() {
+        if line.starts_with(['\t', ' ', '#', '.']) {
+            continue;
+        }
+        let Some((head, rest)) = line.split_once(':') else {
+            continue;
+        };
+        if rest.starts_with('=') || head.contains(['=', '%', '$']) {
+            continue;
+        }
+        for target in head.split_whitespace() {
+            if !targets.iter().any(|t| t == target) {
+                targets.push(target.to_owned());
+            }
+        }
+    }
+    targets
+}
+
+/// Recipes of a justfile, skipping settings, aliases and assignments
+fn justfile_recipes(content: &str) -> Vec<String> {
+    let mut recipes = Vec::new();
+    for line in content.lines() {
+        if line.starts_with([' ', '\t', '#', '[']) || line.contains(":=") {
+            continue;
+        }
+        let line = line.trim_start_matches('@');
+        let Some((head, _)) = line.split_once(':') else {
+            continue;
+        };
+        let Some(name) = head.split_whitespace().next() else {
+            continue;
+        };
+        if matches!(name, "set" | "alias" | "export" | "import" | "mod") || name.starts_with('_') {
+            continue;
+        }
+        recipes.push(name.to_owned());
+    }
+    recipes
+}
+
+/// Script names of a package.json, in declaration order
+fn package_scripts(content: &str) -> Vec<String> {
+    // serde_json without `preserve_order` sorts keys, so scan the object by hand
+    let Some(start) = content.find("\"scripts\"") else {
+        return Vec::new();
+    };
+    let Ok(value) = serde_json::from_str::<serde_json::Value>(content) else {
+        return Vec::new();
+    };
+    let Some(scripts) = value.get("scripts").and_then(|s| s.as_object()) else {
+        return Vec::new();
+    };
+    let mut names: Vec<(usize, String)> = scripts
+        .keys()
+        .map(|name| {
+            let position = content[start..]
+                .find(&format!("\"{}\"", name))
+                .map_or(usize::MAX, |p| start + p);
+            (position, name.clone())
+        })
+        .collect();
+    names.sort();
+    names.into_iter().map(|(_, name)| name).collect()
+}
+
+/// Package manager for the lockfile in `root`
+fn package_runner(root: &Path) -> &'static str {
+    if root.join("pnpm-lock.yaml").exists() {
+        "pnpm"
+    } else if root.join("yarn.lock").exists() {
+        "yarn"
+    } else if root.join("bun.lockb").exists() || root.join("bun.lock").exists() {
+        "bun"
+    } else {
+        "npm"
+    }
+}
+
+/// Lines of the `run:` steps of a GitHub Actions workflow
+fn github_run_steps(content: &str) -> Vec<String> {
+    let Ok(workflow) = serde_yaml::from_str::<YamlValue>(content) else {
+        return Vec::new();
+    };
+    let mut lines = Vec::new();
+    let Some(jobs) = workflow.get("jobs").and_then(YamlValue::as_mapping) else {
+        return lines;
+    };
+    for job in jobs.values() {
+        let Some(steps) = job.get("steps").and_then(YamlValue::as_sequence) else {
+            continue;
+        };
+        for step in steps {
+            if let Some(run) = step.get("run").and_then(YamlValue::as_str) {
+                lines.extend(script_lines(run));
+            }
+        }
+    }
+    lines
+}
+
+/// Lines of the `script:` entries of a GitLab CI config
+fn gitlab_scripts(content: &str) -> Vec<String> {
+    let Ok(config) = serde_yaml::from_str::<YamlValue>(content) else {
+        return Vec::new();
+    };
+    let mut lines = Vec::new();
+    let Some(jobs) = config.as_mapping() else {
+        return lines;
+    };
+    for job in jobs.values() {
+        match job.get("script") {
+            Some(YamlValue::String(script)) => lines.extend(script_lines(script)),
+            Some(YamlValue::Sequence(items)) => {
+                for item in items.iter().filter_map(YamlValue::as_str) {
+                    lines.extend(script_lines(item));
+                }
+            },
+            _ => {},
+        }
+    }
+    lines
+}
+
+fn script_lines(script: &str) -> impl Iterator<Item = String> + '_ {
+    script
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(str::to_owned)
+}
+
+#[cfg(test)]
+#[allow(clippy::str_to_string)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_classify() {
+        assert_eq!(CommandKind::classify("test-unit"), CommandKind::Test);
+        assert_eq!(CommandKind::classify("cargo clippy -- -D warnings"), CommandKind::Lint);
+        assert_eq!(CommandKind::classify("fmt"), CommandKind::Format);
+        assert_eq!(CommandKind::classify("build"), CommandKind::Build);
+        assert_eq!(CommandKind::classify("dev"), CommandKind::Run);
+        assert_eq!(CommandKind::classify("clean"), CommandKind::Other);
+    }
+
+    #[test]
+    fn test_detect_commands() {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path();
+        std::fs::write(
+            root.join("Makefile"),
+            "CC := gcc\n.PHONY: build test\nbuild: deps\n\tgo build ./...\ntest:\n\tgo test ./...\n%.o: %.c\n",
+        )
+        .unwrap();
+        std::fs::write(
+            root.join("package.json"),
+            r#"{"name": "app", "scripts": {"start": "node .", "lint": "eslint ."}}"#,
+        )
+        .unwrap();
+        std::fs::write(root.join("yarn.lock"), "").unwrap();
+        std::fs::write(
+            root.join("justfile"),
+            "set shell := [\"bash\", \"-c\"]\nalias t := test\n# Run tests\ntest filter='':\n    cargo test {{filter}}\n_helper:\n    true\n",
+        )
+        .unwrap();
+        std::fs::create_dir_all(root.join(".github/workflows")).unwrap();
+        std::fs::write(
+            root.join(".github/workflows/ci.yml"),
+            "on: push\njobs:\n  check:\n    runs-on: ubuntu-latest\n    steps:\n      - uses: actions/checkout@v4\n      - run: echo start\n      - run: |\n          cargo fmt --check\n          cargo test --workspace\n",
+        )
+        .unwrap();
+
+        let detected = detect_commands(root);
+        let found: Vec<(CommandKind, &str, &str)> = detected
+            .iter()
+            .map(|c| (c.kind, c.command.as_str(), c.source.as_str()))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                (CommandKind::Build, "make build", "Makefile"),
+                (CommandKind::Test, "make test", "Makefile"),
+                (CommandKind::Run, "yarn start", "package.json"),
+                (CommandKind::Lint, "yarn run lint", "package.json"),
+                (CommandKind::Test, "just test", "justfile"),
+                (CommandKind::Format, "cargo fmt --check", ".github/workflows/ci.yml"),
+                (CommandKind::Test, "cargo test --workspace", ".github/workflows/ci.yml"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_manifest_commands() {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path();
+        std::fs::create_dir(root.join("src")).unwrap();
+        std::fs::write(root.join("Cargo.toml"), "[package]\nname = \"demo\"\n").unwrap();
+        std::fs::write(root.join("src/main.rs"), "fn main() {}\n").unwrap();
+        std::fs::write(root.join("requirements.txt"), "requests\n").unwrap();
+        // Declared commands come first and aren't repeated
+        std::fs::write(root.join(".gitlab-ci.yml"), "test:\n  script: cargo test\n").unwrap();
+
+        let commands: Vec<String> = detect_commands(root)
+            .into_iter()
+            .map(|c| c.command)
+            .collect();
+        assert_eq!(
+            commands,
+            ["cargo test", "cargo build", "cargo run", "pip install -r requirements.txt"]
+        );
+    }
+
+    #[test]
+    fn test_no_commands() {
+        let dir = TempDir::new().unwrap();
+        assert!(detect_commands(dir.path()).is_empty());
+    }
+}
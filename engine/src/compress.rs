@@ -0,0 +1,490 @@
+//! Compression levels applied to file contents
+//!
+//! A [`Compressor`] turns a [`CompressionLevel`] into content transforms, so
+//! the CLI and the bindings shrink files the same way. Each level adds to
+//! the one before:
+//!
+//! - **Minimal**: drops blank lines and trailing whitespace
+//! - **Balanced**: also strips comments, in the file's comment syntax
+//! - **Aggressive**: also replaces function and method bodies with `...`,
+//!   keeping imports, type definitions and class skeletons, and strips
+//!   Python docstrings
+//! - **Extreme**: keeps only the declaration lines of symbols
+//! - **Semantic**: strips comments and docstrings like Aggressive but keeps
+//!   bodies, then collapses near-duplicate function bodies across files
+//!   (with the `embeddings` feature, see [`crate::semantic`])
+//!
+//! Lines keep their original numbers (see [`rewrite_lines`]), and token
+//! counts are scaled to the new content size.
+
+use crate::languages;
+use crate::scan::parse_symbols;
+use crate::transform::{rewrite_lines, scale_counts};
+use crate::types::{CompressionLevel, RepoFile, Repository, Symbol, SymbolKind};
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+/// Applies a compression level to file contents
+#[derive(Debug, Clone, Copy)]
+pub struct Compressor {
+    level: CompressionLevel,
+    remove_comments: bool,
+    remove_empty_lines: bool,
+}
+
+impl Compressor {
+    /// Create a compressor for `level`
+    pub fn new(level: CompressionLevel) -> Self {
+        Self { level, remove_comments: false, remove_empty_lines: false }
+    }
+
+    /// Strip comments even below the Balanced level
+    pub fn with_remove_comments(mut self, enabled: bool) -> Self {
+        self.remove_comments = enabled;
+        self
+    }
+
+    /// Drop blank lines even at the None level
+    pub fn with_remove_empty_lines(mut self, enabled: bool) -> Self {
+        self.remove_empty_lines = enabled;
+        self
+    }
+
+    /// The compression level
+    pub fn level(&self) -> CompressionLevel {
+        self.level
+    }
+
+    fn strips_comments(&self) -> bool {
+        self.remove_comments || self.level >= CompressionLevel::Balanced
+    }
+
+    fn strips_empty_lines(&self) -> bool {
+        self.remove_empty_lines || self.level >= CompressionLevel::Minimal
+    }
+
+    /// Compress one file's content; returns whether it changed
+    pub fn compress_file(&self, file: &mut RepoFile) -> bool {
+        let Some(before) = file.content.as_ref().map(String::len) else {
+            return false;
+        };
+        let language = file.language.clone().unwrap_or_default();
+        let mut changed = false;
+
+        // Symbol lines refer to the original content, so this goes first
+        if self.level == CompressionLevel::Extreme {
+            changed |= keep_declarations(file);
+        } else if self.level == CompressionLevel::Aggressive {
+            changed |= elide_bodies(file);
+        }
+        if self.level >= CompressionLevel::Aggressive && language == "python" {
+            let mut stripper = DocstringStripper::default();
+            changed |= rewrite_lines(file, |line| stripper.strip(line));
+        }
+        if self.strips_comments() && !language.is_empty() {
+            let mut stripper = CommentStripper::new(&language);
+            changed |= rewrite_lines(file, |line| stripper.strip(line));
+        }
+        if self.strips_empty_lines() {
+            changed |= rewrite_lines(file, |line| {
+                (!line.trim().is_empty()).then_some(Cow::Borrowed(line.trim_end()))
+            });
+        }
+
+        if changed {
+            let after = file.content.as_ref().map_or(0, String::len);
+            file.token_count = scale_counts(&file.token_count, after as f64 / before.max(1) as f64);
+        }
+        changed
+    }
+
+    /// Compress every file in a repository; returns the number that changed
+    pub fn compress(&self, repo: &mut Repository) -> usize {
+        if self.level == CompressionLevel::None && !self.remove_comments && !self.remove_empty_lines
+        {
+            return 0;
+        }
+
+        // Semantic clustering finds bodies from symbols, which need the
+        // original content to be parsed
+        #[cfg(feature = "embeddings")]
+        if self.level == CompressionLevel::Semantic {
+            for file in repo.files.iter_mut().filter(|f| f.symbols.is_empty()) {
+                file.symbols = file_symbols(file).into_owned();
+            }
+        }
+
+        let changed: Vec<bool> = repo
+            .files
+            .iter_mut()
+            .map(|file| self.compress_file(file))
+            .collect();
+        let count = changed.iter().filter(|&&c| c).count();
+
+        #[cfg(feature = "embeddings")]
+        if self.level == CompressionLevel::Semantic {
+            let (_, collapsed) = crate::semantic::SemanticCompressor::new().collapse(repo);
+            return count + collapsed.into_iter().filter(|&i| !changed[i]).count();
+        }
+        count
+    }
+}
+
+/// Symbols of a file, parsed here if the scan skipped them
+///
+/// Parsing needs the content to be unchanged so far, so a file already
+/// rewritten without symbols has none.
+fn file_symbols(file: &RepoFile) -> Cow<'_, [Symbol]> {
+    if !file.symbols.is_empty() {
+        Cow::Borrowed(&file.symbols)
+    } else if file.line_numbers.is_none() {
+        Cow::Owned(parse_symbols(
+            file.content.as_deref().unwrap_or_default(),
+            Path::new(&file.relative_path),
+        ))
+    } else {
+        Cow::Borrowed(&[])
+    }
+}
+
+/// Keep only the lines declaring a symbol, imports left out
+fn keep_declarations(file: &mut RepoFile) -> bool {
+    let symbols = file_symbols(file);
+    if symbols.is_empty() {
+        return false;
+    }
+
+    let mut keep: HashSet<usize> = HashSet::new();
+    for symbol in symbols
+        .iter()
+        .filter(|s| s.kind != SymbolKind::Import && s.start_line > 0)
+    {
+        let lines = symbol
+            .signature
+            .as_deref()
+            .map_or(1, |s| s.lines().count().max(1));
+        let start = symbol.start_line as usize;
+        keep.extend(start..start + lines);
+    }
+    let flags: Vec<bool> = file
+        .numbered_lines()
+        .map(|(number, _)| keep.contains(&number))
+        .collect();
+    let mut flags = flags.into_iter();
+    rewrite_lines(file, |line| flags.next().unwrap_or(true).then_some(Cow::Borrowed(line)))
+}
+
+/// What happens to a line when bodies are elided
+enum BodyLine {
+    Keep,
+    Placeholder(String),
+    Drop,
+}
+
+/// Replace function and method bodies with a `...` line
+///
+/// Everything outside a body stays: imports, type definitions, class
+/// headers and fields, and the closing brace or `end` of each function.
+/// Functions nested in an elided body go with it.
+fn elide_bodies(file: &mut RepoFile) -> bool {
+    let plan: Vec<BodyLine> = {
+        let symbols = file_symbols(file);
+        let language = file.language.as_deref().unwrap_or_default();
+        let lines: HashMap<usize, &str> = file.numbered_lines().collect();
+        let mut bodies: Vec<(usize, usize)> = symbols
+            .iter()
+            .filter(|s| matches!(s.kind, SymbolKind::Function | SymbolKind::Method))
+            .filter(|s| s.start_line > 0 && s.end_line > s.start_line)
+            .filter_map(|s| body_range(&lines, language, s))
+            .collect();
+        if bodies.is_empty() {
+            return false;
+        }
+        bodies.sort_unstable();
+
+        let mut placeholders: HashMap<usize, String> = HashMap::new();
+        let mut elided: HashSet<usize> = HashSet::new();
+        let mut covered = 0;
+        for (start, end) in bodies {
+            if start <= covered {
+                continue;
+            }
+            let indent = (start..=end)
+                .filter_map(|n| lines.get(&n))
+                .find(|l| !l.trim().is_empty())
+                .map_or("", |l| &l[..l.len() - l.trim_start().len()]);
+            placeholders.insert(start, format!("{}...", indent));
+            elided.extend(start + 1..=end);
+            covered = end;
+        }
+        file.numbered_lines()
+            .map(|(number, _)| match placeholders.remove(&number) {
+                Some(text) => BodyLine::Placeholder(text),
+                None if elided.contains(&number) => BodyLine::Drop,
+                None => BodyLine::Keep,
+            })
+            .collect()
+    };
+
+    let mut plan = plan.into_iter();
+    rewrite_lines(file, |line| match plan.next() {
+        Some(BodyLine::Placeholder(text)) => Some(Cow::Owned(text)),
+        Some(BodyLine::Drop) => None,
+        Some(BodyLine::Keep) | None => Some(Cow::Borrowed(line)),
+    })
+}
+
+/// First and last line of a function's body, if it has one over several lines
+///
+/// The header ends at the first line opening a block (`{` or `:`), or at the
+/// signature line for `end`-delimited languages. A closing `}` or `end` on
+/// the last line is kept.
+pub(crate) fn body_range(
+    lines: &HashMap<usize, &str>,
+    language: &str,
+    symbol: &Symbol,
+) -> Option<(usize, usize)> {
+    let (start, end) = (symbol.start_line as usize, symbol.end_line as usize);
+    let header_end = if matches!(language, "ruby" | "lua" | "elixir") {
+        start
+            + symbol
+                .signature
+                .as_deref()
+                .map_or(1, |s| s.lines().count().max(1))
+            - 1
+    } else {
+        (start..end).find(|n| lines.get(n).is_some_and(|l| opens_block(l)))?
+    };
+    let closing = lines.get(&end).map(|l| l.trim());
+    let body_end = if closing.is_some_and(|l| l.starts_with('}') || l == "end") {
+        end - 1
+    } else {
+        end
+    };
+    (header_end < body_end).then_some((header_end + 1, body_end))
+}
+
+/// Whether a line ends with `{` or `:`, trailing comment aside
+fn opens_block(line: &str) -> bool {
+    let code = line.split(" //").next().unwrap_or_default();
+    let code = code.split(" #").next().unwrap_or_default().trim_end();
+    code.ends_with('{') || code.ends_with(':')
+}
+
+/// Removes comments line by line, in the comment syntax of one language
+#[derive(Debug, Clone)]
+pub struct CommentStripper {
+    line_comment: &'static str,
+    block_start: &'static str,
+    block_end: &'static str,
+    in_block_comment: bool,
+}
+
+impl CommentStripper {
+    /// Create a stripper for `language`; unknown languages are taken as C-style
+    pub fn new(language: &str) -> Self {
+        let (line_comment, (block_start, block_end)) = match languages::lookup(language) {
+            Some(info) => (info.line_comment.unwrap_or(""), info.block_comment.unwrap_or(("", ""))),
+            None => ("//", ("/*", "*/")),
+        };
+        Self { line_comment, block_start, block_end, in_block_comment: false }
+    }
+
+    /// The line without its comments, or `None` if nothing else is left
+    pub fn strip<'a>(&mut self, line: &'a str) -> Option<Cow<'a, str>> {
+        let (line_comment, block_start, block_end) =
+            (self.line_comment, self.block_start, self.block_end);
+        let trimmed = line.trim();
+
+        // Handle block comments
+        if !block_start.is_empty() && !block_end.is_empty() {
+            if self.in_block_comment {
+                let idx = line.find(block_end)?;
+                self.in_block_comment = false;
+                let after_block = &line[idx + block_end.len()..];
+                return (!after_block.trim().is_empty()).then_some(Cow::Borrowed(after_block));
+            }
+
+            if let Some(idx) = line.find(block_start) {
+                let before = &line[..idx];
+                // Check if block comment ends on same line
+                if let Some(end_idx) = line[idx + block_start.len()..].find(block_end) {
+                    let after = &line[idx + block_start.len() + end_idx + block_end.len()..];
+                    let combined = format!("{}{}", before.trim_end(), after);
+                    return (!combined.trim().is_empty()).then_some(Cow::Owned(combined));
+                }
+                self.in_block_comment = true;
+                return (!before.trim().is_empty()).then_some(Cow::Borrowed(before.trim_end()));
+            }
+        }
+
+        // Handle line comments (simple approach - may not handle strings perfectly)
+        if !line_comment.is_empty() && trimmed.starts_with(line_comment) {
+            return None;
+        }
+
+        // Try to remove trailing line comments
+        if !line_comment.is_empty() {
+            if let Some(idx) = line.find(line_comment) {
+                // Simple heuristic: skip if inside a string
+                let before = &line[..idx];
+                let quote_count = before.matches('"').count() + before.matches('\'').count();
+                if quote_count.is_multiple_of(2) {
+                    let cleaned = before.trim_end();
+                    return (!cleaned.is_empty()).then_some(Cow::Borrowed(cleaned));
+                }
+            }
+        }
+
+        Some(Cow::Borrowed(line))
+    }
+}
+
+/// Removes Python docstrings: string statements opening a module, class or
+/// function
+#[derive(Debug, Clone)]
+struct DocstringStripper {
+    /// Whether the next statement would be a docstring
+    expecting: bool,
+    /// Closing quotes of the docstring being skipped
+    inside: Option<&'static str>,
+}
+
+impl Default for DocstringStripper {
+    fn default() -> Self {
+        Self { expecting: true, inside: None }
+    }
+}
+
+impl DocstringStripper {
+    fn strip<'a>(&mut self, line: &'a str) -> Option<Cow<'a, str>> {
+        let trimmed = line.trim();
+        if let Some(quotes) = self.inside {
+            if trimmed.contains(quotes) {
+                self.inside = None;
+            }
+            return None;
+        }
+        if trimmed.is_empty() {
+            return Some(Cow::Borrowed(line));
+        }
+
+        if self.expecting {
+            self.expecting = false;
+            let body = trimmed.trim_start_matches(['r', 'R', 'u', 'U']);
+            for quotes in ["\"\"\"", "'''"] {
+                if let Some(rest) = body.strip_prefix(quotes) {
+                    if !rest.contains(quotes) {
+                        self.inside = Some(quotes);
+                    }
+                    return None;
+                }
+            }
+        }
+
+        let header = trimmed.starts_with("def ")
+            || trimmed.starts_with("async def ")
+            || trimmed.starts_with("class ");
+        let code = trimmed.split('#').next().unwrap_or_default().trim_end();
+        self.expecting = header && code.ends_with(':');
+        Some(Cow::Borrowed(line))
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::str_to_string)]
+mod tests {
+    use super::*;
+
+    const PYTHON: &str = "\"\"\"Module docs.\"\"\"\n\nimport os\n\n\nclass Store:\n    \"\"\"Keeps items.\n\n    Long description.\n    \"\"\"\n\n    def get(self, key):  # fetch\n        '''One line.'''\n        value = self.items[key]\n        return value\n";
+
+    fn python_file() -> RepoFile {
+        let mut file = RepoFile::new("/repo/store.py", "store.py");
+        file.language = Some("python".to_string());
+        file.content = Some(PYTHON.to_string());
+        file.token_count.claude = 100;
+        file
+    }
+
+    fn compress(level: CompressionLevel) -> RepoFile {
+        let mut file = python_file();
+        Compressor::new(level).compress_file(&mut file);
+        file
+    }
+
+    #[test]
+    fn test_levels_remove_more_and_more() {
+        let none = compress(CompressionLevel::None);
+        assert_eq!(none.content.as_deref(), Some(PYTHON));
+
+        let minimal = compress(CompressionLevel::Minimal);
+        assert!(!minimal.content.as_deref().unwrap().contains("\n\n"));
+        assert!(minimal.content.as_deref().unwrap().contains("# fetch"));
+
+        let balanced = compress(CompressionLevel::Balanced);
+        let text = balanced.content.as_deref().unwrap();
+        assert!(!text.contains("# fetch"));
+        assert!(text.contains("Keeps items."));
+
+        let aggressive = compress(CompressionLevel::Aggressive);
+        assert_eq!(
+            aggressive.content.as_deref(),
+            Some("import os\nclass Store:\n    def get(self, key):\n        ...\n")
+        );
+        let numbers: Vec<usize> = aggressive.numbered_lines().map(|(n, _)| n).collect();
+        assert_eq!(numbers, vec![3, 6, 12, 13]);
+        assert!(aggressive.token_count.claude < 50);
+
+        let extreme = compress(CompressionLevel::Extreme);
+        assert_eq!(extreme.content.as_deref(), Some("class Store:\n    def get(self, key):\n"));
+    }
+
+    #[test]
+    fn test_aggressive_keeps_braces_and_types() {
+        let mut file = RepoFile::new("/repo/lib.rs", "lib.rs");
+        file.language = Some("rust".to_string());
+        file.content = Some(
+            "use std::fmt;\n\npub struct Point {\n    x: i32,\n}\n\nimpl Point {\n    pub fn norm(&self) -> i32 {\n        let x = self.x;\n        x * x\n    }\n}\n"
+                .to_string(),
+        );
+        Compressor::new(CompressionLevel::Aggressive).compress_file(&mut file);
+        assert_eq!(
+            file.content.as_deref(),
+            Some("use std::fmt;\npub struct Point {\n    x: i32,\n}\nimpl Point {\n    pub fn norm(&self) -> i32 {\n        ...\n    }\n}\n")
+        );
+        let numbers: Vec<usize> = file.numbered_lines().map(|(n, _)| n).collect();
+        assert_eq!(numbers, vec![1, 3, 4, 5, 7, 8, 9, 11, 12]);
+    }
+
+    #[test]
+    fn test_flags_apply_below_their_level() {
+        let mut file = python_file();
+        Compressor::new(CompressionLevel::None)
+            .with_remove_comments(true)
+            .compress_file(&mut file);
+        let text = file.content.as_deref().unwrap();
+        assert!(!text.contains("# fetch"));
+        assert!(text.contains("\n\n"));
+    }
+
+    #[test]
+    fn test_comment_stripper() {
+        let mut stripper = CommentStripper::new("rust");
+        let lines: Vec<Option<Cow<'_, str>>> = [
+            "/* start",
+            "still */ let a = 1;",
+            "// gone",
+            "let b = 2; // trailing",
+            "let s = \"//\";",
+        ]
+        .into_iter()
+        .map(|line| stripper.strip(line))
+        .collect();
+        assert_eq!(lines[0], None);
+        assert_eq!(lines[1].as_deref(), Some(" let a = 1;"));
+        assert_eq!(lines[2], None);
+        assert_eq!(lines[3].as_deref(), Some("let b = 2;"));
+        assert_eq!(lines[4].as_deref(), Some("let s = \"//\";"));
+    }
+}
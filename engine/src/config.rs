@@ -1,14 +1,39 @@
 //! Configuration file support for Infiniloom
 //!
 //! Supports `.infiniloomrc`, `.infiniloom.yaml`, `.infiniloom.toml`, and `.infiniloom.json`
-//! with environment variable override support.
-
+//! with environment variable override support. Paths listed in
+//! [`IGNORE_FILE`] are left out of every scan, like `.gitignore` entries.
+
+use crate::cache::write_atomic;
+use crate::default_ignores::is_default_ignored;
+use crate::filter::{And, FileFilter, GlobFilter, LanguageFilter};
+use crate::parser::{Language, LanguageRegistry, LanguageSpec, ParserError};
+use crate::security::{PiiPolicy, SecretKind, SecurityRule, SecurityScanner, Severity};
+use crate::topics::Topic;
+use crate::types::{RepoFile, Repository};
 use figment::{
     providers::{Env, Format, Json, Serialized, Toml, Yaml},
     Figment,
 };
 use serde::{Deserialize, Serialize};
-use std::path::Path;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// Paths to leave out of scans, in `.gitignore` syntax, honored in every
+/// directory of the repository
+pub const IGNORE_FILE: &str = ".infiniloomignore";
+
+/// Config file names, in the order they are looked for
+const CONFIG_FILES: &[&str] = &[
+    ".infiniloomrc",
+    ".infiniloom.yaml",
+    ".infiniloom.yml",
+    ".infiniloom.toml",
+    ".infiniloom.json",
+    "infiniloom.yaml",
+    "infiniloom.toml",
+    "infiniloom.json",
+];
 
 /// Main configuration structure
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -34,6 +59,12 @@ pub struct Config {
 
     /// Include/exclude patterns
     pub patterns: PatternConfig,
+
+    /// Extra tree-sitter grammars loaded at runtime
+    pub grammars: Vec<GrammarConfig>,
+
+    /// Settings applied only to repositories of an inferred topic
+    pub when: Vec<TopicConfig>,
 }
 
 impl Default for Config {
@@ -46,6 +77,8 @@ impl Default for Config {
             security: SecurityConfig::default(),
             performance: PerformanceConfig::default(),
             patterns: PatternConfig::default(),
+            grammars: vec![],
+            when: vec![],
         }
     }
 }
@@ -57,7 +90,7 @@ pub struct ScanConfig {
     /// Include patterns (glob syntax)
     pub include: Vec<String>,
 
-    /// Exclude patterns (glob syntax)
+    /// Exclude patterns (glob syntax), on top of the default ignores
     pub exclude: Vec<String>,
 
     /// Maximum file size to include (in bytes, supports "100KB", "1MB" etc)
@@ -69,34 +102,40 @@ pub struct ScanConfig {
     /// Include hidden files (starting with .)
     pub include_hidden: bool,
 
+    /// Hidden paths to include even without `include_hidden`, on top of the
+    /// built-in allowlist (`.github`, `.env.example`, ...)
+    pub hidden_allowlist: Vec<String>,
+
     /// Respect .gitignore files
     pub respect_gitignore: bool,
 
     /// Read file contents (false = metadata only)
     pub read_contents: bool,
+
+    /// Keep only files in these languages, by identifier or alias (empty = all)
+    pub only_languages: Vec<String>,
+
+    /// Leave out files in these languages
+    pub exclude_languages: Vec<String>,
+
+    /// Leave out dependency, build and generated paths (see `default_ignores`)
+    pub default_ignores: bool,
 }
 
 impl Default for ScanConfig {
     fn default() -> Self {
         Self {
             include: vec!["**/*".to_owned()],
-            exclude: vec![
-                "**/node_modules/**".to_owned(),
-                "**/.git/**".to_owned(),
-                "**/target/**".to_owned(),
-                "**/__pycache__/**".to_owned(),
-                "**/dist/**".to_owned(),
-                "**/build/**".to_owned(),
-                "**/.venv/**".to_owned(),
-                "**/venv/**".to_owned(),
-                "**/*.min.js".to_owned(),
-                "**/*.min.css".to_owned(),
-            ],
+            exclude: Vec::new(),
             max_file_size: "10MB".to_owned(),
             follow_symlinks: false,
             include_hidden: false,
+            hidden_allowlist: Vec::new(),
             respect_gitignore: true,
             read_contents: true,
+            only_languages: Vec::new(),
+            exclude_languages: Vec::new(),
+            default_ignores: true,
         }
     }
 }
@@ -106,22 +145,52 @@ impl ScanConfig {
     pub fn max_file_size_bytes(&self) -> u64 {
         parse_size(&self.max_file_size).unwrap_or(10 * 1024 * 1024)
     }
+
+    /// Filter for `only_languages` and `exclude_languages`
+    pub fn language_filter(&self) -> LanguageFilter {
+        LanguageFilter::only(&self.only_languages).and_except(&self.exclude_languages)
+    }
+
+    /// Filter for `include`, `exclude` and the language lists
+    ///
+    /// Patterns that do not parse are skipped.
+    pub fn file_filter(&self) -> And<GlobFilter, LanguageFilter> {
+        GlobFilter::lenient(&self.include, &self.exclude).and(self.language_filter())
+    }
+
+    /// Drop the files of `repo` this configuration leaves out, returning how
+    /// many were dropped
+    ///
+    /// Applies [`file_filter`](Self::file_filter) and, with `default_ignores`,
+    /// the default ignores, then refreshes the metadata. For repositories from
+    /// the [`scanner`](crate::scanner), which walks without patterns.
+    pub fn select_files(&self, repo: &mut Repository) -> usize {
+        let filter = self.file_filter();
+        let dropped = repo.retain_files(&|f: &RepoFile| {
+            filter.keep(f) && !(self.default_ignores && is_default_ignored(&f.relative_path))
+        });
+        if dropped > 0 {
+            repo.refresh_metadata();
+        }
+        dropped
+    }
 }
 
 /// Output configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(default)]
 pub struct OutputConfig {
-    /// Output format: xml, markdown, json, yaml
+    /// Output format: xml, markdown, json, yaml, or `exec:<command>` to pipe
+    /// the JSON output through an external formatter
     pub format: String,
 
     /// Target LLM model: claude, gpt4o, gpt4, gemini, llama
     pub model: String,
 
-    /// Compression level: none, minimal, balanced, aggressive, extreme
+    /// Compression level: none, minimal, balanced, aggressive, extreme, semantic
     pub compression: String,
 
-    /// Maximum token budget (0 = unlimited)
+    /// Maximum output tokens (0 = unlimited)
     pub token_budget: u32,
 
     /// Include line numbers in code output
@@ -162,6 +231,12 @@ pub struct OutputConfig {
 
     /// Include empty directories in structure
     pub include_empty_directories: bool,
+
+    /// Render git dates in the local timezone instead of ISO-8601 UTC
+    pub local_time: bool,
+
+    /// Time an `exec:` formatter may run before it is killed, e.g. "30s"
+    pub formatter_timeout: Option<String>,
 }
 
 impl Default for OutputConfig {
@@ -169,8 +244,8 @@ impl Default for OutputConfig {
         Self {
             format: "xml".to_owned(),
             model: "claude".to_owned(),
-            compression: "none".to_owned(),
-            token_budget: 0,
+            compression: "balanced".to_owned(),
+            token_budget: 100_000,
             line_numbers: true,
             cache_optimized: true,
             output_file: "-".to_owned(),
@@ -184,6 +259,8 @@ impl Default for OutputConfig {
             remove_comments: false,
             top_files_length: 0,
             include_empty_directories: false,
+            local_time: false,
+            formatter_timeout: None,
         }
     }
 }
@@ -212,6 +289,15 @@ pub struct SymbolConfig {
 
     /// Build dependency graph
     pub build_dependency_graph: bool,
+
+    /// Apply the built-in per-language symbol stop-lists to the repomap
+    pub default_stop_lists: bool,
+
+    /// Extra stop-list patterns per language (`"*"` applies to all), e.g. `test_*`
+    pub stop_lists: HashMap<String, Vec<String>>,
+
+    /// Leave symbols with signatures shorter than this out of the repomap (0 = off)
+    pub min_signature_length: usize,
 }
 
 impl Default for SymbolConfig {
@@ -221,9 +307,12 @@ impl Default for SymbolConfig {
             languages: vec![],
             extract_docstrings: true,
             extract_signatures: true,
-            max_symbols: 100,
+            max_symbols: 50,
             include_imports: true,
             build_dependency_graph: true,
+            default_stop_lists: true,
+            stop_lists: HashMap::new(),
+            min_signature_length: 0,
         }
     }
 }
@@ -232,10 +321,10 @@ impl Default for SymbolConfig {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(default)]
 pub struct SecurityConfig {
-    /// Enable secret scanning
+    /// Scan packed files for secrets and report them with the output
     pub scan_secrets: bool,
 
-    /// Fail on secrets detected
+    /// Refuse to pack when secrets are found (implies `scan_secrets`)
     pub fail_on_secrets: bool,
 
     /// Patterns to allowlist (won't be flagged)
@@ -246,17 +335,140 @@ pub struct SecurityConfig {
 
     /// Redact secrets in output
     pub redact_secrets: bool,
+
+    /// User-defined rules, e.g. for internal token formats
+    pub rules: Vec<SecurityRuleConfig>,
+
+    /// Ids of rules (built-in or user-defined) not to apply
+    pub disabled_rules: Vec<String>,
+
+    /// How personal data (emails, phone numbers, national IDs) is reported
+    pub pii: PiiPolicy,
 }
 
 impl Default for SecurityConfig {
     fn default() -> Self {
         Self {
-            scan_secrets: true,
+            scan_secrets: false,
             fail_on_secrets: false,
             allowlist: vec![],
             custom_patterns: vec![],
             redact_secrets: true,
+            rules: vec![],
+            disabled_rules: vec![],
+            pii: PiiPolicy::default(),
+        }
+    }
+}
+
+impl SecurityConfig {
+    /// Read rules from a standalone file with the same keys as the `security`
+    /// section (`rules`, `disabled_rules`, `allowlist`, ...)
+    #[allow(clippy::result_large_err)]
+    pub fn load_rules(path: &Path) -> Result<Self, ConfigError> {
+        let content = std::fs::read_to_string(path).map_err(ConfigError::IoError)?;
+        let figment = Figment::new().merge(Serialized::defaults(Self::default()));
+        let figment = match path.extension().and_then(|e| e.to_str()) {
+            Some("toml") => figment.merge(Toml::string(&content)),
+            Some("json") => figment.merge(Json::string(&content)),
+            _ => figment.merge(Yaml::string(&content)),
+        };
+        figment.extract().map_err(ConfigError::ParseError)
+    }
+
+    /// Add the rules, disabled rules and allowlist of `other`
+    pub fn extend(&mut self, other: Self) {
+        self.allowlist.extend(other.allowlist);
+        self.custom_patterns.extend(other.custom_patterns);
+        self.rules.extend(other.rules);
+        self.disabled_rules.extend(other.disabled_rules);
+    }
+
+    /// Build a scanner with the built-in rules adjusted by this configuration
+    ///
+    /// `custom_patterns` become generic high-severity rules named
+    /// `custom-1`, `custom-2`, ...
+    #[allow(clippy::result_large_err)]
+    pub fn scanner(&self) -> Result<SecurityScanner, ConfigError> {
+        let mut scanner = SecurityScanner::new();
+        scanner.set_pii_policy(self.pii.clone());
+        for (i, pattern) in self.custom_patterns.iter().enumerate() {
+            let id = format!("custom-{}", i + 1);
+            let rule = SecurityRule::new(&id, pattern, SecretKind::Generic, Severity::High)
+                .map_err(|e| ConfigError::InvalidRule(id, e.to_string()))?;
+            scanner.add_rule(rule);
         }
+        for rule in &self.rules {
+            scanner.add_rule(rule.to_rule()?);
+        }
+        for id in &self.disabled_rules {
+            if !scanner.disable_rule(id) {
+                return Err(ConfigError::InvalidRule(
+                    id.clone(),
+                    "no rule with this id".to_owned(),
+                ));
+            }
+        }
+        for pattern in &self.allowlist {
+            scanner.allowlist(pattern);
+        }
+        Ok(scanner)
+    }
+}
+
+/// A user-defined security rule
+///
+/// ```yaml
+/// security:
+///   rules:
+///     - id: acme-token
+///       pattern: "acme_[a-z0-9]{32}"
+///       kind: access-token
+///       severity: critical
+///       description: ACME internal API token
+///   disabled_rules: [jwt]
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SecurityRuleConfig {
+    /// Rule id; a built-in id replaces that rule
+    pub id: String,
+
+    /// Regex to flag
+    pub pattern: String,
+
+    /// Kind of secret (`api-key`, `access-token`, `private-key`, `password`,
+    /// `connection-string`, `aws-credential`, `github-token`, `generic` or
+    /// `pii`)
+    #[serde(default = "default_rule_kind")]
+    pub kind: SecretKind,
+
+    /// `low`, `medium`, `high` or `critical`
+    #[serde(default = "default_rule_severity")]
+    pub severity: Severity,
+
+    /// What the rule finds, shown in reports
+    #[serde(default)]
+    pub description: Option<String>,
+}
+
+fn default_rule_kind() -> SecretKind {
+    SecretKind::Generic
+}
+
+fn default_rule_severity() -> Severity {
+    Severity::High
+}
+
+impl SecurityRuleConfig {
+    /// Compile the rule
+    #[allow(clippy::result_large_err)]
+    pub fn to_rule(&self) -> Result<SecurityRule, ConfigError> {
+        let rule = SecurityRule::new(&self.id, &self.pattern, self.kind, self.severity)
+            .map_err(|e| ConfigError::InvalidRule(self.id.clone(), e.to_string()))?;
+        Ok(match &self.description {
+            Some(description) => rule.with_description(description),
+            None => rule,
+        })
     }
 }
 
@@ -270,8 +482,10 @@ pub struct PerformanceConfig {
     /// Enable incremental mode (cache results)
     pub incremental: bool,
 
-    /// Cache directory
-    pub cache_dir: String,
+    /// Cache directory (default: the per-user cache, see
+    /// [`repo_cache_dir`](crate::cache::repo_cache_dir))
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cache_dir: Option<PathBuf>,
 
     /// Use memory-mapped I/O for large files
     pub memory_mapped: bool,
@@ -285,7 +499,7 @@ impl Default for PerformanceConfig {
         Self {
             threads: 0, // auto
             incremental: false,
-            cache_dir: ".infiniloom/cache".to_owned(),
+            cache_dir: None,
             memory_mapped: true,
             skip_symbols: false,
         }
@@ -329,59 +543,173 @@ impl Default for PatternConfig {
     }
 }
 
+/// A compiled tree-sitter grammar to register in the [`LanguageRegistry`]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GrammarConfig {
+    /// Language name reported for matching files
+    pub name: String,
+
+    /// Shared library with the compiled grammar, relative to the repository
+    pub library: PathBuf,
+
+    /// Exported constructor (default: `tree_sitter_<name>`)
+    pub symbol: Option<String>,
+
+    /// Symbol query capturing `@name` and `@function`, `@class`, `@method`, ...
+    pub query: String,
+
+    /// File holding the query instead of `query`, relative to the repository
+    pub query_file: Option<PathBuf>,
+
+    /// Extensions of files in this language, without the dot
+    pub extensions: Vec<String>,
+
+    /// Top-level node kinds that hold imports
+    pub import_kinds: Vec<String>,
+}
+
+impl GrammarConfig {
+    /// Load the grammar and register it, resolving paths against `base_dir`
+    pub fn register(&self, base_dir: &Path) -> Result<Language, ParserError> {
+        let symbol = self
+            .symbol
+            .clone()
+            .unwrap_or_else(|| format!("tree_sitter_{}", self.name.replace('-', "_")));
+        let grammar = LanguageRegistry::load_grammar(&base_dir.join(&self.library), &symbol)?;
+        let query = match &self.query_file {
+            Some(file) => std::fs::read_to_string(base_dir.join(file))
+                .map_err(|e| ParserError::LoadError(format!("{}: {}", file.display(), e)))?,
+            None => self.query.clone(),
+        };
+        LanguageRegistry::register(
+            LanguageSpec::new(self.name.clone(), grammar, query)
+                .with_extensions(self.extensions.iter().cloned())
+                .with_import_kinds(self.import_kinds.iter().cloned()),
+        )
+    }
+}
+
+/// Patterns that apply when the repository is classified under `topic`
+///
+/// ```yaml
+/// when:
+///   - topic: machine-learning
+///     exclude: ["**/*.ipynb", "data/**"]
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TopicConfig {
+    /// Topic the patterns are conditional on
+    pub topic: Topic,
+
+    /// Extra exclude patterns (glob syntax)
+    #[serde(default)]
+    pub exclude: Vec<String>,
+}
+
 impl Config {
+    /// Exclude patterns from `when` sections matching any of `topics`
+    pub fn topic_excludes(&self, topics: &[Topic]) -> Vec<String> {
+        self.when
+            .iter()
+            .filter(|w| topics.contains(&w.topic))
+            .flat_map(|w| w.exclude.iter().cloned())
+            .collect()
+    }
+
     /// Load configuration from default locations
     #[allow(clippy::result_large_err)]
     pub fn load(repo_path: &Path) -> Result<Self, ConfigError> {
         Self::load_with_profile(repo_path, None)
     }
 
+    /// Load the configuration that applies to the repository at `path`
+    ///
+    /// Looks for a config file in `path`, then in its parents up to the
+    /// repository root (the first directory with a `.git`), so a subdirectory
+    /// is scanned with its repository's settings. The global config and
+    /// `INFINILOOM_*` variables apply on top, as for [`load`](Self::load).
+    #[allow(clippy::result_large_err)]
+    pub fn discover(path: &Path) -> Result<Self, ConfigError> {
+        match Self::find_file(path) {
+            Some(file) => Self::load_file(&file),
+            None => Self::load(path),
+        }
+    }
+
+    /// The config file [`discover`](Self::discover) loads for `path`, if any
+    pub fn find_file(path: &Path) -> Option<PathBuf> {
+        let path = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+        for dir in path.ancestors() {
+            if let Some(file) = config_file(dir) {
+                return Some(file);
+            }
+            if dir.join(".git").exists() {
+                break;
+            }
+        }
+        None
+    }
+
+    /// Load the config file at `path`, whatever its name
+    ///
+    /// The format follows the extension; other names are read as JSON, YAML
+    /// or TOML depending on their content. The global config and
+    /// `INFINILOOM_*` variables apply on top, as for [`load`](Self::load).
+    #[allow(clippy::result_large_err)]
+    pub fn load_file(path: &Path) -> Result<Self, ConfigError> {
+        std::fs::metadata(path).map_err(ConfigError::IoError)?;
+        Self::load_from(Some(path), None)
+    }
+
+    /// Defaults with only the global config and `INFINILOOM_*` variables,
+    /// the settings the user made rather than a repository
+    #[allow(clippy::result_large_err)]
+    pub fn load_user() -> Result<Self, ConfigError> {
+        Self::load_from(None, None)
+    }
+
+    /// Path of the global config file, whether or not it exists
+    pub fn global_file() -> Option<PathBuf> {
+        dirs::home_dir().map(|home| home.join(".config/infiniloom/config.yaml"))
+    }
+
     /// Load configuration with optional profile override
     #[allow(clippy::result_large_err)]
     pub fn load_with_profile(repo_path: &Path, profile: Option<&str>) -> Result<Self, ConfigError> {
+        Self::load_from(config_file(repo_path).as_deref(), profile)
+    }
+
+    /// Defaults, overridden by `config_file`, the global config and
+    /// `INFINILOOM_*` variables in that order
+    #[allow(clippy::result_large_err)]
+    fn load_from(config_file: Option<&Path>, profile: Option<&str>) -> Result<Self, ConfigError> {
         let mut figment = Figment::new().merge(Serialized::defaults(Config::default()));
 
-        // Try loading from various config file locations
-        let config_files = [
-            repo_path.join(".infiniloomrc"),
-            repo_path.join(".infiniloom.yaml"),
-            repo_path.join(".infiniloom.yml"),
-            repo_path.join(".infiniloom.toml"),
-            repo_path.join(".infiniloom.json"),
-            repo_path.join("infiniloom.yaml"),
-            repo_path.join("infiniloom.toml"),
-            repo_path.join("infiniloom.json"),
-        ];
-
-        for config_file in &config_files {
-            if config_file.exists() {
-                figment = match config_file.extension().and_then(|e| e.to_str()) {
-                    Some("yaml") | Some("yml") => figment.merge(Yaml::file(config_file)),
-                    Some("toml") => figment.merge(Toml::file(config_file)),
-                    Some("json") => figment.merge(Json::file(config_file)),
-                    None => {
-                        // .infiniloomrc - try YAML first, then TOML
-                        if let Ok(content) = std::fs::read_to_string(config_file) {
-                            if content.trim_start().starts_with('{') {
-                                figment.merge(Json::file(config_file))
-                            } else if content.contains(':') {
-                                figment.merge(Yaml::file(config_file))
-                            } else {
-                                figment.merge(Toml::file(config_file))
-                            }
+        if let Some(config_file) = config_file {
+            figment = match config_file.extension().and_then(|e| e.to_str()) {
+                Some("yaml") | Some("yml") => figment.merge(Yaml::file(config_file)),
+                Some("toml") => figment.merge(Toml::file(config_file)),
+                Some("json") => figment.merge(Json::file(config_file)),
+                _ => {
+                    // .infiniloomrc - try YAML first, then TOML
+                    if let Ok(content) = std::fs::read_to_string(config_file) {
+                        if content.trim_start().starts_with('{') {
+                            figment.merge(Json::file(config_file))
+                        } else if content.contains(':') {
+                            figment.merge(Yaml::file(config_file))
                         } else {
-                            figment
+                            figment.merge(Toml::file(config_file))
                         }
-                    },
-                    _ => figment,
-                };
-                break; // Use first found config file
-            }
+                    } else {
+                        figment
+                    }
+                },
+            };
         }
 
         // Check home directory for global config
-        if let Some(home) = dirs::home_dir() {
-            let global_config = home.join(".config/infiniloom/config.yaml");
+        if let Some(global_config) = Self::global_file() {
             if global_config.exists() {
                 figment = figment.merge(Yaml::file(global_config));
             }
@@ -410,7 +738,7 @@ impl Config {
                 .map_err(|e| ConfigError::SerializeError(e.to_string()))?,
         };
 
-        std::fs::write(path, content).map_err(ConfigError::IoError)
+        write_atomic(path, content).map_err(ConfigError::IoError)
     }
 
     /// Generate a default configuration file
@@ -441,6 +769,8 @@ pub enum ConfigError {
     ParseError(figment::Error),
     IoError(std::io::Error),
     SerializeError(String),
+    /// A security rule that does not compile or does not exist (id, reason)
+    InvalidRule(String, String),
 }
 
 impl std::fmt::Display for ConfigError {
@@ -449,12 +779,21 @@ impl std::fmt::Display for ConfigError {
             Self::ParseError(e) => write!(f, "Configuration parse error: {}", e),
             Self::IoError(e) => write!(f, "Configuration I/O error: {}", e),
             Self::SerializeError(e) => write!(f, "Configuration serialize error: {}", e),
+            Self::InvalidRule(id, e) => write!(f, "Invalid security rule '{}': {}", id, e),
         }
     }
 }
 
 impl std::error::Error for ConfigError {}
 
+/// The first config file in `dir`, if any
+fn config_file(dir: &Path) -> Option<PathBuf> {
+    CONFIG_FILES
+        .iter()
+        .map(|name| dir.join(name))
+        .find(|path| path.exists())
+}
+
 /// Parse a size string like "100KB", "1MB", "500" into bytes
 fn parse_size(s: &str) -> Option<u64> {
     let s = s.trim().to_uppercase();
@@ -544,4 +883,123 @@ mod tests {
         config.performance.threads = 8;
         assert_eq!(config.effective_threads(), 8);
     }
+
+    #[test]
+    fn test_grammar_config_reports_load_errors() {
+        let yaml =
+            "grammars:\n  - name: zig\n    library: grammars/zig.so\n    extensions: [zig]\n";
+        let config: Config = serde_yaml::from_str(yaml).unwrap();
+        let grammar = &config.grammars[0];
+        assert_eq!(grammar.extensions, ["zig"]);
+
+        let err = grammar.register(Path::new("/nonexistent")).unwrap_err();
+        assert!(matches!(err, ParserError::LoadError(_)));
+        assert!(err.to_string().contains("zig.so"));
+        assert!(Language::from_extension("zig").is_none());
+    }
+
+    #[test]
+    fn test_security_rules() {
+        let yaml = concat!(
+            "security:\n",
+            "  rules:\n",
+            "    - id: acme-token\n",
+            "      pattern: \"acme_[a-z0-9]{32}\"\n",
+            "      kind: access-token\n",
+            "      severity: critical\n",
+            "      description: ACME internal API token\n",
+            "  disabled_rules: [password]\n",
+            "  pii:\n",
+            "    severity: high\n",
+            "    allowed_domains: [acme.io]\n",
+        );
+        let config: Config = serde_yaml::from_str(yaml).unwrap();
+        let scanner = config.security.scanner().unwrap();
+        assert!(scanner.rule_ids().any(|id| id == "acme-token"));
+        assert!(!scanner.rule_ids().any(|id| id == "password"));
+
+        let findings = scanner.scan(
+            "let key = \"acme_0123456789abcdef0123456789abcdef\";\npassword = \"hunter2hunter2\"",
+            "src/client.rs",
+        );
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].rule, "acme-token");
+        assert_eq!(findings[0].severity, Severity::Critical);
+        assert_eq!(findings[0].label(), "ACME internal API token");
+
+        let findings = scanner.scan("owner = \"ops@acme.io\"\nlead = \"kim@corp.dev\"", "team.py");
+        assert_eq!(findings.len(), 1);
+        assert_eq!((findings[0].line, findings[0].severity), (2, Severity::High));
+
+        let mut config = SecurityConfig::default();
+        config.disabled_rules.push("no-such-rule".to_owned());
+        assert!(matches!(config.scanner(), Err(ConfigError::InvalidRule(..))));
+    }
+
+    #[test]
+    fn test_topic_conditionals() {
+        let yaml = concat!(
+            "when:\n",
+            "  - topic: machine-learning\n",
+            "    exclude: [\"**/*.ipynb\"]\n",
+            "  - topic: infra-as-code\n",
+            "    exclude: [\"**/.terraform/**\"]\n",
+        );
+        let config: Config = serde_yaml::from_str(yaml).unwrap();
+        assert_eq!(config.topic_excludes(&[Topic::MachineLearning]), vec!["**/*.ipynb"]);
+        assert!(config.topic_excludes(&[Topic::CliTool]).is_empty());
+        assert_eq!(
+            config
+                .topic_excludes(&[Topic::InfraAsCode, Topic::MachineLearning])
+                .len(),
+            2
+        );
+    }
+
+    #[test]
+    fn test_language_filter() {
+        let yaml = "scan:\n  only_languages: [rust, python]\n  exclude_languages: [Python]\n";
+        let config: Config = serde_yaml::from_str(yaml).unwrap();
+        let filter = config.scan.language_filter();
+
+        let mut file = RepoFile::new("/repo/main.rs", "main.rs");
+        file.language = Some("rust".to_owned());
+        assert!(filter.keep(&file));
+        file.language = Some("python".to_owned());
+        assert!(!filter.keep(&file));
+        assert!(Config::default().scan.language_filter().is_empty());
+    }
+
+    #[test]
+    fn test_discover_stops_at_repository_root() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = dir.path().join("repo");
+        std::fs::create_dir_all(repo.join(".git")).unwrap();
+        std::fs::create_dir_all(repo.join("src/nested")).unwrap();
+        std::fs::write(repo.join(".infiniloom.yaml"), "scan:\n  max_file_size: 1KB\n").unwrap();
+        std::fs::write(dir.path().join(".infiniloom.yaml"), "scan:\n  max_file_size: 2KB\n")
+            .unwrap();
+
+        let config = Config::discover(&repo.join("src/nested")).unwrap();
+        assert_eq!(config.scan.max_file_size_bytes(), 1024);
+
+        std::fs::remove_file(repo.join(".infiniloom.yaml")).unwrap();
+        let config = Config::discover(&repo.join("src")).unwrap();
+        assert_eq!(config.scan.max_file_size, "10MB");
+    }
+
+    #[test]
+    fn test_load_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("pack.conf");
+        std::fs::write(&file, "[output]\nformat = \"markdown\"\ntoken_budget = 5000\n").unwrap();
+
+        let config = Config::load_file(&file).unwrap();
+        assert_eq!(config.output.format, "markdown");
+        assert_eq!(config.output.token_budget, 5000);
+        assert_eq!(config.output.model, "claude");
+
+        let missing = Config::load_file(&dir.path().join("missing.yaml"));
+        assert!(matches!(missing, Err(ConfigError::IoError(_))));
+    }
 }
@@ -0,0 +1,574 @@
+//! Interface summaries for API contract files
+//!
+//! Protobuf definitions, OpenAPI/Swagger documents and JSON Schemas are the
+//! densest description of how a system talks to the outside world, but their
+//! raw text is mostly boilerplate. [`extract_contracts`] takes them out of a
+//! repository and keeps a compact [`Contract`] summary for each:
+//!
+//! - **Protobuf**: package, services with their RPCs, messages with their
+//!   fields and enums with their values
+//! - **OpenAPI/Swagger**: endpoints with request and response models, and the
+//!   fields of every model
+//! - **JSON Schema**: the fields of the root object and of every definition
+//!
+//! [`render_contracts`] turns the summaries into a dedicated section of the
+//! pack, and [`contracts_section`] into one any output format can carry.
+
+use crate::output::{Locale, Section};
+use crate::types::Repository;
+use serde::Serialize;
+use serde_json::Value;
+use std::fmt;
+
+/// HTTP methods that can appear under an OpenAPI path
+const HTTP_METHODS: &[&str] =
+    &["get", "put", "post", "delete", "patch", "head", "options", "trace"];
+
+/// Kind of API contract
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ContractKind {
+    /// Protocol Buffers (`.proto`)
+    Protobuf,
+    /// OpenAPI 3 or Swagger 2 document
+    OpenApi,
+    /// JSON Schema document
+    JsonSchema,
+}
+
+impl ContractKind {
+    /// Display name
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Protobuf => "Protobuf",
+            Self::OpenApi => "OpenAPI",
+            Self::JsonSchema => "JSON Schema",
+        }
+    }
+}
+
+impl fmt::Display for ContractKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Summary of one contract file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Contract {
+    /// Path relative to the repository root
+    pub path: String,
+    /// Kind of contract
+    pub kind: ContractKind,
+    /// Compact interface summary, one item per line
+    pub summary: String,
+}
+
+impl Contract {
+    /// Summarize `content` if `path` is a contract file
+    pub fn from_file(path: &str, content: &str) -> Option<Self> {
+        let extension = path.rsplit_once('.').map(|(_, ext)| ext.to_lowercase())?;
+        let (kind, summary) = match extension.as_str() {
+            "proto" => (ContractKind::Protobuf, summarize_protobuf(content)),
+            "json" | "yaml" | "yml" => {
+                // Skip parsing the many JSON/YAML files that can't be contracts
+                if !["openapi", "swagger", "$schema"]
+                    .iter()
+                    .any(|marker| content.contains(marker))
+                {
+                    return None;
+                }
+                let document: Value = if extension == "json" {
+                    serde_json::from_str(content).ok()?
+                } else {
+                    serde_yaml::from_str(content).ok()?
+                };
+                if document.get("openapi").is_some() || document.get("swagger").is_some() {
+                    (ContractKind::OpenApi, summarize_openapi(&document))
+                } else if document.get("$schema").is_some() {
+                    (ContractKind::JsonSchema, summarize_json_schema(&document))
+                } else {
+                    return None;
+                }
+            },
+            _ => return None,
+        };
+        Some(Self { path: path.to_owned(), kind, summary })
+    }
+}
+
+/// Remove contract files from `repo` and return their summaries in rank order
+pub fn extract_contracts(repo: &mut Repository) -> Vec<Contract> {
+    let mut contracts = Vec::new();
+    repo.files.retain(|file| {
+        let contract = file
+            .content
+            .as_deref()
+            .and_then(|content| Contract::from_file(&file.relative_path, content));
+        match contract {
+            Some(contract) => {
+                contracts.push(contract);
+                false
+            },
+            None => true,
+        }
+    });
+    contracts
+}
+
+/// Contracts section to append to the pack, empty if there are no contracts
+pub fn render_contracts(contracts: &[Contract], locale: Locale) -> String {
+    if contracts.is_empty() {
+        return String::new();
+    }
+
+    let text = locale.strings();
+    let mut out = format!("\n\n<!-- {} -->\n", text.contracts_title);
+    out.push_str(
+        &text
+            .contracts_intro
+            .replace("{count}", &contracts.len().to_string()),
+    );
+    out.push('\n');
+    for contract in contracts {
+        out.push_str(&format!("\n### {} ({})\n", contract.path, contract.kind));
+        out.push_str(&contract.summary);
+        out.push('\n');
+    }
+    out
+}
+
+/// Contracts as a report section, `None` if there are no contracts
+pub fn contracts_section(contracts: &[Contract], locale: Locale) -> Option<Section> {
+    (!contracts.is_empty())
+        .then(|| Section::new("contracts", render_contracts(contracts, locale), &contracts))
+}
+
+/// A brace-delimited block in a `.proto` file
+enum ProtoScope {
+    Service(usize),
+    Message(String, usize),
+    Enum(usize),
+    Other,
+}
+
+/// Package, services, messages and enums of a `.proto` file
+fn summarize_protobuf(content: &str) -> String {
+    let mut lines = Vec::new();
+    let mut services: Vec<(String, Vec<String>)> = Vec::new();
+    let mut messages: Vec<(String, Vec<String>)> = Vec::new();
+    let mut enums: Vec<(String, Vec<String>)> = Vec::new();
+    let mut stack: Vec<ProtoScope> = Vec::new();
+
+    let source = strip_comments(content);
+    let mut statement = String::new();
+    for c in source.chars() {
+        if !matches!(c, '{' | '}' | ';') {
+            statement.push(c);
+            continue;
+        }
+        let words: Vec<&str> = statement.split_whitespace().collect();
+        let current = stack.last();
+        match c {
+            '{' => {
+                let scope = match (words.first().copied(), current) {
+                    (Some("service"), _) if words.len() > 1 => {
+                        services.push((words[1].to_owned(), Vec::new()));
+                        ProtoScope::Service(services.len() - 1)
+                    },
+                    (Some("message"), _) if words.len() > 1 => {
+                        let name = match current {
+                            Some(ProtoScope::Message(outer, _)) => {
+                                format!("{}.{}", outer, words[1])
+                            },
+                            _ => words[1].to_owned(),
+                        };
+                        messages.push((name.clone(), Vec::new()));
+                        ProtoScope::Message(name, messages.len() - 1)
+                    },
+                    (Some("enum"), _) if words.len() > 1 => {
+                        let name = match current {
+                            Some(ProtoScope::Message(outer, _)) => {
+                                format!("{}.{}", outer, words[1])
+                            },
+                            _ => words[1].to_owned(),
+                        };
+                        enums.push((name, Vec::new()));
+                        ProtoScope::Enum(enums.len() - 1)
+                    },
+                    (Some("rpc"), Some(ProtoScope::Service(i))) => {
+                        services[*i].1.push(rpc_signature(&words));
+                        ProtoScope::Other
+                    },
+                    // oneof fields belong to the enclosing message
+                    (Some("oneof"), Some(ProtoScope::Message(name, i))) => {
+                        ProtoScope::Message(name.clone(), *i)
+                    },
+                    _ => ProtoScope::Other,
+                };
+                stack.push(scope);
+            },
+            '}' => {
+                stack.pop();
+            },
+            _ => match (words.first().copied(), current) {
+                (None | Some("option" | "reserved" | "extensions"), _) => {},
+                (Some("package"), None) if words.len() > 1 => {
+                    lines.push(format!("package {}", words[1]));
+                },
+                (Some("rpc"), Some(ProtoScope::Service(i))) => {
+                    services[*i].1.push(rpc_signature(&words));
+                },
+                (Some(_), Some(ProtoScope::Message(_, i))) => {
+                    if let Some(field) = proto_field(&statement) {
+                        messages[*i].1.push(field);
+                    }
+                },
+                (Some(value), Some(ProtoScope::Enum(i))) => {
+                    enums[*i].1.push(value.trim_end_matches('=').to_owned());
+                },
+                _ => {},
+            },
+        }
+        statement.clear();
+    }
+
+    for (name, rpcs) in services {
+        lines.push(format!("service {}", name));
+        lines.extend(rpcs.into_iter().map(|rpc| format!("  {}", rpc)));
+    }
+    for (name, fields) in messages {
+        lines.push(format!("message {} {{ {} }}", name, fields.join("; ")));
+    }
+    for (name, values) in enums {
+        lines.push(format!("enum {} {{ {} }}", name, values.join(", ")));
+    }
+    lines.join("\n")
+}
+
+/// `rpc Name(Request) returns (Response)` with normalized spacing
+fn rpc_signature(words: &[&str]) -> String {
+    words
+        .join(" ")
+        .replacen(" (", "(", 1)
+        .replace(")returns", ") returns")
+        .replace("returns(", "returns (")
+}
+
+/// `type name` of a message field statement such as `repeated string tags = 2`
+fn proto_field(statement: &str) -> Option<String> {
+    let declaration = statement.split('=').next()?;
+    let words: Vec<&str> = declaration.split_whitespace().collect();
+    if words.len() < 2 {
+        return None;
+    }
+    Some(words.join(" "))
+}
+
+/// `content` with `//` and `/* */` comments removed
+fn strip_comments(content: &str) -> String {
+    let mut out = String::with_capacity(content.len());
+    let mut chars = content.chars().peekable();
+    let mut in_string = false;
+    while let Some(c) = chars.next() {
+        if in_string {
+            in_string = c != '"';
+            out.push(c);
+        } else if c == '"' {
+            in_string = true;
+            out.push(c);
+        } else if c == '/' && chars.peek() == Some(&'/') {
+            for c in chars.by_ref() {
+                if c == '\n' {
+                    out.push('\n');
+                    break;
+                }
+            }
+        } else if c == '/' && chars.peek() == Some(&'*') {
+            chars.next();
+            let mut previous = ' ';
+            for c in chars.by_ref() {
+                if previous == '*' && c == '/' {
+                    break;
+                }
+                previous = c;
+            }
+            out.push(' ');
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+/// Title, endpoints and models of an OpenAPI 3 or Swagger 2 document
+fn summarize_openapi(document: &Value) -> String {
+    let mut lines = Vec::new();
+    let info = &document["info"];
+    if let Some(title) = info["title"].as_str() {
+        match info["version"].as_str() {
+            Some(version) => lines.push(format!("API {} ({})", title, version)),
+            None => lines.push(format!("API {}", title)),
+        }
+    }
+
+    if let Some(paths) = document["paths"].as_object() {
+        lines.push("endpoints:".to_owned());
+        for (path, item) in paths {
+            for method in HTTP_METHODS {
+                let Some(operation) = item.get(*method) else {
+                    continue;
+                };
+                let mut line = format!("  {} {}", method.to_uppercase(), path);
+                if let Some(request) = request_schema(operation) {
+                    line.push_str(&format!(" ({})", schema_type(request)));
+                }
+                if let Some(response) = response_schema(operation) {
+                    line.push_str(&format!(" -> {}", schema_type(response)));
+                }
+                if let Some(about) = operation["summary"]
+                    .as_str()
+                    .or_else(|| operation["operationId"].as_str())
+                {
+                    line.push_str(&format!(" - {}", about));
+                }
+                lines.push(line);
+            }
+        }
+    }
+
+    let models = document["components"]["schemas"]
+        .as_object()
+        .or_else(|| document["definitions"].as_object());
+    if let Some(models) = models {
+        lines.push("models:".to_owned());
+        for (name, schema) in models {
+            lines.push(format!("  {}", model_line(name, schema)));
+        }
+    }
+    lines.join("\n")
+}
+
+/// Body schema of an operation (OpenAPI 3 `requestBody` or Swagger 2 body parameter)
+fn request_schema(operation: &Value) -> Option<&Value> {
+    first_media_schema(&operation["requestBody"]["content"]).or_else(|| {
+        operation["parameters"]
+            .as_array()?
+            .iter()
+            .find(|p| p["in"] == "body")
+            .map(|p| &p["schema"])
+    })
+}
+
+/// Schema of the first successful response of an operation
+fn response_schema(operation: &Value) -> Option<&Value> {
+    let (_, response) = operation["responses"]
+        .as_object()?
+        .iter()
+        .find(|(status, _)| status.starts_with('2'))?;
+    first_media_schema(&response["content"]).or_else(|| response.get("schema"))
+}
+
+/// Schema of the first media type in an OpenAPI 3 `content` map
+fn first_media_schema(content: &Value) -> Option<&Value> {
+    content
+        .as_object()?
+        .values()
+        .find_map(|media| media.get("schema"))
+}
+
+/// Root object and definitions of a JSON Schema document
+fn summarize_json_schema(document: &Value) -> String {
+    let mut lines = Vec::new();
+    let title = document["title"].as_str().unwrap_or("root");
+    lines.push(model_line(title, document));
+    for key in ["$defs", "definitions"] {
+        if let Some(definitions) = document[key].as_object() {
+            for (name, schema) in definitions {
+                lines.push(model_line(name, schema));
+            }
+        }
+    }
+    lines.join("\n")
+}
+
+/// `Name { field: type, optional?: type }` for an object schema, or
+/// `Name: type` for anything else
+fn model_line(name: &str, schema: &Value) -> String {
+    let Some(properties) = schema["properties"].as_object() else {
+        return format!("{}: {}", name, schema_type(schema));
+    };
+    let required: Vec<&str> = schema["required"]
+        .as_array()
+        .map(|r| r.iter().filter_map(Value::as_str).collect())
+        .unwrap_or_default();
+    let fields: Vec<String> = properties
+        .iter()
+        .map(|(field, property)| {
+            let marker = if required.contains(&field.as_str()) {
+                ""
+            } else {
+                "?"
+            };
+            format!("{}{}: {}", field, marker, schema_type(property))
+        })
+        .collect();
+    format!("{} {{ {} }}", name, fields.join(", "))
+}
+
+/// Short type of a schema: a referenced model name, `T[]`, an enum, or the
+/// JSON type
+fn schema_type(schema: &Value) -> String {
+    if let Some(reference) = schema["$ref"].as_str() {
+        return reference.rsplit('/').next().unwrap_or(reference).to_owned();
+    }
+    if let Some(values) = schema["enum"].as_array() {
+        return values
+            .iter()
+            .map(|v| {
+                v.as_str()
+                    .map_or_else(|| v.to_string(), |s| format!("\"{}\"", s))
+            })
+            .collect::<Vec<_>>()
+            .join(" | ");
+    }
+    for (key, separator) in [("oneOf", " | "), ("anyOf", " | "), ("allOf", " & ")] {
+        if let Some(variants) = schema[key].as_array() {
+            return variants
+                .iter()
+                .map(schema_type)
+                .collect::<Vec<_>>()
+                .join(separator);
+        }
+    }
+    match &schema["type"] {
+        Value::String(kind) if kind == "array" => format!("{}[]", schema_type(&schema["items"])),
+        Value::String(kind) => kind.clone(),
+        Value::Array(kinds) => kinds
+            .iter()
+            .filter_map(Value::as_str)
+            .collect::<Vec<_>>()
+            .join(" | "),
+        _ if schema.get("properties").is_some() => "object".to_owned(),
+        _ => "any".to_owned(),
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::str_to_string)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_protobuf_summary() {
+        let proto = r#"
+syntax = "proto3";
+package greeter.v1; // trailing comment
+
+/* Greets people */
+service Greeter {
+  rpc SayHello (HelloRequest) returns (HelloReply);
+  rpc Stream(stream HelloRequest) returns (stream HelloReply) {
+    option deprecated = true;
+  }
+}
+
+message HelloRequest {
+  string name = 1;
+  repeated string tags = 2 [packed = true];
+  oneof target {
+    string email = 3;
+  }
+  message Meta { int64 at = 1; }
+}
+
+enum Status {
+  option allow_alias = true;
+  STATUS_OK = 0;
+  STATUS_ERROR = 1;
+}
+"#;
+        let contract = Contract::from_file("api/greeter.proto", proto).unwrap();
+        assert_eq!(contract.kind, ContractKind::Protobuf);
+        assert_eq!(
+            contract.summary,
+            "package greeter.v1\n\
+             service Greeter\n  \
+             rpc SayHello(HelloRequest) returns (HelloReply)\n  \
+             rpc Stream(stream HelloRequest) returns (stream HelloReply)\n\
+             message HelloRequest { string name; repeated string tags; string email }\n\
+             message HelloRequest.Meta { int64 at }\n\
+             enum Status { STATUS_OK, STATUS_ERROR }"
+        );
+    }
+
+    #[test]
+    fn test_openapi_and_json_schema_summary() {
+        let openapi = r##"
+openapi: 3.0.0
+info:
+  title: Pets
+  version: "1.2"
+paths:
+  /pets/{id}:
+    get:
+      summary: Get a pet
+      responses:
+        "200":
+          content:
+            application/json:
+              schema:
+                $ref: "#/components/schemas/Pet"
+    post:
+      operationId: createPet
+      requestBody:
+        content:
+          application/json:
+            schema:
+              $ref: "#/components/schemas/Pet"
+      responses:
+        "201":
+          description: created
+components:
+  schemas:
+    Pet:
+      type: object
+      required: [id]
+      properties:
+        id: { type: integer }
+        tags: { type: array, items: { type: string } }
+        status: { enum: [available, sold] }
+"##;
+        let contract = Contract::from_file("openapi.yaml", openapi).unwrap();
+        assert_eq!(contract.kind, ContractKind::OpenApi);
+        assert_eq!(
+            contract.summary,
+            "API Pets (1.2)\n\
+             endpoints:\n  \
+             GET /pets/{id} -> Pet - Get a pet\n  \
+             POST /pets/{id} (Pet) - createPet\n\
+             models:\n  \
+             Pet { id: integer, status?: \"available\" | \"sold\", tags?: string[] }"
+        );
+
+        let schema = r##"{
+            "$schema": "https://json-schema.org/draft/2020-12/schema",
+            "title": "Config",
+            "type": "object",
+            "required": ["name"],
+            "properties": { "name": { "type": "string" }, "port": { "$ref": "#/$defs/Port" } },
+            "$defs": { "Port": { "type": "integer" } }
+        }"##;
+        let contract = Contract::from_file("config.schema.json", schema).unwrap();
+        assert_eq!(contract.kind, ContractKind::JsonSchema);
+        assert_eq!(contract.summary, "Config { name: string, port?: Port }\nPort: integer");
+
+        assert!(Contract::from_file("package.json", r#"{"name": "app"}"#).is_none());
+        assert!(Contract::from_file("main.rs", "fn main() {}").is_none());
+
+        let section = contracts_section(&[contract], Locale::En).unwrap();
+        assert_eq!(section.data[0]["kind"], "json_schema");
+        assert_eq!(section.data[0]["path"], "config.schema.json");
+        assert!(contracts_section(&[], Locale::En).is_none());
+    }
+}
@@ -0,0 +1,281 @@
+//! Bundled and on-demand data assets
+//!
+//! Everything the default feature set needs is compiled into the binary:
+//! the tiktoken vocabularies ship inside `tiktoken-rs` and the tree-sitter
+//! grammars are linked statically, so one release binary works offline.
+//!
+//! Assets too large to embed are described by a [`RemoteAsset`] with a pinned
+//! SHA-256 digest and fetched lazily into a [`DataCache`]. A download that
+//! does not match its digest is rejected and never written to the cache.
+
+use crate::cache::{write_atomic, CacheLock, CACHE_DIR_ENV};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use thiserror::Error;
+
+/// Environment variable overriding the data cache location
+pub const DATA_DIR_ENV: &str = "INFINILOOM_DATA_DIR";
+
+/// Kind of data asset
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AssetKind {
+    /// BPE vocabulary for exact token counting
+    Vocabulary,
+    /// Tree-sitter grammar for symbol extraction
+    Grammar,
+}
+
+impl AssetKind {
+    /// Lowercase name for display
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Vocabulary => "vocabulary",
+            Self::Grammar => "grammar",
+        }
+    }
+}
+
+/// An asset compiled into the binary
+#[derive(Debug, Clone, Copy)]
+pub struct EmbeddedAsset {
+    /// Asset name
+    pub name: &'static str,
+    /// What the asset is used for
+    pub kind: AssetKind,
+    /// Crate that provides the data
+    pub source: &'static str,
+}
+
+/// Assets available without network access or external files
+pub const EMBEDDED_ASSETS: &[EmbeddedAsset] = &[
+    EmbeddedAsset { name: "o200k_base", kind: AssetKind::Vocabulary, source: "tiktoken-rs" },
+    EmbeddedAsset { name: "cl100k_base", kind: AssetKind::Vocabulary, source: "tiktoken-rs" },
+    EmbeddedAsset { name: "python", kind: AssetKind::Grammar, source: "tree-sitter-python" },
+    EmbeddedAsset {
+        name: "javascript",
+        kind: AssetKind::Grammar,
+        source: "tree-sitter-javascript",
+    },
+    EmbeddedAsset {
+        name: "typescript",
+        kind: AssetKind::Grammar,
+        source: "tree-sitter-typescript",
+    },
+    EmbeddedAsset { name: "rust", kind: AssetKind::Grammar, source: "tree-sitter-rust" },
+    EmbeddedAsset { name: "go", kind: AssetKind::Grammar, source: "tree-sitter-go" },
+    EmbeddedAsset { name: "java", kind: AssetKind::Grammar, source: "tree-sitter-java" },
+    EmbeddedAsset { name: "c", kind: AssetKind::Grammar, source: "tree-sitter-c" },
+    EmbeddedAsset { name: "cpp", kind: AssetKind::Grammar, source: "tree-sitter-cpp" },
+];
+
+/// An asset fetched on first use and verified against a pinned digest
+#[derive(Debug, Clone, Copy)]
+pub struct RemoteAsset {
+    /// Asset name, used as the cache file name
+    pub name: &'static str,
+    /// What the asset is used for
+    pub kind: AssetKind,
+    /// Download location
+    pub url: &'static str,
+    /// Expected SHA-256 of the content, lowercase hex
+    pub sha256: &'static str,
+}
+
+/// Errors from the data cache
+#[derive(Debug, Error)]
+pub enum DataError {
+    #[error("I/O error: {0}")]
+    Io(#[from] io::Error),
+    #[error("failed to download {name}: {message}")]
+    Download { name: String, message: String },
+    #[error("checksum mismatch for {name}: expected {expected}, got {actual}")]
+    ChecksumMismatch { name: String, expected: String, actual: String },
+    #[error("{name} is not cached and downloads are disabled")]
+    Offline { name: String },
+}
+
+/// Local cache of verified [`RemoteAsset`]s
+#[derive(Debug, Clone)]
+pub struct DataCache {
+    /// Directory holding cached assets
+    dir: PathBuf,
+    /// Never download; only serve what is already cached
+    offline: bool,
+}
+
+impl DataCache {
+    /// Create a cache rooted at `dir`
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into(), offline: false }
+    }
+
+    /// Cache at the default location, if one can be determined
+    ///
+    /// Uses `INFINILOOM_DATA_DIR`, then `$INFINILOOM_CACHE_DIR/data`, then
+    /// `$XDG_CACHE_HOME/infiniloom/data`, then `~/.cache/infiniloom/data`.
+    pub fn default_location() -> Option<Self> {
+        let dir = std::env::var_os(DATA_DIR_ENV)
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os(CACHE_DIR_ENV).map(|dir| PathBuf::from(dir).join("data")))
+            .or_else(|| {
+                std::env::var_os("XDG_CACHE_HOME")
+                    .map(|cache| PathBuf::from(cache).join("infiniloom").join("data"))
+            })
+            .or_else(|| {
+                std::env::var_os("HOME")
+                    .or_else(|| std::env::var_os("USERPROFILE"))
+                    .map(|home| PathBuf::from(home).join(".cache/infiniloom/data"))
+            })?;
+        Some(Self::new(dir))
+    }
+
+    /// Refuse to download missing assets
+    pub fn with_offline(mut self, offline: bool) -> Self {
+        self.offline = offline;
+        self
+    }
+
+    /// Cache directory
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Where `asset` is stored; the digest prefix keeps re-pinned assets apart
+    pub fn path(&self, asset: &RemoteAsset) -> PathBuf {
+        let prefix = asset.sha256.get(..12).unwrap_or(asset.sha256);
+        self.dir.join(format!("{}-{}", asset.name, prefix))
+    }
+
+    /// Path to `asset` if it is cached and intact
+    ///
+    /// A cached file whose digest no longer matches is deleted. A hit marks
+    /// the file as recently used for [`CacheGc`](crate::cache::CacheGc).
+    pub fn get(&self, asset: &RemoteAsset) -> Result<Option<PathBuf>, DataError> {
+        let path = self.path(asset);
+        let _lock = CacheLock::shared(&path).ok();
+        let bytes = match fs::read(&path) {
+            Ok(bytes) => bytes,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+        if sha256_hex(&bytes) == asset.sha256 {
+            if let Ok(file) = fs::File::options().write(true).open(&path) {
+                file.set_modified(SystemTime::now()).ok();
+            }
+            Ok(Some(path))
+        } else {
+            fs::remove_file(&path)?;
+            Ok(None)
+        }
+    }
+
+    /// Path to `asset`, downloading it with `download` if not cached
+    ///
+    /// `download` receives the asset URL and returns the body; keeping the
+    /// transport with the caller means the engine itself needs no HTTP stack.
+    pub fn fetch<F>(&self, asset: &RemoteAsset, download: F) -> Result<PathBuf, DataError>
+    where
+        F: FnOnce(&str) -> Result<Vec<u8>, String>,
+    {
+        if let Some(path) = self.get(asset)? {
+            return Ok(path);
+        }
+        if self.offline {
+            return Err(DataError::Offline { name: asset.name.to_owned() });
+        }
+
+        let bytes = download(asset.url)
+            .map_err(|message| DataError::Download { name: asset.name.to_owned(), message })?;
+        let actual = sha256_hex(&bytes);
+        if actual != asset.sha256 {
+            return Err(DataError::ChecksumMismatch {
+                name: asset.name.to_owned(),
+                expected: asset.sha256.to_owned(),
+                actual,
+            });
+        }
+
+        // Write then rename so concurrent readers never see a partial file
+        let path = self.path(asset);
+        let _lock = CacheLock::exclusive(&path)?;
+        write_atomic(&path, &bytes)?;
+        Ok(path)
+    }
+}
+
+/// Lowercase hex SHA-256 of `bytes`
+pub fn sha256_hex(bytes: &[u8]) -> String {
+    Sha256::digest(bytes)
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+#[cfg(test)]
+#[allow(clippy::str_to_string)]
+mod tests {
+    use super::*;
+    use crate::tokenizer::{TokenModel, Tokenizer};
+
+    // SHA-256 of "hello"
+    const HELLO: RemoteAsset = RemoteAsset {
+        name: "hello",
+        kind: AssetKind::Vocabulary,
+        url: "https://example.invalid/hello",
+        sha256: "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824",
+    };
+
+    #[test]
+    fn test_embedded_vocabularies_work_offline() {
+        let tokenizer = Tokenizer::new();
+        assert!(tokenizer.count("hello world", TokenModel::Gpt4o) > 0);
+        assert!(tokenizer.count("hello world", TokenModel::Gpt4) > 0);
+        assert!(EMBEDDED_ASSETS
+            .iter()
+            .any(|a| a.kind == AssetKind::Grammar && a.name == "rust"));
+    }
+
+    #[test]
+    fn test_fetch_verifies_and_caches() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = DataCache::new(dir.path());
+
+        let path = cache
+            .fetch(&HELLO, |url| {
+                assert_eq!(url, HELLO.url);
+                Ok(b"hello".to_vec())
+            })
+            .unwrap();
+        assert_eq!(fs::read(&path).unwrap(), b"hello");
+
+        // Served from the cache without downloading again
+        let cached = cache
+            .with_offline(true)
+            .fetch(&HELLO, |_| panic!("should not download"))
+            .unwrap();
+        assert_eq!(cached, path);
+    }
+
+    #[test]
+    fn test_fetch_rejects_bad_checksum() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = DataCache::new(dir.path());
+
+        let err = cache
+            .fetch(&HELLO, |_| Ok(b"tampered".to_vec()))
+            .unwrap_err();
+        assert!(matches!(err, DataError::ChecksumMismatch { .. }));
+        assert!(!cache.path(&HELLO).exists());
+
+        // A corrupted cache entry is discarded
+        fs::write(cache.path(&HELLO), b"corrupt").unwrap();
+        assert!(cache.get(&HELLO).unwrap().is_none());
+        assert!(!cache.path(&HELLO).exists());
+
+        let offline = cache.with_offline(true).fetch(&HELLO, |_| unreachable!());
+        assert!(matches!(offline, Err(DataError::Offline { .. })));
+    }
+}
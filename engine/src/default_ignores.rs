@@ -3,6 +3,8 @@
 //! These patterns are applied by default to exclude common non-essential files
 //! that waste tokens without adding value for LLM context.
 
+use std::path::{Component, Path};
+
 /// Default patterns to ignore (dependencies, build outputs, etc.)
 pub const DEFAULT_IGNORES: &[&str] = &[
     // === Dependencies ===
@@ -142,6 +144,15 @@ pub const DEFAULT_IGNORES: &[&str] = &[
     "*.backup",
 ];
 
+/// Example environment files kept although `.env.*` is in [`DEFAULT_IGNORES`]
+pub const ENV_TEMPLATES: &[&str] =
+    &["**/.env.example", "**/.env.sample", "**/.env.template", "**/.env.dist"];
+
+/// Whether [`DEFAULT_IGNORES`] excludes a repository-relative path
+pub fn is_default_ignored(path: &str) -> bool {
+    matches_any(path, DEFAULT_IGNORES) && !matches_any(path, ENV_TEMPLATES)
+}
+
 /// Patterns for test files (can be optionally excluded)
 pub const TEST_IGNORES: &[&str] = &[
     "**/test/**",
@@ -181,6 +192,162 @@ pub const DOC_IGNORES: &[&str] = &[
     "CODE_OF_CONDUCT*",
 ];
 
+/// Infiniloom's own caches and bookkeeping files
+///
+/// Packing these would feed earlier runs back into the next one, so they are
+/// skipped even when the default ignores are disabled.
+pub const ARTIFACT_IGNORES: &[&str] =
+    &[".infiniloom/**", "**/.infiniloom/**", ".pack-manifest.json", "**/.pack-manifest.json"];
+
+/// Hidden paths scanned even without `--hidden`
+///
+/// CI and dev-container setup, lint and format settings, tool version pins
+/// and example environment files describe how the code is built and run.
+/// Editor state, caches and real `.env` files stay out.
+pub const DEFAULT_HIDDEN_ALLOWLIST: &[&str] = &[
+    // === CI and dev environments ===
+    ".github",
+    ".gitlab",
+    ".gitlab-ci.yml",
+    ".circleci",
+    ".buildkite",
+    ".travis.yml",
+    ".devcontainer",
+    ".husky",
+    ".pre-commit-config.yaml",
+    // === Example environment files ===
+    ".env.example",
+    ".env.sample",
+    ".env.template",
+    ".env.dist",
+    // === Lint and format settings ===
+    ".editorconfig",
+    ".eslintrc*",
+    ".prettierrc*",
+    ".stylelintrc*",
+    ".babelrc*",
+    ".rubocop.yml",
+    ".golangci.y*ml",
+    ".rustfmt.toml",
+    ".clang-format",
+    ".clang-tidy",
+    // === Tool versions and build settings ===
+    ".cargo",
+    ".nvmrc",
+    ".node-version",
+    ".python-version",
+    ".ruby-version",
+    ".tool-versions",
+];
+
+/// Which hidden (dot-prefixed) files and directories a scan includes
+///
+/// By default only paths matching [`DEFAULT_HIDDEN_ALLOWLIST`] are included.
+/// An allowlist pattern without a `/` matches a hidden file or directory by
+/// name at any depth; one with a `/` matches its path from the repository
+/// root, and the hidden directories leading to it are entered. Everything
+/// inside an allowed directory is included.
+#[derive(Debug, Clone)]
+pub struct HiddenPolicy {
+    /// Include every hidden path
+    include_all: bool,
+    /// Hidden paths included anyway
+    allowlist: Vec<glob::Pattern>,
+}
+
+impl HiddenPolicy {
+    /// Include every hidden path if `include_all`, otherwise the default allowlist
+    pub fn new(include_all: bool) -> Self {
+        Self { include_all, allowlist: Vec::new() }.with_allowed(DEFAULT_HIDDEN_ALLOWLIST)
+    }
+
+    /// Allow more hidden paths; invalid patterns are ignored
+    pub fn with_allowed<I, S>(mut self, patterns: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.allowlist.extend(
+            patterns
+                .into_iter()
+                .filter_map(|p| glob::Pattern::new(p.as_ref().trim_matches('/')).ok()),
+        );
+        self
+    }
+
+    /// Whether a path relative to the repository root is included
+    ///
+    /// `is_dir` tells whether the path itself is a directory.
+    pub fn allows(&self, relative: &Path, is_dir: bool) -> bool {
+        if self.include_all {
+            return true;
+        }
+
+        let components: Vec<String> = relative
+            .components()
+            .filter_map(|c| match c {
+                Component::Normal(name) => Some(name.to_string_lossy().into_owned()),
+                _ => None,
+            })
+            .collect();
+        let Some(hidden) = components.iter().position(|name| name.starts_with('.')) else {
+            return true;
+        };
+
+        let (rooted, named): (Vec<_>, Vec<_>) = self
+            .allowlist
+            .iter()
+            .partition(|p| p.as_str().contains('/'));
+        if named.iter().any(|p| p.matches(&components[hidden])) {
+            return true;
+        }
+        // The path, or a directory it lies in, matches a rooted pattern
+        if (hidden..components.len()).any(|i| {
+            rooted
+                .iter()
+                .any(|p| p.matches(&components[..=i].join("/")))
+        }) {
+            return true;
+        }
+        // A directory on the way to a rooted pattern
+        let path = components.join("/");
+        is_dir
+            && rooted.iter().any(|p| {
+                p.as_str()
+                    .strip_prefix(path.as_str())
+                    .is_some_and(|rest| rest.starts_with('/'))
+            })
+    }
+}
+
+impl Default for HiddenPolicy {
+    fn default() -> Self {
+        Self::new(false)
+    }
+}
+
+/// Whether `content` looks like the output of an earlier `pack`
+///
+/// Checks the opening lines for the headers each output format writes, so a
+/// previous `context.xml` is recognized whatever it was named.
+pub fn looks_like_pack_output(content: &str) -> bool {
+    let head: Vec<&str> = content.lines().take(6).map(str::trim).collect();
+    let line = |i: usize| head.get(i).copied().unwrap_or("");
+
+    // XML
+    (line(1).starts_with("<repository name=") && line(2) == "<llm_context_guide>")
+        // Markdown
+        || (line(0).starts_with("# Repository: ") && line(2).starts_with("> **Files**:"))
+        // Plain text
+        || (line(0).starts_with("Repository: ") && line(1).starts_with("Files: ") && line(1).contains("| Tokens:"))
+        // JSON
+        || (line(0) == "{" && line(1) == "\"repository\": {" && line(2).starts_with("\"name\":"))
+        // YAML and TOON
+        || head.iter().take(2).any(|l| {
+            l.starts_with("# Repository Context for ") || l.starts_with("# Infiniloom Repository Context")
+        })
+}
+
 /// Check if a path matches any of the given glob patterns
 pub fn matches_any(path: &str, patterns: &[&str]) -> bool {
     for pattern in patterns {
@@ -207,6 +374,16 @@ pub fn matches_any(path: &str, patterns: &[&str]) -> bool {
     false
 }
 
+/// Whether a pack keeps `path` under the default ignores
+///
+/// Dependency, build and generated paths always go; tests and docs go unless
+/// asked for.
+pub fn keeps_by_default(path: &str, include_tests: bool, include_docs: bool) -> bool {
+    !is_default_ignored(path)
+        && (include_tests || !matches_any(path, TEST_IGNORES))
+        && (include_docs || !matches_any(path, DOC_IGNORES))
+}
+
 /// Filter files based on default ignore patterns
 pub fn filter_default_ignores<'a>(
     files: impl Iterator<Item = &'a str>,
@@ -214,24 +391,7 @@ pub fn filter_default_ignores<'a>(
     include_docs: bool,
 ) -> Vec<&'a str> {
     files
-        .filter(|path| {
-            // Always apply default ignores
-            if matches_any(path, DEFAULT_IGNORES) {
-                return false;
-            }
-
-            // Optionally filter tests
-            if !include_tests && matches_any(path, TEST_IGNORES) {
-                return false;
-            }
-
-            // Optionally filter docs
-            if !include_docs && matches_any(path, DOC_IGNORES) {
-                return false;
-            }
-
-            true
-        })
+        .filter(|path| keeps_by_default(path, include_tests, include_docs))
         .collect()
 }
 
@@ -249,6 +409,50 @@ mod tests {
 
         assert!(!matches_any("src/index.ts", DEFAULT_IGNORES));
         assert!(!matches_any("lib/utils.py", DEFAULT_IGNORES));
+
+        assert!(is_default_ignored(".env.local"));
+        assert!(!is_default_ignored(".env.example"));
+        assert!(!is_default_ignored("web/.env.sample"));
+    }
+
+    #[test]
+    fn test_hidden_policy() {
+        let policy = HiddenPolicy::default();
+        assert!(policy.allows(Path::new("src/main.rs"), false));
+        assert!(policy.allows(Path::new(".github/workflows/ci.yml"), false));
+        assert!(policy.allows(Path::new(".github"), true));
+        assert!(policy.allows(Path::new("web/.env.example"), false));
+        assert!(policy.allows(Path::new(".eslintrc.json"), false));
+        assert!(!policy.allows(Path::new(".env"), false));
+        assert!(!policy.allows(Path::new(".idea/workspace.xml"), false));
+        assert!(!policy.allows(Path::new(".cache"), true));
+
+        let policy = HiddenPolicy::new(false).with_allowed([".vscode/settings.json"]);
+        assert!(policy.allows(Path::new(".vscode"), true));
+        assert!(policy.allows(Path::new(".vscode/settings.json"), false));
+        assert!(!policy.allows(Path::new(".vscode/launch.json"), false));
+
+        assert!(HiddenPolicy::new(true).allows(Path::new(".idea/workspace.xml"), false));
+    }
+
+    #[test]
+    fn test_artifact_ignores() {
+        assert!(matches_any(".infiniloom/cache/repo.cache", ARTIFACT_IGNORES));
+        assert!(matches_any("packages/web/.infiniloom/cache/repo.cache", ARTIFACT_IGNORES));
+        assert!(matches_any(".pack-manifest.json", ARTIFACT_IGNORES));
+        assert!(!matches_any("src/infiniloom.rs", ARTIFACT_IGNORES));
+    }
+
+    #[test]
+    fn test_looks_like_pack_output() {
+        let xml = "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<repository name=\"x\" version=\"1.0.0\">\n  <llm_context_guide>\n";
+        assert!(looks_like_pack_output(xml));
+        assert!(looks_like_pack_output("# Repository: x\n\n> **Files**: 4 | **Lines**: 4\n"));
+        assert!(looks_like_pack_output("{\n  \"repository\": {\n    \"name\": \"x\",\n"));
+        assert!(looks_like_pack_output("---\n# Repository Context for Gemini\n"));
+
+        assert!(!looks_like_pack_output("# Repository: notes\n\nSome text\n"));
+        assert!(!looks_like_pack_output("<?xml version=\"1.0\"?>\n<beans>\n"));
     }
 
     #[test]
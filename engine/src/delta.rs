@@ -0,0 +1,379 @@
+//! Delta packs that resend only what changed since a previous pack
+//!
+//! A [`PackManifest`] records a content hash for every packed file. When the
+//! conversation already holds an earlier pack, [`apply_delta`] drops the files
+//! whose hash is unchanged and returns a [`Delta`] describing them, so the new
+//! pack carries changed files in full plus a short reference list.
+//!
+//! Manifests also record the hash of each packed file as it was on disk, so
+//! [`PackManifest::freshness`] can tell a long-lived session which files have
+//! changed since and what a delta refresh would cost before re-packing.
+
+use crate::cache::write_atomic;
+use crate::data::sha256_hex;
+use crate::git::GitRepo;
+use crate::output::{Locale, Section};
+use crate::tokenizer::Tokenizer;
+use crate::types::{Repository, TokenCounts, TokenizerModel};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::BTreeMap;
+use std::path::Path;
+use thiserror::Error;
+
+/// Current manifest format version
+pub const MANIFEST_VERSION: u32 = 1;
+
+/// Length of the hash prefix shown in reference lists
+const SHORT_HASH_LEN: usize = 12;
+
+/// Errors reading or writing manifests
+#[derive(Debug, Error)]
+pub enum DeltaError {
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("invalid manifest: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("unsupported manifest version {0} (expected {MANIFEST_VERSION})")]
+    Version(u32),
+    #[error("manifest has no source hashes, write it again with a current pack")]
+    NoSources,
+}
+
+/// Content hashes of the files in a pack, keyed by path as packed
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PackManifest {
+    /// Manifest format version
+    pub version: u32,
+    /// SHA-256 of each file's packed content
+    pub files: BTreeMap<String, String>,
+    /// SHA-256 of each packed file as read from disk, keyed by path relative
+    /// to the repository root
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub sources: BTreeMap<String, String>,
+}
+
+impl Default for PackManifest {
+    fn default() -> Self {
+        Self { version: MANIFEST_VERSION, files: BTreeMap::new(), sources: BTreeMap::new() }
+    }
+}
+
+impl PackManifest {
+    /// Hash every file with content in `repo`
+    pub fn from_repository(repo: &Repository) -> Self {
+        let files = repo
+            .files
+            .iter()
+            .filter_map(|f| {
+                let content = f.content.as_ref()?;
+                Some((f.relative_path.clone(), sha256_hex(content.as_bytes())))
+            })
+            .collect();
+        Self { version: MANIFEST_VERSION, files, sources: BTreeMap::new() }
+    }
+
+    /// Record the on-disk hash of every file in `repo` for later freshness checks
+    ///
+    /// Transforms change what gets packed, so these hashes come from the files
+    /// themselves. Files that can no longer be read are left out.
+    pub fn with_sources(mut self, repo: &Repository) -> Self {
+        self.sources = repo
+            .files
+            .iter()
+            .filter_map(|f| {
+                let relative = f.path.strip_prefix(&repo.path).ok()?;
+                let bytes = std::fs::read(&f.path).ok()?;
+                Some((relative.to_string_lossy().replace('\\', "/"), sha256_hex(&bytes)))
+            })
+            .collect();
+        self
+    }
+
+    /// Compare the recorded sources against the working tree under `root`
+    pub fn freshness(&self, root: &Path, model: TokenizerModel) -> Result<Freshness, DeltaError> {
+        self.compare_sources(model, |path| std::fs::read(root.join(path)).ok())
+    }
+
+    /// Compare the recorded sources against the files at `rev` in `repo`
+    pub fn freshness_at(
+        &self,
+        repo: &GitRepo,
+        rev: &str,
+        model: TokenizerModel,
+    ) -> Result<Freshness, DeltaError> {
+        self.compare_sources(model, |path| repo.file_at(rev, path).ok().map(String::into_bytes))
+    }
+
+    fn compare_sources(
+        &self,
+        model: TokenizerModel,
+        read: impl Fn(&str) -> Option<Vec<u8>>,
+    ) -> Result<Freshness, DeltaError> {
+        if self.sources.is_empty() && !self.files.is_empty() {
+            return Err(DeltaError::NoSources);
+        }
+
+        let tokenizer = Tokenizer::estimation_only();
+        let mut freshness = Freshness::default();
+        let mut delta = Delta::default();
+        for (path, hash) in &self.sources {
+            match read(path) {
+                Some(bytes) if sha256_hex(&bytes) == *hash => {
+                    freshness.unchanged += 1;
+                    delta.unchanged.push((path.clone(), hash.clone()));
+                },
+                Some(bytes) => {
+                    let counts: TokenCounts =
+                        tokenizer.count_all(&String::from_utf8_lossy(&bytes)).into();
+                    freshness.changed.push((path.clone(), counts.get(model)));
+                },
+                None => {
+                    freshness.removed.push(path.clone());
+                    delta.removed.push(path.clone());
+                },
+            }
+        }
+
+        // A refresh resends changed files and lists the rest by hash
+        if !freshness.is_fresh() {
+            let counts: TokenCounts = tokenizer.count_all(&delta.render()).into();
+            freshness.refresh_tokens = freshness
+                .changed
+                .iter()
+                .map(|(_, tokens)| tokens)
+                .sum::<u32>()
+                + counts.get(model);
+        }
+        Ok(freshness)
+    }
+
+    /// Read a manifest written by [`PackManifest::save`]
+    pub fn load(path: &Path) -> Result<Self, DeltaError> {
+        let manifest: Self = serde_json::from_str(&std::fs::read_to_string(path)?)?;
+        if manifest.version != MANIFEST_VERSION {
+            return Err(DeltaError::Version(manifest.version));
+        }
+        Ok(manifest)
+    }
+
+    /// Write the manifest as JSON
+    pub fn save(&self, path: &Path) -> Result<(), DeltaError> {
+        write_atomic(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+}
+
+/// Files left out of a delta pack
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Delta {
+    /// Files identical to the previous pack, with their content hash
+    pub unchanged: Vec<(String, String)>,
+    /// Files in the previous pack that no longer exist
+    pub removed: Vec<String>,
+}
+
+impl Delta {
+    /// Whether nothing was left out
+    pub fn is_empty(&self) -> bool {
+        self.unchanged.is_empty() && self.removed.is_empty()
+    }
+
+    /// Compact reference list to append to the pack
+    pub fn render(&self) -> String {
+        self.render_localized(Locale::default())
+    }
+
+    /// [`Delta::render`] with titles and notes in `locale`
+    pub fn render_localized(&self, locale: Locale) -> String {
+        let text = locale.strings();
+        let mut out = String::new();
+        if !self.unchanged.is_empty() {
+            out.push_str(&format!("\n\n<!-- {} -->\n", text.unchanged_title));
+            out.push_str(
+                &text
+                    .unchanged_intro
+                    .replace("{count}", &self.unchanged.len().to_string()),
+            );
+            out.push_str("\n\n");
+            for (path, hash) in &self.unchanged {
+                let short = hash.get(..SHORT_HASH_LEN).unwrap_or(hash);
+                out.push_str(&format!("- {} ({} {})\n", path, text.hash, short));
+            }
+        }
+        if !self.removed.is_empty() {
+            out.push_str(&format!("\n\n<!-- {} -->\n", text.removed_title));
+            for path in &self.removed {
+                out.push_str(&format!("- {}\n", path));
+            }
+        }
+        out
+    }
+
+    /// The reference list as a report section, `None` if nothing was left out
+    pub fn section(&self, locale: Locale) -> Option<Section> {
+        if self.is_empty() {
+            return None;
+        }
+        let unchanged: Vec<_> = self
+            .unchanged
+            .iter()
+            .map(|(path, hash)| json!({ "path": path, "hash": hash }))
+            .collect();
+        let data = json!({ "unchanged": unchanged, "removed": self.removed });
+        Some(Section::new("delta", self.render_localized(locale), &data))
+    }
+}
+
+/// Packed files whose source changed since a manifest was written
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Freshness {
+    /// Files that differ from the packed version, with their current token count
+    pub changed: Vec<(String, u32)>,
+    /// Files that no longer exist
+    pub removed: Vec<String>,
+    /// Number of files still identical to the packed version
+    pub unchanged: usize,
+    /// Estimated tokens of a delta pack bringing the session up to date
+    pub refresh_tokens: u32,
+}
+
+impl Freshness {
+    /// Whether the packed context still matches the sources
+    pub fn is_fresh(&self) -> bool {
+        self.changed.is_empty() && self.removed.is_empty()
+    }
+}
+
+/// Drop files from `repo` whose content matches `previous`
+///
+/// Files that are new or changed stay in the repository.
+pub fn apply_delta(repo: &mut Repository, previous: &PackManifest) -> Delta {
+    let current = PackManifest::from_repository(repo);
+
+    let mut unchanged = Vec::new();
+    repo.files
+        .retain(|f| match current.files.get(&f.relative_path) {
+            Some(hash) if previous.files.get(&f.relative_path) == Some(hash) => {
+                unchanged.push((f.relative_path.clone(), hash.clone()));
+                false
+            },
+            _ => true,
+        });
+
+    let removed = previous
+        .files
+        .keys()
+        .filter(|path| !current.files.contains_key(*path))
+        .cloned()
+        .collect();
+
+    Delta { unchanged, removed }
+}
+
+#[cfg(test)]
+#[allow(clippy::str_to_string)]
+mod tests {
+    use super::*;
+    use crate::types::RepoFile;
+
+    fn repo(files: &[(&str, &str)]) -> Repository {
+        let mut repo = Repository::new("test", "/tmp/test");
+        repo.files = files
+            .iter()
+            .map(|(path, content)| RepoFile {
+                path: format!("/tmp/test/{}", path).into(),
+                relative_path: path.to_string(),
+                size_bytes: content.len() as u64,
+                importance: 0.5,
+                content: Some(content.to_string()),
+                ..Default::default()
+            })
+            .collect();
+        repo
+    }
+
+    #[test]
+    fn test_apply_delta() {
+        let previous = PackManifest::from_repository(&repo(&[
+            ("a.rs", "fn a() {}"),
+            ("b.rs", "fn b() {}"),
+            ("c.rs", ""),
+        ]));
+
+        let mut current = repo(&[("a.rs", "fn a() {}"), ("b.rs", "fn b() { 1 }"), ("d.rs", "")]);
+        let delta = apply_delta(&mut current, &previous);
+
+        let kept: Vec<_> = current
+            .files
+            .iter()
+            .map(|f| f.relative_path.as_str())
+            .collect();
+        assert_eq!(kept, ["b.rs", "d.rs"]);
+        assert_eq!(delta.unchanged.len(), 1);
+        assert_eq!(delta.unchanged[0].0, "a.rs");
+        assert_eq!(delta.removed, ["c.rs"]);
+
+        let rendered = delta.render();
+        assert!(rendered.contains("- a.rs (hash "));
+        assert!(rendered.contains("- c.rs"));
+
+        let section = delta.section(Locale::En).unwrap();
+        assert_eq!(section.data["unchanged"][0]["path"], "a.rs");
+        assert_eq!(section.data["removed"][0], "c.rs");
+        assert!(Delta::default().section(Locale::En).is_none());
+    }
+
+    #[test]
+    fn test_manifest_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("manifest.json");
+
+        let manifest = PackManifest::from_repository(&repo(&[("a.rs", "fn a() {}")]));
+        manifest.save(&path).unwrap();
+        assert_eq!(PackManifest::load(&path).unwrap(), manifest);
+
+        std::fs::write(&path, r#"{"version": 99, "files": {}}"#).unwrap();
+        assert!(matches!(PackManifest::load(&path), Err(DeltaError::Version(99))));
+
+        // Manifests without source hashes still load
+        std::fs::write(&path, r#"{"version": 1, "files": {}}"#).unwrap();
+        assert!(PackManifest::load(&path).unwrap().sources.is_empty());
+    }
+
+    #[test]
+    fn test_freshness() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().canonicalize().unwrap();
+        for (path, content) in [("a.rs", "fn a() {}"), ("b.rs", "fn b() {}"), ("c.rs", "")] {
+            std::fs::write(root.join(path), content).unwrap();
+        }
+        let mut packed = repo(&[("a.rs", "fn a() {}"), ("b.rs", "fn b() {}"), ("c.rs", "")]);
+        packed.path = root.clone();
+        for file in &mut packed.files {
+            file.path = root.join(&file.relative_path);
+        }
+        let manifest = PackManifest::from_repository(&packed).with_sources(&packed);
+        assert_eq!(manifest.sources.len(), 3);
+
+        let fresh = manifest.freshness(&root, TokenizerModel::Claude).unwrap();
+        assert!(fresh.is_fresh());
+        assert_eq!(fresh.unchanged, 3);
+        assert_eq!(fresh.refresh_tokens, 0);
+
+        std::fs::write(root.join("b.rs"), "fn b() { 1 }").unwrap();
+        std::fs::remove_file(root.join("c.rs")).unwrap();
+        let stale = manifest.freshness(&root, TokenizerModel::Claude).unwrap();
+        assert_eq!(stale.unchanged, 1);
+        assert_eq!(stale.changed.len(), 1);
+        assert_eq!(stale.changed[0].0, "b.rs");
+        assert_eq!(stale.removed, ["c.rs"]);
+        assert!(stale.refresh_tokens > stale.changed[0].1);
+
+        let bare = PackManifest::from_repository(&packed);
+        assert!(matches!(
+            bare.freshness(&root, TokenizerModel::Claude),
+            Err(DeltaError::NoSources)
+        ));
+    }
+}
@@ -3,10 +3,12 @@
 //! This module provides comprehensive dependency analysis using actual AST parsing
 //! to build accurate import graphs, call graphs, and symbol reference tracking.
 
+use crate::parser::{Language, LanguageRegistry};
 use crate::types::{RepoFile, Repository, SymbolKind};
 use petgraph::algo::tarjan_scc;
 use petgraph::graph::{DiGraph, NodeIndex};
-use std::collections::{HashMap, HashSet};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap, HashSet};
 use std::path::Path;
 
 /// A node in the dependency graph
@@ -101,6 +103,15 @@ pub enum ReferenceContext {
     Reference,
 }
 
+/// An external package and the repository files that import it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExternalUsage {
+    /// Package name as imported
+    pub package: String,
+    /// Relative paths of the importing files, sorted
+    pub files: Vec<String>,
+}
+
 /// Full dependency graph for a repository
 pub struct DependencyGraph {
     /// Graph of file-level dependencies
@@ -115,6 +126,8 @@ pub struct DependencyGraph {
     imports: Vec<ResolvedImport>,
     /// External dependencies (packages not in repo)
     external_deps: HashSet<String>,
+    /// Files importing each external dependency
+    external_importers: HashMap<String, BTreeSet<String>>,
     /// Circular dependency groups
     circular_deps: Vec<Vec<String>>,
 }
@@ -129,6 +142,7 @@ impl DependencyGraph {
             symbol_to_file: HashMap::new(),
             imports: Vec::new(),
             external_deps: HashSet::new(),
+            external_importers: HashMap::new(),
             circular_deps: Vec::new(),
         }
     }
@@ -222,7 +236,7 @@ impl DependencyGraph {
                         self.graph.add_edge(from_idx, to_idx, edge);
                     }
                 } else if resolved.is_external {
-                    self.external_deps.insert(resolved.specifier.clone());
+                    self.add_external(resolved.specifier.clone(), &file.relative_path);
                 }
 
                 self.imports.push(resolved);
@@ -289,11 +303,20 @@ impl DependencyGraph {
         // Add found packages to external deps (filtered for validity)
         for pkg in found_packages {
             if Self::is_valid_package_name(&pkg) {
-                self.external_deps.insert(pkg);
+                self.add_external(pkg, &file.relative_path);
             }
         }
     }
 
+    /// Record that `importer` uses the external package `package`
+    fn add_external(&mut self, package: String, importer: &str) {
+        self.external_importers
+            .entry(package.clone())
+            .or_default()
+            .insert(importer.to_owned());
+        self.external_deps.insert(package);
+    }
+
     /// Check if an import specifier is external (not a relative path)
     fn is_external_specifier(spec: &str) -> bool {
         !spec.starts_with('.') && !spec.starts_with('/')
@@ -342,8 +365,21 @@ impl DependencyGraph {
         let mut imports = Vec::new();
         let text = import_text.trim();
 
-        // Python: import x / from x import y
-        if text.starts_with("import ") {
+        // C/C++: #include "x.h" (project header) or #include <x.h> (system header)
+        if let Some(path) = text.strip_prefix("#include") {
+            let path = path.trim();
+            if path.starts_with(['"', '<']) {
+                imports.push(ParsedImport {
+                    specifier: path.to_owned(),
+                    symbols: vec![],
+                    import_type: DependencyType::Import,
+                });
+            }
+            return imports;
+        }
+
+        // Python: import x / from x import y (JavaScript imports quote their specifier)
+        if text.starts_with("import ") && !text.contains(['\'', '"']) {
             let module = text.trim_start_matches("import ").trim();
             // Handle "import x as y"
             let module = module.split(" as ").next().unwrap_or(module);
@@ -505,6 +541,10 @@ impl DependencyGraph {
     ) -> ResolvedImport {
         let specifier = &import.specifier;
 
+        if matches!(from_file.language.as_deref(), Some("c" | "cpp")) {
+            return self.resolve_include(import, from_file, repo);
+        }
+
         // Check if external package
         if self.is_external_import(specifier) {
             return ResolvedImport {
@@ -568,6 +608,56 @@ impl DependencyGraph {
         }
     }
 
+    /// Resolve a C/C++ `#include` specifier (`"x.h"` or `<x.h>`)
+    ///
+    /// Quoted headers are looked up next to the including file, from the
+    /// repository root, under `include/` and `src/`, and finally anywhere in
+    /// the tree, since include paths usually come from build flags. Angle
+    /// bracket headers are external.
+    fn resolve_include(
+        &self,
+        import: &ParsedImport,
+        from_file: &RepoFile,
+        repo: &Repository,
+    ) -> ResolvedImport {
+        let result = |specifier: &str, to_path: Option<String>, is_external: bool| ResolvedImport {
+            from_path: from_file.relative_path.clone(),
+            to_path,
+            specifier: specifier.to_owned(),
+            symbols: import.symbols.clone(),
+            import_type: import.import_type,
+            line: 0,
+            is_external,
+        };
+
+        if let Some(system) = import.specifier.strip_prefix('<') {
+            return result(system.trim_end_matches('>'), None, true);
+        }
+        let header = import.specifier.trim_matches('"');
+
+        let base_dir = Path::new(&from_file.relative_path)
+            .parent()
+            .map(|p| p.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let candidates = [
+            normalize_path(&format!("{}/{}", base_dir, header)),
+            normalize_path(header),
+            format!("include/{}", header),
+            format!("src/{}", header),
+        ];
+        let suffix = format!("/{}", header);
+        let to_path = candidates
+            .into_iter()
+            .find(|c| repo.files.iter().any(|f| &f.relative_path == c))
+            .or_else(|| {
+                repo.files
+                    .iter()
+                    .find(|f| f.relative_path.ends_with(&suffix))
+                    .map(|f| f.relative_path.clone())
+            });
+        result(header, to_path, false)
+    }
+
     /// Check if import is for an external package
     fn is_external_import(&self, specifier: &str) -> bool {
         // Relative imports are internal
@@ -630,6 +720,7 @@ impl DependencyGraph {
         let mut candidates = Vec::new();
 
         // Language-specific resolution
+        let registered;
         let extensions = match language.as_deref() {
             Some("python") => vec!["py", "pyi"],
             Some("javascript") | Some("jsx") => vec!["js", "jsx", "mjs", "cjs", "ts", "tsx"],
@@ -637,7 +728,17 @@ impl DependencyGraph {
             Some("rust") => vec!["rs"],
             Some("go") => vec!["go"],
             Some("java") => vec!["java"],
-            _ => vec![""],
+            // Grammars registered at runtime resolve to their own extensions
+            Some(name) => {
+                registered = LanguageRegistry::language_named(name)
+                    .filter(Language::is_custom)
+                    .map(LanguageRegistry::extensions)
+                    .unwrap_or_default();
+                let mut extensions: Vec<&str> = registered.iter().map(String::as_str).collect();
+                extensions.push("");
+                extensions
+            },
+            None => vec![""],
         };
 
         // Relative path resolution
@@ -797,6 +898,44 @@ impl DependencyGraph {
         }
     }
 
+    /// Files within `depth` import hops of any of `seeds`, in either direction,
+    /// with their distance from the nearest seed
+    ///
+    /// Seeds are included at distance 0.
+    pub fn neighborhood<'a, I>(&self, seeds: I, depth: usize) -> HashMap<String, usize>
+    where
+        I: IntoIterator<Item = &'a str>,
+    {
+        let mut distances: HashMap<String, usize> = HashMap::new();
+        let mut frontier: Vec<&str> = Vec::new();
+        for seed in seeds {
+            if distances.insert(seed.to_owned(), 0).is_none() {
+                frontier.push(seed);
+            }
+        }
+
+        for distance in 1..=depth {
+            let mut next = Vec::new();
+            for path in frontier {
+                for neighbor in self
+                    .get_imports(path)
+                    .into_iter()
+                    .chain(self.get_importers(path))
+                {
+                    if !distances.contains_key(neighbor) {
+                        distances.insert(neighbor.to_owned(), distance);
+                        next.push(neighbor);
+                    }
+                }
+            }
+            if next.is_empty() {
+                break;
+            }
+            frontier = next;
+        }
+        distances
+    }
+
     /// Get all circular dependency groups
     pub fn get_circular_deps(&self) -> &[Vec<String>] {
         &self.circular_deps
@@ -807,6 +946,27 @@ impl DependencyGraph {
         &self.external_deps
     }
 
+    /// External dependencies with the files importing them, most used first
+    ///
+    /// Ties are broken by package name so the order is deterministic.
+    pub fn external_usage(&self) -> Vec<ExternalUsage> {
+        let mut usage: Vec<ExternalUsage> = self
+            .external_importers
+            .iter()
+            .map(|(package, files)| ExternalUsage {
+                package: package.clone(),
+                files: files.iter().cloned().collect(),
+            })
+            .collect();
+        usage.sort_by(|a, b| {
+            b.files
+                .len()
+                .cmp(&a.files.len())
+                .then_with(|| a.package.cmp(&b.package))
+        });
+        usage
+    }
+
     /// Get top N most important files by import graph
     pub fn get_most_important(&self, n: usize) -> Vec<(&str, f64)> {
         let mut nodes: Vec<_> = self
@@ -893,7 +1053,7 @@ fn normalize_path(path: &str) -> String {
 #[allow(clippy::str_to_string)]
 mod tests {
     use super::*;
-    use crate::types::{Symbol, TokenCounts};
+    use crate::types::Symbol;
 
     #[allow(dead_code)]
     fn create_test_file(path: &str, imports: Vec<&str>) -> RepoFile {
@@ -911,10 +1071,9 @@ mod tests {
             relative_path: path.to_string(),
             language: Some("python".to_string()),
             size_bytes: 100,
-            token_count: TokenCounts::default(),
             symbols,
             importance: 0.5,
-            content: None,
+            ..Default::default()
         }
     }
 
@@ -953,6 +1112,43 @@ mod tests {
         assert_eq!(graph.stats().total_files, 0);
     }
 
+    #[test]
+    fn test_parse_import_statement() {
+        let graph = DependencyGraph::new();
+
+        let python = graph.parse_import_statement("import os, sys", "main.py");
+        assert_eq!(python.len(), 2);
+        assert_eq!(python[1].specifier, "sys");
+
+        let js = graph.parse_import_statement("import { helper } from './util';", "app.js");
+        assert_eq!(js.len(), 1);
+        assert_eq!(js[0].specifier, "./util");
+        assert_eq!(js[0].symbols, vec!["helper"]);
+    }
+
+    #[test]
+    fn test_resolve_includes() {
+        let mut repo = Repository::new("test", "/tmp/test");
+        for (path, imports) in [
+            (
+                "src/main.c",
+                vec!["#include <stdio.h>", "#include \"util.h\"", "#include \"net/io.h\""],
+            ),
+            ("src/util.h", vec![]),
+            ("include/net/io.h", vec![]),
+        ] {
+            let mut file = create_test_file(path, imports);
+            file.language = Some("c".to_string());
+            repo.files.push(file);
+        }
+
+        let graph = DependencyGraph::build(&repo);
+        let mut imports = graph.get_imports("src/main.c");
+        imports.sort_unstable();
+        assert_eq!(imports, vec!["include/net/io.h", "src/util.h"]);
+        assert!(graph.get_external_deps().contains("stdio.h"));
+    }
+
     #[test]
     fn test_extract_string_literal() {
         assert_eq!(
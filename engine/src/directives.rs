@@ -0,0 +1,164 @@
+//! Inline context directives in source comments
+//!
+//! A comment in a file can steer how that file is packed, without touching
+//! any configuration:
+//!
+//! - `infiniloom:pin` ranks the file first and keeps it in budgeted packs
+//! - `infiniloom:exclude-below` leaves out the directive line and everything
+//!   after it
+//! - `infiniloom:summary: <text>` gives the file a one-line summary
+//!
+//! Directives work in any comment syntax (`//`, `#`, `--`, `/* */`, ...) as
+//! long as the comment is alone on its line, so code that merely mentions
+//! one is left alone.
+
+use crate::transform::{rewrite_lines, scale_counts};
+use crate::types::{RepoFile, Repository};
+use std::borrow::Cow;
+
+/// Prefix every directive starts with
+const PREFIX: &str = "infiniloom:";
+
+/// Directives found in one file
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Directives {
+    /// `infiniloom:pin` was present
+    pub pin: bool,
+    /// Line number of the first `infiniloom:exclude-below`
+    pub exclude_below: Option<usize>,
+    /// Text of the first `infiniloom:summary:`
+    pub summary: Option<String>,
+}
+
+impl Directives {
+    /// Find the directives in a file's lines, given with their line numbers
+    pub fn parse<'a, I>(lines: I) -> Self
+    where
+        I: IntoIterator<Item = (usize, &'a str)>,
+    {
+        let mut directives = Self::default();
+        for (number, line) in lines {
+            let Some(directive) = directive(line) else {
+                continue;
+            };
+            if directive == "pin" {
+                directives.pin = true;
+            } else if directive == "exclude-below" {
+                directives.exclude_below.get_or_insert(number);
+            } else if let Some(summary) = directive.strip_prefix("summary:") {
+                let summary = summary.trim();
+                if directives.summary.is_none() && !summary.is_empty() {
+                    directives.summary = Some(summary.to_owned());
+                }
+            }
+        }
+        directives
+    }
+
+    /// Whether no directive was found
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// The directive in a comment line, without its prefix and comment delimiters
+fn directive(line: &str) -> Option<&str> {
+    let start = line.find(PREFIX)?;
+    let leader = line[..start].trim();
+    let is_comment = !leader.is_empty()
+        && leader
+            .chars()
+            .all(|c| matches!(c, '/' | '#' | '*' | '-' | ';' | '%' | '<' | '!'));
+    if !is_comment {
+        return None;
+    }
+    let rest = line[start + PREFIX.len()..].trim_end();
+    let rest = rest
+        .strip_suffix("*/")
+        .or_else(|| rest.strip_suffix("-->"))
+        .unwrap_or(rest);
+    Some(rest.trim())
+}
+
+/// Apply the directives in a file's content; returns whether any was found
+///
+/// Content cut by `exclude-below` takes its symbols with it, and token
+/// counts are scaled to the remaining content.
+pub fn apply_directives(file: &mut RepoFile) -> bool {
+    if !file.content.as_deref().is_some_and(|c| c.contains(PREFIX)) {
+        return false;
+    }
+    let directives = Directives::parse(file.numbered_lines());
+    if directives.is_empty() {
+        return false;
+    }
+
+    file.pinned |= directives.pin;
+    if directives.summary.is_some() {
+        file.summary = directives.summary;
+    }
+    if let Some(cut) = directives.exclude_below {
+        let before = file.content.as_ref().map_or(0, String::len);
+        let numbers: Vec<usize> = file.numbered_lines().map(|(number, _)| number).collect();
+        let mut numbers = numbers.into_iter();
+        rewrite_lines(file, |line| {
+            numbers
+                .next()
+                .is_some_and(|number| number < cut)
+                .then_some(Cow::Borrowed(line))
+        });
+        file.symbols.retain(|s| (s.start_line as usize) < cut);
+        let after = file.content.as_ref().map_or(0, String::len);
+        file.token_count = scale_counts(&file.token_count, after as f64 / before.max(1) as f64);
+    }
+    true
+}
+
+/// Apply the directives of every file; returns the number of files with any
+pub fn apply_to_repo(repo: &mut Repository) -> usize {
+    repo.files
+        .iter_mut()
+        .map(apply_directives)
+        .filter(|&found| found)
+        .count()
+}
+
+#[cfg(test)]
+#[allow(clippy::str_to_string)]
+mod tests {
+    use super::*;
+    use crate::types::{Symbol, SymbolKind};
+
+    #[test]
+    fn test_parse_in_any_comment_syntax() {
+        let content = "// infiniloom:pin\n# infiniloom:summary: handles OAuth refresh\nlet s = \"infiniloom:pin\";\n/* infiniloom:exclude-below */\n-- infiniloom:exclude-below\n";
+        let directives = Directives::parse(content.lines().enumerate().map(|(i, l)| (i + 1, l)));
+        assert!(directives.pin);
+        assert_eq!(directives.summary.as_deref(), Some("handles OAuth refresh"));
+        assert_eq!(directives.exclude_below, Some(4));
+
+        let code = Directives::parse([(1, "let s = \"infiniloom:pin\";")]);
+        assert!(code.is_empty());
+    }
+
+    #[test]
+    fn test_apply_cuts_content_and_symbols() {
+        let mut file = RepoFile::new("/repo/app.py", "app.py");
+        file.content = Some(
+            "def main():\n    pass\n# infiniloom:exclude-below\ndef generated():\n    pass\n"
+                .to_string(),
+        );
+        file.token_count.claude = 100;
+        let mut generated = Symbol::new("generated", SymbolKind::Function);
+        generated.start_line = 4;
+        let mut main = Symbol::new("main", SymbolKind::Function);
+        main.start_line = 1;
+        file.symbols = vec![main, generated];
+
+        assert!(apply_directives(&mut file));
+        assert_eq!(file.content.as_deref(), Some("def main():\n    pass\n"));
+        assert_eq!(file.symbols.len(), 1);
+        assert!(file.token_count.claude < 50);
+        assert!(!file.pinned);
+    }
+}
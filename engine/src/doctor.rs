@@ -0,0 +1,358 @@
+//! Repository health checks that affect pack quality
+//!
+//! Each check looks for something that wastes tokens or hides context
+//! (committed dependencies, huge files, mostly generated code) and, where a
+//! config change would help, suggests a `.infiniloomignore` snippet.
+
+use crate::default_ignores::matches_any;
+use crate::types::{RepoFile, Repository};
+use std::collections::BTreeMap;
+
+/// Files larger than this are reported as giant
+pub const DEFAULT_LARGE_FILE_BYTES: u64 = 1024 * 1024;
+
+/// Generated code above this share of the repository is reported
+pub const DEFAULT_GENERATED_SHARE: f64 = 0.3;
+
+/// Directories that hold third-party code
+const VENDOR_DIRS: &[&str] = &[
+    "node_modules",
+    "vendor",
+    "third_party",
+    "third-party",
+    "bower_components",
+    "site-packages",
+    ".venv",
+    "venv",
+    "Pods",
+];
+
+/// File name patterns produced by code generators
+const GENERATED_PATTERNS: &[&str] = &[
+    "*.generated.*",
+    "*.pb.go",
+    "*_pb2.py",
+    "*_pb2_grpc.py",
+    "*_generated.go",
+    "*.g.dart",
+    "*.gen.ts",
+    "*.gen.js",
+    "*.min.js",
+    "*.min.css",
+    "**/generated/**",
+    "**/__generated__/**",
+];
+
+/// Header markers generators leave in the first lines of a file
+const GENERATED_MARKERS: &[&str] =
+    &["@generated", "code generated", "do not edit", "auto-generated", "autogenerated"];
+
+/// Paths listed per finding before the rest are summarized
+const MAX_LISTED_PATHS: usize = 10;
+
+/// How much a finding matters
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    /// Worth knowing, little effect on packs
+    Info,
+    /// Noticeably degrades packs
+    Warning,
+}
+
+impl Severity {
+    /// Lowercase name for display
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Info => "info",
+            Self::Warning => "warning",
+        }
+    }
+}
+
+/// A single health check result
+#[derive(Debug, Clone, PartialEq)]
+pub struct Finding {
+    /// Short identifier of the check, e.g. `vendored-dependencies`
+    pub check: &'static str,
+    /// How much it matters
+    pub severity: Severity,
+    /// What was found
+    pub message: String,
+    /// Files involved, most relevant first
+    pub paths: Vec<String>,
+    /// Config snippet that addresses the finding
+    pub suggestion: Option<String>,
+}
+
+/// Runs health checks over a scanned repository
+///
+/// The repository should be scanned without default ignores so committed
+/// dependencies and generated files are visible.
+#[derive(Debug, Clone)]
+pub struct Doctor {
+    /// Size above which a file counts as giant
+    large_file_bytes: u64,
+    /// Generated share of total bytes above which to warn
+    generated_share: f64,
+}
+
+impl Default for Doctor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Doctor {
+    /// Create a doctor with default thresholds
+    pub fn new() -> Self {
+        Self {
+            large_file_bytes: DEFAULT_LARGE_FILE_BYTES,
+            generated_share: DEFAULT_GENERATED_SHARE,
+        }
+    }
+
+    /// Report files larger than `bytes`
+    pub fn with_large_file_bytes(mut self, bytes: u64) -> Self {
+        self.large_file_bytes = bytes;
+        self
+    }
+
+    /// Warn when generated code exceeds this share (0.0-1.0) of total bytes
+    pub fn with_generated_share(mut self, share: f64) -> Self {
+        self.generated_share = share;
+        self
+    }
+
+    /// Run every check; findings are ordered by severity, then check name
+    pub fn check(&self, repo: &Repository) -> Vec<Finding> {
+        let mut findings: Vec<Finding> = [
+            self.check_gitignore(repo),
+            self.check_readme(repo),
+            self.check_large_files(repo),
+            self.check_vendored(repo),
+            self.check_generated(repo),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+        findings.sort_by(|a, b| b.severity.cmp(&a.severity).then(a.check.cmp(b.check)));
+        findings
+    }
+
+    fn check_gitignore(&self, repo: &Repository) -> Option<Finding> {
+        if repo.path.join(".gitignore").exists() {
+            return None;
+        }
+        Some(Finding {
+            check: "missing-gitignore",
+            severity: Severity::Warning,
+            message: "No .gitignore; build outputs and local files may end up in packs".to_owned(),
+            paths: Vec::new(),
+            suggestion: Some(
+                ".gitignore:\n  target/\n  dist/\n  build/\n  node_modules/\n  .env\n".to_owned(),
+            ),
+        })
+    }
+
+    fn check_readme(&self, repo: &Repository) -> Option<Finding> {
+        let has_readme = repo.files.iter().any(|f| {
+            !f.relative_path.contains('/') && f.relative_path.to_lowercase().starts_with("readme")
+        });
+        if has_readme {
+            return None;
+        }
+        Some(Finding {
+            check: "missing-readme",
+            severity: Severity::Info,
+            message: "No README at the repository root; packs lack a project overview".to_owned(),
+            paths: Vec::new(),
+            suggestion: None,
+        })
+    }
+
+    fn check_large_files(&self, repo: &Repository) -> Option<Finding> {
+        let mut large: Vec<&RepoFile> = repo
+            .files
+            .iter()
+            .filter(|f| f.size_bytes > self.large_file_bytes)
+            .collect();
+        if large.is_empty() {
+            return None;
+        }
+        large.sort_by_key(|f| std::cmp::Reverse(f.size_bytes));
+
+        let paths: Vec<String> = large.iter().map(|f| f.relative_path.clone()).collect();
+        Some(Finding {
+            check: "giant-files",
+            severity: Severity::Warning,
+            message: format!(
+                "{} files over {} KiB are tracked and will crowd out other context",
+                large.len(),
+                self.large_file_bytes / 1024
+            ),
+            suggestion: Some(ignore_snippet(paths.iter().cloned())),
+            paths,
+        })
+    }
+
+    fn check_vendored(&self, repo: &Repository) -> Option<Finding> {
+        // Group files under the outermost vendor directory they sit in
+        let mut dirs: BTreeMap<String, usize> = BTreeMap::new();
+        for file in &repo.files {
+            if let Some(dir) = vendor_dir(&file.relative_path) {
+                *dirs.entry(dir.to_owned()).or_default() += 1;
+            }
+        }
+        if dirs.is_empty() {
+            return None;
+        }
+
+        let files: usize = dirs.values().sum();
+        Some(Finding {
+            check: "vendored-dependencies",
+            severity: Severity::Warning,
+            message: format!(
+                "{} files of third-party code are committed in {} directories",
+                files,
+                dirs.len()
+            ),
+            suggestion: Some(ignore_snippet(dirs.keys().map(|d| format!("{}/**", d)))),
+            paths: dirs.into_keys().collect(),
+        })
+    }
+
+    fn check_generated(&self, repo: &Repository) -> Option<Finding> {
+        let total: u64 = repo.files.iter().map(|f| f.size_bytes).sum();
+        if total == 0 {
+            return None;
+        }
+
+        let mut generated: Vec<&RepoFile> = repo.files.iter().filter(|f| is_generated(f)).collect();
+        let generated_bytes: u64 = generated.iter().map(|f| f.size_bytes).sum();
+        let share = generated_bytes as f64 / total as f64;
+        if share <= self.generated_share {
+            return None;
+        }
+        generated.sort_by_key(|f| std::cmp::Reverse(f.size_bytes));
+
+        let paths: Vec<String> = generated.iter().map(|f| f.relative_path.clone()).collect();
+        Some(Finding {
+            check: "generated-code",
+            severity: Severity::Warning,
+            message: format!(
+                "{:.0}% of the repository by size is generated code ({} files)",
+                share * 100.0,
+                generated.len()
+            ),
+            suggestion: Some(ignore_snippet(paths.iter().cloned())),
+            paths,
+        })
+    }
+}
+
+/// The outermost vendor directory containing `path`, if any
+fn vendor_dir(path: &str) -> Option<&str> {
+    let mut end = 0;
+    for component in path.split('/') {
+        end += component.len();
+        if end >= path.len() {
+            // The file name itself is not a directory
+            return None;
+        }
+        if VENDOR_DIRS.contains(&component) {
+            return Some(&path[..end]);
+        }
+        end += 1;
+    }
+    None
+}
+
+/// Whether a file looks machine-generated, by name or header comment
+pub fn is_generated(file: &RepoFile) -> bool {
+    if matches_any(&file.relative_path, GENERATED_PATTERNS) {
+        return true;
+    }
+    file.content.as_deref().is_some_and(|content| {
+        content.lines().take(5).any(|line| {
+            let line = line.to_lowercase();
+            GENERATED_MARKERS.iter().any(|m| line.contains(m))
+        })
+    })
+}
+
+/// `.infiniloomignore` lines for `patterns`, listing at most a handful
+fn ignore_snippet(patterns: impl Iterator<Item = String>) -> String {
+    let patterns: Vec<String> = patterns.collect();
+    let mut snippet = String::from(".infiniloomignore:\n");
+    for pattern in patterns.iter().take(MAX_LISTED_PATHS) {
+        snippet.push_str(&format!("  {}\n", pattern));
+    }
+    if patterns.len() > MAX_LISTED_PATHS {
+        snippet.push_str(&format!("  # ...and {} more\n", patterns.len() - MAX_LISTED_PATHS));
+    }
+    snippet
+}
+
+#[cfg(test)]
+#[allow(clippy::str_to_string)]
+mod tests {
+    use super::*;
+
+    fn file(path: &str, size: u64, content: &str) -> RepoFile {
+        RepoFile {
+            path: format!("/nonexistent/{}", path).into(),
+            relative_path: path.to_string(),
+            size_bytes: size,
+            importance: 0.5,
+            content: Some(content.to_string()),
+            ..Default::default()
+        }
+    }
+
+    fn checks(findings: &[Finding]) -> Vec<&str> {
+        findings.iter().map(|f| f.check).collect()
+    }
+
+    #[test]
+    fn test_unhealthy_repository() {
+        let mut repo = Repository::new("test", "/nonexistent");
+        repo.files = vec![
+            file("src/main.rs", 100, "fn main() {}"),
+            file("vendor/github.com/x/y.go", 100, "package y"),
+            file("web/node_modules/a/index.js", 100, ""),
+            file("api/api.pb.go", 400, ""),
+            file("src/schema.rs", 2_000_000, "// @generated by build.rs"),
+        ];
+
+        let findings = Doctor::new().check(&repo);
+        assert_eq!(
+            checks(&findings),
+            [
+                "generated-code",
+                "giant-files",
+                "missing-gitignore",
+                "vendored-dependencies",
+                "missing-readme"
+            ]
+        );
+
+        let vendored = &findings[3];
+        assert_eq!(vendored.paths, ["vendor", "web/node_modules"]);
+        assert!(vendored
+            .suggestion
+            .as_ref()
+            .unwrap()
+            .contains("  web/node_modules/**\n"));
+        assert_eq!(findings[1].paths, ["src/schema.rs"]);
+    }
+
+    #[test]
+    fn test_healthy_repository() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join(".gitignore"), "target/\n").unwrap();
+
+        let mut repo = Repository::new("test", dir.path());
+        repo.files = vec![file("README.md", 100, "# Test"), file("src/vendor.rs", 100, "")];
+        assert!(Doctor::new().check(&repo).is_empty());
+    }
+}
@@ -0,0 +1,342 @@
+//! Entry point detection from code and manifests
+//!
+//! File names such as `main.rs` or `index.ts` catch most entry points, and
+//! the ranking heuristics and output formatters match those themselves. This
+//! module finds the ones names miss: a top-level `main` function in any file
+//! (including `#[tokio::main]` and Go's `func main()` outside `main.go`), an
+//! `if __name__ == "__main__":` guard, and the files that manifests declare
+//! (`main`, `module` and `bin` in `package.json`, `[lib]` and `[[bin]]` in
+//! `Cargo.toml`, `[project.scripts]` in `pyproject.toml`).
+
+use crate::types::{RepoFile, Repository, SymbolKind};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+
+/// Extensions tried for a declared JavaScript entry without one
+const SCRIPT_EXTENSIONS: &[&str] = &[".js", ".mjs", ".cjs", ".ts", "/index.js", "/index.ts"];
+
+/// How a file was found to be an entry point
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EntrySource {
+    /// Defines a top-level `main` function
+    MainFunction,
+    /// Runs code under `if __name__ == "__main__":`
+    MainGuard,
+    /// Declared as a package's main module or library root
+    ManifestMain,
+    /// Declared as an executable
+    ManifestBin,
+}
+
+impl EntrySource {
+    /// Short name, used as the entry type in outputs
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::MainFunction => "main",
+            Self::MainGuard => "script",
+            Self::ManifestMain => "library",
+            Self::ManifestBin => "binary",
+        }
+    }
+}
+
+/// A file detected as an entry point
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct EntryPoint {
+    /// Relative path of the file
+    pub path: String,
+    /// How it was detected
+    pub source: EntrySource,
+}
+
+/// Entry points declared by manifests or defined in code, sorted by path
+///
+/// A file found both ways is reported with its manifest declaration.
+pub fn detect_entry_points(repo: &Repository) -> Vec<EntryPoint> {
+    let mut entries: BTreeMap<String, EntrySource> = BTreeMap::new();
+    for entry in manifest_entry_points(repo) {
+        entries.entry(entry.path).or_insert(entry.source);
+    }
+    for file in &repo.files {
+        if let Some(source) = code_entry_point(file) {
+            entries.entry(file.relative_path.clone()).or_insert(source);
+        }
+    }
+    entries
+        .into_iter()
+        .map(|(path, source)| EntryPoint { path, source })
+        .collect()
+}
+
+/// How the code of `file` makes it an entry point, if it does
+///
+/// Parsed symbols are used when the file has them; the content is checked
+/// for the forms symbols don't capture, such as `__main__` guards.
+pub fn code_entry_point(file: &RepoFile) -> Option<EntrySource> {
+    let language = file.language.as_deref()?;
+    let content = file.content.as_deref();
+    let lines = || content.into_iter().flat_map(str::lines);
+
+    if language == "python" {
+        let guard = lines().any(|l| l.starts_with("if __name__") && l.contains("__main__"));
+        return guard.then_some(EntrySource::MainGuard);
+    }
+    if language == "go" {
+        // Only package main builds an executable
+        let main_package = lines().any(|l| l.trim_end() == "package main");
+        let main = lines().any(|l| l.starts_with("func main()"));
+        return (main_package && main).then_some(EntrySource::MainFunction);
+    }
+
+    let main_symbol = file
+        .symbols
+        .iter()
+        .any(|s| s.name == "main" && s.kind == SymbolKind::Function && s.parent.is_none());
+    let main_code = lines().any(|line| {
+        let line = line.trim();
+        match language {
+            // #[tokio::main], #[async_std::main], #[actix_web::main], ...
+            "rust" => {
+                (line.starts_with("#[") && line.ends_with("::main]"))
+                    || ["fn main(", "async fn main(", "pub fn main("]
+                        .iter()
+                        .any(|p| line.starts_with(p))
+            },
+            "c" | "cpp" => line.starts_with("int main(") || line.starts_with("int main ("),
+            "java" | "csharp" => {
+                line.contains("static void main(") || line.contains("static void Main(")
+            },
+            "kotlin" => line.starts_with("fun main("),
+            _ => false,
+        }
+    });
+    (main_symbol || main_code).then_some(EntrySource::MainFunction)
+}
+
+/// Entry points declared by `package.json`, `Cargo.toml` and `pyproject.toml`
+/// files anywhere in the repository
+pub fn manifest_entry_points(repo: &Repository) -> Vec<EntryPoint> {
+    let paths: HashSet<&str> = repo
+        .files
+        .iter()
+        .map(|f| f.relative_path.as_str())
+        .collect();
+    let mut entries = Vec::new();
+    for file in &repo.files {
+        let (dir, name) = match file.relative_path.rsplit_once('/') {
+            Some((dir, name)) => (dir, name),
+            None => ("", file.relative_path.as_str()),
+        };
+        let declared = match name {
+            "package.json" => manifest_content(file).map(|c| package_json_entries(&c)),
+            "Cargo.toml" => manifest_content(file).map(|c| cargo_entries(&c)),
+            "pyproject.toml" => manifest_content(file).map(|c| pyproject_entries(&c)),
+            _ => None,
+        };
+        for (declared, source) in declared.unwrap_or_default() {
+            let candidates = match name {
+                "package.json" => script_candidates(dir, &declared),
+                "pyproject.toml" => module_candidates(dir, &declared),
+                _ => vec![join(dir, &declared)],
+            };
+            if let Some(path) = candidates.into_iter().find(|c| paths.contains(c.as_str())) {
+                entries.push(EntryPoint { path, source });
+            }
+        }
+    }
+    entries
+}
+
+/// `main`, `module` and `bin` of a `package.json`
+fn package_json_entries(content: &str) -> Vec<(String, EntrySource)> {
+    let Ok(value) = serde_json::from_str::<serde_json::Value>(content) else {
+        return Vec::new();
+    };
+    let mut entries: Vec<(String, EntrySource)> = ["main", "module"]
+        .iter()
+        .filter_map(|key| value.get(key)?.as_str())
+        .map(|path| (path.to_owned(), EntrySource::ManifestMain))
+        .collect();
+    match value.get("bin") {
+        Some(serde_json::Value::String(path)) => {
+            entries.push((path.clone(), EntrySource::ManifestBin));
+        },
+        Some(serde_json::Value::Object(bins)) => entries.extend(
+            bins.values()
+                .filter_map(|v| v.as_str())
+                .map(|path| (path.to_owned(), EntrySource::ManifestBin)),
+        ),
+        _ => {},
+    }
+    entries
+}
+
+/// `[lib]` and `[[bin]]` paths of a `Cargo.toml`
+fn cargo_entries(content: &str) -> Vec<(String, EntrySource)> {
+    let Ok(table) = content.parse::<toml::Table>() else {
+        return Vec::new();
+    };
+    let path = |target: &toml::Value| target.get("path")?.as_str().map(str::to_owned);
+    let mut entries: Vec<(String, EntrySource)> = table
+        .get("lib")
+        .and_then(path)
+        .map(|p| (p, EntrySource::ManifestMain))
+        .into_iter()
+        .collect();
+    if let Some(bins) = table.get("bin").and_then(|b| b.as_array()) {
+        entries.extend(
+            bins.iter()
+                .filter_map(path)
+                .map(|p| (p, EntrySource::ManifestBin)),
+        );
+    }
+    entries
+}
+
+/// Modules of the `[project.scripts]` and `[tool.poetry.scripts]` of a
+/// `pyproject.toml`, as dotted names
+fn pyproject_entries(content: &str) -> Vec<(String, EntrySource)> {
+    let Ok(table) = content.parse::<toml::Table>() else {
+        return Vec::new();
+    };
+    let project = table.get("project").and_then(|p| p.get("scripts"));
+    let poetry = table
+        .get("tool")
+        .and_then(|t| t.get("poetry"))
+        .and_then(|p| p.get("scripts"));
+    [project, poetry]
+        .into_iter()
+        .flatten()
+        .filter_map(|scripts| scripts.as_table())
+        .flat_map(|scripts| scripts.values())
+        .filter_map(|target| target.as_str())
+        .map(|target| {
+            let module = target.split(':').next().unwrap_or(target).trim();
+            (module.to_owned(), EntrySource::ManifestBin)
+        })
+        .collect()
+}
+
+/// Files a JavaScript entry may refer to, which may leave out the extension
+fn script_candidates(dir: &str, declared: &str) -> Vec<String> {
+    let path = join(dir, declared);
+    let mut candidates = vec![path.clone()];
+    candidates.extend(
+        SCRIPT_EXTENSIONS
+            .iter()
+            .map(|ext| format!("{}{}", path, ext)),
+    );
+    candidates
+}
+
+/// Files a dotted Python module may live in, flat or under `src/`
+fn module_candidates(dir: &str, module: &str) -> Vec<String> {
+    let path = module.replace('.', "/");
+    ["", "src/"]
+        .iter()
+        .flat_map(|root| {
+            [
+                join(dir, &format!("{}{}.py", root, path)),
+                join(dir, &format!("{}{}/__init__.py", root, path)),
+            ]
+        })
+        .collect()
+}
+
+/// `path` relative to the repository, given relative to `dir`
+fn join(dir: &str, path: &str) -> String {
+    let path = path.trim_start_matches("./");
+    if dir.is_empty() {
+        path.to_owned()
+    } else {
+        format!("{}/{}", dir, path)
+    }
+}
+
+fn manifest_content(file: &RepoFile) -> Option<String> {
+    file.content
+        .clone()
+        .or_else(|| fs::read_to_string(&file.path).ok())
+}
+
+#[cfg(test)]
+#[allow(clippy::str_to_string)]
+mod tests {
+    use super::*;
+    use crate::types::Symbol;
+
+    fn file(path: &str, language: &str, content: &str) -> RepoFile {
+        let mut file = RepoFile::new(format!("/nonexistent/{}", path), path);
+        file.language = Some(language.to_string());
+        file.content = Some(content.to_string());
+        file
+    }
+
+    fn repo(files: Vec<RepoFile>) -> Repository {
+        let mut repo = Repository::new("test", "/nonexistent");
+        repo.files = files;
+        repo
+    }
+
+    #[test]
+    fn test_code_entry_points() {
+        let tokio = file("src/server.rs", "rust", "#[tokio::main]\nasync fn main() {}\n");
+        assert_eq!(code_entry_point(&tokio), Some(EntrySource::MainFunction));
+
+        let guard = file(
+            "tools/gen.py",
+            "python",
+            "def run():\n    pass\n\nif __name__ == '__main__':\n    run()\n",
+        );
+        assert_eq!(code_entry_point(&guard), Some(EntrySource::MainGuard));
+
+        let go = file("cmd/tool/run.go", "go", "package main\n\nfunc main() {\n}\n");
+        assert_eq!(code_entry_point(&go), Some(EntrySource::MainFunction));
+        let go_lib = file("pkg/util.go", "go", "package util\n\nfunc main() {\n}\n");
+        assert_eq!(code_entry_point(&go_lib), None);
+
+        let mut parsed = file("src/start.c", "c", "");
+        parsed
+            .symbols
+            .push(Symbol::new("main", SymbolKind::Function));
+        assert_eq!(code_entry_point(&parsed), Some(EntrySource::MainFunction));
+
+        let plain = file("src/util.rs", "rust", "pub fn helper() {}\n");
+        assert_eq!(code_entry_point(&plain), None);
+    }
+
+    #[test]
+    fn test_manifest_entry_points() {
+        let repo = repo(vec![
+            file(
+                "web/package.json",
+                "json",
+                r#"{"main": "./lib/index", "bin": {"web-cli": "bin/cli.js"}}"#,
+            ),
+            file("web/lib/index.js", "javascript", ""),
+            file("web/bin/cli.js", "javascript", ""),
+            file("Cargo.toml", "toml", "[lib]\npath = \"core/root.rs\"\n\n[[bin]]\nname = \"tool\"\npath = \"tools/tool.rs\"\n"),
+            file("core/root.rs", "rust", ""),
+            file("tools/tool.rs", "rust", ""),
+            file("pyproject.toml", "toml", "[project.scripts]\nmytool = \"mytool.cli:main\"\n"),
+            file("src/mytool/cli.py", "python", ""),
+        ]);
+        let detected = detect_entry_points(&repo);
+        let entries: Vec<(&str, EntrySource)> = detected
+            .iter()
+            .map(|e| (e.path.as_str(), e.source))
+            .collect();
+        assert_eq!(
+            entries,
+            vec![
+                ("core/root.rs", EntrySource::ManifestMain),
+                ("src/mytool/cli.py", EntrySource::ManifestBin),
+                ("tools/tool.rs", EntrySource::ManifestBin),
+                ("web/bin/cli.js", EntrySource::ManifestBin),
+                ("web/lib/index.js", EntrySource::ManifestMain),
+            ]
+        );
+    }
+}
@@ -0,0 +1,366 @@
+//! Repository map quality evaluation
+//!
+//! Measures how well [`RepoMapGenerator`] surfaces what matters on small
+//! labeled fixture repositories, so ranking changes can be judged by numbers
+//! rather than by eyeballing output:
+//!
+//! - **file coverage**: share of known-important files that own a key symbol
+//! - **symbol precision / recall**: key symbols vs. a labeled set
+//! - **budget adherence**: map token count relative to the requested budget
+//! - **churn**: instability of the key symbol set across repeated runs
+//!
+//! Enabled with the `eval` feature; run with `cargo test --features eval`.
+
+use crate::parser::{Language, Parser};
+use crate::ranking::{rank_files, sort_files_by_importance};
+use crate::repomap::{RepoMap, RepoMapGenerator};
+use crate::types::{RepoFile, Repository, SymbolKind, TokenCounts};
+use std::collections::HashSet;
+use std::fmt;
+use std::path::PathBuf;
+
+/// A small repository with labels for what a good map should contain
+#[derive(Debug, Clone)]
+pub struct EvalFixture {
+    /// Fixture name used in reports
+    pub name: String,
+    /// Source files as (relative path, content)
+    pub files: Vec<(String, String)>,
+    /// Files a good map must cover
+    pub important_files: Vec<String>,
+    /// Symbol names a good map should rank as key symbols
+    pub important_symbols: Vec<String>,
+    /// Token budget passed to the map generator
+    pub budget: u32,
+}
+
+impl EvalFixture {
+    /// Create an empty fixture
+    pub fn new(name: impl Into<String>, budget: u32) -> Self {
+        Self {
+            name: name.into(),
+            files: Vec::new(),
+            important_files: Vec::new(),
+            important_symbols: Vec::new(),
+            budget,
+        }
+    }
+
+    /// Add a source file
+    pub fn with_file(mut self, path: impl Into<String>, content: impl Into<String>) -> Self {
+        self.files.push((path.into(), content.into()));
+        self
+    }
+
+    /// Label a file as important
+    pub fn expect_file(mut self, path: impl Into<String>) -> Self {
+        self.important_files.push(path.into());
+        self
+    }
+
+    /// Label a symbol as important
+    pub fn expect_symbol(mut self, name: impl Into<String>) -> Self {
+        self.important_symbols.push(name.into());
+        self
+    }
+
+    /// Parse and rank the fixture into a repository, as the scanner would
+    pub fn build_repo(&self) -> Repository {
+        let root = PathBuf::from("/eval").join(&self.name);
+        let mut parser = Parser::new();
+        let mut repo = Repository::new(self.name.clone(), root.clone());
+
+        for (path, content) in &self.files {
+            let language = path
+                .rsplit_once('.')
+                .and_then(|(_, ext)| Language::from_extension(ext));
+            let symbols = language
+                .and_then(|lang| parser.parse(content, lang).ok())
+                .unwrap_or_default();
+            let len = content.len() as f32;
+
+            repo.files.push(RepoFile {
+                path: root.join(path),
+                relative_path: path.clone(),
+                language: language.map(|l| l.name().to_owned()),
+                size_bytes: content.len() as u64,
+                token_count: TokenCounts {
+                    claude: (len / 3.5) as u32,
+                    gpt4o: (len / 4.0) as u32,
+                    gpt4: (len / 3.7) as u32,
+                    gemini: (len / 3.8) as u32,
+                    llama: (len / 3.5) as u32,
+                },
+                symbols,
+                importance: 0.5,
+                content: Some(content.clone()),
+                ..Default::default()
+            });
+        }
+
+        repo.metadata.total_files = repo.files.len() as u32;
+        rank_files(&mut repo);
+        sort_files_by_importance(&mut repo);
+        repo
+    }
+}
+
+/// Quality metrics for one fixture
+#[derive(Debug, Clone, PartialEq)]
+pub struct MapMetrics {
+    /// Share of important files owning at least one key symbol (0.0 - 1.0)
+    pub file_coverage: f64,
+    /// Share of key symbols that are labeled important (0.0 - 1.0)
+    pub symbol_precision: f64,
+    /// Share of labeled symbols that appear as key symbols (0.0 - 1.0)
+    pub symbol_recall: f64,
+    /// Map token count divided by budget (<= 1.0 means within budget)
+    pub budget_ratio: f64,
+    /// Mean Jaccard distance of key symbol sets across runs (0.0 = stable)
+    pub churn: f64,
+}
+
+impl MapMetrics {
+    /// Whether the map stayed within its token budget
+    pub fn within_budget(&self) -> bool {
+        self.budget_ratio <= 1.0
+    }
+}
+
+/// Evaluation result for one fixture
+#[derive(Debug, Clone)]
+pub struct EvalReport {
+    /// Fixture name
+    pub fixture: String,
+    /// Computed metrics
+    pub metrics: MapMetrics,
+}
+
+impl fmt::Display for EvalReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let m = &self.metrics;
+        write!(
+            f,
+            "{}: coverage {:.2}, precision {:.2}, recall {:.2}, budget {:.2}{}, churn {:.2}",
+            self.fixture,
+            m.file_coverage,
+            m.symbol_precision,
+            m.symbol_recall,
+            m.budget_ratio,
+            if m.within_budget() { "" } else { " (over)" },
+            m.churn
+        )
+    }
+}
+
+/// Runs fixtures through the map generator and scores the result
+pub struct Evaluator {
+    /// Number of map generations used to measure churn
+    runs: usize,
+    /// Maximum key symbols requested from the generator
+    max_symbols: usize,
+}
+
+impl Default for Evaluator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Evaluator {
+    /// Create an evaluator with default settings
+    pub fn new() -> Self {
+        Self { runs: 3, max_symbols: 10 }
+    }
+
+    /// Set number of runs used to measure churn
+    pub fn with_runs(mut self, runs: usize) -> Self {
+        self.runs = runs.max(1);
+        self
+    }
+
+    /// Set maximum key symbols requested from the generator
+    pub fn with_max_symbols(mut self, max: usize) -> Self {
+        self.max_symbols = max;
+        self
+    }
+
+    /// Evaluate a single fixture
+    pub fn evaluate(&self, fixture: &EvalFixture) -> EvalReport {
+        let maps: Vec<RepoMap> = (0..self.runs)
+            .map(|_| {
+                let repo = fixture.build_repo();
+                RepoMapGenerator::new(fixture.budget)
+                    .with_max_symbols(self.max_symbols)
+                    .generate(&repo)
+            })
+            .collect();
+
+        let first = &maps[0];
+        let key_symbols: Vec<_> = first
+            .key_symbols
+            .iter()
+            .filter(|s| s.kind != SymbolKind::Import.name())
+            .collect();
+
+        let covered_files: HashSet<&str> = key_symbols.iter().map(|s| s.file.as_str()).collect();
+        let file_coverage = ratio(
+            fixture
+                .important_files
+                .iter()
+                .filter(|f| covered_files.contains(f.as_str()))
+                .count(),
+            fixture.important_files.len(),
+        );
+
+        let labeled: HashSet<&str> = fixture
+            .important_symbols
+            .iter()
+            .map(String::as_str)
+            .collect();
+        let ranked: HashSet<&str> = key_symbols.iter().map(|s| s.name.as_str()).collect();
+        let hits = ranked.intersection(&labeled).count();
+
+        let churn = if maps.len() < 2 {
+            0.0
+        } else {
+            let base = symbol_keys(first);
+            let total: f64 = maps[1..]
+                .iter()
+                .map(|m| jaccard_distance(&base, &symbol_keys(m)))
+                .sum();
+            total / (maps.len() - 1) as f64
+        };
+
+        EvalReport {
+            fixture: fixture.name.clone(),
+            metrics: MapMetrics {
+                file_coverage,
+                symbol_precision: ratio(hits, ranked.len()),
+                symbol_recall: ratio(hits, labeled.len()),
+                budget_ratio: first.token_count as f64 / fixture.budget.max(1) as f64,
+                churn,
+            },
+        }
+    }
+
+    /// Evaluate several fixtures
+    pub fn evaluate_all(&self, fixtures: &[EvalFixture]) -> Vec<EvalReport> {
+        fixtures.iter().map(|f| self.evaluate(f)).collect()
+    }
+}
+
+/// Built-in labeled fixtures covering common project shapes
+pub fn builtin_fixtures() -> Vec<EvalFixture> {
+    vec![
+        EvalFixture::new("rust-crate", 1000)
+            .with_file(
+                "src/lib.rs",
+                "pub mod engine;\npub mod config;\n\n/// Build and run the engine\npub fn run(config: config::Config) -> engine::Engine {\n    engine::Engine::new(config)\n}\n",
+            )
+            .with_file(
+                "src/engine.rs",
+                "use crate::config::Config;\n\n/// Core processing engine\npub struct Engine {\n    config: Config,\n}\n\nimpl Engine {\n    pub fn new(config: Config) -> Self {\n        Self { config }\n    }\n\n    pub fn process(&self, input: &str) -> String {\n        input.repeat(self.config.repeat)\n    }\n}\n",
+            )
+            .with_file(
+                "src/config.rs",
+                "/// Engine configuration\npub struct Config {\n    pub repeat: usize,\n}\n\nimpl Default for Config {\n    fn default() -> Self {\n        Self { repeat: 1 }\n    }\n}\n",
+            )
+            .with_file(
+                "tests/engine_test.rs",
+                "use demo::engine::Engine;\n\nfn helper_fixture() -> String {\n    String::new()\n}\n\n#[test]\nfn test_process() {\n    let _ = helper_fixture();\n}\n",
+            )
+            .expect_file("src/lib.rs")
+            .expect_file("src/engine.rs")
+            .expect_file("src/config.rs")
+            .expect_symbol("run")
+            .expect_symbol("Engine")
+            .expect_symbol("process")
+            .expect_symbol("Config"),
+        EvalFixture::new("python-package", 1000)
+            .with_file(
+                "app/__init__.py",
+                "from .service import UserService\nfrom .models import User\n",
+            )
+            .with_file(
+                "app/models.py",
+                "class User:\n    \"\"\"A registered user.\"\"\"\n\n    def __init__(self, name):\n        self.name = name\n",
+            )
+            .with_file(
+                "app/service.py",
+                "from .models import User\n\n\nclass UserService:\n    \"\"\"Manages users.\"\"\"\n\n    def __init__(self):\n        self.users = []\n\n    def register(self, name):\n        user = User(name)\n        self.users.append(user)\n        return user\n",
+            )
+            .with_file(
+                "scripts/seed.py",
+                "def seed_data():\n    return []\n",
+            )
+            .expect_file("app/models.py")
+            .expect_file("app/service.py")
+            .expect_symbol("User")
+            .expect_symbol("UserService")
+            .expect_symbol("register"),
+    ]
+}
+
+fn ratio(part: usize, whole: usize) -> f64 {
+    if whole == 0 {
+        1.0
+    } else {
+        part as f64 / whole as f64
+    }
+}
+
+fn symbol_keys(map: &RepoMap) -> HashSet<String> {
+    map.key_symbols
+        .iter()
+        .map(|s| format!("{}:{}", s.file, s.name))
+        .collect()
+}
+
+fn jaccard_distance(a: &HashSet<String>, b: &HashSet<String>) -> f64 {
+    let union = a.union(b).count();
+    if union == 0 {
+        return 0.0;
+    }
+    1.0 - a.intersection(b).count() as f64 / union as f64
+}
+
+#[cfg(test)]
+#[allow(clippy::str_to_string)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builtin_fixtures_quality() {
+        let reports = Evaluator::new().evaluate_all(&builtin_fixtures());
+        for report in &reports {
+            let m = &report.metrics;
+            assert!(m.within_budget(), "{}", report);
+            assert!(m.file_coverage >= 0.66, "{}", report);
+            assert!(m.symbol_precision >= 0.4, "{}", report);
+            assert!(m.symbol_recall >= 0.75, "{}", report);
+            assert!(m.churn <= 0.2, "{}", report);
+        }
+    }
+
+    #[test]
+    fn test_perfect_labels_score_one() {
+        let fixture = EvalFixture::new("single", 500)
+            .with_file("src/lib.rs", "pub fn only() {}\n")
+            .expect_file("src/lib.rs")
+            .expect_symbol("only");
+        let metrics = Evaluator::new().evaluate(&fixture).metrics;
+        assert_eq!(metrics.file_coverage, 1.0);
+        assert_eq!(metrics.symbol_precision, 1.0);
+        assert_eq!(metrics.symbol_recall, 1.0);
+        assert_eq!(metrics.churn, 0.0);
+    }
+
+    #[test]
+    fn test_jaccard_distance() {
+        let a: HashSet<String> = ["x", "y"].iter().map(|s| s.to_string()).collect();
+        let b: HashSet<String> = ["y", "z"].iter().map(|s| s.to_string()).collect();
+        assert!((jaccard_distance(&a, &b) - 2.0 / 3.0).abs() < 1e-9);
+        assert_eq!(jaccard_distance(&a, &a), 0.0);
+    }
+}
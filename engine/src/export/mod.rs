@@ -0,0 +1,450 @@
+//! Symbol index exporters for editors and code-intelligence tools
+//!
+//! Writes the symbols extracted by the parser in formats other tools already
+//! understand:
+//!
+//! - **ctags**: universal-ctags JSON lines (`ctags --output-format=json`)
+//! - **LSIF**: Language Server Index Format 0.4.3 dump (JSON lines)
+//! - **Parquet**: columnar datasets of files, symbols or dependency edges
+//!   (requires the `parquet` feature)
+
+#[cfg(feature = "parquet")]
+mod parquet;
+
+#[cfg(feature = "parquet")]
+pub use self::parquet::{export_parquet, ROW_GROUP_SIZE};
+
+use crate::types::{RepoFile, Repository, Symbol, SymbolKind};
+use serde_json::{json, Value};
+use std::path::{Path, PathBuf};
+
+/// Table written by columnar exports
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Dataset {
+    /// One row per file: path, language, size, lines, token counts
+    Files,
+    /// One row per symbol: file, name, kind, parent, signature, line range
+    Symbols,
+    /// One row per import: source file, resolved target, specifier, kind
+    Edges,
+}
+
+/// Symbol index export format
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// universal-ctags JSON lines
+    Ctags,
+    /// Language Server Index Format
+    Lsif,
+}
+
+/// Export the repository's symbol index in the given format
+pub fn export_symbols(repo: &Repository, format: ExportFormat) -> String {
+    match format {
+        ExportFormat::Ctags => export_ctags(repo),
+        ExportFormat::Lsif => export_lsif(repo),
+    }
+}
+
+/// Export symbols as universal-ctags JSON lines, sorted by tag name
+pub fn export_ctags(repo: &Repository) -> String {
+    let mut tags: Vec<(&RepoFile, &Symbol)> = repo
+        .files
+        .iter()
+        .flat_map(|f| exported_symbols(f).map(move |s| (f, s)))
+        .collect();
+    tags.sort_by(|(fa, a), (fb, b)| {
+        a.name
+            .cmp(&b.name)
+            .then_with(|| fa.relative_path.cmp(&fb.relative_path))
+            .then_with(|| a.start_line.cmp(&b.start_line))
+    });
+
+    let mut out = String::new();
+    let ptags = [
+        ("JSON_OUTPUT_VERSION", "0.0", "in development"),
+        ("TAG_FILE_SORTED", "1", "0=unsorted, 1=sorted, 2=foldcase"),
+        ("TAG_PROGRAM_NAME", "Infiniloom", ""),
+        ("TAG_PROGRAM_VERSION", crate::VERSION, ""),
+    ];
+    for (name, path, pattern) in ptags {
+        push_line(
+            &mut out,
+            &json!({"_type": "ptag", "name": name, "path": path, "pattern": pattern}),
+        );
+    }
+
+    for (file, symbol) in tags {
+        let mut tag = json!({
+            "_type": "tag",
+            "name": symbol.name,
+            "path": file.relative_path,
+            "line": symbol.start_line,
+            "kind": symbol.kind.name(),
+        });
+        if let Some(text) = line_text(file, symbol.start_line) {
+            tag["pattern"] = Value::String(ctags_pattern(text));
+        }
+        if let Some(language) = &file.language {
+            tag["language"] = Value::String(language.clone());
+        }
+        if symbol.end_line > symbol.start_line {
+            tag["end"] = json!(symbol.end_line);
+        }
+        if let Some(signature) = &symbol.signature {
+            tag["signature"] = Value::String(signature.clone());
+        }
+        if let Some(parent) = &symbol.parent {
+            tag["scope"] = Value::String(parent.clone());
+            tag["scopeKind"] = Value::String(parent_kind(file, parent).to_owned());
+        }
+        push_line(&mut out, &tag);
+    }
+
+    out
+}
+
+/// Export symbols as an LSIF dump with definition and hover results
+pub fn export_lsif(repo: &Repository) -> String {
+    let mut writer = LsifWriter::default();
+    let root = project_root(&repo.path);
+
+    writer.vertex(json!({
+        "label": "metaData",
+        "version": "0.4.3",
+        "projectRoot": file_uri(&root),
+        "positionEncoding": "utf-16",
+        "toolInfo": {"name": "infiniloom", "version": crate::VERSION},
+    }));
+    let project = writer.vertex(json!({
+        "label": "project",
+        "kind": repo.metadata.languages.first().map(|l| l.language.as_str()).unwrap_or(""),
+        "name": repo.name,
+    }));
+    writer.event("begin", "project", project);
+
+    let mut documents = Vec::new();
+    for file in &repo.files {
+        let symbols: Vec<&Symbol> = exported_symbols(file).collect();
+        if symbols.is_empty() {
+            continue;
+        }
+
+        let document = writer.vertex(json!({
+            "label": "document",
+            "uri": file_uri(&root.join(&file.relative_path)),
+            "languageId": file.language.as_deref().unwrap_or(""),
+        }));
+        writer.event("begin", "document", document);
+        documents.push(document);
+
+        let mut ranges = Vec::new();
+        for symbol in symbols {
+            let line = symbol.start_line.saturating_sub(1);
+            let (start, end) = name_columns(file, symbol);
+            let full_end = symbol.end_line.max(symbol.start_line).saturating_sub(1);
+            let range = writer.vertex(json!({
+                "label": "range",
+                "start": {"line": line, "character": start},
+                "end": {"line": line, "character": end},
+                "tag": {
+                    "type": "definition",
+                    "text": symbol.name,
+                    "kind": lsp_symbol_kind(symbol.kind),
+                    "fullRange": {
+                        "start": {"line": line, "character": 0},
+                        "end": {"line": full_end, "character": 0},
+                    },
+                },
+            }));
+            ranges.push(range);
+
+            let result_set = writer.vertex(json!({"label": "resultSet"}));
+            writer.edge("next", range, json!({"inV": result_set}));
+
+            let definition = writer.vertex(json!({"label": "definitionResult"}));
+            writer.edge("textDocument/definition", result_set, json!({"inV": definition}));
+            writer.edge("item", definition, json!({"inVs": [range], "document": document}));
+
+            let mut contents = Vec::new();
+            if let Some(signature) = &symbol.signature {
+                contents.push(json!({
+                    "language": file.language.as_deref().unwrap_or(""),
+                    "value": signature,
+                }));
+            }
+            if let Some(doc) = &symbol.docstring {
+                contents.push(Value::String(doc.trim().to_owned()));
+            }
+            if !contents.is_empty() {
+                let hover = writer
+                    .vertex(json!({"label": "hoverResult", "result": {"contents": contents}}));
+                writer.edge("textDocument/hover", result_set, json!({"inV": hover}));
+            }
+        }
+
+        writer.edge("contains", document, json!({"inVs": ranges}));
+        writer.event("end", "document", document);
+    }
+
+    if !documents.is_empty() {
+        writer.edge("contains", project, json!({"inVs": documents}));
+    }
+    writer.event("end", "project", project);
+
+    writer.out
+}
+
+/// Sequential id allocator and line writer for LSIF elements
+#[derive(Default)]
+struct LsifWriter {
+    next_id: u64,
+    out: String,
+}
+
+impl LsifWriter {
+    fn vertex(&mut self, mut body: Value) -> u64 {
+        self.next_id += 1;
+        body["id"] = json!(self.next_id);
+        body["type"] = json!("vertex");
+        push_line(&mut self.out, &body);
+        self.next_id
+    }
+
+    /// `$event` vertex opening or closing the scope of a project or document
+    fn event(&mut self, kind: &str, scope: &str, data: u64) {
+        self.vertex(json!({"label": "$event", "kind": kind, "scope": scope, "data": data}));
+    }
+
+    fn edge(&mut self, label: &str, out_v: u64, mut body: Value) {
+        self.next_id += 1;
+        body["id"] = json!(self.next_id);
+        body["type"] = json!("edge");
+        body["label"] = json!(label);
+        body["outV"] = json!(out_v);
+        push_line(&mut self.out, &body);
+    }
+}
+
+/// Symbols worth indexing (imports are references, not definitions)
+fn exported_symbols(file: &RepoFile) -> impl Iterator<Item = &Symbol> {
+    file.symbols
+        .iter()
+        .filter(|s| s.kind != SymbolKind::Import && s.start_line > 0)
+}
+
+fn push_line(out: &mut String, value: &Value) {
+    out.push_str(&value.to_string());
+    out.push('\n');
+}
+
+/// Text of a 1-indexed line, if the file content is available
+fn line_text(file: &RepoFile, line: u32) -> Option<&str> {
+    file.content
+        .as_deref()?
+        .lines()
+        .nth(line.checked_sub(1)? as usize)
+}
+
+/// Build a ctags search pattern (`/^line$/`) with ctags escaping rules
+fn ctags_pattern(line: &str) -> String {
+    let escaped = line.replace('\\', "\\\\").replace('/', "\\/");
+    format!("/^{}$/", escaped)
+}
+
+/// Kind of the enclosing symbol, looked up by name in the same file
+fn parent_kind<'a>(file: &'a RepoFile, parent: &str) -> &'a str {
+    file.symbols
+        .iter()
+        .find(|s| s.name == parent && s.kind != SymbolKind::Import)
+        .map(|s| s.kind.name())
+        .unwrap_or("class")
+}
+
+/// UTF-16 column range of the symbol name on its definition line
+fn name_columns(file: &RepoFile, symbol: &Symbol) -> (usize, usize) {
+    let name_len = symbol.name.encode_utf16().count();
+    match line_text(file, symbol.start_line).and_then(|text| {
+        text.find(symbol.name.as_str())
+            .map(|byte| text[..byte].encode_utf16().count())
+    }) {
+        Some(start) => (start, start + name_len),
+        None => (0, name_len),
+    }
+}
+
+/// Map a symbol kind to the LSP `SymbolKind` number
+fn lsp_symbol_kind(kind: SymbolKind) -> u8 {
+    match kind {
+        SymbolKind::Module => 2,
+        SymbolKind::Class => 5,
+        SymbolKind::Method => 6,
+        SymbolKind::Enum => 10,
+        SymbolKind::Interface | SymbolKind::Trait => 11,
+        SymbolKind::Function | SymbolKind::Macro => 12,
+        SymbolKind::Variable | SymbolKind::Import | SymbolKind::Export => 13,
+        SymbolKind::Constant => 14,
+        SymbolKind::Struct => 23,
+        SymbolKind::TypeAlias => 26,
+    }
+}
+
+/// Absolute, canonical form of the repository root, so that URIs of a
+/// repository scanned from a relative path (`.`) resolve outside the dump
+fn project_root(path: &Path) -> PathBuf {
+    path.canonicalize()
+        .or_else(|_| std::path::absolute(path))
+        .unwrap_or_else(|_| path.to_path_buf())
+}
+
+fn file_uri(path: &Path) -> String {
+    url::Url::from_file_path(path)
+        .map(String::from)
+        .unwrap_or_else(|_| format!("file://{}", path.to_string_lossy()))
+}
+
+#[cfg(test)]
+#[allow(clippy::str_to_string)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn create_test_repo() -> Repository {
+        let mut calc = Symbol::new("Calculator", SymbolKind::Struct);
+        calc.start_line = 2;
+        calc.end_line = 4;
+        let mut add = Symbol::new("add", SymbolKind::Method);
+        add.start_line = 6;
+        add.end_line = 6;
+        add.parent = Some("Calculator".to_string());
+        add.signature = Some("pub fn add(&mut self, x: f64)".to_string());
+        add.docstring = Some("Add a value".to_string());
+        let import = Symbol::new("std::fmt", SymbolKind::Import);
+
+        let content = "use std::fmt;\npub struct Calculator {\n    value: f64,\n}\nimpl Calculator {\n    pub fn add(&mut self, x: f64) {}\n}\n";
+        let mut repo = Repository::new("calc", "/tmp/calc");
+        repo.files.push(RepoFile {
+            path: PathBuf::from("/tmp/calc/src/lib.rs"),
+            relative_path: "src/lib.rs".to_string(),
+            language: Some("rust".to_string()),
+            size_bytes: content.len() as u64,
+            symbols: vec![import, calc, add],
+            importance: 0.5,
+            content: Some(content.to_string()),
+            ..Default::default()
+        });
+        repo
+    }
+
+    fn parse_lines(output: &str) -> Vec<Value> {
+        output
+            .lines()
+            .map(|l| serde_json::from_str(l).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_ctags_tags() {
+        let lines = parse_lines(&export_ctags(&create_test_repo()));
+        let tags: Vec<&Value> = lines.iter().filter(|v| v["_type"] == "tag").collect();
+
+        // Sorted by name, imports skipped
+        assert_eq!(tags.len(), 2);
+        assert_eq!(tags[0]["name"], "Calculator");
+        assert_eq!(tags[0]["pattern"], "/^pub struct Calculator {$/");
+        assert_eq!(tags[1]["name"], "add");
+        assert_eq!(tags[1]["scope"], "Calculator");
+        assert_eq!(tags[1]["scopeKind"], "struct");
+        assert_eq!(tags[1]["line"], 6);
+    }
+
+    #[test]
+    fn test_ctags_pattern_escaping() {
+        assert_eq!(ctags_pattern("a/b\\c"), "/^a\\/b\\\\c$/");
+    }
+
+    #[test]
+    fn test_lsif_structure() {
+        let lines = parse_lines(&export_lsif(&create_test_repo()));
+
+        assert_eq!(lines[0]["label"], "metaData");
+        let ids: Vec<u64> = lines.iter().map(|v| v["id"].as_u64().unwrap()).collect();
+        assert!(ids.windows(2).all(|w| w[0] < w[1]));
+
+        let ranges: Vec<&Value> = lines.iter().filter(|v| v["label"] == "range").collect();
+        assert_eq!(ranges.len(), 2);
+        let add = ranges.iter().find(|r| r["tag"]["text"] == "add").unwrap();
+        assert_eq!(add["start"]["line"], 5);
+        assert_eq!(add["start"]["character"], 11);
+        assert_eq!(add["end"]["character"], 14);
+        assert_eq!(add["tag"]["kind"], 6);
+
+        assert_eq!(lines.iter().filter(|v| v["label"] == "hoverResult").count(), 1);
+        assert_eq!(lines.iter().filter(|v| v["label"] == "document").count(), 1);
+        assert!(lines
+            .iter()
+            .any(|v| v["label"] == "contains" && v["inVs"].as_array().unwrap().len() == 2));
+    }
+
+    #[test]
+    fn test_lsif_scope_events() {
+        let lines = parse_lines(&export_lsif(&create_test_repo()));
+        let events: Vec<(&str, &str, u64)> = lines
+            .iter()
+            .filter(|v| v["label"] == "$event")
+            .map(|v| {
+                (
+                    v["kind"].as_str().unwrap(),
+                    v["scope"].as_str().unwrap(),
+                    v["data"].as_u64().unwrap(),
+                )
+            })
+            .collect();
+        let id_of = |label: &str| {
+            lines.iter().find(|v| v["label"] == label).unwrap()["id"]
+                .as_u64()
+                .unwrap()
+        };
+        let (project, document) = (id_of("project"), id_of("document"));
+
+        assert_eq!(
+            events,
+            vec![
+                ("begin", "project", project),
+                ("begin", "document", document),
+                ("end", "document", document),
+                ("end", "project", project),
+            ]
+        );
+        // Every element of the document comes between its begin and end events
+        let position = |kind: &str, scope: &str| {
+            lines
+                .iter()
+                .position(|v| v["label"] == "$event" && v["kind"] == kind && v["scope"] == scope)
+                .unwrap()
+        };
+        let (begin, end) = (position("begin", "document"), position("end", "document"));
+        assert!(lines[begin..end].iter().any(|v| v["label"] == "range"));
+        assert_eq!(lines.last().unwrap()["label"], "$event");
+    }
+
+    #[test]
+    fn test_lsif_relative_root() {
+        let mut repo = create_test_repo();
+        repo.path = PathBuf::from(".");
+        let lines = parse_lines(&export_lsif(&repo));
+
+        let cwd = std::env::current_dir().unwrap().canonicalize().unwrap();
+        assert_eq!(lines[0]["projectRoot"], file_uri(&cwd));
+        let document = lines.iter().find(|v| v["label"] == "document").unwrap();
+        assert_eq!(document["uri"], file_uri(&cwd.join("src/lib.rs")));
+        assert!(!document["uri"].as_str().unwrap().contains("/./"));
+    }
+
+    #[test]
+    fn test_export_symbols_dispatch() {
+        let repo = create_test_repo();
+        assert_eq!(export_symbols(&repo, ExportFormat::Ctags), export_ctags(&repo));
+        assert_eq!(export_symbols(&repo, ExportFormat::Lsif), export_lsif(&repo));
+    }
+}
@@ -0,0 +1,447 @@
+//! Multi-repository federation for service-spanning context
+//!
+//! A federation config (`federation.yaml`) lists the repositories that make up
+//! a system, where to get them, which files matter and how many tokens each
+//! may use. Repositories are tagged with the services they implement, so the
+//! context for one service can be assembled from every repository involved:
+//!
+//! ```yaml
+//! cache_dir: .infiniloom/federation
+//! budget: 150000
+//! repos:
+//!   - name: checkout-api
+//!     url: https://github.com/acme/checkout-api
+//!     branch: main
+//!     include: ["src/**"]
+//!     services: [checkout]
+//!   - name: payments
+//!     path: ../payments
+//!     exclude: ["**/fixtures/**"]
+//!     budget: 40000
+//!     services: [checkout, billing]
+//! ```
+
+use crate::cache::CacheLock;
+use crate::remote::{RemoteError, RemoteRepo};
+use crate::types::{LanguageStats, Repository, TokenizerModel};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+/// Clone cache location, relative to the config file
+pub const DEFAULT_CACHE_DIR: &str = ".infiniloom/federation";
+
+/// Errors loading a federation or fetching its repositories
+#[derive(Debug, Error)]
+pub enum FederationError {
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("invalid federation config: {0}")]
+    Yaml(#[from] serde_yaml::Error),
+    #[error("invalid federation config: {0}")]
+    Invalid(String),
+    #[error("no repositories belong to service '{0}'")]
+    UnknownService(String),
+    #[error("{name}: {source}")]
+    Remote {
+        name: String,
+        #[source]
+        source: RemoteError,
+    },
+    #[error("{0}: not in the clone cache; run without --no-fetch first")]
+    NotCached(String),
+}
+
+/// A set of repositories packed together
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FederationConfig {
+    /// Where remote repositories are cloned (default: [`DEFAULT_CACHE_DIR`])
+    pub cache_dir: Option<PathBuf>,
+    /// Token budget shared by the selected repositories
+    pub budget: Option<u32>,
+    /// Member repositories
+    pub repos: Vec<FederatedRepo>,
+}
+
+/// One repository in a federation
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FederatedRepo {
+    /// Short unique name, used as the path prefix in merged packs
+    pub name: String,
+    /// Remote URL (anything `RemoteRepo::parse` accepts)
+    pub url: Option<String>,
+    /// Local checkout, instead of `url`
+    pub path: Option<PathBuf>,
+    /// Branch to fetch (default: the remote's default branch)
+    pub branch: Option<String>,
+    /// Glob patterns of files to keep (empty = all)
+    pub include: Vec<String>,
+    /// Glob patterns of files to drop
+    pub exclude: Vec<String>,
+    /// Token budget for this repository, overriding its share of the total
+    pub budget: Option<u32>,
+    /// Services this repository is part of
+    pub services: Vec<String>,
+}
+
+impl FederationConfig {
+    /// Read and validate a config; relative paths are resolved against the
+    /// config file's directory
+    pub fn load(path: &Path) -> Result<Self, FederationError> {
+        let mut config: Self = serde_yaml::from_str(&std::fs::read_to_string(path)?)?;
+        config.validate()?;
+
+        let base = path.parent().unwrap_or(Path::new("."));
+        let cache_dir = config
+            .cache_dir
+            .take()
+            .unwrap_or_else(|| PathBuf::from(DEFAULT_CACHE_DIR));
+        config.cache_dir = Some(base.join(cache_dir));
+        for repo in &mut config.repos {
+            if let Some(local) = repo.path.take() {
+                repo.path = Some(base.join(local));
+            }
+        }
+        Ok(config)
+    }
+
+    fn validate(&self) -> Result<(), FederationError> {
+        if self.repos.is_empty() {
+            return Err(FederationError::Invalid("no repositories listed".to_owned()));
+        }
+        let mut names = HashSet::new();
+        for repo in &self.repos {
+            if repo.name.is_empty() || repo.name.contains(['/', '\\']) || repo.name.starts_with('.')
+            {
+                return Err(FederationError::Invalid(format!(
+                    "'{}' is not a valid repository name",
+                    repo.name
+                )));
+            }
+            if !names.insert(repo.name.as_str()) {
+                return Err(FederationError::Invalid(format!(
+                    "duplicate repository '{}'",
+                    repo.name
+                )));
+            }
+            if repo.url.is_some() == repo.path.is_some() {
+                return Err(FederationError::Invalid(format!(
+                    "{}: set exactly one of `url` and `path`",
+                    repo.name
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Directory remote repositories are cloned into
+    pub fn cache_dir(&self) -> PathBuf {
+        self.cache_dir
+            .clone()
+            .unwrap_or_else(|| PathBuf::from(DEFAULT_CACHE_DIR))
+    }
+
+    /// Repositories belonging to `service`, or all of them
+    pub fn select(&self, service: Option<&str>) -> Result<Vec<&FederatedRepo>, FederationError> {
+        let Some(service) = service else {
+            return Ok(self.repos.iter().collect());
+        };
+        let selected: Vec<_> = self
+            .repos
+            .iter()
+            .filter(|r| r.services.iter().any(|s| s == service))
+            .collect();
+        if selected.is_empty() {
+            return Err(FederationError::UnknownService(service.to_owned()));
+        }
+        Ok(selected)
+    }
+
+    /// Token budget for `repo` when `selected` repositories are packed
+    ///
+    /// A repository's own budget wins; otherwise the shared budget is split
+    /// evenly. `None` means unlimited.
+    pub fn budget_for(&self, repo: &FederatedRepo, selected: usize) -> Option<u32> {
+        repo.budget
+            .or_else(|| self.budget.map(|total| total / selected.max(1) as u32))
+    }
+
+    /// Every service named in the config, with its repositories
+    pub fn services(&self) -> BTreeMap<&str, Vec<&str>> {
+        let mut services: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+        for repo in &self.repos {
+            for service in &repo.services {
+                services.entry(service).or_default().push(&repo.name);
+            }
+        }
+        services
+    }
+}
+
+impl FederatedRepo {
+    /// Where the repository's files are, fetching or updating the cached
+    /// clone of a remote unless `fetch` is false
+    pub fn checkout(&self, cache_dir: &Path, fetch: bool) -> Result<PathBuf, FederationError> {
+        if let Some(path) = &self.path {
+            return Ok(path.clone());
+        }
+        let url = self.url.as_deref().unwrap_or_default();
+        let mut remote = RemoteRepo::parse(url)
+            .map_err(|source| FederationError::Remote { name: self.name.clone(), source })?;
+        if self.branch.is_some() {
+            remote.branch = self.branch.clone();
+        }
+
+        let target = cache_dir.join(&self.name);
+        if !fetch {
+            if !target.join(".git").exists() {
+                return Err(FederationError::NotCached(self.name.clone()));
+            }
+            return Ok(remote
+                .subdir
+                .as_ref()
+                .map(|subdir| target.join(subdir))
+                .filter(|p| p.exists())
+                .unwrap_or(target));
+        }
+
+        // Two runs syncing one clone would corrupt it
+        let _lock = CacheLock::exclusive(&target)?;
+        remote
+            .sync(&target)
+            .map_err(|source| FederationError::Remote { name: self.name.clone(), source })
+    }
+
+    /// Where the repository comes from, for display
+    pub fn source(&self) -> String {
+        match (&self.url, &self.path) {
+            (Some(url), _) => url.clone(),
+            (None, Some(path)) => path.display().to_string(),
+            (None, None) => String::new(),
+        }
+    }
+
+    /// Keep only files matching `include` and not matching `exclude`
+    pub fn apply_filters(&self, repo: &mut Repository) {
+        let globs = |patterns: &[String]| -> Vec<glob::Pattern> {
+            patterns
+                .iter()
+                .filter_map(|p| glob::Pattern::new(p).ok())
+                .collect()
+        };
+        let include = globs(&self.include);
+        let exclude = globs(&self.exclude);
+        repo.files.retain(|f| {
+            (include.is_empty() || include.iter().any(|p| p.matches(&f.relative_path)))
+                && !exclude.iter().any(|p| p.matches(&f.relative_path))
+        });
+    }
+}
+
+/// Drop files, in order, once `budget` tokens are used; returns how many
+/// were dropped
+///
+/// Files should already be sorted by importance. Files too large for the
+/// remaining budget are skipped so smaller ones further down can still fit.
+pub fn trim_to_budget(repo: &mut Repository, budget: u32, model: TokenizerModel) -> usize {
+    let before = repo.files.len();
+    let mut used = 0u32;
+    repo.files.retain(|f| {
+        let tokens = f.token_count.get(model);
+        if used.saturating_add(tokens) > budget {
+            return false;
+        }
+        used += tokens;
+        true
+    });
+    before - repo.files.len()
+}
+
+/// Combine repositories into one, prefixing each file path with its
+/// repository name
+pub fn merge(name: &str, root: &Path, parts: Vec<(String, Repository)>) -> Repository {
+    let mut merged = Repository::new(name, root);
+    let mut languages: BTreeMap<String, (u32, u64)> = BTreeMap::new();
+
+    for (prefix, repo) in parts {
+        let meta = &mut merged.metadata;
+        meta.total_lines += repo.metadata.total_lines;
+        for lang in &repo.metadata.languages {
+            let entry = languages.entry(lang.language.clone()).or_default();
+            entry.0 += lang.files;
+            entry.1 += lang.lines;
+        }
+        for dep in repo.metadata.external_dependencies {
+            if !meta.external_dependencies.contains(&dep) {
+                meta.external_dependencies.push(dep);
+            }
+        }
+        for mut file in repo.files {
+            file.relative_path = format!("{}/{}", prefix, file.relative_path);
+            merged.files.push(file);
+        }
+    }
+
+    let meta = &mut merged.metadata;
+    meta.total_files = merged.files.len() as u32;
+    for file in &merged.files {
+        let counts = &file.token_count;
+        meta.total_tokens.claude += counts.claude;
+        meta.total_tokens.gpt4o += counts.gpt4o;
+        meta.total_tokens.gpt4 += counts.gpt4;
+        meta.total_tokens.gemini += counts.gemini;
+        meta.total_tokens.llama += counts.llama;
+    }
+    let language_files: u32 = languages.values().map(|(files, _)| files).sum();
+    meta.languages = languages
+        .into_iter()
+        .map(|(language, (files, lines))| LanguageStats {
+            language,
+            files,
+            lines,
+            percentage: if language_files > 0 {
+                files as f32 / language_files as f32 * 100.0
+            } else {
+                0.0
+            },
+        })
+        .collect();
+    meta.external_dependencies.sort();
+    merged
+}
+
+/// One row of the cross-repository index
+#[derive(Debug, Clone, Serialize)]
+pub struct IndexEntry {
+    /// Repository name
+    pub name: String,
+    /// URL or local path
+    pub source: String,
+    /// Branch packed, if known
+    pub branch: Option<String>,
+    /// Commit packed, if known
+    pub commit: Option<String>,
+    /// Files packed
+    pub files: usize,
+    /// Tokens packed, for the target model
+    pub tokens: u32,
+    /// Files dropped to stay within budget
+    pub dropped: usize,
+    /// Per-repository pack, when packs are split
+    pub pack: Option<String>,
+}
+
+/// Markdown index of the repositories in a federated pack
+pub fn render_index(title: &str, entries: &[IndexEntry]) -> String {
+    let mut out = format!("# Cross-Repository Index: {}\n\n", title);
+    out.push_str("| Repository | Source | Revision | Files | Tokens | Pack |\n");
+    out.push_str("|------------|--------|----------|-------|--------|------|\n");
+    for entry in entries {
+        let revision = match (&entry.branch, &entry.commit) {
+            (Some(branch), Some(commit)) => {
+                format!("{}@{}", branch, commit.get(..12).unwrap_or(commit))
+            },
+            (Some(branch), None) => branch.clone(),
+            (None, Some(commit)) => commit.get(..12).unwrap_or(commit).to_owned(),
+            (None, None) => "-".to_owned(),
+        };
+        let files = if entry.dropped > 0 {
+            format!("{} (+{} over budget)", entry.files, entry.dropped)
+        } else {
+            entry.files.to_string()
+        };
+        out.push_str(&format!(
+            "| {} | {} | {} | {} | {} | {} |\n",
+            entry.name,
+            entry.source,
+            revision,
+            files,
+            entry.tokens,
+            entry.pack.as_deref().unwrap_or("-")
+        ));
+    }
+    out
+}
+
+#[cfg(test)]
+#[allow(clippy::str_to_string)]
+mod tests {
+    use super::*;
+    use crate::types::{RepoFile, TokenCounts};
+
+    fn repo(files: &[(&str, u32)]) -> Repository {
+        let mut repo = Repository::new("test", "/tmp/test");
+        repo.files = files
+            .iter()
+            .map(|(path, tokens)| RepoFile {
+                path: format!("/tmp/test/{}", path).into(),
+                relative_path: path.to_string(),
+                language: Some("rust".to_string()),
+                size_bytes: 100,
+                token_count: TokenCounts { claude: *tokens, ..TokenCounts::default() },
+                importance: 0.5,
+                content: Some(String::new()),
+                ..Default::default()
+            })
+            .collect();
+        repo
+    }
+
+    #[test]
+    fn test_load_and_select() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("federation.yaml");
+        std::fs::write(
+            &path,
+            "budget: 1000\nrepos:\n  - name: api\n    url: github:acme/api\n    services: [checkout]\n  - name: web\n    path: ../web\n    budget: 300\n    services: [checkout, storefront]\n",
+        )
+        .unwrap();
+
+        let config = FederationConfig::load(&path).unwrap();
+        assert_eq!(config.cache_dir(), dir.path().join(DEFAULT_CACHE_DIR));
+        assert_eq!(config.repos[1].path, Some(dir.path().join("../web")));
+
+        let checkout = config.select(Some("checkout")).unwrap();
+        assert_eq!(checkout.len(), 2);
+        assert_eq!(config.budget_for(checkout[0], 2), Some(500));
+        assert_eq!(config.budget_for(checkout[1], 2), Some(300));
+        assert_eq!(config.select(Some("storefront")).unwrap()[0].name, "web");
+        assert!(matches!(config.select(Some("search")), Err(FederationError::UnknownService(_))));
+
+        std::fs::write(&path, "repos:\n  - name: api\n    url: a/b\n    path: .\n").unwrap();
+        assert!(matches!(FederationConfig::load(&path), Err(FederationError::Invalid(_))));
+    }
+
+    #[test]
+    fn test_filters_budget_and_merge() {
+        let member = FederatedRepo {
+            name: "api".to_string(),
+            include: vec!["src/**".to_string()],
+            exclude: vec!["src/gen/**".to_string()],
+            ..FederatedRepo::default()
+        };
+        let mut api =
+            repo(&[("src/a.rs", 60), ("src/gen/b.rs", 10), ("src/c.rs", 50), ("README", 5)]);
+        member.apply_filters(&mut api);
+        assert_eq!(api.files.len(), 2);
+
+        assert_eq!(trim_to_budget(&mut api, 100, TokenizerModel::Claude), 1);
+        assert_eq!(api.files[0].relative_path, "src/a.rs");
+
+        let merged = merge(
+            "checkout",
+            Path::new("/tmp"),
+            vec![("api".to_string(), api), ("web".to_string(), repo(&[("index.ts", 7)]))],
+        );
+        let paths: Vec<_> = merged
+            .files
+            .iter()
+            .map(|f| f.relative_path.as_str())
+            .collect();
+        assert_eq!(paths, ["api/src/a.rs", "web/index.ts"]);
+        assert_eq!(merged.metadata.total_tokens.claude, 67);
+    }
+}
@@ -0,0 +1,465 @@
+//! Context for a question about one file
+//!
+//! [`FileContext::build`] assembles "one file plus halo": the file itself in
+//! full, and around it what a reader needs to follow it without the rest of
+//! the repository:
+//!
+//! - signatures of the symbols it uses from the files it imports
+//! - definitions of the types it names, from anywhere in the repository
+//! - signatures of the functions that call into it, in files importing it
+//!
+//! The halo is added in that order until the token budget runs out. A file
+//! over the budget on its own is cut at a symbol boundary and gets no halo.
+
+use crate::budget::BudgetAllocator;
+use crate::dependencies::DependencyGraph;
+use crate::output::{declaration, is_exported};
+use crate::tokenizer::Tokenizer;
+use crate::types::{RepoFile, Repository, Symbol, SymbolKind, TokenizerModel};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashSet};
+use std::fmt::Write;
+
+/// Default token budget of a file context
+pub const DEFAULT_FILE_CONTEXT_BUDGET: u32 = 6000;
+
+/// Why a halo item was included
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HaloKind {
+    /// A symbol the file uses from a file it imports
+    Import,
+    /// A type the file names
+    Type,
+    /// A function calling into the file
+    Caller,
+}
+
+/// A symbol shown around the file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct HaloItem {
+    /// Why it is included
+    pub kind: HaloKind,
+    /// File defining the symbol
+    pub path: String,
+    /// Line the symbol starts on
+    pub line: u32,
+    /// Symbol name
+    pub name: String,
+    /// Declaration, or the whole definition for types
+    pub text: String,
+    /// Symbols of the file a caller uses
+    pub uses: Vec<String>,
+    /// Tokens of `text`
+    pub tokens: u32,
+}
+
+/// A file and the halo of related symbols that fit its budget
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FileContext {
+    /// Relative path of the file
+    pub path: String,
+    /// Language of the file
+    pub language: Option<String>,
+    /// File content, cut short if `truncated`
+    pub content: String,
+    /// Whether the file alone was over the budget and was cut
+    pub truncated: bool,
+    /// Related symbols, in the order they were chosen
+    pub halo: Vec<HaloItem>,
+    /// Related symbols left out for the budget
+    pub omitted: usize,
+    /// Token budget
+    pub budget: u32,
+    /// Tokens used by the file and the halo
+    pub tokens: u32,
+}
+
+impl FileContext {
+    /// Gather the context of the file at relative `path` within `budget`
+    /// tokens of `model`
+    ///
+    /// Files without extracted symbols are parsed first. Returns `None` if
+    /// no scanned file has that path.
+    pub fn build(
+        repo: &mut Repository,
+        path: &str,
+        budget: u32,
+        model: TokenizerModel,
+    ) -> Option<Self> {
+        crate::scan::parse_missing_symbols(repo);
+        let path = path.trim_start_matches("./");
+        let target = repo.files.iter().find(|f| f.relative_path == path)?;
+
+        let tokenizer = Tokenizer::estimation_only();
+        let mut content = target.content.clone().unwrap_or_default();
+        let mut tokens = tokenizer.count(&content, model.into());
+        let truncated = tokens > budget;
+        if truncated {
+            let mut single = Repository::new(repo.name.clone(), repo.path.clone());
+            single.files.push(target.clone());
+            single.files[0].token_count.set(model, tokens);
+            BudgetAllocator::new(budget, model)
+                .with_min_file_tokens(0)
+                .allocate(&mut single);
+            // Not even the first line fitting leaves the file out entirely
+            content = single
+                .files
+                .first()
+                .and_then(|f| f.content.clone())
+                .unwrap_or_default();
+            tokens = tokenizer.count(&content, model.into());
+        }
+
+        let mut context = Self {
+            path: path.to_owned(),
+            language: target.language.clone(),
+            content,
+            truncated,
+            halo: Vec::new(),
+            omitted: 0,
+            budget,
+            tokens,
+        };
+        if truncated {
+            return Some(context);
+        }
+
+        for mut item in halo(repo, target) {
+            item.tokens = tokenizer.count(&item.text, model.into());
+            if context.tokens + item.tokens <= budget {
+                context.tokens += item.tokens;
+                context.halo.push(item);
+            } else {
+                context.omitted += 1;
+            }
+        }
+        Some(context)
+    }
+
+    /// Markdown with the file first and its halo grouped by kind
+    pub fn render(&self) -> String {
+        let mut output = String::new();
+        let language = self.language.as_deref().unwrap_or_default();
+        writeln!(output, "# File context: {}\n", self.path).unwrap();
+        let mut notes = format!("{} of {} tokens", self.tokens, self.budget);
+        if self.truncated {
+            notes.push_str("; the file was cut to fit");
+        }
+        if self.omitted > 0 {
+            notes.push_str(&format!("; {} related symbols left out", self.omitted));
+        }
+        writeln!(output, "{}\n", notes).unwrap();
+        writeln!(output, "## {}\n", self.path).unwrap();
+        writeln!(output, "```{}\n{}\n```\n", language, self.content.trim_end()).unwrap();
+
+        let sections = [
+            (HaloKind::Import, "Imported signatures"),
+            (HaloKind::Type, "Type definitions"),
+            (HaloKind::Caller, "Callers"),
+        ];
+        for (kind, title) in sections {
+            let items: Vec<&HaloItem> = self.halo.iter().filter(|i| i.kind == kind).collect();
+            if items.is_empty() {
+                continue;
+            }
+            writeln!(output, "## {}\n", title).unwrap();
+            if kind == HaloKind::Type {
+                for item in items {
+                    writeln!(output, "{}:{}\n", item.path, item.line).unwrap();
+                    writeln!(output, "```{}\n{}\n```\n", language, item.text.trim_end()).unwrap();
+                }
+                continue;
+            }
+            let mut by_file: BTreeMap<&str, Vec<&HaloItem>> = BTreeMap::new();
+            for item in items {
+                by_file.entry(&item.path).or_default().push(item);
+            }
+            for (path, items) in by_file {
+                writeln!(output, "{}", path).unwrap();
+                for item in items {
+                    if item.uses.is_empty() {
+                        writeln!(output, "  {}: {}", item.line, item.text).unwrap();
+                    } else {
+                        writeln!(
+                            output,
+                            "  {}: {}  (uses {})",
+                            item.line,
+                            item.text,
+                            item.uses.join(", ")
+                        )
+                        .unwrap();
+                    }
+                }
+            }
+            output.push('\n');
+        }
+        output
+    }
+}
+
+/// Candidate halo items of `target`, most useful first
+fn halo(repo: &Repository, target: &RepoFile) -> Vec<HaloItem> {
+    let graph = DependencyGraph::build(repo);
+    let file = |path: &str| repo.files.iter().find(|f| f.relative_path == path);
+    let used = identifiers(target.content.as_deref().unwrap_or_default());
+    let mut items = Vec::new();
+
+    let mut imported = graph.get_imports(&target.relative_path);
+    imported.retain(|p| *p != target.relative_path);
+    imported.sort_unstable();
+    imported.dedup();
+    for source in imported.iter().filter_map(|p| file(p)) {
+        let lines = lines(source);
+        for symbol in &source.symbols {
+            if is_type(symbol.kind)
+                || symbol.kind == SymbolKind::Import
+                || !used.contains(symbol.name.as_str())
+            {
+                continue;
+            }
+            let line = line_of(&lines, symbol);
+            if is_exported(source, symbol, line.unwrap_or_default()) {
+                items.push(item(HaloKind::Import, source, symbol, declaration(symbol, line)));
+            }
+        }
+    }
+
+    // A type defined in several files is taken from an imported one, or
+    // left out as ambiguous
+    let mut types: BTreeMap<&str, Vec<(&RepoFile, &Symbol)>> = BTreeMap::new();
+    for source in repo
+        .files
+        .iter()
+        .filter(|f| f.relative_path != target.relative_path)
+    {
+        for symbol in source.symbols.iter().filter(|s| is_type(s.kind)) {
+            if used.contains(symbol.name.as_str()) {
+                types
+                    .entry(&symbol.name)
+                    .or_default()
+                    .push((source, symbol));
+            }
+        }
+    }
+    for definitions in types.values() {
+        let chosen = match definitions.as_slice() {
+            [single] => Some(single),
+            many => many
+                .iter()
+                .find(|(f, _)| imported.contains(&f.relative_path.as_str())),
+        };
+        if let Some((source, symbol)) = chosen {
+            let lines = lines(source);
+            let start = (symbol.start_line as usize).saturating_sub(1);
+            let end = (symbol.end_line as usize).min(lines.len());
+            if start < end {
+                items.push(item(HaloKind::Type, source, symbol, lines[start..end].join("\n")));
+            }
+        }
+    }
+
+    let defined: HashSet<&str> = target
+        .symbols
+        .iter()
+        .filter(|s| s.kind != SymbolKind::Import)
+        .map(|s| s.name.as_str())
+        .collect();
+    let mut importers = graph.get_importers(&target.relative_path);
+    importers.retain(|p| *p != target.relative_path);
+    importers.sort_unstable();
+    importers.dedup();
+    for caller in importers.iter().filter_map(|p| file(p)) {
+        let lines = lines(caller);
+        for symbol in &caller.symbols {
+            if !matches!(symbol.kind, SymbolKind::Function | SymbolKind::Method) {
+                continue;
+            }
+            let start = (symbol.start_line as usize).saturating_sub(1);
+            let end = (symbol.end_line as usize).min(lines.len());
+            if start >= end {
+                continue;
+            }
+            let body = lines[start..end].join("\n");
+            let mut uses: Vec<String> = identifiers(&body)
+                .into_iter()
+                .filter(|name| defined.contains(name) && *name != symbol.name)
+                .map(str::to_owned)
+                .collect();
+            if uses.is_empty() {
+                continue;
+            }
+            uses.sort();
+            let mut caller_item = item(
+                HaloKind::Caller,
+                caller,
+                symbol,
+                declaration(symbol, line_of(&lines, symbol)),
+            );
+            caller_item.uses = uses;
+            items.push(caller_item);
+        }
+    }
+
+    items
+}
+
+fn item(kind: HaloKind, file: &RepoFile, symbol: &Symbol, text: String) -> HaloItem {
+    HaloItem {
+        kind,
+        path: file.relative_path.clone(),
+        line: symbol.start_line,
+        name: symbol.name.clone(),
+        text,
+        uses: Vec::new(),
+        tokens: 0,
+    }
+}
+
+fn is_type(kind: SymbolKind) -> bool {
+    matches!(
+        kind,
+        SymbolKind::Class
+            | SymbolKind::Interface
+            | SymbolKind::Struct
+            | SymbolKind::Enum
+            | SymbolKind::TypeAlias
+            | SymbolKind::Trait
+    )
+}
+
+fn lines(file: &RepoFile) -> Vec<&str> {
+    file.content
+        .as_deref()
+        .map(|c| c.lines().collect())
+        .unwrap_or_default()
+}
+
+/// The trimmed line a symbol starts on
+fn line_of<'a>(lines: &[&'a str], symbol: &Symbol) -> Option<&'a str> {
+    let index = (symbol.start_line as usize).checked_sub(1)?;
+    lines.get(index).map(|l| l.trim())
+}
+
+/// Distinct identifiers in source text
+fn identifiers(text: &str) -> HashSet<&str> {
+    text.split(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .filter(|w| w.starts_with(|c: char| c.is_alphabetic() || c == '_'))
+        .collect()
+}
+
+#[cfg(test)]
+#[allow(clippy::str_to_string)]
+mod tests {
+    use super::*;
+
+    fn file(path: &str, content: &str, symbols: &[(&str, SymbolKind, u32, u32)]) -> RepoFile {
+        let mut file = RepoFile::new(path, path);
+        file.language = Some("javascript".to_string());
+        file.content = Some(content.to_string());
+        file.symbols = symbols
+            .iter()
+            .map(|&(name, kind, start, end)| {
+                let mut symbol = Symbol::new(name, kind);
+                symbol.start_line = start;
+                symbol.end_line = end;
+                symbol
+            })
+            .collect();
+        file
+    }
+
+    fn repo() -> Repository {
+        let mut repo = Repository::new("test", "/tmp/test");
+        repo.files.push(file(
+            "src/models.js",
+            "export class User {\n  constructor(name) { this.name = name; }\n}\n",
+            &[("User", SymbolKind::Class, 1, 3)],
+        ));
+        repo.files.push(file(
+            "src/util.js",
+            "export function slugify(text) {\n  return text.toLowerCase();\n}\n\nexport function unused() {}\n",
+            &[("slugify", SymbolKind::Function, 1, 3), ("unused", SymbolKind::Function, 5, 5)],
+        ));
+        repo.files.push(file(
+            "src/service.js",
+            "import { slugify } from './util';\nimport { User } from './models';\n\nexport function register(name) {\n  return slugify(new User(name).name);\n}\n",
+            &[
+                ("import { slugify } from './util';", SymbolKind::Import, 1, 1),
+                ("import { User } from './models';", SymbolKind::Import, 2, 2),
+                ("register", SymbolKind::Function, 4, 6),
+            ],
+        ));
+        repo.files.push(file(
+            "src/main.js",
+            "import { register } from './service';\n\nfunction main() {\n  register('ada');\n}\n\nfunction other() {}\n",
+            &[
+                ("import { register } from './service';", SymbolKind::Import, 1, 1),
+                ("main", SymbolKind::Function, 3, 5),
+                ("other", SymbolKind::Function, 7, 7),
+            ],
+        ));
+        repo
+    }
+
+    #[test]
+    fn test_file_plus_halo() {
+        let mut repo = repo();
+        let context =
+            FileContext::build(&mut repo, "./src/service.js", 6000, TokenizerModel::Claude)
+                .unwrap();
+        assert!(!context.truncated);
+        assert_eq!(context.omitted, 0);
+        let halo: Vec<(HaloKind, &str, &str)> = context
+            .halo
+            .iter()
+            .map(|i| (i.kind, i.path.as_str(), i.name.as_str()))
+            .collect();
+        assert_eq!(
+            halo,
+            vec![
+                (HaloKind::Import, "src/util.js", "slugify"),
+                (HaloKind::Type, "src/models.js", "User"),
+                (HaloKind::Caller, "src/main.js", "main"),
+            ]
+        );
+        assert_eq!(
+            context.halo[1].text,
+            "export class User {\n  constructor(name) { this.name = name; }\n}"
+        );
+        assert_eq!(context.halo[2].uses, vec!["register"]);
+
+        let output = context.render();
+        assert!(output.starts_with("# File context: src/service.js\n"));
+        assert!(output.contains(
+            "## Imported signatures\n\nsrc/util.js\n  1: export function slugify(text)\n"
+        ));
+        assert!(
+            output.contains("## Callers\n\nsrc/main.js\n  3: function main()  (uses register)\n")
+        );
+
+        assert!(FileContext::build(&mut repo, "missing.js", 6000, TokenizerModel::Claude).is_none());
+    }
+
+    #[test]
+    fn test_budget() {
+        let mut repo = repo();
+        // Room for the file only: the halo is left out
+        let content = repo.files[2].content.clone().unwrap();
+        let own = Tokenizer::estimation_only().count(&content, TokenizerModel::Claude.into());
+        let context =
+            FileContext::build(&mut repo, "src/service.js", own, TokenizerModel::Claude).unwrap();
+        assert!(context.halo.is_empty());
+        assert_eq!(context.omitted, 3);
+        assert!(context.tokens <= own);
+
+        let context =
+            FileContext::build(&mut repo, "src/service.js", own / 2, TokenizerModel::Claude)
+                .unwrap();
+        assert!(context.truncated);
+        assert!(context.halo.is_empty());
+        assert!(!context.content.is_empty());
+        assert!(context.content.len() < content.len());
+        assert!(context.tokens <= own / 2);
+    }
+}
@@ -0,0 +1,538 @@
+//! Composable file selection
+//!
+//! A [`FileFilter`] decides whether a scanned file stays in a repository. The
+//! built-in filters select by path ([`GlobFilter`]), size ([`SizeFilter`]),
+//! language ([`LanguageFilter`]), git status ([`GitStatusFilter`]) and rank
+//! ([`ImportanceFilter`]); any `Fn(&RepoFile) -> bool` is a filter too.
+//! Filters combine with [`FileFilter::and`], [`FileFilter::or`] and
+//! [`FileFilter::not`], or stack in a [`FilterStack`].
+//!
+//! [`ScanOptions::with_filter`](crate::ScanOptions::with_filter) applies
+//! filters while scanning, and [`Repository::retain_files`] to a repository
+//! scanned already.
+//!
+//! ```
+//! use infiniloom_engine::filter::{FileFilter, GlobFilter, LanguageFilter, SizeFilter};
+//! use infiniloom_engine::{Repository, ScanOptions};
+//! # let dir = tempfile::tempdir()?;
+//! # std::fs::create_dir(dir.path().join("src"))?;
+//! # std::fs::write(dir.path().join("src/app.py"), "def run():\n    pass\n")?;
+//! # std::fs::write(dir.path().join("src/app_test.py"), "def test():\n    pass\n")?;
+//! # std::fs::write(dir.path().join("notes.md"), "# Notes\n")?;
+//!
+//! let filter = LanguageFilter::only(["python"])
+//!     .and(GlobFilter::new(["src/**"], ["**/*_test.py"])?)
+//!     .and(SizeFilter::new().with_max_bytes(100_000));
+//! let repo = Repository::scan(dir.path(), ScanOptions::default().with_filter(filter))?;
+//! assert_eq!(repo.files.len(), 1);
+//! assert_eq!(repo.files[0].relative_path, "src/app.py");
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use crate::git::FileStatus;
+use crate::languages;
+use crate::types::{RepoFile, Repository, TokenizerModel};
+use glob::Pattern;
+use std::fmt;
+use std::sync::Arc;
+use thiserror::Error;
+
+/// Errors building a filter
+#[derive(Debug, Error)]
+pub enum FilterError {
+    /// A glob pattern did not parse
+    #[error("invalid glob {pattern}: {source}")]
+    InvalidGlob {
+        pattern: String,
+        #[source]
+        source: glob::PatternError,
+    },
+}
+
+/// Decides which files a repository keeps
+pub trait FileFilter: Send + Sync {
+    /// Whether `file` stays
+    fn keep(&self, file: &RepoFile) -> bool;
+
+    /// Keep files both filters keep
+    fn and<F: FileFilter>(self, other: F) -> And<Self, F>
+    where
+        Self: Sized,
+    {
+        And(self, other)
+    }
+
+    /// Keep files either filter keeps
+    fn or<F: FileFilter>(self, other: F) -> Or<Self, F>
+    where
+        Self: Sized,
+    {
+        Or(self, other)
+    }
+
+    /// Keep the files this filter drops
+    fn not(self) -> Not<Self>
+    where
+        Self: Sized,
+    {
+        Not(self)
+    }
+}
+
+impl<F> FileFilter for F
+where
+    F: Fn(&RepoFile) -> bool + Send + Sync,
+{
+    fn keep(&self, file: &RepoFile) -> bool {
+        self(file)
+    }
+}
+
+/// Files two filters both keep, from [`FileFilter::and`]
+#[derive(Debug, Clone)]
+pub struct And<A, B>(A, B);
+
+impl<A: FileFilter, B: FileFilter> FileFilter for And<A, B> {
+    fn keep(&self, file: &RepoFile) -> bool {
+        self.0.keep(file) && self.1.keep(file)
+    }
+}
+
+/// Files either of two filters keeps, from [`FileFilter::or`]
+#[derive(Debug, Clone)]
+pub struct Or<A, B>(A, B);
+
+impl<A: FileFilter, B: FileFilter> FileFilter for Or<A, B> {
+    fn keep(&self, file: &RepoFile) -> bool {
+        self.0.keep(file) || self.1.keep(file)
+    }
+}
+
+/// Files a filter drops, from [`FileFilter::not`]
+#[derive(Debug, Clone)]
+pub struct Not<F>(F);
+
+impl<F: FileFilter> FileFilter for Not<F> {
+    fn keep(&self, file: &RepoFile) -> bool {
+        !self.0.keep(file)
+    }
+}
+
+/// Filters applied in order; a file stays if every one keeps it
+#[derive(Clone, Default)]
+pub struct FilterStack {
+    filters: Vec<Arc<dyn FileFilter>>,
+}
+
+impl FilterStack {
+    /// An empty stack, which keeps every file
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a filter on top of the stack
+    pub fn with(mut self, filter: impl FileFilter + 'static) -> Self {
+        self.push(filter);
+        self
+    }
+
+    /// Add a filter on top of the stack
+    pub fn push(&mut self, filter: impl FileFilter + 'static) {
+        self.filters.push(Arc::new(filter));
+    }
+
+    /// Number of filters
+    pub fn len(&self) -> usize {
+        self.filters.len()
+    }
+
+    /// Whether the stack has no filters
+    pub fn is_empty(&self) -> bool {
+        self.filters.is_empty()
+    }
+}
+
+impl fmt::Debug for FilterStack {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FilterStack")
+            .field("filters", &self.filters.len())
+            .finish()
+    }
+}
+
+impl FileFilter for FilterStack {
+    fn keep(&self, file: &RepoFile) -> bool {
+        self.filters.iter().all(|f| f.keep(file))
+    }
+}
+
+/// Selects files by relative path
+///
+/// A file stays if it matches an include pattern, or there are none, and
+/// matches no exclude pattern.
+#[derive(Debug, Clone, Default)]
+pub struct GlobFilter {
+    include: Vec<Pattern>,
+    exclude: Vec<Pattern>,
+}
+
+impl GlobFilter {
+    /// Filter by glob patterns, failing on the first one that does not parse
+    pub fn new<I, E>(include: I, exclude: E) -> Result<Self, FilterError>
+    where
+        I: IntoIterator,
+        I::Item: AsRef<str>,
+        E: IntoIterator,
+        E::Item: AsRef<str>,
+    {
+        let compile = |pattern: &str| {
+            Pattern::new(pattern)
+                .map_err(|source| FilterError::InvalidGlob { pattern: pattern.to_owned(), source })
+        };
+        Ok(Self {
+            include: include
+                .into_iter()
+                .map(|p| compile(p.as_ref()))
+                .collect::<Result<_, _>>()?,
+            exclude: exclude
+                .into_iter()
+                .map(|p| compile(p.as_ref()))
+                .collect::<Result<_, _>>()?,
+        })
+    }
+
+    /// Filter by glob patterns, ignoring those that do not parse
+    pub fn lenient<I, E>(include: I, exclude: E) -> Self
+    where
+        I: IntoIterator,
+        I::Item: AsRef<str>,
+        E: IntoIterator,
+        E::Item: AsRef<str>,
+    {
+        Self {
+            include: include
+                .into_iter()
+                .filter_map(|p| Pattern::new(p.as_ref()).ok())
+                .collect(),
+            exclude: exclude
+                .into_iter()
+                .filter_map(|p| Pattern::new(p.as_ref()).ok())
+                .collect(),
+        }
+    }
+
+    /// Whether every file stays
+    pub fn is_empty(&self) -> bool {
+        self.include.is_empty() && self.exclude.is_empty()
+    }
+
+    /// Include patterns; with none, every path is included
+    pub fn include_patterns(&self) -> &[Pattern] {
+        &self.include
+    }
+
+    /// Exclude patterns
+    pub fn exclude_patterns(&self) -> &[Pattern] {
+        &self.exclude
+    }
+}
+
+impl FileFilter for GlobFilter {
+    fn keep(&self, file: &RepoFile) -> bool {
+        let path = file.relative_path.as_str();
+        (self.include.is_empty() || self.include.iter().any(|p| p.matches(path)))
+            && !self.exclude.iter().any(|p| p.matches(path))
+    }
+}
+
+/// Selects files by size on disk and token count
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SizeFilter {
+    min_bytes: u64,
+    max_bytes: Option<u64>,
+    max_tokens: Option<(u32, TokenizerModel)>,
+}
+
+impl SizeFilter {
+    /// A filter that keeps files of any size
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Drop files smaller than `bytes`
+    pub fn with_min_bytes(mut self, bytes: u64) -> Self {
+        self.min_bytes = bytes;
+        self
+    }
+
+    /// Drop files larger than `bytes`
+    pub fn with_max_bytes(mut self, bytes: u64) -> Self {
+        self.max_bytes = Some(bytes);
+        self
+    }
+
+    /// Drop files of more than `tokens` tokens for `model`
+    pub fn with_max_tokens(mut self, tokens: u32, model: TokenizerModel) -> Self {
+        self.max_tokens = Some((tokens, model));
+        self
+    }
+}
+
+impl FileFilter for SizeFilter {
+    fn keep(&self, file: &RepoFile) -> bool {
+        file.size_bytes >= self.min_bytes
+            && self.max_bytes.is_none_or(|max| file.size_bytes <= max)
+            && self
+                .max_tokens
+                .is_none_or(|(max, model)| file.token_count.get(model) <= max)
+    }
+}
+
+/// Selects files by language
+///
+/// Languages are named by identifier or alias (`rust`, `yml`, `c++`), ignoring
+/// case. Files of no known language are dropped by [`LanguageFilter::only`]
+/// and kept by [`LanguageFilter::except`].
+#[derive(Debug, Clone, Default)]
+pub struct LanguageFilter {
+    only: Vec<String>,
+    except: Vec<String>,
+}
+
+impl LanguageFilter {
+    /// Keep only files in one of `languages`
+    pub fn only<I>(languages: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: AsRef<str>,
+    {
+        Self { only: normalize(languages), except: Vec::new() }
+    }
+
+    /// Drop files in any of `languages`
+    pub fn except<I>(languages: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: AsRef<str>,
+    {
+        Self { only: Vec::new(), except: normalize(languages) }
+    }
+
+    /// Also drop files in any of `languages`
+    pub fn and_except<I>(mut self, languages: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: AsRef<str>,
+    {
+        self.except.extend(normalize(languages));
+        self
+    }
+
+    /// Whether the filter keeps every file
+    pub fn is_empty(&self) -> bool {
+        self.only.is_empty() && self.except.is_empty()
+    }
+
+    /// Whether files of `language` (`None` for unknown) are kept
+    pub fn allows(&self, language: Option<&str>) -> bool {
+        let language = language.map(canonical_language);
+        let listed = |list: &[String]| {
+            language
+                .as_ref()
+                .is_some_and(|l| list.iter().any(|x| x == l))
+        };
+        (self.only.is_empty() || listed(&self.only)) && !listed(&self.except)
+    }
+
+    /// Names in the filter that are neither a known language nor an alias
+    pub fn unknown(&self) -> Vec<&str> {
+        self.only
+            .iter()
+            .chain(&self.except)
+            .filter(|l| languages::lookup(l).is_none())
+            .map(String::as_str)
+            .collect()
+    }
+}
+
+impl FileFilter for LanguageFilter {
+    fn keep(&self, file: &RepoFile) -> bool {
+        self.allows(file.language.as_deref())
+    }
+}
+
+fn normalize<I>(languages: I) -> Vec<String>
+where
+    I: IntoIterator,
+    I::Item: AsRef<str>,
+{
+    languages
+        .into_iter()
+        .map(|l| canonical_language(l.as_ref()))
+        .collect()
+}
+
+/// Scanner identifier of a language name or alias
+fn canonical_language(language: &str) -> String {
+    languages::lookup(language).map_or_else(|| language.to_ascii_lowercase(), |l| l.id.to_owned())
+}
+
+/// Selects files by how git sees them
+///
+/// Reads the status that [`annotate_changes`](crate::git::annotate_changes)
+/// records on each file; files without one are dropped.
+#[derive(Debug, Clone, Default)]
+pub struct GitStatusFilter {
+    statuses: Vec<FileStatus>,
+}
+
+impl GitStatusFilter {
+    /// Keep files with one of `statuses`
+    pub fn new(statuses: impl IntoIterator<Item = FileStatus>) -> Self {
+        Self { statuses: statuses.into_iter().collect() }
+    }
+
+    /// Keep files with any change
+    pub fn changed() -> Self {
+        Self::default()
+    }
+}
+
+impl FileFilter for GitStatusFilter {
+    fn keep(&self, file: &RepoFile) -> bool {
+        file.git_change.as_ref().is_some_and(|change| {
+            self.statuses.is_empty() || self.statuses.iter().any(|s| s.code() == change.status)
+        })
+    }
+}
+
+/// Selects files ranked at least as important as a threshold
+///
+/// Files pinned by a directive always stay. Scores come from ranking, so
+/// apply this to ranked repositories.
+#[derive(Debug, Clone, Copy)]
+pub struct ImportanceFilter {
+    min: f32,
+}
+
+impl ImportanceFilter {
+    /// Keep files with an importance of `min` (0.0 to 1.0) or more
+    pub fn at_least(min: f32) -> Self {
+        Self { min }
+    }
+}
+
+impl FileFilter for ImportanceFilter {
+    fn keep(&self, file: &RepoFile) -> bool {
+        file.pinned || file.importance >= self.min
+    }
+}
+
+impl Repository {
+    /// Drop the files `filter` does not keep, returning how many were dropped
+    ///
+    /// Metadata such as file and token totals is left as it was; see
+    /// [`Repository::refresh_metadata`].
+    pub fn retain_files(&mut self, filter: &dyn FileFilter) -> usize {
+        let before = self.files.len();
+        self.files.retain(|f| filter.keep(f));
+        before - self.files.len()
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::str_to_string)]
+mod tests {
+    use super::*;
+    use crate::types::GitFileChange;
+
+    fn file(path: &str, language: &str, size: u64, importance: f32) -> RepoFile {
+        let mut file = RepoFile::new(format!("/repo/{}", path), path);
+        file.language = Some(language.to_string());
+        file.size_bytes = size;
+        file.token_count.claude = (size / 4) as u32;
+        file.importance = importance;
+        file
+    }
+
+    fn repo() -> Repository {
+        let mut repo = Repository::new("test", "/repo");
+        repo.files = vec![
+            file("src/main.rs", "rust", 400, 0.9),
+            file("src/lib.ts", "typescript", 4_000, 0.6),
+            file("tests/main_test.rs", "rust", 800, 0.2),
+            file("README.md", "markdown", 100, 0.4),
+        ];
+        repo
+    }
+
+    fn kept(filter: &dyn FileFilter) -> Vec<String> {
+        let mut repo = repo();
+        repo.retain_files(filter);
+        repo.files.into_iter().map(|f| f.relative_path).collect()
+    }
+
+    #[test]
+    fn test_builtin_filters() {
+        let globs = GlobFilter::new(["src/**", "tests/**"], ["**/*_test.rs"]).unwrap();
+        assert_eq!(kept(&globs), vec!["src/main.rs", "src/lib.ts"]);
+        assert!(GlobFilter::new(["[oops"], [""; 0]).is_err());
+        assert!(GlobFilter::lenient(["[oops"], [""; 0]).is_empty());
+
+        let size = SizeFilter::new()
+            .with_min_bytes(200)
+            .with_max_tokens(500, TokenizerModel::Claude);
+        assert_eq!(kept(&size), vec!["src/main.rs", "tests/main_test.rs"]);
+
+        assert_eq!(
+            kept(&LanguageFilter::only(["RUST"])),
+            vec!["src/main.rs", "tests/main_test.rs"]
+        );
+        assert_eq!(kept(&LanguageFilter::except(["TypeScript", "markdown"])).len(), 2);
+        assert_eq!(
+            kept(&LanguageFilter::only(["rust", "typescript"]).and_except(["typescript"])).len(),
+            2
+        );
+
+        assert_eq!(kept(&ImportanceFilter::at_least(0.5)), vec!["src/main.rs", "src/lib.ts"]);
+    }
+
+    #[test]
+    fn test_git_status_filter() {
+        let mut repo = repo();
+        repo.files[1].git_change = Some(GitFileChange {
+            status: "A".to_string(),
+            additions: 3,
+            deletions: 0,
+            symbols: Vec::new(),
+        });
+        repo.files[2].git_change = Some(GitFileChange {
+            status: "M".to_string(),
+            additions: 1,
+            deletions: 1,
+            symbols: Vec::new(),
+        });
+
+        let mut added = repo.clone();
+        assert_eq!(added.retain_files(&GitStatusFilter::new([FileStatus::Added])), 3);
+        assert_eq!(added.files[0].relative_path, "src/lib.ts");
+        repo.retain_files(&GitStatusFilter::changed());
+        assert_eq!(repo.files.len(), 2);
+    }
+
+    #[test]
+    fn test_composition() {
+        let rust = LanguageFilter::only(["rust"]);
+        let important = ImportanceFilter::at_least(0.5);
+        assert_eq!(kept(&rust.clone().and(important)), vec!["src/main.rs"]);
+        assert_eq!(kept(&rust.clone().or(important)).len(), 3);
+        assert_eq!(kept(&rust.clone().not()), vec!["src/lib.ts", "README.md"]);
+
+        let stack = FilterStack::new()
+            .with(rust)
+            .with(|f: &RepoFile| !f.relative_path.starts_with("tests/"));
+        assert_eq!(stack.len(), 2);
+        assert_eq!(kept(&stack), vec!["src/main.rs"]);
+        assert_eq!(kept(&FilterStack::new()).len(), 4);
+    }
+}
@@ -0,0 +1,160 @@
+//! Packing one subsystem of a repository
+//!
+//! [`retain_focus`] narrows a repository to the files matching a set of focus
+//! targets plus the files within a few import hops of them. A target is
+//! matched as:
+//!
+//! - a glob when it contains `*`, `?` or `[` (`src/auth/**`)
+//! - a file path, or a directory holding files (`src/auth`)
+//! - otherwise, the name of a symbol defined in a file (`MyService`)
+
+use crate::dependencies::DependencyGraph;
+use crate::types::{Repository, SymbolKind};
+use glob::Pattern;
+use std::collections::HashSet;
+
+/// Default number of import hops kept around the focused files
+pub const DEFAULT_FOCUS_DEPTH: usize = 1;
+
+/// Files kept by [`retain_focus`]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FocusSelection {
+    /// Files matching a focus target
+    pub focused: Vec<String>,
+    /// Files within the depth limit of a focused file
+    pub related: Vec<String>,
+    /// Targets that matched no file
+    pub unmatched: Vec<String>,
+}
+
+/// Reduce a repository to the files matching `targets` and everything they
+/// import or are imported by, up to `depth` hops away
+///
+/// Call this before narrowing the file set further, since the dependency
+/// graph is built from the repository as given. Files without extracted
+/// symbols are parsed to find their imports and definitions. If no target
+/// matches, the repository is left empty.
+pub fn retain_focus(repo: &mut Repository, targets: &[String], depth: usize) -> FocusSelection {
+    crate::scan::parse_missing_symbols(repo);
+
+    let mut selection = FocusSelection::default();
+    let mut focused = HashSet::new();
+    for target in targets {
+        let matches = matching_files(repo, target);
+        if matches.is_empty() {
+            selection.unmatched.push(target.clone());
+        }
+        focused.extend(matches);
+    }
+
+    let graph = DependencyGraph::build(repo);
+    let kept = graph.neighborhood(focused.iter().map(String::as_str), depth);
+    for file in &repo.files {
+        let path = &file.relative_path;
+        if focused.contains(path) {
+            selection.focused.push(path.clone());
+        } else if kept.contains_key(path) {
+            selection.related.push(path.clone());
+        }
+    }
+
+    repo.files.retain(|f| kept.contains_key(&f.relative_path));
+    selection
+}
+
+/// Paths of the files a single focus target selects
+fn matching_files(repo: &Repository, target: &str) -> Vec<String> {
+    let target = target.trim().trim_start_matches("./");
+
+    if target.contains(['*', '?', '[']) {
+        let Ok(pattern) = Pattern::new(target) else {
+            return Vec::new();
+        };
+        return repo
+            .files
+            .iter()
+            .filter(|f| pattern.matches(&f.relative_path))
+            .map(|f| f.relative_path.clone())
+            .collect();
+    }
+
+    let dir = target.trim_end_matches('/');
+    let by_path: Vec<String> = repo
+        .files
+        .iter()
+        .filter(|f| {
+            let path = f.relative_path.as_str();
+            path == dir || (path.starts_with(dir) && path[dir.len()..].starts_with('/'))
+        })
+        .map(|f| f.relative_path.clone())
+        .collect();
+    if !by_path.is_empty() {
+        return by_path;
+    }
+
+    repo.files
+        .iter()
+        .filter(|f| {
+            f.symbols
+                .iter()
+                .any(|s| s.kind != SymbolKind::Import && s.name == target)
+        })
+        .map(|f| f.relative_path.clone())
+        .collect()
+}
+
+#[cfg(test)]
+#[allow(clippy::str_to_string)]
+mod tests {
+    use super::*;
+    use crate::types::RepoFile;
+
+    fn repo() -> Repository {
+        let mut repo = Repository::new("test", "/tmp/test");
+        for (path, content) in [
+            ("src/app.js", "import { Service } from './auth/service';\nnew Service();\n"),
+            ("src/auth/service.js", "import { hash } from '../util';\nexport class Service {}\n"),
+            ("src/util.js", "export function hash() { return 1; }\n"),
+            ("src/other.js", "export function unrelated() {}\n"),
+        ] {
+            let mut file = RepoFile::new(format!("/tmp/test/{}", path), path);
+            file.language = Some("javascript".to_string());
+            file.content = Some(content.to_string());
+            repo.files.push(file);
+        }
+        repo
+    }
+
+    fn kept(repo: &Repository) -> Vec<&str> {
+        repo.files
+            .iter()
+            .map(|f| f.relative_path.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn test_focus_on_directory() {
+        let mut repo = repo();
+        let selection = retain_focus(&mut repo, &["src/auth/".to_string()], DEFAULT_FOCUS_DEPTH);
+        assert_eq!(selection.focused, vec!["src/auth/service.js"]);
+        assert_eq!(selection.related, vec!["src/app.js", "src/util.js"]);
+        assert_eq!(kept(&repo), vec!["src/app.js", "src/auth/service.js", "src/util.js"]);
+    }
+
+    #[test]
+    fn test_focus_on_symbol_glob_and_depth() {
+        let mut repo = repo();
+        let selection = retain_focus(&mut repo, &["hash".to_string()], 0);
+        assert_eq!(selection.focused, vec!["src/util.js"]);
+        assert_eq!(kept(&repo), vec!["src/util.js"]);
+
+        let mut repo = self::repo();
+        retain_focus(&mut repo, &["src/u*.js".to_string()], 2);
+        assert_eq!(kept(&repo), vec!["src/app.js", "src/auth/service.js", "src/util.js"]);
+
+        let mut repo = self::repo();
+        let selection = retain_focus(&mut repo, &["Missing".to_string()], 1);
+        assert_eq!(selection.unmatched, vec!["Missing"]);
+        assert!(repo.files.is_empty());
+    }
+}
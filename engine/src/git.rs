@@ -2,15 +2,42 @@
 //!
 //! Provides integration with Git for:
 //! - Getting changed files between commits
-//! - Extracting commit history
+//! - Extracting commit history, streamed with a cap and an age limit
 //! - Blame information for file importance
-
+//! - Reducing a repository to the files changed since a base ref
+//! - Collecting the commits and diffs of a commit range for review
+//! - Naming the symbols a diff touches, by mapping changed lines onto parsed
+//!   symbol ranges
+//! - Reading the files of any revision from the object store, for packs of
+//!   past snapshots
+
+use crate::dependencies::DependencyGraph;
+use crate::output::{Locale, Section};
+use crate::parser::Language;
+use crate::types::{GitFileChange, Repository, Symbol, SymbolChange, SymbolChangeKind, SymbolKind};
+use serde_json::json;
+use std::collections::{HashMap, HashSet};
+use std::io::{BufRead, BufReader, Lines};
 use std::path::Path;
-use std::process::Command;
+use std::process::{Child, ChildStdout, Command, Stdio};
+
+/// `git log` format parsed by [`parse_log`] and [`LogStream`]
+const LOG_FORMAT: &str = "--format=%H%n%h%n%an%n%ae%n%ad%n%s%n---COMMIT---";
+
+/// Separator line ending each commit in [`LOG_FORMAT`]
+const COMMIT_SEPARATOR: &str = "---COMMIT---";
+
+/// Files per diff whose symbols are looked up; larger diffs are truncated
+const MAX_SYMBOL_DIFF_FILES: usize = 100;
 
 /// Git repository wrapper
+///
+/// Commit dates are rendered as ISO-8601 UTC (`2024-01-01T09:30:00Z`)
+/// regardless of the machine's timezone or locale, so output is reproducible.
+/// [`GitRepo::with_local_time`] switches to the local timezone with offset.
 pub struct GitRepo {
     path: String,
+    local_time: bool,
 }
 
 /// A git commit entry
@@ -33,6 +60,102 @@ pub struct ChangedFile {
     pub deletions: u32,
 }
 
+/// A changed file with its unified diff
+#[derive(Debug, Clone)]
+pub struct FileDiff {
+    pub file: ChangedFile,
+    /// Output of `git diff` for the file
+    pub patch: String,
+    /// Symbols the diff added, modified or removed
+    pub symbols: Vec<SymbolChange>,
+}
+
+/// Commits and file changes between two revisions
+#[derive(Debug, Clone)]
+pub struct RangeDiff {
+    /// Full hash the range starts from (exclusive)
+    pub from: String,
+    /// Full hash the range ends at
+    pub to: String,
+    /// Commits in the range, newest first
+    pub commits: Vec<Commit>,
+    /// Files changed between `from` and `to`
+    pub files: Vec<FileDiff>,
+}
+
+impl RangeDiff {
+    /// Render the commits and diffs as a section appended to a pack
+    pub fn render_localized(&self, locale: Locale) -> String {
+        let text = locale.strings();
+        let mut out = format!("\n\n<!-- {} -->\n", text.range_title);
+        out.push_str(
+            &text
+                .range_intro
+                .replace("{files}", &self.files.len().to_string())
+                .replace("{commits}", &self.commits.len().to_string())
+                .replace("{from}", short(&self.from))
+                .replace("{to}", short(&self.to)),
+        );
+        out.push('\n');
+        if !self.commits.is_empty() {
+            out.push('\n');
+            for commit in &self.commits {
+                out.push_str(&format!(
+                    "- {} {} ({})\n",
+                    commit.short_hash, commit.message, commit.author
+                ));
+            }
+        }
+        for diff in &self.files {
+            let file = &diff.file;
+            out.push_str(&format!(
+                "\n### {} ({} +{} -{})\n",
+                file.path,
+                file.status.code(),
+                file.additions,
+                file.deletions
+            ));
+            if !diff.symbols.is_empty() {
+                let symbols: Vec<String> = diff.symbols.iter().map(ToString::to_string).collect();
+                out.push_str(&format!("{}\n", symbols.join(", ")));
+            }
+            out.push_str("```diff\n");
+            out.push_str(&diff.patch);
+            if !diff.patch.ends_with('\n') {
+                out.push('\n');
+            }
+            out.push_str("```\n");
+        }
+        out
+    }
+
+    /// The commits and diffs as a report section
+    pub fn section(&self, locale: Locale) -> Section {
+        let commits: Vec<_> = self
+            .commits
+            .iter()
+            .map(|c| json!({ "hash": c.hash, "author": c.author, "message": c.message }))
+            .collect();
+        let files: Vec<_> = self
+            .files
+            .iter()
+            .map(|diff| {
+                let symbols: Vec<String> = diff.symbols.iter().map(ToString::to_string).collect();
+                json!({
+                    "path": diff.file.path,
+                    "status": diff.file.status.code(),
+                    "additions": diff.file.additions,
+                    "deletions": diff.file.deletions,
+                    "symbols": symbols,
+                    "patch": diff.patch,
+                })
+            })
+            .collect();
+        let data = json!({ "from": self.from, "to": self.to, "commits": commits, "files": files });
+        Section::new("range", self.render_localized(locale), &data)
+    }
+}
+
 /// File change status
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum FileStatus {
@@ -45,6 +168,18 @@ pub enum FileStatus {
 }
 
 impl FileStatus {
+    /// Single-letter status code as shown by `git status --short`
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::Added => "A",
+            Self::Modified => "M",
+            Self::Deleted => "D",
+            Self::Renamed => "R",
+            Self::Copied => "C",
+            Self::Unknown => "?",
+        }
+    }
+
     fn from_char(c: char) -> Self {
         match c {
             'A' => Self::Added,
@@ -94,7 +229,15 @@ impl GitRepo {
             return Err(GitError::NotAGitRepo);
         }
 
-        Ok(Self { path: path.to_string_lossy().to_string() })
+        Ok(Self { path: path.to_string_lossy().to_string(), local_time: false })
+    }
+
+    /// Render commit dates in the local timezone instead of UTC
+    ///
+    /// Blame dates only carry a Unix timestamp and are always UTC.
+    pub fn with_local_time(mut self, local_time: bool) -> Self {
+        self.local_time = local_time;
+        self
     }
 
     /// Check if path is a git repository
@@ -168,7 +311,9 @@ impl GitRepo {
         Ok(files)
     }
 
-    /// Get files changed in working tree
+    /// Get files changed in working tree, with lines added/removed against `HEAD`
+    ///
+    /// Untracked files count all their lines as added.
     pub fn status(&self) -> Result<Vec<ChangedFile>, GitError> {
         let output = self.run_git(&["status", "--porcelain"])?;
 
@@ -179,8 +324,19 @@ impl GitRepo {
                 continue;
             }
 
-            let status_char = line.chars().nth(1).unwrap_or(' ');
-            let path = line[3..].to_string();
+            // Prefer the worktree column, fall back to the index for staged changes
+            let mut chars = line.chars();
+            let index_char = chars.next().unwrap_or(' ');
+            let worktree_char = chars.next().unwrap_or(' ');
+            let status_char = if worktree_char == ' ' {
+                index_char
+            } else {
+                worktree_char
+            };
+
+            // Renames are shown as "old -> new"
+            let path = line[3..].rsplit(" -> ").next().unwrap_or("");
+            let path = path.trim_matches('"').to_owned();
 
             let status = match status_char {
                 '?' | 'A' => FileStatus::Added,
@@ -193,43 +349,333 @@ impl GitRepo {
             files.push(ChangedFile { path, status, additions: 0, deletions: 0 });
         }
 
+        // A repository without commits has no HEAD to diff against
+        let stats = self.diff_stats().unwrap_or_default();
+        for file in &mut files {
+            if let Some(&(additions, deletions)) = stats.get(&file.path) {
+                file.additions = additions;
+                file.deletions = deletions;
+            } else if file.status == FileStatus::Added {
+                file.additions = std::fs::read_to_string(Path::new(&self.path).join(&file.path))
+                    .map(|content| content.lines().count() as u32)
+                    .unwrap_or(0);
+            }
+        }
+
         Ok(files)
     }
 
+    /// Files changed on this branch relative to `base`, including uncommitted
+    /// and untracked files
+    ///
+    /// Changes are taken from the merge base of `base` and `HEAD`, as a pull
+    /// request against `base` would show them, so commits that landed on
+    /// `base` since the branch was created are not included.
+    pub fn changed_since(&self, base: &str) -> Result<Vec<ChangedFile>, GitError> {
+        let merge_base = self.run_git(&["merge-base", base, "HEAD"])?;
+        let merge_base = merge_base.trim();
+        let output = self.run_git(&["diff", "--name-status", "--no-renames", merge_base])?;
+        let stats = self.diff_stats_against(merge_base)?;
+
+        let mut files = Vec::new();
+        for line in output.lines() {
+            let Some((status, path)) = line.split_once('\t') else {
+                continue;
+            };
+            let status = status
+                .chars()
+                .next()
+                .map(FileStatus::from_char)
+                .unwrap_or(FileStatus::Unknown);
+            let (additions, deletions) = stats.get(path).copied().unwrap_or_default();
+            files.push(ChangedFile { path: path.to_owned(), status, additions, deletions });
+        }
+
+        let untracked = self.run_git(&["ls-files", "--others", "--exclude-standard"])?;
+        for path in untracked.lines().filter(|p| !p.is_empty()) {
+            let additions = std::fs::read_to_string(Path::new(&self.path).join(path))
+                .map(|content| content.lines().count() as u32)
+                .unwrap_or(0);
+            files.push(ChangedFile {
+                path: path.to_owned(),
+                status: FileStatus::Added,
+                additions,
+                deletions: 0,
+            });
+        }
+
+        Ok(files)
+    }
+
+    /// Lines added and removed per path in the working tree relative to `HEAD`
+    fn diff_stats(&self) -> Result<HashMap<String, (u32, u32)>, GitError> {
+        self.diff_stats_against("HEAD")
+    }
+
+    /// Lines added and removed per path in the working tree relative to `rev`
+    fn diff_stats_against(&self, rev: &str) -> Result<HashMap<String, (u32, u32)>, GitError> {
+        let output = self.run_git(&["diff", rev, "--numstat", "--no-renames"])?;
+        Ok(parse_numstat(&output))
+    }
+
+    /// Lines added and removed per path between two revisions
+    fn diff_stats_between(
+        &self,
+        from: &str,
+        to: &str,
+    ) -> Result<HashMap<String, (u32, u32)>, GitError> {
+        let output = self.run_git(&["diff", from, to, "--numstat", "--no-renames"])?;
+        Ok(parse_numstat(&output))
+    }
+
+    /// Full hash of a revision
+    fn resolve(&self, rev: &str) -> Result<String, GitError> {
+        let output = self.run_git(&["rev-parse", "--verify", &format!("{}^{{commit}}", rev)])?;
+        Ok(output.trim().to_owned())
+    }
+
     /// Get recent commits
     pub fn log(&self, count: usize) -> Result<Vec<Commit>, GitError> {
-        let output = self.run_git(&[
-            "log",
-            &format!("-{}", count),
-            "--format=%H%n%h%n%an%n%ae%n%ad%n%s%n---COMMIT---",
-            "--date=short",
-        ])?;
+        self.log_stream(count, None)?.collect()
+    }
 
-        let mut commits = Vec::new();
-        let mut lines = output.lines().peekable();
+    /// Stream up to `max_count` recent commits, newest first
+    ///
+    /// Commits are parsed as `git log` produces them, so memory stays flat
+    /// however long the history is, and the process is stopped once the
+    /// stream is dropped. `since` limits the history by age: a shorthand such
+    /// as `90d`, `12w`, `6m` or `1y`, or any date `git log --since` accepts.
+    pub fn log_stream(&self, max_count: usize, since: Option<&str>) -> Result<LogStream, GitError> {
+        let mut args = vec![
+            "log".to_owned(),
+            format!("--max-count={}", max_count),
+            LOG_FORMAT.to_owned(),
+            self.date_arg().to_owned(),
+        ];
+        if let Some(since) = since {
+            args.push(format!("--since={}", since_arg(since)));
+        }
+
+        let mut child = self
+            .git_command(&args)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| GitError::CommandFailed(e.to_string()))?;
+        let stdout = child
+            .stdout
+            .take()
+            .ok_or_else(|| GitError::CommandFailed("no output from git log".to_owned()))?;
+
+        Ok(LogStream { child, lines: BufReader::new(stdout).lines(), remaining: max_count })
+    }
 
-        while lines.peek().is_some() {
-            let hash = lines.next().unwrap_or("").to_owned();
-            if hash.is_empty() {
+    /// Commits and per-file diffs of a commit range
+    ///
+    /// `range` is `FROM..TO`, `FROM...TO` (changes since the merge base, as a
+    /// pull request shows them) or a single revision meaning `REV..HEAD`.
+    pub fn range_diff(&self, range: &str) -> Result<RangeDiff, GitError> {
+        let (from, to) = if let Some((base, head)) = range.split_once("...") {
+            let head = if head.is_empty() { "HEAD" } else { head };
+            let base = if base.is_empty() { "HEAD" } else { base };
+            (self.run_git(&["merge-base", base, head])?, head.to_owned())
+        } else if let Some((from, to)) = range.split_once("..") {
+            let from = if from.is_empty() { "HEAD" } else { from };
+            let to = if to.is_empty() { "HEAD" } else { to };
+            (from.to_owned(), to.to_owned())
+        } else {
+            (range.to_owned(), "HEAD".to_owned())
+        };
+        let from = self.resolve(from.trim())?;
+        let to = self.resolve(&to)?;
+
+        let output =
+            self.run_git(&["log", LOG_FORMAT, self.date_arg(), &format!("{}..{}", from, to)])?;
+        let commits = parse_log(&output);
+
+        let output = self.run_git(&["diff", "--name-status", "--no-renames", &from, &to])?;
+        let stats = self.diff_stats_between(&from, &to)?;
+        let mut files = Vec::new();
+        for line in output.lines() {
+            let Some((status, path)) = line.split_once('\t') else {
                 continue;
-            }
+            };
+            let status = status
+                .chars()
+                .next()
+                .map(FileStatus::from_char)
+                .unwrap_or(FileStatus::Unknown);
+            let (additions, deletions) = stats.get(path).copied().unwrap_or_default();
+            let patch = self.diff_content(&from, &to, path)?;
+            let symbols = parse_patch(&patch)
+                .iter()
+                .flat_map(|file| self.patch_symbols(file, &from, Some(&to)))
+                .collect();
+            files.push(FileDiff {
+                file: ChangedFile { path: path.to_owned(), status, additions, deletions },
+                patch,
+                symbols,
+            });
+        }
 
-            let short_hash = lines.next().unwrap_or("").to_owned();
-            let author = lines.next().unwrap_or("").to_owned();
-            let email = lines.next().unwrap_or("").to_owned();
-            let date = lines.next().unwrap_or("").to_owned();
-            let message = lines.next().unwrap_or("").to_owned();
+        Ok(RangeDiff { from, to, commits, files })
+    }
 
-            // Skip separator
-            while lines.peek().map(|l| *l != "---COMMIT---").unwrap_or(false) {
-                lines.next();
-            }
-            lines.next(); // Skip the separator
+    /// Content of a file at a revision
+    pub fn file_at(&self, rev: &str, path: &str) -> Result<String, GitError> {
+        self.run_git(&["show", &format!("{}:{}", rev, path)])
+    }
 
-            commits.push(Commit { hash, short_hash, author, email, date, message });
-        }
+    /// The commit a revision names
+    pub fn commit(&self, rev: &str) -> Result<Commit, GitError> {
+        let output = self.run_git(&["log", "-1", LOG_FORMAT, self.date_arg(), rev])?;
+        parse_log(&output)
+            .into_iter()
+            .next()
+            .ok_or_else(|| GitError::ParseError(format!("No commit for {}", rev)))
+    }
 
-        Ok(commits)
+    /// Commits after `from` up to and including `to` that touch `paths`,
+    /// oldest first
+    ///
+    /// Only commits descending from `from` count, as in `git bisect`. Empty
+    /// `paths` means the whole tree.
+    pub fn commits_between(
+        &self,
+        from: &str,
+        to: &str,
+        paths: &[String],
+    ) -> Result<Vec<Commit>, GitError> {
+        let range = format!("{}..{}", from, to);
+        let mut args =
+            vec!["log", "--reverse", "--ancestry-path", LOG_FORMAT, self.date_arg(), &range, "--"];
+        args.extend(paths.iter().map(String::as_str));
+        Ok(parse_log(&self.run_git(&args)?))
+    }
+
+    /// Files under `paths` at a revision with their sizes, without touching
+    /// the working tree
+    ///
+    /// Submodules are left out, as they have no contents at the revision.
+    pub fn ls_tree(&self, rev: &str, paths: &[String]) -> Result<Vec<TreeEntry>, GitError> {
+        let mut args = vec!["ls-tree", "-r", "-z", "-l", rev, "--"];
+        args.extend(paths.iter().map(String::as_str));
+        let output = self.run_git(&args)?;
+        output
+            .split('\0')
+            .filter(|entry| !entry.is_empty())
+            .filter_map(|entry| {
+                // `<mode> <type> <object> <size>\t<path>`
+                let (info, path) = entry.split_once('\t')?;
+                let mut fields = info.split_whitespace();
+                (fields.nth(1)? == "blob").then(|| {
+                    let size = fields.nth(1).and_then(|s| s.parse().ok()).ok_or_else(|| {
+                        GitError::ParseError(format!("bad ls-tree entry: {}", entry))
+                    })?;
+                    Ok(TreeEntry { path: path.to_owned(), size })
+                })
+            })
+            .collect()
+    }
+
+    /// Raw contents of files at a revision, streamed from the object store by
+    /// one `git cat-file --batch` process
+    ///
+    /// Paths missing at the revision are left out. Only one blob is held at a
+    /// time, so filter out large files before asking for them.
+    pub fn read_blobs(&self, rev: &str, paths: Vec<String>) -> Result<BlobStream, GitError> {
+        use std::io::Write;
+
+        let mut child = self
+            .git_command(&["cat-file", "--batch"])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(|e| GitError::CommandFailed(e.to_string()))?;
+        let mut stdin = child
+            .stdin
+            .take()
+            .ok_or_else(|| GitError::CommandFailed("no input to git cat-file".to_owned()))?;
+        let requests: String = paths.iter().map(|p| format!("{}:{}\n", rev, p)).collect();
+        // Write from another thread so a full stdout pipe can't deadlock us
+        let writer = std::thread::spawn(move || stdin.write_all(requests.as_bytes()));
+
+        let stdout = child
+            .stdout
+            .take()
+            .ok_or_else(|| GitError::CommandFailed("no output from git cat-file".to_owned()))?;
+        Ok(BlobStream {
+            child,
+            reader: BufReader::new(stdout),
+            paths: paths.into_iter(),
+            writer: Some(writer),
+            failed: false,
+        })
+    }
+
+    /// Symbols a commit added, modified or removed
+    pub fn commit_symbol_changes(&self, hash: &str) -> Result<Vec<SymbolChange>, GitError> {
+        let patch = self.run_git(&[
+            "diff-tree",
+            "-p",
+            "-M",
+            "--root",
+            "--no-commit-id",
+            "--no-color",
+            "--no-ext-diff",
+            hash,
+        ])?;
+        let parent = format!("{}^", hash);
+        Ok(parse_patch(&patch)
+            .iter()
+            .take(MAX_SYMBOL_DIFF_FILES)
+            .flat_map(|file| self.patch_symbols(file, &parent, Some(hash)))
+            .collect())
+    }
+
+    /// Symbols added, modified or removed in the working tree relative to `HEAD`
+    ///
+    /// Untracked files are not part of the diff and contribute no symbols.
+    pub fn worktree_symbol_changes(&self) -> Result<Vec<SymbolChange>, GitError> {
+        let patch = self.run_git(&["diff", "HEAD", "-M", "--no-color", "--no-ext-diff"])?;
+        Ok(parse_patch(&patch)
+            .iter()
+            .take(MAX_SYMBOL_DIFF_FILES)
+            .flat_map(|file| self.patch_symbols(file, "HEAD", None))
+            .collect())
+    }
+
+    /// Symbols one file's patch touches, parsing the file at `old_rev` and at
+    /// `new_rev` (the working tree if `None`)
+    fn patch_symbols(
+        &self,
+        file: &FilePatch,
+        old_rev: &str,
+        new_rev: Option<&str>,
+    ) -> Vec<SymbolChange> {
+        let path = file.new_path.as_deref().or(file.old_path.as_deref());
+        let Some(path) = path.filter(|p| is_parseable(p)) else {
+            return Vec::new();
+        };
+
+        let old_symbols = file
+            .old_path
+            .as_deref()
+            .and_then(|old| self.file_at(old_rev, old).ok())
+            .map(|content| crate::scan::parse_symbols(&content, Path::new(path)))
+            .unwrap_or_default();
+        let new_symbols = file
+            .new_path
+            .as_deref()
+            .and_then(|new| match new_rev {
+                Some(rev) => self.file_at(rev, new).ok(),
+                None => std::fs::read_to_string(Path::new(&self.path).join(new)).ok(),
+            })
+            .map(|content| crate::scan::parse_symbols(&content, Path::new(path)))
+            .unwrap_or_default();
+
+        symbol_changes(path, &file.removed, &file.added, &old_symbols, &new_symbols)
     }
 
     /// Get commits that modified a specific file
@@ -237,15 +683,15 @@ impl GitRepo {
         let output = self.run_git(&[
             "log",
             &format!("-{}", count),
-            "--format=%H%n%h%n%an%n%ae%n%ad%n%s%n---COMMIT---",
-            "--date=short",
+            LOG_FORMAT,
+            self.date_arg(),
             "--follow",
             "--",
             path,
         ])?;
 
         let mut commits = Vec::new();
-        let commit_blocks: Vec<&str> = output.split("---COMMIT---").collect();
+        let commit_blocks: Vec<&str> = output.split(COMMIT_SEPARATOR).collect();
 
         for block in commit_blocks {
             let lines: Vec<&str> = block.lines().filter(|l| !l.is_empty()).collect();
@@ -347,11 +793,30 @@ impl GitRepo {
         Ok(output.lines().count() as u32)
     }
 
+    /// `--date` argument matching the configured timezone
+    fn date_arg(&self) -> &'static str {
+        if self.local_time {
+            "--date=iso-strict-local"
+        } else {
+            // Evaluated with TZ=UTC (see run_git), so the literal Z is accurate
+            "--date=format-local:%Y-%m-%dT%H:%M:%SZ"
+        }
+    }
+
+    /// A git command in the repository, with the timezone set up for dates
+    fn git_command<S: AsRef<std::ffi::OsStr>>(&self, args: &[S]) -> Command {
+        let mut command = Command::new("git");
+        command.current_dir(&self.path).args(args);
+        if !self.local_time {
+            command.env("TZ", "UTC");
+        }
+        command
+    }
+
     /// Run a git command and return output
     fn run_git(&self, args: &[&str]) -> Result<String, GitError> {
-        let output = Command::new("git")
-            .current_dir(&self.path)
-            .args(args)
+        let output = self
+            .git_command(args)
             .output()
             .map_err(|e| GitError::CommandFailed(e.to_string()))?;
 
@@ -364,7 +829,514 @@ impl GitRepo {
     }
 }
 
-/// Format Unix timestamp as YYYY-MM-DD
+/// A file in a tree, see [`GitRepo::ls_tree`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TreeEntry {
+    /// Path relative to the repository root
+    pub path: String,
+    /// Size of the blob in bytes
+    pub size: u64,
+}
+
+/// Blobs streamed from a running `git cat-file`, see [`GitRepo::read_blobs`]
+pub struct BlobStream {
+    child: Child,
+    reader: BufReader<ChildStdout>,
+    /// Paths still to be read, in the order they were requested
+    paths: std::vec::IntoIter<String>,
+    writer: Option<std::thread::JoinHandle<std::io::Result<()>>>,
+    /// Set once a read failed, leaving git to be stopped on drop
+    failed: bool,
+}
+
+impl BlobStream {
+    /// Wait for git to exit once every blob is read
+    fn finish(&mut self) -> Result<(), GitError> {
+        if let Some(writer) = self.writer.take() {
+            writer
+                .join()
+                .map_err(|_| GitError::CommandFailed("git cat-file writer panicked".to_owned()))?
+                .map_err(|e| GitError::CommandFailed(e.to_string()))?;
+        }
+        self.child
+            .wait()
+            .map_err(|e| GitError::CommandFailed(e.to_string()))?;
+        Ok(())
+    }
+
+    /// The next blob, `None` for a path missing at the revision
+    fn read_next(&mut self, path: String) -> Result<Option<(String, Vec<u8>)>, GitError> {
+        use std::io::Read;
+
+        let mut header = String::new();
+        self.reader
+            .read_line(&mut header)
+            .map_err(|e| GitError::CommandFailed(e.to_string()))?;
+        // `<sha> blob <size>`, or `<object> missing`
+        let mut fields = header.split_whitespace();
+        let (Some(_), Some(kind), Some(size)) = (fields.next(), fields.next(), fields.next())
+        else {
+            return Ok(None);
+        };
+        let size: usize = size
+            .parse()
+            .map_err(|_| GitError::ParseError(format!("bad cat-file header: {}", header)))?;
+        let mut content = vec![0; size + 1];
+        self.reader
+            .read_exact(&mut content)
+            .map_err(|e| GitError::CommandFailed(e.to_string()))?;
+        content.truncate(size);
+        Ok((kind == "blob").then_some((path, content)))
+    }
+}
+
+impl Iterator for BlobStream {
+    type Item = Result<(String, Vec<u8>), GitError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+        loop {
+            let Some(path) = self.paths.next() else {
+                return self.finish().err().map(Err);
+            };
+            match self.read_next(path) {
+                Ok(Some(blob)) => return Some(Ok(blob)),
+                Ok(None) => continue,
+                Err(e) => {
+                    self.failed = true;
+                    return Some(Err(e));
+                },
+            }
+        }
+    }
+}
+
+impl Drop for BlobStream {
+    fn drop(&mut self) {
+        // Stop git if the stream was abandoned early
+        self.child.kill().ok();
+        self.child.wait().ok();
+    }
+}
+
+/// Commits streamed from a running `git log`, see [`GitRepo::log_stream`]
+pub struct LogStream {
+    child: Child,
+    lines: Lines<BufReader<ChildStdout>>,
+    /// Commits still to be read before the cap is reached
+    remaining: usize,
+}
+
+impl LogStream {
+    /// Wait for git to exit once its output is read, reporting a failure
+    fn finish(&mut self) -> Result<(), GitError> {
+        let mut stderr = String::new();
+        if let Some(pipe) = self.child.stderr.as_mut() {
+            std::io::Read::read_to_string(pipe, &mut stderr).ok();
+        }
+        let status = self
+            .child
+            .wait()
+            .map_err(|e| GitError::CommandFailed(e.to_string()))?;
+        if status.success() {
+            Ok(())
+        } else {
+            Err(GitError::CommandFailed(stderr))
+        }
+    }
+
+    fn next_line(&mut self) -> Result<Option<String>, GitError> {
+        self.lines
+            .next()
+            .transpose()
+            .map_err(|e| GitError::ParseError(e.to_string()))
+    }
+}
+
+impl Iterator for LogStream {
+    type Item = Result<Commit, GitError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+
+        let mut fields = Vec::with_capacity(6);
+        loop {
+            match self.next_line() {
+                Ok(Some(line)) if line == COMMIT_SEPARATOR => break,
+                // Anything past the six fields is ignored
+                Ok(Some(line)) => {
+                    if fields.len() < 6 && !(fields.is_empty() && line.is_empty()) {
+                        fields.push(line);
+                    }
+                },
+                Ok(None) if fields.is_empty() => {
+                    self.remaining = 0;
+                    return self.finish().err().map(Err);
+                },
+                Ok(None) => break,
+                Err(e) => return Some(Err(e)),
+            }
+        }
+        self.remaining -= 1;
+
+        fields.resize(6, String::new());
+        let mut fields = fields.into_iter();
+        let mut field = || fields.next().unwrap_or_default();
+        Some(Ok(Commit {
+            hash: field(),
+            short_hash: field(),
+            author: field(),
+            email: field(),
+            date: field(),
+            message: field(),
+        }))
+    }
+}
+
+impl Drop for LogStream {
+    fn drop(&mut self) {
+        // Stop git if the stream was abandoned early
+        self.child.kill().ok();
+        self.child.wait().ok();
+    }
+}
+
+/// `git log --since` value for an age shorthand (`90d`, `12w`, `6m`, `1y`),
+/// or `since` as it is
+fn since_arg(since: &str) -> String {
+    let since = since.trim();
+    let unit = match since.chars().last() {
+        Some('d') => "days",
+        Some('w') => "weeks",
+        Some('m') => "months",
+        Some('y') => "years",
+        _ => return since.to_owned(),
+    };
+    match since[..since.len() - 1].parse::<u32>() {
+        Ok(n) => format!("{} {} ago", n, unit),
+        Err(_) => since.to_owned(),
+    }
+}
+
+/// Attach working-tree changes to the matching files of a repository
+///
+/// Files without changes are left untouched; deleted files have no entry to
+/// annotate. Returns the number of annotated files.
+pub fn annotate_changes(repo: &mut Repository, changes: &[ChangedFile]) -> usize {
+    let by_path: HashMap<&str, &ChangedFile> =
+        changes.iter().map(|c| (c.path.as_str(), c)).collect();
+
+    let mut annotated = 0;
+    for file in &mut repo.files {
+        if let Some(change) = by_path.get(file.relative_path.as_str()) {
+            file.git_change = Some(GitFileChange {
+                status: change.status.code().to_owned(),
+                additions: change.additions,
+                deletions: change.deletions,
+                symbols: Vec::new(),
+            });
+            annotated += 1;
+        }
+    }
+    annotated
+}
+
+/// Attach symbol changes to the `git_change` of the files they belong to
+///
+/// Call after [`annotate_changes`]; files without a change entry are skipped.
+pub fn annotate_symbol_changes(repo: &mut Repository, symbols: &[SymbolChange]) {
+    for file in &mut repo.files {
+        if let Some(change) = &mut file.git_change {
+            change.symbols.extend(
+                symbols
+                    .iter()
+                    .filter(|s| s.path == file.relative_path)
+                    .cloned(),
+            );
+        }
+    }
+}
+
+/// Symbols touched by a diff of one file
+///
+/// `removed` and `added` are the changed line numbers on the old and new
+/// side. A symbol is touched when its line range contains a changed line;
+/// only the innermost touched symbols are reported, except that members of
+/// an added or removed container are folded into it. Names are qualified
+/// with their parent (`AuthService.refresh_token`) and matched across the two
+/// sides to tell modifications from additions and removals.
+pub fn symbol_changes(
+    path: &str,
+    removed: &[u32],
+    added: &[u32],
+    old_symbols: &[Symbol],
+    new_symbols: &[Symbol],
+) -> Vec<SymbolChange> {
+    let old_names: HashSet<String> = old_symbols.iter().map(qualified_name).collect();
+    let new_names: HashSet<String> = new_symbols.iter().map(qualified_name).collect();
+
+    let mut changes = Vec::new();
+    let mut seen = HashSet::new();
+    let sides = [
+        (new_symbols, added, &old_names, SymbolChangeKind::Added),
+        (old_symbols, removed, &new_names, SymbolChangeKind::Removed),
+    ];
+    for (symbols, lines, other_side, missing) in sides {
+        // Some grammars report a method both bare and with its parent
+        let is_duplicate = |s: &Symbol| {
+            s.parent.is_none()
+                && symbols.iter().any(|other| {
+                    other.parent.is_some()
+                        && other.name == s.name
+                        && (other.start_line, other.end_line) == (s.start_line, s.end_line)
+                })
+        };
+        let touched: Vec<(&Symbol, SymbolChangeKind)> = symbols
+            .iter()
+            .filter(|s| s.kind != SymbolKind::Import && !is_duplicate(s))
+            .filter(|s| {
+                lines
+                    .iter()
+                    .any(|l| (s.start_line..=s.end_line).contains(l))
+            })
+            .map(|s| {
+                let change = if other_side.contains(&qualified_name(s)) {
+                    SymbolChangeKind::Modified
+                } else {
+                    missing
+                };
+                (s, change)
+            })
+            .collect();
+
+        let contains = |outer: &Symbol, inner: &Symbol| {
+            !std::ptr::eq(outer, inner)
+                && outer.start_line <= inner.start_line
+                && inner.end_line <= outer.end_line
+        };
+        for &(symbol, change) in &touched {
+            let folded = touched
+                .iter()
+                .any(|&(outer, outer_change)| outer_change == missing && contains(outer, symbol));
+            let has_inner =
+                change != missing && touched.iter().any(|&(inner, _)| contains(symbol, inner));
+            let name = qualified_name(symbol);
+            if !folded && !has_inner && seen.insert(name.clone()) {
+                changes.push(SymbolChange { path: path.to_owned(), name, change });
+            }
+        }
+    }
+    changes
+}
+
+/// Symbol name qualified with its parent, if any
+fn qualified_name(symbol: &Symbol) -> String {
+    match &symbol.parent {
+        Some(parent) => format!("{}.{}", parent, symbol.name),
+        None => symbol.name.clone(),
+    }
+}
+
+/// Whether symbols can be extracted from a path
+fn is_parseable(path: &str) -> bool {
+    Path::new(path)
+        .extension()
+        .and_then(|e| e.to_str())
+        .and_then(Language::from_extension)
+        .is_some()
+}
+
+/// One file's section of a unified diff
+#[derive(Debug, Default)]
+struct FilePatch {
+    /// Path before the change, `None` for added files
+    old_path: Option<String>,
+    /// Path after the change, `None` for deleted files
+    new_path: Option<String>,
+    /// Removed line numbers, in the old file
+    removed: Vec<u32>,
+    /// Added line numbers, in the new file
+    added: Vec<u32>,
+}
+
+/// Split unified diff output into per-file changed line numbers
+fn parse_patch(patch: &str) -> Vec<FilePatch> {
+    let mut files: Vec<FilePatch> = Vec::new();
+    // Lines left in the current hunk, on the old and new side
+    let (mut old_left, mut new_left) = (0u32, 0u32);
+    let (mut old_line, mut new_line) = (0u32, 0u32);
+
+    for line in patch.lines() {
+        if old_left > 0 || new_left > 0 {
+            let Some(file) = files.last_mut() else {
+                break;
+            };
+            match line.as_bytes().first() {
+                Some(b'-') => {
+                    file.removed.push(old_line);
+                    old_line += 1;
+                    old_left = old_left.saturating_sub(1);
+                },
+                Some(b'+') => {
+                    file.added.push(new_line);
+                    new_line += 1;
+                    new_left = new_left.saturating_sub(1);
+                },
+                // "\ No newline at end of file"
+                Some(b'\\') => {},
+                _ => {
+                    old_line += 1;
+                    new_line += 1;
+                    old_left = old_left.saturating_sub(1);
+                    new_left = new_left.saturating_sub(1);
+                },
+            }
+        } else if line.starts_with("diff --git ") {
+            files.push(FilePatch::default());
+        } else if let Some(path) = line.strip_prefix("--- ") {
+            if let Some(file) = files.last_mut() {
+                file.old_path = path.strip_prefix("a/").map(str::to_owned);
+            }
+        } else if let Some(path) = line.strip_prefix("+++ ") {
+            if let Some(file) = files.last_mut() {
+                file.new_path = path.strip_prefix("b/").map(str::to_owned);
+            }
+        } else if let Some(header) = line.strip_prefix("@@ -") {
+            // "@@ -old_start[,old_count] +new_start[,new_count] @@"
+            let mut ranges = header.split(' ');
+            let old = ranges.next().map(parse_range);
+            let new = ranges
+                .next()
+                .and_then(|r| r.strip_prefix('+'))
+                .map(parse_range);
+            if let (Some((o_start, o_count)), Some((n_start, n_count))) = (old, new) {
+                (old_line, old_left, new_line, new_left) = (o_start, o_count, n_start, n_count);
+            }
+        }
+    }
+    files
+}
+
+/// Parse a hunk range such as `12,3` or `12` (a count of one)
+fn parse_range(range: &str) -> (u32, u32) {
+    let (start, count) = range.split_once(',').unwrap_or((range, "1"));
+    (start.parse().unwrap_or(0), count.parse().unwrap_or(0))
+}
+
+/// Files kept by [`retain_changes`]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ChangeSelection {
+    /// Changed files present in the repository
+    pub changed: Vec<String>,
+    /// Unchanged files that import, or are imported by, a changed file
+    pub neighbors: Vec<String>,
+}
+
+impl ChangeSelection {
+    /// Whether `path` is one of the changed files
+    pub fn is_changed(&self, path: &str) -> bool {
+        self.changed.iter().any(|p| p == path)
+    }
+}
+
+/// Reduce a repository to the changed files and their direct dependency
+/// neighborhood, annotating the changed files
+///
+/// The dependency graph is built from the repository as given, so call this
+/// before narrowing the file set further. Files without extracted symbols are
+/// parsed to find their imports.
+pub fn retain_changes(repo: &mut Repository, changes: &[ChangedFile]) -> ChangeSelection {
+    crate::scan::parse_missing_symbols(repo);
+    let graph = DependencyGraph::build(repo);
+    let changed: HashSet<&str> = changes
+        .iter()
+        .filter(|c| c.status != FileStatus::Deleted)
+        .map(|c| c.path.as_str())
+        .collect();
+
+    let mut selection = ChangeSelection::default();
+    let mut neighbors = HashSet::new();
+    for file in &repo.files {
+        let path = file.relative_path.as_str();
+        if changed.contains(path) {
+            selection.changed.push(path.to_owned());
+            neighbors.extend(graph.get_imports(path));
+            neighbors.extend(graph.get_importers(path));
+        }
+    }
+    selection.neighbors = repo
+        .files
+        .iter()
+        .map(|f| f.relative_path.as_str())
+        .filter(|path| neighbors.contains(path) && !changed.contains(path))
+        .map(str::to_owned)
+        .collect();
+
+    annotate_changes(repo, changes);
+    repo.files.retain(|f| {
+        changed.contains(f.relative_path.as_str()) || neighbors.contains(f.relative_path.as_str())
+    });
+    selection
+}
+
+/// Lines added and removed per path from `git diff --numstat` output
+fn parse_numstat(output: &str) -> HashMap<String, (u32, u32)> {
+    let mut stats = HashMap::new();
+    for line in output.lines() {
+        let mut parts = line.splitn(3, '\t');
+        let (Some(added), Some(removed), Some(path)) = (parts.next(), parts.next(), parts.next())
+        else {
+            continue;
+        };
+        // Binary files report "-" for both counts
+        stats.insert(path.to_owned(), (added.parse().unwrap_or(0), removed.parse().unwrap_or(0)));
+    }
+    stats
+}
+
+/// Parse `git log` output in [`LOG_FORMAT`]
+fn parse_log(output: &str) -> Vec<Commit> {
+    let mut commits = Vec::new();
+    let mut lines = output.lines().peekable();
+
+    while lines.peek().is_some() {
+        let hash = lines.next().unwrap_or("").to_owned();
+        if hash.is_empty() {
+            continue;
+        }
+
+        let short_hash = lines.next().unwrap_or("").to_owned();
+        let author = lines.next().unwrap_or("").to_owned();
+        let email = lines.next().unwrap_or("").to_owned();
+        let date = lines.next().unwrap_or("").to_owned();
+        let message = lines.next().unwrap_or("").to_owned();
+
+        // Skip separator
+        while lines
+            .peek()
+            .map(|l| *l != COMMIT_SEPARATOR)
+            .unwrap_or(false)
+        {
+            lines.next();
+        }
+        lines.next(); // Skip the separator
+
+        commits.push(Commit { hash, short_hash, author, email, date, message });
+    }
+
+    commits
+}
+
+/// First 7 characters of a commit hash
+fn short(hash: &str) -> &str {
+    &hash[..hash.len().min(7)]
+}
+
+/// Format Unix timestamp as ISO-8601 UTC (YYYY-MM-DDTHH:MM:SSZ)
 fn format_timestamp(ts: i64) -> String {
     use std::time::{Duration, UNIX_EPOCH};
 
@@ -403,8 +1375,17 @@ fn format_timestamp(ts: i64) -> String {
     }
 
     let day = remaining_days + 1;
-
-    format!("{:04}-{:02}-{:02}", year, month, day)
+    let secs_of_day = ts.rem_euclid(secs_per_day);
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        secs_of_day / 3600,
+        secs_of_day % 3600 / 60,
+        secs_of_day % 60
+    )
 }
 
 fn is_leap_year(year: i64) -> bool {
@@ -499,11 +1480,223 @@ mod tests {
         assert!(files.contains(&"test.txt".to_string()));
     }
 
+    #[test]
+    fn test_status_diff_stats() {
+        let temp = init_test_repo();
+        std::fs::write(temp.path().join("test.txt"), "hello\nworld\n").unwrap();
+        std::fs::write(temp.path().join("new.txt"), "a\nb\nc\n").unwrap();
+
+        let git = GitRepo::open(temp.path()).unwrap();
+        let changes = git.status().unwrap();
+
+        let modified = changes.iter().find(|c| c.path == "test.txt").unwrap();
+        assert_eq!(modified.status, FileStatus::Modified);
+        assert_eq!((modified.additions, modified.deletions), (2, 1));
+        let added = changes.iter().find(|c| c.path == "new.txt").unwrap();
+        assert_eq!(added.status, FileStatus::Added);
+        assert_eq!(added.additions, 3);
+
+        let mut repo = Repository::new("test", temp.path());
+        repo.files
+            .push(crate::types::RepoFile::new(temp.path().join("test.txt"), "test.txt"));
+        repo.files
+            .push(crate::types::RepoFile::new(temp.path().join("other.txt"), "other.txt"));
+        assert_eq!(annotate_changes(&mut repo, &changes), 1);
+        assert_eq!(repo.files[0].git_change.as_ref().unwrap().summary(), "M +2 -1");
+        assert!(repo.files[1].git_change.is_none());
+    }
+
+    #[test]
+    fn test_changed_since_and_retain_changes() {
+        let temp = init_test_repo();
+        let git = |args: &[&str]| {
+            Command::new("git")
+                .current_dir(temp.path())
+                .args(args)
+                .output()
+                .unwrap()
+        };
+        std::fs::write(temp.path().join("util.js"), "export const helper = () => 1;\n").unwrap();
+        std::fs::write(temp.path().join("app.js"), "import { helper } from './util';\n").unwrap();
+        std::fs::write(temp.path().join("other.js"), "export const other = 0;\n").unwrap();
+        git(&["add", "."]);
+        git(&["commit", "-m", "Add modules"]);
+        git(&["checkout", "-b", "feature"]);
+        std::fs::write(temp.path().join("util.js"), "export const helper = () => 2;\n").unwrap();
+        git(&["commit", "-am", "Change helper"]);
+        std::fs::write(temp.path().join("new.js"), "export const x = 1;\n").unwrap();
+
+        let repo_git = GitRepo::open(temp.path()).unwrap();
+        let mut changes = repo_git.changed_since("HEAD~1").unwrap();
+        changes.sort_by(|a, b| a.path.cmp(&b.path));
+        assert_eq!(changes.len(), 2);
+        assert_eq!((changes[0].path.as_str(), changes[0].status), ("new.js", FileStatus::Added));
+        assert_eq!(changes[1].path, "util.js");
+        assert_eq!((changes[1].additions, changes[1].deletions), (1, 1));
+
+        let mut repo = Repository::new("test", temp.path());
+        for name in ["app.js", "new.js", "other.js", "util.js"] {
+            let mut file = crate::types::RepoFile::new(temp.path().join(name), name);
+            file.language = Some("javascript".to_string());
+            file.content = Some(std::fs::read_to_string(temp.path().join(name)).unwrap());
+            repo.files.push(file);
+        }
+        let selection = retain_changes(&mut repo, &changes);
+        assert_eq!(selection.changed, vec!["new.js", "util.js"]);
+        assert_eq!(selection.neighbors, vec!["app.js"]);
+        let kept: Vec<_> = repo
+            .files
+            .iter()
+            .map(|f| f.relative_path.as_str())
+            .collect();
+        assert_eq!(kept, vec!["app.js", "new.js", "util.js"]);
+        assert!(repo.files[2].git_change.is_some());
+        assert!(repo.files[0].git_change.is_none());
+    }
+
+    #[test]
+    fn test_range_diff() {
+        let temp = init_test_repo();
+        let git = |args: &[&str]| {
+            Command::new("git")
+                .current_dir(temp.path())
+                .args(args)
+                .output()
+                .unwrap()
+        };
+        std::fs::write(temp.path().join("a.txt"), "one\n").unwrap();
+        git(&["add", "."]);
+        git(&["commit", "-m", "Add a"]);
+        std::fs::write(temp.path().join("a.txt"), "two\n").unwrap();
+        std::fs::write(temp.path().join("b.txt"), "new\n").unwrap();
+        git(&["add", "."]);
+        git(&["commit", "-m", "Change a, add b"]);
+
+        let repo = GitRepo::open(temp.path()).unwrap();
+        let range = repo.range_diff("HEAD~1..HEAD").unwrap();
+        assert_eq!(range.commits.len(), 1);
+        assert_eq!(range.commits[0].message, "Change a, add b");
+        let paths: Vec<_> = range.files.iter().map(|d| d.file.path.as_str()).collect();
+        assert_eq!(paths, vec!["a.txt", "b.txt"]);
+        assert_eq!(range.files[0].file.status, FileStatus::Modified);
+        assert!(range.files[0].patch.contains("-one\n+two"));
+        assert_eq!(range.files[1].file.status, FileStatus::Added);
+        assert_eq!(repo.file_at(&range.to, "a.txt").unwrap(), "two\n");
+
+        // A single revision means everything since it
+        assert_eq!(repo.range_diff("HEAD~1").unwrap().files.len(), 2);
+        assert!(repo.range_diff("no-such-ref..HEAD").is_err());
+
+        let rendered = range.render_localized(Locale::En);
+        assert!(rendered.contains("<!-- Changes in Range -->"));
+        assert!(rendered.contains("### a.txt (M +1 -1)\n```diff\n"));
+    }
+
+    #[test]
+    fn test_commit_symbol_changes() {
+        let temp = init_test_repo();
+        let git = |args: &[&str]| {
+            Command::new("git")
+                .current_dir(temp.path())
+                .args(args)
+                .output()
+                .unwrap()
+        };
+        let write = |content: &str| std::fs::write(temp.path().join("auth.py"), content).unwrap();
+        write("class AuthService:\n    def login(self):\n        return 1\n\n    def refresh_token(self):\n        return 2\n\ndef helper():\n    pass\n");
+        git(&["add", "."]);
+        git(&["commit", "-m", "Add auth"]);
+        write("class AuthService:\n    def login(self):\n        return 1\n\n    def refresh_token(self):\n        return 3\n\ndef audit():\n    pass\n");
+        git(&["commit", "-am", "Refresh differently"]);
+
+        let repo = GitRepo::open(temp.path()).unwrap();
+        let head = repo.current_commit().unwrap();
+        let changes: Vec<String> = repo
+            .commit_symbol_changes(&head)
+            .unwrap()
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        assert_eq!(
+            changes,
+            vec!["modified: `AuthService.refresh_token`", "added: `audit`", "removed: `helper`"]
+        );
+
+        // The root commit adds the class as a whole
+        let root = repo.commit_symbol_changes(&format!("{}^", head)).unwrap();
+        let names: Vec<_> = root.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, vec!["AuthService", "helper"]);
+        assert!(root.iter().all(|c| c.change == SymbolChangeKind::Added));
+
+        write("class AuthService:\n    def login(self):\n        return 0\n\n    def refresh_token(self):\n        return 3\n\ndef audit():\n    pass\n");
+        let worktree = repo.worktree_symbol_changes().unwrap();
+        assert_eq!(worktree.len(), 1);
+        assert_eq!(worktree[0].to_string(), "modified: `AuthService.login`");
+        assert_eq!(worktree[0].path, "auth.py");
+    }
+
+    #[test]
+    fn test_log_stream() {
+        let temp = init_test_repo();
+        let commit = |message: &str, date: &str| {
+            Command::new("git")
+                .current_dir(temp.path())
+                .env("GIT_COMMITTER_DATE", date)
+                .env("GIT_AUTHOR_DATE", date)
+                .args(["commit", "--allow-empty", "-m", message])
+                .output()
+                .unwrap()
+        };
+        commit("Old", "2001-01-01T00:00:00Z");
+        commit("Recent", "2099-01-01T00:00:00Z");
+        commit("Latest", "2099-01-02T00:00:00Z");
+
+        let repo = GitRepo::open(temp.path()).unwrap();
+        let mut stream = repo.log_stream(2, None).unwrap();
+        assert_eq!(stream.next().unwrap().unwrap().message, "Latest");
+        assert_eq!(stream.next().unwrap().unwrap().message, "Recent");
+        assert!(stream.next().is_none());
+
+        let recent: Vec<_> = repo
+            .log_stream(100, Some("2002-01-01"))
+            .unwrap()
+            .map(|c| c.unwrap().message)
+            .collect();
+        assert_eq!(recent, vec!["Latest", "Recent"]);
+        assert_eq!(repo.log_stream(100, Some("52w")).unwrap().count(), 2);
+        assert!(repo.log_stream(100, None).unwrap().count() >= 3);
+
+        assert_eq!(since_arg("90d"), "90 days ago");
+        assert_eq!(since_arg("6m"), "6 months ago");
+        assert_eq!(since_arg("2024-01-01"), "2024-01-01");
+    }
+
     #[test]
     fn test_format_timestamp() {
         // 2024-01-01 00:00:00 UTC
         let ts = 1704067200;
         let date = format_timestamp(ts);
-        assert_eq!(date, "2024-01-01");
+        assert_eq!(date, "2024-01-01T00:00:00Z");
+        assert_eq!(format_timestamp(ts + 3661), "2024-01-01T01:01:01Z");
+    }
+
+    #[test]
+    fn test_log_dates_are_utc() {
+        let temp = init_test_repo();
+        Command::new("git")
+            .current_dir(temp.path())
+            .env("GIT_COMMITTER_DATE", "2024-03-05T23:30:00+05:00")
+            .args(["commit", "--allow-empty", "-m", "Dated"])
+            .env("GIT_AUTHOR_DATE", "2024-03-05T23:30:00+05:00")
+            .output()
+            .unwrap();
+
+        let repo = GitRepo::open(temp.path()).unwrap();
+        let commits = repo.log(1).unwrap();
+        assert_eq!(commits[0].date, "2024-03-05T18:30:00Z");
+
+        let local = GitRepo::open(temp.path()).unwrap().with_local_time(true);
+        let commits = local.log(1).unwrap();
+        assert!(commits[0].date.starts_with("2024-03-0"), "{}", commits[0].date);
     }
 }
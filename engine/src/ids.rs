@@ -0,0 +1,277 @@
+//! Stable short IDs for files and symbols
+//!
+//! Maps, indexes and chunks name each file `F…` and each symbol `S…` next to
+//! its path, so a conversation spread over several packs can refer to "F3K9QZ"
+//! even where a later pack cuts the path short, orders files differently or
+//! leaves the file out.
+//!
+//! An ID is a hash of the workspace-relative path (for symbols, also the
+//! parent, name, kind and how many identical symbols come before it in the
+//! file), written as Crockford base32. Edits, ranking and pack options don't
+//! change it, and two runs over the same tree agree. IDs are 6 characters
+//! after the prefix; the rare IDs whose first characters collide with another
+//! one in the same repository get longer until they differ.
+
+use crate::types::{Repository, SymbolKind};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap};
+
+/// Characters after the prefix, unless needed to tell two IDs apart
+pub const MIN_ID_LEN: usize = 6;
+
+/// Longest ID body: 60 of the 64 hashed bits
+const MAX_ID_LEN: usize = 12;
+
+/// Crockford base32: no I, L, O or U to misread
+const ALPHABET: &[u8; 32] = b"0123456789ABCDEFGHJKMNPQRSTVWXYZ";
+
+/// IDs of the files in a repository and the symbols they define
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IdTable {
+    files: BTreeMap<String, FileIds>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct FileIds {
+    id: String,
+    /// Defined symbols in file order; imports get no ID
+    symbols: Vec<SymbolId>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct SymbolId {
+    name: String,
+    line: u32,
+    id: String,
+}
+
+/// What an ID refers to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IdTarget<'a> {
+    /// A file, by path
+    File(&'a str),
+    /// A symbol, by file, name and first line
+    Symbol { file: &'a str, name: &'a str, line: u32 },
+}
+
+impl IdTable {
+    /// Assign IDs to the files and symbols of `repo`
+    pub fn new(repo: &Repository) -> Self {
+        let mut file_hashes = Vec::with_capacity(repo.files.len());
+        let mut symbol_hashes = Vec::new();
+        for (file_index, file) in repo.files.iter().enumerate() {
+            let path = file.relative_path.as_str();
+            file_hashes.push(hash(path));
+
+            let mut seen: HashMap<(Option<&str>, &str, SymbolKind), u32> = HashMap::new();
+            for symbol in &file.symbols {
+                if symbol.kind == SymbolKind::Import {
+                    continue;
+                }
+                let ordinal = seen
+                    .entry((symbol.parent.as_deref(), &symbol.name, symbol.kind))
+                    .or_default();
+                let key = format!(
+                    "{}\0{}\0{}\0{}\0{}",
+                    path,
+                    symbol.parent.as_deref().unwrap_or(""),
+                    symbol.name,
+                    symbol.kind.name(),
+                    ordinal
+                );
+                *ordinal += 1;
+                symbol_hashes.push((file_index, symbol, hash(&key)));
+            }
+        }
+
+        let file_ids = encode_unique('F', &file_hashes);
+        let symbol_ids =
+            encode_unique('S', &symbol_hashes.iter().map(|&(_, _, h)| h).collect::<Vec<_>>());
+
+        let mut files: Vec<FileIds> = file_ids
+            .into_iter()
+            .map(|id| FileIds { id, symbols: Vec::new() })
+            .collect();
+        for ((file_index, symbol, _), id) in symbol_hashes.into_iter().zip(symbol_ids) {
+            files[file_index].symbols.push(SymbolId {
+                name: symbol.name.clone(),
+                line: symbol.start_line,
+                id,
+            });
+        }
+        Self {
+            files: repo
+                .files
+                .iter()
+                .map(|f| f.relative_path.clone())
+                .zip(files)
+                .collect(),
+        }
+    }
+
+    /// ID of the file at `path`
+    pub fn file(&self, path: &str) -> Option<&str> {
+        self.files.get(path).map(|f| f.id.as_str())
+    }
+
+    /// ID of the symbol `name` starting at `line` in the file at `path`
+    pub fn symbol(&self, path: &str, name: &str, line: u32) -> Option<&str> {
+        self.files
+            .get(path)?
+            .symbols
+            .iter()
+            .find(|s| s.line == line && s.name == name)
+            .map(|s| s.id.as_str())
+    }
+
+    /// ID of the first symbol named `name` in the file at `path`
+    pub fn symbol_named(&self, path: &str, name: &str) -> Option<&str> {
+        self.files
+            .get(path)?
+            .symbols
+            .iter()
+            .find(|s| s.name == name)
+            .map(|s| s.id.as_str())
+    }
+
+    /// The file or symbol an ID refers to; IDs are matched ignoring case
+    pub fn resolve(&self, id: &str) -> Option<IdTarget<'_>> {
+        for (path, file) in &self.files {
+            if file.id.eq_ignore_ascii_case(id) {
+                return Some(IdTarget::File(path));
+            }
+            if let Some(symbol) = file.symbols.iter().find(|s| s.id.eq_ignore_ascii_case(id)) {
+                return Some(IdTarget::Symbol {
+                    file: path,
+                    name: &symbol.name,
+                    line: symbol.line,
+                });
+            }
+        }
+        None
+    }
+
+    /// Number of files with an ID
+    pub fn len(&self) -> usize {
+        self.files.len()
+    }
+
+    /// Whether the table has no files
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+}
+
+/// First 64 bits of the SHA-256 of `key`
+fn hash(key: &str) -> u64 {
+    let digest = Sha256::digest(key.as_bytes());
+    u64::from_be_bytes(digest[..8].try_into().unwrap_or_default())
+}
+
+/// `prefix` and the shortest base32 form of each hash, from
+/// [`MIN_ID_LEN`] characters up, that no other hash shares
+fn encode_unique(prefix: char, hashes: &[u64]) -> Vec<String> {
+    let mut lengths = vec![MIN_ID_LEN; hashes.len()];
+    for len in MIN_ID_LEN..MAX_ID_LEN {
+        let mut groups: HashMap<u64, Vec<usize>> = HashMap::new();
+        for (i, &h) in hashes.iter().enumerate() {
+            if lengths[i] == len {
+                groups.entry(h >> (64 - 5 * len)).or_default().push(i);
+            }
+        }
+        // Everything sharing a prefix moves on together, so a short ID is
+        // never the start of a longer one; identical hashes end up at the
+        // longest form
+        for group in groups.values().filter(|g| g.len() > 1) {
+            for &i in group {
+                lengths[i] = len + 1;
+            }
+        }
+    }
+    hashes
+        .iter()
+        .zip(lengths)
+        .map(|(&h, len)| {
+            let mut id = String::with_capacity(len + 1);
+            id.push(prefix);
+            for i in 0..len {
+                let digit = (h >> (64 - 5 * (i + 1))) & 0x1f;
+                id.push(ALPHABET[digit as usize] as char);
+            }
+            id
+        })
+        .collect()
+}
+
+#[cfg(test)]
+#[allow(clippy::str_to_string)]
+mod tests {
+    use super::*;
+    use crate::types::{RepoFile, Symbol};
+
+    fn repo(paths: &[&str]) -> Repository {
+        let mut repo = Repository::new("test", "/tmp/test");
+        for path in paths {
+            let mut file = RepoFile::new(format!("/tmp/test/{}", path), *path);
+            let mut import = Symbol::new("import os", SymbolKind::Import);
+            import.start_line = 1;
+            let mut first = Symbol::new("new", SymbolKind::Method);
+            first.parent = Some("Config".to_string());
+            first.start_line = 3;
+            let mut second = first.clone();
+            second.start_line = 9;
+            file.symbols = vec![import, first, second];
+            repo.files.push(file);
+        }
+        repo
+    }
+
+    #[test]
+    fn test_ids_are_stable() {
+        let ids = IdTable::new(&repo(&["src/main.rs", "src/config.rs"]));
+        let file = ids.file("src/config.rs").unwrap();
+        assert!(file.starts_with('F'));
+        assert_eq!(file.len(), 1 + MIN_ID_LEN);
+        assert!(file[1..].bytes().all(|b| ALPHABET.contains(&b)));
+
+        // Other files, their order and symbol lines don't matter
+        let mut reordered = repo(&["lib.rs", "src/config.rs"]);
+        reordered.files.reverse();
+        reordered.files[0].symbols[1].start_line = 30;
+        let again = IdTable::new(&reordered);
+        assert_eq!(again.file("src/config.rs"), Some(file));
+        assert_eq!(again.symbol("src/config.rs", "new", 30), ids.symbol("src/config.rs", "new", 3));
+
+        // Identical symbols are told apart by order, and imports get none
+        let first = ids.symbol("src/config.rs", "new", 3).unwrap();
+        let second = ids.symbol("src/config.rs", "new", 9).unwrap();
+        assert!(first.starts_with('S'));
+        assert_ne!(first, second);
+        assert_eq!(ids.symbol_named("src/config.rs", "new"), Some(first));
+        assert_eq!(ids.symbol("src/config.rs", "import os", 1), None);
+        assert_ne!(ids.file("src/main.rs"), Some(file));
+
+        assert_eq!(ids.resolve(&file.to_lowercase()), Some(IdTarget::File("src/config.rs")));
+        assert_eq!(
+            ids.resolve(second),
+            Some(IdTarget::Symbol { file: "src/config.rs", name: "new", line: 9 })
+        );
+        assert_eq!(ids.resolve("F000000"), None);
+    }
+
+    #[test]
+    fn test_collisions_lengthen() {
+        let shared = 0b10101u64 << 59 | 0b01010u64 << 54;
+        let hashes = [shared, shared | 1 << 20, 0xFFFF_0000_0000_0000];
+        let ids = encode_unique('F', &hashes);
+        assert_eq!(ids[2].len(), 1 + MIN_ID_LEN);
+        assert!(ids[0].len() > 1 + MIN_ID_LEN);
+        assert_ne!(ids[0], ids[1]);
+        assert_eq!(ids[0][..1 + MIN_ID_LEN], ids[1][..1 + MIN_ID_LEN]);
+
+        // Identical hashes stop at the longest form
+        let ids = encode_unique('S', &[7, 7]);
+        assert_eq!(ids[0].len(), 1 + MAX_ID_LEN);
+    }
+}
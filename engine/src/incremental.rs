@@ -1,6 +1,9 @@
 //! Incremental scanning with file watching and caching
 //!
 //! Provides efficient re-scanning by caching results and only processing changed files.
+//! The cache is kept outside the repository, in its
+//! [`repo_cache_dir`](crate::cache::repo_cache_dir), or only in memory when
+//! that is not writable.
 
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -8,8 +11,9 @@ use std::fs;
 use std::path::{Path, PathBuf};
 use std::time::SystemTime;
 
+use crate::cache::{is_writable_dir, repo_cache_dir, write_atomic, CacheLock};
 use crate::tokenizer::TokenCounts;
-use crate::types::Symbol;
+use crate::types::{Symbol, SymbolKind};
 
 /// Cache entry for a single file
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -30,6 +34,8 @@ pub struct CachedFile {
     pub language: Option<String>,
     /// Line count
     pub lines: usize,
+    /// Whether symbols were extracted (false if the scan skipped parsing)
+    pub parsed: bool,
 }
 
 /// Cached symbol (simplified for storage)
@@ -40,6 +46,8 @@ pub struct CachedSymbol {
     pub start_line: u32,
     pub end_line: u32,
     pub signature: Option<String>,
+    pub docstring: Option<String>,
+    pub parent: Option<String>,
 }
 
 impl From<&Symbol> for CachedSymbol {
@@ -50,6 +58,24 @@ impl From<&Symbol> for CachedSymbol {
             start_line: s.start_line,
             end_line: s.end_line,
             signature: s.signature.clone(),
+            docstring: s.docstring.clone(),
+            parent: s.parent.clone(),
+        }
+    }
+}
+
+impl CachedSymbol {
+    /// Restore the symbol as the parser produced it (references and
+    /// importance are recomputed by ranking)
+    pub fn to_symbol(&self) -> Symbol {
+        let kind = SymbolKind::from_name(&self.kind).unwrap_or(SymbolKind::Function);
+        Symbol {
+            signature: self.signature.clone(),
+            docstring: self.docstring.clone(),
+            start_line: self.start_line,
+            end_line: self.end_line,
+            parent: self.parent.clone(),
+            ..Symbol::new(self.name.clone(), kind)
         }
     }
 }
@@ -75,7 +101,7 @@ pub struct RepoCache {
 
 impl RepoCache {
     /// Current cache version
-    pub const VERSION: u32 = 1;
+    pub const VERSION: u32 = 2;
 
     /// Create a new empty cache
     pub fn new(root_path: &str) -> Self {
@@ -97,9 +123,15 @@ impl RepoCache {
 
     /// Load cache from file
     pub fn load(cache_path: &Path) -> Result<Self, CacheError> {
+        // Best effort: a read-only cache can still be read without a lock
+        let _lock = CacheLock::shared(cache_path).ok();
         let content = fs::read(cache_path).map_err(|e| CacheError::IoError(e.to_string()))?;
+        Self::from_bytes(&content)
+    }
 
-        let cache: Self = bincode::deserialize(&content)
+    /// Decode a cache written by [`RepoCache::save`]
+    pub fn from_bytes(content: &[u8]) -> Result<Self, CacheError> {
+        let cache: Self = bincode::deserialize(content)
             .map_err(|e| CacheError::DeserializeError(e.to_string()))?;
 
         // Check version compatibility
@@ -123,14 +155,17 @@ impl RepoCache {
         let content =
             bincode::serialize(self).map_err(|e| CacheError::SerializeError(e.to_string()))?;
 
-        fs::write(cache_path, content).map_err(|e| CacheError::IoError(e.to_string()))?;
+        // Concurrent runs take turns, and readers only ever see a whole cache
+        let _lock =
+            CacheLock::exclusive(cache_path).map_err(|e| CacheError::IoError(e.to_string()))?;
+        write_atomic(cache_path, content).map_err(|e| CacheError::IoError(e.to_string()))?;
 
         Ok(())
     }
 
-    /// Get default cache path for a repository
+    /// Get default cache path for a repository, outside the repository
     pub fn default_cache_path(repo_path: &Path) -> PathBuf {
-        repo_path.join(".infiniloom/cache/repo.cache")
+        repo_cache_dir(repo_path).join("repo.cache")
     }
 
     /// Check if a file needs rescanning
@@ -221,19 +256,26 @@ impl std::error::Error for CacheError {}
 /// Incremental scanner that uses caching
 pub struct IncrementalScanner {
     cache: RepoCache,
-    cache_path: PathBuf,
+    /// Where saves go; `None` keeps the cache in memory only
+    cache_path: Option<PathBuf>,
     dirty: bool,
 }
 
 impl IncrementalScanner {
     /// Create or load an incremental scanner for a repository
+    ///
+    /// The cache lives at [`RepoCache::default_cache_path`]. If its directory
+    /// is not writable, as on a read-only filesystem, an existing cache is
+    /// still read but changes are only kept in memory.
     pub fn new(repo_path: &Path) -> Self {
         let cache_path = RepoCache::default_cache_path(repo_path);
-
-        let cache = RepoCache::load(&cache_path)
-            .unwrap_or_else(|_| RepoCache::new(&repo_path.to_string_lossy()));
-
-        Self { cache, cache_path, dirty: false }
+        let writable = cache_path.parent().is_some_and(is_writable_dir);
+        let mut scanner = Self::with_cache_path(repo_path, cache_path);
+        if !writable {
+            log::debug!("cache directory is not writable; keeping the incremental cache in memory");
+            scanner.cache_path = None;
+        }
+        scanner
     }
 
     /// Create with custom cache path
@@ -241,7 +283,17 @@ impl IncrementalScanner {
         let cache = RepoCache::load(&cache_path)
             .unwrap_or_else(|_| RepoCache::new(&repo_path.to_string_lossy()));
 
-        Self { cache, cache_path, dirty: false }
+        Self { cache, cache_path: Some(cache_path), dirty: false }
+    }
+
+    /// Create with an empty cache that is never written to disk
+    pub fn in_memory(repo_path: &Path) -> Self {
+        Self { cache: RepoCache::new(&repo_path.to_string_lossy()), cache_path: None, dirty: false }
+    }
+
+    /// Where [`save`](Self::save) writes the cache, if anywhere
+    pub fn cache_path(&self) -> Option<&Path> {
+        self.cache_path.as_deref()
     }
 
     /// Check if a file needs to be rescanned
@@ -280,12 +332,20 @@ impl IncrementalScanner {
         self.dirty = true;
     }
 
-    /// Save cache if modified
+    /// Drop cached files that are not in `current_files`, returning how many were dropped
+    pub fn prune(&mut self, current_files: &[&str]) -> usize {
+        let deleted = self.cache.find_deleted_files(current_files);
+        for path in &deleted {
+            self.cache.remove_file(path);
+        }
+        self.dirty |= !deleted.is_empty();
+        deleted.len()
+    }
+
+    /// Save cache if modified; an in-memory cache is only totalled
     pub fn save(&mut self) -> Result<(), CacheError> {
         if self.dirty {
-            self.cache.recalculate_totals();
-            self.cache.save(&self.cache_path)?;
-            self.dirty = false;
+            self.force_save()?;
         }
         Ok(())
     }
@@ -293,7 +353,9 @@ impl IncrementalScanner {
     /// Force save cache
     pub fn force_save(&mut self) -> Result<(), CacheError> {
         self.cache.recalculate_totals();
-        self.cache.save(&self.cache_path)?;
+        if let Some(cache_path) = &self.cache_path {
+            self.cache.save(cache_path)?;
+        }
         self.dirty = false;
         Ok(())
     }
@@ -355,7 +417,7 @@ pub mod watcher {
 
             let watcher = RecommendedWatcher::new(
                 move |res| {
-                    let _ = tx.send(res);
+                    tx.send(res).ok();
                 },
                 Config::default(),
             )?;
@@ -446,6 +508,7 @@ mod tests {
                 symbols: vec![],
                 language: Some("python".to_string()),
                 lines: 10,
+                parsed: false,
             },
         );
 
@@ -473,6 +536,7 @@ mod tests {
                 symbols: vec![],
                 language: None,
                 lines: 0,
+                parsed: false,
             },
         );
 
@@ -485,7 +549,7 @@ mod tests {
     fn test_incremental_scanner() {
         let temp = TempDir::new().unwrap();
 
-        let mut scanner = IncrementalScanner::new(temp.path());
+        let mut scanner = IncrementalScanner::in_memory(temp.path());
         assert!(scanner.needs_rescan(&temp.path().join("test.py")));
 
         scanner.update(CachedFile {
@@ -497,9 +561,46 @@ mod tests {
             symbols: vec![],
             language: Some("python".to_string()),
             lines: 5,
+            parsed: false,
         });
 
         assert!(scanner.get_cached("test.py").is_some());
+        assert_eq!(scanner.prune(&["other.py"]), 1);
+        assert!(scanner.get_cached("test.py").is_none());
+
+        // Nothing is written, inside the repository or anywhere else
+        scanner.save().unwrap();
+        assert!(scanner.cache_path().is_none());
+        assert_eq!(fs::read_dir(temp.path()).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn test_default_cache_path_is_outside_repository() {
+        let temp = TempDir::new().unwrap();
+        let repo = temp.path().join("app");
+        fs::create_dir(&repo).unwrap();
+
+        let path = RepoCache::default_cache_path(&repo);
+        assert!(!path.starts_with(&repo));
+        assert!(path.to_string_lossy().contains("app-"));
+        assert_eq!(path, RepoCache::default_cache_path(&repo.join(".")));
+        assert_ne!(path, RepoCache::default_cache_path(temp.path()));
+    }
+
+    #[test]
+    fn test_cached_symbol_roundtrip() {
+        let mut symbol = Symbol::new("area", SymbolKind::Method);
+        symbol.start_line = 3;
+        symbol.end_line = 7;
+        symbol.parent = Some("Circle".to_string());
+        symbol.docstring = Some("Area of the circle".to_string());
+
+        let restored = CachedSymbol::from(&symbol).to_symbol();
+        assert_eq!(restored.name, "area");
+        assert_eq!(restored.kind, SymbolKind::Method);
+        assert_eq!((restored.start_line, restored.end_line), (3, 7));
+        assert_eq!(restored.parent.as_deref(), Some("Circle"));
+        assert_eq!(restored.docstring.as_deref(), Some("Area of the circle"));
     }
 
     #[test]
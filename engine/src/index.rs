@@ -0,0 +1,215 @@
+//! Persistent repository index
+//!
+//! Scanning reads, tokenizes and parses every file, which dominates the run
+//! time of most commands. [`RepoIndex`] saves the result once, together with
+//! the symbol reference graph and the file dependency graph, so later runs
+//! load it instead of scanning. It is written as CBOR to `index` in the
+//! repository's [`repo_cache_dir`], so indexing a read-only checkout works
+//! and leaves the checkout untouched.
+//!
+//! An index does not follow edits: [`RepoIndex::changed_files`] lists indexed
+//! files that were modified or deleted since, but files added later are only
+//! picked up by building the index again.
+
+use crate::cache::{repo_cache_dir, write_atomic, CacheLock};
+use crate::dependencies::DependencyGraph;
+use crate::ids::IdTable;
+use crate::repomap::{RepoMapGenerator, SymbolEdge};
+use crate::types::Repository;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use thiserror::Error;
+
+/// An import of one file by another, or of an external package
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileDependency {
+    /// Importing file
+    pub from: String,
+    /// Imported file, if the import resolved to one in the repository
+    pub to: Option<String>,
+    /// Import specifier as written
+    pub specifier: String,
+    /// Line of the import
+    pub line: u32,
+    /// Whether the specifier names an external package
+    pub external: bool,
+}
+
+/// Size and modification time of an indexed file, to notice edits
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct FileStamp {
+    path: String,
+    size: u64,
+    mtime: u64,
+}
+
+/// A scanned repository with its symbol and dependency graphs
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RepoIndex {
+    version: u32,
+    /// When the index was built (Unix timestamp)
+    pub created_at: u64,
+    /// The repository as scanned, with contents and symbols
+    pub repository: Repository,
+    /// References between symbols, as ranking sees them
+    pub symbol_edges: Vec<SymbolEdge>,
+    /// Imports between files and of external packages
+    pub dependencies: Vec<FileDependency>,
+    /// File and symbol IDs across the whole repository
+    pub ids: IdTable,
+    stamps: Vec<FileStamp>,
+}
+
+/// Errors reading or writing an index
+#[derive(Debug, Error)]
+pub enum IndexError {
+    /// The index file could not be read or written
+    #[error("I/O error: {0}")]
+    Io(#[from] io::Error),
+    /// The index could not be encoded
+    #[error("could not encode index: {0}")]
+    Encode(#[from] ciborium::ser::Error<io::Error>),
+    /// The index could not be decoded
+    #[error("corrupt index: {0}")]
+    Decode(#[from] ciborium::de::Error<io::Error>),
+    /// The index was written by an incompatible version
+    #[error("index version {found} is not supported (expected {expected}); rebuild it")]
+    VersionMismatch { expected: u32, found: u32 },
+}
+
+impl RepoIndex {
+    /// Current index layout version
+    pub const VERSION: u32 = 2;
+
+    /// Index a scanned repository
+    ///
+    /// The repository should have been scanned with contents and symbols.
+    /// File stamps are read from disk now, so build right after scanning.
+    pub fn build(repository: Repository) -> Self {
+        let symbol_edges = RepoMapGenerator::new(0).symbol_edges(&repository);
+        let dependencies = DependencyGraph::build(&repository)
+            .get_all_imports()
+            .iter()
+            .map(|import| FileDependency {
+                from: import.from_path.clone(),
+                to: import.to_path.clone(),
+                specifier: import.specifier.clone(),
+                line: import.line,
+                external: import.is_external,
+            })
+            .collect();
+        let ids = IdTable::new(&repository);
+        let stamps = repository
+            .files
+            .iter()
+            .filter_map(|file| {
+                let (size, mtime) = stamp(&file.path)?;
+                Some(FileStamp { path: file.relative_path.clone(), size, mtime })
+            })
+            .collect();
+
+        Self {
+            version: Self::VERSION,
+            created_at: unix_time(SystemTime::now()),
+            repository,
+            symbol_edges,
+            dependencies,
+            ids,
+            stamps,
+        }
+    }
+
+    /// Path of the index of the repository at `repo_path`, outside the
+    /// repository
+    pub fn default_path(repo_path: &Path) -> PathBuf {
+        repo_cache_dir(repo_path).join("index")
+    }
+
+    /// Load an index written by [`RepoIndex::save`]
+    pub fn load(path: &Path) -> Result<Self, IndexError> {
+        // Best effort: a read-only index can still be read without a lock
+        let _lock = CacheLock::shared(path).ok();
+        let index: Self = ciborium::from_reader(fs::read(path)?.as_slice())?;
+        if index.version != Self::VERSION {
+            return Err(IndexError::VersionMismatch {
+                expected: Self::VERSION,
+                found: index.version,
+            });
+        }
+        Ok(index)
+    }
+
+    /// Write the index, replacing any earlier one in one step
+    pub fn save(&self, path: &Path) -> Result<(), IndexError> {
+        let mut content = Vec::new();
+        ciborium::into_writer(self, &mut content)?;
+        let _lock = CacheLock::exclusive(path)?;
+        write_atomic(path, content)?;
+        Ok(())
+    }
+
+    /// Indexed files modified or deleted since the index was built
+    pub fn changed_files(&self) -> Vec<&str> {
+        self.stamps
+            .iter()
+            .filter(|s| stamp(&self.repository.path.join(&s.path)) != Some((s.size, s.mtime)))
+            .map(|s| s.path.as_str())
+            .collect()
+    }
+}
+
+/// Size and modification time of a file
+fn stamp(path: &Path) -> Option<(u64, u64)> {
+    let metadata = fs::metadata(path).ok()?;
+    Some((metadata.len(), unix_time(metadata.modified().ok()?)))
+}
+
+fn unix_time(time: SystemTime) -> u64 {
+    time.duration_since(SystemTime::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+#[allow(clippy::str_to_string)]
+mod tests {
+    use super::*;
+    use crate::types::{RepoFile, Symbol, SymbolKind};
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_round_trip_and_staleness() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("main.py");
+        fs::write(&path, "import util\n\ndef main():\n    util.run()\n").unwrap();
+
+        let mut repo = Repository::new("test", dir.path());
+        let mut file = RepoFile::new(&path, "main.py");
+        file.language = Some("python".to_string());
+        file.content = Some(fs::read_to_string(&path).unwrap());
+        file.symbols = vec![
+            Symbol::new("import util", SymbolKind::Import),
+            Symbol::new("main", SymbolKind::Function),
+        ];
+        repo.files.push(file);
+
+        let index = RepoIndex::build(repo);
+        assert_eq!(index.dependencies.len(), 1);
+        assert!(index.dependencies[0].external);
+        let index_path = RepoIndex::default_path(dir.path());
+        index.save(&index_path).unwrap();
+
+        let loaded = RepoIndex::load(&index_path).unwrap();
+        assert_eq!(loaded.repository.files[0].symbols.len(), 2);
+        assert_eq!(loaded.dependencies, index.dependencies);
+        assert_eq!(loaded.ids, index.ids);
+        assert!(loaded.ids.file("main.py").is_some());
+        assert!(loaded.changed_files().is_empty());
+
+        fs::remove_file(&path).unwrap();
+        assert_eq!(loaded.changed_files(), vec!["main.py"]);
+    }
+}
@@ -0,0 +1,158 @@
+//! Language metadata shared by formatters and content transforms
+//!
+//! The scanner labels files with short identifiers (`tsx`, `vlang`,
+//! `csharp`). Those are not always valid Markdown fence tags or readable
+//! names, and each has its own comment syntax, so every consumer looks them
+//! up here instead of keeping its own table.
+
+/// Metadata for one language, keyed by the scanner's identifier
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LanguageInfo {
+    /// Identifier used in `RepoFile::language`
+    pub id: &'static str,
+    /// Other spellings accepted by [`lookup`]
+    pub aliases: &'static [&'static str],
+    /// Human-readable name
+    pub display_name: &'static str,
+    /// Tag for Markdown code fences
+    pub fence: &'static str,
+    /// Line comment prefix
+    pub line_comment: Option<&'static str>,
+    /// Block comment delimiters
+    pub block_comment: Option<(&'static str, &'static str)>,
+    /// Prefix that marks documentation comments
+    pub doc_comment: Option<&'static str>,
+}
+
+impl LanguageInfo {
+    /// Whether the language has any comment syntax
+    pub fn has_comments(&self) -> bool {
+        self.line_comment.is_some() || self.block_comment.is_some()
+    }
+}
+
+const C_BLOCK: Option<(&str, &str)> = Some(("/*", "*/"));
+const HTML_BLOCK: Option<(&str, &str)> = Some(("<!--", "-->"));
+
+/// Build a table entry; aliases are filled in separately where needed
+const fn lang(
+    id: &'static str,
+    display_name: &'static str,
+    fence: &'static str,
+    line_comment: Option<&'static str>,
+    block_comment: Option<(&'static str, &'static str)>,
+    doc_comment: Option<&'static str>,
+) -> LanguageInfo {
+    LanguageInfo { id, aliases: &[], display_name, fence, line_comment, block_comment, doc_comment }
+}
+
+/// Every language the scanners can report
+pub const LANGUAGES: &[LanguageInfo] = &[
+    lang("python", "Python", "python", Some("#"), None, Some("\"\"\"")),
+    lang("javascript", "JavaScript", "javascript", Some("//"), C_BLOCK, Some("/**")),
+    lang("jsx", "JSX", "jsx", Some("//"), C_BLOCK, Some("/**")),
+    lang("typescript", "TypeScript", "typescript", Some("//"), C_BLOCK, Some("/**")),
+    lang("tsx", "TSX", "tsx", Some("//"), C_BLOCK, Some("/**")),
+    lang("rust", "Rust", "rust", Some("//"), C_BLOCK, Some("///")),
+    lang("go", "Go", "go", Some("//"), C_BLOCK, Some("//")),
+    lang("java", "Java", "java", Some("//"), C_BLOCK, Some("/**")),
+    lang("kotlin", "Kotlin", "kotlin", Some("//"), C_BLOCK, Some("/**")),
+    lang("scala", "Scala", "scala", Some("//"), C_BLOCK, Some("/**")),
+    lang("groovy", "Groovy", "groovy", Some("//"), C_BLOCK, Some("/**")),
+    lang("clojure", "Clojure", "clojure", Some(";"), None, None),
+    lang("c", "C", "c", Some("//"), C_BLOCK, Some("/**")),
+    LanguageInfo {
+        aliases: &["c++"],
+        ..lang("cpp", "C++", "cpp", Some("//"), C_BLOCK, Some("///"))
+    },
+    LanguageInfo {
+        aliases: &["c#", "cs"],
+        ..lang("csharp", "C#", "csharp", Some("//"), C_BLOCK, Some("///"))
+    },
+    lang("ruby", "Ruby", "ruby", Some("#"), Some(("=begin", "=end")), Some("#")),
+    lang("php", "PHP", "php", Some("//"), C_BLOCK, Some("/**")),
+    lang("swift", "Swift", "swift", Some("//"), C_BLOCK, Some("///")),
+    LanguageInfo {
+        aliases: &["sh", "shell"],
+        ..lang("bash", "Bash", "bash", Some("#"), None, None)
+    },
+    lang("zsh", "Zsh", "zsh", Some("#"), None, None),
+    lang("fish", "Fish", "fish", Some("#"), None, None),
+    lang("powershell", "PowerShell", "powershell", Some("#"), Some(("<#", "#>")), None),
+    lang("html", "HTML", "html", None, HTML_BLOCK, None),
+    lang("css", "CSS", "css", None, C_BLOCK, None),
+    lang("scss", "SCSS", "scss", Some("//"), C_BLOCK, Some("///")),
+    lang("sass", "Sass", "sass", Some("//"), C_BLOCK, Some("///")),
+    lang("less", "Less", "less", Some("//"), C_BLOCK, None),
+    lang("json", "JSON", "json", None, None, None),
+    LanguageInfo { aliases: &["yml"], ..lang("yaml", "YAML", "yaml", Some("#"), None, None) },
+    lang("toml", "TOML", "toml", Some("#"), None, None),
+    lang("xml", "XML", "xml", None, HTML_BLOCK, None),
+    lang("ini", "INI", "ini", Some(";"), None, None),
+    lang("markdown", "Markdown", "markdown", None, HTML_BLOCK, None),
+    lang("mdx", "MDX", "mdx", None, Some(("{/*", "*/}")), None),
+    lang("rst", "reStructuredText", "rst", None, None, None),
+    lang("text", "Plain text", "text", None, None, None),
+    lang("zig", "Zig", "zig", Some("//"), None, Some("///")),
+    lang("lua", "Lua", "lua", Some("--"), Some(("--[[", "]]")), Some("---")),
+    lang("sql", "SQL", "sql", Some("--"), C_BLOCK, None),
+    lang("elixir", "Elixir", "elixir", Some("#"), None, Some("@doc")),
+    lang("erlang", "Erlang", "erlang", Some("%"), None, Some("%%")),
+    lang("haskell", "Haskell", "haskell", Some("--"), Some(("{-", "-}")), Some("-- |")),
+    lang("ocaml", "OCaml", "ocaml", None, Some(("(*", "*)")), Some("(**")),
+    lang("fsharp", "F#", "fsharp", Some("//"), Some(("(*", "*)")), Some("///")),
+    lang("vue", "Vue", "vue", None, HTML_BLOCK, None),
+    lang("svelte", "Svelte", "svelte", None, HTML_BLOCK, None),
+    lang("dockerfile", "Dockerfile", "dockerfile", Some("#"), None, None),
+    lang("terraform", "Terraform", "hcl", Some("#"), C_BLOCK, None),
+    lang("make", "Makefile", "makefile", Some("#"), None, None),
+    lang("cmake", "CMake", "cmake", Some("#"), None, None),
+    lang("nix", "Nix", "nix", Some("#"), C_BLOCK, None),
+    lang("julia", "Julia", "julia", Some("#"), Some(("#=", "=#")), Some("\"\"\"")),
+    lang("r", "R", "r", Some("#"), None, Some("#'")),
+    lang("dart", "Dart", "dart", Some("//"), C_BLOCK, Some("///")),
+    lang("nim", "Nim", "nim", Some("#"), Some(("#[", "]#")), Some("##")),
+    lang("vlang", "V", "v", Some("//"), C_BLOCK, None),
+    lang("crystal", "Crystal", "crystal", Some("#"), None, None),
+];
+
+/// Metadata for a language identifier or alias (case-insensitive)
+pub fn lookup(language: &str) -> Option<&'static LanguageInfo> {
+    let language = language.to_ascii_lowercase();
+    LANGUAGES
+        .iter()
+        .find(|l| l.id == language || l.aliases.contains(&language.as_str()))
+}
+
+/// Markdown fence tag for `language`, or the identifier itself if unknown
+pub fn fence_tag(language: &str) -> &str {
+    lookup(language).map_or(language, |l| l.fence)
+}
+
+/// Display name for `language`, or the identifier itself if unknown
+pub fn display_name(language: &str) -> &str {
+    lookup(language).map_or(language, |l| l.display_name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lookup_and_fallbacks() {
+        assert_eq!(fence_tag("vlang"), "v");
+        assert_eq!(fence_tag("TSX"), "tsx");
+        assert_eq!(fence_tag("cobol"), "cobol");
+        assert_eq!(display_name("csharp"), "C#");
+        assert_eq!(lookup("yml").map(|l| l.id), Some("yaml"));
+        assert!(!lookup("json").is_some_and(LanguageInfo::has_comments));
+    }
+
+    #[test]
+    fn test_ids_are_unique() {
+        let mut ids: Vec<_> = LANGUAGES.iter().map(|l| l.id).collect();
+        ids.sort_unstable();
+        ids.dedup();
+        assert_eq!(ids.len(), LANGUAGES.len());
+    }
+}
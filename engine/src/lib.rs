@@ -4,23 +4,65 @@
 //! context for large language models, including:
 //!
 //! - Repository mapping with PageRank-based symbol ranking
+//! - Stable short file and symbol IDs for cross-referencing across packs
 //! - Intelligent semantic chunking
-//! - Model-specific output formatters (Claude, GPT, Gemini)
-//! - Security scanning for secrets
+//! - One pack pipeline (scan, filter, rank, compress, map, format) for every frontend
+//! - Model-specific output formatters (Claude, GPT, Gemini) with versioned layouts
+//! - Compression levels shared by the CLI and the bindings, including semantic
+//!   deduplication of near-identical functions (`embeddings` feature)
+//! - Signature packs: tree, exported signatures and dependencies for agents
+//! - Security scanning for secrets and personal data
+//! - Build, test and run commands from Makefiles, package.json, justfiles and CI
+//! - Topic inference (web backend, CLI tool, ML, infrastructure, mobile) for routing
+//! - Compact summaries of Protobuf, OpenAPI and JSON Schema contracts
 //! - Accurate token counting (tiktoken for OpenAI, estimation for others)
 //! - Full AST-based dependency resolution
+//! - External dependency usage: which files import each package
 //! - Memory-mapped file scanning for large repositories
+//! - One parallel filesystem scanner for the CLI and every binding
+//! - A versioned scan report shared by the CLI and the bindings
+//! - Composable file filters by path, size, language, git status and importance
+//! - Scanning tar, tar.gz and zip archives from memory, without touching disk
+//! - Adaptive sampling for repositories beyond any token budget
+//! - Importance-weighted per-file token budgets, cut at symbol boundaries
+//! - Inline `infiniloom:` comment directives to pin, cut and summarize files
 //! - Incremental scanning with caching
-//! - Remote Git repository support
+//! - A persistent repository index with symbol and dependency graphs
+//! - Cache locking and size-bounded garbage collection for parallel runs
+//! - Cooperative cancellation of scans, repository maps and formatting
+//! - Freshness checks that tell long-lived sessions when to re-pack
+//! - Remote Git repository support with retries, resumable clones and offline use
+//! - Review context for commit ranges
+//! - Snapshot packs across a revision range for regression hunting
+//! - Focused packs of one subsystem and its import neighborhood
+//! - Workspace detection (Cargo, npm, yarn, pnpm, Go) and packs of single packages
+//! - Single-file context: the file plus the signatures and types around it
+//! - Query-relevant packs ranked by BM25 or embedding similarity to a question
+//! - BM25 full-text search over file contents and symbols
+//! - Multi-repository federation for service-spanning packs
+//! - Entry point detection from main functions, `__main__` guards and manifests
+//! - Deterministic onboarding reports
+//! - Include, focus and budget suggestions from a log of past queries
+//! - Symbol index export (universal-ctags JSON, LSIF, Parquet)
+//! - Self-contained tokenizer and grammar data for offline single-binary use
 //!
 //! # Example
 //!
-//! ```rust,ignore
-//! use infiniloom_engine::{Repository, RepoMapGenerator, OutputFormatter};
+//! Scan, map and format a repository in three calls:
 //!
-//! let repo = Repository::scan("/path/to/repo")?;
+//! ```
+//! use infiniloom_engine::output::Formatter;
+//! use infiniloom_engine::{OutputFormatter, RepoMapGenerator, Repository, ScanOptions};
+//! # let dir = tempfile::tempdir()?;
+//! # std::fs::write(dir.path().join("main.py"), "def main():\n    print('hi')\n")?;
+//! # let path = dir.path();
+//!
+//! let repo = Repository::scan(path, ScanOptions::default())?;
 //! let map = RepoMapGenerator::new(2000).generate(&repo);
 //! let output = OutputFormatter::claude().format(&repo, &map);
+//!
+//! assert!(output.contains("main.py"));
+//! # Ok::<(), Box<dyn std::error::Error>>(())
 //! ```
 
 // Core modules
@@ -34,36 +76,131 @@ pub mod security;
 pub mod types;
 
 // New modules
+pub mod archive;
+pub mod bisect;
+pub mod budget;
+pub mod bundle;
+pub mod cache;
+pub mod cancel;
+pub mod commands;
+pub mod compress;
 pub mod config;
+pub mod contracts;
+pub mod data;
+pub mod delta;
 pub mod dependencies;
+pub mod directives;
+pub mod doctor;
+pub mod entry_points;
+pub mod export;
+pub mod federation;
+pub mod file_context;
+pub mod filter;
+pub mod focus;
 pub mod git;
+pub mod ids;
 pub mod incremental;
+pub mod index;
+pub mod languages;
 pub mod mmap_scanner;
+pub mod packages;
+pub mod pipeline;
+pub mod query;
 pub mod remote;
+pub mod report;
+pub mod sampling;
+pub mod scan;
+pub mod scan_report;
+pub mod scanner;
+pub mod search;
 pub mod tokenizer;
+pub mod topics;
+pub mod transform;
+pub mod tuning;
+pub mod watch;
 
 #[cfg(feature = "embeddings")]
 pub mod semantic;
 
+#[cfg(feature = "eval")]
+pub mod eval;
+
 // Re-exports from core modules
-pub use chunking::{Chunk, ChunkStrategy, Chunker};
-pub use output::{OutputFormat, OutputFormatter};
-pub use parser::{Language, Parser, ParserError};
-pub use ranking::{rank_files, sort_files_by_importance, SymbolRanker};
-pub use repomap::{RepoMap, RepoMapGenerator};
-pub use security::SecurityScanner;
+pub use chunking::{Chunk, ChunkPlan, ChunkStrategy, Chunker};
+pub use output::{FormatVersion, IndexLimit, Locale, OutputFormat, OutputFormatter, PathRewriter};
+pub use parser::{Language, LanguageRegistry, LanguageSpec, Parser, ParserError};
+pub use ranking::{
+    file_importance, rank_files, sort_files_by_importance, FileImportance, SymbolRanker,
+};
+pub use repomap::{RepoMap, RepoMapGenerator, SymbolEdge, SymbolFilter};
+pub use security::{PiiPolicy, SecurityBaseline, SecurityRule, SecurityScanner};
 pub use types::*;
 
 // Re-exports from new modules
+pub use archive::{read_archive, write_tar, Archive, ArchiveEntry, ArchiveError, ArchiveFormat};
+pub use bisect::{BisectPack, Snapshot, SnapshotChanges, DEFAULT_BISECT_STEPS};
+pub use budget::{BudgetAllocator, BudgetReport, TruncatedFile};
+pub use bundle::{Bundle, BundleError, BundleFile, BundleIndex, BundlePart};
+pub use cache::{CacheEntry, CacheGc, CacheLock, EntryKind as CacheEntryKind, GcReport};
+pub use cancel::{CancellationToken, Cancelled};
+pub use compress::{CommentStripper, Compressor};
 pub use config::{
-    Config, OutputConfig, PerformanceConfig, ScanConfig, SecurityConfig, SymbolConfig,
+    Config, GrammarConfig, OutputConfig, PerformanceConfig, ScanConfig, SecurityConfig,
+    SecurityRuleConfig, SymbolConfig, TopicConfig,
+};
+pub use contracts::{
+    contracts_section, extract_contracts, render_contracts, Contract, ContractKind,
+};
+pub use data::{DataCache, DataError, RemoteAsset};
+pub use default_ignores::{HiddenPolicy, DEFAULT_HIDDEN_ALLOWLIST};
+pub use delta::{apply_delta, Delta, DeltaError, Freshness, PackManifest};
+pub use dependencies::{
+    DependencyEdge, DependencyGraph, DependencyNode, ExternalUsage, ResolvedImport,
 };
-pub use dependencies::{DependencyEdge, DependencyGraph, DependencyNode, ResolvedImport};
-pub use git::{ChangedFile, Commit, FileStatus, GitError, GitRepo};
+pub use directives::{apply_directives, Directives};
+pub use doctor::{Doctor, Finding};
+pub use entry_points::{detect_entry_points, EntryPoint, EntrySource};
+pub use export::{export_symbols, Dataset, ExportFormat};
+pub use federation::{FederatedRepo, FederationConfig, FederationError};
+pub use file_context::{FileContext, HaloItem, HaloKind, DEFAULT_FILE_CONTEXT_BUDGET};
+pub use filter::{
+    FileFilter, FilterError, FilterStack, GitStatusFilter, GlobFilter, ImportanceFilter,
+    LanguageFilter, SizeFilter,
+};
+pub use focus::{retain_focus, FocusSelection, DEFAULT_FOCUS_DEPTH};
+pub use git::{
+    retain_changes, symbol_changes, BlobStream, ChangeSelection, ChangedFile, Commit, FileDiff,
+    FileStatus, GitError, GitRepo, LogStream, RangeDiff, TreeEntry,
+};
+pub use ids::{IdTable, IdTarget};
 pub use incremental::{CachedFile, FileChange, IncrementalScanner, RepoCache};
+pub use index::{FileDependency, IndexError, RepoIndex};
+pub use languages::LanguageInfo;
 pub use mmap_scanner::{MappedFile, MmapScanner, ScannedFile as MmapScannedFile};
-pub use remote::{GitProvider, RemoteError, RemoteRepo};
+pub use packages::{detect_packages, retain_packages, Package, PackageKind, PackageSelection};
+pub use pipeline::{PackError, PackOptions, PackPhase, PackPipeline};
+pub use query::{retain_relevant, QueryMatch, QueryScoring, QuerySelection};
+pub use remote::{CloneCache, GitProvider, RemoteError, RemoteRepo, RetryPolicy};
+pub use report::ReportGenerator;
+pub use sampling::{ModuleSample, Sampler, SamplingReport};
+pub use scan::{ScanError, ScanOptions};
+pub use scan_report::{
+    DependencySummary, ExternalSummary, FileSummary, GitSummary, LanguageSummary, ScanReport,
+    SCAN_REPORT_VERSION,
+};
+pub use scanner::{
+    scan_repository, scan_repository_incremental, scan_repository_with_progress, IncrementalStats,
+    ScanConfig as ScannerConfig, ScanProgress,
+};
+pub use search::{SearchHit, SearchIndex};
 pub use tokenizer::{TokenCounts as AccurateTokenCounts, TokenModel, Tokenizer};
+pub use topics::{infer_topics, Topic};
+pub use transform::{LineLimiter, LongLineMode};
+pub use tuning::{parse_queries, TuningAdvisor, TuningReport};
+pub use watch::{EventBatcher, WatchFilter};
+
+#[cfg(feature = "embeddings")]
+pub use semantic::{SemanticAnalyzer, SemanticCompressor, SemanticReport};
 
 /// Library version
 pub const VERSION: &str = env!("CARGO_PKG_VERSION");
@@ -10,6 +10,7 @@ use std::io;
 use std::path::Path;
 use std::sync::atomic::{AtomicU64, Ordering};
 
+use crate::parser::Language;
 use crate::tokenizer::{TokenCounts, TokenModel, Tokenizer};
 
 /// A memory-mapped file for efficient reading
@@ -22,6 +23,9 @@ impl MappedFile {
     /// Open a file with memory mapping
     pub fn open(path: &Path) -> io::Result<Self> {
         let file = File::open(path)?;
+        // SAFETY: the mapping is read-only and lives as long as `MappedFile`;
+        // concurrent truncation by another process is an accepted risk of mmap.
+        #[allow(unsafe_code)]
         let mmap = unsafe { MmapOptions::new().map(&file)? };
 
         Ok(Self { mmap, path: path.to_string_lossy().to_string() })
@@ -202,6 +206,54 @@ impl MmapScanner {
             (content, false)
         };
 
+        let scanned = self.scan_text(path, relative_path, content_bytes);
+        if scanned.is_some() {
+            self.stats.bytes_read.fetch_add(size, Ordering::Relaxed);
+        }
+        Ok(scanned)
+    }
+
+    /// Scan file contents that came from somewhere other than the disk,
+    /// such as a git object
+    ///
+    /// `path` only determines the language. Binary and oversized contents
+    /// are skipped.
+    pub fn scan_bytes(
+        &self,
+        path: &Path,
+        relative_path: String,
+        content: Vec<u8>,
+    ) -> Option<ScannedFile> {
+        let size = content.len() as u64;
+        if size > self.max_file_size {
+            self.stats
+                .files_skipped_size
+                .fetch_add(1, Ordering::Relaxed);
+            return None;
+        }
+        if is_binary_content(&content) {
+            self.stats
+                .files_skipped_binary
+                .fetch_add(1, Ordering::Relaxed);
+            return None;
+        }
+
+        let scanned = self.scan_text(path, relative_path, content);
+        if scanned.is_some() {
+            self.stats.bytes_read.fetch_add(size, Ordering::Relaxed);
+        }
+        scanned
+    }
+
+    /// Decode, token-count and classify non-binary contents
+    fn scan_text(
+        &self,
+        path: &Path,
+        relative_path: String,
+        content_bytes: Vec<u8>,
+    ) -> Option<ScannedFile> {
+        let size = content_bytes.len() as u64;
+
         // Convert to string
         let content_str = match String::from_utf8(content_bytes) {
             Ok(s) => s,
@@ -209,7 +261,7 @@ impl MmapScanner {
                 self.stats
                     .files_skipped_binary
                     .fetch_add(1, Ordering::Relaxed);
-                return Ok(None);
+                return None;
             },
         };
 
@@ -223,9 +275,8 @@ impl MmapScanner {
         let language = detect_language(path);
 
         self.stats.files_scanned.fetch_add(1, Ordering::Relaxed);
-        self.stats.bytes_read.fetch_add(size, Ordering::Relaxed);
 
-        Ok(Some(ScannedFile {
+        Some(ScannedFile {
             path: path.to_string_lossy().to_string(),
             relative_path,
             size_bytes: size,
@@ -234,7 +285,7 @@ impl MmapScanner {
             language,
             content: Some(content_str),
             is_binary: false,
-        }))
+        })
     }
 
     /// Scan multiple files in parallel
@@ -294,6 +345,9 @@ fn is_binary_content(content: &[u8]) -> bool {
 /// Detect language from file extension
 fn detect_language(path: &Path) -> Option<String> {
     let ext = path.extension()?.to_str()?;
+    if let Some(custom) = Language::from_extension(ext).filter(Language::is_custom) {
+        return Some(custom.name().to_owned());
+    }
 
     let lang = match ext.to_lowercase().as_str() {
         "py" | "pyw" | "pyi" => "python",
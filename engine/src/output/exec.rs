@@ -0,0 +1,279 @@
+//! External formatters run as a subprocess
+//!
+//! An [`ExecFormatter`] is the escape hatch for bespoke output formats: it
+//! writes the JSON representation of the repository and its map (the output
+//! of [`JsonFormatter`], `{"repository": ..., "map": ...}`) to a command's
+//! stdin and uses the command's stdout as the final output.
+//!
+//! The command is started directly, not through a shell, with an empty
+//! environment apart from `PATH`. It is killed if it runs past its timeout,
+//! and its output is rejected beyond a size limit. None of this contains the
+//! command: it runs with the caller's permissions and can do anything they
+//! can, so callers must only take formatters from configuration the user
+//! wrote, never from a repository being packed.
+
+use super::{Formatter, JsonFormatter};
+use crate::repomap::RepoMap;
+use crate::types::Repository;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, ExitStatus, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+use thiserror::Error;
+
+/// Prefix marking an external formatter in a `format` setting
+pub const EXEC_PREFIX: &str = "exec:";
+
+/// Default time a formatter may run
+pub const DEFAULT_EXEC_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Default limit on a formatter's output
+pub const DEFAULT_MAX_OUTPUT: usize = 256 * 1024 * 1024;
+
+/// Bytes of stderr kept for error messages
+const MAX_STDERR: u64 = 4096;
+
+/// How often a running formatter is checked for exit
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Errors running an external formatter
+#[derive(Debug, Error)]
+pub enum ExecError {
+    #[error("empty formatter command")]
+    EmptyCommand,
+    #[error("failed to start formatter `{0}`: {1}")]
+    Spawn(String, #[source] std::io::Error),
+    #[error("I/O error talking to formatter: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("formatter timed out after {0:?}")]
+    Timeout(Duration),
+    #[error("formatter output exceeds {0} bytes")]
+    OutputTooLarge(usize),
+    #[error("formatter exited with {status}: {stderr}")]
+    Failed { status: ExitStatus, stderr: String },
+    #[error("formatter output is not valid UTF-8")]
+    InvalidOutput,
+}
+
+/// Formatter that delegates to an external command
+#[derive(Debug, Clone)]
+pub struct ExecFormatter {
+    /// Program to run
+    program: String,
+    /// Arguments passed to the program
+    args: Vec<String>,
+    /// Directory the command runs in, and that relative programs resolve against
+    working_dir: Option<PathBuf>,
+    /// Time the command may run before it is killed
+    timeout: Duration,
+    /// Largest accepted output in bytes
+    max_output: usize,
+    /// Pass the caller's environment through instead of only `PATH`
+    inherit_env: bool,
+}
+
+impl ExecFormatter {
+    /// Create a formatter for `command`, a program followed by its arguments
+    ///
+    /// The command is split on whitespace; there is no shell quoting.
+    pub fn new(command: &str) -> Result<Self, ExecError> {
+        let mut words = command.split_whitespace().map(str::to_owned);
+        let program = words.next().ok_or(ExecError::EmptyCommand)?;
+        Ok(Self {
+            program,
+            args: words.collect(),
+            working_dir: None,
+            timeout: DEFAULT_EXEC_TIMEOUT,
+            max_output: DEFAULT_MAX_OUTPUT,
+            inherit_env: false,
+        })
+    }
+
+    /// Create a formatter from a `format` setting such as `exec:./my-formatter`,
+    /// or `None` if the setting names a built-in format
+    pub fn from_spec(spec: &str) -> Option<Result<Self, ExecError>> {
+        spec.trim().strip_prefix(EXEC_PREFIX).map(Self::new)
+    }
+
+    /// Run the command in `dir`, resolving a relative program path against it
+    pub fn with_working_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.working_dir = Some(dir.into());
+        self
+    }
+
+    /// Set how long the command may run (default 30 seconds)
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Set the largest accepted output in bytes (default 256 MiB)
+    pub fn with_max_output(mut self, bytes: usize) -> Self {
+        self.max_output = bytes;
+        self
+    }
+
+    /// Pass the full environment to the command instead of only `PATH`
+    pub fn with_inherited_env(mut self, inherit: bool) -> Self {
+        self.inherit_env = inherit;
+        self
+    }
+
+    /// The command as configured, for messages
+    pub fn command(&self) -> String {
+        std::iter::once(&self.program)
+            .chain(&self.args)
+            .cloned()
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// Format `repo` and `map` with the external command
+    pub fn format(&self, repo: &Repository, map: &RepoMap) -> Result<String, ExecError> {
+        self.run(JsonFormatter::new().format(repo, map))
+    }
+
+    /// Format `repo` alone with the external command
+    pub fn format_repo(&self, repo: &Repository) -> Result<String, ExecError> {
+        self.run(JsonFormatter::new().format_repo(repo))
+    }
+
+    fn run(&self, input: String) -> Result<String, ExecError> {
+        let mut command = Command::new(self.program_path());
+        command
+            .args(&self.args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+        if let Some(dir) = &self.working_dir {
+            command.current_dir(dir);
+        }
+        if !self.inherit_env {
+            command.env_clear();
+            if let Some(path) = std::env::var_os("PATH") {
+                command.env("PATH", path);
+            }
+        }
+
+        let mut child = command
+            .spawn()
+            .map_err(|e| ExecError::Spawn(self.command(), e))?;
+
+        // Feed and drain the pipes on threads so a chatty command can't block
+        let stdin = child.stdin.take();
+        let writer = thread::spawn(move || {
+            if let Some(mut stdin) = stdin {
+                // A command may exit without reading all of its input
+                stdin.write_all(input.as_bytes()).ok();
+            }
+        });
+        let stdout = child.stdout.take();
+        let limit = self.max_output as u64 + 1;
+        let reader = thread::spawn(move || -> std::io::Result<Vec<u8>> {
+            let mut out = Vec::new();
+            if let Some(stdout) = stdout {
+                stdout.take(limit).read_to_end(&mut out)?;
+            }
+            Ok(out)
+        });
+        let stderr = child.stderr.take();
+        let error_reader = thread::spawn(move || {
+            let mut err = Vec::new();
+            if let Some(mut stderr) = stderr {
+                // Keep the start for the error message but drain the rest
+                stderr.by_ref().take(MAX_STDERR).read_to_end(&mut err).ok();
+                std::io::copy(&mut stderr, &mut std::io::sink()).ok();
+            }
+            err
+        });
+
+        let deadline = Instant::now() + self.timeout;
+        let status = loop {
+            if let Some(status) = child.try_wait()? {
+                break status;
+            }
+            if Instant::now() >= deadline {
+                child.kill().ok();
+                child.wait()?;
+                return Err(ExecError::Timeout(self.timeout));
+            }
+            thread::sleep(POLL_INTERVAL);
+        };
+
+        writer.join().ok();
+        let output = reader.join().unwrap_or_else(|_| Ok(Vec::new()))?;
+        let stderr = error_reader.join().unwrap_or_default();
+
+        // Checked first: a command cut off at the limit may fail on the closed pipe
+        if output.len() > self.max_output {
+            return Err(ExecError::OutputTooLarge(self.max_output));
+        }
+        if !status.success() {
+            return Err(ExecError::Failed {
+                status,
+                stderr: String::from_utf8_lossy(&stderr).trim().to_owned(),
+            });
+        }
+        String::from_utf8(output).map_err(|_| ExecError::InvalidOutput)
+    }
+
+    /// The program, resolved against the working directory if it is a relative path
+    fn program_path(&self) -> PathBuf {
+        let program = Path::new(&self.program);
+        match &self.working_dir {
+            Some(dir) if program.is_relative() && program.components().count() > 1 => {
+                dir.join(program)
+            },
+            _ => program.to_path_buf(),
+        }
+    }
+}
+
+#[cfg(all(test, unix))]
+#[allow(clippy::str_to_string)]
+mod tests {
+    use super::*;
+    use crate::repomap::RepoMapGenerator;
+
+    #[test]
+    fn test_exec_formatter() {
+        let repo = Repository::new("demo", "/tmp/demo");
+        let map = RepoMapGenerator::new(100).generate(&repo);
+
+        assert!(ExecFormatter::from_spec("xml").is_none());
+        assert!(matches!(ExecFormatter::from_spec("exec: "), Some(Err(ExecError::EmptyCommand))));
+
+        let cat = ExecFormatter::from_spec("exec:cat").unwrap().unwrap();
+        let output = cat.format(&repo, &map).unwrap();
+        let json: serde_json::Value = serde_json::from_str(&output).unwrap();
+        assert_eq!(json["repository"]["name"], "demo");
+        assert!(json.get("map").is_some());
+
+        let output = ExecFormatter::new("env")
+            .unwrap()
+            .format_repo(&repo)
+            .unwrap();
+        let names: Vec<&str> = output
+            .lines()
+            .filter_map(|l| l.split_once('=').map(|(name, _)| name))
+            .collect();
+        assert!(names.iter().all(|name| *name == "PATH"), "{:?}", names);
+
+        let truncated = ExecFormatter::new("cat").unwrap().with_max_output(10);
+        assert!(matches!(truncated.format(&repo, &map), Err(ExecError::OutputTooLarge(10))));
+
+        let failing = ExecFormatter::new("ls /nonexistent-infiniloom-dir").unwrap();
+        assert!(matches!(failing.format(&repo, &map), Err(ExecError::Failed { .. })));
+
+        let slow = ExecFormatter::new("sleep 5")
+            .unwrap()
+            .with_timeout(Duration::from_millis(100));
+        assert!(matches!(slow.format(&repo, &map), Err(ExecError::Timeout(_))));
+
+        let missing = ExecFormatter::new("./no-such-formatter")
+            .unwrap()
+            .with_working_dir("/tmp");
+        assert!(matches!(missing.format(&repo, &map), Err(ExecError::Spawn(..))));
+    }
+}
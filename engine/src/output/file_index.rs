@@ -0,0 +1,194 @@
+//! Limits on the file index of a pack
+//!
+//! The file index lists every packed file, which for tens of thousands of
+//! files takes more tokens than a small budget allows. [`IndexLimit`] keeps
+//! the most important files and rolls crowded directories up into one entry;
+//! formatters say how many files that left out.
+
+use crate::types::{RepoFile, Repository, TokenizerModel};
+use std::collections::HashMap;
+
+/// How many files the file index lists
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct IndexLimit {
+    /// List at most this many files, the most important ones (0 = all)
+    pub max_files: usize,
+    /// List at most this many files of one directory and roll the others up
+    /// into a single entry (0 = never)
+    pub per_directory: usize,
+}
+
+impl IndexLimit {
+    /// List every file
+    pub fn none() -> Self {
+        Self::default()
+    }
+
+    /// Set the number of files to list
+    pub fn with_max_files(mut self, max_files: usize) -> Self {
+        self.max_files = max_files;
+        self
+    }
+
+    /// Set the number of files listed per directory before rolling up
+    pub fn with_per_directory(mut self, per_directory: usize) -> Self {
+        self.per_directory = per_directory;
+        self
+    }
+
+    /// Whether every file is listed
+    pub fn is_none(&self) -> bool {
+        self.max_files == 0 && self.per_directory == 0
+    }
+
+    /// Entries of the file index of `repo`, in file order
+    pub(crate) fn apply<'a>(&self, repo: &'a Repository) -> FileIndex<'a> {
+        let mut listed = vec![true; repo.files.len()];
+        if self.max_files > 0 && repo.files.len() > self.max_files {
+            let mut by_importance: Vec<usize> = (0..repo.files.len()).collect();
+            by_importance.sort_by(|&a, &b| {
+                repo.files[b]
+                    .importance
+                    .total_cmp(&repo.files[a].importance)
+            });
+            for &i in &by_importance[self.max_files..] {
+                listed[i] = false;
+            }
+        }
+
+        let mut entries = Vec::new();
+        let mut omitted = Vec::new();
+        let mut seen: HashMap<&str, usize> = HashMap::new();
+        let mut rollups: HashMap<&str, usize> = HashMap::new();
+        for (file, listed) in repo.files.iter().zip(listed) {
+            if !listed {
+                omitted.push(file);
+                continue;
+            }
+            let directory = directory(&file.relative_path);
+            let count = seen.entry(directory).or_default();
+            *count += 1;
+            if self.per_directory == 0 || *count <= self.per_directory {
+                entries.push(IndexEntry::File(file));
+                continue;
+            }
+            // The roll-up stands where the first file it hides would have
+            match rollups.get(directory) {
+                Some(&at) => {
+                    if let IndexEntry::Rollup { files, .. } = &mut entries[at] {
+                        files.push(file);
+                    }
+                },
+                None => {
+                    rollups.insert(directory, entries.len());
+                    entries.push(IndexEntry::Rollup { directory, files: vec![file] });
+                },
+            }
+        }
+
+        FileIndex { entries, omitted }
+    }
+}
+
+/// File index of a repository under an [`IndexLimit`]
+pub(crate) struct FileIndex<'a> {
+    pub(crate) entries: Vec<IndexEntry<'a>>,
+    /// Files left out for being less important than the listed ones
+    pub(crate) omitted: Vec<&'a RepoFile>,
+}
+
+/// A line of the file index
+pub(crate) enum IndexEntry<'a> {
+    File(&'a RepoFile),
+    /// Files of a directory beyond the per-directory limit
+    Rollup {
+        directory: &'a str,
+        files: Vec<&'a RepoFile>,
+    },
+}
+
+impl FileIndex<'_> {
+    /// Number of files listed individually
+    pub(crate) fn listed(&self) -> usize {
+        self.entries
+            .iter()
+            .filter(|e| matches!(e, IndexEntry::File(_)))
+            .count()
+    }
+}
+
+/// Total tokens of `files` for `model`
+pub(crate) fn total_tokens(files: &[&RepoFile], model: TokenizerModel) -> u64 {
+    files
+        .iter()
+        .map(|f| u64::from(f.token_count.get(model)))
+        .sum()
+}
+
+/// Directory of a relative path, `.` for the root
+fn directory(path: &str) -> &str {
+    match path.rfind('/') {
+        Some(i) => &path[..i],
+        None => ".",
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::str_to_string)]
+mod tests {
+    use super::*;
+
+    fn repo() -> Repository {
+        let mut repo = Repository::new("test", "/tmp/test");
+        for (path, importance) in [
+            ("main.rs", 0.9),
+            ("gen/a.rs", 0.1),
+            ("gen/b.rs", 0.2),
+            ("gen/c.rs", 0.3),
+            ("lib.rs", 0.8),
+        ] {
+            let mut file = RepoFile::new(format!("/tmp/test/{}", path), path);
+            file.importance = importance;
+            repo.files.push(file);
+        }
+        repo
+    }
+
+    fn paths(index: &FileIndex<'_>) -> Vec<String> {
+        index
+            .entries
+            .iter()
+            .map(|e| match e {
+                IndexEntry::File(f) => f.relative_path.clone(),
+                IndexEntry::Rollup { directory, files } => {
+                    format!("{}/ +{}", directory, files.len())
+                },
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_unlimited() {
+        let repo = repo();
+        let index = IndexLimit::none().apply(&repo);
+        assert_eq!(index.listed(), 5);
+        assert!(index.omitted.is_empty());
+    }
+
+    #[test]
+    fn test_max_files_keeps_most_important() {
+        let repo = repo();
+        let index = IndexLimit::none().with_max_files(3).apply(&repo);
+        assert_eq!(paths(&index), vec!["main.rs", "gen/c.rs", "lib.rs"]);
+        assert_eq!(index.omitted.len(), 2);
+    }
+
+    #[test]
+    fn test_directory_rollup() {
+        let repo = repo();
+        let index = IndexLimit::none().with_per_directory(2).apply(&repo);
+        assert_eq!(paths(&index), vec!["main.rs", "gen/a.rs", "gen/b.rs", "gen/ +1", "lib.rs"]);
+        assert_eq!(index.listed(), 4);
+        assert!(index.omitted.is_empty());
+    }
+}
@@ -0,0 +1,357 @@
+//! Well-formedness checks for generated output
+//!
+//! File contents end up inside markup, so a stray `]]>`, a control
+//! character or an oddly indented first line can corrupt a pack without any
+//! error at generation time. [`lint_output`] parses XML, JSON and YAML output
+//! back before it is written, so a malformed pack fails loudly instead of
+//! confusing whatever reads it. Markdown, TOON and the text formats have no
+//! structure a parser could reject and always pass.
+
+use super::OutputFormat;
+use serde::de::{Deserialize, IgnoredAny};
+use std::borrow::Cow;
+use thiserror::Error;
+
+/// Structural problems in generated output
+#[derive(Debug, Error)]
+pub enum LintError {
+    #[error("malformed XML at line {line}, column {column}: {message}")]
+    Xml { line: usize, column: usize, message: String },
+    #[error("malformed JSON: {0}")]
+    Json(serde_json::Error),
+    #[error("malformed YAML: {0}")]
+    Yaml(serde_yaml::Error),
+}
+
+/// Check that `output` parses as `format`
+pub fn lint_output(format: OutputFormat, output: &str) -> Result<(), LintError> {
+    match format {
+        OutputFormat::Xml => XmlChecker::new(output).check(),
+        OutputFormat::Json => {
+            serde_json::from_str::<IgnoredAny>(output).map_err(LintError::Json)?;
+            Ok(())
+        },
+        OutputFormat::Yaml => {
+            for document in serde_yaml::Deserializer::from_str(output) {
+                IgnoredAny::deserialize(document).map_err(LintError::Yaml)?;
+            }
+            Ok(())
+        },
+        OutputFormat::Markdown
+        | OutputFormat::Toon
+        | OutputFormat::Plain
+        | OutputFormat::Signatures => Ok(()),
+    }
+}
+
+/// Entities XML defines without a DTD
+const PREDEFINED_ENTITIES: &[&str] = &["amp", "lt", "gt", "quot", "apos"];
+
+/// Minimal XML 1.0 well-formedness checker
+///
+/// Covers what the XML formatter emits: elements, attributes, character and
+/// entity references, CDATA sections, comments and processing instructions.
+/// A DOCTYPE is skipped without being interpreted.
+struct XmlChecker<'a> {
+    text: &'a str,
+    pos: usize,
+    open: Vec<&'a str>,
+    roots: usize,
+}
+
+impl<'a> XmlChecker<'a> {
+    fn new(text: &'a str) -> Self {
+        Self { text, pos: 0, open: Vec::new(), roots: 0 }
+    }
+
+    fn check(mut self) -> Result<(), LintError> {
+        while self.pos < self.text.len() {
+            if self.eat("<![CDATA[") {
+                self.in_element("CDATA section")?;
+                let body = self.until("]]>", "unterminated CDATA section")?;
+                self.chars(body)?;
+            } else if self.eat("<!--") {
+                self.until("-->", "unterminated comment")?;
+            } else if self.eat("<?") {
+                self.until("?>", "unterminated processing instruction")?;
+            } else if self.eat("<!DOCTYPE") {
+                self.until(">", "unterminated DOCTYPE")?;
+            } else if self.eat("</") {
+                self.end_tag()?;
+            } else if self.eat("<") {
+                self.start_tag()?;
+            } else if self.eat("&") {
+                self.in_element("reference")?;
+                self.reference()?;
+            } else {
+                let c = self.rest().chars().next().unwrap_or_default();
+                if !c.is_whitespace() {
+                    self.in_element("text")?;
+                }
+                if self.rest().starts_with("]]>") {
+                    return Err(self.error("`]]>` outside a CDATA section"));
+                }
+                self.chars(&self.text[self.pos..self.pos + c.len_utf8()])?;
+                self.pos += c.len_utf8();
+            }
+        }
+        if let Some(name) = self.open.last() {
+            return Err(self.error(&format!("element <{}> is never closed", name)));
+        }
+        if self.roots == 0 {
+            return Err(self.error("no root element"));
+        }
+        Ok(())
+    }
+
+    fn rest(&self) -> &'a str {
+        &self.text[self.pos..]
+    }
+
+    fn eat(&mut self, prefix: &str) -> bool {
+        let found = self.rest().starts_with(prefix);
+        if found {
+            self.pos += prefix.len();
+        }
+        found
+    }
+
+    /// Text up to `end`, moving past it
+    fn until(&mut self, end: &str, message: &str) -> Result<&'a str, LintError> {
+        let Some(offset) = self.rest().find(end) else {
+            return Err(self.error(message));
+        };
+        let body = &self.rest()[..offset];
+        self.pos += offset + end.len();
+        Ok(body)
+    }
+
+    fn in_element(&self, what: &str) -> Result<(), LintError> {
+        if self.open.is_empty() {
+            return Err(self.error(&format!("{} outside the root element", what)));
+        }
+        Ok(())
+    }
+
+    /// Reject characters XML does not allow anywhere, even in CDATA
+    fn chars(&self, text: &str) -> Result<(), LintError> {
+        match text.chars().find(|&c| !is_xml_char(c)) {
+            Some(c) => Err(self.error(&format!("character {:?} is not allowed in XML", c))),
+            None => Ok(()),
+        }
+    }
+
+    fn name(&mut self) -> Result<&'a str, LintError> {
+        let rest = self.rest();
+        let len = rest
+            .char_indices()
+            .find(|&(i, c)| {
+                !(is_name_char(c) && (i > 0 || (!c.is_ascii_digit() && c != '-' && c != '.')))
+            })
+            .map_or(rest.len(), |(i, _)| i);
+        if len == 0 {
+            return Err(self.error("expected a name"));
+        }
+        self.pos += len;
+        Ok(&rest[..len])
+    }
+
+    fn skip_whitespace(&mut self) -> bool {
+        let rest = self.rest();
+        let trimmed = rest.trim_start_matches([' ', '\t', '\r', '\n']);
+        self.pos += rest.len() - trimmed.len();
+        trimmed.len() < rest.len()
+    }
+
+    fn start_tag(&mut self) -> Result<(), LintError> {
+        if self.open.is_empty() {
+            if self.roots > 0 {
+                return Err(self.error("more than one root element"));
+            }
+            self.roots += 1;
+        }
+        let name = self.name()?;
+        loop {
+            let spaced = self.skip_whitespace();
+            if self.eat("/>") {
+                return Ok(());
+            }
+            if self.eat(">") {
+                self.open.push(name);
+                return Ok(());
+            }
+            if !spaced {
+                return Err(self.error(&format!("malformed tag <{}>", name)));
+            }
+            self.attribute()?;
+        }
+    }
+
+    fn attribute(&mut self) -> Result<(), LintError> {
+        let name = self.name()?;
+        self.skip_whitespace();
+        if !self.eat("=") {
+            return Err(self.error(&format!("attribute `{}` has no value", name)));
+        }
+        self.skip_whitespace();
+        let quote = match self.rest().chars().next() {
+            Some(quote @ ('"' | '\'')) => quote,
+            _ => return Err(self.error(&format!("value of `{}` is not quoted", name))),
+        };
+        self.pos += 1;
+        loop {
+            match self.rest().chars().next() {
+                None => return Err(self.error(&format!("value of `{}` is not closed", name))),
+                Some(c) if c == quote => {
+                    self.pos += 1;
+                    return Ok(());
+                },
+                Some('<') => return Err(self.error(&format!("`<` in the value of `{}`", name))),
+                Some('&') => {
+                    self.pos += 1;
+                    self.reference()?;
+                },
+                Some(c) => {
+                    self.chars(&self.text[self.pos..self.pos + c.len_utf8()])?;
+                    self.pos += c.len_utf8();
+                },
+            }
+        }
+    }
+
+    fn end_tag(&mut self) -> Result<(), LintError> {
+        let start = self.pos - "</".len();
+        let name = self.name()?;
+        self.skip_whitespace();
+        if !self.eat(">") {
+            return Err(self.error(&format!("malformed closing tag </{}>", name)));
+        }
+        match self.open.pop() {
+            Some(open) if open == name => Ok(()),
+            Some(open) => Err(self.error_at(start, &format!("</{}> closes <{}>", name, open))),
+            None => Err(self.error_at(start, &format!("</{}> has no matching start tag", name))),
+        }
+    }
+
+    /// An entity or character reference, after its `&`
+    fn reference(&mut self) -> Result<(), LintError> {
+        let Some(end) = self.rest().find(';').filter(|&end| end <= 32) else {
+            return Err(self.error("`&` does not start a reference"));
+        };
+        let reference = &self.rest()[..end];
+        let valid = match reference.strip_prefix('#') {
+            Some(code) => {
+                let value = match code.strip_prefix('x') {
+                    Some(hex) => u32::from_str_radix(hex, 16),
+                    None => code.parse(),
+                };
+                value.ok().and_then(char::from_u32).is_some_and(is_xml_char)
+            },
+            None => PREDEFINED_ENTITIES.contains(&reference),
+        };
+        if !valid {
+            return Err(self.error(&format!("unknown reference `&{};`", reference)));
+        }
+        self.pos += end + 1;
+        Ok(())
+    }
+
+    fn error(&self, message: &str) -> LintError {
+        self.error_at(self.pos, message)
+    }
+
+    fn error_at(&self, pos: usize, message: &str) -> LintError {
+        let before = &self.text[..pos.min(self.text.len())];
+        let line = before.matches('\n').count() + 1;
+        let column = before
+            .rsplit('\n')
+            .next()
+            .unwrap_or_default()
+            .chars()
+            .count()
+            + 1;
+        LintError::Xml { line, column, message: message.to_owned() }
+    }
+}
+
+/// Write characters neither XML nor YAML can carry as `\x1b`-style escapes
+///
+/// Tabs and line breaks are kept; other control characters, common in code
+/// that emits terminal colors, would make the whole document unreadable.
+pub(crate) fn escape_controls(text: &str) -> Cow<'_, str> {
+    if !text.chars().any(needs_escape) {
+        return Cow::Borrowed(text);
+    }
+    let mut escaped = String::with_capacity(text.len() + 8);
+    for c in text.chars() {
+        match c {
+            c if !needs_escape(c) => escaped.push(c),
+            c if (c as u32) < 0x100 => escaped.push_str(&format!("\\x{:02x}", c as u32)),
+            c => escaped.push_str(&format!("\\u{{{:04x}}}", c as u32)),
+        }
+    }
+    Cow::Owned(escaped)
+}
+
+fn needs_escape(c: char) -> bool {
+    (c.is_control() && !matches!(c, '\t' | '\n' | '\r')) || matches!(c, '\u{FFFE}' | '\u{FFFF}')
+}
+
+/// Whether XML 1.0 allows `c` in a document
+fn is_xml_char(c: char) -> bool {
+    matches!(c, '\t' | '\n' | '\r') || (c >= ' ' && !matches!(c, '\u{FFFE}' | '\u{FFFF}'))
+}
+
+/// Whether `c` may appear in an element or attribute name
+fn is_name_char(c: char) -> bool {
+    c.is_alphanumeric() || matches!(c, '_' | ':' | '-' | '.')
+}
+
+#[cfg(test)]
+#[allow(clippy::str_to_string)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_xml_well_formedness() {
+        let valid = "<?xml version=\"1.0\"?>\n<!-- pack -->\n<repo name=\"a &amp; b\">\n  <file path='x'><![CDATA[if a < b && c]]></file>\n  <empty/>&#x41;\n</repo>\n";
+        assert!(lint_output(OutputFormat::Xml, valid).is_ok());
+
+        for (broken, message) in [
+            ("<repo><file></repo>", "</repo> closes <file>"),
+            ("<repo><![CDATA[a ]]> b]]></repo>", "`]]>` outside a CDATA section"),
+            ("<repo>\n  <![CDATA[\u{1b}[31m]]></repo>", "character '\\u{1b}' is not allowed"),
+            ("<repo a=\"1 < 2\"/>", "`<` in the value of `a`"),
+            ("<repo>AT&T</repo>", "`&` does not start a reference"),
+            ("<repo/><repo/>", "more than one root element"),
+            ("<repo>", "element <repo> is never closed"),
+        ] {
+            let error = lint_output(OutputFormat::Xml, broken)
+                .unwrap_err()
+                .to_string();
+            assert!(error.contains(message), "{}: {}", broken, error);
+        }
+
+        let error = lint_output(OutputFormat::Xml, "<repo>\n  <a></b>\n</repo>").unwrap_err();
+        assert!(error
+            .to_string()
+            .starts_with("malformed XML at line 2, column 6"));
+    }
+
+    #[test]
+    fn test_escape_controls() {
+        assert!(matches!(escape_controls("a\tb\n"), Cow::Borrowed(_)));
+        assert_eq!(escape_controls("\u{1b}[31m\u{0}\u{FFFF}"), "\\x1b[31m\\x00\\u{ffff}");
+        let xml = format!("<a>{}</a>", escape_controls("\u{7}\u{85}"));
+        assert!(lint_output(OutputFormat::Xml, &xml).is_ok());
+    }
+
+    #[test]
+    fn test_json_and_yaml() {
+        assert!(lint_output(OutputFormat::Json, "{\"a\": [1, 2]}").is_ok());
+        assert!(lint_output(OutputFormat::Json, "").is_err());
+        assert!(lint_output(OutputFormat::Yaml, "---\na:\n  - b: |\n      text\n").is_ok());
+        assert!(lint_output(OutputFormat::Yaml, "a: |\n    indented\n  less\nb: 1\n").is_err());
+        assert!(lint_output(OutputFormat::Markdown, "```\nunclosed").is_ok());
+    }
+}
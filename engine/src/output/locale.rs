@@ -0,0 +1,491 @@
+//! Localized text for the human-readable parts of the output
+//!
+//! Tags, keys and file contents stay as they are; only the prose the
+//! formatters add around them (section titles, labels, guidance and notes
+//! about omitted content) follows the [`Locale`]. Templates mark their
+//! arguments with `{name}` placeholders.
+
+/// Language of the text injected around the packed repository
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum Locale {
+    /// English
+    #[default]
+    En,
+    /// Japanese
+    Ja,
+    /// German
+    De,
+    /// Spanish
+    Es,
+}
+
+impl Locale {
+    /// Parse a language code such as `ja` or `de-AT`
+    pub fn from_code(code: &str) -> Option<Self> {
+        let language = code.split(['-', '_']).next().unwrap_or(code);
+        match language.to_lowercase().as_str() {
+            "en" => Some(Self::En),
+            "ja" => Some(Self::Ja),
+            "de" => Some(Self::De),
+            "es" => Some(Self::Es),
+            _ => None,
+        }
+    }
+
+    /// ISO 639-1 code
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::En => "en",
+            Self::Ja => "ja",
+            Self::De => "de",
+            Self::Es => "es",
+        }
+    }
+
+    /// Text for this locale
+    pub fn strings(&self) -> &'static Strings {
+        match self {
+            Self::En => &EN,
+            Self::Ja => &JA,
+            Self::De => &DE,
+            Self::Es => &ES,
+        }
+    }
+}
+
+/// Every piece of prose the output can contain, for one locale
+#[derive(Debug)]
+pub struct Strings {
+    /// Purpose line of the XML guide (`{repo}`)
+    pub guide_purpose: &'static str,
+    /// Guide tip pointing at `<overview>`
+    pub guide_overview: &'static str,
+    /// Guide tip pointing at `<entry_points>`
+    pub guide_entry_points: &'static str,
+    /// Guide tip pointing at `<repository_map>`
+    pub guide_repository_map: &'static str,
+    /// Guide tip about file order
+    pub guide_file_order: &'static str,
+
+    pub repository: &'static str,
+    pub overview: &'static str,
+    pub metric: &'static str,
+    pub value: &'static str,
+    pub files: &'static str,
+    pub lines: &'static str,
+    pub tokens: &'static str,
+    pub primary_language: &'static str,
+    pub framework: &'static str,
+    /// Inferred repository topics (web backend, CLI tool, ...)
+    pub topics: &'static str,
+    /// Title of the build, test and run commands
+    pub commands: &'static str,
+    pub command: &'static str,
+    pub source: &'static str,
+    pub languages: &'static str,
+    pub language: &'static str,
+    pub percentage: &'static str,
+    pub repository_map: &'static str,
+    pub key_symbols: &'static str,
+    pub rank: &'static str,
+    pub symbol: &'static str,
+    pub kind: &'static str,
+    pub file: &'static str,
+    pub line: &'static str,
+    pub module_dependencies: &'static str,
+    /// Title of the external dependencies listed with their importing files
+    pub dependency_usage: &'static str,
+    pub project_structure: &'static str,
+    pub directory_structure: &'static str,
+    pub git: &'static str,
+    pub ranked_by: &'static str,
+    /// Directory of the project structure rolled up into one line (`{count}`)
+    pub index_rollup: &'static str,
+    /// Files left out of the project structure (`{count}`)
+    pub index_omitted: &'static str,
+
+    /// Title comment of the YAML output
+    pub context_title: &'static str,
+    /// Reminder that the query goes after the context
+    pub query_at_end: &'static str,
+    /// Marker above the query placeholder
+    pub query_below: &'static str,
+    /// Query placeholder
+    pub query_placeholder: &'static str,
+
+    /// Note closing output cut at the token limit
+    pub output_truncated: &'static str,
+
+    /// Title of the list of files already sent in a previous context
+    pub unchanged_title: &'static str,
+    /// Introduction of that list (`{count}`)
+    pub unchanged_intro: &'static str,
+    /// Title of the list of files removed since the previous context
+    pub removed_title: &'static str,
+    pub hash: &'static str,
+
+    pub sampling_title: &'static str,
+    /// Introduction of the sampling report (`{budget}`)
+    pub sampling_intro: &'static str,
+    /// `{count}`
+    pub sampled_full: &'static str,
+    /// `{count}`
+    pub sampled_signatures: &'static str,
+    /// `{count}`, `{modules}`
+    pub sampled_representative: &'static str,
+    /// `{count}`
+    pub sampled_omitted: &'static str,
+    /// Files kept as outlines only (`{count}`)
+    pub sampled_outlines: &'static str,
+    /// Note on a file whose body was left out, leaving only its outline
+    pub outline_only: &'static str,
+    pub module: &'static str,
+    pub remaining_files: &'static str,
+    pub sampled: &'static str,
+    pub sampled_files: &'static str,
+
+    pub budget_title: &'static str,
+    /// Introduction of the list of files trimmed to the budget (`{budget}`)
+    pub budget_intro: &'static str,
+    /// `{count}`
+    pub budget_truncated: &'static str,
+    /// Lines kept of a truncated file (`{kept}`, `{total}`)
+    pub budget_kept_lines: &'static str,
+
+    pub contracts_title: &'static str,
+    /// Introduction of the contract summaries (`{count}`)
+    pub contracts_intro: &'static str,
+    /// Title of the commit range section
+    pub range_title: &'static str,
+    /// Introduction of the commit range section (`{files}`, `{commits}`, `{from}`, `{to}`)
+    pub range_intro: &'static str,
+}
+
+static EN: Strings = Strings {
+    guide_purpose: "This is a comprehensive code context for the {repo} repository, optimized for AI-assisted code understanding and generation.",
+    guide_overview: "Start with the <overview> section to understand the project's purpose and structure",
+    guide_entry_points: "Check <entry_points> to find main application files",
+    guide_repository_map: "Use <repository_map> to understand relationships between modules",
+    guide_file_order: "Files are ordered by importance - most critical files come first",
+
+    repository: "Repository",
+    overview: "Overview",
+    metric: "Metric",
+    value: "Value",
+    files: "Files",
+    lines: "Lines",
+    tokens: "Tokens",
+    primary_language: "Primary Language",
+    framework: "Framework",
+    topics: "Topics",
+    commands: "Commands",
+    command: "Command",
+    source: "Source",
+    languages: "Languages",
+    language: "Language",
+    percentage: "Percentage",
+    repository_map: "Repository Map",
+    key_symbols: "Key Symbols",
+    rank: "Rank",
+    symbol: "Symbol",
+    kind: "Type",
+    file: "File",
+    line: "Line",
+    module_dependencies: "Module Dependencies",
+    dependency_usage: "Dependency Usage",
+    project_structure: "Project Structure",
+    directory_structure: "Directory Structure",
+    git: "Git",
+    ranked_by: "Ranked by",
+    index_rollup: "{count} more files",
+    index_omitted: "{count} less important files are not listed",
+
+    context_title: "Repository Context for Gemini",
+    query_at_end: "Note: Query should be at the END of this context",
+    query_below: "INSERT YOUR QUERY BELOW THIS LINE",
+    query_placeholder: "Your question about this repository",
+
+    output_truncated: "Output truncated to fit token limit",
+
+    unchanged_title: "Unchanged Since Previous Context",
+    unchanged_intro: "{count} files are the same as in the previous context and are not repeated:",
+    removed_title: "Removed Since Previous Context",
+    hash: "hash",
+
+    sampling_title: "Sampling Report",
+    sampling_intro: "This repository exceeds the {budget} token budget, so this context is a sample:",
+    sampled_full: "Full content: {count} top-ranked files",
+    sampled_signatures: "Signatures only: {count} files",
+    sampled_representative: "Representative samples: {count} files from {modules} modules",
+    sampled_omitted: "Omitted: {count} files",
+    sampled_outlines: "Outlined: {count} files (symbols and line ranges only)",
+    outline_only: "Outline only: symbols and line ranges, content left out for the token budget",
+    module: "Module",
+    remaining_files: "Remaining files",
+    sampled: "Sampled",
+    sampled_files: "Sampled files",
+
+    budget_title: "Token Budget",
+    budget_intro: "Files were trimmed to fit the {budget} token budget, in proportion to their importance:",
+    budget_truncated: "Truncated: {count} files",
+    budget_kept_lines: "kept lines 1-{kept} of {total}",
+
+    contracts_title: "API Contracts",
+    contracts_intro: "{count} contract files are summarized here instead of included in full:",
+    range_title: "Changes in Range",
+    range_intro: "{files} files changed between {from} and {to} in {commits} commits:",
+};
+
+static JA: Strings = Strings {
+    guide_purpose: "これは {repo} リポジトリの包括的なコードコンテキストで、AI によるコードの理解と生成に最適化されています。",
+    guide_overview: "まず <overview> セクションでプロジェクトの目的と構成を把握してください",
+    guide_entry_points: "<entry_points> でアプリケーションの主要なファイルを確認してください",
+    guide_repository_map: "<repository_map> でモジュール間の関係を把握してください",
+    guide_file_order: "ファイルは重要度順に並んでおり、最も重要なファイルが先頭にあります",
+
+    repository: "リポジトリ",
+    overview: "概要",
+    metric: "項目",
+    value: "値",
+    files: "ファイル数",
+    lines: "行数",
+    tokens: "トークン数",
+    primary_language: "主要言語",
+    framework: "フレームワーク",
+    topics: "トピック",
+    commands: "コマンド一覧",
+    command: "コマンド",
+    source: "定義元",
+    languages: "言語",
+    language: "言語",
+    percentage: "割合",
+    repository_map: "リポジトリマップ",
+    key_symbols: "主要シンボル",
+    rank: "順位",
+    symbol: "シンボル",
+    kind: "種類",
+    file: "ファイル",
+    line: "行",
+    module_dependencies: "モジュールの依存関係",
+    dependency_usage: "外部依存関係の利用箇所",
+    project_structure: "プロジェクト構成",
+    directory_structure: "ディレクトリ構成",
+    git: "Git",
+    ranked_by: "順位の根拠",
+    index_rollup: "ほか {count} ファイル",
+    index_omitted: "重要度の低い {count} ファイルは省略されています",
+
+    context_title: "Gemini 向けリポジトリコンテキスト",
+    query_at_end: "注: 質問はこのコンテキストの最後に記述してください",
+    query_below: "この行の下に質問を記入してください",
+    query_placeholder: "このリポジトリについての質問",
+
+    output_truncated: "トークン上限に収めるため出力を切り詰めました",
+
+    unchanged_title: "前回のコンテキストから変更なし",
+    unchanged_intro: "{count} 個のファイルは前回のコンテキストと同一のため、再掲していません:",
+    removed_title: "前回のコンテキスト以降に削除",
+    hash: "ハッシュ",
+
+    sampling_title: "サンプリングレポート",
+    sampling_intro: "このリポジトリは {budget} トークンの予算を超えるため、このコンテキストはサンプルです:",
+    sampled_full: "全文: 上位 {count} ファイル",
+    sampled_signatures: "シグネチャのみ: {count} ファイル",
+    sampled_representative: "代表サンプル: {modules} モジュールから {count} ファイル",
+    sampled_omitted: "省略: {count} ファイル",
+    sampled_outlines: "アウトラインのみ: {count} ファイル (シンボルと行範囲)",
+    outline_only: "アウトラインのみ: トークン予算のため本文は省略されています",
+    module: "モジュール",
+    remaining_files: "残りのファイル数",
+    sampled: "サンプル数",
+    sampled_files: "サンプルしたファイル",
+
+    budget_title: "トークン予算",
+    budget_intro: "{budget} トークンの予算に収めるため、重要度に応じてファイルを切り詰めました:",
+    budget_truncated: "切り詰め: {count} ファイル",
+    budget_kept_lines: "{total} 行中 1-{kept} 行を掲載",
+
+    contracts_title: "API コントラクト",
+    contracts_intro: "{count} 件のコントラクトファイルは全文ではなく要約として掲載しています:",
+    range_title: "範囲内の変更",
+    range_intro: "{from} から {to} までの {commits} 件のコミットで {files} 個のファイルが変更されました:",
+};
+
+static DE: Strings = Strings {
+    guide_purpose: "Dies ist ein umfassender Code-Kontext für das Repository {repo}, optimiert für KI-gestütztes Verstehen und Erzeugen von Code.",
+    guide_overview: "Beginne mit dem Abschnitt <overview>, um Zweck und Aufbau des Projekts zu verstehen",
+    guide_entry_points: "In <entry_points> stehen die wichtigsten Einstiegsdateien der Anwendung",
+    guide_repository_map: "Nutze <repository_map>, um die Beziehungen zwischen Modulen zu verstehen",
+    guide_file_order: "Die Dateien sind nach Wichtigkeit sortiert - die wichtigsten zuerst",
+
+    repository: "Repository",
+    overview: "Überblick",
+    metric: "Kennzahl",
+    value: "Wert",
+    files: "Dateien",
+    lines: "Zeilen",
+    tokens: "Tokens",
+    primary_language: "Hauptsprache",
+    framework: "Framework",
+    topics: "Themen",
+    commands: "Befehle",
+    command: "Befehl",
+    source: "Quelle",
+    languages: "Sprachen",
+    language: "Sprache",
+    percentage: "Anteil",
+    repository_map: "Repository-Übersicht",
+    key_symbols: "Wichtige Symbole",
+    rank: "Rang",
+    symbol: "Symbol",
+    kind: "Art",
+    file: "Datei",
+    line: "Zeile",
+    module_dependencies: "Modulabhängigkeiten",
+    dependency_usage: "Nutzung externer Abhängigkeiten",
+    project_structure: "Projektstruktur",
+    directory_structure: "Verzeichnisstruktur",
+    git: "Git",
+    ranked_by: "Rang durch",
+    index_rollup: "{count} weitere Dateien",
+    index_omitted: "{count} weniger wichtige Dateien sind nicht aufgeführt",
+
+    context_title: "Repository-Kontext für Gemini",
+    query_at_end: "Hinweis: Die Frage gehört an das ENDE dieses Kontexts",
+    query_below: "FRAGE UNTER DIESER ZEILE EINFÜGEN",
+    query_placeholder: "Deine Frage zu diesem Repository",
+
+    output_truncated: "Ausgabe gekürzt, um das Token-Limit einzuhalten",
+
+    unchanged_title: "Unverändert seit dem vorherigen Kontext",
+    unchanged_intro: "{count} Dateien sind identisch mit dem vorherigen Kontext und werden nicht wiederholt:",
+    removed_title: "Seit dem vorherigen Kontext entfernt",
+    hash: "Hash",
+
+    sampling_title: "Stichprobenbericht",
+    sampling_intro: "Dieses Repository übersteigt das Budget von {budget} Tokens, daher ist dieser Kontext eine Stichprobe:",
+    sampled_full: "Vollständiger Inhalt: {count} am höchsten eingestufte Dateien",
+    sampled_signatures: "Nur Signaturen: {count} Dateien",
+    sampled_representative: "Repräsentative Stichproben: {count} Dateien aus {modules} Modulen",
+    sampled_omitted: "Ausgelassen: {count} Dateien",
+    sampled_outlines: "Nur Gliederung: {count} Dateien (Symbole und Zeilenbereiche)",
+    outline_only: "Nur Gliederung: Inhalt wegen des Token-Budgets ausgelassen",
+    module: "Modul",
+    remaining_files: "Verbleibende Dateien",
+    sampled: "Stichproben",
+    sampled_files: "Ausgewählte Dateien",
+
+    budget_title: "Token-Budget",
+    budget_intro: "Dateien wurden nach Wichtigkeit gekürzt, um das Budget von {budget} Tokens einzuhalten:",
+    budget_truncated: "Gekürzt: {count} Dateien",
+    budget_kept_lines: "Zeilen 1-{kept} von {total} behalten",
+
+    contracts_title: "API-Verträge",
+    contracts_intro: "{count} Vertragsdateien sind hier zusammengefasst statt vollständig enthalten:",
+    range_title: "Änderungen im Bereich",
+    range_intro: "{files} Dateien zwischen {from} und {to} in {commits} Commits geändert:",
+};
+
+static ES: Strings = Strings {
+    guide_purpose: "Este es un contexto de código completo del repositorio {repo}, optimizado para la comprensión y generación de código asistidas por IA.",
+    guide_overview: "Empieza por la sección <overview> para entender el propósito y la estructura del proyecto",
+    guide_entry_points: "Consulta <entry_points> para encontrar los archivos principales de la aplicación",
+    guide_repository_map: "Usa <repository_map> para entender las relaciones entre módulos",
+    guide_file_order: "Los archivos están ordenados por importancia: los más críticos aparecen primero",
+
+    repository: "Repositorio",
+    overview: "Resumen",
+    metric: "Métrica",
+    value: "Valor",
+    files: "Archivos",
+    lines: "Líneas",
+    tokens: "Tokens",
+    primary_language: "Lenguaje principal",
+    framework: "Framework",
+    topics: "Temas",
+    commands: "Comandos",
+    command: "Comando",
+    source: "Origen",
+    languages: "Lenguajes",
+    language: "Lenguaje",
+    percentage: "Porcentaje",
+    repository_map: "Mapa del repositorio",
+    key_symbols: "Símbolos clave",
+    rank: "Posición",
+    symbol: "Símbolo",
+    kind: "Tipo",
+    file: "Archivo",
+    line: "Línea",
+    module_dependencies: "Dependencias entre módulos",
+    dependency_usage: "Uso de dependencias externas",
+    project_structure: "Estructura del proyecto",
+    directory_structure: "Estructura de directorios",
+    git: "Git",
+    ranked_by: "Clasificado por",
+    index_rollup: "{count} archivos más",
+    index_omitted: "{count} archivos menos importantes no se muestran",
+
+    context_title: "Contexto del repositorio para Gemini",
+    query_at_end: "Nota: la consulta debe ir al FINAL de este contexto",
+    query_below: "ESCRIBE TU CONSULTA DEBAJO DE ESTA LÍNEA",
+    query_placeholder: "Tu pregunta sobre este repositorio",
+
+    output_truncated: "Salida recortada para ajustarse al límite de tokens",
+
+    unchanged_title: "Sin cambios desde el contexto anterior",
+    unchanged_intro: "{count} archivos son iguales que en el contexto anterior y no se repiten:",
+    removed_title: "Eliminados desde el contexto anterior",
+    hash: "hash",
+
+    sampling_title: "Informe de muestreo",
+    sampling_intro: "Este repositorio supera el presupuesto de {budget} tokens, así que este contexto es una muestra:",
+    sampled_full: "Contenido completo: {count} archivos mejor clasificados",
+    sampled_signatures: "Solo firmas: {count} archivos",
+    sampled_representative: "Muestras representativas: {count} archivos de {modules} módulos",
+    sampled_omitted: "Omitidos: {count} archivos",
+    sampled_outlines: "Solo esquema: {count} archivos (símbolos y rangos de líneas)",
+    outline_only: "Solo esquema: contenido omitido por el presupuesto de tokens",
+    module: "Módulo",
+    remaining_files: "Archivos restantes",
+    sampled: "Muestreados",
+    sampled_files: "Archivos muestreados",
+
+    budget_title: "Presupuesto de tokens",
+    budget_intro: "Los archivos se recortaron según su importancia para ajustarse al presupuesto de {budget} tokens:",
+    budget_truncated: "Truncados: {count} archivos",
+    budget_kept_lines: "se conservan las líneas 1-{kept} de {total}",
+
+    contracts_title: "Contratos de API",
+    contracts_intro: "{count} archivos de contrato se resumen aquí en lugar de incluirse completos:",
+    range_title: "Cambios en el rango",
+    range_intro: "{files} archivos cambiados entre {from} y {to} en {commits} commits:",
+};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_code() {
+        assert_eq!(Locale::from_code("ja"), Some(Locale::Ja));
+        assert_eq!(Locale::from_code("de-AT"), Some(Locale::De));
+        assert_eq!(Locale::from_code("ES_mx"), Some(Locale::Es));
+        assert_eq!(Locale::from_code("fr"), None);
+        assert_eq!(Locale::default().code(), "en");
+    }
+
+    #[test]
+    fn test_templates_keep_placeholders() {
+        for locale in [Locale::En, Locale::Ja, Locale::De, Locale::Es] {
+            let strings = locale.strings();
+            assert!(strings.guide_purpose.contains("{repo}"));
+            assert!(strings.guide_overview.contains("<overview>"));
+            assert!(strings.unchanged_intro.contains("{count}"));
+            assert!(strings.sampling_intro.contains("{budget}"));
+            assert!(strings.sampled_representative.contains("{modules}"));
+            assert!(strings.sampled_outlines.contains("{count}"));
+            assert!(strings.index_rollup.contains("{count}"));
+            assert!(strings.index_omitted.contains("{count}"));
+        }
+    }
+}
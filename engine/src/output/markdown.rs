@@ -1,6 +1,7 @@
 //! GPT-optimized Markdown output formatter
 
-use crate::output::Formatter;
+use crate::languages;
+use crate::output::{FormatVersion, Formatter, IndexEntry, IndexLimit, Locale};
 use crate::repomap::RepoMap;
 use crate::types::Repository;
 use std::fmt::Write;
@@ -15,6 +16,12 @@ pub struct MarkdownFormatter {
     include_tree: bool,
     /// Include line numbers in code
     include_line_numbers: bool,
+    /// Files shown in the file tree
+    index_limit: IndexLimit,
+    /// Language of titles and labels
+    locale: Locale,
+    /// Layout version to write
+    version: FormatVersion,
 }
 
 impl MarkdownFormatter {
@@ -25,6 +32,9 @@ impl MarkdownFormatter {
             include_mermaid: true,
             include_tree: true,
             include_line_numbers: true,
+            index_limit: IndexLimit::none(),
+            locale: Locale::default(),
+            version: FormatVersion::default(),
         }
     }
 
@@ -46,15 +56,49 @@ impl MarkdownFormatter {
         self
     }
 
+    /// Limit the files shown in the file tree
+    pub fn with_index_limit(mut self, limit: IndexLimit) -> Self {
+        self.index_limit = limit;
+        self
+    }
+
+    /// Set the language of titles and labels
+    pub fn with_locale(mut self, locale: Locale) -> Self {
+        self.locale = locale;
+        self
+    }
+
+    /// Set the layout version to write
+    pub fn with_format_version(mut self, version: FormatVersion) -> Self {
+        self.version = version;
+        self
+    }
+
+    /// Render only the repository map section, e.g. for a saved map
+    pub fn format_map(&self, map: &RepoMap) -> String {
+        let mut output = String::new();
+        self.write_repomap(&mut output, map);
+        output
+    }
+
     fn write_header(&self, output: &mut String, repo: &Repository) {
-        writeln!(output, "# Repository: {}", repo.name).unwrap();
+        let text = self.locale.strings();
+        writeln!(output, "# {}: {}", text.repository, repo.name).unwrap();
+        if let Some(version) = self.version.marker() {
+            writeln!(output, "<!-- format_version: {} -->", version).unwrap();
+        }
         writeln!(output).unwrap();
 
         // Quick stats
         writeln!(
             output,
-            "> **Files**: {} | **Lines**: {} | **Tokens**: {}",
-            repo.metadata.total_files, repo.metadata.total_lines, repo.metadata.total_tokens.gpt4o
+            "> **{}**: {} | **{}**: {} | **{}**: {}",
+            text.files,
+            repo.metadata.total_files,
+            text.lines,
+            repo.metadata.total_lines,
+            text.tokens,
+            repo.metadata.total_tokens.gpt4o
         )
         .unwrap();
         writeln!(output).unwrap();
@@ -65,30 +109,55 @@ impl MarkdownFormatter {
             return;
         }
 
-        writeln!(output, "## Overview").unwrap();
+        let text = self.locale.strings();
+        writeln!(output, "## {}", text.overview).unwrap();
         writeln!(output).unwrap();
 
         // Stats table
-        writeln!(output, "| Metric | Value |").unwrap();
+        writeln!(output, "| {} | {} |", text.metric, text.value).unwrap();
         writeln!(output, "|--------|-------|").unwrap();
-        writeln!(output, "| Files | {} |", repo.metadata.total_files).unwrap();
-        writeln!(output, "| Lines | {} |", repo.metadata.total_lines).unwrap();
+        writeln!(output, "| {} | {} |", text.files, repo.metadata.total_files).unwrap();
+        writeln!(output, "| {} | {} |", text.lines, repo.metadata.total_lines).unwrap();
 
         if let Some(lang) = repo.metadata.languages.first() {
-            writeln!(output, "| Primary Language | {} |", lang.language).unwrap();
+            writeln!(output, "| {} | {} |", text.primary_language, lang.language).unwrap();
         }
 
         if let Some(framework) = &repo.metadata.framework {
-            writeln!(output, "| Framework | {} |", framework).unwrap();
+            writeln!(output, "| {} | {} |", text.framework, framework).unwrap();
+        }
+
+        if !repo.metadata.topics.is_empty() {
+            let topics: Vec<&str> = repo.metadata.topics.iter().map(|t| t.id()).collect();
+            writeln!(output, "| {} | {} |", text.topics, topics.join(", ")).unwrap();
         }
 
         writeln!(output).unwrap();
 
+        if !repo.metadata.commands.is_empty() {
+            writeln!(output, "### {}", text.commands).unwrap();
+            writeln!(output).unwrap();
+            writeln!(output, "| {} | {} | {} |", text.kind, text.command, text.source).unwrap();
+            writeln!(output, "|------|---------|--------|").unwrap();
+            for command in &repo.metadata.commands {
+                writeln!(
+                    output,
+                    "| {} | `{}` | {} |",
+                    command.kind.name(),
+                    command.command.replace('|', "\\|"),
+                    command.source
+                )
+                .unwrap();
+            }
+            writeln!(output).unwrap();
+        }
+
         // Language breakdown
         if repo.metadata.languages.len() > 1 {
-            writeln!(output, "### Languages").unwrap();
+            writeln!(output, "### {}", text.languages).unwrap();
             writeln!(output).unwrap();
-            writeln!(output, "| Language | Files | Percentage |").unwrap();
+            writeln!(output, "| {} | {} | {} |", text.language, text.files, text.percentage)
+                .unwrap();
             writeln!(output, "|----------|-------|------------|").unwrap();
 
             for lang in &repo.metadata.languages {
@@ -104,15 +173,21 @@ impl MarkdownFormatter {
     }
 
     fn write_repomap(&self, output: &mut String, map: &RepoMap) {
-        writeln!(output, "## Repository Map").unwrap();
+        let text = self.locale.strings();
+        writeln!(output, "## {}", text.repository_map).unwrap();
         writeln!(output).unwrap();
         writeln!(output, "{}", map.summary).unwrap();
         writeln!(output).unwrap();
 
         // Key symbols table
-        writeln!(output, "### Key Symbols").unwrap();
+        writeln!(output, "### {}", text.key_symbols).unwrap();
         writeln!(output).unwrap();
-        writeln!(output, "| Rank | Symbol | Type | File | Line |").unwrap();
+        writeln!(
+            output,
+            "| {} | {} | {} | {} | {} |",
+            text.rank, text.symbol, text.kind, text.file, text.line
+        )
+        .unwrap();
         writeln!(output, "|------|--------|------|------|------|").unwrap();
 
         for sym in map.key_symbols.iter().take(15) {
@@ -125,9 +200,20 @@ impl MarkdownFormatter {
         }
         writeln!(output).unwrap();
 
+        // External dependencies by usage
+        if !map.external_usage.is_empty() {
+            writeln!(output, "### {}", text.dependency_usage).unwrap();
+            writeln!(output).unwrap();
+            for usage in &map.external_usage {
+                let files: Vec<String> = usage.files.iter().map(|f| format!("`{}`", f)).collect();
+                writeln!(output, "- `{}` → {}", usage.package, files.join(", ")).unwrap();
+            }
+            writeln!(output).unwrap();
+        }
+
         // Mermaid dependency graph
         if self.include_mermaid && !map.module_graph.edges.is_empty() {
-            writeln!(output, "### Module Dependencies").unwrap();
+            writeln!(output, "### {}", text.module_dependencies).unwrap();
             writeln!(output).unwrap();
             writeln!(output, "```mermaid").unwrap();
             writeln!(output, "graph LR").unwrap();
@@ -159,21 +245,32 @@ impl MarkdownFormatter {
             return;
         }
 
-        writeln!(output, "## Project Structure").unwrap();
+        let text = self.locale.strings();
+        writeln!(output, "## {}", text.project_structure).unwrap();
         writeln!(output).unwrap();
         writeln!(output, "```").unwrap();
 
-        // Build tree structure
-        let mut paths: Vec<_> = repo
-            .files
+        // Build tree structure; a rolled-up directory ends in a line counting
+        // its remaining files, sorted after everything else in it
+        let index = self.index_limit.apply(repo);
+        let mut paths: Vec<(String, Option<usize>)> = index
+            .entries
             .iter()
-            .map(|f| f.relative_path.as_str())
+            .map(|entry| match entry {
+                IndexEntry::File(file) => (file.relative_path.clone(), None),
+                IndexEntry::Rollup { directory: ".", files } => {
+                    (ROLLUP_KEY.to_owned(), Some(files.len()))
+                },
+                IndexEntry::Rollup { directory, files } => {
+                    (format!("{}/{}", directory, ROLLUP_KEY), Some(files.len()))
+                },
+            })
             .collect();
         paths.sort();
 
         // Simple tree rendering
         let mut prev_parts: Vec<&str> = Vec::new();
-        for path in paths {
+        for (path, rollup) in &paths {
             let parts: Vec<_> = path.split('/').collect();
 
             // Find common prefix with previous path
@@ -189,6 +286,11 @@ impl MarkdownFormatter {
             // Print new parts
             for (i, part) in parts.iter().enumerate().skip(common) {
                 let indent = "  ".repeat(i);
+                if let Some(count) = rollup.filter(|_| i == parts.len() - 1) {
+                    let label = text.index_rollup.replace("{count}", &count.to_string());
+                    writeln!(output, "{}… {}", indent, label).unwrap();
+                    continue;
+                }
                 let prefix = if i == parts.len() - 1 {
                     "📄 "
                 } else {
@@ -202,33 +304,69 @@ impl MarkdownFormatter {
 
         writeln!(output, "```").unwrap();
         writeln!(output).unwrap();
+        if !index.omitted.is_empty() {
+            let note = text
+                .index_omitted
+                .replace("{count}", &index.omitted.len().to_string());
+            writeln!(output, "*{}*", note).unwrap();
+            writeln!(output).unwrap();
+        }
     }
 
     fn write_files(&self, output: &mut String, repo: &Repository) {
-        writeln!(output, "## Files").unwrap();
+        let text = self.locale.strings();
+        writeln!(output, "## {}", text.files).unwrap();
         writeln!(output).unwrap();
 
         for file in &repo.files {
-            if let Some(content) = &file.content {
+            if file.content.is_some() || file.outline_only {
                 writeln!(output, "### {}", file.relative_path).unwrap();
                 writeln!(output).unwrap();
 
                 // File metadata
-                writeln!(
+                write!(
                     output,
-                    "> **Tokens**: {} | **Language**: {}",
+                    "> **{}**: {} | **{}**: {}",
+                    text.tokens,
                     file.token_count.gpt4o,
-                    file.language.as_deref().unwrap_or("unknown")
+                    text.language,
+                    file.language
+                        .as_deref()
+                        .map_or("unknown", languages::display_name)
                 )
                 .unwrap();
+                if let Some(change) = &file.git_change {
+                    write!(output, " | **{}**: {}", text.git, change.describe()).unwrap();
+                }
+                if !file.rank_factors.is_empty() {
+                    write!(output, " | **{}**: {}", text.ranked_by, file.rank_factors.join("; "))
+                        .unwrap();
+                }
                 writeln!(output).unwrap();
+                writeln!(output).unwrap();
+                if let Some(summary) = &file.summary {
+                    writeln!(output, "{}", summary).unwrap();
+                    writeln!(output).unwrap();
+                }
+
+                let Some(content) = &file.content else {
+                    writeln!(output, "*{}*", text.outline_only).unwrap();
+                    writeln!(output).unwrap();
+                    writeln!(output, "```").unwrap();
+                    for line in file.outline() {
+                        writeln!(output, "{}", line).unwrap();
+                    }
+                    writeln!(output, "```").unwrap();
+                    writeln!(output).unwrap();
+                    continue;
+                };
 
                 // Code block with language
-                let lang = file.language.as_deref().unwrap_or("");
+                let lang = file.language.as_deref().map_or("", languages::fence_tag);
                 writeln!(output, "```{}", lang).unwrap();
                 if self.include_line_numbers {
-                    for (i, line) in content.lines().enumerate() {
-                        writeln!(output, "{:4} {}", i + 1, line).unwrap();
+                    for (number, line) in file.numbered_lines() {
+                        writeln!(output, "{:4} {}", number, line).unwrap();
                     }
                 } else {
                     writeln!(output, "{}", content).unwrap();
@@ -240,6 +378,9 @@ impl MarkdownFormatter {
     }
 }
 
+/// Sort key of a roll-up line: after every file name in its directory
+const ROLLUP_KEY: &str = "\u{10FFFF}";
+
 impl Default for MarkdownFormatter {
     fn default() -> Self {
         Self::new()
@@ -292,9 +433,9 @@ mod tests {
                 language: Some("python".to_string()),
                 size_bytes: 100,
                 token_count: TokenCounts { claude: 50, gpt4o: 48, gpt4: 49, gemini: 47, llama: 46 },
-                symbols: Vec::new(),
                 importance: 0.8,
                 content: Some("def main():\n    print('hello')".to_string()),
+                ..Default::default()
             }],
             metadata: RepoMetadata {
                 total_files: 1,
@@ -318,6 +459,8 @@ mod tests {
                 commit: None,
                 directory_structure: None,
                 external_dependencies: vec![],
+                commands: Vec::new(),
+                topics: Vec::new(),
                 git_history: None,
             },
         }
@@ -335,4 +478,14 @@ mod tests {
         assert!(output.contains("## Overview"));
         assert!(output.contains("```python"));
     }
+
+    #[test]
+    fn test_format_map_only() {
+        let repo = create_test_repo();
+        let map = RepoMapGenerator::new(1000).generate(&repo);
+
+        let output = MarkdownFormatter::new().format_map(&map);
+        assert!(output.starts_with("## Repository Map"));
+        assert!(!output.contains("```python"));
+    }
 }
@@ -1,14 +1,37 @@
 //! Output formatters for different LLM models
 
+mod exec;
+mod file_index;
+mod lint;
+mod locale;
 mod markdown;
+mod paths;
+mod sections;
+mod signatures;
 mod toon;
+mod version;
 mod xml;
 
+use crate::cancel::{CancellationToken, Cancelled};
+use crate::languages;
 use crate::repomap::RepoMap;
 use crate::types::Repository;
-
+use lint::escape_controls;
+use std::borrow::Cow;
+
+pub use exec::{ExecError, ExecFormatter, DEFAULT_EXEC_TIMEOUT, DEFAULT_MAX_OUTPUT, EXEC_PREFIX};
+pub use file_index::IndexLimit;
+pub(crate) use file_index::{total_tokens, IndexEntry};
+pub use lint::{lint_output, LintError};
+pub use locale::{Locale, Strings};
 pub use markdown::MarkdownFormatter;
+pub use paths::PathRewriter;
+pub use sections::Section;
+pub use signatures::SignatureFormatter;
+pub(crate) use signatures::{declaration, is_exported};
 pub use toon::ToonFormatter;
+pub use version::{FormatVersion, FormatVersionError, FORMAT_VERSION, OLDEST_FORMAT_VERSION};
+pub(crate) use xml::escape_xml;
 pub use xml::XmlFormatter;
 
 /// Output format type
@@ -27,6 +50,43 @@ pub enum OutputFormat {
     Toon,
     /// Plain text (simple, no formatting)
     Plain,
+    /// Tree, exported signatures and dependency edges only
+    Signatures,
+}
+
+impl OutputFormat {
+    /// Infer an output format from a file extension
+    pub fn from_extension(ext: &str) -> Option<Self> {
+        match ext.to_lowercase().as_str() {
+            "xml" => Some(Self::Xml),
+            "md" | "markdown" => Some(Self::Markdown),
+            "json" => Some(Self::Json),
+            "yaml" | "yml" => Some(Self::Yaml),
+            "toon" => Some(Self::Toon),
+            "txt" | "text" => Some(Self::Plain),
+            "sig" => Some(Self::Signatures),
+            _ => None,
+        }
+    }
+
+    /// Conventional file extension, the inverse of [`OutputFormat::from_extension`]
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Xml => "xml",
+            Self::Markdown => "md",
+            Self::Json => "json",
+            Self::Yaml => "yaml",
+            Self::Toon => "toon",
+            Self::Plain => "txt",
+            Self::Signatures => "sig",
+        }
+    }
+
+    /// Whether the output is a document that text appended or prepended to
+    /// it would break, so extra content has to go in as a [`Section`]
+    pub fn is_structured(&self) -> bool {
+        matches!(self, Self::Xml | Self::Json | Self::Yaml | Self::Toon)
+    }
 }
 
 /// Output formatter trait
@@ -34,11 +94,57 @@ pub trait Formatter {
     /// Format repository with map
     fn format(&self, repo: &Repository, map: &RepoMap) -> String;
 
+    /// Format repository with map, followed by report sections
+    ///
+    /// Text formats append each section's Markdown; structured formats put
+    /// its data inside the document, so the output still parses.
+    fn format_with_sections(
+        &self,
+        repo: &Repository,
+        map: &RepoMap,
+        sections: &[Section],
+    ) -> String {
+        let mut output = self.format(repo, map);
+        for section in sections {
+            output.push_str(&section.text);
+        }
+        output
+    }
+
     /// Format repository only
     fn format_repo(&self, repo: &Repository) -> String;
 
     /// Get format name
     fn name(&self) -> &'static str;
+
+    /// Format repository with map unless `cancel` is cancelled before or
+    /// while formatting; no partial output is returned
+    fn format_cancellable(
+        &self,
+        repo: &Repository,
+        map: &RepoMap,
+        cancel: &CancellationToken,
+    ) -> Result<String, Cancelled> {
+        cancel.check()?;
+        let output = self.format(repo, map);
+        cancel.check()?;
+        Ok(output)
+    }
+
+    /// [`format_with_sections`](Self::format_with_sections) unless `cancel`
+    /// is cancelled before or while formatting; no partial output is returned
+    fn format_with_sections_cancellable(
+        &self,
+        repo: &Repository,
+        map: &RepoMap,
+        sections: &[Section],
+        cancel: &CancellationToken,
+    ) -> Result<String, Cancelled> {
+        cancel.check()?;
+        let output = self.format_with_sections(repo, map, sections);
+        cancel.check()?;
+        Ok(output)
+    }
 }
 
 /// Output formatter factory
@@ -57,12 +163,12 @@ impl OutputFormatter {
 
     /// Create JSON formatter
     pub fn json() -> JsonFormatter {
-        JsonFormatter
+        JsonFormatter::new()
     }
 
     /// Create YAML formatter (Gemini)
     pub fn gemini() -> YamlFormatter {
-        YamlFormatter
+        YamlFormatter::new()
     }
 
     /// Create formatter by format type
@@ -72,32 +178,83 @@ impl OutputFormatter {
 
     /// Create formatter by format type with line numbers option
     pub fn by_format_with_options(format: OutputFormat, line_numbers: bool) -> Box<dyn Formatter> {
-        Self::by_format_with_all_options(format, line_numbers, true)
+        Self::by_format_with_all_options(format, line_numbers, true, Locale::default())
     }
 
     /// Create formatter by format type with all options
+    ///
+    /// `locale` applies to the prose around the content; JSON and TOON have
+    /// none.
     pub fn by_format_with_all_options(
         format: OutputFormat,
         line_numbers: bool,
         show_file_index: bool,
+        locale: Locale,
+    ) -> Box<dyn Formatter> {
+        Self::by_format_version(
+            format,
+            FormatVersion::CURRENT,
+            line_numbers,
+            show_file_index,
+            IndexLimit::none(),
+            locale,
+        )
+    }
+
+    /// Create formatter writing an older layout, with all options
+    ///
+    /// Which versions can be requested follows the deprecation policy of
+    /// [`FormatVersion::new`]; show [`FormatVersion::deprecation`] to users
+    /// who ask for a deprecated one. `index_limit` applies to the file index
+    /// of XML and TOON and the project structure of Markdown.
+    pub fn by_format_version(
+        format: OutputFormat,
+        version: FormatVersion,
+        line_numbers: bool,
+        show_file_index: bool,
+        index_limit: IndexLimit,
+        locale: Locale,
     ) -> Box<dyn Formatter> {
         match format {
             OutputFormat::Xml => Box::new(
                 XmlFormatter::new(true)
                     .with_line_numbers(line_numbers)
-                    .with_file_index(show_file_index),
+                    .with_file_index(show_file_index)
+                    .with_index_limit(index_limit)
+                    .with_locale(locale)
+                    .with_format_version(version),
+            ),
+            OutputFormat::Markdown => Box::new(
+                MarkdownFormatter::new()
+                    .with_line_numbers(line_numbers)
+                    .with_index_limit(index_limit)
+                    .with_locale(locale)
+                    .with_format_version(version),
+            ),
+            OutputFormat::Json => Box::new(JsonFormatter::new().with_format_version(version)),
+            OutputFormat::Yaml => Box::new(
+                YamlFormatter::new()
+                    .with_locale(locale)
+                    .with_format_version(version),
             ),
-            OutputFormat::Markdown => {
-                Box::new(MarkdownFormatter::new().with_line_numbers(line_numbers))
-            },
-            OutputFormat::Json => Box::new(JsonFormatter),
-            OutputFormat::Yaml => Box::new(YamlFormatter),
             OutputFormat::Toon => Box::new(
                 ToonFormatter::new()
                     .with_line_numbers(line_numbers)
-                    .with_file_index(show_file_index),
+                    .with_file_index(show_file_index)
+                    .with_index_limit(index_limit)
+                    .with_format_version(version),
+            ),
+            OutputFormat::Plain => Box::new(
+                PlainFormatter::new()
+                    .with_line_numbers(line_numbers)
+                    .with_locale(locale)
+                    .with_format_version(version),
+            ),
+            OutputFormat::Signatures => Box::new(
+                SignatureFormatter::new()
+                    .with_line_numbers(line_numbers)
+                    .with_format_version(version),
             ),
-            OutputFormat::Plain => Box::new(PlainFormatter::new().with_line_numbers(line_numbers)),
         }
     }
 
@@ -108,21 +265,55 @@ impl OutputFormatter {
 }
 
 /// JSON formatter
-pub struct JsonFormatter;
+pub struct JsonFormatter {
+    /// Layout version to write
+    version: FormatVersion,
+}
+
+impl JsonFormatter {
+    /// Create a new JSON formatter
+    pub fn new() -> Self {
+        Self { version: FormatVersion::default() }
+    }
+
+    /// Set the layout version to write
+    pub fn with_format_version(mut self, version: FormatVersion) -> Self {
+        self.version = version;
+        self
+    }
+}
+
+impl Default for JsonFormatter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
 impl Formatter for JsonFormatter {
     fn format(&self, repo: &Repository, map: &RepoMap) -> String {
+        self.format_with_sections(repo, map, &[])
+    }
+
+    fn format_with_sections(
+        &self,
+        repo: &Repository,
+        map: &RepoMap,
+        sections: &[Section],
+    ) -> String {
         #[derive(serde::Serialize)]
         struct Output<'a> {
             repository: &'a Repository,
             map: &'a RepoMap,
+            #[serde(flatten)]
+            sections: sections::SectionMap<'a>,
         }
 
-        serde_json::to_string_pretty(&Output { repository: repo, map }).unwrap_or_default()
+        let output = Output { repository: repo, map, sections: sections::SectionMap(sections) };
+        serde_json::to_string_pretty(&Versioned::new(self.version, &output)).unwrap_or_default()
     }
 
     fn format_repo(&self, repo: &Repository) -> String {
-        serde_json::to_string_pretty(repo).unwrap_or_default()
+        serde_json::to_string_pretty(&Versioned::new(self.version, repo)).unwrap_or_default()
     }
 
     fn name(&self) -> &'static str {
@@ -134,12 +325,20 @@ impl Formatter for JsonFormatter {
 pub struct PlainFormatter {
     /// Include line numbers in code
     include_line_numbers: bool,
+    /// Language of titles and labels
+    locale: Locale,
+    /// Layout version to write
+    version: FormatVersion,
 }
 
 impl PlainFormatter {
     /// Create a new plain formatter
     pub fn new() -> Self {
-        Self { include_line_numbers: true }
+        Self {
+            include_line_numbers: true,
+            locale: Locale::default(),
+            version: FormatVersion::default(),
+        }
     }
 
     /// Set line numbers option
@@ -147,6 +346,24 @@ impl PlainFormatter {
         self.include_line_numbers = enabled;
         self
     }
+
+    /// Set the language of titles and labels
+    pub fn with_locale(mut self, locale: Locale) -> Self {
+        self.locale = locale;
+        self
+    }
+
+    /// Set the layout version to write
+    pub fn with_format_version(mut self, version: FormatVersion) -> Self {
+        self.version = version;
+        self
+    }
+
+    fn write_marker(&self, output: &mut String) {
+        if let Some(version) = self.version.marker() {
+            output.push_str(&format!("format_version: {}\n", version));
+        }
+    }
 }
 
 impl Default for PlainFormatter {
@@ -157,20 +374,47 @@ impl Default for PlainFormatter {
 
 impl Formatter for PlainFormatter {
     fn format(&self, repo: &Repository, map: &RepoMap) -> String {
+        let text = self.locale.strings();
         let mut output = String::new();
 
         // Header
-        output.push_str(&format!("Repository: {}\n", repo.name));
+        self.write_marker(&mut output);
+        output.push_str(&format!("{}: {}\n", text.repository, repo.name));
         output.push_str(&format!(
-            "Files: {} | Lines: {} | Tokens: {}\n",
-            repo.metadata.total_files, repo.metadata.total_lines, repo.metadata.total_tokens.claude
+            "{}: {} | {}: {} | {}: {}\n",
+            text.files,
+            repo.metadata.total_files,
+            text.lines,
+            repo.metadata.total_lines,
+            text.tokens,
+            repo.metadata.total_tokens.claude
         ));
+        if !repo.metadata.topics.is_empty() {
+            let topics: Vec<&str> = repo.metadata.topics.iter().map(|t| t.id()).collect();
+            output.push_str(&format!("{}: {}\n", text.topics, topics.join(", ")));
+        }
         output.push_str(&"=".repeat(60));
         output.push('\n');
         output.push('\n');
 
+        // Build, test and run commands
+        if !repo.metadata.commands.is_empty() {
+            output.push_str(&format!("{}\n", text.commands.to_uppercase()));
+            output.push_str(&"-".repeat(40));
+            output.push('\n');
+            for command in &repo.metadata.commands {
+                output.push_str(&format!(
+                    "{:<8} {}  ({})\n",
+                    command.kind.name(),
+                    command.command,
+                    command.source
+                ));
+            }
+            output.push('\n');
+        }
+
         // Repository map summary
-        output.push_str("REPOSITORY MAP\n");
+        output.push_str(&format!("{}\n", text.repository_map.to_uppercase()));
         output.push_str(&"-".repeat(40));
         output.push('\n');
         output.push_str(&map.summary);
@@ -178,7 +422,7 @@ impl Formatter for PlainFormatter {
 
         // Directory structure
         if let Some(structure) = &repo.metadata.directory_structure {
-            output.push_str("DIRECTORY STRUCTURE\n");
+            output.push_str(&format!("{}\n", text.directory_structure.to_uppercase()));
             output.push_str(&"-".repeat(40));
             output.push('\n');
             output.push_str(structure);
@@ -186,24 +430,30 @@ impl Formatter for PlainFormatter {
         }
 
         // Files
-        output.push_str("FILES\n");
+        output.push_str(&format!("{}\n", text.files.to_uppercase()));
         output.push_str(&"=".repeat(60));
         output.push('\n');
 
         for file in &repo.files {
             output.push('\n');
-            output.push_str(&format!("File: {}\n", file.relative_path));
+            output.push_str(&format!("{}: {}\n", text.file, file.relative_path));
             if let Some(lang) = &file.language {
-                output.push_str(&format!("Language: {}\n", lang));
+                output.push_str(&format!("{}: {}\n", text.language, languages::display_name(lang)));
+            }
+            output.push_str(&format!("{}: {}\n", text.tokens, file.token_count.claude));
+            if let Some(change) = &file.git_change {
+                output.push_str(&format!("{}: {}\n", text.git, change.describe()));
+            }
+            if !file.rank_factors.is_empty() {
+                output.push_str(&format!("{}: {}\n", text.ranked_by, file.rank_factors.join("; ")));
             }
-            output.push_str(&format!("Tokens: {}\n", file.token_count.claude));
             output.push_str(&"-".repeat(40));
             output.push('\n');
 
             if let Some(content) = &file.content {
                 if self.include_line_numbers {
-                    for (i, line) in content.lines().enumerate() {
-                        output.push_str(&format!("{:4} {}\n", i + 1, line));
+                    for (number, line) in file.numbered_lines() {
+                        output.push_str(&format!("{:4} {}\n", number, line));
                     }
                 } else {
                     output.push_str(content);
@@ -211,6 +461,11 @@ impl Formatter for PlainFormatter {
                         output.push('\n');
                     }
                 }
+            } else if file.outline_only {
+                output.push_str(&format!("{}\n", text.outline_only));
+                for line in file.outline() {
+                    output.push_str(&format!("{}\n", line));
+                }
             }
             output.push_str(&"-".repeat(40));
             output.push('\n');
@@ -221,12 +476,13 @@ impl Formatter for PlainFormatter {
 
     fn format_repo(&self, repo: &Repository) -> String {
         let mut output = String::new();
+        self.write_marker(&mut output);
         for file in &repo.files {
             output.push_str(&format!("=== {} ===\n", file.relative_path));
             if let Some(content) = &file.content {
                 if self.include_line_numbers {
-                    for (i, line) in content.lines().enumerate() {
-                        output.push_str(&format!("{:4} {}\n", i + 1, line));
+                    for (number, line) in file.numbered_lines() {
+                        output.push_str(&format!("{:4} {}\n", number, line));
                     }
                 } else {
                     output.push_str(content);
@@ -246,23 +502,70 @@ impl Formatter for PlainFormatter {
 }
 
 /// YAML formatter (Gemini-optimized)
-pub struct YamlFormatter;
+pub struct YamlFormatter {
+    /// Language of the comments and query placeholder
+    locale: Locale,
+    /// Layout version to write
+    version: FormatVersion,
+}
+
+impl YamlFormatter {
+    /// Create a new YAML formatter
+    pub fn new() -> Self {
+        Self { locale: Locale::default(), version: FormatVersion::default() }
+    }
+
+    /// Set the language of the comments and query placeholder
+    pub fn with_locale(mut self, locale: Locale) -> Self {
+        self.locale = locale;
+        self
+    }
+
+    /// Set the layout version to write
+    pub fn with_format_version(mut self, version: FormatVersion) -> Self {
+        self.version = version;
+        self
+    }
+}
+
+impl Default for YamlFormatter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
 impl Formatter for YamlFormatter {
     fn format(&self, repo: &Repository, map: &RepoMap) -> String {
+        self.format_with_sections(repo, map, &[])
+    }
+
+    fn format_with_sections(
+        &self,
+        repo: &Repository,
+        map: &RepoMap,
+        sections: &[Section],
+    ) -> String {
+        let text = self.locale.strings();
         let mut output = String::new();
 
         // YAML header
         output.push_str("---\n");
-        output.push_str("# Repository Context for Gemini\n");
-        output.push_str("# Note: Query should be at the END of this context\n\n");
+        output.push_str(&format!("# {}\n", text.context_title));
+        output.push_str(&format!("# {}\n\n", text.query_at_end));
+        if let Some(version) = self.version.marker() {
+            output.push_str(&format!("format_version: {}\n\n", version));
+        }
 
         // Metadata
         output.push_str("metadata:\n");
-        output.push_str(&format!("  name: {}\n", repo.name));
+        output.push_str(&format!("  name: {}\n", yaml_scalar(&repo.name)));
         output.push_str(&format!("  files: {}\n", repo.metadata.total_files));
         output.push_str(&format!("  lines: {}\n", repo.metadata.total_lines));
         output.push_str(&format!("  tokens: {}\n", repo.metadata.total_tokens.gemini));
+        if !repo.metadata.topics.is_empty() {
+            let topics: Vec<&str> = repo.metadata.topics.iter().map(|t| t.id()).collect();
+            output.push_str(&format!("  topics: [{}]\n", topics.join(", ")));
+        }
         output.push('\n');
 
         // Languages
@@ -270,50 +573,102 @@ impl Formatter for YamlFormatter {
         for lang in &repo.metadata.languages {
             output.push_str(&format!(
                 "  - name: {}\n    files: {}\n    percentage: {:.1}%\n",
-                lang.language, lang.files, lang.percentage
+                yaml_scalar(&lang.language),
+                lang.files,
+                lang.percentage
             ));
         }
         output.push('\n');
 
+        // Commands
+        if !repo.metadata.commands.is_empty() {
+            output.push_str("commands:\n");
+            for command in &repo.metadata.commands {
+                output.push_str(&format!(
+                    "  - kind: {}\n    command: {}\n    source: {}\n",
+                    command.kind.name(),
+                    yaml_string(&command.command),
+                    yaml_scalar(&command.source)
+                ));
+            }
+            output.push('\n');
+        }
+
         // Repository map
         output.push_str("repository_map:\n");
-        output.push_str(&format!("  summary: |\n    {}\n", map.summary.replace('\n', "\n    ")));
+        output.push_str(&format!("  summary: {}", yaml_block(&map.summary, 4)));
         output.push_str("  key_symbols:\n");
         for sym in &map.key_symbols {
             output.push_str(&format!(
                 "    - name: {}\n      type: {}\n      file: {}\n      rank: {}\n",
-                sym.name, sym.kind, sym.file, sym.rank
+                yaml_scalar(&sym.name),
+                yaml_scalar(&sym.kind),
+                yaml_scalar(&sym.file),
+                sym.rank
             ));
         }
+        if !map.external_usage.is_empty() {
+            output.push_str("  dependency_usage:\n");
+            for usage in &map.external_usage {
+                output.push_str(&format!(
+                    "    - package: {}\n      files: {}\n",
+                    yaml_scalar(&usage.package),
+                    yaml_string(&usage.files)
+                ));
+            }
+        }
         output.push('\n');
 
         // Files
         output.push_str("files:\n");
         for file in &repo.files {
-            output.push_str(&format!("  - path: {}\n", file.relative_path));
+            output.push_str(&format!("  - path: {}\n", yaml_scalar(&file.relative_path)));
             if let Some(lang) = &file.language {
-                output.push_str(&format!("    language: {}\n", lang));
+                output.push_str(&format!("    language: {}\n", yaml_scalar(lang)));
             }
             output.push_str(&format!("    tokens: {}\n", file.token_count.gemini));
+            if let Some(change) = &file.git_change {
+                output.push_str(&format!(
+                    "    git: {{status: {}, additions: {}, deletions: {}}}\n",
+                    change.status, change.additions, change.deletions
+                ));
+                if !change.symbols.is_empty() {
+                    let symbols: Vec<String> =
+                        change.symbols.iter().map(ToString::to_string).collect();
+                    output.push_str(&format!("    changed_symbols: {}\n", yaml_string(&symbols)));
+                }
+            }
+            if !file.rank_factors.is_empty() {
+                output.push_str(&format!("    ranked_by: {}\n", yaml_string(&file.rank_factors)));
+            }
+            if let Some(summary) = &file.summary {
+                output.push_str(&format!("    summary: {}\n", yaml_string(summary)));
+            }
 
             if let Some(content) = &file.content {
-                output.push_str("    content: |\n");
-                for line in content.lines() {
-                    output.push_str(&format!("      {}\n", line));
+                output.push_str(&format!("    content: {}", yaml_block(content, 6)));
+            } else if file.outline_only {
+                output.push_str("    outline:\n");
+                for line in file.outline() {
+                    output.push_str(&format!("      - {}\n", yaml_string(&line)));
                 }
             }
         }
 
-        // Query placeholder at end (Gemini best practice)
-        output.push_str("\n# --- INSERT YOUR QUERY BELOW THIS LINE ---\n");
+        // Reports, then the query placeholder at end (Gemini best practice)
+        if !sections.is_empty() {
+            output.push('\n');
+            output.push_str(&sections::yaml(sections));
+        }
+        output.push_str(&format!("\n# --- {} ---\n", text.query_below));
         output.push_str("query: |\n");
-        output.push_str("  [Your question about this repository]\n");
+        output.push_str(&format!("  [{}]\n", text.query_placeholder));
 
         output
     }
 
     fn format_repo(&self, repo: &Repository) -> String {
-        serde_yaml::to_string(repo).unwrap_or_default()
+        serde_yaml::to_string(&Versioned::new(self.version, repo)).unwrap_or_default()
     }
 
     fn name(&self) -> &'static str {
@@ -321,6 +676,57 @@ impl Formatter for YamlFormatter {
     }
 }
 
+/// A serialized value with the layout version as its first field
+#[derive(serde::Serialize)]
+struct Versioned<'a, T: serde::Serialize + ?Sized> {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    format_version: Option<u32>,
+    #[serde(flatten)]
+    value: &'a T,
+}
+
+impl<'a, T: serde::Serialize + ?Sized> Versioned<'a, T> {
+    fn new(version: FormatVersion, value: &'a T) -> Self {
+        Self { format_version: version.marker(), value }
+    }
+}
+
+/// A YAML plain scalar for `value`, double-quoted when it would not read
+/// back as the same string
+fn yaml_scalar(value: &str) -> Cow<'_, str> {
+    let plain = !value.is_empty()
+        && value.trim() == value
+        && !value.starts_with(|c: char| "-?:,[]{}#&*!|>'\"%@`".contains(c))
+        && !value.ends_with(':')
+        && !value.contains(": ")
+        && !value.contains(" #")
+        && !value.chars().any(char::is_control);
+    if plain {
+        Cow::Borrowed(value)
+    } else {
+        Cow::Owned(yaml_string(value))
+    }
+}
+
+/// A double-quoted YAML scalar or flow sequence
+///
+/// JSON strings and arrays are valid YAML, with escapes YAML understands.
+fn yaml_string<T: serde::Serialize + ?Sized>(value: &T) -> String {
+    serde_json::to_string(value).unwrap_or_default()
+}
+
+/// A literal block scalar with its lines indented by `indent` spaces
+///
+/// The indentation indicator keeps leading spaces of the first line from
+/// being taken as the block's indentation.
+fn yaml_block(text: &str, indent: usize) -> String {
+    let mut block = String::from("|2\n");
+    for line in escape_controls(text).lines() {
+        block.push_str(&format!("{:indent$}{}\n", "", line.replace('\r', "\\r")));
+    }
+    block
+}
+
 #[cfg(test)]
 #[allow(clippy::str_to_string)]
 mod tests {
@@ -338,9 +744,9 @@ mod tests {
                 language: Some("python".to_string()),
                 size_bytes: 100,
                 token_count: TokenCounts { claude: 50, gpt4o: 48, gpt4: 49, gemini: 47, llama: 46 },
-                symbols: Vec::new(),
                 importance: 0.8,
                 content: Some("def main():\n    print('hello')".to_string()),
+                ..Default::default()
             }],
             metadata: RepoMetadata {
                 total_files: 1,
@@ -364,6 +770,8 @@ mod tests {
                 commit: None,
                 directory_structure: Some("main.py\n".to_string()),
                 external_dependencies: vec!["requests".to_string()],
+                commands: Vec::new(),
+                topics: Vec::new(),
                 git_history: None,
             },
         }
@@ -392,4 +800,176 @@ mod tests {
         assert!(output.contains("name: test"));
         assert!(output.contains("# --- INSERT YOUR QUERY"));
     }
+
+    #[test]
+    fn test_format_with_sections_cancellable() {
+        let repo = create_test_repo();
+        let map = RepoMapGenerator::new(1000).generate(&repo);
+        let sections = [Section::new("notes", "## Notes\n", &"notes")];
+        let formatter = OutputFormatter::by_format(OutputFormat::Json);
+
+        let cancel = CancellationToken::new();
+        assert_eq!(
+            formatter.format_with_sections_cancellable(&repo, &map, &sections, &cancel),
+            Ok(formatter.format_with_sections(&repo, &map, &sections))
+        );
+        cancel.cancel();
+        assert_eq!(
+            formatter.format_with_sections_cancellable(&repo, &map, &sections, &cancel),
+            Err(Cancelled)
+        );
+    }
+
+    #[test]
+    fn test_git_change_annotations() {
+        let mut repo = create_test_repo();
+        repo.files[0].git_change = Some(crate::types::GitFileChange {
+            status: "M".to_string(),
+            additions: 3,
+            deletions: 1,
+            symbols: vec![crate::types::SymbolChange {
+                path: repo.files[0].relative_path.clone(),
+                name: "Auth.refresh".to_string(),
+                change: crate::types::SymbolChangeKind::Modified,
+            }],
+        });
+        let map = RepoMapGenerator::new(1000).generate(&repo);
+
+        let expected = [
+            (
+                OutputFormat::Xml,
+                "git_status=\"M\" additions=\"3\" deletions=\"1\" changed_symbols=\"modified: Auth.refresh\"",
+            ),
+            (OutputFormat::Markdown, "**Git**: M +3 -1 (modified: `Auth.refresh`)"),
+            (OutputFormat::Json, "\"change\": \"modified\""),
+            (OutputFormat::Yaml, "git: {status: M, additions: 3, deletions: 1}"),
+            (OutputFormat::Yaml, "changed_symbols: [\"modified: `Auth.refresh`\"]"),
+            (OutputFormat::Toon, "|M +3 -1 (modified: `Auth.refresh`):"),
+            (OutputFormat::Plain, "Git: M +3 -1 (modified: `Auth.refresh`)"),
+        ];
+        for (format, annotation) in expected {
+            let output = OutputFormatter::by_format(format).format(&repo, &map);
+            assert!(output.contains(annotation), "{:?} missing {}", format, annotation);
+        }
+
+        repo.files[0].git_change = None;
+        let output = OutputFormatter::json().format(&repo, &map);
+        assert!(!output.contains("git_change"));
+    }
+
+    #[test]
+    fn test_rank_factor_annotations() {
+        let mut repo = create_test_repo();
+        repo.files[0].rank_factors = vec!["entry point (+1.00)".to_string()];
+        let map = RepoMapGenerator::new(1000).generate(&repo);
+
+        let expected = [
+            (OutputFormat::Xml, "ranked_by=\"entry point (+1.00)\""),
+            (OutputFormat::Markdown, "**Ranked by**: entry point (+1.00)"),
+            (OutputFormat::Json, "\"rank_factors\": ["),
+            (OutputFormat::Yaml, "ranked_by: [\"entry point (+1.00)\"]"),
+            (OutputFormat::Toon, "{path,tokens,importance,ranked_by}:"),
+            (OutputFormat::Plain, "Ranked by: entry point (+1.00)"),
+        ];
+        for (format, annotation) in expected {
+            let output = OutputFormatter::by_format(format).format(&repo, &map);
+            assert!(output.contains(annotation), "{:?} missing {}", format, annotation);
+        }
+
+        repo.files[0].rank_factors.clear();
+        let output = OutputFormatter::json().format(&repo, &map);
+        assert!(!output.contains("rank_factors"));
+    }
+
+    #[test]
+    fn test_localized_output() {
+        let repo = create_test_repo();
+        let map = RepoMapGenerator::new(1000).generate(&repo);
+
+        let expected = [
+            (
+                OutputFormat::Xml,
+                Locale::De,
+                "<purpose>Dies ist ein umfassender Code-Kontext für das Repository test",
+            ),
+            (OutputFormat::Markdown, Locale::Ja, "## 概要"),
+            (OutputFormat::Yaml, Locale::Es, "# --- ESCRIBE TU CONSULTA DEBAJO DE ESTA LÍNEA ---"),
+            (OutputFormat::Plain, Locale::De, "VERZEICHNISSTRUKTUR"),
+        ];
+        for (format, locale, text) in expected {
+            let output = OutputFormatter::by_format_with_all_options(format, true, true, locale)
+                .format(&repo, &map);
+            assert!(output.contains(text), "{:?} missing {}", format, text);
+        }
+
+        // Structure and content are not translated
+        let output =
+            OutputFormatter::by_format_with_all_options(OutputFormat::Xml, true, true, Locale::Ja)
+                .format(&repo, &map);
+        assert!(output.contains("<how_to_use>"));
+        assert!(output.contains("print('hello')"));
+    }
+
+    #[test]
+    fn test_format_version_markers() {
+        let repo = create_test_repo();
+        let map = RepoMapGenerator::new(1000).generate(&repo);
+        let v1 = FormatVersion::new(1).unwrap();
+
+        let expected = [
+            (OutputFormat::Xml, "<repository name=\"test\" format_version=\"2\">"),
+            (OutputFormat::Markdown, "<!-- format_version: 2 -->\n"),
+            (OutputFormat::Json, "{\n  \"format_version\": 2,\n  \"repository\": {"),
+            (OutputFormat::Yaml, "\nformat_version: 2\n"),
+            (OutputFormat::Toon, "\nformat_version: 2\nmetadata:"),
+            (OutputFormat::Plain, "format_version: 2\n"),
+            (OutputFormat::Signatures, "# format_version: 2\n"),
+        ];
+        for (format, marker) in expected {
+            let current = OutputFormatter::by_format(format);
+            assert!(
+                current.format(&repo, &map).contains(marker),
+                "{:?} missing {}",
+                format,
+                marker
+            );
+            assert!(current.format_repo(&repo).contains("format_version"), "{:?}", format);
+
+            let old = OutputFormatter::by_format_version(
+                format,
+                v1,
+                true,
+                true,
+                IndexLimit::none(),
+                Locale::default(),
+            );
+            assert!(!old.format(&repo, &map).contains("format_version"), "{:?}", format);
+            assert!(!old.format_repo(&repo).contains("format_version"), "{:?}", format);
+        }
+
+        let old = OutputFormatter::by_format_version(
+            OutputFormat::Xml,
+            v1,
+            true,
+            true,
+            IndexLimit::none(),
+            Locale::En,
+        );
+        assert!(old
+            .format(&repo, &map)
+            .contains("<repository name=\"test\" version=\"1.0.0\">"));
+
+        let parsed: serde_yaml::Value =
+            serde_yaml::from_str(&OutputFormatter::gemini().format_repo(&repo)).unwrap();
+        assert_eq!(parsed["format_version"], 2);
+        assert_eq!(parsed["name"], "test");
+    }
+
+    #[test]
+    fn test_format_from_extension() {
+        assert!(matches!(OutputFormat::from_extension("md"), Some(OutputFormat::Markdown)));
+        assert!(matches!(OutputFormat::from_extension("JSON"), Some(OutputFormat::Json)));
+        assert!(matches!(OutputFormat::from_extension("yml"), Some(OutputFormat::Yaml)));
+        assert!(OutputFormat::from_extension("rs").is_none());
+    }
 }
@@ -0,0 +1,187 @@
+//! Path rewriting for output
+//!
+//! Packs generated inside containers or from temporary clones carry paths the
+//! reader never sees (`/workspace/build/src/lib.rs`). [`PathRewriter`] strips
+//! prefixes and remaps path roots so every path reference in the output
+//! matches the user's view of the repository.
+
+use crate::types::Repository;
+use std::path::PathBuf;
+
+/// Strips and remaps path prefixes
+#[derive(Debug, Clone, Default)]
+pub struct PathRewriter {
+    /// Prefixes removed from the start of paths
+    strip_prefixes: Vec<String>,
+    /// `(old, new)` prefix replacements, first match wins
+    mappings: Vec<(String, String)>,
+}
+
+impl PathRewriter {
+    /// Create a rewriter that leaves paths unchanged
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Remove `prefix` from the start of paths
+    pub fn with_strip_prefix(mut self, prefix: impl Into<String>) -> Self {
+        let prefix = normalize(&prefix.into());
+        if !prefix.is_empty() {
+            self.strip_prefixes.push(prefix);
+        }
+        self
+    }
+
+    /// Replace the path prefix `old` with `new`
+    pub fn with_mapping(mut self, old: impl Into<String>, new: impl Into<String>) -> Self {
+        let old = normalize(&old.into());
+        if !old.is_empty() {
+            self.mappings.push((old, normalize(&new.into())));
+        }
+        self
+    }
+
+    /// Whether this rewriter changes nothing
+    pub fn is_empty(&self) -> bool {
+        self.strip_prefixes.is_empty() && self.mappings.is_empty()
+    }
+
+    /// Rewrite a single path
+    ///
+    /// Prefixes only match whole path components, so stripping `src` leaves
+    /// `srcgen/main.rs` untouched. Stripping happens before remapping.
+    pub fn rewrite(&self, path: &str) -> String {
+        let mut path = path.replace('\\', "/");
+
+        for prefix in &self.strip_prefixes {
+            if let Some(rest) = strip_component_prefix(&path, prefix) {
+                if !rest.is_empty() {
+                    path = rest.to_owned();
+                    break;
+                }
+            }
+        }
+
+        for (old, new) in &self.mappings {
+            if let Some(rest) = strip_component_prefix(&path, old) {
+                path = match (new.is_empty(), rest.is_empty()) {
+                    (true, _) => rest.to_owned(),
+                    (false, true) => new.clone(),
+                    (false, false) => format!("{}/{}", new, rest),
+                };
+                break;
+            }
+        }
+
+        path
+    }
+
+    /// Rewrite every path reference stored in a repository
+    ///
+    /// Covers file paths and git change entries. Derived data (repository
+    /// maps, directory trees) should be generated after this runs.
+    pub fn apply(&self, repo: &mut Repository) {
+        if self.is_empty() {
+            return;
+        }
+
+        for file in &mut repo.files {
+            file.relative_path = self.rewrite(&file.relative_path);
+            file.path = PathBuf::from(self.rewrite(&file.path.to_string_lossy()));
+            if let Some(change) = &mut file.git_change {
+                for symbol in &mut change.symbols {
+                    symbol.path = self.rewrite(&symbol.path);
+                }
+            }
+        }
+
+        if let Some(history) = &mut repo.metadata.git_history {
+            for changed in &mut history.changed_files {
+                changed.path = self.rewrite(&changed.path);
+            }
+            for symbol in history.commits.iter_mut().flat_map(|c| &mut c.symbols) {
+                symbol.path = self.rewrite(&symbol.path);
+            }
+        }
+    }
+}
+
+/// Use forward slashes and drop trailing separators (but keep a bare `/`)
+fn normalize(path: &str) -> String {
+    let path = path.replace('\\', "/");
+    let trimmed = path.trim_end_matches('/');
+    if trimmed.is_empty() && path.starts_with('/') {
+        "/".to_owned()
+    } else {
+        trimmed.to_owned()
+    }
+}
+
+/// Strip `prefix` if it matches whole leading components of `path`
+fn strip_component_prefix<'a>(path: &'a str, prefix: &str) -> Option<&'a str> {
+    if prefix == "/" {
+        return path.strip_prefix('/');
+    }
+    let rest = path.strip_prefix(prefix)?;
+    if rest.is_empty() {
+        Some(rest)
+    } else {
+        rest.strip_prefix('/')
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::str_to_string)]
+mod tests {
+    use super::*;
+    use crate::types::{GitChangedFile, GitHistory, RepoFile};
+
+    #[test]
+    fn test_strip_prefix() {
+        let rewriter = PathRewriter::new().with_strip_prefix("/workspace/app/");
+        assert_eq!(rewriter.rewrite("/workspace/app/src/lib.rs"), "src/lib.rs");
+        assert_eq!(rewriter.rewrite("/workspace/application/x.rs"), "/workspace/application/x.rs");
+        assert_eq!(rewriter.rewrite("src/lib.rs"), "src/lib.rs");
+    }
+
+    #[test]
+    fn test_mapping() {
+        let rewriter = PathRewriter::new()
+            .with_strip_prefix("build")
+            .with_mapping("packages/core", "core")
+            .with_mapping("vendor", "");
+        assert_eq!(rewriter.rewrite("build/packages/core/index.ts"), "core/index.ts");
+        assert_eq!(rewriter.rewrite("packages/core"), "core");
+        assert_eq!(rewriter.rewrite("vendor/lib/a.js"), "lib/a.js");
+        assert_eq!(rewriter.rewrite("packages/corelib/a.ts"), "packages/corelib/a.ts");
+    }
+
+    #[test]
+    fn test_apply_to_repository() {
+        let mut repo = Repository::new("test", "/tmp/clone");
+        repo.files.push(RepoFile {
+            path: "/tmp/clone/src/main.rs".into(),
+            relative_path: "src/main.rs".to_string(),
+            language: Some("rust".to_string()),
+            importance: 0.5,
+            ..Default::default()
+        });
+        repo.metadata.git_history = Some(GitHistory {
+            commits: Vec::new(),
+            changed_files: vec![GitChangedFile {
+                path: "src/main.rs".to_string(),
+                status: "M".to_string(),
+            }],
+        });
+
+        PathRewriter::new()
+            .with_strip_prefix("/tmp/clone")
+            .with_mapping("src", "app/src")
+            .apply(&mut repo);
+
+        assert_eq!(repo.files[0].relative_path, "app/src/main.rs");
+        assert_eq!(repo.files[0].path, PathBuf::from("app/src/main.rs"));
+        let history = repo.metadata.git_history.unwrap();
+        assert_eq!(history.changed_files[0].path, "app/src/main.rs");
+    }
+}
@@ -0,0 +1,236 @@
+//! Reports placed after the files of a pack
+//!
+//! Delta, sampling, contract and deadline reports are Markdown in the text
+//! formats. XML, JSON, YAML and TOON carry the same content as data inside the
+//! document instead, so appending a report never stops the output parsing.
+
+use super::escape_xml;
+use super::toon::escape_toon;
+use serde::ser::{SerializeMap, Serializer};
+use serde::Serialize;
+use serde_json::Value;
+use std::fmt::Write;
+
+/// A report placed after the files, such as what a delta pack left out
+#[derive(Debug, Clone, PartialEq)]
+pub struct Section {
+    /// Element or key naming the section in structured formats
+    pub name: &'static str,
+    /// Markdown for the text formats, starting with its own spacing
+    pub text: String,
+    /// The same content as data for the structured formats
+    pub data: Value,
+}
+
+impl Section {
+    /// A section named `name`, written as `text` or as `data`
+    pub fn new(name: &'static str, text: impl Into<String>, data: &impl Serialize) -> Self {
+        Self { name, text: text.into(), data: serde_json::to_value(data).unwrap_or(Value::Null) }
+    }
+}
+
+/// Sections as one map entry each, in order, for flattening into JSON
+pub(super) struct SectionMap<'a>(pub &'a [Section]);
+
+impl Serialize for SectionMap<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(self.0.len()))?;
+        for section in self.0 {
+            map.serialize_entry(section.name, &section.data)?;
+        }
+        map.end()
+    }
+}
+
+/// Write `sections` as elements one level below the root
+pub(super) fn write_xml(output: &mut String, sections: &[Section]) {
+    for section in sections {
+        xml_value(output, section.name, &section.data, 1);
+    }
+}
+
+fn xml_value(output: &mut String, name: &str, value: &Value, depth: usize) {
+    let indent = "  ".repeat(depth);
+    match value {
+        Value::Null => writeln!(output, "{}<{}/>", indent, name).unwrap(),
+        Value::Object(fields) if fields.is_empty() => {
+            writeln!(output, "{}<{}/>", indent, name).unwrap();
+        },
+        Value::Array(items) if items.is_empty() => {
+            writeln!(output, "{}<{}/>", indent, name).unwrap();
+        },
+        Value::Object(fields) => {
+            writeln!(output, "{}<{}>", indent, name).unwrap();
+            for (key, value) in fields {
+                xml_value(output, key, value, depth + 1);
+            }
+            writeln!(output, "{}</{}>", indent, name).unwrap();
+        },
+        Value::Array(items) => {
+            writeln!(output, "{}<{}>", indent, name).unwrap();
+            for item in items {
+                xml_value(output, "item", item, depth + 1);
+            }
+            writeln!(output, "{}</{}>", indent, name).unwrap();
+        },
+        Value::String(text) => {
+            writeln!(output, "{}<{}>{}</{}>", indent, name, escape_xml(text), name).unwrap();
+        },
+        Value::Bool(_) | Value::Number(_) => {
+            writeln!(output, "{}<{}>{}</{}>", indent, name, value, name).unwrap();
+        },
+    }
+}
+
+/// `sections` as top-level YAML keys
+pub(super) fn yaml(sections: &[Section]) -> String {
+    let mut mapping = serde_yaml::Mapping::new();
+    for section in sections {
+        let data = serde_yaml::to_value(&section.data).unwrap_or(serde_yaml::Value::Null);
+        mapping.insert(section.name.into(), data);
+    }
+    if mapping.is_empty() {
+        return String::new();
+    }
+    serde_yaml::to_string(&mapping).unwrap_or_default()
+}
+
+/// Write `sections` as top-level TOON keys
+pub(super) fn write_toon(output: &mut String, sections: &[Section]) {
+    for section in sections {
+        toon_value(output, section.name, &section.data, 0);
+    }
+}
+
+fn toon_value(output: &mut String, key: &str, value: &Value, depth: usize) {
+    let indent = "  ".repeat(depth);
+    match value {
+        Value::Object(fields) => {
+            writeln!(output, "{}{}:", indent, key).unwrap();
+            for (key, value) in fields {
+                toon_value(output, key, value, depth + 1);
+            }
+        },
+        Value::Array(items) => {
+            if let Some(fields) = table_fields(items) {
+                writeln!(output, "{}{}[{}]{{{}}}:", indent, key, items.len(), fields.join(","))
+                    .unwrap();
+                for item in items {
+                    let row: Vec<String> = fields.iter().map(|f| toon_scalar(&item[f])).collect();
+                    writeln!(output, "{}  {}", indent, row.join(",")).unwrap();
+                }
+            } else if items.iter().all(is_scalar) {
+                let values: Vec<String> = items.iter().map(toon_scalar).collect();
+                writeln!(output, "{}{}[{}]: {}", indent, key, items.len(), values.join(","))
+                    .unwrap();
+            } else {
+                writeln!(output, "{}{}[{}]:", indent, key, items.len()).unwrap();
+                for item in items {
+                    toon_value(output, "-", item, depth + 1);
+                }
+            }
+        },
+        Value::String(text) if text.contains('\n') => {
+            writeln!(output, "{}{}: |", indent, key).unwrap();
+            for line in text.lines() {
+                writeln!(output, "{}  {}", indent, line).unwrap();
+            }
+        },
+        _ => writeln!(output, "{}{}: {}", indent, key, toon_scalar(value)).unwrap(),
+    }
+}
+
+/// Field names when every item is an object of the same scalar fields
+fn table_fields(items: &[Value]) -> Option<Vec<&str>> {
+    let first = items.first()?.as_object()?;
+    let fields: Vec<&str> = first.keys().map(String::as_str).collect();
+    let uniform = items.iter().all(|item| {
+        item.as_object().is_some_and(|object| {
+            object.len() == fields.len()
+                && fields.iter().all(|f| {
+                    object
+                        .get(*f)
+                        .is_some_and(|v| is_scalar(v) && !is_multiline(v))
+                })
+        })
+    });
+    uniform.then_some(fields)
+}
+
+fn is_scalar(value: &Value) -> bool {
+    !matches!(value, Value::Object(_) | Value::Array(_))
+}
+
+fn is_multiline(value: &Value) -> bool {
+    value.as_str().is_some_and(|text| text.contains('\n'))
+}
+
+fn toon_scalar(value: &Value) -> String {
+    match value {
+        Value::String(text) => escape_toon(text),
+        other => other.to_string(),
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::str_to_string)]
+mod tests {
+    use super::*;
+    use crate::output::{lint_output, Formatter, OutputFormat, OutputFormatter};
+    use crate::repomap::RepoMapGenerator;
+    use crate::types::Repository;
+    use serde_json::json;
+
+    fn sections() -> Vec<Section> {
+        vec![
+            Section::new(
+                "delta",
+                "\n\n<!-- Unchanged files -->\n- src/a.rs\n",
+                &json!({ "unchanged": [{ "path": "src/a.rs", "hash": "1f2e" }], "removed": [] }),
+            ),
+            Section::new(
+                "deadline",
+                "\n\n<!-- Deadline of 1s applied -->\n",
+                &json!({ "budget": "1s", "degradations": ["skipped security scan"] }),
+            ),
+        ]
+    }
+
+    #[test]
+    fn test_sections_keep_structured_output_valid() {
+        let repo = Repository::new("demo", "/tmp/demo");
+        let map = RepoMapGenerator::new(100).generate(&repo);
+        for format in [OutputFormat::Xml, OutputFormat::Json, OutputFormat::Yaml] {
+            let output =
+                OutputFormatter::by_format(format).format_with_sections(&repo, &map, &sections());
+            lint_output(format, &output).unwrap();
+            assert!(output.contains("skipped security scan"), "{:?}", format);
+            assert!(!output.contains("<!-- Deadline"), "{:?}", format);
+        }
+
+        let json: Value = serde_json::from_str(&OutputFormatter::json().format_with_sections(
+            &repo,
+            &map,
+            &sections(),
+        ))
+        .unwrap();
+        assert_eq!(json["delta"]["unchanged"][0]["path"], "src/a.rs");
+        assert_eq!(json["deadline"]["budget"], "1s");
+    }
+
+    #[test]
+    fn test_sections_in_text_and_toon() {
+        let repo = Repository::new("demo", "/tmp/demo");
+        let map = RepoMapGenerator::new(100).generate(&repo);
+        let markdown = OutputFormatter::by_format(OutputFormat::Markdown).format_with_sections(
+            &repo,
+            &map,
+            &sections(),
+        );
+        assert!(markdown.ends_with("<!-- Deadline of 1s applied -->\n"));
+
+        let toon = OutputFormatter::toon().format_with_sections(&repo, &map, &sections());
+        assert!(toon.contains("  unchanged[1]{hash,path}:\n    1f2e,src/a.rs\n"));
+        assert!(toon.contains("  degradations[1]: skipped security scan\n"));
+    }
+}
@@ -0,0 +1,312 @@
+//! Signature pack: the smallest useful context for agent tool use
+//!
+//! Three sections and no code bodies:
+//!
+//! - the directory tree, directories only, with their file counts
+//! - exported symbol signatures grouped by file, methods under their type
+//! - file-level dependency edges within the repository
+//!
+//! Files keep the packer's order, so a truncated pack loses the least
+//! important ones first.
+
+use crate::dependencies::DependencyGraph;
+use crate::output::{FormatVersion, Formatter};
+use crate::repomap::RepoMap;
+use crate::types::{RepoFile, Repository, Symbol, SymbolKind};
+use std::collections::{BTreeMap, HashSet};
+use std::fmt::Write;
+
+/// Formatter emitting only the tree, exported signatures and dependencies
+pub struct SignatureFormatter {
+    /// Prefix signatures with their line number
+    include_line_numbers: bool,
+    /// Layout version to write
+    version: FormatVersion,
+}
+
+impl SignatureFormatter {
+    /// Create a new signature formatter
+    pub fn new() -> Self {
+        Self { include_line_numbers: true, version: FormatVersion::default() }
+    }
+
+    /// Set line numbers option
+    pub fn with_line_numbers(mut self, enabled: bool) -> Self {
+        self.include_line_numbers = enabled;
+        self
+    }
+
+    /// Set the layout version to write
+    pub fn with_format_version(mut self, version: FormatVersion) -> Self {
+        self.version = version;
+        self
+    }
+
+    fn write_tree(&self, output: &mut String, repo: &Repository) {
+        // Direct file count of every directory, parents included
+        let mut dirs: BTreeMap<&str, usize> = BTreeMap::new();
+        let mut root_files = 0;
+        for file in &repo.files {
+            let path = file.relative_path.as_str();
+            match path.rfind('/') {
+                Some(end) => {
+                    *dirs.entry(&path[..end]).or_default() += 1;
+                    let mut dir = &path[..end];
+                    while let Some(end) = dir.rfind('/') {
+                        dir = &dir[..end];
+                        dirs.entry(dir).or_default();
+                    }
+                },
+                None => root_files += 1,
+            }
+        }
+
+        writeln!(output, "## Tree\n").unwrap();
+        writeln!(output, "./ {}", root_files).unwrap();
+        for (dir, count) in dirs {
+            let depth = dir.matches('/').count() + 1;
+            let name = dir.rsplit('/').next().unwrap_or(dir);
+            writeln!(output, "{}{}/ {}", "  ".repeat(depth), name, count).unwrap();
+        }
+        output.push('\n');
+    }
+
+    fn write_signatures(&self, output: &mut String, repo: &Repository) {
+        writeln!(output, "## Signatures\n").unwrap();
+        for file in &repo.files {
+            let lines: Vec<&str> = file
+                .content
+                .as_deref()
+                .map(|c| c.lines().collect())
+                .unwrap_or_default();
+            let mut symbols: Vec<(&Symbol, String)> = file
+                .symbols
+                .iter()
+                .filter(|s| s.kind != SymbolKind::Import && s.start_line > 0)
+                .filter_map(|s| {
+                    // Transforms may have dropped lines before the declaration
+                    let index = match &file.line_numbers {
+                        Some(numbers) => numbers.iter().position(|&n| n == s.start_line),
+                        None => Some(s.start_line as usize - 1),
+                    };
+                    let source = index.and_then(|i| lines.get(i)).map(|l| l.trim());
+                    is_exported(file, s, source.unwrap_or_default())
+                        .then(|| (s, declaration(s, source)))
+                })
+                .collect();
+            // Methods of private types are private, and parsers may report a
+            // method both with and without its type
+            let types: HashSet<&str> = symbols
+                .iter()
+                .filter(|(s, _)| s.parent.is_none())
+                .map(|(s, _)| s.name.as_str())
+                .collect();
+            symbols.retain(|(s, _)| s.parent.as_deref().is_none_or(|p| types.contains(p)));
+            symbols.sort_by_key(|(s, _)| (s.start_line, s.parent.is_none()));
+            symbols.dedup_by(|(a, _), (b, _)| a.start_line == b.start_line && a.name == b.name);
+            if symbols.is_empty() {
+                continue;
+            }
+
+            writeln!(output, "{}", file.relative_path).unwrap();
+            for (symbol, decl) in symbols {
+                let indent = if symbol.parent.is_some() {
+                    "    "
+                } else {
+                    "  "
+                };
+                if self.include_line_numbers {
+                    writeln!(output, "{}{}: {}", indent, symbol.start_line, decl).unwrap();
+                } else {
+                    writeln!(output, "{}{}", indent, decl).unwrap();
+                }
+            }
+        }
+        output.push('\n');
+    }
+
+    fn write_dependencies(&self, output: &mut String, repo: &Repository) {
+        let graph = DependencyGraph::build(repo);
+        let mut edges = Vec::new();
+        for file in &repo.files {
+            let mut imports = graph.get_imports(&file.relative_path);
+            imports.retain(|to| *to != file.relative_path);
+            if imports.is_empty() {
+                continue;
+            }
+            imports.sort_unstable();
+            imports.dedup();
+            edges.push(format!("{} -> {}", file.relative_path, imports.join(", ")));
+        }
+        if edges.is_empty() {
+            return;
+        }
+
+        writeln!(output, "## Dependencies\n").unwrap();
+        for edge in edges {
+            writeln!(output, "{}", edge).unwrap();
+        }
+        output.push('\n');
+    }
+}
+
+impl Default for SignatureFormatter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Formatter for SignatureFormatter {
+    fn format(&self, repo: &Repository, _map: &RepoMap) -> String {
+        self.format_repo(repo)
+    }
+
+    fn format_repo(&self, repo: &Repository) -> String {
+        let mut output = String::new();
+        if let Some(version) = self.version.marker() {
+            writeln!(output, "# format_version: {}", version).unwrap();
+        }
+        writeln!(output, "# {} ({} files, signatures only)\n", repo.name, repo.files.len())
+            .unwrap();
+        self.write_tree(&mut output, repo);
+        self.write_signatures(&mut output, repo);
+        self.write_dependencies(&mut output, repo);
+        output
+    }
+
+    fn name(&self) -> &'static str {
+        "signatures"
+    }
+}
+
+/// Whether a symbol is part of its file's public surface
+///
+/// `source` is the trimmed line the symbol starts on, which carries the
+/// visibility keywords that parsed signatures leave out.
+pub(crate) fn is_exported(file: &RepoFile, symbol: &Symbol, source: &str) -> bool {
+    let name = symbol.name.as_str();
+    match file.language.as_deref() {
+        Some("rust") => source.starts_with("pub "),
+        Some("go") => name.starts_with(|c: char| c.is_uppercase()),
+        Some("python") => !name.starts_with('_'),
+        Some("javascript" | "typescript") => {
+            if symbol.parent.is_some() {
+                !name.starts_with('#') && !source.starts_with("private")
+            } else {
+                source.starts_with("export")
+            }
+        },
+        _ => !name.starts_with('_') && !source.split_whitespace().any(|w| w == "private"),
+    }
+}
+
+/// The declaration to show: the longer of the parsed signature and the
+/// source line, else the kind and name, without any opening brace or colon
+///
+/// Parsed signatures span lines but can stop early, as at a Python type
+/// annotation, and leave out `export`.
+pub(crate) fn declaration(symbol: &Symbol, source: Option<&str>) -> String {
+    let signature = symbol
+        .signature
+        .as_ref()
+        .map(|s| s.split_whitespace().collect::<Vec<_>>().join(" "));
+    let source = source.filter(|line| line.contains(symbol.name.as_str()));
+    let decl = match (signature, source) {
+        (Some(signature), Some(line)) if line.len() > signature.len() => line.to_owned(),
+        (Some(signature), _) => signature,
+        (None, Some(line)) => line.to_owned(),
+        (None, None) => return format!("{} {}", symbol.kind.name(), symbol.name),
+    };
+    decl.trim_end_matches(['{', ':', ' ']).to_owned()
+}
+
+#[cfg(test)]
+#[allow(clippy::str_to_string)]
+mod tests {
+    use super::*;
+    use crate::types::RepoMetadata;
+
+    fn file(path: &str, language: &str, content: &str, symbols: Vec<Symbol>) -> RepoFile {
+        RepoFile {
+            path: path.into(),
+            relative_path: path.to_string(),
+            language: Some(language.to_string()),
+            size_bytes: content.len() as u64,
+            symbols,
+            importance: 0.5,
+            content: Some(content.to_string()),
+            ..Default::default()
+        }
+    }
+
+    fn symbol(name: &str, kind: SymbolKind, line: u32, parent: Option<&str>) -> Symbol {
+        let mut symbol = Symbol::new(name, kind);
+        symbol.start_line = line;
+        symbol.end_line = line + 2;
+        symbol.parent = parent.map(str::to_string);
+        symbol
+    }
+
+    #[test]
+    fn test_signature_pack() {
+        let lib = "pub struct Parser {\n    depth: usize,\n}\n\nimpl Parser {\n    pub fn parse(&self, input: &str) -> usize {\n        input.len() + self.depth\n    }\n}\n\nfn helper() -> u32 {\n    42\n}\n";
+        let mut parse = symbol("parse", SymbolKind::Method, 6, Some("Parser"));
+        parse.signature = Some("pub fn parse(&self, input: &str) -> usize".to_string());
+        let repo = Repository {
+            name: "demo".to_string(),
+            path: "/demo".into(),
+            files: vec![
+                file(
+                    "src/lib.rs",
+                    "rust",
+                    lib,
+                    vec![
+                        symbol("Parser", SymbolKind::Struct, 1, None),
+                        parse,
+                        symbol("helper", SymbolKind::Function, 11, None),
+                    ],
+                ),
+                file(
+                    "src/bin/cli.py",
+                    "python",
+                    "def run():\n    pass\n",
+                    vec![symbol("run", SymbolKind::Function, 1, None)],
+                ),
+                file(
+                    "web/app.js",
+                    "javascript",
+                    "import { greet } from './greet';\n\nclass App {\n  start() {}\n}\n",
+                    vec![
+                        symbol("import { greet } from './greet'", SymbolKind::Import, 1, None),
+                        symbol("App", SymbolKind::Class, 3, None),
+                        symbol("start", SymbolKind::Method, 4, Some("App")),
+                    ],
+                ),
+                file(
+                    "web/greet.js",
+                    "javascript",
+                    "export function greet(name) {\n  return name;\n}\n",
+                    vec![symbol("greet", SymbolKind::Function, 1, None)],
+                ),
+                file("README.md", "markdown", "# demo\n", Vec::new()),
+            ],
+            metadata: RepoMetadata::default(),
+        };
+
+        let output = SignatureFormatter::new().format_repo(&repo);
+        assert!(output.contains("./ 1\n  src/ 1\n    bin/ 1\n  web/ 2\n"));
+        assert!(output.contains("src/lib.rs\n  1: pub struct Parser\n    6: pub fn parse(&self, input: &str) -> usize\n"));
+        assert!(output.contains("src/bin/cli.py\n  1: def run()"));
+        assert!(!output.contains("helper"));
+        assert!(!output.contains("input.len()"));
+        assert!(output.contains("web/greet.js\n  1: export function greet(name)\n"));
+        // Neither the private class nor its method
+        assert!(!output.contains("start()"));
+        assert!(output.contains("## Dependencies\n\nweb/app.js -> web/greet.js\n"));
+
+        let output = SignatureFormatter::new()
+            .with_line_numbers(false)
+            .format_repo(&repo);
+        assert!(output.contains("  pub struct Parser\n"));
+    }
+}
@@ -5,9 +5,11 @@
 //!
 //! Format specification: https://github.com/toon-format/toon
 
-use crate::output::Formatter;
+use crate::output::{
+    sections, total_tokens, FormatVersion, Formatter, IndexEntry, IndexLimit, Section,
+};
 use crate::repomap::RepoMap;
-use crate::types::Repository;
+use crate::types::{Repository, TokenizerModel};
 use std::fmt::Write;
 
 /// TOON formatter - most token-efficient format for LLMs
@@ -18,12 +20,22 @@ pub struct ToonFormatter {
     use_tabular: bool,
     /// Include file index/summary section
     show_file_index: bool,
+    /// Files listed in the file index
+    index_limit: IndexLimit,
+    /// Layout version to write
+    version: FormatVersion,
 }
 
 impl ToonFormatter {
     /// Create a new TOON formatter with default settings
     pub fn new() -> Self {
-        Self { include_line_numbers: true, use_tabular: true, show_file_index: true }
+        Self {
+            include_line_numbers: true,
+            use_tabular: true,
+            show_file_index: true,
+            index_limit: IndexLimit::none(),
+            version: FormatVersion::default(),
+        }
     }
 
     /// Set line numbers option
@@ -44,7 +56,22 @@ impl ToonFormatter {
         self
     }
 
+    /// Limit the files listed in the file index
+    pub fn with_index_limit(mut self, limit: IndexLimit) -> Self {
+        self.index_limit = limit;
+        self
+    }
+
+    /// Set the layout version to write
+    pub fn with_format_version(mut self, version: FormatVersion) -> Self {
+        self.version = version;
+        self
+    }
+
     fn write_metadata(&self, output: &mut String, repo: &Repository) {
+        if let Some(version) = self.version.marker() {
+            writeln!(output, "format_version: {}", version).unwrap();
+        }
         writeln!(output, "metadata:").unwrap();
         writeln!(output, "  name: {}", repo.name).unwrap();
         writeln!(output, "  files: {}", repo.metadata.total_files).unwrap();
@@ -60,6 +87,10 @@ impl ToonFormatter {
         if let Some(ref commit) = repo.metadata.commit {
             writeln!(output, "  commit: {}", commit).unwrap();
         }
+        if !repo.metadata.topics.is_empty() {
+            let topics: Vec<&str> = repo.metadata.topics.iter().map(|t| t.id()).collect();
+            writeln!(output, "  topics[{}]: {}", topics.len(), topics.join(",")).unwrap();
+        }
         output.push('\n');
     }
 
@@ -76,6 +107,26 @@ impl ToonFormatter {
         output.push('\n');
     }
 
+    fn write_commands(&self, output: &mut String, repo: &Repository) {
+        if repo.metadata.commands.is_empty() {
+            return;
+        }
+
+        let count = repo.metadata.commands.len();
+        writeln!(output, "commands[{}]{{kind,command,source}}:", count).unwrap();
+        for command in &repo.metadata.commands {
+            writeln!(
+                output,
+                "  {},{},{}",
+                command.kind.name(),
+                escape_toon(&command.command),
+                escape_toon(&command.source)
+            )
+            .unwrap();
+        }
+        output.push('\n');
+    }
+
     fn write_directory_structure(&self, output: &mut String, repo: &Repository) {
         if let Some(ref structure) = repo.metadata.directory_structure {
             writeln!(output, "directory_structure: |").unwrap();
@@ -140,6 +191,21 @@ impl ToonFormatter {
                 .unwrap();
             }
         }
+
+        // External dependencies with their importers joined by `;`
+        if !map.external_usage.is_empty() {
+            let count = map.external_usage.len();
+            writeln!(output, "  dependency_usage[{}]{{package,files}}:", count).unwrap();
+            for usage in &map.external_usage {
+                writeln!(
+                    output,
+                    "    {},{}",
+                    escape_toon(&usage.package),
+                    escape_toon(&usage.files.join(";"))
+                )
+                .unwrap();
+            }
+        }
         output.push('\n');
     }
 
@@ -148,9 +214,23 @@ impl ToonFormatter {
             return;
         }
 
-        let count = repo.files.len();
-        writeln!(output, "file_index[{}]{{path,tokens,importance}}:", count).unwrap();
-        for file in &repo.files {
+        let index = self.index_limit.apply(repo);
+        let count = index.listed();
+        let explained = repo.files.iter().any(|f| !f.rank_factors.is_empty());
+        if explained {
+            writeln!(output, "file_index[{}]{{path,tokens,importance,ranked_by}}:", count).unwrap();
+        } else {
+            writeln!(output, "file_index[{}]{{path,tokens,importance}}:", count).unwrap();
+        }
+        let mut rollups = Vec::new();
+        for entry in &index.entries {
+            let file = match entry {
+                IndexEntry::File(file) => file,
+                IndexEntry::Rollup { directory, files } => {
+                    rollups.push((directory, files));
+                    continue;
+                },
+            };
             let importance = if file.importance > 0.8 {
                 "critical"
             } else if file.importance > 0.6 {
@@ -160,7 +240,7 @@ impl ToonFormatter {
             } else {
                 "low"
             };
-            writeln!(
+            write!(
                 output,
                 "  {},{},{}",
                 escape_toon(&file.relative_path),
@@ -168,6 +248,33 @@ impl ToonFormatter {
                 importance
             )
             .unwrap();
+            if explained {
+                write!(output, ",{}", escape_toon(&file.rank_factors.join("; "))).unwrap();
+            }
+            writeln!(output).unwrap();
+        }
+        if !rollups.is_empty() {
+            writeln!(output, "file_index_rollups[{}]{{directory,files,tokens}}:", rollups.len())
+                .unwrap();
+            for (directory, files) in rollups {
+                writeln!(
+                    output,
+                    "  {},{},{}",
+                    escape_toon(directory),
+                    files.len(),
+                    total_tokens(files, TokenizerModel::Claude)
+                )
+                .unwrap();
+            }
+        }
+        if !index.omitted.is_empty() {
+            writeln!(
+                output,
+                "file_index_omitted: {} files, {} tokens",
+                index.omitted.len(),
+                total_tokens(&index.omitted, TokenizerModel::Claude)
+            )
+            .unwrap();
         }
         output.push('\n');
     }
@@ -176,23 +283,34 @@ impl ToonFormatter {
         writeln!(output, "files:").unwrap();
 
         for file in &repo.files {
-            if let Some(ref content) = file.content {
-                // Compact file header: path|language|tokens
+            if file.content.is_some() || file.outline_only {
+                // Compact file header: path|language|tokens[|git change][|outline]
                 let lang = file.language.as_deref().unwrap_or("?");
-                writeln!(
+                write!(
                     output,
-                    "- {}|{}|{}:",
+                    "- {}|{}|{}",
                     escape_toon(&file.relative_path),
                     lang,
                     file.token_count.claude
                 )
                 .unwrap();
+                if let Some(change) = &file.git_change {
+                    write!(output, "|{}", change.describe()).unwrap();
+                }
+                let Some(content) = &file.content else {
+                    writeln!(output, "|outline:").unwrap();
+                    for line in file.outline() {
+                        writeln!(output, "  {}", line).unwrap();
+                    }
+                    continue;
+                };
+                writeln!(output, ":").unwrap();
 
                 // Content with minimal line numbers
                 if self.include_line_numbers {
-                    for (i, line) in content.lines().enumerate() {
+                    for (number, line) in file.numbered_lines() {
                         // Use variable-width line numbers with single space after
-                        writeln!(output, "  {}:{}", i + 1, line).unwrap();
+                        writeln!(output, "  {}:{}", number, line).unwrap();
                     }
                 } else {
                     for line in content.lines() {
@@ -212,6 +330,15 @@ impl Default for ToonFormatter {
 
 impl Formatter for ToonFormatter {
     fn format(&self, repo: &Repository, map: &RepoMap) -> String {
+        self.format_with_sections(repo, map, &[])
+    }
+
+    fn format_with_sections(
+        &self,
+        repo: &Repository,
+        map: &RepoMap,
+        sections: &[Section],
+    ) -> String {
         let mut output = String::new();
 
         // TOON header comment
@@ -221,6 +348,7 @@ impl Formatter for ToonFormatter {
 
         self.write_metadata(&mut output, repo);
         self.write_languages(&mut output, repo);
+        self.write_commands(&mut output, repo);
         self.write_directory_structure(&mut output, repo);
         self.write_dependencies(&mut output, repo);
         self.write_repomap(&mut output, map);
@@ -228,6 +356,7 @@ impl Formatter for ToonFormatter {
             self.write_file_index(&mut output, repo);
         }
         self.write_files(&mut output, repo);
+        sections::write_toon(&mut output, sections);
 
         output
     }
@@ -240,6 +369,7 @@ impl Formatter for ToonFormatter {
 
         self.write_metadata(&mut output, repo);
         self.write_languages(&mut output, repo);
+        self.write_commands(&mut output, repo);
         self.write_directory_structure(&mut output, repo);
         self.write_dependencies(&mut output, repo);
         if self.show_file_index {
@@ -262,7 +392,7 @@ impl Formatter for ToonFormatter {
 /// - Matches reserved literals (true, false, null)
 /// - Matches numeric patterns
 /// - Contains control characters or delimiters (comma, pipe, newline)
-fn escape_toon(s: &str) -> String {
+pub(super) fn escape_toon(s: &str) -> String {
     // Check if quoting is needed
     let needs_quotes = s.is_empty()
         || s.starts_with(' ')
@@ -309,9 +439,9 @@ mod tests {
                 language: Some("python".to_string()),
                 size_bytes: 100,
                 token_count: TokenCounts { claude: 50, gpt4o: 48, gpt4: 49, gemini: 47, llama: 46 },
-                symbols: Vec::new(),
                 importance: 0.8,
                 content: Some("def main():\n    print('hello')".to_string()),
+                ..Default::default()
             }],
             metadata: RepoMetadata {
                 total_files: 1,
@@ -335,6 +465,8 @@ mod tests {
                 commit: None,
                 directory_structure: Some("main.py\n".to_string()),
                 external_dependencies: vec!["requests".to_string(), "numpy".to_string()],
+                commands: Vec::new(),
+                topics: Vec::new(),
                 git_history: None,
             },
         }
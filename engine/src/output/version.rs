@@ -0,0 +1,119 @@
+//! Versions of the output layouts
+//!
+//! Every output says which layout it follows with a `format_version` marker,
+//! so a parser can check it is reading a layout it understands. One number
+//! covers all formats. It goes up with any change that can break a parser
+//! written for the previous layout: a renamed, moved or removed element,
+//! field or section, or content encoded differently. New optional fields do
+//! not change it.
+//!
+//! Deprecation policy: the formatter factory produces the current layout by
+//! default and the one before it on request, with a deprecation warning to
+//! pass on to the user. Older layouts are removed when a new version comes
+//! out, and requesting one is an error.
+//!
+//! | Version | Layout |
+//! |---------|--------|
+//! | 2 | `format_version` marker in every format; the XML root drops `version="1.0.0"` |
+//! | 1 | No marker (deprecated) |
+
+use std::fmt;
+use thiserror::Error;
+
+/// Version of the current output layout
+pub const FORMAT_VERSION: u32 = 2;
+
+/// Oldest layout still produced
+pub const OLDEST_FORMAT_VERSION: u32 = FORMAT_VERSION - 1;
+
+/// Layout version the formatters write, checked against the deprecation
+/// policy
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct FormatVersion(u32);
+
+/// A requested layout version that is not produced
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Error)]
+pub enum FormatVersionError {
+    /// No such version was ever released
+    #[error("format version {0} does not exist; the current one is {FORMAT_VERSION}")]
+    Unknown(u32),
+    /// The version was removed under the deprecation policy
+    #[error(
+        "format version {0} is no longer supported; the oldest supported one is {OLDEST_FORMAT_VERSION}"
+    )]
+    Removed(u32),
+}
+
+impl FormatVersion {
+    /// The current layout
+    pub const CURRENT: Self = Self(FORMAT_VERSION);
+
+    /// A supported layout version
+    pub fn new(version: u32) -> Result<Self, FormatVersionError> {
+        if version == 0 || version > FORMAT_VERSION {
+            Err(FormatVersionError::Unknown(version))
+        } else if version < OLDEST_FORMAT_VERSION {
+            Err(FormatVersionError::Removed(version))
+        } else {
+            Ok(Self(version))
+        }
+    }
+
+    /// The version number
+    pub fn number(self) -> u32 {
+        self.0
+    }
+
+    /// Whether the layout will be removed with the next version
+    pub fn is_deprecated(self) -> bool {
+        self.0 < FORMAT_VERSION
+    }
+
+    /// Warning to show for a deprecated layout
+    pub fn deprecation(self) -> Option<String> {
+        self.is_deprecated().then(|| {
+            format!(
+                "format version {} is deprecated and will be removed in the next release that changes the layout; update parsers to version {}",
+                self.0, FORMAT_VERSION
+            )
+        })
+    }
+
+    /// The `format_version` marker outputs carry, if the layout has one
+    pub(crate) fn marker(self) -> Option<u32> {
+        (self.0 >= 2).then_some(self.0)
+    }
+}
+
+impl Default for FormatVersion {
+    fn default() -> Self {
+        Self::CURRENT
+    }
+}
+
+impl fmt::Display for FormatVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_deprecation_policy() {
+        assert_eq!(FormatVersion::default().number(), FORMAT_VERSION);
+        assert!(FormatVersion::CURRENT.deprecation().is_none());
+
+        let previous = FormatVersion::new(FORMAT_VERSION - 1).unwrap();
+        assert!(previous.is_deprecated());
+        assert!(previous.deprecation().unwrap().contains("deprecated"));
+
+        assert_eq!(FormatVersion::new(0), Err(FormatVersionError::Unknown(0)));
+        assert_eq!(
+            FormatVersion::new(FORMAT_VERSION + 1),
+            Err(FormatVersionError::Unknown(FORMAT_VERSION + 1))
+        );
+    }
+}
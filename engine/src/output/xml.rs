@@ -6,9 +6,16 @@
 //! 3. Showing architecture and dependencies
 //! 4. Prioritizing files by importance for code tasks
 
-use crate::output::Formatter;
+use crate::entry_points::{detect_entry_points, EntrySource};
+use crate::ids::IdTable;
+use crate::output::lint::escape_controls;
+use crate::output::{
+    sections, total_tokens, FormatVersion, Formatter, IndexEntry, IndexLimit, Locale, Section,
+};
 use crate::repomap::RepoMap;
-use crate::types::Repository;
+use crate::types::{RepoFile, Repository, TokenizerModel};
+use std::borrow::Cow;
+use std::collections::HashMap;
 use std::fmt::Write;
 
 /// XML formatter optimized for Claude
@@ -21,12 +28,26 @@ pub struct XmlFormatter {
     use_cdata: bool,
     /// Include file index/summary section
     show_file_index: bool,
+    /// Files listed in the file index
+    index_limit: IndexLimit,
+    /// Language of the context guide
+    locale: Locale,
+    /// Layout version to write
+    version: FormatVersion,
 }
 
 impl XmlFormatter {
     /// Create a new XML formatter
     pub fn new(cache_optimized: bool) -> Self {
-        Self { include_line_numbers: true, cache_optimized, use_cdata: true, show_file_index: true }
+        Self {
+            include_line_numbers: true,
+            cache_optimized,
+            use_cdata: true,
+            show_file_index: true,
+            index_limit: IndexLimit::none(),
+            locale: Locale::default(),
+            version: FormatVersion::default(),
+        }
     }
 
     /// Set line numbers option
@@ -47,23 +68,54 @@ impl XmlFormatter {
         self
     }
 
+    /// Limit the files listed in the file index
+    pub fn with_index_limit(mut self, limit: IndexLimit) -> Self {
+        self.index_limit = limit;
+        self
+    }
+
+    /// Set the language of the context guide
+    pub fn with_locale(mut self, locale: Locale) -> Self {
+        self.locale = locale;
+        self
+    }
+
+    /// Set the layout version to write
+    pub fn with_format_version(mut self, version: FormatVersion) -> Self {
+        self.version = version;
+        self
+    }
+
+    /// Opening `<repository>` tag; `unversioned` are the attributes of
+    /// layouts without a version marker
+    fn write_root(&self, output: &mut String, repo: &Repository, unversioned: &str) {
+        let name = escape_xml(&repo.name);
+        if let Some(version) = self.version.marker() {
+            writeln!(output, r#"<repository name="{}" format_version="{}">"#, name, version)
+        } else {
+            writeln!(output, r#"<repository name="{}"{}>"#, name, unversioned)
+        }
+        .unwrap();
+    }
+
     fn write_llm_instructions(&self, output: &mut String, repo: &Repository) {
+        let text = self.locale.strings();
         writeln!(output, "  <llm_context_guide>").unwrap();
-        writeln!(output, "    <purpose>This is a comprehensive code context for the {} repository, optimized for AI-assisted code understanding and generation.</purpose>", escape_xml(&repo.name)).unwrap();
-        writeln!(output, "    <how_to_use>").unwrap();
-        writeln!(output, "      <tip>Start with the <overview> section to understand the project's purpose and structure</tip>").unwrap();
-        writeln!(output, "      <tip>Check <entry_points> to find main application files</tip>")
-            .unwrap();
-        writeln!(
-            output,
-            "      <tip>Use <repository_map> to understand relationships between modules</tip>"
-        )
-        .unwrap();
         writeln!(
             output,
-            "      <tip>Files are ordered by importance - most critical files come first</tip>"
+            "    <purpose>{}</purpose>",
+            escape_xml(&text.guide_purpose.replace("{repo}", &repo.name))
         )
         .unwrap();
+        writeln!(output, "    <how_to_use>").unwrap();
+        for tip in [
+            text.guide_overview,
+            text.guide_entry_points,
+            text.guide_repository_map,
+            text.guide_file_order,
+        ] {
+            writeln!(output, "      <tip>{}</tip>", escape_xml(tip)).unwrap();
+        }
         writeln!(output, "    </how_to_use>").unwrap();
         writeln!(output, "  </llm_context_guide>").unwrap();
     }
@@ -90,16 +142,53 @@ impl XmlFormatter {
             writeln!(output, "    <framework>{}</framework>", escape_xml(framework)).unwrap();
         }
 
-        // Auto-detect entry points (exclude empty __init__.py files)
+        // Inferred topics
+        if !repo.metadata.topics.is_empty() {
+            writeln!(output, "    <topics>").unwrap();
+            for topic in &repo.metadata.topics {
+                writeln!(output, "      <topic>{}</topic>", topic.id()).unwrap();
+            }
+            writeln!(output, "    </topics>").unwrap();
+        }
+
+        // Declared build, test and run commands
+        if !repo.metadata.commands.is_empty() {
+            writeln!(output, "    <commands>").unwrap();
+            for command in &repo.metadata.commands {
+                writeln!(
+                    output,
+                    "      <command kind=\"{}\" source=\"{}\">{}</command>",
+                    command.kind.name(),
+                    escape_xml(&command.source),
+                    escape_xml(&command.command)
+                )
+                .unwrap();
+            }
+            writeln!(output, "    </commands>").unwrap();
+        }
+
+        // Auto-detect entry points by name, code and manifests (exclude
+        // empty __init__.py files)
         writeln!(output, "    <entry_points>").unwrap();
+        let detected: HashMap<String, EntrySource> = detect_entry_points(repo)
+            .into_iter()
+            .map(|e| (e.path, e.source))
+            .collect();
         let mut entry_count = 0;
         for file in &repo.files {
-            if self.is_entry_point(&file.relative_path) {
+            let source = detected.get(&file.relative_path);
+            if source.is_some() || self.is_entry_point(&file.relative_path) {
                 // Skip empty __init__.py files
-                if file.relative_path.ends_with("__init__.py") && file.token_count.claude < 50 {
+                if source.is_none()
+                    && file.relative_path.ends_with("__init__.py")
+                    && file.token_count.claude < 50
+                {
                     continue;
                 }
-                let entry_type = self.get_entry_type(&file.relative_path);
+                let entry_type = match source {
+                    Some(source) => source.name(),
+                    None => self.get_entry_type(&file.relative_path),
+                };
                 writeln!(
                     output,
                     "      <entry path=\"{}\" type=\"{}\" tokens=\"{}\"/>",
@@ -306,7 +395,7 @@ impl XmlFormatter {
         // Directory structure
         if let Some(ref structure) = repo.metadata.directory_structure {
             writeln!(output, "    <directory_structure><![CDATA[").unwrap();
-            output.push_str(structure);
+            output.push_str(&escape_cdata(structure));
             writeln!(output, "]]></directory_structure>").unwrap();
         }
 
@@ -344,8 +433,22 @@ impl XmlFormatter {
                         escape_xml(&commit.date)
                     )
                     .unwrap();
-                    writeln!(output, "        <message><![CDATA[{}]]></message>", commit.message)
+                    writeln!(
+                        output,
+                        "        <message><![CDATA[{}]]></message>",
+                        escape_cdata(&commit.message)
+                    )
+                    .unwrap();
+                    for symbol in &commit.symbols {
+                        writeln!(
+                            output,
+                            "        <symbol change=\"{}\" path=\"{}\" name=\"{}\"/>",
+                            symbol.change.name(),
+                            escape_xml(&symbol.path),
+                            escape_xml(&symbol.name)
+                        )
                         .unwrap();
+                    }
                     writeln!(output, "      </commit>").unwrap();
                 }
                 writeln!(output, "    </recent_commits>").unwrap();
@@ -379,14 +482,15 @@ impl XmlFormatter {
         writeln!(output, "  <repository_map token_budget=\"{}\">", map.token_count).unwrap();
 
         // Summary with CDATA
-        writeln!(output, "    <summary><![CDATA[{}]]></summary>", map.summary).unwrap();
+        writeln!(output, "    <summary><![CDATA[{}]]></summary>", escape_cdata(&map.summary))
+            .unwrap();
 
         // Key symbols
         writeln!(output, "    <key_symbols>").unwrap();
         for symbol in &map.key_symbols {
-            writeln!(
+            write!(
                 output,
-                "      <symbol name=\"{}\" type=\"{}\" file=\"{}\" line=\"{}\" rank=\"{}\">",
+                "      <symbol name=\"{}\" type=\"{}\" file=\"{}\" line=\"{}\" rank=\"{}\"",
                 escape_xml(&symbol.name),
                 escape_xml(&symbol.kind),
                 escape_xml(&symbol.file),
@@ -394,9 +498,18 @@ impl XmlFormatter {
                 symbol.rank
             )
             .unwrap();
+            if let Some(id) = &symbol.id {
+                write!(output, " id=\"{}\"", id).unwrap();
+            }
+            writeln!(output, ">").unwrap();
 
             if let Some(sig) = &symbol.signature {
-                writeln!(output, "        <signature><![CDATA[{}]]></signature>", sig).unwrap();
+                writeln!(
+                    output,
+                    "        <signature><![CDATA[{}]]></signature>",
+                    escape_cdata(sig)
+                )
+                .unwrap();
             }
 
             writeln!(output, "      </symbol>").unwrap();
@@ -419,29 +532,55 @@ impl XmlFormatter {
             writeln!(output, "    </modules>").unwrap();
         }
 
+        // External dependencies by usage
+        if !map.external_usage.is_empty() {
+            writeln!(output, "    <dependency_usage>").unwrap();
+            for usage in &map.external_usage {
+                writeln!(
+                    output,
+                    "      <dependency name=\"{}\" files=\"{}\">",
+                    escape_xml(&usage.package),
+                    usage.files.len()
+                )
+                .unwrap();
+                for file in &usage.files {
+                    writeln!(output, "        <importer path=\"{}\"/>", escape_xml(file)).unwrap();
+                }
+                writeln!(output, "      </dependency>").unwrap();
+            }
+            writeln!(output, "    </dependency_usage>").unwrap();
+        }
+
         writeln!(output, "  </repository_map>").unwrap();
     }
 
-    fn write_file_index(&self, output: &mut String, repo: &Repository) {
-        writeln!(output, "  <file_index entries=\"{}\">", repo.files.len()).unwrap();
-
-        for file in &repo.files {
-            let importance = if file.importance > 0.8 {
-                "critical"
-            } else if file.importance > 0.6 {
-                "high"
-            } else if file.importance > 0.3 {
-                "normal"
-            } else {
-                "low"
-            };
+    fn write_file_index(&self, output: &mut String, repo: &Repository, ids: &IdTable) {
+        let index = self.index_limit.apply(repo);
+        write!(output, "  <file_index entries=\"{}\"", repo.files.len()).unwrap();
+        if !self.index_limit.is_none() {
+            write!(output, " listed=\"{}\"", index.listed()).unwrap();
+        }
+        writeln!(output, ">").unwrap();
 
+        for entry in &index.entries {
+            match entry {
+                IndexEntry::File(file) => self.write_index_file(output, file, ids),
+                IndexEntry::Rollup { directory, files } => writeln!(
+                    output,
+                    "    <directory path=\"{}\" files=\"{}\" tokens=\"{}\"/>",
+                    escape_xml(directory),
+                    files.len(),
+                    total_tokens(files, TokenizerModel::Claude)
+                )
+                .unwrap(),
+            }
+        }
+        if !index.omitted.is_empty() {
             writeln!(
                 output,
-                "    <file path=\"{}\" tokens=\"{}\" importance=\"{}\"/>",
-                escape_xml(&file.relative_path),
-                file.token_count.claude,
-                importance
+                "    <omitted files=\"{}\" tokens=\"{}\"/>",
+                index.omitted.len(),
+                total_tokens(&index.omitted, TokenizerModel::Claude)
             )
             .unwrap();
         }
@@ -449,30 +588,101 @@ impl XmlFormatter {
         writeln!(output, "  </file_index>").unwrap();
     }
 
-    fn write_files(&self, output: &mut String, repo: &Repository) {
+    fn write_index_file(&self, output: &mut String, file: &RepoFile, ids: &IdTable) {
+        let importance = if file.importance > 0.8 {
+            "critical"
+        } else if file.importance > 0.6 {
+            "high"
+        } else if file.importance > 0.3 {
+            "normal"
+        } else {
+            "low"
+        };
+
+        write!(
+            output,
+            "    <file path=\"{}\" tokens=\"{}\" importance=\"{}\"",
+            escape_xml(&file.relative_path),
+            file.token_count.claude,
+            importance
+        )
+        .unwrap();
+        if let Some(id) = ids.file(&file.relative_path) {
+            write!(output, " id=\"{}\"", id).unwrap();
+        }
+        if let Some(summary) = &file.summary {
+            write!(output, " summary=\"{}\"", escape_xml(summary)).unwrap();
+        }
+        if !file.rank_factors.is_empty() {
+            write!(output, " ranked_by=\"{}\"", escape_xml(&file.rank_factors.join("; "))).unwrap();
+        }
+        writeln!(output, "/>").unwrap();
+    }
+
+    fn write_files(&self, output: &mut String, repo: &Repository, ids: &IdTable) {
         writeln!(output, "  <files>").unwrap();
 
         for file in &repo.files {
-            if let Some(content) = &file.content {
-                writeln!(
+            if file.content.is_some() || file.outline_only {
+                write!(
                     output,
-                    "    <file path=\"{}\" language=\"{}\" tokens=\"{}\">",
+                    "    <file path=\"{}\" language=\"{}\" tokens=\"{}\"",
                     escape_xml(&file.relative_path),
                     file.language.as_deref().unwrap_or("unknown"),
                     file.token_count.claude
                 )
                 .unwrap();
-
-                if self.include_line_numbers {
-                    writeln!(output, "      <content line_numbers=\"true\"><![CDATA[").unwrap();
-                    for (i, line) in content.lines().enumerate() {
-                        writeln!(output, "{:4} | {}", i + 1, line).unwrap();
+                if let Some(id) = ids.file(&file.relative_path) {
+                    write!(output, " id=\"{}\"", id).unwrap();
+                }
+                if let Some(change) = &file.git_change {
+                    write!(
+                        output,
+                        " git_status=\"{}\" additions=\"{}\" deletions=\"{}\"",
+                        escape_xml(&change.status),
+                        change.additions,
+                        change.deletions
+                    )
+                    .unwrap();
+                    if !change.symbols.is_empty() {
+                        let symbols: Vec<String> = change
+                            .symbols
+                            .iter()
+                            .map(|s| format!("{}: {}", s.change.name(), s.name))
+                            .collect();
+                        write!(output, " changed_symbols=\"{}\"", escape_xml(&symbols.join(", ")))
+                            .unwrap();
+                    }
+                }
+                if file.outline_only {
+                    write!(output, " outline=\"true\"").unwrap();
+                }
+                writeln!(output, ">").unwrap();
+
+                if let Some(content) = &file.content {
+                    if self.include_line_numbers {
+                        writeln!(output, "      <content line_numbers=\"true\"><![CDATA[").unwrap();
+                        for (number, line) in file.numbered_lines() {
+                            writeln!(output, "{:4} | {}", number, escape_cdata(line)).unwrap();
+                        }
+                        writeln!(output, "]]></content>").unwrap();
+                    } else if self.use_cdata {
+                        writeln!(
+                            output,
+                            "      <content><![CDATA[{}]]></content>",
+                            escape_cdata(content)
+                        )
+                        .unwrap();
+                    } else {
+                        writeln!(output, "      <content>{}</content>", escape_xml(content))
+                            .unwrap();
                     }
-                    writeln!(output, "]]></content>").unwrap();
-                } else if self.use_cdata {
-                    writeln!(output, "      <content><![CDATA[{}]]></content>", content).unwrap();
                 } else {
-                    writeln!(output, "      <content>{}</content>", escape_xml(content)).unwrap();
+                    writeln!(output, "      <outline>").unwrap();
+                    for line in file.outline() {
+                        writeln!(output, "        {}", escape_xml(&line)).unwrap();
+                    }
+                    writeln!(output, "      </outline>").unwrap();
                 }
 
                 writeln!(output, "    </file>").unwrap();
@@ -485,12 +695,20 @@ impl XmlFormatter {
 
 impl Formatter for XmlFormatter {
     fn format(&self, repo: &Repository, map: &RepoMap) -> String {
+        self.format_with_sections(repo, map, &[])
+    }
+
+    fn format_with_sections(
+        &self,
+        repo: &Repository,
+        map: &RepoMap,
+        sections: &[Section],
+    ) -> String {
         let mut output = String::new();
 
         // XML declaration
         writeln!(output, r#"<?xml version="1.0" encoding="UTF-8"?>"#).unwrap();
-        writeln!(output, r#"<repository name="{}" version="1.0.0">"#, escape_xml(&repo.name))
-            .unwrap();
+        self.write_root(&mut output, repo, r#" version="1.0.0""#);
 
         // LLM context guide (helps LLMs understand how to use this context)
         self.write_llm_instructions(&mut output, repo);
@@ -508,9 +726,10 @@ impl Formatter for XmlFormatter {
         // Git history (if available) - provides context on recent changes
         self.write_git_history(&mut output, repo);
 
+        let ids = IdTable::new(repo);
         self.write_repomap(&mut output, map);
         if self.show_file_index {
-            self.write_file_index(&mut output, repo);
+            self.write_file_index(&mut output, repo, &ids);
         }
 
         if self.cache_optimized {
@@ -518,12 +737,13 @@ impl Formatter for XmlFormatter {
             writeln!(output, "  <!-- DYNAMIC_CONTENT_START -->").unwrap();
         }
 
-        self.write_files(&mut output, repo);
+        self.write_files(&mut output, repo, &ids);
 
         if self.cache_optimized {
             writeln!(output, "  <!-- DYNAMIC_CONTENT_END -->").unwrap();
         }
 
+        sections::write_xml(&mut output, sections);
         writeln!(output, "</repository>").unwrap();
 
         output
@@ -533,13 +753,14 @@ impl Formatter for XmlFormatter {
         let mut output = String::new();
 
         writeln!(output, r#"<?xml version="1.0" encoding="UTF-8"?>"#).unwrap();
-        writeln!(output, r#"<repository name="{}">"#, escape_xml(&repo.name)).unwrap();
+        self.write_root(&mut output, repo, "");
 
+        let ids = IdTable::new(repo);
         self.write_metadata(&mut output, repo);
         if self.show_file_index {
-            self.write_file_index(&mut output, repo);
+            self.write_file_index(&mut output, repo, &ids);
         }
-        self.write_files(&mut output, repo);
+        self.write_files(&mut output, repo, &ids);
 
         writeln!(output, "</repository>").unwrap();
 
@@ -551,12 +772,23 @@ impl Formatter for XmlFormatter {
     }
 }
 
+/// Text to put in a CDATA section
+///
+/// A `]]>` in the text would end the section early, so it is split across
+/// two sections; characters XML cannot carry are escaped.
+fn escape_cdata(s: &str) -> Cow<'_, str> {
+    match escape_controls(s) {
+        text if text.contains("]]>") => Cow::Owned(text.replace("]]>", "]]]]><![CDATA[>")),
+        text => text,
+    }
+}
+
 /// Escape XML special characters (single-pass for performance)
-fn escape_xml(s: &str) -> String {
+pub(crate) fn escape_xml(s: &str) -> String {
     // Pre-allocate with some extra capacity for escapes
     let mut result = String::with_capacity(s.len() + s.len() / 10);
 
-    for c in s.chars() {
+    for c in escape_controls(s).chars() {
         match c {
             '&' => result.push_str("&amp;"),
             '<' => result.push_str("&lt;"),
@@ -587,9 +819,9 @@ mod tests {
                 language: Some("python".to_string()),
                 size_bytes: 100,
                 token_count: TokenCounts { claude: 50, gpt4o: 48, gpt4: 49, gemini: 47, llama: 46 },
-                symbols: Vec::new(),
                 importance: 0.8,
                 content: Some("def main():\n    print('hello')".to_string()),
+                ..Default::default()
             }],
             metadata: RepoMetadata {
                 total_files: 1,
@@ -613,6 +845,8 @@ mod tests {
                 commit: None,
                 directory_structure: Some("main.py\n".to_string()),
                 external_dependencies: vec!["requests".to_string(), "numpy".to_string()],
+                commands: Vec::new(),
+                topics: Vec::new(),
                 git_history: None,
             },
         }
@@ -630,6 +864,71 @@ mod tests {
         assert!(output.contains("<repository name=\"test\""));
         assert!(output.contains("CACHEABLE_PREFIX_START"));
         assert!(output.contains("<file path=\"main.py\""));
+
+        // The file carries the same ID in the index and with its content
+        let id = IdTable::new(&repo).file("main.py").unwrap().to_owned();
+        assert_eq!(output.matches(&format!(" id=\"{}\"", id)).count(), 2);
+    }
+
+    #[test]
+    fn test_xml_outline_only_file() {
+        let mut repo = create_test_repo();
+        let mut symbol = crate::types::Symbol::new("main", crate::types::SymbolKind::Function);
+        symbol.signature = Some("def main():".to_string());
+        symbol.start_line = 1;
+        symbol.end_line = 2;
+        repo.files[0].symbols.push(symbol);
+        assert!(repo.files[0].reduce_to_outline());
+
+        let map = RepoMapGenerator::new(1000).generate(&repo);
+        let output = XmlFormatter::new(true).format(&repo, &map);
+        assert!(output.contains("outline=\"true\">"));
+        assert!(output.contains("<outline>\n        1-2 def main():\n      </outline>"));
+        assert!(!output.contains("print('hello')"));
+    }
+
+    #[test]
+    fn test_xml_commands() {
+        let mut repo = create_test_repo();
+        repo.metadata
+            .commands
+            .push(crate::commands::ProjectCommand {
+                kind: crate::commands::CommandKind::Test,
+                command: "pytest -q && echo ok".to_string(),
+                source: "Makefile".to_string(),
+            });
+
+        let map = RepoMapGenerator::new(1000).generate(&repo);
+        let output = XmlFormatter::new(true).format(&repo, &map);
+        assert!(output.contains(
+            "<command kind=\"test\" source=\"Makefile\">pytest -q &amp;&amp; echo ok</command>"
+        ));
+    }
+
+    #[test]
+    fn test_xml_topics() {
+        let mut repo = create_test_repo();
+        repo.metadata.topics =
+            vec![crate::topics::Topic::MachineLearning, crate::topics::Topic::CliTool];
+
+        let map = RepoMapGenerator::new(1000).generate(&repo);
+        let output = XmlFormatter::new(true).format(&repo, &map);
+        assert!(output.contains(
+            "<topics>\n      <topic>machine-learning</topic>\n      <topic>cli-tool</topic>\n    </topics>"
+        ));
+    }
+
+    #[test]
+    fn test_xml_entry_points_from_code() {
+        let mut repo = create_test_repo();
+        let mut script = repo.files[0].clone();
+        script.relative_path = "tools/export_data.py".to_string();
+        script.content = Some("if __name__ == \"__main__\":\n    run()\n".to_string());
+        repo.files.push(script);
+
+        let map = RepoMapGenerator::new(1000).generate(&repo);
+        let output = XmlFormatter::new(true).format(&repo, &map);
+        assert!(output.contains("<entry path=\"tools/export_data.py\" type=\"script\""));
     }
 
     #[test]
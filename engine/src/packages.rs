@@ -0,0 +1,506 @@
+//! Packages of monorepos and workspaces
+//!
+//! [`detect_packages`] finds the members of Cargo workspaces, npm, yarn and
+//! pnpm workspaces, and Go multi-module repositories from their manifests,
+//! along with which other members each one depends on. [`retain_packages`]
+//! narrows a repository to some of them and the members they depend on.
+//!
+//! A file belongs to the package whose directory is the longest prefix of its
+//! path, so a package nested in another one takes its files away from it.
+
+use crate::topics::{go_mod_deps, package_json_deps, toml_table_keys};
+use crate::types::Repository;
+use glob::{MatchOptions, Pattern};
+use serde::Serialize;
+use std::collections::{BTreeSet, HashSet, VecDeque};
+use std::fs;
+
+/// Workspace manifests at the root, kept when packing a member
+const ROOT_MANIFESTS: &[&str] = &["Cargo.toml", "package.json", "pnpm-workspace.yaml", "go.work"];
+
+/// Sections of `Cargo.toml` naming dependencies
+const CARGO_DEPENDENCIES: &[&str] = &["dependencies", "dev-dependencies", "build-dependencies"];
+
+/// Build system a package belongs to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PackageKind {
+    /// A crate of a Cargo workspace
+    Cargo,
+    /// A member of an npm, yarn or pnpm workspace
+    Npm,
+    /// A Go module
+    Go,
+}
+
+impl PackageKind {
+    /// Name of the package manager
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Cargo => "cargo",
+            Self::Npm => "npm",
+            Self::Go => "go",
+        }
+    }
+}
+
+/// A workspace member
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Package {
+    /// Name from the manifest; the module path for Go
+    pub name: String,
+    /// Directory relative to the repository root, empty for the root
+    pub path: String,
+    /// Build system
+    pub kind: PackageKind,
+    /// Names of the other members it depends on, sorted
+    pub dependencies: Vec<String>,
+}
+
+impl Package {
+    /// Whether the file at `path` lies in the package's directory
+    pub fn contains(&self, path: &str) -> bool {
+        self.path.is_empty()
+            || path
+                .strip_prefix(self.path.as_str())
+                .is_some_and(|rest| rest.starts_with('/'))
+    }
+}
+
+/// Packages kept by [`retain_packages`]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PackageSelection {
+    /// Packages asked for
+    pub selected: Vec<String>,
+    /// Members they depend on, directly or not
+    pub dependencies: Vec<String>,
+    /// Names that matched no package
+    pub unmatched: Vec<String>,
+}
+
+/// Members of the workspaces in `repo`, sorted by path
+///
+/// Manifests are read from file contents, or from disk for files scanned
+/// without them.
+pub fn detect_packages(repo: &Repository) -> Vec<Package> {
+    let mut packages = Vec::new();
+    packages.extend(cargo_packages(repo));
+    packages.extend(npm_packages(repo));
+    packages.extend(go_packages(repo));
+
+    let names: HashSet<String> = packages.iter().map(|p| p.name.clone()).collect();
+    for package in &mut packages {
+        let own = package.name.clone();
+        package
+            .dependencies
+            .retain(|d| *d != own && names.contains(d));
+        package.dependencies.sort();
+        package.dependencies.dedup();
+    }
+    packages.sort_by(|a, b| a.path.cmp(&b.path).then(a.kind.cmp(&b.kind)));
+    packages
+}
+
+/// Reduce a repository to the packages named in `names` and the members they
+/// depend on
+///
+/// A name is matched against package names and then package directories.
+/// Workspace manifests at the root are kept too. If no name matches, the
+/// repository is left empty.
+pub fn retain_packages(repo: &mut Repository, names: &[String]) -> PackageSelection {
+    let packages = detect_packages(repo);
+    let mut selection = PackageSelection::default();
+
+    let mut queue = VecDeque::new();
+    for name in names {
+        let name = name.trim().trim_start_matches("./").trim_end_matches('/');
+        match packages
+            .iter()
+            .position(|p| p.name == name)
+            .or_else(|| packages.iter().position(|p| p.path == name))
+        {
+            Some(i) => queue.push_back(i),
+            None => selection.unmatched.push(name.to_owned()),
+        }
+    }
+    let mut kept: BTreeSet<usize> = queue.iter().copied().collect();
+    let requested = kept.clone();
+    while let Some(i) = queue.pop_front() {
+        for dependency in &packages[i].dependencies {
+            for (j, _) in packages
+                .iter()
+                .enumerate()
+                .filter(|(_, p)| p.name == *dependency)
+            {
+                if kept.insert(j) {
+                    queue.push_back(j);
+                }
+            }
+        }
+    }
+    for &i in &kept {
+        let name = packages[i].name.clone();
+        if requested.contains(&i) {
+            selection.selected.push(name);
+        } else {
+            selection.dependencies.push(name);
+        }
+    }
+
+    if kept.is_empty() {
+        repo.files.clear();
+        return selection;
+    }
+    let kept_paths: HashSet<&str> = kept.iter().map(|&i| packages[i].path.as_str()).collect();
+    repo.files.retain(|file| {
+        let path = file.relative_path.as_str();
+        if ROOT_MANIFESTS.contains(&path) {
+            return true;
+        }
+        packages
+            .iter()
+            .filter(|p| p.contains(path))
+            .max_by_key(|p| p.path.len())
+            .is_some_and(|owner| kept_paths.contains(owner.path.as_str()))
+    });
+    selection
+}
+
+/// Crates of the workspace in the root `Cargo.toml`, or the root crate alone
+fn cargo_packages(repo: &Repository) -> Vec<Package> {
+    let Some(root) = read_manifest(repo, "Cargo.toml").and_then(|c| c.parse::<toml::Table>().ok())
+    else {
+        return Vec::new();
+    };
+    let mut manifests = vec![String::new()];
+    if let Some(workspace) = root.get("workspace").and_then(|w| w.as_table()) {
+        let members = patterns(workspace.get("members"));
+        let excluded = patterns(workspace.get("exclude"));
+        manifests.extend(
+            member_dirs(repo, "Cargo.toml")
+                .filter(|dir| matches_any(&members, dir) && !matches_any(&excluded, dir)),
+        );
+    }
+
+    manifests
+        .into_iter()
+        .filter_map(|dir| {
+            let content = read_manifest(repo, &manifest_path(&dir, "Cargo.toml"))?;
+            let table = content.parse::<toml::Table>().ok()?;
+            let name = table.get("package")?.get("name")?.as_str()?.to_owned();
+            Some(Package {
+                name,
+                path: dir,
+                kind: PackageKind::Cargo,
+                dependencies: toml_table_keys(&content, CARGO_DEPENDENCIES),
+            })
+        })
+        .collect()
+}
+
+/// Members of the npm or yarn workspaces in the root `package.json`, or of
+/// `pnpm-workspace.yaml`; the root package alone if there are none
+fn npm_packages(repo: &Repository) -> Vec<Package> {
+    let root = read_manifest(repo, "package.json")
+        .and_then(|c| serde_json::from_str::<serde_json::Value>(&c).ok());
+    let mut globs: Vec<String> = match root.as_ref().and_then(|r| r.get("workspaces")) {
+        // npm and yarn take a list, yarn 1 also `{ "packages": [...] }`
+        Some(serde_json::Value::Array(list)) => strings(list),
+        Some(serde_json::Value::Object(map)) => map
+            .get("packages")
+            .and_then(|p| p.as_array())
+            .map(|list| strings(list))
+            .unwrap_or_default(),
+        _ => Vec::new(),
+    };
+    if let Some(pnpm) = read_manifest(repo, "pnpm-workspace.yaml")
+        .and_then(|c| serde_yaml::from_str::<serde_yaml::Value>(&c).ok())
+    {
+        globs.extend(
+            pnpm.get("packages")
+                .and_then(|p| p.as_sequence())
+                .into_iter()
+                .flatten()
+                .filter_map(|p| p.as_str())
+                .map(str::to_owned),
+        );
+    }
+
+    let dirs: Vec<String> = if globs.is_empty() {
+        vec![String::new()]
+    } else {
+        let (excluded, included): (Vec<String>, Vec<String>) =
+            globs.into_iter().partition(|g| g.starts_with('!'));
+        let included = compile(included.iter().map(String::as_str));
+        let excluded = compile(excluded.iter().map(|g| &g[1..]));
+        member_dirs(repo, "package.json")
+            .filter(|dir| matches_any(&included, dir) && !matches_any(&excluded, dir))
+            .collect()
+    };
+
+    dirs.into_iter()
+        .filter_map(|dir| {
+            let content = read_manifest(repo, &manifest_path(&dir, "package.json"))?;
+            let value = serde_json::from_str::<serde_json::Value>(&content).ok()?;
+            let name = value.get("name")?.as_str()?.to_owned();
+            Some(Package {
+                name,
+                path: dir,
+                kind: PackageKind::Npm,
+                dependencies: package_json_deps(&content),
+            })
+        })
+        .collect()
+}
+
+/// Modules listed in `go.work`, or every `go.mod` when there is none
+fn go_packages(repo: &Repository) -> Vec<Package> {
+    let dirs: Vec<String> = match read_manifest(repo, "go.work") {
+        Some(work) => go_work_dirs(&work),
+        None => std::iter::once(String::new())
+            .chain(member_dirs(repo, "go.mod"))
+            .collect(),
+    };
+
+    dirs.into_iter()
+        .filter_map(|dir| {
+            let content = read_manifest(repo, &manifest_path(&dir, "go.mod"))?;
+            let name = content
+                .lines()
+                .find_map(|line| line.trim().strip_prefix("module "))?
+                .trim()
+                .trim_matches('"')
+                .to_owned();
+            Some(Package {
+                name,
+                path: dir,
+                kind: PackageKind::Go,
+                dependencies: go_mod_deps(&content),
+            })
+        })
+        .collect()
+}
+
+/// Directories of the `use` directives of a `go.work` file
+fn go_work_dirs(content: &str) -> Vec<String> {
+    let mut dirs = Vec::new();
+    let mut in_block = false;
+    for line in content.lines().map(str::trim) {
+        let dir = if line.starts_with("use (") {
+            in_block = true;
+            continue;
+        } else if in_block && line.starts_with(')') {
+            in_block = false;
+            continue;
+        } else if let Some(dir) = line.strip_prefix("use ") {
+            dir
+        } else if in_block {
+            line
+        } else {
+            continue;
+        };
+        let dir = dir
+            .split("//")
+            .next()
+            .unwrap_or_default()
+            .trim()
+            .trim_matches('"');
+        match dir.trim_start_matches("./").trim_end_matches('/') {
+            "" => {},
+            "." => dirs.push(String::new()),
+            dir => dirs.push(dir.to_owned()),
+        }
+    }
+    dirs
+}
+
+/// Directories below the root holding a file named `manifest`
+fn member_dirs<'a>(repo: &'a Repository, manifest: &'a str) -> impl Iterator<Item = String> + 'a {
+    repo.files.iter().filter_map(move |file| {
+        let dir = file
+            .relative_path
+            .strip_suffix(manifest)?
+            .strip_suffix('/')?;
+        Some(dir.to_owned())
+    })
+}
+
+/// Content of the manifest at `path`, from the scan or from disk
+fn read_manifest(repo: &Repository, path: &str) -> Option<String> {
+    match repo.files.iter().find(|f| f.relative_path == path) {
+        Some(file) => file
+            .content
+            .clone()
+            .or_else(|| fs::read_to_string(&file.path).ok()),
+        None => fs::read_to_string(repo.path.join(path)).ok(),
+    }
+}
+
+fn manifest_path(dir: &str, manifest: &str) -> String {
+    if dir.is_empty() {
+        manifest.to_owned()
+    } else {
+        format!("{}/{}", dir, manifest)
+    }
+}
+
+fn strings(list: &[serde_json::Value]) -> Vec<String> {
+    list.iter()
+        .filter_map(|v| v.as_str())
+        .map(str::to_owned)
+        .collect()
+}
+
+fn patterns(value: Option<&toml::Value>) -> Vec<Pattern> {
+    compile(
+        value
+            .and_then(|v| v.as_array())
+            .into_iter()
+            .flatten()
+            .filter_map(|v| v.as_str()),
+    )
+}
+
+fn compile<'a>(globs: impl Iterator<Item = &'a str>) -> Vec<Pattern> {
+    globs
+        .filter_map(|g| Pattern::new(g.trim_start_matches("./").trim_end_matches('/')).ok())
+        .collect()
+}
+
+fn matches_any(patterns: &[Pattern], dir: &str) -> bool {
+    let options = MatchOptions { require_literal_separator: true, ..MatchOptions::default() };
+    patterns.iter().any(|p| p.matches_with(dir, options))
+}
+
+#[cfg(test)]
+#[allow(clippy::str_to_string)]
+mod tests {
+    use super::*;
+    use crate::types::RepoFile;
+
+    fn repo(files: &[(&str, &str)]) -> Repository {
+        let mut repo = Repository::new("test", "/nonexistent");
+        for (path, content) in files {
+            let mut file = RepoFile::new(format!("/nonexistent/{}", path), *path);
+            file.content = Some(content.to_string());
+            repo.files.push(file);
+        }
+        repo
+    }
+
+    fn summary(packages: &[Package]) -> Vec<(&str, &str, Vec<&str>)> {
+        packages
+            .iter()
+            .map(|p| {
+                (
+                    p.name.as_str(),
+                    p.path.as_str(),
+                    p.dependencies.iter().map(String::as_str).collect(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_cargo_workspace() {
+        let repo = repo(&[
+            ("Cargo.toml", "[workspace]\nmembers = [\"crates/*\"]\nexclude = [\"crates/old\"]\n"),
+            ("crates/core/Cargo.toml", "[package]\nname = \"core\"\n"),
+            (
+                "crates/cli/Cargo.toml",
+                "[package]\nname = \"cli\"\n[dependencies]\ncore = { path = \"../core\" }\nserde = \"1\"\n",
+            ),
+            ("crates/old/Cargo.toml", "[package]\nname = \"old\"\n"),
+            ("crates/cli/tests/fixture/Cargo.toml", "[package]\nname = \"fixture\"\n"),
+        ]);
+        assert_eq!(
+            summary(&repo.packages()),
+            vec![("cli", "crates/cli", vec!["core"]), ("core", "crates/core", vec![])]
+        );
+        assert_eq!(repo.packages()[0].kind.name(), "cargo");
+    }
+
+    #[test]
+    fn test_npm_and_pnpm_workspaces() {
+        let npm = repo(&[
+            ("package.json", r#"{"name": "root", "workspaces": ["apps/*", "libs/*"]}"#),
+            ("apps/web/package.json", r#"{"name": "web", "dependencies": {"@acme/ui": "*"}}"#),
+            ("libs/ui/package.json", r#"{"name": "@acme/ui", "devDependencies": {"react": "18"}}"#),
+        ]);
+        assert_eq!(
+            summary(&npm.packages()),
+            vec![("web", "apps/web", vec!["@acme/ui"]), ("@acme/ui", "libs/ui", vec![])]
+        );
+
+        let pnpm = repo(&[
+            ("package.json", r#"{"name": "root", "private": true}"#),
+            ("pnpm-workspace.yaml", "packages:\n  - 'packages/**'\n  - '!packages/internal/**'\n"),
+            ("packages/a/package.json", r#"{"name": "a"}"#),
+            ("packages/internal/b/package.json", r#"{"name": "b"}"#),
+        ]);
+        assert_eq!(summary(&pnpm.packages()), vec![("a", "packages/a", vec![])]);
+
+        // Without workspaces the root package stands alone
+        let single = repo(&[("package.json", r#"{"name": "solo"}"#)]);
+        assert_eq!(summary(&single.packages()), vec![("solo", "", vec![])]);
+    }
+
+    #[test]
+    fn test_go_modules() {
+        let modules = [
+            ("api/go.mod", "module example.com/api\n\nrequire (\n\texample.com/shared v0.0.0\n)\n"),
+            ("shared/go.mod", "module example.com/shared\n"),
+        ];
+        let repo_without_work = repo(&modules);
+        assert_eq!(
+            summary(&repo_without_work.packages()),
+            vec![
+                ("example.com/api", "api", vec!["example.com/shared"]),
+                ("example.com/shared", "shared", vec![])
+            ]
+        );
+
+        let mut files = modules.to_vec();
+        files.push(("go.work", "go 1.22\n\nuse ./api\n"));
+        assert_eq!(summary(&repo(&files).packages()).len(), 1);
+    }
+
+    #[test]
+    fn test_retain_packages() {
+        let mut repo = repo(&[
+            ("Cargo.toml", "[workspace]\nmembers = [\"app\", \"lib\", \"lib/nested\", \"tool\"]\n"),
+            ("README.md", "# Workspace\n"),
+            ("app/Cargo.toml", "[package]\nname = \"app\"\n[dependencies]\nlib = \"*\"\n"),
+            ("app/src/main.rs", "fn main() {}\n"),
+            ("lib/Cargo.toml", "[package]\nname = \"lib\"\n"),
+            ("lib/src/lib.rs", "pub fn run() {}\n"),
+            ("lib/nested/Cargo.toml", "[package]\nname = \"nested\"\n"),
+            ("lib/nested/src/lib.rs", "\n"),
+            ("tool/Cargo.toml", "[package]\nname = \"tool\"\n"),
+        ]);
+        let selection = retain_packages(&mut repo, &["app".to_string(), "missing".to_string()]);
+        assert_eq!(selection.selected, vec!["app"]);
+        assert_eq!(selection.dependencies, vec!["lib"]);
+        assert_eq!(selection.unmatched, vec!["missing"]);
+        let paths: Vec<&str> = repo
+            .files
+            .iter()
+            .map(|f| f.relative_path.as_str())
+            .collect();
+        assert_eq!(
+            paths,
+            vec![
+                "Cargo.toml",
+                "app/Cargo.toml",
+                "app/src/main.rs",
+                "lib/Cargo.toml",
+                "lib/src/lib.rs"
+            ]
+        );
+
+        // Directories work as names too
+        let selection = retain_packages(&mut repo, &["lib/".to_string()]);
+        assert_eq!(selection.selected, vec!["lib"]);
+        assert_eq!(repo.files.len(), 3);
+    }
+}
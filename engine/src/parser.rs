@@ -12,30 +12,38 @@
 //! - Rust
 //! - Go
 //! - Java
+//! - C
+//! - C++
+//!
+//! Other grammars can be added at runtime through the [`LanguageRegistry`],
+//! either from a `tree_sitter::Language` linked into the host program or from
+//! a compiled grammar library named in the config file.
 //!
 //! # Example
 //!
-//! ```rust,ignore
-//! use infiniloom_engine::parser::{Parser, Language};
+//! ```
+//! use infiniloom_engine::parser::{Language, Parser};
 //!
-//! let parser = Parser::new();
-//! let source_code = std::fs::read_to_string("example.py")?;
-//! let symbols = parser.parse(&source_code, Language::Python)?;
+//! let mut parser = Parser::new();
+//! let source_code = "def greet(name):\n    return f'hi {name}'\n";
+//! let symbols = parser.parse(source_code, Language::Python)?;
 //!
-//! for symbol in symbols {
-//!     println!("{}: {} (lines {}-{})",
-//!         symbol.kind.name(),
-//!         symbol.name,
-//!         symbol.start_line,
-//!         symbol.end_line
-//!     );
-//! }
+//! let greet = &symbols[0];
+//! assert_eq!(greet.name, "greet");
+//! assert_eq!(greet.kind.name(), "function");
+//! assert_eq!((greet.start_line, greet.end_line), (1, 2));
+//! # Ok::<(), infiniloom_engine::ParserError>(())
 //! ```
 
 use crate::types::{Symbol, SymbolKind};
 use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{OnceLock, PoisonError, RwLock};
 use thiserror::Error;
-use tree_sitter::{Node, Parser as TSParser, Query, QueryCursor, Tree};
+use tree_sitter::{
+    Language as TSLanguage, Node, Parser as TSParser, Query, QueryCursor, Tree, LANGUAGE_VERSION,
+    MIN_COMPATIBLE_LANGUAGE_VERSION,
+};
 
 /// Parser errors
 #[derive(Debug, Error)]
@@ -51,6 +59,9 @@ pub enum ParserError {
 
     #[error("Invalid UTF-8 in source code")]
     InvalidUtf8,
+
+    #[error("Failed to load grammar: {0}")]
+    LoadError(String),
 }
 
 /// Supported programming languages
@@ -62,20 +73,37 @@ pub enum Language {
     Rust,
     Go,
     Java,
+    C,
+    Cpp,
+    /// A grammar added through the [`LanguageRegistry`]
+    Custom(CustomLanguage),
 }
 
+/// Handle of a language registered at runtime
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CustomLanguage(usize);
+
 impl Language {
-    /// Detect language from file extension
+    /// Built-in languages, in registry order
+    pub const BUILTIN: [Self; 8] = [
+        Self::Python,
+        Self::JavaScript,
+        Self::TypeScript,
+        Self::Rust,
+        Self::Go,
+        Self::Java,
+        Self::C,
+        Self::Cpp,
+    ];
+
+    /// Detect language from file extension, as mapped in the [`LanguageRegistry`]
     pub fn from_extension(ext: &str) -> Option<Self> {
-        match ext.to_lowercase().as_str() {
-            "py" | "pyw" => Some(Self::Python),
-            "js" | "jsx" | "mjs" | "cjs" => Some(Self::JavaScript),
-            "ts" | "tsx" => Some(Self::TypeScript),
-            "rs" => Some(Self::Rust),
-            "go" => Some(Self::Go),
-            "java" => Some(Self::Java),
-            _ => None,
-        }
+        LanguageRegistry::language_for_extension(ext)
+    }
+
+    /// Whether the language was registered at runtime
+    pub fn is_custom(&self) -> bool {
+        matches!(self, Self::Custom(_))
     }
 
     /// Get language name as string
@@ -87,7 +115,317 @@ impl Language {
             Self::Rust => "rust",
             Self::Go => "go",
             Self::Java => "java",
+            Self::C => "c",
+            Self::Cpp => "cpp",
+            Self::Custom(custom) => LanguageRegistry::read().custom_names[custom.0],
+        }
+    }
+}
+
+/// A tree-sitter grammar and the query that finds its symbols
+///
+/// The query captures the symbol name as `@name` and the definition as one of
+/// `@function`, `@class`, `@method`, `@struct`, `@enum`, `@interface` or
+/// `@trait`, like the built-in queries.
+#[derive(Debug, Clone)]
+pub struct LanguageSpec {
+    name: String,
+    grammar: TSLanguage,
+    query: String,
+    extensions: Vec<String>,
+    import_kinds: Vec<String>,
+}
+
+impl LanguageSpec {
+    /// Describe a grammar named `name` whose symbols `query` captures
+    pub fn new(name: impl Into<String>, grammar: TSLanguage, query: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            grammar,
+            query: query.into(),
+            extensions: Vec::new(),
+            import_kinds: Vec::new(),
+        }
+    }
+
+    /// File extensions (without the dot) parsed with this grammar
+    pub fn with_extensions<I, S>(mut self, extensions: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.extensions = extensions
+            .into_iter()
+            .map(|ext| ext.into().to_lowercase())
+            .collect();
+        self
+    }
+
+    /// Top-level node kinds that hold imports, e.g. `import_statement`
+    pub fn with_import_kinds<I, S>(mut self, kinds: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.import_kinds = kinds.into_iter().map(Into::into).collect();
+        self
+    }
+}
+
+/// Process-wide table of grammars, queries and extension mappings
+///
+/// Holds the built-in languages from the start. [`LanguageRegistry::register`]
+/// adds a grammar (or replaces a built-in one of the same name) and maps its
+/// extensions; every [`Parser`], the scanners and import resolution pick it
+/// up from then on.
+#[derive(Debug)]
+pub struct LanguageRegistry {
+    specs: HashMap<Language, LanguageSpec>,
+    extensions: HashMap<String, Language>,
+    /// Names of custom languages, indexed by [`CustomLanguage`]; leaked once
+    /// per name so [`Language::name`] can stay `&'static`
+    custom_names: Vec<&'static str>,
+}
+
+static REGISTRY: OnceLock<RwLock<LanguageRegistry>> = OnceLock::new();
+
+impl LanguageRegistry {
+    fn global() -> &'static RwLock<Self> {
+        REGISTRY.get_or_init(|| RwLock::new(Self::builtin()))
+    }
+
+    fn read() -> std::sync::RwLockReadGuard<'static, Self> {
+        Self::global()
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn write() -> std::sync::RwLockWriteGuard<'static, Self> {
+        Self::global()
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn builtin() -> Self {
+        let mut registry =
+            Self { specs: HashMap::new(), extensions: HashMap::new(), custom_names: Vec::new() };
+        let specs = [
+            (
+                Language::Python,
+                LanguageSpec::new("python", tree_sitter_python::language(), PYTHON_QUERY)
+                    .with_extensions(["py", "pyw"])
+                    .with_import_kinds(["import_statement", "import_from_statement"]),
+            ),
+            (
+                Language::JavaScript,
+                LanguageSpec::new(
+                    "javascript",
+                    tree_sitter_javascript::language(),
+                    JAVASCRIPT_QUERY,
+                )
+                .with_extensions(["js", "jsx", "mjs", "cjs"])
+                .with_import_kinds(["import_statement"]),
+            ),
+            (
+                Language::TypeScript,
+                LanguageSpec::new(
+                    "typescript",
+                    tree_sitter_typescript::language_typescript(),
+                    TYPESCRIPT_QUERY,
+                )
+                .with_extensions(["ts", "tsx"])
+                .with_import_kinds(["import_statement"]),
+            ),
+            (
+                Language::Rust,
+                LanguageSpec::new("rust", tree_sitter_rust::language(), RUST_QUERY)
+                    .with_extensions(["rs"])
+                    .with_import_kinds(["use_declaration"]),
+            ),
+            (
+                Language::Go,
+                LanguageSpec::new("go", tree_sitter_go::language(), GO_QUERY)
+                    .with_extensions(["go"])
+                    .with_import_kinds(["import_declaration"]),
+            ),
+            (
+                Language::Java,
+                LanguageSpec::new("java", tree_sitter_java::language(), JAVA_QUERY)
+                    .with_extensions(["java"])
+                    .with_import_kinds(["import_declaration"]),
+            ),
+            // C and C++ collect `#include`s themselves, inside conditionals too
+            (
+                Language::C,
+                LanguageSpec::new("c", tree_sitter_c::language(), C_QUERY).with_extensions(["c"]),
+            ),
+            (
+                // Headers are parsed as C++, whose grammar also accepts plain C
+                Language::Cpp,
+                LanguageSpec::new("cpp", tree_sitter_cpp::language(), CPP_QUERY)
+                    .with_extensions(["h", "cpp", "cc", "cxx", "c++", "hpp", "hh", "hxx"]),
+            ),
+        ];
+        for (language, spec) in specs {
+            registry.insert(language, spec);
+        }
+        registry
+    }
+
+    fn insert(&mut self, language: Language, spec: LanguageSpec) {
+        for ext in &spec.extensions {
+            self.extensions.insert(ext.clone(), language);
+        }
+        self.specs.insert(language, spec);
+    }
+
+    /// Add a grammar, or replace the one registered under the same name
+    ///
+    /// The query is compiled once here so a broken query fails registration
+    /// instead of every parse.
+    pub fn register(spec: LanguageSpec) -> Result<Language, ParserError> {
+        Query::new(spec.grammar, &spec.query)
+            .map_err(|e| ParserError::QueryError(format!("{}: {}", spec.name, e)))?;
+
+        let mut registry = Self::write();
+        let language = match registry.lookup_name(&spec.name) {
+            Some(language) => language,
+            None => {
+                let name: &'static str = Box::leak(spec.name.clone().into_boxed_str());
+                registry.custom_names.push(name);
+                Language::Custom(CustomLanguage(registry.custom_names.len() - 1))
+            },
+        };
+        registry.insert(language, spec);
+        Ok(language)
+    }
+
+    /// Parse files with extension `ext` (without the dot) as `language`
+    pub fn map_extension(ext: &str, language: Language) {
+        Self::write()
+            .extensions
+            .insert(ext.to_lowercase(), language);
+    }
+
+    /// Language files with extension `ext` are parsed as
+    pub fn language_for_extension(ext: &str) -> Option<Language> {
+        Self::read().extensions.get(&ext.to_lowercase()).copied()
+    }
+
+    /// Language registered under `name`, built-in or custom
+    pub fn language_named(name: &str) -> Option<Language> {
+        Self::read().lookup_name(name)
+    }
+
+    /// Extensions currently mapped to `language`, sorted
+    pub fn extensions(language: Language) -> Vec<String> {
+        let mut extensions: Vec<_> = Self::read()
+            .extensions
+            .iter()
+            .filter(|(_, l)| **l == language)
+            .map(|(ext, _)| ext.clone())
+            .collect();
+        extensions.sort();
+        extensions
+    }
+
+    fn lookup_name(&self, name: &str) -> Option<Language> {
+        self.specs
+            .iter()
+            .find(|(_, spec)| spec.name == name)
+            .map(|(language, _)| *language)
+    }
+
+    /// Grammar and query source of `language`
+    fn grammar(language: Language) -> Option<(TSLanguage, String)> {
+        Self::read()
+            .specs
+            .get(&language)
+            .map(|spec| (spec.grammar, spec.query.clone()))
+    }
+
+    /// Top-level node kinds that hold imports in `language`
+    fn import_kinds(language: Language) -> Vec<String> {
+        Self::read()
+            .specs
+            .get(&language)
+            .map(|spec| spec.import_kinds.clone())
+            .unwrap_or_default()
+    }
+
+    /// Load a compiled grammar from a shared library
+    ///
+    /// `symbol` is the exported constructor, `tree_sitter_<name>` for grammars
+    /// built by the tree-sitter CLI. The library stays loaded for the rest of
+    /// the process, since parsers keep pointers into it.
+    #[cfg(unix)]
+    #[allow(unsafe_code)]
+    pub fn load_grammar(library: &Path, symbol: &str) -> Result<TSLanguage, ParserError> {
+        use std::ffi::{c_char, c_int, c_void, CStr, CString};
+        use std::os::unix::ffi::OsStrExt;
+
+        extern "C" {
+            fn dlopen(filename: *const c_char, flags: c_int) -> *mut c_void;
+            fn dlsym(handle: *mut c_void, symbol: *const c_char) -> *mut c_void;
+            fn dlerror() -> *mut c_char;
         }
+        const RTLD_NOW: c_int = 2;
+
+        let load_error = |what: &str| {
+            // SAFETY: dlerror returns null or a NUL-terminated message
+            let message = unsafe {
+                let message = dlerror();
+                (!message.is_null()).then(|| CStr::from_ptr(message).to_string_lossy().into_owned())
+            };
+            ParserError::LoadError(format!(
+                "{}: {}",
+                library.display(),
+                message.unwrap_or_else(|| what.to_owned())
+            ))
+        };
+        let path = CString::new(library.as_os_str().as_bytes())
+            .map_err(|_| ParserError::LoadError(format!("{}: invalid path", library.display())))?;
+        let name = CString::new(symbol)
+            .map_err(|_| ParserError::LoadError(format!("invalid symbol {}", symbol)))?;
+
+        // SAFETY: both strings are NUL-terminated; the handle is never closed
+        let handle = unsafe { dlopen(path.as_ptr(), RTLD_NOW) };
+        if handle.is_null() {
+            return Err(load_error("cannot open library"));
+        }
+        // SAFETY: `handle` is a live library handle
+        let constructor = unsafe { dlsym(handle, name.as_ptr()) };
+        if constructor.is_null() {
+            return Err(load_error(&format!("no symbol {}", symbol)));
+        }
+        // SAFETY: tree-sitter grammars export `const TSLanguage *tree_sitter_<name>(void)`,
+        // which `TSLanguage` wraps transparently
+        let grammar = unsafe {
+            let constructor: unsafe extern "C" fn() -> TSLanguage =
+                std::mem::transmute(constructor);
+            constructor()
+        };
+
+        let version = grammar.version();
+        if !(MIN_COMPATIBLE_LANGUAGE_VERSION..=LANGUAGE_VERSION).contains(&version) {
+            return Err(ParserError::LoadError(format!(
+                "{}: grammar ABI version {} is not supported (expected {}-{})",
+                library.display(),
+                version,
+                MIN_COMPATIBLE_LANGUAGE_VERSION,
+                LANGUAGE_VERSION
+            )));
+        }
+        Ok(grammar)
+    }
+
+    /// Load a compiled grammar from a shared library (unsupported here)
+    #[cfg(not(unix))]
+    pub fn load_grammar(library: &Path, _symbol: &str) -> Result<TSLanguage, ParserError> {
+        Err(ParserError::LoadError(format!(
+            "{}: loading grammar libraries is not supported on this platform",
+            library.display()
+        )))
     }
 }
 
@@ -109,18 +447,14 @@ impl Parser {
     fn ensure_initialized(&mut self, language: Language) -> Result<(), ParserError> {
         use std::collections::hash_map::Entry;
         if let Entry::Vacant(parser_entry) = self.parsers.entry(language) {
-            let (parser, query) = match language {
-                Language::Python => (Self::init_python_parser()?, Self::python_query()?),
-                Language::JavaScript => {
-                    (Self::init_javascript_parser()?, Self::javascript_query()?)
-                },
-                Language::TypeScript => {
-                    (Self::init_typescript_parser()?, Self::typescript_query()?)
-                },
-                Language::Rust => (Self::init_rust_parser()?, Self::rust_query()?),
-                Language::Go => (Self::init_go_parser()?, Self::go_query()?),
-                Language::Java => (Self::init_java_parser()?, Self::java_query()?),
-            };
+            let (grammar, query_source) = LanguageRegistry::grammar(language)
+                .ok_or_else(|| ParserError::UnsupportedLanguage(language.name().to_owned()))?;
+            let mut parser = TSParser::new();
+            parser
+                .set_language(grammar)
+                .map_err(|e| ParserError::ParseError(e.to_string()))?;
+            let query = Query::new(grammar, &query_source)
+                .map_err(|e| ParserError::QueryError(e.to_string()))?;
             parser_entry.insert(parser);
             self.queries.insert(language, query);
         }
@@ -167,9 +501,27 @@ impl Parser {
 
         let matches = cursor.matches(query, root_node, source_code.as_bytes());
 
+        // C/C++ prototypes, kept only when the file doesn't define the function
+        let mut prototypes = Vec::new();
         for m in matches {
             if let Some(symbol) = self.process_match(&m, source_code, query, language) {
-                symbols.push(symbol);
+                let is_prototype = m
+                    .captures
+                    .iter()
+                    .any(|c| matches!(c.node.kind(), "declaration" | "field_declaration"));
+                if is_prototype {
+                    prototypes.push(symbol);
+                } else {
+                    symbols.push(symbol);
+                }
+            }
+        }
+        for prototype in prototypes {
+            let defined = symbols
+                .iter()
+                .any(|s| s.name == prototype.name && s.parent == prototype.parent);
+            if !defined {
+                symbols.push(prototype);
             }
         }
 
@@ -217,11 +569,18 @@ impl Parser {
         let name = name_node.utf8_text(source_code.as_bytes()).ok()?;
 
         // Find the definition node (usually the largest capture)
-        let def_node = captures
+        let mut def_node = captures
             .iter()
             .max_by_key(|c| c.node.byte_range().len())
             .map(|c| c.node)
             .unwrap_or(name_node);
+        // A C++ template spans its `template <...>` header
+        if let Some(template) = def_node
+            .parent()
+            .filter(|p| p.kind() == "template_declaration")
+        {
+            def_node = template;
+        }
 
         let start_line = def_node.start_position().row as u32 + 1;
         let end_line = def_node.end_position().row as u32 + 1;
@@ -235,6 +594,7 @@ impl Parser {
         // Extract parent if this is a method
         let parent = if symbol_kind == SymbolKind::Method {
             self.extract_parent(def_node, source_code)
+                .or_else(|| Self::qualified_scope(name_node, source_code))
         } else {
             None
         };
@@ -342,6 +702,33 @@ impl Parser {
                 }
                 None
             },
+            Language::C | Language::Cpp => {
+                // Prototypes are whole signatures; definitions stop at the body
+                match node.kind() {
+                    "declaration" | "field_declaration" => {
+                        let text = node.utf8_text(source_code.as_bytes()).ok()?;
+                        return Some(text.trim().trim_end_matches(';').replace('\n', " "));
+                    },
+                    "function_definition" | "template_declaration" => {
+                        let function = if node.kind() == "template_declaration" {
+                            node.named_child(node.named_child_count().saturating_sub(1))?
+                        } else {
+                            node
+                        };
+                        if let Some(body) = function.child_by_field_name("body") {
+                            let start = node.start_byte();
+                            let end = body.start_byte();
+                            return Some(
+                                source_code[start..end].trim().to_owned().replace('\n', " "),
+                            );
+                        }
+                    },
+                    _ => {},
+                }
+                None
+            },
+            // Registered grammars fall back to the first line below
+            Language::Custom(_) => None,
             Language::Java => {
                 // For Java, get method declaration
                 if node.kind() == "method_declaration" {
@@ -451,6 +838,20 @@ impl Parser {
                 }
                 None
             },
+            Language::C | Language::Cpp => {
+                // Look for a `/** */` or `//` comment immediately before
+                let prev_sibling = node.prev_sibling()?;
+                if prev_sibling.kind() != "comment" {
+                    return None;
+                }
+                let text = prev_sibling.utf8_text(source_code.as_bytes()).ok()?;
+                if text.starts_with("/*") {
+                    Some(self.clean_jsdoc(text))
+                } else {
+                    Some(text.trim_start_matches('/').trim().to_owned())
+                }
+            },
+            Language::Custom(_) => None,
             Language::Java => {
                 // Look for JavaDoc comment
                 if let Some(prev_sibling) = node.prev_sibling() {
@@ -472,8 +873,15 @@ impl Parser {
         let mut current = node.parent()?;
 
         while let Some(parent) = current.parent() {
-            if ["class_definition", "class_declaration", "struct_item", "impl_item"]
-                .contains(&parent.kind())
+            if [
+                "class_definition",
+                "class_declaration",
+                "struct_item",
+                "impl_item",
+                "class_specifier",
+                "struct_specifier",
+            ]
+            .contains(&parent.kind())
             {
                 // Find the name node
                 for child in parent.children(&mut parent.walk()) {
@@ -490,6 +898,18 @@ impl Parser {
         None
     }
 
+    /// Class or namespace of an out-of-line C++ definition (`Foo::bar`)
+    fn qualified_scope(name_node: Node<'_>, source_code: &str) -> Option<String> {
+        let qualified = name_node
+            .parent()
+            .filter(|p| p.kind() == "qualified_identifier")?;
+        let scope = qualified.child_by_field_name("scope")?;
+        scope
+            .utf8_text(source_code.as_bytes())
+            .ok()
+            .map(str::to_owned)
+    }
+
     /// Extract import statements (only top-level for performance)
     fn extract_imports(
         &self,
@@ -499,19 +919,18 @@ impl Parser {
     ) -> Result<Vec<Symbol>, ParserError> {
         let mut imports = Vec::new();
 
-        let import_kinds = match language {
-            Language::Python => vec!["import_statement", "import_from_statement"],
-            Language::JavaScript | Language::TypeScript => vec!["import_statement"],
-            Language::Rust => vec!["use_declaration"],
-            Language::Go => vec!["import_declaration"],
-            Language::Java => vec!["import_declaration"],
-        };
+        if matches!(language, Language::C | Language::Cpp) {
+            let mut includes = Vec::new();
+            Self::collect_includes(root_node, source_code, &mut includes);
+            return Ok(includes);
+        }
+        let import_kinds = LanguageRegistry::import_kinds(language);
 
         // Only check top-level children (imports are typically at module level)
         // This is much faster than recursive traversal for large files
         let mut cursor = root_node.walk();
         for child in root_node.children(&mut cursor) {
-            if import_kinds.contains(&child.kind()) {
+            if import_kinds.iter().any(|kind| kind == child.kind()) {
                 if let Ok(text) = child.utf8_text(source_code.as_bytes()) {
                     let mut symbol = Symbol::new(text.trim(), SymbolKind::Import);
                     symbol.start_line = child.start_position().row as u32 + 1;
@@ -539,6 +958,28 @@ impl Parser {
         Ok(imports)
     }
 
+    /// Collect `#include` directives, including those inside header guards
+    /// and other conditional blocks
+    fn collect_includes(node: Node<'_>, source_code: &str, includes: &mut Vec<Symbol>) {
+        let mut cursor = node.walk();
+        for child in node.children(&mut cursor) {
+            match child.kind() {
+                "preproc_include" => {
+                    if let Ok(text) = child.utf8_text(source_code.as_bytes()) {
+                        let mut symbol = Symbol::new(text.trim(), SymbolKind::Import);
+                        symbol.start_line = child.start_position().row as u32 + 1;
+                        symbol.end_line = symbol.start_line;
+                        includes.push(symbol);
+                    }
+                },
+                "preproc_if" | "preproc_ifdef" | "preproc_else" | "preproc_elif" => {
+                    Self::collect_includes(child, source_code, includes);
+                },
+                _ => {},
+            }
+        }
+    }
+
     /// Clean JSDoc comment
     fn clean_jsdoc(&self, text: &str) -> String {
         text.lines()
@@ -559,180 +1000,196 @@ impl Parser {
     fn clean_javadoc(&self, text: &str) -> String {
         self.clean_jsdoc(text) // Same format as JSDoc
     }
+}
 
-    // Language-specific parser initializers
-
-    fn init_python_parser() -> Result<TSParser, ParserError> {
-        let mut parser = TSParser::new();
-        parser
-            .set_language(tree_sitter_python::language())
-            .map_err(|e| ParserError::ParseError(e.to_string()))?;
-        Ok(parser)
-    }
-
-    fn init_javascript_parser() -> Result<TSParser, ParserError> {
-        let mut parser = TSParser::new();
-        parser
-            .set_language(tree_sitter_javascript::language())
-            .map_err(|e| ParserError::ParseError(e.to_string()))?;
-        Ok(parser)
-    }
-
-    fn init_typescript_parser() -> Result<TSParser, ParserError> {
-        let mut parser = TSParser::new();
-        parser
-            .set_language(tree_sitter_typescript::language_typescript())
-            .map_err(|e| ParserError::ParseError(e.to_string()))?;
-        Ok(parser)
-    }
-
-    fn init_rust_parser() -> Result<TSParser, ParserError> {
-        let mut parser = TSParser::new();
-        parser
-            .set_language(tree_sitter_rust::language())
-            .map_err(|e| ParserError::ParseError(e.to_string()))?;
-        Ok(parser)
-    }
-
-    fn init_go_parser() -> Result<TSParser, ParserError> {
-        let mut parser = TSParser::new();
-        parser
-            .set_language(tree_sitter_go::language())
-            .map_err(|e| ParserError::ParseError(e.to_string()))?;
-        Ok(parser)
-    }
-
-    fn init_java_parser() -> Result<TSParser, ParserError> {
-        let mut parser = TSParser::new();
-        parser
-            .set_language(tree_sitter_java::language())
-            .map_err(|e| ParserError::ParseError(e.to_string()))?;
-        Ok(parser)
-    }
-
-    // Language-specific queries
-
-    fn python_query() -> Result<Query, ParserError> {
-        let query_string = r#"
-            (function_definition
-              name: (identifier) @name) @function
-
-            (class_definition
-              name: (identifier) @name) @class
-
-            (class_definition
-              body: (block
-                (function_definition
-                  name: (identifier) @name))) @method
-        "#;
-
-        Query::new(tree_sitter_python::language(), query_string)
-            .map_err(|e| ParserError::QueryError(e.to_string()))
-    }
+// Symbol queries of the built-in languages
 
-    fn javascript_query() -> Result<Query, ParserError> {
-        let query_string = r#"
-            (function_declaration
-              name: (identifier) @name) @function
+const PYTHON_QUERY: &str = r#"
+    (function_definition
+      name: (identifier) @name) @function
 
-            (class_declaration
-              name: (identifier) @name) @class
+    (class_definition
+      name: (identifier) @name) @class
 
-            (method_definition
-              name: (property_identifier) @name) @method
+    (class_definition
+      body: (block
+        (function_definition
+          name: (identifier) @name) @method))
+"#;
 
-            (arrow_function) @function
+const JAVASCRIPT_QUERY: &str = r#"
+    (function_declaration
+      name: (identifier) @name) @function
 
-            (function_expression) @function
-        "#;
+    (class_declaration
+      name: (identifier) @name) @class
 
-        Query::new(tree_sitter_javascript::language(), query_string)
-            .map_err(|e| ParserError::QueryError(e.to_string()))
-    }
+    (method_definition
+      name: (property_identifier) @name) @method
 
-    fn typescript_query() -> Result<Query, ParserError> {
-        let query_string = r#"
-            (function_declaration
-              name: (identifier) @name) @function
+    (arrow_function) @function
 
-            (class_declaration
-              name: (type_identifier) @name) @class
+    (function_expression) @function
+"#;
 
-            (interface_declaration
-              name: (type_identifier) @name) @interface
+const TYPESCRIPT_QUERY: &str = r#"
+    (function_declaration
+      name: (identifier) @name) @function
 
-            (method_definition
-              name: (property_identifier) @name) @method
+    (class_declaration
+      name: (type_identifier) @name) @class
 
-            (enum_declaration
-              name: (identifier) @name) @enum
-        "#;
+    (interface_declaration
+      name: (type_identifier) @name) @interface
 
-        Query::new(tree_sitter_typescript::language_typescript(), query_string)
-            .map_err(|e| ParserError::QueryError(e.to_string()))
-    }
+    (method_definition
+      name: (property_identifier) @name) @method
 
-    fn rust_query() -> Result<Query, ParserError> {
-        let query_string = r#"
-            (function_item
-              name: (identifier) @name) @function
+    (enum_declaration
+      name: (identifier) @name) @enum
+"#;
 
-            (struct_item
-              name: (type_identifier) @name) @struct
+const RUST_QUERY: &str = r#"
+    (function_item
+      name: (identifier) @name) @function
 
-            (enum_item
-              name: (type_identifier) @name) @enum
+    (struct_item
+      name: (type_identifier) @name) @struct
 
-            (trait_item
-              name: (type_identifier) @name) @trait
-        "#;
+    (enum_item
+      name: (type_identifier) @name) @enum
 
-        Query::new(tree_sitter_rust::language(), query_string)
-            .map_err(|e| ParserError::QueryError(e.to_string()))
-    }
+    (trait_item
+      name: (type_identifier) @name) @trait
+"#;
 
-    fn go_query() -> Result<Query, ParserError> {
-        let query_string = r#"
-            (function_declaration
-              name: (identifier) @name) @function
+const GO_QUERY: &str = r#"
+    (function_declaration
+      name: (identifier) @name) @function
 
-            (method_declaration
-              name: (field_identifier) @name) @method
+    (method_declaration
+      name: (field_identifier) @name) @method
 
-            (type_declaration
-              (type_spec
-                name: (type_identifier) @name
-                type: (struct_type))) @struct
+    (type_declaration
+      (type_spec
+        name: (type_identifier) @name
+        type: (struct_type))) @struct
 
-            (type_declaration
-              (type_spec
-                name: (type_identifier) @name
-                type: (interface_type))) @interface
-        "#;
+    (type_declaration
+      (type_spec
+        name: (type_identifier) @name
+        type: (interface_type))) @interface
+"#;
 
-        Query::new(tree_sitter_go::language(), query_string)
-            .map_err(|e| ParserError::QueryError(e.to_string()))
-    }
+const JAVA_QUERY: &str = r#"
+    (method_declaration
+      name: (identifier) @name) @method
 
-    fn java_query() -> Result<Query, ParserError> {
-        let query_string = r#"
-            (method_declaration
-              name: (identifier) @name) @method
+    (class_declaration
+      name: (identifier) @name) @class
 
-            (class_declaration
-              name: (identifier) @name) @class
+    (interface_declaration
+      name: (identifier) @name) @interface
 
-            (interface_declaration
-              name: (identifier) @name) @interface
+    (enum_declaration
+      name: (identifier) @name) @enum
+"#;
 
-            (enum_declaration
-              name: (identifier) @name) @enum
-        "#;
+const C_QUERY: &str = r#"
+    (function_definition
+      declarator: (function_declarator
+        declarator: (identifier) @name)) @function
+
+    (function_definition
+      declarator: (pointer_declarator
+        declarator: (function_declarator
+          declarator: (identifier) @name))) @function
+
+    (declaration
+      declarator: (function_declarator
+        declarator: (identifier) @name)) @function
+
+    (declaration
+      declarator: (pointer_declarator
+        declarator: (function_declarator
+          declarator: (identifier) @name))) @function
+
+    (struct_specifier
+      name: (type_identifier) @name
+      body: (field_declaration_list)) @struct
+
+    (type_definition
+      type: (struct_specifier
+        !name
+        body: (field_declaration_list))
+      declarator: (type_identifier) @name) @struct
+
+    (enum_specifier
+      name: (type_identifier) @name
+      body: (enumerator_list)) @enum
+
+    (type_definition
+      type: (enum_specifier
+        !name
+        body: (enumerator_list))
+      declarator: (type_identifier) @name) @enum
+"#;
 
-        Query::new(tree_sitter_java::language(), query_string)
-            .map_err(|e| ParserError::QueryError(e.to_string()))
-    }
-}
+const CPP_QUERY: &str = r#"
+    (function_definition
+      declarator: (function_declarator
+        declarator: (identifier) @name)) @function
+
+    (function_definition
+      declarator: (pointer_declarator
+        declarator: (function_declarator
+          declarator: (identifier) @name))) @function
+
+    (function_definition
+      declarator: (reference_declarator
+        (function_declarator
+          declarator: (identifier) @name))) @function
+
+    (function_definition
+      declarator: (function_declarator
+        declarator: (field_identifier) @name)) @method
+
+    (function_definition
+      declarator: (function_declarator
+        declarator: (qualified_identifier
+          name: (identifier) @name))) @method
+
+    (declaration
+      declarator: (function_declarator
+        declarator: (identifier) @name)) @function
+
+    (declaration
+      declarator: (pointer_declarator
+        declarator: (function_declarator
+          declarator: (identifier) @name))) @function
+
+    (field_declaration
+      declarator: (function_declarator
+        declarator: (field_identifier) @name)) @method
+
+    (class_specifier
+      name: (type_identifier) @name
+      body: (field_declaration_list)) @class
+
+    (struct_specifier
+      name: (type_identifier) @name
+      body: (field_declaration_list)) @struct
+
+    (type_definition
+      type: (struct_specifier
+        !name
+        body: (field_declaration_list))
+      declarator: (type_identifier) @name) @struct
+
+    (enum_specifier
+      name: (type_identifier) @name
+      body: (enumerator_list)) @enum
+"#;
 
 impl Default for Parser {
     fn default() -> Self {
@@ -752,9 +1209,59 @@ mod tests {
         assert_eq!(Language::from_extension("rs"), Some(Language::Rust));
         assert_eq!(Language::from_extension("go"), Some(Language::Go));
         assert_eq!(Language::from_extension("java"), Some(Language::Java));
+        assert_eq!(Language::from_extension("c"), Some(Language::C));
+        assert_eq!(Language::from_extension("hpp"), Some(Language::Cpp));
         assert_eq!(Language::from_extension("unknown"), None);
     }
 
+    #[test]
+    fn test_register_custom_language() {
+        // Any grammar works; reuse Python's under another name
+        let starlark = LanguageRegistry::register(
+            LanguageSpec::new(
+                "starlark",
+                tree_sitter_python::language(),
+                "(function_definition name: (identifier) @name) @function",
+            )
+            .with_extensions(["star", "BZL"])
+            .with_import_kinds(["import_from_statement"]),
+        )
+        .unwrap();
+        assert!(starlark.is_custom());
+        assert_eq!(starlark.name(), "starlark");
+        assert_eq!(Language::from_extension("bzl"), Some(starlark));
+        assert_eq!(LanguageRegistry::language_named("starlark"), Some(starlark));
+        assert_eq!(LanguageRegistry::extensions(starlark), ["bzl", "star"]);
+
+        let mut parser = Parser::new();
+        let symbols = parser
+            .parse("from a import b\n\ndef rule(ctx):\n    pass\n\nclass C:\n    pass\n", starlark)
+            .unwrap();
+        let names: Vec<_> = symbols.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, ["rule", "from a import b"]);
+        assert_eq!(symbols[0].signature.as_deref(), Some("def rule(ctx):"));
+
+        // Registering the same name again keeps the handle
+        let again = LanguageRegistry::register(LanguageSpec::new(
+            "starlark",
+            tree_sitter_python::language(),
+            "(class_definition name: (identifier) @name) @class",
+        ))
+        .unwrap();
+        assert_eq!(again, starlark);
+
+        let broken = LanguageRegistry::register(LanguageSpec::new(
+            "broken",
+            tree_sitter_python::language(),
+            "(no_such_node) @function",
+        ));
+        assert!(matches!(broken, Err(ParserError::QueryError(_))));
+        assert!(LanguageRegistry::language_named("broken").is_none());
+
+        LanguageRegistry::map_extension("pyi", Language::Python);
+        assert_eq!(Language::from_extension("pyi"), Some(Language::Python));
+    }
+
     #[test]
     fn test_parse_python() {
         let mut parser = Parser::new();
@@ -787,7 +1294,9 @@ class MyClass:
         let method = symbols
             .iter()
             .find(|s| s.name == "method" && s.kind == SymbolKind::Method);
-        assert!(method.is_some());
+        let method = method.unwrap();
+        assert_eq!(method.parent.as_deref(), Some("MyClass"));
+        assert_eq!((method.start_line, method.end_line), (7, 8));
     }
 
     #[test]
@@ -902,6 +1411,101 @@ class TestClass implements TestInterface {
         assert!(enum_sym.is_some());
     }
 
+    #[test]
+    fn test_parse_c() {
+        let mut parser = Parser::new();
+        let source = r#"
+#ifndef UTIL_H
+#define UTIL_H
+#include <stdio.h>
+#include "config.h"
+
+/** Add two numbers */
+int add(int a, int b);
+char *name(void);
+int add(int a, int b) { return a + b; }
+
+struct point { int x; int y; };
+typedef struct { int w; } size_t2;
+enum color { RED, GREEN };
+#endif
+"#;
+
+        let symbols = parser.parse(source, Language::C).unwrap();
+        let find = |name: &str| {
+            symbols
+                .iter()
+                .filter(|s| s.name == name)
+                .collect::<Vec<_>>()
+        };
+
+        let add = find("add");
+        assert_eq!(add.len(), 1, "prototype is dropped when the function is defined");
+        assert_eq!(add[0].start_line, 10);
+        assert_eq!(add[0].signature.as_deref(), Some("int add(int a, int b)"));
+        assert_eq!(find("name")[0].kind, SymbolKind::Function);
+        assert_eq!(find("point")[0].kind, SymbolKind::Struct);
+        assert_eq!(find("size_t2")[0].kind, SymbolKind::Struct);
+        assert_eq!(find("color")[0].kind, SymbolKind::Enum);
+
+        let includes: Vec<_> = symbols
+            .iter()
+            .filter(|s| s.kind == SymbolKind::Import)
+            .map(|s| s.name.as_str())
+            .collect();
+        assert_eq!(includes, vec!["#include <stdio.h>", "#include \"config.h\""]);
+    }
+
+    #[test]
+    fn test_parse_cpp() {
+        let mut parser = Parser::new();
+        let source = r#"
+#include "shape.hpp"
+
+namespace geo {
+/// A drawable shape
+class Shape {
+public:
+    virtual double area() const;
+    void draw() { render(); }
+};
+
+double Shape::area() const { return 0.0; }
+
+template <typename T>
+T max_of(T a, T b) { return a > b ? a : b; }
+}
+"#;
+
+        let symbols = parser.parse(source, Language::Cpp).unwrap();
+        let find = |name: &str| symbols.iter().find(|s| s.name == name).unwrap();
+
+        let shape = find("Shape");
+        assert_eq!(shape.kind, SymbolKind::Class);
+        assert_eq!(shape.docstring.as_deref(), Some("A drawable shape"));
+
+        let area = symbols
+            .iter()
+            .filter(|s| s.name == "area")
+            .collect::<Vec<_>>();
+        assert_eq!(area.len(), 1);
+        assert_eq!(area[0].kind, SymbolKind::Method);
+        assert_eq!(area[0].parent.as_deref(), Some("Shape"));
+        assert_eq!(find("draw").parent.as_deref(), Some("Shape"));
+
+        let max_of = find("max_of");
+        assert_eq!(max_of.start_line, 14);
+        assert!(max_of
+            .signature
+            .as_deref()
+            .unwrap()
+            .starts_with("template <typename T>"));
+
+        assert!(symbols
+            .iter()
+            .any(|s| s.kind == SymbolKind::Import && s.name == "#include \"shape.hpp\""));
+    }
+
     #[test]
     fn test_symbol_metadata() {
         let mut parser = Parser::new();
@@ -0,0 +1,271 @@
+//! Packing the files relevant to a question
+//!
+//! [`retain_relevant`] scores every file against a free-text query, keeps the
+//! ones that match and orders them most relevant first, so a token budget is
+//! spent on the content the question is about.
+//!
+//! Files are scored with the BM25 ranking of a [`SearchIndex`]. With the
+//! `embeddings` feature the query is embedded instead, and a file scores by
+//! the closest of its outline, its content and its symbols; keyword scoring
+//! remains the fallback when no file is close.
+
+use crate::search::{query_terms, SearchHit, SearchIndex};
+use crate::types::{RepoFile, Repository};
+use std::collections::HashMap;
+
+/// Matching symbols reported per file
+#[cfg(feature = "embeddings")]
+const MAX_MATCHED_SYMBOLS: usize = 5;
+
+/// How files were scored
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QueryScoring {
+    /// BM25 keyword scoring
+    Keywords,
+    /// Similarity of query and symbol embeddings
+    Embeddings,
+}
+
+impl QueryScoring {
+    /// Short name for messages
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Keywords => "keywords",
+            Self::Embeddings => "embeddings",
+        }
+    }
+}
+
+/// A file relevant to the query
+#[derive(Debug, Clone, PartialEq)]
+pub struct QueryMatch {
+    /// Relative path of the file
+    pub path: String,
+    /// Relevance; comparable only within one ranking
+    pub score: f32,
+    /// Query terms found in the file, normalized
+    pub terms: Vec<String>,
+    /// Symbols whose names contain a query term
+    pub symbols: Vec<String>,
+}
+
+impl QueryMatch {
+    /// Why the file matched, for `--explain-ranking`
+    pub fn describe(&self) -> String {
+        let mut description =
+            format!("query match on {} ({:.2})", self.terms.join(", "), self.score);
+        if !self.symbols.is_empty() {
+            description.push_str(&format!(" in {}", self.symbols.join(", ")));
+        }
+        description
+    }
+}
+
+impl From<SearchHit> for QueryMatch {
+    fn from(hit: SearchHit) -> Self {
+        Self { path: hit.path, score: hit.score, terms: hit.terms, symbols: hit.symbols }
+    }
+}
+
+/// Files kept by [`retain_relevant`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct QuerySelection {
+    /// How the files were scored
+    pub scoring: QueryScoring,
+    /// Matching files, most relevant first
+    pub matches: Vec<QueryMatch>,
+    /// Files left out for not matching
+    pub dropped: usize,
+}
+
+/// Reduce a repository to the files relevant to `query`, most relevant first
+///
+/// Files without extracted symbols are parsed first. Each kept file's
+/// importance becomes its score relative to the best match. Files pinned by
+/// a directive are kept whether they match or not. If nothing matches, only
+/// pinned files remain.
+pub fn retain_relevant(repo: &mut Repository, query: &str) -> QuerySelection {
+    crate::scan::parse_missing_symbols(repo);
+
+    let (scoring, matches) = score_files(repo, query);
+    let scores: HashMap<&str, f32> = matches.iter().map(|m| (m.path.as_str(), m.score)).collect();
+    let best = matches.first().map_or(1.0, |m| m.score);
+
+    let before = repo.files.len();
+    repo.files
+        .retain(|f| f.pinned || scores.contains_key(f.relative_path.as_str()));
+    repo.files.sort_by(|a, b| {
+        let score = |f: &RepoFile| scores.get(f.relative_path.as_str()).copied().unwrap_or(0.0);
+        score(b).total_cmp(&score(a))
+    });
+    for file in &mut repo.files {
+        if let Some(score) = scores.get(file.relative_path.as_str()) {
+            file.importance = score / best;
+        }
+    }
+
+    let dropped = before - repo.files.len();
+    QuerySelection { scoring, matches, dropped }
+}
+
+/// Score every file against `query`, returning the matching ones best first
+///
+/// Files are scored by their symbols, so they should have been parsed.
+pub fn score_files(repo: &Repository, query: &str) -> (QueryScoring, Vec<QueryMatch>) {
+    let query_terms = query_terms(query);
+    if query_terms.is_empty() {
+        return (QueryScoring::Keywords, Vec::new());
+    }
+
+    #[cfg(feature = "embeddings")]
+    {
+        let matches = embeddings::score(repo, &query_terms);
+        if !matches.is_empty() {
+            return (QueryScoring::Embeddings, sorted(matches));
+        }
+    }
+    let hits = SearchIndex::build(repo).search(query, usize::MAX);
+    (QueryScoring::Keywords, hits.into_iter().map(QueryMatch::from).collect())
+}
+
+#[cfg(feature = "embeddings")]
+fn sorted(mut matches: Vec<QueryMatch>) -> Vec<QueryMatch> {
+    matches.sort_by(|a, b| {
+        b.score
+            .total_cmp(&a.score)
+            .then_with(|| a.path.cmp(&b.path))
+    });
+    matches
+}
+
+#[cfg(feature = "embeddings")]
+mod embeddings {
+    //! Scoring by embedding similarity
+
+    use super::QueryMatch;
+    use crate::search::{defined_symbols, terms};
+    use crate::semantic::SemanticAnalyzer;
+    use crate::types::Repository;
+
+    /// Cosine similarity a file's closest symbol needs to match
+    const MIN_SIMILARITY: f32 = 0.05;
+
+    /// Files whose outline, content or one of whose symbols is close to the
+    /// query
+    pub(super) fn score(repo: &Repository, query_terms: &[String]) -> Vec<QueryMatch> {
+        let analyzer = SemanticAnalyzer::new();
+        let Ok(query) = analyzer.embed(&query_terms.join(" ")) else {
+            return Vec::new();
+        };
+        let similarity = |text: &str| {
+            let normalized: Vec<String> = terms(text).collect();
+            analyzer
+                .embed(&normalized.join(" "))
+                .map_or(0.0, |e| e.iter().zip(&query).map(|(a, b)| a * b).sum::<f32>())
+        };
+
+        let mut matches = Vec::new();
+        for file in &repo.files {
+            let lines: Vec<&str> = file
+                .content
+                .as_deref()
+                .unwrap_or_default()
+                .lines()
+                .collect();
+            let names: Vec<&str> = defined_symbols(file).map(|s| s.name.as_str()).collect();
+            let outline = format!("{} {}", file.relative_path, names.join(" "));
+            let bodies = file.symbols.iter().filter_map(|s| {
+                let start = (s.start_line as usize).checked_sub(1)?;
+                let end = (s.end_line as usize).min(lines.len());
+                (start < end).then(|| lines[start..end].join("\n"))
+            });
+            let content = file.content.clone().unwrap_or_default();
+            let score = [outline, content]
+                .into_iter()
+                .chain(bodies)
+                .map(|chunk| similarity(&chunk))
+                .fold(0.0f32, f32::max);
+            if score < MIN_SIMILARITY {
+                continue;
+            }
+            let mut found: Vec<String> = terms(&file.relative_path)
+                .chain(names.iter().flat_map(|n| terms(n)))
+                .chain(terms(file.content.as_deref().unwrap_or_default()))
+                .filter(|t| query_terms.contains(t))
+                .collect();
+            found.sort_by_key(|t| query_terms.iter().position(|q| q == t));
+            found.dedup();
+            let mut symbols: Vec<String> = defined_symbols(file)
+                .filter(|s| terms(&s.name).any(|t| found.contains(&t)))
+                .map(|s| s.name.clone())
+                .collect();
+            symbols.dedup();
+            symbols.truncate(super::MAX_MATCHED_SYMBOLS);
+            matches.push(QueryMatch {
+                path: file.relative_path.clone(),
+                score,
+                terms: found,
+                symbols,
+            });
+        }
+        matches
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::str_to_string)]
+mod tests {
+    use super::*;
+    use crate::types::{Symbol, SymbolKind};
+
+    fn file(path: &str, content: &str, symbols: &[&str]) -> RepoFile {
+        let mut file = RepoFile::new(path, path);
+        file.content = Some(content.to_string());
+        file.symbols = symbols
+            .iter()
+            .map(|&name| Symbol::new(name, SymbolKind::Function))
+            .collect();
+        file
+    }
+
+    #[test]
+    fn test_retain_relevant() {
+        let mut repo = Repository::new("test", "/tmp/test");
+        repo.files
+            .push(file("src/render.rs", "fn draw(canvas) { canvas.fill() }", &["draw"]));
+        repo.files.push(file(
+            "src/session.rs",
+            "fn refresh(session) { if session.token.expired() { login() } }",
+            &["refresh"],
+        ));
+        repo.files.push(file(
+            "src/auth.rs",
+            "fn authenticate(user, password) { check(password); issue_token(user) }",
+            &["authenticate", "issue_token"],
+        ));
+        let mut pinned = file("README.md", "A drawing app", &[]);
+        pinned.pinned = true;
+        repo.files.push(pinned);
+
+        let selection = retain_relevant(&mut repo, "How does authentication work with tokens?");
+        assert_eq!(selection.dropped, 1);
+        let paths: Vec<&str> = repo
+            .files
+            .iter()
+            .map(|f| f.relative_path.as_str())
+            .collect();
+        assert_eq!(paths, vec!["src/auth.rs", "src/session.rs", "README.md"]);
+        assert_eq!(repo.files[0].importance, 1.0);
+
+        let best = &selection.matches[0];
+        assert_eq!(best.terms, vec!["authentic", "token"]);
+        assert_eq!(best.symbols, vec!["authenticate", "issue_token"]);
+        assert!(best
+            .describe()
+            .starts_with("query match on authentic, token ("));
+
+        let selection = retain_relevant(&mut repo, "the and of");
+        assert!(selection.matches.is_empty());
+        assert_eq!(repo.files.len(), 1);
+    }
+}
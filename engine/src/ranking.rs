@@ -1,9 +1,8 @@
 //! Symbol importance ranking
 
-#[cfg(test)]
-use crate::types::RepoFile;
-use crate::types::{Repository, Symbol, SymbolKind};
-use std::collections::HashMap;
+use crate::entry_points::{code_entry_point, manifest_entry_points};
+use crate::types::{RepoFile, Repository, Symbol, SymbolKind};
+use std::collections::{HashMap, HashSet};
 
 /// Symbol ranker using multiple heuristics
 pub struct SymbolRanker {
@@ -134,220 +133,257 @@ fn type_importance(kind: SymbolKind) -> f32 {
     }
 }
 
-/// Rank files by importance using heuristics
-/// Priority: Entry points > Core implementation > Libraries > Config > Tests
-pub fn rank_files(repo: &mut Repository) {
-    // Critical entry point patterns (highest priority)
-    let critical_entry_patterns = [
-        "__main__.py",
-        "main.rs",
-        "main.go",
-        "main.c",
-        "main.cpp",
-        "main.ts",
-        "main.js",
-        "index.ts",
-        "index.js",
-        "index.tsx",
-        "index.jsx",
-        "app.ts",
-        "app.js",
-        "app.py",
-        "app.go",
-        "app.rb",
-        "server.ts",
-        "server.js",
-        "server.py",
-        "server.go",
-        "cli.rs",
-        "cli.ts",
-        "cli.js",
-        "cli.py",
-        "lib.rs",
-        "mod.rs",
-    ];
-
-    // Important implementation directories
-    let core_dirs =
-        ["/src/", "/lib/", "/core/", "/pkg/", "/internal/", "/app/", "/cmd/", "/bin/", "/crates/"];
-
-    // Entry point file prefixes (less specific)
-    let entry_prefixes = [
-        "main.",
-        "index.",
-        "app.",
-        "server.",
-        "cli.",
-        "mod.",
-        "lib.",
-        "init.",
-        "__init__.",
-        "entry.",
-        "bootstrap.",
-    ];
-
-    // Documentation (medium-low importance but still useful)
-    let doc_patterns = ["readme.", "changelog.", "contributing.", "license.", "authors."];
-
-    // Config patterns (low importance - metadata not code)
-    let config_patterns = [
-        "config.",
-        "settings.",
-        ".config",
-        "package.json",
-        "cargo.toml",
-        "pyproject.toml",
-        "setup.py",
-        "setup.cfg",
-        "tsconfig.",
-        "webpack.",
-        ".eslint",
-        ".prettier",
-        "jest.config",
-        "vite.config",
-        ".env",
-        "makefile",
-        "dockerfile",
-        "docker-compose",
-        ".github/",
-        ".gitlab",
-    ];
-
-    // Test patterns (lowest priority for code understanding)
-    // Note: Use both with and without leading slash to match root dirs
-    let test_patterns = [
-        "test_",
-        "_test.",
-        ".test.",
-        "spec.",
-        "_spec.",
-        "/tests/",
-        "tests/",
-        "/test/",
-        "test/",
-        "/__tests__/",
-        "__tests__/",
-        "/testing/",
-        "testing/",
-        "/fixtures/",
-        "fixtures/",
-        "/mocks/",
-        "mocks/",
-        "mock_",
-        "_mock.",
-        "/e2e/",
-        "e2e/",
-        "/integration/",
-        "integration/",
-        "/unit/",
-        "unit/",
-        "/examples/",
-        "examples/",
-        "/example/",
-        "example/",
-        "/benchmark/",
-        "benchmark/",
-    ];
-
-    // Vendor/generated patterns (exclude or very low priority)
-    let vendor_patterns = [
-        "/vendor/",
-        "vendor/",
-        "/node_modules/",
-        "node_modules/",
-        "/dist/",
-        "dist/",
-        "/build/",
-        "build/",
-        "/target/",
-        "target/",
-        "/__pycache__/",
-        "__pycache__/",
-        "/.next/",
-        ".next/",
-        "/coverage/",
-        "coverage/",
-        "/.cache/",
-        ".cache/",
-        "/generated/",
-        "generated/",
-        "/.generated/",
-        ".generated/",
-        "/gen/",
-        "gen/",
-        ".min.js",
-        ".min.css",
-        ".bundle.",
-        "/benchmarks/",
-        "benchmarks/",
-    ];
-
-    for file in &mut repo.files {
-        let filename = file.filename().to_lowercase();
-        let path = file.relative_path.to_lowercase();
+/// Critical entry point file names (highest priority)
+const CRITICAL_ENTRY_PATTERNS: &[&str] = &[
+    "__main__.py",
+    "main.rs",
+    "main.go",
+    "main.c",
+    "main.cpp",
+    "main.ts",
+    "main.js",
+    "index.ts",
+    "index.js",
+    "index.tsx",
+    "index.jsx",
+    "app.ts",
+    "app.js",
+    "app.py",
+    "app.go",
+    "app.rb",
+    "server.ts",
+    "server.js",
+    "server.py",
+    "server.go",
+    "cli.rs",
+    "cli.ts",
+    "cli.js",
+    "cli.py",
+    "lib.rs",
+    "mod.rs",
+];
+
+/// Important implementation directories
+const CORE_DIRS: &[&str] =
+    &["/src/", "/lib/", "/core/", "/pkg/", "/internal/", "/app/", "/cmd/", "/bin/", "/crates/"];
+
+/// Entry point file prefixes (less specific)
+const ENTRY_PREFIXES: &[&str] = &[
+    "main.",
+    "index.",
+    "app.",
+    "server.",
+    "cli.",
+    "mod.",
+    "lib.",
+    "init.",
+    "__init__.",
+    "entry.",
+    "bootstrap.",
+];
+
+/// Documentation (medium-low importance but still useful)
+const DOC_PATTERNS: &[&str] = &["readme.", "changelog.", "contributing.", "license.", "authors."];
+
+/// Config patterns (low importance - metadata not code)
+const CONFIG_PATTERNS: &[&str] = &[
+    "config.",
+    "settings.",
+    ".config",
+    "package.json",
+    "cargo.toml",
+    "pyproject.toml",
+    "setup.py",
+    "setup.cfg",
+    "tsconfig.",
+    "webpack.",
+    ".eslint",
+    ".prettier",
+    "jest.config",
+    "vite.config",
+    ".env",
+    "makefile",
+    "dockerfile",
+    "docker-compose",
+    ".github/",
+    ".gitlab",
+];
+
+/// Test patterns (lowest priority for code understanding)
+///
+/// Uses both with and without leading slash to match root dirs
+const TEST_PATTERNS: &[&str] = &[
+    "test_",
+    "_test.",
+    ".test.",
+    "spec.",
+    "_spec.",
+    "/tests/",
+    "tests/",
+    "/test/",
+    "test/",
+    "/__tests__/",
+    "__tests__/",
+    "/testing/",
+    "testing/",
+    "/fixtures/",
+    "fixtures/",
+    "/mocks/",
+    "mocks/",
+    "mock_",
+    "_mock.",
+    "/e2e/",
+    "e2e/",
+    "/integration/",
+    "integration/",
+    "/unit/",
+    "unit/",
+    "/examples/",
+    "examples/",
+    "/example/",
+    "example/",
+    "/benchmark/",
+    "benchmark/",
+];
+
+/// Vendor/generated patterns (exclude or very low priority)
+const VENDOR_PATTERNS: &[&str] = &[
+    "/vendor/",
+    "vendor/",
+    "/node_modules/",
+    "node_modules/",
+    "/dist/",
+    "dist/",
+    "/build/",
+    "build/",
+    "/target/",
+    "target/",
+    "/__pycache__/",
+    "__pycache__/",
+    "/.next/",
+    ".next/",
+    "/coverage/",
+    "coverage/",
+    "/.cache/",
+    ".cache/",
+    "/generated/",
+    "generated/",
+    "/.generated/",
+    ".generated/",
+    "/gen/",
+    "gen/",
+    ".min.js",
+    ".min.css",
+    ".bundle.",
+    "/benchmarks/",
+    "benchmarks/",
+];
+
+/// Breakdown of a file's heuristic importance score
+#[derive(Debug, Clone, PartialEq)]
+pub struct FileImportance {
+    /// Path category that determined the base score
+    pub category: &'static str,
+    /// Base score for the category
+    pub base: f32,
+    /// Boost for the number of symbols in the file
+    pub symbol_boost: f32,
+    /// Boost for common implementation file names (handler, service, ...)
+    pub name_boost: f32,
+}
 
-        let mut importance: f32;
+impl FileImportance {
+    /// Final importance score (0.0 - 1.0)
+    pub fn total(&self) -> f32 {
+        (self.base + self.symbol_boost + self.name_boost).min(1.0)
+    }
 
-        // Check vendor/generated first (exclude from ranking)
-        if vendor_patterns.iter().any(|p| path.contains(p)) {
-            importance = 0.05;
+    /// Contributions to the score, largest first, for annotating outputs
+    pub fn factors(&self) -> Vec<String> {
+        let mut parts = vec![(self.category, self.base)];
+        if self.symbol_boost > 0.0 {
+            parts.push(("symbol count", self.symbol_boost));
         }
-        // Check test patterns (low priority)
-        else if test_patterns.iter().any(|p| path.contains(p)) {
-            importance = 0.15;
+        if self.name_boost > 0.0 {
+            parts.push(("implementation name", self.name_boost));
         }
-        // Check config patterns (low priority)
-        else if config_patterns
+        parts.sort_by(|a, b| b.1.total_cmp(&a.1));
+        parts
+            .into_iter()
+            .map(|(reason, weight)| format!("{} ({:+.2})", reason, weight))
+            .collect()
+    }
+}
+
+/// Compute the importance breakdown for a single file
+pub fn file_importance(file: &RepoFile) -> FileImportance {
+    let filename = file.filename().to_lowercase();
+    let path = file.relative_path.to_lowercase();
+
+    let is_vendor = VENDOR_PATTERNS.iter().any(|p| path.contains(p));
+    let is_test = TEST_PATTERNS.iter().any(|p| path.contains(p));
+
+    let (category, base) = if file.pinned {
+        ("pinned by directive", 1.0)
+    } else if is_vendor {
+        // Check vendor/generated first (exclude from ranking)
+        ("vendor/generated", 0.05)
+    } else if is_test {
+        ("test", 0.15)
+    } else if CONFIG_PATTERNS
+        .iter()
+        .any(|p| filename.contains(p) || path.contains(p))
+    {
+        ("config", 0.25)
+    } else if DOC_PATTERNS.iter().any(|p| filename.starts_with(p)) {
+        ("documentation", 0.35)
+    } else if CRITICAL_ENTRY_PATTERNS.iter().any(|p| filename == *p)
+        || code_entry_point(file).is_some()
+    {
+        ("entry point", 1.0)
+    } else if ENTRY_PREFIXES.iter().any(|p| filename.starts_with(p)) {
+        ("entry point prefix", 0.9)
+    } else if CORE_DIRS.iter().any(|p| path.contains(p)) {
+        ("core directory", 0.75)
+    } else {
+        ("source", 0.5)
+    };
+
+    let mut importance = FileImportance { category, base, symbol_boost: 0.0, name_boost: 0.0 };
+
+    // Only apply boosts if not in test/vendor directories
+    if !is_vendor && !is_test {
+        // Boost based on symbol count (more symbols = more important code)
+        importance.symbol_boost = (file.symbols.len() as f32 / 50.0).min(0.15);
+
+        // Slight boost for files with common implementation names
+        if ["handler", "service", "controller", "model", "util", "helper", "router", "middleware"]
             .iter()
-            .any(|p| filename.contains(p) || path.contains(p))
+            .any(|n| filename.contains(n))
         {
-            importance = 0.25;
-        }
-        // Check doc patterns
-        else if doc_patterns.iter().any(|p| filename.starts_with(p)) {
-            importance = 0.35;
-        }
-        // Check critical entry points (highest priority)
-        else if critical_entry_patterns.iter().any(|p| filename == *p) {
-            importance = 1.0;
-        }
-        // Check entry point prefixes
-        else if entry_prefixes.iter().any(|p| filename.starts_with(p)) {
-            importance = 0.9;
-        }
-        // Check core directories
-        else if core_dirs.iter().any(|p| path.contains(p)) {
-            importance = 0.75;
-        }
-        // Default for other source files
-        else {
-            importance = 0.5;
+            importance.name_boost = 0.1;
         }
+    }
 
-        // Only apply boosts if not in test/vendor directories
-        let is_test_or_vendor = vendor_patterns.iter().any(|p| path.contains(p))
-            || test_patterns.iter().any(|p| path.contains(p));
-
-        if !is_test_or_vendor {
-            // Boost based on symbol count (more symbols = more important code)
-            let symbol_boost = (file.symbols.len() as f32 / 50.0).min(0.15);
-            importance = (importance + symbol_boost).min(1.0);
-
-            // Slight boost for files with common implementation names
-            if filename.contains("handler")
-                || filename.contains("service")
-                || filename.contains("controller")
-                || filename.contains("model")
-                || filename.contains("util")
-                || filename.contains("helper")
-                || filename.contains("router")
-                || filename.contains("middleware")
-            {
-                importance = (importance + 0.1).min(1.0);
-            }
-        }
+    importance
+}
 
-        file.importance = importance;
+/// Rank files by importance using heuristics
+/// Priority: Entry points > Core implementation > Libraries > Config > Tests
+///
+/// Files that manifests declare as entry points rank as entry points too.
+pub fn rank_files(repo: &mut Repository) {
+    let declared: HashSet<String> = manifest_entry_points(repo)
+        .into_iter()
+        .map(|e| e.path)
+        .collect();
+    for file in &mut repo.files {
+        let mut importance = file_importance(file);
+        if importance.base < 1.0 && declared.contains(&file.relative_path) {
+            importance.category = "declared entry point";
+            importance.base = 1.0;
+        }
+        file.importance = importance.total();
     }
 }
 
@@ -364,7 +400,20 @@ pub fn sort_files_by_importance(repo: &mut Repository) {
 #[allow(clippy::str_to_string)]
 mod tests {
     use super::*;
-    use crate::types::TokenCounts;
+
+    #[test]
+    fn test_importance_factors() {
+        let importance = FileImportance {
+            category: "core directory",
+            base: 0.75,
+            symbol_boost: 0.0,
+            name_boost: 0.1,
+        };
+        assert_eq!(
+            importance.factors(),
+            vec!["core directory (+0.75)", "implementation name (+0.10)"]
+        );
+    }
 
     #[test]
     fn test_type_importance() {
@@ -380,7 +429,6 @@ mod tests {
             relative_path: "main.py".to_string(),
             language: Some("python".to_string()),
             size_bytes: 100,
-            token_count: TokenCounts::default(),
             symbols: vec![
                 Symbol {
                     name: "main".to_string(),
@@ -400,7 +448,7 @@ mod tests {
                 },
             ],
             importance: 0.5,
-            content: None,
+            ..Default::default()
         });
 
         let ranker = SymbolRanker::new();
@@ -411,4 +459,41 @@ mod tests {
         let helper_importance = repo.files[0].symbols[1].importance;
         assert!(main_importance > helper_importance);
     }
+
+    #[test]
+    fn test_file_importance_breakdown() {
+        let file = |path: &str| RepoFile {
+            path: format!("/tmp/test/{}", path).into(),
+            relative_path: path.to_string(),
+            importance: 0.5,
+            ..Default::default()
+        };
+
+        let service = file_importance(&file("pkg/user_service.go"));
+        assert_eq!(service.category, "source");
+        assert_eq!(service.name_boost, 0.1);
+        assert!((service.total() - 0.6).abs() < 1e-6);
+
+        let test = file_importance(&file("tests/user_service.rs"));
+        assert_eq!(test.category, "test");
+        assert_eq!(test.name_boost, 0.0);
+        assert_eq!(file_importance(&file("src/main.rs")).total(), 1.0);
+    }
+
+    #[test]
+    fn test_entry_points_beyond_file_names() {
+        let mut worker = RepoFile::new("/tmp/test/pkg/worker.rs", "pkg/worker.rs");
+        worker.language = Some("rust".to_string());
+        worker.content = Some("#[tokio::main]\nasync fn main() {}\n".to_string());
+        assert_eq!(file_importance(&worker).category, "entry point");
+
+        let mut manifest = RepoFile::new("/tmp/test/package.json", "package.json");
+        manifest.content = Some(r#"{"bin": "scripts/run.js"}"#.to_string());
+        let mut repo = Repository::new("test", "/tmp/test");
+        repo.files.push(manifest);
+        repo.files
+            .push(RepoFile::new("/tmp/test/scripts/run.js", "scripts/run.js"));
+        rank_files(&mut repo);
+        assert_eq!(repo.files[1].importance, 1.0);
+    }
 }
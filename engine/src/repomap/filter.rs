@@ -0,0 +1,209 @@
+//! Symbol noise filtering before graph building
+//!
+//! Generated bindings and test helpers contribute thousands of trivially
+//! named symbols (`new`, `default`, `test_*`) that would otherwise crowd
+//! meaningful API out of the `max_symbols` slots.
+
+use crate::config::SymbolConfig;
+use crate::types::{Symbol, SymbolKind};
+use std::collections::HashMap;
+
+/// Stop-list key that applies to every language
+pub const ANY_LANGUAGE: &str = "*";
+
+/// Built-in stop-lists, keyed by language name as reported by the scanner
+const DEFAULT_STOP_LISTS: &[(&str, &[&str])] = &[
+    (
+        "rust",
+        &[
+            "new",
+            "default",
+            "fmt",
+            "clone",
+            "from",
+            "into",
+            "drop",
+            "eq",
+            "hash",
+            "deref",
+            "deref_mut",
+            "as_ref",
+            "test_*",
+        ],
+    ),
+    (
+        "python",
+        &[
+            "__init__", "__repr__", "__str__", "__eq__", "__hash__", "__len__", "setUp",
+            "tearDown", "test_*",
+        ],
+    ),
+    ("javascript", &["constructor", "toString", "toJSON"]),
+    ("jsx", &["constructor", "toString", "render"]),
+    ("typescript", &["constructor", "toString", "toJSON"]),
+    ("tsx", &["constructor", "toString", "render"]),
+    ("go", &["String", "Error", "init", "Test*", "Benchmark*"]),
+    ("java", &["toString", "equals", "hashCode", "test*"]),
+    ("kotlin", &["toString", "equals", "hashCode"]),
+    ("csharp", &["ToString", "Equals", "GetHashCode", "Dispose"]),
+];
+
+/// Drops low-signal symbols before they enter the symbol graph
+///
+/// Patterns are exact names or contain a single `*` wildcard (`test_*`,
+/// `*_generated`). Imports are never dropped since they carry the graph's
+/// edges.
+#[derive(Debug, Clone)]
+pub struct SymbolFilter {
+    /// Patterns per language, plus [`ANY_LANGUAGE`] for all languages
+    stop_lists: HashMap<String, Vec<String>>,
+    /// Drop symbols whose signature is shorter than this (0 disables)
+    min_signature_length: usize,
+}
+
+impl Default for SymbolFilter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SymbolFilter {
+    /// Create a filter with the built-in stop-lists
+    pub fn new() -> Self {
+        let stop_lists = DEFAULT_STOP_LISTS
+            .iter()
+            .map(|(lang, names)| {
+                ((*lang).to_owned(), names.iter().map(|n| (*n).to_owned()).collect())
+            })
+            .collect();
+        Self { stop_lists, min_signature_length: 0 }
+    }
+
+    /// Create a filter that keeps every symbol
+    pub fn none() -> Self {
+        Self { stop_lists: HashMap::new(), min_signature_length: 0 }
+    }
+
+    /// Build a filter from the `symbols` configuration section
+    pub fn from_config(config: &SymbolConfig) -> Self {
+        let mut filter = if config.default_stop_lists {
+            Self::new()
+        } else {
+            Self::none()
+        };
+        for (language, patterns) in &config.stop_lists {
+            for pattern in patterns {
+                filter = filter.with_stop_word(language, pattern.clone());
+            }
+        }
+        filter.with_min_signature_length(config.min_signature_length)
+    }
+
+    /// Add a stop-list pattern for `language` (or [`ANY_LANGUAGE`])
+    pub fn with_stop_word(mut self, language: &str, pattern: impl Into<String>) -> Self {
+        self.stop_lists
+            .entry(language.to_lowercase())
+            .or_default()
+            .push(pattern.into());
+        self
+    }
+
+    /// Drop symbols whose signature is shorter than `len` characters
+    pub fn with_min_signature_length(mut self, len: usize) -> Self {
+        self.min_signature_length = len;
+        self
+    }
+
+    /// Whether this filter keeps every symbol
+    pub fn is_empty(&self) -> bool {
+        self.min_signature_length == 0 && self.stop_lists.values().all(Vec::is_empty)
+    }
+
+    /// Whether `symbol`, found in a file of `language`, should be kept
+    pub fn keeps(&self, language: Option<&str>, symbol: &Symbol) -> bool {
+        if symbol.kind == SymbolKind::Import {
+            return true;
+        }
+
+        if self.min_signature_length > 0 {
+            if let Some(signature) = &symbol.signature {
+                if signature.trim().chars().count() < self.min_signature_length {
+                    return false;
+                }
+            }
+        }
+
+        let stopped = |key: &str| {
+            self.stop_lists
+                .get(key)
+                .is_some_and(|patterns| patterns.iter().any(|p| matches(p, &symbol.name)))
+        };
+        !(stopped(ANY_LANGUAGE) || language.is_some_and(stopped))
+    }
+}
+
+/// Match `name` against an exact pattern or one with a single `*`
+fn matches(pattern: &str, name: &str) -> bool {
+    match pattern.split_once('*') {
+        None => pattern == name,
+        Some((prefix, suffix)) => {
+            name.len() >= prefix.len() + suffix.len()
+                && name.starts_with(prefix)
+                && name.ends_with(suffix)
+        },
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::str_to_string)]
+mod tests {
+    use super::*;
+
+    fn function(name: &str, signature: Option<&str>) -> Symbol {
+        let mut symbol = Symbol::new(name, SymbolKind::Function);
+        symbol.signature = signature.map(str::to_string);
+        symbol
+    }
+
+    #[test]
+    fn test_default_stop_lists_are_per_language() {
+        let filter = SymbolFilter::new();
+        assert!(!filter.keeps(Some("rust"), &function("new", None)));
+        assert!(!filter.keeps(Some("python"), &function("test_parse", None)));
+        assert!(filter.keeps(Some("python"), &function("new", None)));
+        assert!(filter.keeps(Some("rust"), &function("parse", None)));
+        assert!(filter.keeps(None, &function("new", None)));
+
+        let import = Symbol::new("new", SymbolKind::Import);
+        assert!(filter.keeps(Some("rust"), &import));
+    }
+
+    #[test]
+    fn test_custom_patterns_and_signature_length() {
+        let filter = SymbolFilter::none()
+            .with_stop_word(ANY_LANGUAGE, "*_pb2")
+            .with_stop_word("Go", "Get*")
+            .with_min_signature_length(12);
+
+        assert!(!filter.keeps(Some("python"), &function("user_pb2", None)));
+        assert!(!filter.keeps(Some("go"), &function("GetName", None)));
+        assert!(filter.keeps(Some("go"), &function("Name", None)));
+        assert!(!filter.keeps(Some("rust"), &function("f", Some("fn f()"))));
+        assert!(filter.keeps(Some("rust"), &function("parse", Some("fn parse(input: &str)"))));
+        assert!(filter.keeps(Some("rust"), &function("parse", None)));
+        assert!(SymbolFilter::none().is_empty());
+    }
+
+    #[test]
+    fn test_from_config() {
+        let config = SymbolConfig {
+            default_stop_lists: false,
+            stop_lists: HashMap::from([("rust".to_string(), vec!["builder".to_string()])]),
+            ..Default::default()
+        };
+
+        let filter = SymbolFilter::from_config(&config);
+        assert!(filter.keeps(Some("rust"), &function("new", None)));
+        assert!(!filter.keeps(Some("rust"), &function("builder", None)));
+    }
+}
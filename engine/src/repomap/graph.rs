@@ -2,7 +2,9 @@
 
 use crate::types::{RepoFile, Symbol};
 use petgraph::graph::{DiGraph, NodeIndex};
-use std::collections::HashMap;
+use petgraph::Direction;
+use rayon::prelude::*;
+use std::collections::HashSet;
 
 /// A node in the symbol graph
 #[derive(Debug, Clone)]
@@ -29,115 +31,178 @@ pub(super) enum EdgeType {
     References,
 }
 
+/// Position of a node in the graph, also its index into rank vectors
+pub(super) type NodeId = usize;
+
 /// Graph of symbols with reference relationships
 pub(super) struct SymbolGraph {
     /// The underlying directed graph
     graph: DiGraph<SymbolNode, EdgeType>,
-    /// Map from symbol key to node index
-    symbol_indices: HashMap<String, NodeIndex>,
 }
 
 impl SymbolGraph {
     /// Create a new empty graph
     pub(super) fn new() -> Self {
-        Self { graph: DiGraph::new(), symbol_indices: HashMap::new() }
+        Self { graph: DiGraph::new() }
     }
 
-    /// Add all symbols from a file
-    pub(super) fn add_file(&mut self, file: &RepoFile) {
-        for symbol in &file.symbols {
-            let node = SymbolNode { symbol: symbol.clone(), file_path: file.relative_path.clone() };
+    /// Build a graph of the symbols from `files` that pass `keep`
+    ///
+    /// Symbols are cloned in parallel; node IDs follow file and symbol order.
+    pub(super) fn build(
+        files: &[RepoFile],
+        keep: impl Fn(&RepoFile, &Symbol) -> bool + Sync,
+    ) -> Self {
+        let nodes: Vec<SymbolNode> = files
+            .par_iter()
+            .flat_map_iter(|file| {
+                let keep = &keep;
+                file.symbols
+                    .iter()
+                    .filter(move |s| keep(file, s))
+                    .map(move |symbol| SymbolNode {
+                        symbol: symbol.clone(),
+                        file_path: file.relative_path.clone(),
+                    })
+            })
+            .collect();
+
+        let mut graph = DiGraph::with_capacity(nodes.len(), 0);
+        for node in nodes {
+            graph.add_node(node);
+        }
+        Self { graph }
+    }
 
-            let idx = self.graph.add_node(node);
-            let key = format!("{}:{}", file.relative_path, symbol.name);
-            self.symbol_indices.insert(key, idx);
+    /// Add the symbols from a file that pass `keep`
+    #[allow(dead_code)]
+    pub(super) fn add_file(&mut self, file: &RepoFile, keep: impl Fn(&Symbol) -> bool) {
+        for symbol in file.symbols.iter().filter(|s| keep(s)) {
+            let node = SymbolNode { symbol: symbol.clone(), file_path: file.relative_path.clone() };
+            self.graph.add_node(node);
         }
     }
 
+    /// All nodes with their IDs
+    pub(super) fn nodes(&self) -> impl Iterator<Item = (NodeId, &SymbolNode)> {
+        self.graph
+            .node_indices()
+            .map(|idx| (idx.index(), &self.graph[idx]))
+    }
+
     /// Add a reference edge between symbols
-    pub(super) fn add_reference(&mut self, from: &str, to: &str, edge_type: EdgeType) {
-        if let (Some(&from_idx), Some(&to_idx)) =
-            (self.symbol_indices.get(from), self.symbol_indices.get(to))
-        {
-            self.graph.add_edge(from_idx, to_idx, edge_type);
+    pub(super) fn add_reference(&mut self, from: NodeId, to: NodeId, edge_type: EdgeType) {
+        let count = self.graph.node_count();
+        if from < count && to < count {
+            self.graph
+                .add_edge(NodeIndex::new(from), NodeIndex::new(to), edge_type);
         }
     }
 
-    /// Compute PageRank scores for all symbols
-    pub(super) fn compute_pagerank(&self, damping: f64, iterations: usize) -> HashMap<String, f64> {
+    /// Pairs of each node and the node `resolve` finds for it, resolved in
+    /// parallel
+    pub(super) fn resolve_edges(
+        &self,
+        resolve: impl Fn(&SymbolNode) -> Option<NodeId> + Sync,
+    ) -> Vec<(NodeId, NodeId)> {
+        (0..self.graph.node_count())
+            .into_par_iter()
+            .filter_map(|id| resolve(&self.graph[NodeIndex::new(id)]).map(|to| (id, to)))
+            .collect()
+    }
+
+    /// Add reference edges of one type
+    pub(super) fn add_references(&mut self, edges: &[(NodeId, NodeId)], edge_type: EdgeType) {
+        self.graph.reserve_edges(edges.len());
+        for &(from, to) in edges {
+            self.add_reference(from, to, edge_type);
+        }
+    }
+
+    /// Compute PageRank scores for all symbols, indexed by node ID
+    ///
+    /// Each iteration pulls rank along incoming edges, so nodes update in
+    /// parallel without contention.
+    pub(super) fn compute_pagerank(&self, damping: f64, iterations: usize) -> Vec<f64> {
         let node_count = self.graph.node_count();
         if node_count == 0 {
-            return HashMap::new();
+            return Vec::new();
         }
 
-        // Initialize ranks
-        let initial_rank = 1.0 / node_count as f64;
-        let mut ranks: Vec<f64> = vec![initial_rank; node_count];
-        let mut new_ranks: Vec<f64> = vec![0.0; node_count];
+        let out_degree: Vec<usize> = (0..node_count)
+            .into_par_iter()
+            .map(|id| self.graph.neighbors(NodeIndex::new(id)).count())
+            .collect();
+        let incoming: Vec<Vec<NodeId>> = (0..node_count)
+            .into_par_iter()
+            .map(|id| {
+                self.graph
+                    .neighbors_directed(NodeIndex::new(id), Direction::Incoming)
+                    .map(NodeIndex::index)
+                    .collect()
+            })
+            .collect();
 
-        // Iterative PageRank computation (optimized dangling node handling)
-        for _ in 0..iterations {
-            // Reset new ranks with teleportation probability
-            let teleport = (1.0 - damping) / node_count as f64;
-            new_ranks.fill(teleport);
-
-            // First pass: accumulate dangling node contribution
-            let mut dangling_sum = 0.0;
-            for node_idx in self.graph.node_indices() {
-                let out_degree = self.graph.neighbors(node_idx).count();
-                if out_degree == 0 {
-                    dangling_sum += ranks[node_idx.index()];
-                }
-            }
-            // Distribute dangling mass to all nodes at once (O(n) instead of O(n²))
-            let dangling_contribution = damping * dangling_sum / node_count as f64;
-            for rank in &mut new_ranks {
-                *rank += dangling_contribution;
-            }
+        let mut ranks = vec![1.0 / node_count as f64; node_count];
+        let mut new_ranks = vec![0.0; node_count];
+        let teleport = (1.0 - damping) / node_count as f64;
 
-            // Second pass: distribute rank from non-dangling nodes to neighbors
-            for node_idx in self.graph.node_indices() {
-                let out_degree = self.graph.neighbors(node_idx).count();
-                if out_degree > 0 {
-                    let rank_contribution = damping * ranks[node_idx.index()] / out_degree as f64;
-                    for neighbor in self.graph.neighbors(node_idx) {
-                        new_ranks[neighbor.index()] += rank_contribution;
-                    }
-                }
-            }
+        for _ in 0..iterations {
+            // Dangling nodes spread their rank over every node
+            let dangling_sum: f64 = ranks
+                .par_iter()
+                .zip(&out_degree)
+                .filter(|(_, &degree)| degree == 0)
+                .map(|(rank, _)| rank)
+                .sum();
+            let base = teleport + damping * dangling_sum / node_count as f64;
+
+            new_ranks.par_iter_mut().enumerate().for_each(|(id, rank)| {
+                let inflow: f64 = incoming[id]
+                    .iter()
+                    .map(|&from| ranks[from] / out_degree[from] as f64)
+                    .sum();
+                *rank = base + damping * inflow;
+            });
 
-            // Swap ranks
             std::mem::swap(&mut ranks, &mut new_ranks);
         }
 
-        // Build result map
-        let mut result = HashMap::new();
-        for (key, &idx) in &self.symbol_indices {
-            result.insert(key.clone(), ranks[idx.index()]);
+        ranks
+    }
+
+    /// Nodes in files matching `in_focus`, and nodes one edge away from them
+    /// in either direction
+    pub(super) fn focus_nodes(
+        &self,
+        in_focus: impl Fn(&str) -> bool,
+    ) -> (HashSet<NodeId>, HashSet<NodeId>) {
+        let mut focused = HashSet::new();
+        let mut neighbors = HashSet::new();
+        for idx in self.graph.node_indices() {
+            if in_focus(&self.graph[idx].file_path) {
+                focused.insert(idx.index());
+                neighbors.extend(self.graph.neighbors_undirected(idx).map(NodeIndex::index));
+            }
         }
-        result
+        neighbors.retain(|id| !focused.contains(id));
+        (focused, neighbors)
     }
 
-    /// Get top N symbols using pre-computed ranks
+    /// Get top N symbols and their ranks using pre-computed ranks
     pub(super) fn get_top_symbols_with_ranks(
         &self,
-        ranks: &HashMap<String, f64>,
+        ranks: &[f64],
         n: usize,
-    ) -> Vec<&SymbolNode> {
+    ) -> Vec<(&SymbolNode, f64)> {
         let mut ranked: Vec<_> = self
-            .graph
-            .node_indices()
-            .filter_map(|idx| {
-                let node = self.graph.node_weight(idx)?;
-                let key = format!("{}:{}", node.file_path, node.symbol.name);
-                let rank = ranks.get(&key).copied().unwrap_or(0.0);
-                Some((node, rank))
-            })
+            .nodes()
+            .map(|(id, node)| (node, ranks.get(id).copied().unwrap_or(0.0)))
             .collect();
 
         ranked.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
-
-        ranked.into_iter().take(n).map(|(node, _)| node).collect()
+        ranked.truncate(n);
+        ranked
     }
 
     /// Get top N symbols by PageRank (computes ranks internally - use get_top_symbols_with_ranks if ranks already computed)
@@ -145,6 +210,9 @@ impl SymbolGraph {
     pub(super) fn get_top_symbols(&self, n: usize) -> Vec<&SymbolNode> {
         let ranks = self.compute_pagerank(0.85, 100);
         self.get_top_symbols_with_ranks(&ranks, n)
+            .into_iter()
+            .map(|(node, _)| node)
+            .collect()
     }
 
     /// Get number of nodes
@@ -187,19 +255,61 @@ mod tests {
             relative_path: "main.py".to_owned(),
             language: Some("python".to_owned()),
             size_bytes: 100,
-            token_count: Default::default(),
             symbols: vec![
                 Symbol::new("main", SymbolKind::Function),
                 Symbol::new("helper", SymbolKind::Function),
             ],
             importance: 0.5,
-            content: None,
+            ..Default::default()
         };
 
-        graph.add_file(&file);
+        graph.add_file(&file, |_| true);
         assert_eq!(graph.node_count(), 2);
     }
 
+    #[test]
+    fn test_build_resolves_edges_in_parallel() {
+        let file = |path: &str, names: &[&str]| RepoFile {
+            path: path.into(),
+            relative_path: path.to_owned(),
+            language: Some("python".to_owned()),
+            size_bytes: 100,
+            symbols: names
+                .iter()
+                .map(|n| Symbol::new(*n, SymbolKind::Function))
+                .collect(),
+            importance: 0.5,
+            ..Default::default()
+        };
+        let files: Vec<RepoFile> = (0..100)
+            .map(|i| file(&format!("m{}.py", i), &["f", "g", "_skip"]))
+            .collect();
+
+        let mut graph = SymbolGraph::build(&files, |_, s| !s.name.starts_with('_'));
+        assert_eq!(graph.node_count(), 200);
+        // Node IDs follow file order
+        let ids: Vec<_> = graph
+            .nodes()
+            .filter(|(_, n)| n.file_path == "m7.py")
+            .collect();
+        assert_eq!(ids[0].0, 14);
+
+        // Every `g` calls the `f` beside it
+        let edges = graph.resolve_edges(|node| {
+            (node.symbol.name == "g").then(|| {
+                graph
+                    .nodes()
+                    .position(|(_, n)| n.file_path == node.file_path)
+                    .unwrap()
+            })
+        });
+        graph.add_references(&edges, EdgeType::Calls);
+        assert_eq!(graph.edge_count(), 100);
+
+        let ranks = graph.compute_pagerank(0.85, 20);
+        assert!(ranks[0] > ranks[1]);
+    }
+
     #[test]
     fn test_pagerank() {
         let mut graph = SymbolGraph::new();
@@ -210,26 +320,25 @@ mod tests {
             relative_path: "main.py".to_owned(),
             language: Some("python".to_owned()),
             size_bytes: 100,
-            token_count: Default::default(),
             symbols: vec![
                 Symbol::new("a", SymbolKind::Function),
                 Symbol::new("b", SymbolKind::Function),
                 Symbol::new("c", SymbolKind::Function),
             ],
             importance: 0.5,
-            content: None,
+            ..Default::default()
         };
 
-        graph.add_file(&file);
-        graph.add_reference("main.py:a", "main.py:b", EdgeType::Calls);
-        graph.add_reference("main.py:b", "main.py:c", EdgeType::Calls);
-        graph.add_reference("main.py:a", "main.py:c", EdgeType::Calls);
+        graph.add_file(&file, |_| true);
+        graph.add_reference(0, 1, EdgeType::Calls);
+        graph.add_reference(1, 2, EdgeType::Calls);
+        graph.add_reference(0, 2, EdgeType::Calls);
 
         let ranks = graph.compute_pagerank(0.85, 100);
 
         // C should have highest rank (most incoming edges)
-        let rank_a = ranks.get("main.py:a").unwrap();
-        let rank_c = ranks.get("main.py:c").unwrap();
-        assert!(rank_c > rank_a);
+        assert!(ranks[2] > ranks[0]);
+        // Ranks stay a probability distribution
+        assert!((ranks.iter().sum::<f64>() - 1.0).abs() < 1e-9);
     }
 }
@@ -1,16 +1,33 @@
 //! Repository map generation with PageRank-based symbol ranking
 
+mod filter;
 mod graph;
 
+use crate::cancel::{CancellationToken, Cancelled};
+use crate::dependencies::{DependencyGraph, ExternalUsage};
+use crate::ids::IdTable;
 #[cfg(test)]
 use crate::types::{RepoFile, Symbol};
 use crate::types::{Repository, SymbolKind, TokenizerModel};
-use graph::SymbolGraph;
-use serde::Serialize;
+pub use filter::{SymbolFilter, ANY_LANGUAGE};
+use graph::{NodeId, SymbolGraph};
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
+/// Rank multiplier for symbols in focused files
+const FOCUS_BOOST: f64 = 10.0;
+
+/// Rank multiplier for symbols linked to a focused file by an import
+const FOCUS_NEIGHBOR_BOOST: f64 = 3.0;
+
+/// External dependencies listed in the map, most used first
+const MAX_EXTERNAL_USAGE: usize = 10;
+
 /// A repository map - a concise summary of the codebase
-#[derive(Debug, Clone, Serialize)]
+///
+/// Serializes to and from JSON, so a map can be saved and reloaded without
+/// scanning the repository again.
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RepoMap {
     /// Text summary of the repository
     pub summary: String,
@@ -20,13 +37,18 @@ pub struct RepoMap {
     pub module_graph: ModuleGraph,
     /// Index of all files with metadata
     pub file_index: Vec<FileIndexEntry>,
+    /// Most used external dependencies with the files importing them
+    pub external_usage: Vec<ExternalUsage>,
     /// Total token count for this map
     pub token_count: u32,
 }
 
 /// A symbol with its computed rank
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RankedSymbol {
+    /// Stable symbol ID, see [`crate::ids`]; imports have none
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
     /// Symbol name
     pub name: String,
     /// Symbol kind
@@ -46,7 +68,7 @@ pub struct RankedSymbol {
 }
 
 /// Graph of module dependencies
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ModuleGraph {
     /// Module nodes
     pub nodes: Vec<ModuleNode>,
@@ -55,7 +77,7 @@ pub struct ModuleGraph {
 }
 
 /// A module/directory node
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ModuleNode {
     /// Module name (usually directory name)
     pub name: String,
@@ -66,7 +88,7 @@ pub struct ModuleNode {
 }
 
 /// A dependency edge between modules
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ModuleEdge {
     /// Source module
     pub from: String,
@@ -77,8 +99,10 @@ pub struct ModuleEdge {
 }
 
 /// File index entry
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FileIndexEntry {
+    /// Stable file ID, see [`crate::ids`]
+    pub id: String,
     /// Relative file path
     pub path: String,
     /// Token count
@@ -89,6 +113,19 @@ pub struct FileIndexEntry {
     pub summary: Option<String>,
 }
 
+/// A reference from one symbol to another in the ranking graph
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SymbolEdge {
+    /// File of the referencing symbol
+    pub from_file: String,
+    /// Name of the referencing symbol
+    pub from_symbol: String,
+    /// File of the referenced symbol
+    pub to_file: String,
+    /// Name of the referenced symbol
+    pub to_symbol: String,
+}
+
 /// Generator for repository maps
 pub struct RepoMapGenerator {
     /// Token budget for the map
@@ -98,12 +135,22 @@ pub struct RepoMapGenerator {
     max_symbols: usize,
     /// Target model for token counting
     model: TokenizerModel,
+    /// Drops noise symbols before graph building
+    symbol_filter: SymbolFilter,
+    /// Files or directories whose symbols (and their imports) rank first
+    focus: Vec<String>,
 }
 
 impl RepoMapGenerator {
     /// Create a new generator with token budget
     pub fn new(token_budget: u32) -> Self {
-        Self { token_budget, max_symbols: 50, model: TokenizerModel::Claude }
+        Self {
+            token_budget,
+            max_symbols: 50,
+            model: TokenizerModel::Claude,
+            symbol_filter: SymbolFilter::default(),
+            focus: Vec::new(),
+        }
     }
 
     /// Set maximum symbols to include
@@ -118,48 +165,129 @@ impl RepoMapGenerator {
         self
     }
 
+    /// Set the filter applied to symbols before ranking
+    pub fn with_symbol_filter(mut self, filter: SymbolFilter) -> Self {
+        self.symbol_filter = filter;
+        self
+    }
+
+    /// Bias ranking towards these files or directories (relative paths)
+    ///
+    /// Symbols defined in focused files are boosted the most, followed by
+    /// symbols they import or that import them.
+    pub fn with_focus<I, S>(mut self, paths: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.focus = paths
+            .into_iter()
+            .map(|p| {
+                let p: String = p.into();
+                p.trim_start_matches("./").trim_end_matches('/').to_owned()
+            })
+            .filter(|p| !p.is_empty() && p != ".")
+            .collect();
+        self
+    }
+
+    /// Whether `path` is one of the focus paths or lies beneath one
+    fn in_focus(&self, path: &str) -> bool {
+        self.focus.iter().any(|f| {
+            path == f || (path.starts_with(f.as_str()) && path[f.len()..].starts_with('/'))
+        })
+    }
+
     /// Generate a repository map
     pub fn generate(&self, repo: &Repository) -> RepoMap {
-        // Build symbol graph
-        let mut graph = SymbolGraph::new();
-        for file in &repo.files {
-            graph.add_file(file);
+        match self.try_generate(repo, &CancellationToken::new()) {
+            Ok(map) => map,
+            Err(Cancelled) => unreachable!("a new token is never cancelled"),
         }
+    }
 
-        // Build lookup index for fast import resolution
-        let symbol_index = self.build_symbol_index(repo);
+    /// Generate a repository map, giving up between steps once `cancel` is
+    /// cancelled
+    pub fn try_generate(
+        &self,
+        repo: &Repository,
+        cancel: &CancellationToken,
+    ) -> Result<RepoMap, Cancelled> {
+        // Build symbol graph
+        let mut graph = SymbolGraph::build(&repo.files, |file, symbol| {
+            self.symbol_filter.keeps(file.language.as_deref(), symbol)
+        });
+        cancel.check()?;
 
-        // Extract references from symbols using index
-        self.extract_references_fast(&mut graph, repo, &symbol_index);
+        // Extract references from symbols using a lookup index
+        let edges = self.extract_references_fast(&graph, &self.build_symbol_index(&graph));
+        graph.add_references(&edges, graph::EdgeType::Imports);
+        cancel.check()?;
 
         // Compute PageRank once
-        let ranks = graph.compute_pagerank(0.85, 20); // Reduced iterations for speed
+        let mut ranks = graph.compute_pagerank(0.85, 20); // Reduced iterations for speed
+        if !self.focus.is_empty() {
+            let (focused, neighbors) = graph.focus_nodes(|path| self.in_focus(path));
+            for (id, rank) in ranks.iter_mut().enumerate() {
+                if focused.contains(&id) {
+                    *rank *= FOCUS_BOOST;
+                } else if neighbors.contains(&id) {
+                    *rank *= FOCUS_NEIGHBOR_BOOST;
+                }
+            }
+        }
+
+        cancel.check()?;
 
         // Get top symbols using pre-computed ranks
-        let key_symbols = self.build_ranked_symbols_fast(&graph, &ranks);
+        let ids = IdTable::new(repo);
+        let key_symbols = self.build_ranked_symbols_fast(&graph, &ranks, &ids);
 
         // Build module graph
         let module_graph = self.build_module_graph(repo);
 
         // Build file index
-        let file_index = self.build_file_index(repo);
+        let file_index = self.build_file_index(repo, &ids);
+
+        cancel.check()?;
+
+        // Map external packages to the files that import them
+        let mut external_usage = DependencyGraph::build(repo).external_usage();
+        external_usage.truncate(MAX_EXTERNAL_USAGE);
 
         // Generate summary
         let summary = self.generate_summary(repo, &key_symbols);
 
         // Estimate token count
-        let token_count = self.estimate_tokens(&key_symbols, &file_index);
+        let token_count = self.estimate_tokens(&key_symbols, &file_index, &external_usage);
 
-        RepoMap { summary, key_symbols, module_graph, file_index, token_count }
+        Ok(RepoMap { summary, key_symbols, module_graph, file_index, external_usage, token_count })
+    }
+
+    /// The symbol references ranking runs PageRank on, in graph order
+    pub fn symbol_edges(&self, repo: &Repository) -> Vec<SymbolEdge> {
+        let graph = SymbolGraph::build(&repo.files, |file, symbol| {
+            self.symbol_filter.keeps(file.language.as_deref(), symbol)
+        });
+        let nodes: Vec<_> = graph.nodes().map(|(_, node)| node).collect();
+        self.extract_references_fast(&graph, &self.build_symbol_index(&graph))
+            .into_iter()
+            .map(|(from, to)| SymbolEdge {
+                from_file: nodes[from].file_path.clone(),
+                from_symbol: nodes[from].symbol.name.clone(),
+                to_file: nodes[to].file_path.clone(),
+                to_symbol: nodes[to].symbol.name.clone(),
+            })
+            .collect()
     }
 
     /// Build an index of symbols for fast lookup
-    fn build_symbol_index(&self, repo: &Repository) -> HashMap<String, String> {
+    fn build_symbol_index<'a>(&self, graph: &'a SymbolGraph) -> HashMap<&'a str, NodeId> {
         let mut index = HashMap::new();
-        for file in &repo.files {
+        for (id, node) in graph.nodes() {
             // Index by file path (without extension)
-            let path_key = file
-                .relative_path
+            let path_key = node
+                .file_path
                 .trim_end_matches(".rs")
                 .trim_end_matches(".py")
                 .trim_end_matches(".js")
@@ -167,14 +295,10 @@ impl RepoMapGenerator {
                 .trim_end_matches(".go")
                 .trim_end_matches(".java");
 
-            for symbol in &file.symbols {
-                // Index by symbol name
-                index
-                    .insert(symbol.name.clone(), format!("{}:{}", file.relative_path, symbol.name));
-                // Index by path component
-                index
-                    .insert(path_key.to_owned(), format!("{}:{}", file.relative_path, symbol.name));
-            }
+            // Index by symbol name
+            index.insert(node.symbol.name.as_str(), id);
+            // Index by path component
+            index.insert(path_key, id);
         }
         index
     }
@@ -182,108 +306,42 @@ impl RepoMapGenerator {
     /// Fast reference extraction using pre-built index
     fn extract_references_fast(
         &self,
-        graph: &mut SymbolGraph,
-        repo: &Repository,
-        index: &HashMap<String, String>,
-    ) {
-        for file in &repo.files {
-            for symbol in &file.symbols {
-                if symbol.kind == SymbolKind::Import {
-                    // Fast lookup using index
-                    if let Some(target) = index.get(&symbol.name) {
-                        let from_key = format!("{}:{}", file.relative_path, symbol.name);
-                        graph.add_reference(&from_key, target, graph::EdgeType::Imports);
-                    }
-                }
+        graph: &SymbolGraph,
+        index: &HashMap<&str, NodeId>,
+    ) -> Vec<(NodeId, NodeId)> {
+        graph.resolve_edges(|node| {
+            if node.symbol.kind == SymbolKind::Import {
+                index.get(node.symbol.name.as_str()).copied()
+            } else {
+                None
             }
-        }
+        })
     }
 
     /// Build ranked symbols using pre-computed ranks
     fn build_ranked_symbols_fast(
         &self,
         graph: &SymbolGraph,
-        ranks: &HashMap<String, f64>,
+        ranks: &[f64],
+        ids: &IdTable,
     ) -> Vec<RankedSymbol> {
         let top_nodes = graph.get_top_symbols_with_ranks(ranks, self.max_symbols);
 
         top_nodes
             .iter()
             .enumerate()
-            .map(|(i, node)| {
-                let key = format!("{}:{}", node.file_path, node.symbol.name);
-                let rank_score = ranks.get(&key).copied().unwrap_or(0.0);
-
-                RankedSymbol {
-                    name: node.symbol.name.clone(),
-                    kind: node.symbol.kind.name().to_owned(),
-                    file: node.file_path.clone(),
-                    line: node.symbol.start_line,
-                    signature: node.symbol.signature.clone(),
-                    references: node.symbol.references,
-                    rank: (i + 1) as u32,
-                    importance: rank_score as f32,
-                }
-            })
-            .collect()
-    }
-
-    #[allow(dead_code)]
-    fn extract_references(&self, graph: &mut SymbolGraph, repo: &Repository) {
-        // This is a simplified version - real implementation would use AST
-        for file in &repo.files {
-            for symbol in &file.symbols {
-                // Add edges based on symbol references
-                // In real implementation, parse imports and function calls
-                if symbol.kind == SymbolKind::Import {
-                    // Try to resolve import target
-                    if let Some(target) = self.resolve_import(&symbol.name, repo) {
-                        let from_key = format!("{}:{}", file.relative_path, symbol.name);
-                        graph.add_reference(&from_key, &target, graph::EdgeType::Imports);
-                    }
-                }
-            }
-        }
-    }
-
-    #[allow(dead_code)]
-    fn resolve_import(&self, name: &str, repo: &Repository) -> Option<String> {
-        // Simple resolution - look for matching symbol
-        for file in &repo.files {
-            for symbol in &file.symbols {
-                if symbol.name == name || file.relative_path.contains(name) {
-                    return Some(format!("{}:{}", file.relative_path, symbol.name));
-                }
-            }
-        }
-        None
-    }
-
-    #[allow(dead_code)]
-    fn build_ranked_symbols(
-        &self,
-        graph: &SymbolGraph,
-        ranks: &HashMap<String, f64>,
-    ) -> Vec<RankedSymbol> {
-        let top_nodes = graph.get_top_symbols(self.max_symbols);
-
-        top_nodes
-            .iter()
-            .enumerate()
-            .map(|(i, node)| {
-                let key = format!("{}:{}", node.file_path, node.symbol.name);
-                let rank_score = ranks.get(&key).copied().unwrap_or(0.0);
-
-                RankedSymbol {
-                    name: node.symbol.name.clone(),
-                    kind: node.symbol.kind.name().to_owned(),
-                    file: node.file_path.clone(),
-                    line: node.symbol.start_line,
-                    signature: node.symbol.signature.clone(),
-                    references: node.symbol.references,
-                    rank: (i + 1) as u32,
-                    importance: rank_score as f32,
-                }
+            .map(|(i, (node, rank_score))| RankedSymbol {
+                id: ids
+                    .symbol(&node.file_path, &node.symbol.name, node.symbol.start_line)
+                    .map(str::to_owned),
+                name: node.symbol.name.clone(),
+                kind: node.symbol.kind.name().to_owned(),
+                file: node.file_path.clone(),
+                line: node.symbol.start_line,
+                signature: node.symbol.signature.clone(),
+                references: node.symbol.references,
+                rank: (i + 1) as u32,
+                importance: *rank_score as f32,
             })
             .collect()
     }
@@ -317,7 +375,7 @@ impl RepoMapGenerator {
         }
     }
 
-    fn build_file_index(&self, repo: &Repository) -> Vec<FileIndexEntry> {
+    fn build_file_index(&self, repo: &Repository, ids: &IdTable) -> Vec<FileIndexEntry> {
         let mut files: Vec<_> = repo
             .files
             .iter()
@@ -333,10 +391,11 @@ impl RepoMapGenerator {
                 };
 
                 FileIndexEntry {
+                    id: ids.file(&f.relative_path).unwrap_or_default().to_owned(),
                     path: f.relative_path.clone(),
                     tokens: f.token_count.get(self.model),
                     importance: importance.to_owned(),
-                    summary: None,
+                    summary: f.summary.clone(),
                 }
             })
             .collect();
@@ -387,13 +446,22 @@ impl RepoMapGenerator {
         )
     }
 
-    fn estimate_tokens(&self, symbols: &[RankedSymbol], files: &[FileIndexEntry]) -> u32 {
-        // Rough estimate: ~25 tokens per symbol entry, ~10 per file entry
+    fn estimate_tokens(
+        &self,
+        symbols: &[RankedSymbol],
+        files: &[FileIndexEntry],
+        external_usage: &[ExternalUsage],
+    ) -> u32 {
+        // Rough estimate: ~25 tokens per symbol entry, ~10 per file entry or importer
         let symbol_tokens = symbols.len() as u32 * 25;
         let file_tokens = files.len() as u32 * 10;
+        let usage_tokens: u32 = external_usage
+            .iter()
+            .map(|u| 5 + u.files.len() as u32 * 10)
+            .sum();
         let overhead = 100; // Headers, summary, etc.
 
-        symbol_tokens + file_tokens + overhead
+        symbol_tokens + file_tokens + usage_tokens + overhead
     }
 }
 
@@ -431,7 +499,7 @@ mod tests {
                     parent: None,
                 }],
                 importance: 0.9,
-                content: None,
+                ..Default::default()
             }],
             metadata: RepoMetadata {
                 total_files: 1,
@@ -455,6 +523,8 @@ mod tests {
                 commit: None,
                 directory_structure: None,
                 external_dependencies: vec![],
+                commands: Vec::new(),
+                topics: Vec::new(),
                 git_history: None,
             },
         }
@@ -468,5 +538,73 @@ mod tests {
 
         assert!(!map.summary.is_empty());
         assert!(!map.file_index.is_empty());
+
+        // Symbols and files carry the IDs the rest of the pack uses
+        let ids = IdTable::new(&repo);
+        let entry = &map.file_index[0];
+        assert_eq!(Some(entry.id.as_str()), ids.file(&entry.path));
+        let symbol = &map.key_symbols[0];
+        assert_eq!(symbol.id.as_deref(), ids.symbol(&symbol.file, &symbol.name, symbol.line));
+        assert!(symbol.id.is_some());
+    }
+
+    #[test]
+    fn test_repomap_json_round_trip() {
+        let map = RepoMapGenerator::new(2000).generate(&create_test_repo());
+        let json = serde_json::to_string(&map).unwrap();
+        let loaded: RepoMap = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(loaded.summary, map.summary);
+        assert_eq!(loaded.token_count, map.token_count);
+        assert_eq!(loaded.key_symbols[0].id, map.key_symbols[0].id);
+        assert_eq!(loaded.file_index.len(), map.file_index.len());
+    }
+
+    #[test]
+    fn test_try_generate_cancelled() {
+        let repo = create_test_repo();
+        let cancel = CancellationToken::new();
+        cancel.cancel();
+        assert_eq!(
+            RepoMapGenerator::new(2000)
+                .try_generate(&repo, &cancel)
+                .err(),
+            Some(Cancelled)
+        );
+    }
+
+    #[test]
+    fn test_focus_boosts_focused_files() {
+        let mut repo = create_test_repo();
+        let mut other = repo.files[0].clone();
+        other.relative_path = "lib/helpers.py".to_string();
+        other.symbols[0].name = "helper".to_string();
+        repo.files.push(other);
+
+        let top =
+            |generator: RepoMapGenerator| generator.generate(&repo).key_symbols[0].file.clone();
+        assert_eq!(top(RepoMapGenerator::new(2000).with_focus(["lib/"])), "lib/helpers.py");
+        assert_eq!(top(RepoMapGenerator::new(2000).with_focus(["./src"])), "src/main.py");
+
+        let generator = RepoMapGenerator::new(2000).with_focus(["src"]);
+        assert!(generator.in_focus("src/main.py"));
+        assert!(!generator.in_focus("src2/main.py"));
+    }
+
+    #[test]
+    fn test_external_usage_lists_importers() {
+        let mut repo = create_test_repo();
+        let import = |name: &str| Symbol::new(name, SymbolKind::Import);
+        repo.files[0].symbols.push(import("import requests"));
+        let mut worker = repo.files[0].clone();
+        worker.relative_path = "sync/worker.py".to_string();
+        worker.symbols.push(import("import numpy"));
+        repo.files.push(worker);
+
+        let map = RepoMapGenerator::new(2000).generate(&repo);
+        assert_eq!(map.external_usage[0].package, "requests");
+        assert_eq!(map.external_usage[0].files, vec!["src/main.py", "sync/worker.py"]);
+        assert_eq!(map.external_usage[1].package, "numpy");
+        assert_eq!(map.external_usage[1].files, vec!["sync/worker.py"]);
     }
 }
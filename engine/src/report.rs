@@ -0,0 +1,385 @@
+//! Deterministic onboarding reports
+//!
+//! Builds a human-readable Markdown overview of a repository (modules, key
+//! symbols, dependency highlights, entry points and build commands) purely
+//! from the scanner, repo map and dependency analyses. No LLM is involved and
+//! the same input always yields the same report, so it can be committed.
+
+use crate::commands::detect_commands;
+use crate::dependencies::DependencyGraph;
+use crate::entry_points::{code_entry_point, manifest_entry_points};
+use crate::repomap::RepoMap;
+use crate::types::{RepoFile, Repository, SymbolKind};
+use std::fmt::Write;
+
+/// File names that conventionally hold a program or library entry point
+const ENTRY_POINT_FILES: &[&str] = &[
+    "main.rs",
+    "lib.rs",
+    "main.go",
+    "main.py",
+    "__main__.py",
+    "app.py",
+    "main.ts",
+    "main.js",
+    "index.ts",
+    "index.js",
+    "index.tsx",
+    "index.jsx",
+    "app.ts",
+    "app.js",
+    "server.ts",
+    "server.js",
+    "server.py",
+    "server.go",
+    "Main.java",
+    "Application.java",
+];
+
+/// Generator for Markdown onboarding reports
+pub struct ReportGenerator {
+    /// Maximum number of key symbols to describe
+    max_symbols: usize,
+    /// Maximum number of entries in each dependency list
+    max_dependencies: usize,
+}
+
+impl Default for ReportGenerator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ReportGenerator {
+    /// Create a generator with default limits
+    pub fn new() -> Self {
+        Self { max_symbols: 25, max_dependencies: 10 }
+    }
+
+    /// Set maximum number of key symbols to describe
+    pub fn with_max_symbols(mut self, max: usize) -> Self {
+        self.max_symbols = max;
+        self
+    }
+
+    /// Set maximum number of entries in each dependency list
+    pub fn with_max_dependencies(mut self, max: usize) -> Self {
+        self.max_dependencies = max;
+        self
+    }
+
+    /// Generate the onboarding report as Markdown
+    pub fn generate(&self, repo: &Repository, map: &RepoMap) -> String {
+        let deps = DependencyGraph::build(repo);
+        let mut out = String::new();
+
+        writeln!(out, "# {} — Onboarding Report", repo.name).unwrap();
+        writeln!(out).unwrap();
+        writeln!(out, "_Generated by Infiniloom from static analysis of the repository._").unwrap();
+        writeln!(out).unwrap();
+
+        self.write_overview(&mut out, repo);
+        self.write_modules(&mut out, map);
+        self.write_entry_points(&mut out, repo);
+        self.write_build_commands(&mut out, repo);
+        self.write_key_symbols(&mut out, repo, map);
+        self.write_dependencies(&mut out, repo, &deps);
+
+        out
+    }
+
+    fn write_overview(&self, out: &mut String, repo: &Repository) {
+        writeln!(out, "## Overview").unwrap();
+        writeln!(out).unwrap();
+        writeln!(out, "- **Files**: {}", repo.metadata.total_files).unwrap();
+        writeln!(out, "- **Lines**: {}", repo.metadata.total_lines).unwrap();
+        if let Some(framework) = &repo.metadata.framework {
+            writeln!(out, "- **Framework**: {}", framework).unwrap();
+        }
+        if let Some(description) = &repo.metadata.description {
+            writeln!(out, "- **Description**: {}", description).unwrap();
+        }
+        writeln!(out).unwrap();
+
+        let mut languages = repo.metadata.languages.clone();
+        languages.sort_by(|a, b| {
+            b.files
+                .cmp(&a.files)
+                .then_with(|| a.language.cmp(&b.language))
+        });
+        if !languages.is_empty() {
+            writeln!(out, "| Language | Files | Share |").unwrap();
+            writeln!(out, "|----------|-------|-------|").unwrap();
+            for lang in &languages {
+                writeln!(out, "| {} | {} | {:.1}% |", lang.language, lang.files, lang.percentage)
+                    .unwrap();
+            }
+            writeln!(out).unwrap();
+        }
+    }
+
+    fn write_modules(&self, out: &mut String, map: &RepoMap) {
+        let mut modules = map.module_graph.nodes.clone();
+        if modules.is_empty() {
+            return;
+        }
+        modules.sort_by(|a, b| b.tokens.cmp(&a.tokens).then_with(|| a.name.cmp(&b.name)));
+
+        writeln!(out, "## Modules").unwrap();
+        writeln!(out).unwrap();
+        writeln!(out, "| Module | Files | Tokens |").unwrap();
+        writeln!(out, "|--------|-------|--------|").unwrap();
+        for module in &modules {
+            writeln!(out, "| `{}` | {} | {} |", module.name, module.files, module.tokens).unwrap();
+        }
+        writeln!(out).unwrap();
+    }
+
+    fn write_entry_points(&self, out: &mut String, repo: &Repository) {
+        let entry_points = find_entry_points(repo);
+        if entry_points.is_empty() {
+            return;
+        }
+
+        writeln!(out, "## Entry Points").unwrap();
+        writeln!(out).unwrap();
+        for path in entry_points {
+            writeln!(out, "- `{}`", path).unwrap();
+        }
+        writeln!(out).unwrap();
+    }
+
+    fn write_build_commands(&self, out: &mut String, repo: &Repository) {
+        let commands = detect_commands(&repo.path);
+        if commands.is_empty() {
+            return;
+        }
+
+        writeln!(out, "## Build & Run").unwrap();
+        writeln!(out).unwrap();
+        writeln!(out, "| Command | Kind | Source |").unwrap();
+        writeln!(out, "|---------|------|--------|").unwrap();
+        for cmd in &commands {
+            writeln!(
+                out,
+                "| `{}` | {} | `{}` |",
+                cmd.command.replace('|', "\\|"),
+                cmd.kind.name(),
+                cmd.source.replace('|', "\\|")
+            )
+            .unwrap();
+        }
+        writeln!(out).unwrap();
+    }
+
+    fn write_key_symbols(&self, out: &mut String, repo: &Repository, map: &RepoMap) {
+        let symbols: Vec<_> = map
+            .key_symbols
+            .iter()
+            .filter(|s| s.kind != SymbolKind::Import.name())
+            .take(self.max_symbols)
+            .collect();
+        if symbols.is_empty() {
+            return;
+        }
+
+        writeln!(out, "## Key Symbols").unwrap();
+        writeln!(out).unwrap();
+        for sym in symbols {
+            writeln!(out, "### `{}` ({})", sym.name, sym.kind).unwrap();
+            writeln!(out).unwrap();
+            writeln!(out, "Defined in `{}:{}`", sym.file, sym.line).unwrap();
+            if let Some(sig) = &sym.signature {
+                writeln!(out).unwrap();
+                writeln!(out, "```").unwrap();
+                writeln!(out, "{}", sig.trim()).unwrap();
+                writeln!(out, "```").unwrap();
+            }
+            if let Some(doc) = find_docstring(repo, &sym.file, &sym.name, sym.line) {
+                writeln!(out).unwrap();
+                for line in doc.lines() {
+                    writeln!(out, "> {}", line.trim()).unwrap();
+                }
+            }
+            writeln!(out).unwrap();
+        }
+    }
+
+    fn write_dependencies(&self, out: &mut String, repo: &Repository, deps: &DependencyGraph) {
+        let mut most_imported: Vec<(&str, usize)> = repo
+            .files
+            .iter()
+            .map(|f| (f.relative_path.as_str(), deps.get_importers(&f.relative_path).len()))
+            .filter(|(_, count)| *count > 0)
+            .collect();
+        most_imported.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+        most_imported.truncate(self.max_dependencies);
+
+        let mut external: Vec<&String> = deps.get_external_deps().iter().collect();
+        external.sort();
+
+        let cycles = deps.get_circular_deps();
+
+        if most_imported.is_empty() && external.is_empty() && cycles.is_empty() {
+            return;
+        }
+
+        writeln!(out, "## Dependencies").unwrap();
+        writeln!(out).unwrap();
+
+        if !most_imported.is_empty() {
+            writeln!(out, "Most imported files:").unwrap();
+            writeln!(out).unwrap();
+            for (path, count) in &most_imported {
+                writeln!(out, "- `{}` (imported by {} files)", path, count).unwrap();
+            }
+            writeln!(out).unwrap();
+        }
+
+        if !external.is_empty() {
+            writeln!(out, "External packages ({}):", external.len()).unwrap();
+            writeln!(out).unwrap();
+            for dep in external.iter().take(self.max_dependencies) {
+                writeln!(out, "- `{}`", dep).unwrap();
+            }
+            if external.len() > self.max_dependencies {
+                writeln!(out, "- … and {} more", external.len() - self.max_dependencies).unwrap();
+            }
+            writeln!(out).unwrap();
+        }
+
+        if !cycles.is_empty() {
+            writeln!(out, "Circular dependencies:").unwrap();
+            writeln!(out).unwrap();
+            let mut cycles: Vec<Vec<String>> = cycles.to_vec();
+            for cycle in &mut cycles {
+                cycle.sort();
+            }
+            cycles.sort();
+            for cycle in &cycles {
+                writeln!(out, "- {}", cycle.join(" ↔ ")).unwrap();
+            }
+            writeln!(out).unwrap();
+        }
+    }
+}
+
+/// Find files that look like program or library entry points, or that
+/// manifests declare as such
+pub fn find_entry_points(repo: &Repository) -> Vec<String> {
+    let mut entries: Vec<String> = repo
+        .files
+        .iter()
+        .filter(|f| is_entry_point(f))
+        .map(|f| f.relative_path.clone())
+        .chain(manifest_entry_points(repo).into_iter().map(|e| e.path))
+        .collect();
+    entries.sort();
+    entries.dedup();
+    entries
+}
+
+fn is_entry_point(file: &RepoFile) -> bool {
+    let filename = file.relative_path.rsplit('/').next().unwrap_or("");
+    ENTRY_POINT_FILES.contains(&filename) || code_entry_point(file).is_some()
+}
+
+/// Look up the docstring for a ranked symbol in the scanned files
+fn find_docstring<'a>(repo: &'a Repository, file: &str, name: &str, line: u32) -> Option<&'a str> {
+    repo.files
+        .iter()
+        .find(|f| f.relative_path == file)?
+        .symbols
+        .iter()
+        .find(|s| s.name == name && s.start_line == line)?
+        .docstring
+        .as_deref()
+        .map(str::trim)
+        .filter(|d| !d.is_empty())
+}
+
+#[cfg(test)]
+#[allow(clippy::str_to_string)]
+mod tests {
+    use super::*;
+    use crate::repomap::RepoMapGenerator;
+    use crate::types::{LanguageStats, RepoMetadata, Symbol};
+    use std::path::PathBuf;
+
+    fn file(path: &str, content: &str, symbols: Vec<Symbol>) -> RepoFile {
+        RepoFile {
+            path: PathBuf::from("/tmp/test").join(path),
+            relative_path: path.to_string(),
+            language: Some("rust".to_string()),
+            size_bytes: content.len() as u64,
+            symbols,
+            importance: 0.5,
+            content: Some(content.to_string()),
+            ..Default::default()
+        }
+    }
+
+    fn create_test_repo() -> Repository {
+        let mut main = Symbol::new("main", SymbolKind::Function);
+        main.start_line = 1;
+        main.docstring = Some("Program entry point".to_string());
+        main.signature = Some("fn main()".to_string());
+
+        Repository {
+            name: "demo".to_string(),
+            path: PathBuf::from("/tmp/test"),
+            files: vec![
+                file("src/main.rs", "fn main() {}", vec![main]),
+                file("src/util.rs", "pub fn helper() {}", vec![]),
+                file("Cargo.toml", "[package]\nname = \"demo\"", vec![]),
+                file(
+                    "Makefile",
+                    "CC := gcc\n.PHONY: all\nall: build\nbuild:\n\tcargo build\n",
+                    vec![],
+                ),
+            ],
+            metadata: RepoMetadata {
+                total_files: 4,
+                total_lines: 8,
+                languages: vec![LanguageStats {
+                    language: "rust".to_string(),
+                    files: 2,
+                    lines: 2,
+                    percentage: 50.0,
+                }],
+                ..Default::default()
+            },
+        }
+    }
+
+    #[test]
+    fn test_report_sections() {
+        let dir = tempfile::TempDir::new().unwrap();
+        std::fs::create_dir(dir.path().join("src")).unwrap();
+        std::fs::write(dir.path().join("Cargo.toml"), "[package]\nname = \"demo\"").unwrap();
+        std::fs::write(dir.path().join("src/main.rs"), "fn main() {}").unwrap();
+        std::fs::write(dir.path().join("package.json"), r#"{"scripts": {"a|b": "true"}}"#).unwrap();
+        let mut repo = create_test_repo();
+        repo.path = dir.path().to_path_buf();
+        let map = RepoMapGenerator::new(1000).generate(&repo);
+        let report = ReportGenerator::new().generate(&repo, &map);
+
+        assert!(report.starts_with("# demo — Onboarding Report"));
+        assert!(report.contains("## Overview"));
+        assert!(report.contains("## Modules"));
+        assert!(report.contains("## Entry Points"));
+        assert!(report.contains("- `src/main.rs`"));
+        assert!(report.contains("| `cargo run` | run | `Cargo.toml` |"));
+        assert!(report.contains("| `npm run a\\|b` | other | `package.json` |"));
+        assert!(report.contains("### `main` (function)"));
+        assert!(report.contains("> Program entry point"));
+    }
+
+    #[test]
+    fn test_report_is_deterministic() {
+        let repo = create_test_repo();
+        let map = RepoMapGenerator::new(1000).generate(&repo);
+        let generator = ReportGenerator::new();
+        assert_eq!(generator.generate(&repo, &map), generator.generate(&repo, &map));
+    }
+}
@@ -0,0 +1,486 @@
+//! Adaptive sampling for repositories that exceed any feasible budget
+//!
+//! When even extreme compression cannot fit a repository, a [`Sampler`] turns
+//! the pack into an approximation with three tiers, taken in rank order:
+//!
+//! 1. **Full**: the top-ranked files with their complete content
+//! 2. **Signatures**: the next files reduced to their symbol signatures
+//! 3. **Samples**: for every module (top-level directory), the files closest
+//!    to the module's median size, so each part of the codebase is represented
+//!
+//! Everything else is left out, or with [`Sampler::with_outlines`] reduced to
+//! an outline of its symbols while budget remains. The returned
+//! [`SamplingReport`] says which files landed in which tier and how much of
+//! each module was covered, so the reader of the pack knows what it is
+//! looking at.
+
+use crate::output::{Locale, Section};
+use crate::tokenizer::Tokenizer;
+use crate::types::{RepoFile, Repository, TokenCounts, TokenizerModel};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+
+/// Module name used for files at the repository root
+const ROOT_MODULE: &str = ".";
+
+/// Splits a token budget between full files, signatures and module samples
+#[derive(Debug, Clone)]
+pub struct Sampler {
+    budget: u32,
+    model: TokenizerModel,
+    full_share: f32,
+    signature_share: f32,
+    outlines: bool,
+}
+
+impl Sampler {
+    /// Create a sampler for a content budget in tokens of `model`
+    pub fn new(budget: u32, model: TokenizerModel) -> Self {
+        Self { budget, model, full_share: 0.5, signature_share: 0.25, outlines: false }
+    }
+
+    /// Fraction of the budget for files with full content (default 0.5)
+    pub fn with_full_share(mut self, share: f32) -> Self {
+        self.full_share = share.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Fraction of the budget for signature-only files (default 0.25); the
+    /// rest goes to module samples
+    pub fn with_signature_share(mut self, share: f32) -> Self {
+        self.signature_share = share.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Keep an outline (symbols with line ranges) of files that would be left
+    /// out, in rank order, as long as the unspent budget covers it
+    pub fn with_outlines(mut self, outlines: bool) -> Self {
+        self.outlines = outlines;
+        self
+    }
+
+    /// Reduce `repo` to the sampled files, keeping their rank order
+    ///
+    /// `repo.files` must already be sorted by rank. If everything fits the
+    /// budget the repository is left as it is.
+    pub fn sample(&self, repo: &mut Repository) -> SamplingReport {
+        let mut report = SamplingReport { budget: self.budget, ..Default::default() };
+        let total: u64 = repo
+            .files
+            .iter()
+            .map(|f| f.token_count.get(self.model) as u64)
+            .sum();
+        if total <= self.budget as u64 {
+            report.full = repo.files.iter().map(|f| f.relative_path.clone()).collect();
+            return report;
+        }
+
+        let full_budget = (self.budget as f32 * self.full_share) as u32;
+        let signature_budget = (self.budget as f32 * self.signature_share) as u32;
+        let sample_budget = self.budget.saturating_sub(full_budget + signature_budget);
+
+        let mut tiers: Vec<Option<Tier>> = vec![None; repo.files.len()];
+
+        // Tier 1: full content, greedily in rank order
+        let mut used = 0u32;
+        for (i, file) in repo.files.iter().enumerate() {
+            let tokens = file.token_count.get(self.model);
+            if used + tokens <= full_budget {
+                used += tokens;
+                tiers[i] = Some(Tier::Full);
+            }
+        }
+
+        // Tier 2: signatures of the next files that have symbols
+        let tokenizer = Tokenizer::estimation_only();
+        let mut signatures: HashMap<usize, (String, TokenCounts)> = HashMap::new();
+        let mut used = 0u32;
+        for (i, file) in repo.files.iter().enumerate() {
+            if tiers[i].is_some() {
+                continue;
+            }
+            let Some(text) = signature_text(file) else {
+                continue;
+            };
+            let counts: TokenCounts = tokenizer.count_all(&text).into();
+            let tokens = counts.get(self.model);
+            if used + tokens <= signature_budget {
+                used += tokens;
+                tiers[i] = Some(Tier::Signatures);
+                signatures.insert(i, (text, counts));
+            }
+        }
+
+        // Tier 3: representative files per module from what is left
+        let mut modules: BTreeMap<String, Vec<usize>> = BTreeMap::new();
+        for (i, file) in repo.files.iter().enumerate() {
+            if tiers[i].is_none() {
+                modules
+                    .entry(module_of(&file.relative_path).to_owned())
+                    .or_default()
+                    .push(i);
+            }
+        }
+        let pool_tokens: u64 = modules
+            .values()
+            .flatten()
+            .map(|&i| repo.files[i].token_count.get(self.model) as u64)
+            .sum();
+        for (module, members) in &modules {
+            let tokens: u64 = members
+                .iter()
+                .map(|&i| repo.files[i].token_count.get(self.model) as u64)
+                .sum();
+            let share = if pool_tokens > 0 {
+                tokens as f64 / pool_tokens as f64
+            } else {
+                0.0
+            };
+            let module_budget = (sample_budget as f64 * share) as u32;
+
+            let mut sampled = Vec::new();
+            let mut used = 0u32;
+            for i in by_distance_from_median(members, &repo.files, self.model) {
+                let file_tokens = repo.files[i].token_count.get(self.model);
+                if used + file_tokens <= module_budget {
+                    used += file_tokens;
+                    tiers[i] = Some(Tier::Sample);
+                    sampled.push(repo.files[i].relative_path.clone());
+                }
+            }
+            report.modules.push(ModuleSample {
+                module: module.clone(),
+                files: members.len(),
+                tokens,
+                sampled,
+            });
+        }
+
+        // Tier 4: outlines of the rest from whatever the other tiers left over
+        let mut outlines: HashMap<usize, TokenCounts> = HashMap::new();
+        if self.outlines {
+            let spent: u32 = tiers
+                .iter()
+                .enumerate()
+                .map(|(i, tier)| match tier {
+                    Some(Tier::Signatures) => signatures[&i].1.get(self.model),
+                    Some(_) => repo.files[i].token_count.get(self.model),
+                    None => 0,
+                })
+                .sum();
+            let mut left = self.budget.saturating_sub(spent);
+            for (i, file) in repo.files.iter().enumerate() {
+                let outline = file.outline();
+                if tiers[i].is_some() || outline.is_empty() {
+                    continue;
+                }
+                let counts: TokenCounts = tokenizer.count_all(&outline.join("\n")).into();
+                let tokens = counts.get(self.model);
+                if tokens <= left {
+                    left -= tokens;
+                    tiers[i] = Some(Tier::Outline);
+                    outlines.insert(i, counts);
+                }
+            }
+        }
+
+        // Apply the tiers, keeping rank order
+        let files = std::mem::take(&mut repo.files);
+        for (i, mut file) in files.into_iter().enumerate() {
+            match tiers[i] {
+                Some(Tier::Full) => report.full.push(file.relative_path.clone()),
+                Some(Tier::Signatures) => {
+                    let (text, counts) = signatures.remove(&i).unwrap_or_default();
+                    file.token_count = counts;
+                    file.content = Some(text);
+                    file.line_numbers = None;
+                    report.signatures.push(file.relative_path.clone());
+                },
+                Some(Tier::Sample) => {},
+                Some(Tier::Outline) => {
+                    file.reduce_to_outline();
+                    file.token_count = outlines.remove(&i).unwrap_or_default();
+                    report.outlines.push(file.relative_path.clone());
+                },
+                None => {
+                    report.omitted += 1;
+                    continue;
+                },
+            }
+            repo.files.push(file);
+        }
+
+        report
+    }
+}
+
+/// How a sampled file is included
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Tier {
+    Full,
+    Signatures,
+    Sample,
+    Outline,
+}
+
+/// Coverage of one module by the sampled tier
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct ModuleSample {
+    /// Top-level directory, or "." for files at the root
+    pub module: String,
+    /// Files of the module that were left for sampling
+    pub files: usize,
+    /// Their tokens
+    pub tokens: u64,
+    /// Files included as representatives
+    pub sampled: Vec<String>,
+}
+
+/// What a [`Sampler`] kept, for appending to the pack
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct SamplingReport {
+    /// Content budget in tokens
+    pub budget: u32,
+    /// Files with full content
+    pub full: Vec<String>,
+    /// Files reduced to signatures
+    pub signatures: Vec<String>,
+    /// Per-module samples of the remaining files
+    pub modules: Vec<ModuleSample>,
+    /// Files reduced to an outline of their symbols
+    pub outlines: Vec<String>,
+    /// Files left out entirely
+    pub omitted: usize,
+}
+
+impl SamplingReport {
+    /// Whether any file was reduced or left out
+    pub fn is_sampled(&self) -> bool {
+        !self.signatures.is_empty() || !self.modules.is_empty()
+    }
+
+    /// Number of representative files across all modules
+    pub fn sampled_files(&self) -> usize {
+        self.modules.iter().map(|m| m.sampled.len()).sum()
+    }
+
+    /// Explanation of the sample to append to the pack
+    pub fn render(&self) -> String {
+        self.render_localized(Locale::default())
+    }
+
+    /// The explanation as a report section, `None` if nothing was sampled
+    pub fn section(&self, locale: Locale) -> Option<Section> {
+        self.is_sampled()
+            .then(|| Section::new("sampling", self.render_localized(locale), self))
+    }
+
+    /// [`SamplingReport::render`] with the explanation in `locale`
+    pub fn render_localized(&self, locale: Locale) -> String {
+        if !self.is_sampled() {
+            return String::new();
+        }
+
+        let text = locale.strings();
+        let mut out = format!("\n\n<!-- {} -->\n", text.sampling_title);
+        out.push_str(
+            &text
+                .sampling_intro
+                .replace("{budget}", &self.budget.to_string()),
+        );
+        out.push_str("\n\n");
+        let mut lines = vec![
+            text.sampled_full
+                .replace("{count}", &self.full.len().to_string()),
+            text.sampled_signatures
+                .replace("{count}", &self.signatures.len().to_string()),
+            text.sampled_representative
+                .replace("{count}", &self.sampled_files().to_string())
+                .replace("{modules}", &self.modules.len().to_string()),
+        ];
+        if !self.outlines.is_empty() {
+            lines.push(
+                text.sampled_outlines
+                    .replace("{count}", &self.outlines.len().to_string()),
+            );
+        }
+        lines.push(
+            text.sampled_omitted
+                .replace("{count}", &self.omitted.to_string()),
+        );
+        for line in lines {
+            out.push_str(&format!("- {}\n", line));
+        }
+
+        if !self.modules.is_empty() {
+            out.push_str(&format!(
+                "\n| {} | {} | {} | {} |\n",
+                text.module, text.remaining_files, text.sampled, text.sampled_files
+            ));
+            out.push_str("|--------|-----------------|---------|---------------|\n");
+            for module in &self.modules {
+                out.push_str(&format!(
+                    "| {} | {} | {} | {} |\n",
+                    module.module,
+                    module.files,
+                    module.sampled.len(),
+                    module.sampled.join(", ")
+                ));
+            }
+        }
+        out
+    }
+}
+
+/// One line per symbol: its signature, or kind and name when it has none
+fn signature_text(file: &RepoFile) -> Option<String> {
+    if file.symbols.is_empty() {
+        return None;
+    }
+    let mut text = String::new();
+    for symbol in &file.symbols {
+        match &symbol.signature {
+            Some(signature) => text.push_str(signature.trim()),
+            None => {
+                text.push_str(symbol.kind.name());
+                text.push(' ');
+                text.push_str(&symbol.name);
+            },
+        }
+        text.push('\n');
+    }
+    Some(text)
+}
+
+/// Top-level directory of a relative path
+fn module_of(path: &str) -> &str {
+    match path.split_once('/') {
+        Some((module, _)) => module,
+        None => ROOT_MODULE,
+    }
+}
+
+/// Indices ordered by how close each file's size is to the median of the group
+fn by_distance_from_median(
+    members: &[usize],
+    files: &[RepoFile],
+    model: TokenizerModel,
+) -> Vec<usize> {
+    let mut sizes: Vec<u32> = members
+        .iter()
+        .map(|&i| files[i].token_count.get(model))
+        .collect();
+    sizes.sort_unstable();
+    let median = sizes.get(sizes.len() / 2).copied().unwrap_or(0);
+
+    let mut ordered = members.to_vec();
+    ordered.sort_by_key(|&i| (files[i].token_count.get(model).abs_diff(median), i));
+    ordered
+}
+
+#[cfg(test)]
+#[allow(clippy::str_to_string)]
+mod tests {
+    use super::*;
+    use crate::types::{Symbol, SymbolKind};
+
+    fn file(path: &str, tokens: u32, symbols: &[&str]) -> RepoFile {
+        let mut file = RepoFile::new(format!("/repo/{}", path), path);
+        file.token_count = TokenCounts {
+            claude: tokens,
+            gpt4o: tokens,
+            gpt4: tokens,
+            gemini: tokens,
+            llama: tokens,
+        };
+        file.content = Some("x".repeat(tokens as usize * 4));
+        file.symbols = symbols
+            .iter()
+            .map(|name| {
+                let mut symbol = Symbol::new(*name, SymbolKind::Function);
+                symbol.signature = Some(format!("fn {}()", name));
+                symbol
+            })
+            .collect();
+        file
+    }
+
+    #[test]
+    fn test_small_repo_is_untouched() {
+        let mut repo = Repository::new("test", "/repo");
+        repo.files = vec![file("src/main.rs", 100, &[]), file("src/lib.rs", 100, &[])];
+
+        let report = Sampler::new(1000, TokenizerModel::Claude).sample(&mut repo);
+        assert!(!report.is_sampled());
+        assert_eq!(repo.files.len(), 2);
+        assert!(report.render().is_empty());
+    }
+
+    #[test]
+    fn test_tiers() {
+        let mut repo = Repository::new("test", "/repo");
+        repo.files = vec![
+            file("src/main.rs", 400, &["main"]),
+            file("src/big.rs", 5000, &["alpha", "beta"]),
+            file("src/other.rs", 300, &[]),
+            file("docs/a.md", 150, &[]),
+            file("docs/b.md", 120, &[]),
+            file("docs/c.md", 2000, &[]),
+        ];
+
+        let report = Sampler::new(1000, TokenizerModel::Claude).sample(&mut repo);
+        assert!(report.is_sampled());
+        assert_eq!(report.full, vec!["src/main.rs"]);
+        assert_eq!(report.signatures, vec!["src/big.rs"]);
+
+        let big = repo
+            .files
+            .iter()
+            .find(|f| f.relative_path == "src/big.rs")
+            .unwrap();
+        assert_eq!(big.content.as_deref(), Some("fn alpha()\nfn beta()\n"));
+
+        let docs = report.modules.iter().find(|m| m.module == "docs").unwrap();
+        assert_eq!(docs.files, 3);
+        assert!(!docs.sampled.contains(&"docs/c.md".to_string()));
+
+        // Rank order is preserved and omitted files are counted
+        assert_eq!(repo.files[0].relative_path, "src/main.rs");
+        assert_eq!(report.omitted + repo.files.len(), 6);
+        assert!(report.render().contains("| docs | 3 |"));
+
+        let section = report.section(Locale::En).unwrap();
+        assert_eq!(section.data["full"][0], "src/main.rs");
+        assert_eq!(section.data["modules"][0]["module"], "docs");
+    }
+
+    #[test]
+    fn test_outlines_fill_leftover_budget() {
+        let mut repo = Repository::new("test", "/repo");
+        repo.files = vec![
+            file("src/main.rs", 400, &["main"]),
+            file("src/big.rs", 5000, &["alpha", "beta"]),
+            file("src/huge.rs", 9000, &["gamma"]),
+            file("src/plain.rs", 3000, &[]),
+        ];
+
+        let report = Sampler::new(1000, TokenizerModel::Claude)
+            .with_signature_share(0.0)
+            .with_outlines(true)
+            .sample(&mut repo);
+        assert_eq!(report.outlines, vec!["src/big.rs", "src/huge.rs"]);
+
+        let huge = repo
+            .files
+            .iter()
+            .find(|f| f.relative_path == "src/huge.rs")
+            .unwrap();
+        assert!(huge.outline_only);
+        assert!(huge.content.is_none());
+        assert_eq!(huge.outline(), vec!["0-0 fn gamma()"]);
+
+        // Files without symbols have no outline and are still left out
+        assert!(!repo.files.iter().any(|f| f.relative_path == "src/plain.rs"));
+        assert!(report.render().contains("Outlined: 2 files"));
+    }
+}
@@ -0,0 +1,596 @@
+//! High-level repository scanning
+//!
+//! [`Repository::scan`] walks a directory, reads and token-counts text files,
+//! extracts symbols and ranks files, producing a [`Repository`] ready for
+//! [`RepoMapGenerator`](crate::RepoMapGenerator) and the output formatters.
+//! [`Repository::scan_revision`] does the same for a past revision, reading
+//! from git's object store, [`Repository::scan_archive`] for a tar or zip
+//! archive held in memory and [`Repository::scan_files`] for loose files held
+//! in memory.
+//!
+//! ```
+//! use infiniloom_engine::{Repository, ScanOptions};
+//! # let dir = tempfile::tempdir()?;
+//! # std::fs::write(dir.path().join("app.py"), "def run():\n    pass\n")?;
+//! # let path = dir.path();
+//!
+//! let repo = Repository::scan(path, ScanOptions::default().with_hidden(true))?;
+//! assert_eq!(repo.files[0].relative_path, "app.py");
+//! assert_eq!(repo.files[0].symbols[0].name, "run");
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use crate::archive::{read_archive, ArchiveError};
+use crate::cancel::{CancellationToken, Cancelled};
+use crate::config::{self, IGNORE_FILE};
+use crate::default_ignores::{is_default_ignored, HiddenPolicy};
+use crate::filter::{FileFilter, FilterStack};
+use crate::git::{GitError, GitRepo};
+use crate::mmap_scanner::{MmapScanner, ScannedFile};
+use crate::parser::{Language, Parser};
+use crate::ranking::{rank_files, sort_files_by_importance};
+use crate::types::{LanguageStats, RepoFile, Repository, TokenCounts};
+use ignore::WalkBuilder;
+use rayon::prelude::*;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+thread_local! {
+    static THREAD_PARSER: RefCell<Parser> = RefCell::new(Parser::new());
+}
+
+/// Scan errors
+#[derive(Debug, Error)]
+pub enum ScanError {
+    #[error("Not a directory: {0}")]
+    NotADirectory(PathBuf),
+
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("{0}")]
+    Git(#[from] GitError),
+
+    #[error("{0}")]
+    Archive(#[from] ArchiveError),
+
+    #[error("Scan cancelled")]
+    Cancelled(#[from] Cancelled),
+}
+
+/// Options for [`Repository::scan`]
+#[derive(Debug, Clone)]
+pub struct ScanOptions {
+    /// Include hidden files and directories
+    pub include_hidden: bool,
+    /// Hidden paths to include on top of
+    /// [`DEFAULT_HIDDEN_ALLOWLIST`](crate::default_ignores::DEFAULT_HIDDEN_ALLOWLIST)
+    pub hidden_allowlist: Vec<String>,
+    /// Respect `.gitignore` files
+    pub respect_gitignore: bool,
+    /// Skip dependency, build and generated paths (see `default_ignores`)
+    pub use_default_ignores: bool,
+    /// Skip files larger than this many bytes
+    pub max_file_size: u64,
+    /// Extract symbols with tree-sitter
+    pub extract_symbols: bool,
+    /// Rank and sort files by importance
+    pub rank: bool,
+    /// Files to keep, decided after ranking
+    pub filters: FilterStack,
+    /// Stops the scan between files once cancelled
+    pub cancel: CancellationToken,
+}
+
+impl Default for ScanOptions {
+    fn default() -> Self {
+        Self {
+            include_hidden: false,
+            hidden_allowlist: Vec::new(),
+            respect_gitignore: true,
+            use_default_ignores: true,
+            max_file_size: 50 * 1024 * 1024,
+            extract_symbols: true,
+            rank: true,
+            filters: FilterStack::new(),
+            cancel: CancellationToken::new(),
+        }
+    }
+}
+
+impl ScanOptions {
+    /// Include hidden files and directories
+    pub fn with_hidden(mut self, include_hidden: bool) -> Self {
+        self.include_hidden = include_hidden;
+        self
+    }
+
+    /// Include hidden paths matching `patterns`, as well as the default allowlist
+    pub fn with_hidden_allowlist<I, S>(mut self, patterns: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.hidden_allowlist = patterns.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Respect `.gitignore` files
+    pub fn with_gitignore(mut self, respect_gitignore: bool) -> Self {
+        self.respect_gitignore = respect_gitignore;
+        self
+    }
+
+    /// Skip dependency, build and generated paths
+    pub fn with_default_ignores(mut self, use_default_ignores: bool) -> Self {
+        self.use_default_ignores = use_default_ignores;
+        self
+    }
+
+    /// Skip files larger than `bytes`
+    pub fn with_max_file_size(mut self, bytes: u64) -> Self {
+        self.max_file_size = bytes;
+        self
+    }
+
+    /// Extract symbols with tree-sitter
+    pub fn with_symbols(mut self, extract_symbols: bool) -> Self {
+        self.extract_symbols = extract_symbols;
+        self
+    }
+
+    /// Rank and sort files by importance
+    pub fn with_ranking(mut self, rank: bool) -> Self {
+        self.rank = rank;
+        self
+    }
+
+    /// Keep only the files `filter` keeps, on top of earlier filters
+    ///
+    /// Filters see files after ranking, so importance reflects the whole
+    /// repository, and metadata counts only the files kept.
+    pub fn with_filter(mut self, filter: impl FileFilter + 'static) -> Self {
+        self.filters.push(filter);
+        self
+    }
+
+    /// Stop the scan with [`ScanError::Cancelled`] once `cancel` is cancelled
+    pub fn with_cancellation(mut self, cancel: CancellationToken) -> Self {
+        self.cancel = cancel;
+        self
+    }
+
+    /// Scan as the `scan` section of a config file says: hidden files,
+    /// `.gitignore`, default ignores, size limit, patterns and languages
+    pub fn with_config(self, config: &config::ScanConfig) -> Self {
+        self.with_hidden(config.include_hidden)
+            .with_hidden_allowlist(&config.hidden_allowlist)
+            .with_gitignore(config.respect_gitignore)
+            .with_default_ignores(config.default_ignores)
+            .with_max_file_size(config.max_file_size_bytes())
+            .with_filter(config.file_filter())
+    }
+}
+
+impl Repository {
+    /// Scan a repository directory
+    pub fn scan(path: impl AsRef<Path>, options: ScanOptions) -> Result<Self, ScanError> {
+        let root = path.as_ref().canonicalize()?;
+        if !root.is_dir() {
+            return Err(ScanError::NotADirectory(root));
+        }
+
+        let name = root
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "repository".to_owned());
+
+        let paths = collect_paths(&root, &options);
+        options.cancel.check()?;
+        let path_refs: Vec<&Path> = paths.iter().map(PathBuf::as_path).collect();
+        let scanner = MmapScanner::new().with_max_file_size(options.max_file_size);
+
+        let mut files: Vec<RepoFile> = scanner
+            .scan_files_parallel(&path_refs, &root)
+            .into_par_iter()
+            .map(|scanned| repo_file(scanned, &options))
+            .collect::<Result<_, _>>()?;
+        files.sort_by(|a, b| a.relative_path.cmp(&b.relative_path));
+
+        let mut repo = Repository::new(name, root);
+        repo.files = files;
+        finish(&mut repo, &options);
+
+        Ok(repo)
+    }
+
+    /// Scan the files under `paths` as they were at revision `rev`
+    ///
+    /// Contents come from git's object store, so the working tree is neither
+    /// read nor changed. Hidden and default-ignored paths are skipped as in
+    /// [`Repository::scan`]; `.gitignore` has nothing to do, since a revision
+    /// only holds tracked files. Empty `paths` means the whole tree.
+    pub fn scan_revision(
+        path: impl AsRef<Path>,
+        rev: &str,
+        paths: &[String],
+        options: ScanOptions,
+    ) -> Result<Self, ScanError> {
+        let root = path.as_ref().canonicalize()?;
+        let git = GitRepo::open(&root)?;
+        let commit = git.commit(rev)?;
+
+        let hidden =
+            HiddenPolicy::new(options.include_hidden).with_allowed(&options.hidden_allowlist);
+        // Sizes come from the tree, so oversized blobs are never read
+        let tracked: Vec<String> = git
+            .ls_tree(&commit.hash, paths)?
+            .into_iter()
+            .filter(|entry| entry.size <= options.max_file_size)
+            .map(|entry| entry.path)
+            .filter(|p| hidden.allows(Path::new(p), false))
+            .filter(|p| !options.use_default_ignores || !is_default_ignored(p))
+            .collect();
+
+        let scanner = MmapScanner::new().with_max_file_size(options.max_file_size);
+        let mut files: Vec<RepoFile> = git
+            .read_blobs(&commit.hash, tracked)?
+            .par_bridge()
+            .map(|blob| -> Result<_, ScanError> {
+                let (relative, content) = blob?;
+                match scanner.scan_bytes(&root.join(&relative), relative, content) {
+                    Some(scanned) => Ok(Some(repo_file(scanned, &options)?)),
+                    None => Ok(None),
+                }
+            })
+            .filter_map(Result::transpose)
+            .collect::<Result<_, _>>()?;
+        files.sort_by(|a, b| a.relative_path.cmp(&b.relative_path));
+
+        let name = root
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "repository".to_owned());
+        let mut repo = Repository::new(name, root);
+        repo.files = files;
+        repo.metadata.commit = Some(commit.hash);
+        finish(&mut repo, &options);
+
+        Ok(repo)
+    }
+
+    /// Scan the files of a tar, tar.gz or zip archive held in memory
+    ///
+    /// Nothing is written to disk. The repository is named after the
+    /// archive's top-level directory, or `name` if it has none, and file paths
+    /// are relative to that directory. Hidden and default-ignored paths are
+    /// skipped as in [`Repository::scan`]; `.gitignore` files in the archive
+    /// are not applied.
+    pub fn scan_archive(bytes: &[u8], name: &str, options: ScanOptions) -> Result<Self, ScanError> {
+        let archive = read_archive(bytes)?;
+        let name = archive.root.unwrap_or_else(|| name.to_owned());
+        let files = archive.entries.into_iter().map(|e| (e.path, e.content));
+        Self::scan_files(files, &name, options)
+    }
+
+    /// Scan files held in memory, given as `/`-separated relative path and
+    /// content
+    ///
+    /// For callers without a filesystem, such as WebAssembly. Hidden and
+    /// default-ignored paths are skipped as in [`Repository::scan`].
+    pub fn scan_files<I>(files: I, name: &str, options: ScanOptions) -> Result<Self, ScanError>
+    where
+        I: IntoIterator<Item = (String, Vec<u8>)>,
+    {
+        let root = PathBuf::from(name);
+
+        let hidden =
+            HiddenPolicy::new(options.include_hidden).with_allowed(&options.hidden_allowlist);
+        let scanner = MmapScanner::new().with_max_file_size(options.max_file_size);
+        let mut files: Vec<RepoFile> = files
+            .into_iter()
+            .collect::<Vec<_>>()
+            .into_par_iter()
+            .filter(|(path, _)| hidden.allows(Path::new(path), false))
+            .filter(|(path, _)| !options.use_default_ignores || !is_default_ignored(path))
+            .filter_map(|(path, content)| scanner.scan_bytes(&root.join(&path), path, content))
+            .map(|scanned| repo_file(scanned, &options))
+            .collect::<Result<_, _>>()?;
+        files.sort_by(|a, b| a.relative_path.cmp(&b.relative_path));
+
+        let mut repo = Repository::new(name, root);
+        repo.files = files;
+        finish(&mut repo, &options);
+
+        Ok(repo)
+    }
+
+    /// Recount file, line, token and language totals from the current files
+    ///
+    /// Call this after dropping files, e.g. with
+    /// [`retain_files`](Self::retain_files), so statistics describe what is
+    /// left. Lines are counted from content and are zero for files without.
+    pub fn refresh_metadata(&mut self) {
+        fill_metadata(self);
+    }
+}
+
+/// Rank, filter and total up the files of a scanned repository
+fn finish(repo: &mut Repository, options: &ScanOptions) {
+    if options.rank {
+        rank_files(repo);
+        sort_files_by_importance(repo);
+    }
+    repo.retain_files(&options.filters);
+    fill_metadata(repo);
+}
+
+/// A scanned file with its symbols, before ranking, unless the scan was cancelled
+fn repo_file(scanned: ScannedFile, options: &ScanOptions) -> Result<RepoFile, Cancelled> {
+    options.cancel.check()?;
+    let path = PathBuf::from(&scanned.path);
+    let symbols = match (&scanned.content, options.extract_symbols) {
+        (Some(content), true) => parse_symbols(content, &path),
+        _ => Vec::new(),
+    };
+
+    Ok(RepoFile {
+        path,
+        relative_path: scanned.relative_path.replace('\\', "/"),
+        language: scanned.language,
+        size_bytes: scanned.size_bytes,
+        token_count: scanned.token_counts.into(),
+        symbols,
+        importance: 0.5,
+        content: scanned.content,
+        ..Default::default()
+    })
+}
+
+fn collect_paths(root: &Path, options: &ScanOptions) -> Vec<PathBuf> {
+    let hidden = HiddenPolicy::new(options.include_hidden).with_allowed(&options.hidden_allowlist);
+    let walk_root = root.to_path_buf();
+    WalkBuilder::new(root)
+        .hidden(false)
+        .git_ignore(options.respect_gitignore)
+        .git_global(options.respect_gitignore)
+        .git_exclude(options.respect_gitignore)
+        .add_custom_ignore_filename(IGNORE_FILE)
+        .filter_entry(move |entry| {
+            entry.file_name() != ".git"
+                && hidden.allows(
+                    entry
+                        .path()
+                        .strip_prefix(&walk_root)
+                        .unwrap_or(entry.path()),
+                    entry.file_type().is_some_and(|t| t.is_dir()),
+                )
+        })
+        .build()
+        .filter_map(Result::ok)
+        .filter(|entry| entry.file_type().is_some_and(|t| t.is_file()))
+        .map(|entry| entry.into_path())
+        .filter(|path| {
+            if !options.use_default_ignores {
+                return true;
+            }
+            let relative = path.strip_prefix(root).unwrap_or(path);
+            !is_default_ignored(&relative.to_string_lossy().replace('\\', "/"))
+        })
+        .collect()
+}
+
+/// Parse symbols for files that have content but none extracted yet
+pub(crate) fn parse_missing_symbols(repo: &mut Repository) {
+    for file in &mut repo.files {
+        if file.symbols.is_empty() {
+            if let Some(content) = &file.content {
+                file.symbols = parse_symbols(content, &file.path);
+            }
+        }
+    }
+}
+
+/// Symbols of a file, by the language its extension maps to
+pub(crate) fn parse_symbols(content: &str, path: &Path) -> Vec<crate::types::Symbol> {
+    let Some(language) = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .and_then(Language::from_extension)
+    else {
+        return Vec::new();
+    };
+
+    THREAD_PARSER.with(|parser| {
+        parser
+            .borrow_mut()
+            .parse(content, language)
+            .unwrap_or_default()
+    })
+}
+
+pub(crate) fn fill_metadata(repo: &mut Repository) {
+    let mut languages: HashMap<String, (u32, u64)> = HashMap::new();
+    let mut total_lines = 0u64;
+    let mut total_tokens = TokenCounts::default();
+
+    for file in &repo.files {
+        let lines = file
+            .content
+            .as_ref()
+            .map_or(0, |c| c.lines().count() as u64);
+        total_lines += lines;
+        total_tokens.claude += file.token_count.claude;
+        total_tokens.gpt4o += file.token_count.gpt4o;
+        total_tokens.gpt4 += file.token_count.gpt4;
+        total_tokens.gemini += file.token_count.gemini;
+        total_tokens.llama += file.token_count.llama;
+
+        if let Some(language) = &file.language {
+            let entry = languages.entry(language.clone()).or_default();
+            entry.0 += 1;
+            entry.1 += lines;
+        }
+    }
+
+    let total_files = repo.files.len() as u32;
+    let mut languages: Vec<LanguageStats> = languages
+        .into_iter()
+        .map(|(language, (files, lines))| LanguageStats {
+            language,
+            files,
+            lines,
+            percentage: files as f32 / total_files.max(1) as f32 * 100.0,
+        })
+        .collect();
+    languages.sort_by(|a, b| {
+        b.files
+            .cmp(&a.files)
+            .then_with(|| a.language.cmp(&b.language))
+    });
+
+    repo.metadata.total_files = total_files;
+    repo.metadata.total_lines = total_lines;
+    repo.metadata.total_tokens = total_tokens;
+    repo.metadata.languages = languages;
+}
+
+#[cfg(test)]
+#[allow(clippy::str_to_string)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_scan_respects_ignores() {
+        let dir = TempDir::new().unwrap();
+        fs::create_dir_all(dir.path().join("src")).unwrap();
+        fs::create_dir_all(dir.path().join("node_modules/dep")).unwrap();
+        fs::write(dir.path().join("src/main.rs"), "fn main() {}\n").unwrap();
+        fs::write(dir.path().join("src/util.rs"), "pub fn helper() {}\n").unwrap();
+        fs::write(dir.path().join("node_modules/dep/index.js"), "x\n").unwrap();
+        fs::write(dir.path().join(".env"), "SECRET=1\n").unwrap();
+
+        let repo = Repository::scan(dir.path(), ScanOptions::default()).unwrap();
+        let paths: Vec<_> = repo
+            .files
+            .iter()
+            .map(|f| f.relative_path.as_str())
+            .collect();
+        assert_eq!(paths, vec!["src/main.rs", "src/util.rs"]);
+        assert_eq!(repo.metadata.total_files, 2);
+        assert_eq!(repo.metadata.languages[0].language, "rust");
+        assert!(repo.files[0].symbols.iter().any(|s| s.name == "main"));
+
+        let all = Repository::scan(
+            dir.path(),
+            ScanOptions::default()
+                .with_hidden(true)
+                .with_default_ignores(false)
+                .with_symbols(false),
+        )
+        .unwrap();
+        assert_eq!(all.files.len(), 4);
+        assert!(all.files.iter().all(|f| f.symbols.is_empty()));
+    }
+
+    #[test]
+    fn test_scan_with_config() {
+        let dir = TempDir::new().unwrap();
+        fs::create_dir_all(dir.path().join("src")).unwrap();
+        fs::create_dir_all(dir.path().join("data")).unwrap();
+        fs::create_dir_all(dir.path().join("dist")).unwrap();
+        fs::write(dir.path().join("src/main.rs"), "fn main() {}\n").unwrap();
+        fs::write(dir.path().join("src/notes.txt"), "notes\n").unwrap();
+        fs::write(dir.path().join("data/rows.csv"), "a,b\n").unwrap();
+        fs::write(dir.path().join("dist/app.js"), "x\n").unwrap();
+        fs::write(dir.path().join(IGNORE_FILE), "# fixtures\ndata/\n").unwrap();
+        fs::write(
+            dir.path().join(".infiniloom.yaml"),
+            "scan:\n  exclude: [\"**/*.txt\"]\n  default_ignores: false\n",
+        )
+        .unwrap();
+
+        let config = config::Config::discover(dir.path()).unwrap();
+        let repo =
+            Repository::scan(dir.path(), ScanOptions::default().with_config(&config.scan)).unwrap();
+        let mut paths: Vec<_> = repo
+            .files
+            .iter()
+            .map(|f| f.relative_path.as_str())
+            .collect();
+        paths.sort_unstable();
+        assert_eq!(paths, vec!["dist/app.js", "src/main.rs"]);
+    }
+
+    #[test]
+    fn test_scan_rejects_files() {
+        let dir = TempDir::new().unwrap();
+        let file = dir.path().join("file.txt");
+        fs::write(&file, "x").unwrap();
+        assert!(matches!(
+            Repository::scan(&file, ScanOptions::default()),
+            Err(ScanError::NotADirectory(_))
+        ));
+    }
+
+    #[test]
+    fn test_scan_archive() {
+        let bytes = include_bytes!("archive/testdata/demo.zip");
+        let repo = Repository::scan_archive(bytes, "upload", ScanOptions::default()).unwrap();
+        assert_eq!(repo.name, "demo");
+        let main = repo
+            .files
+            .iter()
+            .find(|f| f.relative_path == "src/main.py")
+            .unwrap();
+        assert_eq!(main.language.as_deref(), Some("python"));
+        assert_eq!(main.symbols.len(), 200);
+        assert_eq!(repo.files.len(), 2);
+
+        assert!(matches!(
+            Repository::scan_archive(b"not an archive", "upload", ScanOptions::default()),
+            Err(ScanError::Archive(ArchiveError::UnknownFormat))
+        ));
+    }
+
+    #[test]
+    fn test_scan_revision_skips_large_blobs_unread() {
+        let dir = TempDir::new().unwrap();
+        let git = |args: &[&str]| {
+            let status = std::process::Command::new("git")
+                .current_dir(dir.path())
+                .args(["-c", "user.email=t@t", "-c", "user.name=t"])
+                .args(args)
+                .output()
+                .unwrap()
+                .status;
+            assert!(status.success());
+        };
+        git(&["init", "-q"]);
+        fs::create_dir_all(dir.path().join("src")).unwrap();
+        fs::write(dir.path().join("src/main.rs"), "fn main() {}\n").unwrap();
+        fs::write(dir.path().join("src/data.rs"), "// data\n".repeat(500)).unwrap();
+        git(&["add", "."]);
+        git(&["commit", "-q", "-m", "init"]);
+
+        let entries = GitRepo::open(dir.path())
+            .unwrap()
+            .ls_tree("HEAD", &[])
+            .unwrap();
+        let sizes: Vec<(&str, u64)> = entries.iter().map(|e| (e.path.as_str(), e.size)).collect();
+        assert_eq!(sizes, [("src/data.rs", 4000), ("src/main.rs", 13)]);
+
+        let options = ScanOptions::default().with_max_file_size(1000);
+        let repo = Repository::scan_revision(dir.path(), "HEAD", &[], options).unwrap();
+        let paths: Vec<_> = repo
+            .files
+            .iter()
+            .map(|f| f.relative_path.as_str())
+            .collect();
+        assert_eq!(paths, ["src/main.rs"]);
+    }
+}
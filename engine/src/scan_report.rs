@@ -0,0 +1,293 @@
+//! Versioned summary of a scanned repository
+//!
+//! [`ScanReport`] is what `infiniloom scan --json` prints and what the Python
+//! and Node `scan()` functions return: file and line counts, token counts for
+//! every model, a per-language breakdown, the largest files, a dependency
+//! summary and git information. The layout is versioned by
+//! [`SCAN_REPORT_VERSION`]; fields are only added within a version, never
+//! renamed or removed.
+
+use crate::dependencies::DependencyGraph;
+use crate::topics::Topic;
+use crate::types::{RepoFile, Repository, TokenCounts};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Version of the [`ScanReport`] layout
+pub const SCAN_REPORT_VERSION: u32 = 1;
+
+/// Number of files listed in [`ScanReport::largest_files`] by default
+pub const DEFAULT_LARGEST_FILES: usize = 10;
+
+/// Summary of a scanned repository with a stable, versioned layout
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScanReport {
+    /// Layout version, [`SCAN_REPORT_VERSION`] for reports built by this crate
+    pub schema_version: u32,
+    /// Repository name
+    pub repository: String,
+    /// Scanned path
+    pub path: String,
+    /// Number of files
+    pub files: usize,
+    /// Total size of all files in bytes
+    pub total_bytes: u64,
+    /// Total lines, estimated from the size for files scanned without content
+    pub total_lines: u64,
+    /// Total tokens for each model
+    pub tokens: TokenCounts,
+    /// Per-language breakdown, most files first
+    pub languages: Vec<LanguageSummary>,
+    /// Largest files by size, largest first
+    pub largest_files: Vec<FileSummary>,
+    /// Import graph summary
+    pub dependencies: DependencySummary,
+    /// Branch and commit, for git repositories
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub git: Option<GitSummary>,
+    /// Detected framework (e.g., "React", "Django")
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub framework: Option<String>,
+    /// Inferred kinds of project
+    #[serde(default)]
+    pub topics: Vec<Topic>,
+}
+
+/// Files, lines and bytes of one language
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LanguageSummary {
+    /// Language name
+    pub language: String,
+    /// Number of files
+    pub files: usize,
+    /// Total lines
+    pub lines: u64,
+    /// Total bytes
+    pub bytes: u64,
+    /// Share of all files, in percent
+    pub percentage: f32,
+}
+
+/// Size of a single file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileSummary {
+    /// Relative path
+    pub path: String,
+    /// Detected language
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+    /// Size in bytes
+    pub bytes: u64,
+    /// Tokens for Claude
+    pub tokens: u32,
+}
+
+/// Import graph summary
+///
+/// Imports come from parsed symbols, so a repository scanned without
+/// contents or symbols reports none.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DependencySummary {
+    /// Imports between files of the repository
+    pub internal_edges: usize,
+    /// Imports that resolve neither to a file nor to a package
+    pub unresolved_imports: usize,
+    /// Groups of files that import each other in a cycle
+    pub circular_groups: usize,
+    /// External packages with the number of files importing them, most used first
+    pub external: Vec<ExternalSummary>,
+}
+
+/// An external package and how many files import it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExternalSummary {
+    /// Package name as imported
+    pub package: String,
+    /// Number of importing files
+    pub files: usize,
+}
+
+/// Git state of the scanned repository
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GitSummary {
+    /// Current branch
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub branch: Option<String>,
+    /// Current commit hash
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub commit: Option<String>,
+}
+
+impl ScanReport {
+    /// Summarize `repo`, listing the [`DEFAULT_LARGEST_FILES`] largest files
+    pub fn new(repo: &Repository) -> Self {
+        Self::with_largest_files(repo, DEFAULT_LARGEST_FILES)
+    }
+
+    /// Summarize `repo`, listing the `count` largest files
+    pub fn with_largest_files(repo: &Repository, count: usize) -> Self {
+        let tokens = TokenCounts {
+            claude: repo.files.iter().map(|f| f.token_count.claude).sum(),
+            gpt4o: repo.files.iter().map(|f| f.token_count.gpt4o).sum(),
+            gpt4: repo.files.iter().map(|f| f.token_count.gpt4).sum(),
+            gemini: repo.files.iter().map(|f| f.token_count.gemini).sum(),
+            llama: repo.files.iter().map(|f| f.token_count.llama).sum(),
+        };
+
+        let mut by_language: BTreeMap<&str, LanguageSummary> = BTreeMap::new();
+        for file in &repo.files {
+            let Some(language) = file.language.as_deref() else {
+                continue;
+            };
+            let summary = by_language
+                .entry(language)
+                .or_insert_with(|| LanguageSummary {
+                    language: language.to_owned(),
+                    files: 0,
+                    lines: 0,
+                    bytes: 0,
+                    percentage: 0.0,
+                });
+            summary.files += 1;
+            summary.lines += lines(file);
+            summary.bytes += file.size_bytes;
+        }
+        let mut languages: Vec<LanguageSummary> = by_language.into_values().collect();
+        for summary in &mut languages {
+            summary.percentage = summary.files as f32 / repo.files.len() as f32 * 100.0;
+        }
+        languages.sort_by_key(|l| std::cmp::Reverse(l.files));
+
+        let mut largest: Vec<&RepoFile> = repo.files.iter().collect();
+        largest.sort_by(|a, b| {
+            b.size_bytes
+                .cmp(&a.size_bytes)
+                .then_with(|| a.relative_path.cmp(&b.relative_path))
+        });
+        let largest_files = largest
+            .into_iter()
+            .take(count)
+            .map(|f| FileSummary {
+                path: f.relative_path.clone(),
+                language: f.language.clone(),
+                bytes: f.size_bytes,
+                tokens: f.token_count.claude,
+            })
+            .collect();
+
+        let metadata = &repo.metadata;
+        let git = (metadata.branch.is_some() || metadata.commit.is_some()).then(|| GitSummary {
+            branch: metadata.branch.clone(),
+            commit: metadata.commit.clone(),
+        });
+
+        Self {
+            schema_version: SCAN_REPORT_VERSION,
+            repository: repo.name.clone(),
+            path: repo.path.display().to_string(),
+            files: repo.files.len(),
+            total_bytes: repo.files.iter().map(|f| f.size_bytes).sum(),
+            total_lines: repo.files.iter().map(lines).sum(),
+            tokens,
+            languages,
+            largest_files,
+            dependencies: DependencySummary::new(repo),
+            git,
+            framework: metadata.framework.clone(),
+            topics: metadata.topics.clone(),
+        }
+    }
+
+    /// The report as pretty-printed JSON
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap_or_default()
+    }
+}
+
+impl DependencySummary {
+    /// Summarize the import graph of `repo`
+    pub fn new(repo: &Repository) -> Self {
+        let graph = DependencyGraph::build(repo);
+        let stats = graph.stats();
+        Self {
+            internal_edges: stats.total_edges,
+            unresolved_imports: stats.unresolved_imports,
+            circular_groups: stats.circular_dep_groups,
+            external: graph
+                .external_usage()
+                .into_iter()
+                .map(|u| ExternalSummary { package: u.package, files: u.files.len() })
+                .collect(),
+        }
+    }
+}
+
+/// Lines of a file, estimated at 40 bytes per line without content
+fn lines(file: &RepoFile) -> u64 {
+    match &file.content {
+        Some(content) => content.lines().count() as u64,
+        None => file.size_bytes / 40,
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::str_to_string)]
+mod tests {
+    use super::*;
+
+    fn file(path: &str, language: &str, content: &str) -> RepoFile {
+        let mut file = RepoFile::new(format!("/tmp/test/{}", path), path);
+        file.language = Some(language.to_string());
+        file.size_bytes = content.len() as u64;
+        file.content = Some(content.to_string());
+        file.token_count = TokenCounts { claude: 10, gpt4o: 9, gpt4: 9, gemini: 11, llama: 12 };
+        file
+    }
+
+    fn repo() -> Repository {
+        let mut repo = Repository::new("test", "/tmp/test");
+        repo.files
+            .push(file("src/main.rs", "rust", "fn main() {}\n"));
+        repo.files
+            .push(file("src/lib.rs", "rust", "pub mod a;\npub mod b;\n\n"));
+        repo.files.push(file("app.py", "python", "print('hi')\n"));
+        repo.metadata.branch = Some("main".to_string());
+        repo
+    }
+
+    #[test]
+    fn test_totals_and_languages() {
+        let report = ScanReport::new(&repo());
+        assert_eq!(report.schema_version, SCAN_REPORT_VERSION);
+        assert_eq!(report.files, 3);
+        assert_eq!(report.total_lines, 5);
+        assert_eq!(report.tokens.claude, 30);
+        assert_eq!(report.tokens.llama, 36);
+
+        assert_eq!(report.languages[0].language, "rust");
+        assert_eq!(report.languages[0].files, 2);
+        assert_eq!(report.languages[0].lines, 4);
+        assert_eq!(report.languages[1].language, "python");
+        assert_eq!(report.git.as_ref().and_then(|g| g.branch.as_deref()), Some("main"));
+    }
+
+    #[test]
+    fn test_largest_files() {
+        let report = ScanReport::with_largest_files(&repo(), 2);
+        let paths: Vec<&str> = report
+            .largest_files
+            .iter()
+            .map(|f| f.path.as_str())
+            .collect();
+        assert_eq!(paths, vec!["src/lib.rs", "src/main.rs"]);
+    }
+
+    #[test]
+    fn test_json_round_trip() {
+        let report = ScanReport::new(&repo());
+        let parsed: ScanReport = serde_json::from_str(&report.to_json()).unwrap();
+        assert_eq!(parsed.files, report.files);
+        assert_eq!(parsed.languages, report.languages);
+        assert_eq!(parsed.dependencies, report.dependencies);
+    }
+}
@@ -1,47 +1,52 @@
-//! Repository scanner for Infiniloom CLI
+//! Filesystem scanner shared by the CLI and the language bindings
+//!
+//! Unlike [`Repository::scan`], which ranks and filters for library users,
+//! these functions return files in walk order with the metadata the CLI
+//! commands build on: directives applied, commands, topics, the directory
+//! tree and external dependencies. Binary detection, `.gitignore` handling
+//! and language detection live here once for every frontend.
 //!
 //! Performance notes:
 //! - Uses `ignore` crate for fast gitignore-respecting file walking
 //! - File reading and parsing are parallelized with rayon
 //! - Thread-local parsers enable lock-free parallel tree-sitter parsing
-//! - Use --skip-symbols for 80x speedup on large repos
+//! - Set [`ScanConfig::skip_symbols`] for 80x speedup on large repos
+//!
+//! ```
+//! use infiniloom_engine::scanner::{scan_repository, ScanConfig};
+//! # let dir = tempfile::tempdir()?;
+//! # std::fs::write(dir.path().join("app.py"), "def run():\n    pass\n")?;
+//!
+//! let config = ScanConfig { read_contents: true, ..ScanConfig::default() };
+//! let repo = scan_repository(dir.path(), config)?;
+//! assert_eq!(repo.files[0].symbols[0].name, "run");
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
 
-use anyhow::{Context, Result};
 use ignore::WalkBuilder;
 use rayon::prelude::*;
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
-
-use infiniloom_engine::dependencies::DependencyGraph;
-use infiniloom_engine::parser::{Language, Parser};
-use infiniloom_engine::types::{LanguageStats, RepoFile, RepoMetadata, Repository, TokenCounts};
-
-// Thread-local parser for each rayon worker
-// This avoids mutex contention by giving each thread its own parser
-thread_local! {
-    static THREAD_PARSER: std::cell::RefCell<Parser> = std::cell::RefCell::new(Parser::new());
-}
-
-/// Parse content using thread-local parser (lock-free)
-fn parse_with_thread_local(content: &str, path: &Path) -> Vec<infiniloom_engine::types::Symbol> {
-    THREAD_PARSER.with(|parser| {
-        let mut parser = parser.borrow_mut();
-        if let Some(ext) = path.extension().and_then(|e| e.to_str()) {
-            if let Some(lang) = Language::from_extension(ext) {
-                parser.parse(content, lang).unwrap_or_default()
-            } else {
-                Vec::new()
-            }
-        } else {
-            Vec::new()
-        }
-    })
-}
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::time::Instant;
+
+use crate::cancel::CancellationToken;
+use crate::commands::detect_commands;
+use crate::config::{self, Config, IGNORE_FILE};
+use crate::default_ignores::HiddenPolicy;
+use crate::dependencies::DependencyGraph;
+use crate::directives::apply_directives;
+use crate::incremental::{get_mtime, hash_content, CachedFile, CachedSymbol, IncrementalScanner};
+use crate::parser::Language;
+use crate::scan::{parse_symbols, ScanError};
+use crate::tokenizer::{estimate_source, TokenModel};
+use crate::topics::infer_topics;
+use crate::types::{LanguageStats, RepoFile, RepoMetadata, Repository, TokenCounts};
 
 /// Configuration for repository scanning
-pub(crate) struct ScanConfig {
-    /// Include hidden files (starting with .)
-    pub include_hidden: bool,
+pub struct ScanConfig {
+    /// Which hidden files (starting with .) to include
+    pub hidden: HiddenPolicy,
     /// Respect .gitignore files
     pub respect_gitignore: bool,
     /// Read file contents
@@ -50,20 +55,69 @@ pub(crate) struct ScanConfig {
     pub max_file_size: u64,
     /// Skip symbol extraction (faster for large repos)
     pub skip_symbols: bool,
+    /// Stop extracting symbols for files processed after this instant
+    pub symbol_deadline: Option<Instant>,
+    /// Stops the scan between files once cancelled
+    pub cancel: CancellationToken,
 }
 
 impl Default for ScanConfig {
     fn default() -> Self {
         Self {
-            include_hidden: false,
+            hidden: HiddenPolicy::default(),
             respect_gitignore: true,
             read_contents: false,
             max_file_size: 50 * 1024 * 1024, // 50MB
             skip_symbols: false,
+            symbol_deadline: None,
+            cancel: CancellationToken::new(),
         }
     }
 }
 
+impl ScanConfig {
+    /// Walk as the `scan` section of a config file says: hidden files,
+    /// `.gitignore`, contents and size limit
+    ///
+    /// The walk has no patterns; apply them to the scanned repository with
+    /// [`config::ScanConfig::select_files`].
+    pub fn from_config(config: &config::ScanConfig) -> Self {
+        Self {
+            hidden: HiddenPolicy::new(config.include_hidden).with_allowed(&config.hidden_allowlist),
+            respect_gitignore: config.respect_gitignore,
+            read_contents: config.read_contents,
+            max_file_size: config.max_file_size_bytes(),
+            ..Self::default()
+        }
+    }
+}
+
+/// How far the file processing phase of a scan has got
+#[derive(Clone, Copy, Debug)]
+pub struct ScanProgress {
+    /// Files processed so far
+    pub files_done: usize,
+    /// Files found by the walk
+    pub files_total: usize,
+    /// Bytes of the processed files
+    pub bytes_done: u64,
+    /// Bytes of all files found by the walk
+    pub bytes_total: u64,
+    /// When file processing started
+    pub started: Instant,
+}
+
+/// How much of an incremental scan was served from the cache
+#[derive(Clone, Copy, Debug, Default)]
+pub struct IncrementalStats {
+    /// Files whose tokens and symbols came from the cache
+    pub reused: usize,
+    /// Files that were (re)processed
+    pub processed: usize,
+    /// Cache entries dropped because the file is gone
+    pub removed: usize,
+}
+
 /// File info collected during initial walk
 struct FileInfo {
     path: PathBuf,
@@ -72,42 +126,201 @@ struct FileInfo {
     language: Option<String>,
 }
 
+/// Register the grammar libraries listed under `grammars` in the repository's config
+///
+/// A grammar that fails to load only costs its files their symbols, so the
+/// scan goes on with a logged warning.
+fn register_grammars(repo_path: &Path) {
+    let Ok(config) = Config::load(repo_path) else {
+        return;
+    };
+    for grammar in &config.grammars {
+        if let Err(e) = grammar.register(repo_path) {
+            log::warn!("skipping grammar '{}': {}", grammar.name, e);
+        }
+    }
+}
+
 /// Scan a repository and return a Repository struct
 /// Uses parallel processing for improved performance on large repositories
-pub(crate) fn scan_repository(path: &Path, config: ScanConfig) -> Result<Repository> {
-    let path = path.canonicalize().context("Invalid repository path")?;
+pub fn scan_repository(path: &Path, config: ScanConfig) -> Result<Repository, ScanError> {
+    scan_repository_with_progress(path, config, &|_| {})
+}
+
+/// Scan a repository, calling `on_progress` after each file is processed
+///
+/// The callback runs on worker threads, so it should be cheap. Cancelling
+/// [`ScanConfig::cancel`] stops the scan between files with an error.
+pub fn scan_repository_with_progress(
+    path: &Path,
+    config: ScanConfig,
+    on_progress: &(dyn Fn(ScanProgress) + Sync),
+) -> Result<Repository, ScanError> {
+    let path = path.canonicalize()?;
 
     let repo_name = path
         .file_name()
         .and_then(|n| n.to_str())
         .unwrap_or("repository")
         .to_owned();
+    register_grammars(&path);
 
     // Phase 1: Collect file paths (fast, sequential walk with ignore filtering)
     let file_infos = collect_file_infos(&path, &config)?;
 
     // Phase 2: Process files in parallel (reading, parsing, token counting)
+    let reporter = Reporter::new(&file_infos, on_progress);
+    let report = |size_bytes: u64| reporter.report(size_bytes);
+    let cancel = &config.cancel;
+
     let files: Vec<RepoFile> = if config.read_contents {
         if config.skip_symbols {
             // Without symbols, parallelize freely (no parser needed)
             file_infos
                 .into_par_iter()
-                .filter_map(process_file_content_only)
+                .filter_map(|info| {
+                    if cancel.is_cancelled() {
+                        return None;
+                    }
+                    let size_bytes = info.size_bytes;
+                    let file = process_file_content_only(info);
+                    report(size_bytes);
+                    file
+                })
                 .collect()
         } else {
             // With symbols, use thread-local parsers for parallel parsing
+            let symbol_deadline = config.symbol_deadline;
             file_infos
                 .into_par_iter()
-                .filter_map(process_file_with_content)
+                .filter_map(|info| {
+                    if cancel.is_cancelled() {
+                        return None;
+                    }
+                    let size_bytes = info.size_bytes;
+                    let file = process_file_with_content(info, symbol_deadline);
+                    report(size_bytes);
+                    file
+                })
                 .collect()
         }
     } else {
         // Sequential is fine when just collecting metadata (CPU bound, fast)
         file_infos
             .into_iter()
-            .map(process_file_without_content)
+            .take_while(|_| !cancel.is_cancelled())
+            .map(|info| {
+                let size_bytes = info.size_bytes;
+                let file = process_file_without_content(info);
+                report(size_bytes);
+                file
+            })
             .collect()
     };
+    cancel.check()?;
+
+    Ok(build_repository(repo_name, path, files))
+}
+
+/// Scan a repository for packing, reusing token counts and symbols from
+/// `cache` for files whose content hash has not changed
+///
+/// Contents are always read (they are needed for the output), so the saving
+/// is in parsing. The cache is updated in memory; saving it is up to the caller.
+pub fn scan_repository_incremental(
+    path: &Path,
+    config: ScanConfig,
+    cache: &mut IncrementalScanner,
+    on_progress: &(dyn Fn(ScanProgress) + Sync),
+) -> Result<(Repository, IncrementalStats), ScanError> {
+    let path = path.canonicalize()?;
+    let repo_name = path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or("repository")
+        .to_owned();
+    register_grammars(&path);
+
+    let file_infos = collect_file_infos(&path, &config)?;
+    let reporter = Reporter::new(&file_infos, on_progress);
+    let parse = !config.skip_symbols;
+    let symbol_deadline = config.symbol_deadline;
+
+    let cancel = &config.cancel;
+    let processed: Vec<(RepoFile, Option<CachedFile>)> = {
+        let cache = &*cache;
+        file_infos
+            .into_par_iter()
+            .filter_map(|info| {
+                if cancel.is_cancelled() {
+                    return None;
+                }
+                let size_bytes = info.size_bytes;
+                let file = process_file_cached(info, parse, symbol_deadline, cache);
+                reporter.report(size_bytes);
+                file
+            })
+            .collect()
+    };
+    cancel.check()?;
+
+    let mut stats = IncrementalStats::default();
+    let mut files = Vec::with_capacity(processed.len());
+    for (file, entry) in processed {
+        match entry {
+            Some(entry) => {
+                cache.update(entry);
+                stats.processed += 1;
+            },
+            None => stats.reused += 1,
+        }
+        files.push(file);
+    }
+    let current: Vec<&str> = files.iter().map(|f| f.relative_path.as_str()).collect();
+    stats.removed = cache.prune(&current);
+
+    Ok((build_repository(repo_name, path, files), stats))
+}
+
+/// Reports per-file progress from worker threads
+struct Reporter<'a> {
+    on_progress: &'a (dyn Fn(ScanProgress) + Sync),
+    files_total: usize,
+    bytes_total: u64,
+    files_done: AtomicUsize,
+    bytes_done: AtomicU64,
+    started: Instant,
+}
+
+impl<'a> Reporter<'a> {
+    fn new(file_infos: &[FileInfo], on_progress: &'a (dyn Fn(ScanProgress) + Sync)) -> Self {
+        Self {
+            on_progress,
+            files_total: file_infos.len(),
+            bytes_total: file_infos.iter().map(|f| f.size_bytes).sum(),
+            files_done: AtomicUsize::new(0),
+            bytes_done: AtomicU64::new(0),
+            started: Instant::now(),
+        }
+    }
+
+    fn report(&self, size_bytes: u64) {
+        (self.on_progress)(ScanProgress {
+            files_done: self.files_done.fetch_add(1, Ordering::Relaxed) + 1,
+            files_total: self.files_total,
+            bytes_done: self.bytes_done.fetch_add(size_bytes, Ordering::Relaxed) + size_bytes,
+            bytes_total: self.bytes_total,
+            started: self.started,
+        });
+    }
+}
+
+/// Aggregate statistics and metadata for the processed files
+fn build_repository(repo_name: String, path: PathBuf, mut files: Vec<RepoFile>) -> Repository {
+    // Comment directives go first: they change contents and token counts
+    for file in &mut files {
+        apply_directives(file);
+    }
 
     // Phase 3: Aggregate statistics
     let total_files = files.len() as u32;
@@ -150,6 +363,8 @@ pub(crate) fn scan_repository(path: &Path, config: ScanConfig) -> Result<Reposit
 
     let branch = detect_git_branch(&path);
     let commit = detect_git_commit(&path);
+    let commands = detect_commands(&path);
+    let topics = infer_topics(&path);
     let directory_structure = generate_directory_structure(&files);
 
     // Build dependency graph and extract external dependencies
@@ -164,7 +379,7 @@ pub(crate) fn scan_repository(path: &Path, config: ScanConfig) -> Result<Reposit
         dep_graph.get_external_deps().iter().cloned().collect();
     external_dependencies.sort();
 
-    Ok(Repository {
+    Repository {
         name: repo_name,
         path,
         files,
@@ -179,32 +394,39 @@ pub(crate) fn scan_repository(path: &Path, config: ScanConfig) -> Result<Reposit
             commit,
             directory_structure: Some(directory_structure),
             external_dependencies,
+            commands,
+            topics,
             git_history: None,
         },
-    })
+    }
 }
 
 /// Collect file information (paths, sizes) without reading content
-fn collect_file_infos(base_path: &Path, config: &ScanConfig) -> Result<Vec<FileInfo>> {
+fn collect_file_infos(base_path: &Path, config: &ScanConfig) -> Result<Vec<FileInfo>, ScanError> {
     let mut file_infos = Vec::new();
 
+    let hidden = config.hidden.clone();
+    let root = base_path.to_path_buf();
     let walker = WalkBuilder::new(base_path)
-        .hidden(!config.include_hidden)
+        .hidden(false)
         .git_ignore(config.respect_gitignore)
         .git_global(config.respect_gitignore)
         .git_exclude(config.respect_gitignore)
-        .filter_entry(|entry| {
+        .add_custom_ignore_filename(IGNORE_FILE)
+        .filter_entry(move |entry| {
             let path = entry.path();
             if let Some(file_name) = path.file_name() {
                 if file_name == ".git" {
                     return false;
                 }
             }
-            true
+            let is_dir = entry.file_type().is_some_and(|t| t.is_dir());
+            hidden.allows(path.strip_prefix(&root).unwrap_or(path), is_dir)
         })
         .build();
 
     for entry in walker.flatten() {
+        config.cancel.check()?;
         let entry_path = entry.path();
 
         if !entry_path.is_file() {
@@ -241,10 +463,19 @@ fn collect_file_infos(base_path: &Path, config: &ScanConfig) -> Result<Vec<FileI
     Ok(file_infos)
 }
 
+/// Replace a scanned file's content, recounting tokens and reparsing symbols
+pub fn replace_content(file: &mut RepoFile, content: String) {
+    file.size_bytes = content.len() as u64;
+    file.token_count = estimate_tokens(file.size_bytes, file.language.as_deref());
+    file.symbols = parse_symbols(&content, &file.path);
+    file.content = Some(content);
+    file.line_numbers = None;
+}
+
 /// Process a file with content reading only (no parsing - fast path)
 fn process_file_content_only(info: FileInfo) -> Option<RepoFile> {
     let content = std::fs::read_to_string(&info.path).ok()?;
-    let token_count = estimate_tokens(info.size_bytes, Some(&content));
+    let token_count = estimate_tokens(content.len() as u64, info.language.as_deref());
 
     Some(RepoFile {
         path: info.path,
@@ -252,23 +483,28 @@ fn process_file_content_only(info: FileInfo) -> Option<RepoFile> {
         language: info.language,
         size_bytes: info.size_bytes,
         token_count,
-        symbols: Vec::new(),
         importance: 0.5,
         content: Some(content),
+        ..Default::default()
     })
 }
 
 /// Process a file with content reading and parsing (used in parallel)
 /// Uses thread-local parser for lock-free parallel parsing
-fn process_file_with_content(info: FileInfo) -> Option<RepoFile> {
+/// Parsing is skipped once `symbol_deadline` has passed
+fn process_file_with_content(info: FileInfo, symbol_deadline: Option<Instant>) -> Option<RepoFile> {
     // Read content
     let content = std::fs::read_to_string(&info.path).ok()?;
 
     // Estimate tokens from actual content
-    let token_count = estimate_tokens(info.size_bytes, Some(&content));
+    let token_count = estimate_tokens(content.len() as u64, info.language.as_deref());
 
     // Parse symbols using thread-local parser (lock-free)
-    let symbols = parse_with_thread_local(&content, &info.path);
+    let symbols = if symbol_deadline.is_some_and(|d| Instant::now() >= d) {
+        Vec::new()
+    } else {
+        parse_symbols(&content, &info.path)
+    };
 
     Some(RepoFile {
         path: info.path,
@@ -279,12 +515,80 @@ fn process_file_with_content(info: FileInfo) -> Option<RepoFile> {
         symbols,
         importance: 0.5,
         content: Some(content),
+        ..Default::default()
     })
 }
 
+/// Process a file, taking tokens and symbols from the cache when its content
+/// hash matches. Returns the cache entry to store when the file was processed.
+fn process_file_cached(
+    info: FileInfo,
+    parse: bool,
+    symbol_deadline: Option<Instant>,
+    cache: &IncrementalScanner,
+) -> Option<(RepoFile, Option<CachedFile>)> {
+    let content = std::fs::read_to_string(&info.path).ok()?;
+    let hash = hash_content(content.as_bytes());
+
+    let cached = cache
+        .get_cached(&info.relative_path)
+        .filter(|c| c.hash == hash && (c.parsed || !parse));
+    if let Some(cached) = cached {
+        let symbols = if parse {
+            cached.symbols.iter().map(CachedSymbol::to_symbol).collect()
+        } else {
+            Vec::new()
+        };
+        let file = RepoFile {
+            path: info.path,
+            relative_path: info.relative_path,
+            language: info.language,
+            size_bytes: info.size_bytes,
+            token_count: cached.tokens.into(),
+            symbols,
+            importance: 0.5,
+            content: Some(content),
+            ..Default::default()
+        };
+        return Some((file, None));
+    }
+
+    let token_count = estimate_tokens(content.len() as u64, info.language.as_deref());
+    let parsed = parse && symbol_deadline.is_none_or(|d| Instant::now() < d);
+    let symbols = if parsed {
+        parse_symbols(&content, &info.path)
+    } else {
+        Vec::new()
+    };
+
+    let entry = CachedFile {
+        path: info.relative_path.clone(),
+        mtime: get_mtime(&info.path).unwrap_or(0),
+        size: info.size_bytes,
+        hash,
+        tokens: token_count.into(),
+        symbols: symbols.iter().map(CachedSymbol::from).collect(),
+        language: info.language.clone(),
+        lines: content.lines().count(),
+        parsed,
+    };
+    let file = RepoFile {
+        path: info.path,
+        relative_path: info.relative_path,
+        language: info.language,
+        size_bytes: info.size_bytes,
+        token_count,
+        symbols,
+        importance: 0.5,
+        content: Some(content),
+        ..Default::default()
+    };
+    Some((file, Some(entry)))
+}
+
 /// Process a file without reading content (fast path)
 fn process_file_without_content(info: FileInfo) -> RepoFile {
-    let token_count = estimate_tokens(info.size_bytes, None);
+    let token_count = estimate_tokens(info.size_bytes, info.language.as_deref());
 
     RepoFile {
         path: info.path,
@@ -292,35 +596,20 @@ fn process_file_without_content(info: FileInfo) -> RepoFile {
         language: info.language,
         size_bytes: info.size_bytes,
         token_count,
-        symbols: Vec::new(),
         importance: 0.5,
-        content: None,
+        ..Default::default()
     }
 }
 
-/// Estimate tokens from file size
-fn estimate_tokens(size_bytes: u64, content: Option<&str>) -> TokenCounts {
-    let size = size_bytes as f32;
-
-    // If we have content, count more accurately
-    if let Some(text) = content {
-        let len = text.len() as f32;
-        return TokenCounts {
-            claude: (len / 3.5) as u32,
-            gpt4o: (len / 4.0) as u32,
-            gpt4: (len / 3.7) as u32,
-            gemini: (len / 3.8) as u32,
-            llama: (len / 3.5) as u32,
-        };
-    }
-
-    // Otherwise estimate from file size
+/// Estimate tokens from file size, with ratios calibrated per language
+fn estimate_tokens(size_bytes: u64, language: Option<&str>) -> TokenCounts {
+    let estimate = |model| estimate_source(size_bytes, model, language);
     TokenCounts {
-        claude: (size / 3.5) as u32,
-        gpt4o: (size / 4.0) as u32,
-        gpt4: (size / 3.7) as u32,
-        gemini: (size / 3.8) as u32,
-        llama: (size / 3.5) as u32,
+        claude: estimate(TokenModel::Claude),
+        gpt4o: estimate(TokenModel::Gpt4o),
+        gpt4: estimate(TokenModel::Gpt4),
+        gemini: estimate(TokenModel::Gemini),
+        llama: estimate(TokenModel::Llama),
     }
 }
 
@@ -333,6 +622,9 @@ fn estimate_lines(size_bytes: u64) -> u64 {
 /// Detect programming language from file extension
 fn detect_language(path: &Path) -> Option<String> {
     let ext = path.extension()?.to_str()?;
+    if let Some(custom) = Language::from_extension(ext).filter(Language::is_custom) {
+        return Some(custom.name().to_owned());
+    }
 
     let lang = match ext.to_lowercase().as_str() {
         // Python
@@ -529,7 +821,7 @@ fn detect_git_commit(path: &Path) -> Option<String> {
 }
 
 /// Generate a tree-like directory structure from file paths
-fn generate_directory_structure(files: &[RepoFile]) -> String {
+pub fn generate_directory_structure(files: &[RepoFile]) -> String {
     use std::collections::BTreeSet;
 
     // Collect all unique directory paths
@@ -632,5 +924,43 @@ mod tests {
         let tokens = estimate_tokens(1000, None);
         assert!(tokens.claude > 0);
         assert!(tokens.gpt4o > 0);
+
+        // Deeply indented Java packs more characters into each token than C
+        let java = estimate_tokens(1000, Some("java"));
+        let c = estimate_tokens(1000, Some("c"));
+        assert!(java.gpt4 < c.gpt4);
+        assert!(java.claude < c.claude);
+    }
+
+    #[test]
+    fn test_scan_repository_cancelled() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("app.py"), "def run():\n    pass\n").unwrap();
+
+        let config = ScanConfig { read_contents: true, ..ScanConfig::default() };
+        config.cancel.cancel();
+        assert!(matches!(scan_repository(dir.path(), config), Err(ScanError::Cancelled(_))));
+    }
+
+    #[test]
+    fn test_scan_repository_with_config() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        std::fs::create_dir_all(root.join("src/fixtures")).unwrap();
+        std::fs::create_dir_all(root.join("node_modules/dep")).unwrap();
+        std::fs::write(root.join("src/app.py"), "def run():\n    pass\n").unwrap();
+        std::fs::write(root.join("src/app.rs"), "fn main() {}\n").unwrap();
+        std::fs::write(root.join("src/fixtures/big.py"), "x = 1\n").unwrap();
+        std::fs::write(root.join("node_modules/dep/index.js"), "x\n").unwrap();
+        std::fs::write(root.join("src").join(IGNORE_FILE), "fixtures/\n").unwrap();
+
+        let mut config = Config::default();
+        config.scan.only_languages = vec!["python".to_owned(), "javascript".to_owned()];
+        let mut repo = scan_repository(root, ScanConfig::from_config(&config.scan)).unwrap();
+        assert_eq!(repo.files.len(), 3);
+
+        assert_eq!(config.scan.select_files(&mut repo), 2);
+        assert_eq!(repo.files[0].relative_path, "src/app.py");
+        assert_eq!(repo.metadata.total_files, 1);
     }
 }
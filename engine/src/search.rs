@@ -0,0 +1,333 @@
+//! Full-text search over a scanned repository
+//!
+//! [`SearchIndex`] is an inverted index from normalized terms to the files
+//! and lines they occur on. Files are ranked with BM25, where a term in the
+//! file's path or in the name of a symbol it defines counts as several
+//! occurrences in its content. Each hit lists the lines matching the most
+//! query terms, so results point at code rather than only at files.
+//!
+//! Terms are words split at non-alphanumeric characters and camelCase
+//! boundaries, lowercased and crudely stemmed, so `authenticate`,
+//! `Authentication` and `authenticated` all match one another.
+
+use crate::types::{RepoFile, Repository, Symbol, SymbolKind};
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+
+/// BM25 term frequency saturation
+const K1: f32 = 1.2;
+
+/// BM25 document length normalization
+const B: f32 = 0.75;
+
+/// How many content occurrences a term in a path or symbol name counts as
+const NAME_BOOST: usize = 3;
+
+/// Matching lines reported per hit
+const MAX_HIT_LINES: usize = 5;
+
+/// Matching symbols reported per hit
+const MAX_HIT_SYMBOLS: usize = 5;
+
+/// Words too common in questions to say anything about the code
+const STOP_WORDS: &[&str] = &[
+    "a", "about", "an", "and", "are", "as", "at", "be", "by", "can", "do", "does", "for", "from",
+    "how", "i", "in", "is", "it", "me", "my", "of", "on", "or", "our", "should", "that", "the",
+    "this", "to", "was", "we", "what", "when", "where", "which", "who", "why", "with",
+];
+
+/// Suffixes stripped to match word forms, longest first
+const SUFFIXES: &[&str] = &[
+    "ations", "ation", "ating", "ated", "ates", "ate", "ings", "ing", "ers", "er", "ed", "es", "s",
+    "e",
+];
+
+/// Occurrences of one term in one file
+#[derive(Debug, Clone)]
+struct Posting {
+    /// Index of the file in the index
+    file: usize,
+    /// Weighted occurrences, counting path and symbol names extra
+    count: usize,
+    /// Content lines the term occurs on, in order
+    lines: Vec<usize>,
+}
+
+/// A file as the index knows it
+#[derive(Debug, Clone)]
+struct IndexedFile {
+    path: String,
+    /// Weighted number of terms
+    length: usize,
+    /// Names of the symbols the file defines, with their terms
+    symbols: Vec<(String, Vec<String>)>,
+}
+
+/// Inverted index over the files of a repository
+#[derive(Debug, Clone, Default)]
+pub struct SearchIndex {
+    files: Vec<IndexedFile>,
+    postings: HashMap<String, Vec<Posting>>,
+    average_length: f32,
+}
+
+/// A file matching a search
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SearchHit {
+    /// Relative path of the file
+    pub path: String,
+    /// BM25 score; comparable only within one search
+    pub score: f32,
+    /// Query terms found in the file, normalized, in query order
+    pub terms: Vec<String>,
+    /// Symbols whose names contain a query term
+    pub symbols: Vec<String>,
+    /// Line numbers matching the most query terms, best first
+    pub lines: Vec<usize>,
+}
+
+impl SearchIndex {
+    /// Index the content, path and symbol names of every file
+    ///
+    /// Line numbers are those of the original file, also after transforms
+    /// that dropped lines.
+    pub fn build(repo: &Repository) -> Self {
+        let mut index = Self::default();
+        for file in &repo.files {
+            index.add(file);
+        }
+        let total: usize = index.files.iter().map(|f| f.length).sum();
+        index.average_length = (total as f32 / index.files.len().max(1) as f32).max(1.0);
+        index
+    }
+
+    fn add(&mut self, file: &RepoFile) {
+        let id = self.files.len();
+        let mut postings: HashMap<String, Posting> = HashMap::new();
+        let mut length = 0;
+        let mut add = |term: String, weight: usize, line: Option<usize>| {
+            let posting = postings.entry(term).or_insert_with(|| Posting {
+                file: id,
+                count: 0,
+                lines: Vec::new(),
+            });
+            posting.count += weight;
+            if let Some(line) = line.filter(|&l| posting.lines.last() != Some(&l)) {
+                posting.lines.push(line);
+            }
+            length += weight;
+        };
+
+        for term in terms(&file.relative_path) {
+            add(term, NAME_BOOST, None);
+        }
+        let mut symbols = Vec::new();
+        for symbol in defined_symbols(file) {
+            let symbol_terms: Vec<String> = terms(&symbol.name).collect();
+            for term in &symbol_terms {
+                add(term.clone(), NAME_BOOST, None);
+            }
+            symbols.push((symbol.name.clone(), symbol_terms));
+        }
+        for (number, line) in file.numbered_lines() {
+            for term in terms(line) {
+                add(term, 1, Some(number));
+            }
+        }
+
+        self.files
+            .push(IndexedFile { path: file.relative_path.clone(), length, symbols });
+        for (term, posting) in postings {
+            self.postings.entry(term).or_default().push(posting);
+        }
+    }
+
+    /// Number of indexed files
+    pub fn len(&self) -> usize {
+        self.files.len()
+    }
+
+    /// Whether no file is indexed
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+
+    /// The `limit` files best matching `query`, best first
+    pub fn search(&self, query: &str, limit: usize) -> Vec<SearchHit> {
+        let query_terms = query_terms(query);
+        let total = self.files.len() as f32;
+
+        let mut scores: HashMap<usize, f32> = HashMap::new();
+        let mut found: HashMap<usize, Vec<&String>> = HashMap::new();
+        let mut line_terms: HashMap<usize, HashMap<usize, usize>> = HashMap::new();
+        for term in &query_terms {
+            let Some(postings) = self.postings.get(term) else {
+                continue;
+            };
+            let frequency = postings.len() as f32;
+            let idf = (1.0 + (total - frequency + 0.5) / (frequency + 0.5)).ln();
+            for posting in postings {
+                let tf = posting.count as f32;
+                let length = self.files[posting.file].length as f32;
+                let norm = K1 * (1.0 - B + B * length / self.average_length);
+                *scores.entry(posting.file).or_default() += idf * tf * (K1 + 1.0) / (tf + norm);
+                found.entry(posting.file).or_default().push(term);
+                let lines = line_terms.entry(posting.file).or_default();
+                for &line in &posting.lines {
+                    *lines.entry(line).or_default() += 1;
+                }
+            }
+        }
+
+        let mut ranked: Vec<(usize, f32)> = scores.into_iter().collect();
+        ranked.sort_by(|a, b| {
+            b.1.total_cmp(&a.1)
+                .then_with(|| self.files[a.0].path.cmp(&self.files[b.0].path))
+        });
+        ranked.truncate(limit);
+
+        ranked
+            .into_iter()
+            .map(|(id, score)| {
+                let file = &self.files[id];
+                let terms: Vec<String> = found
+                    .remove(&id)
+                    .unwrap_or_default()
+                    .into_iter()
+                    .cloned()
+                    .collect();
+                let mut symbols: Vec<String> = file
+                    .symbols
+                    .iter()
+                    .filter(|(_, names)| names.iter().any(|t| terms.contains(t)))
+                    .map(|(name, _)| name.clone())
+                    .collect();
+                symbols.dedup();
+                symbols.truncate(MAX_HIT_SYMBOLS);
+                let mut lines: Vec<(usize, usize)> = line_terms
+                    .remove(&id)
+                    .unwrap_or_default()
+                    .into_iter()
+                    .collect();
+                lines.sort_by_key(|&(line, count)| (std::cmp::Reverse(count), line));
+                lines.truncate(MAX_HIT_LINES);
+                SearchHit {
+                    path: file.path.clone(),
+                    score,
+                    terms,
+                    symbols,
+                    lines: lines.into_iter().map(|(line, _)| line).collect(),
+                }
+            })
+            .collect()
+    }
+}
+
+/// Symbols a file defines, leaving out its imports
+pub(crate) fn defined_symbols(file: &RepoFile) -> impl Iterator<Item = &Symbol> {
+    file.symbols.iter().filter(|s| s.kind != SymbolKind::Import)
+}
+
+/// Distinct terms of a query, in order
+pub(crate) fn query_terms(query: &str) -> Vec<String> {
+    let mut seen = HashSet::new();
+    terms(query).filter(|t| seen.insert(t.clone())).collect()
+}
+
+/// Normalized search terms of a text
+///
+/// Stop words, single letters and numbers are dropped.
+pub(crate) fn terms(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.split(|c: char| !c.is_alphanumeric())
+        .flat_map(split_case)
+        .map(str::to_lowercase)
+        .filter(|word| word.chars().count() > 1 && !word.chars().all(|c| c.is_ascii_digit()))
+        .filter(|word| !STOP_WORDS.contains(&word.as_str()))
+        .map(|word| stem(&word).to_owned())
+}
+
+/// Split an identifier at camelCase boundaries (`HTTPServer` is `HTTP`, `Server`)
+fn split_case(word: &str) -> Vec<&str> {
+    let chars: Vec<(usize, char)> = word.char_indices().collect();
+    let mut parts = Vec::new();
+    let mut start = 0;
+    for i in 1..chars.len() {
+        let (index, c) = chars[i];
+        let previous = chars[i - 1].1;
+        let next_lower = chars.get(i + 1).is_some_and(|&(_, n)| n.is_lowercase());
+        if c.is_uppercase() && (previous.is_lowercase() || (previous.is_uppercase() && next_lower))
+        {
+            parts.push(&word[start..index]);
+            start = index;
+        }
+    }
+    parts.push(&word[start..]);
+    parts
+}
+
+/// Strip one common suffix, keeping at least four characters
+fn stem(word: &str) -> &str {
+    for suffix in SUFFIXES {
+        if let Some(stem) = word.strip_suffix(suffix) {
+            if stem.len() >= 4 && !(*suffix == "s" && stem.ends_with('s')) {
+                return stem;
+            }
+        }
+    }
+    word
+}
+
+#[cfg(test)]
+#[allow(clippy::str_to_string)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_terms() {
+        let found: Vec<String> =
+            terms("How does HTTPServer authenticate users_by token?").collect();
+        assert_eq!(found, vec!["http", "serv", "authentic", "user", "token"]);
+        let forms: Vec<String> = terms("authentication authenticated process processes").collect();
+        assert_eq!(forms, vec!["authentic", "authentic", "process", "process"]);
+    }
+
+    fn file(path: &str, content: &str, symbols: &[&str]) -> RepoFile {
+        let mut file = RepoFile::new(path, path);
+        file.content = Some(content.to_string());
+        file.symbols = symbols
+            .iter()
+            .map(|&name| Symbol::new(name, SymbolKind::Function))
+            .collect();
+        file
+    }
+
+    #[test]
+    fn test_search_ranks_files_and_lines() {
+        let mut repo = Repository::new("test", "/tmp/test");
+        repo.files.push(file(
+            "src/session.rs",
+            "fn refresh(session) {\n    if session.token.expired() {\n        login()\n    }\n}\n",
+            &["refresh"],
+        ));
+        repo.files.push(file(
+            "src/auth.rs",
+            "// Password checks\nfn authenticate(user, password) {\n    check(password);\n    issue_token(user)\n}\n",
+            &["authenticate", "issue_token"],
+        ));
+        repo.files
+            .push(file("src/render.rs", "fn draw(canvas) {}\n", &["draw"]));
+        let index = SearchIndex::build(&repo);
+        assert_eq!(index.len(), 3);
+
+        let hits = index.search("password token", 10);
+        let paths: Vec<&str> = hits.iter().map(|h| h.path.as_str()).collect();
+        assert_eq!(paths, vec!["src/auth.rs", "src/session.rs"]);
+        assert_eq!(hits[0].terms, vec!["password", "token"]);
+        assert_eq!(hits[0].symbols, vec!["issue_token"]);
+        assert_eq!(hits[0].lines, vec![1, 2, 3, 4]);
+        assert_eq!(hits[1].lines, vec![2]);
+
+        assert_eq!(index.search("password token", 1).len(), 1);
+        assert!(index.search("kubernetes", 10).is_empty());
+    }
+}
@@ -1,50 +1,73 @@
 //! Semantic analysis module for code embeddings
 //!
 //! This module provides semantic code understanding through embeddings,
-//! enabling similarity search and intelligent code chunking.
+//! enabling similarity search and semantic compression.
 //!
-//! **Note**: This module currently provides stub implementations.
-//! Full implementation using candle for local embeddings is planned
-//! for a future release when the `embeddings` feature is enabled.
+//! Embeddings are computed locally by feature hashing: identifier tokens and
+//! token bigrams are hashed into a fixed number of signed buckets and the
+//! vector is normalized, so near-identical code lands close together without
+//! any model download.
+//!
+//! [`SemanticCompressor`] uses them for [`CompressionLevel::Semantic`]: it
+//! embeds every function body in the repository, clusters near-duplicates
+//! and keeps one representative per cluster. The others are replaced by a
+//! comment pointing at the representative.
+//!
+//! [`CompressionLevel::Semantic`]: crate::types::CompressionLevel::Semantic
+
+use crate::compress::body_range;
+use crate::languages;
+use crate::transform::rewrite_lines;
+use crate::types::{Repository, SymbolKind};
+use anyhow::{bail, Result};
+use std::borrow::Cow;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+
+/// Dimensions of an embedding
+pub const EMBEDDING_DIMENSIONS: usize = 384;
 
-use anyhow::Result;
+/// Default cosine similarity above which two bodies are near-duplicates
+pub const DEFAULT_SIMILARITY_THRESHOLD: f32 = 0.92;
+
+/// Bodies shorter than this many lines are too generic to cluster
+const MIN_BODY_LINES: usize = 3;
 
 /// Semantic analyzer using code embeddings
-///
-/// Currently provides stub implementations that return placeholder values.
-/// Full implementation planned for future release.
 #[derive(Debug)]
 pub struct SemanticAnalyzer {
-    _model_path: Option<String>,
+    model_path: Option<String>,
 }
 
 impl SemanticAnalyzer {
     /// Create a new semantic analyzer
     pub fn new() -> Self {
-        Self { _model_path: None }
+        Self { model_path: None }
     }
 
     /// Create a semantic analyzer with a custom model path
+    ///
+    /// Loading models is not supported yet, so [`SemanticAnalyzer::embed`]
+    /// fails for an analyzer created this way.
     pub fn with_model(model_path: &str) -> Self {
-        Self { _model_path: Some(model_path.to_string()) }
+        Self { model_path: Some(model_path.to_owned()) }
     }
 
-    /// Generate embeddings for code content
+    /// Generate the embedding of code content
     ///
-    /// **Stub implementation**: Returns a zero vector of 384 dimensions.
-    /// Full implementation will use candle for local embedding generation.
-    pub fn embed(&self, _content: &str) -> Result<Vec<f32>> {
-        // Stub: Returns zero vector. Full implementation would use candle.
-        Ok(vec![0.0; 384])
+    /// The vector has [`EMBEDDING_DIMENSIONS`] entries and unit length, or is
+    /// all zeros for content without any token.
+    pub fn embed(&self, content: &str) -> Result<Vec<f32>> {
+        if let Some(path) = &self.model_path {
+            bail!("embedding models are not supported yet: {}", path);
+        }
+        Ok(hashed_embedding(content))
     }
 
-    /// Calculate similarity between two code snippets
-    ///
-    /// **Stub implementation**: Always returns 0.0.
-    /// Full implementation will compute cosine similarity of embeddings.
-    pub fn similarity(&self, _a: &str, _b: &str) -> Result<f32> {
-        // Stub: Returns 0.0. Full implementation would compute cosine similarity.
-        Ok(0.0)
+    /// Cosine similarity between two code snippets, from -1.0 to 1.0
+    pub fn similarity(&self, a: &str, b: &str) -> Result<f32> {
+        Ok(cosine(&self.embed(a)?, &self.embed(b)?))
     }
 }
 
@@ -54,13 +77,248 @@ impl Default for SemanticAnalyzer {
     }
 }
 
+/// Feature-hashed embedding of identifier tokens and token bigrams
+fn hashed_embedding(content: &str) -> Vec<f32> {
+    let mut vector = vec![0.0f32; EMBEDDING_DIMENSIONS];
+    let tokens: Vec<&str> = content
+        .split(|c: char| !c.is_alphanumeric() && c != '_')
+        .filter(|t| !t.is_empty())
+        .collect();
+    let mut add = |feature: &dyn Fn(&mut DefaultHasher)| {
+        let mut hasher = DefaultHasher::new();
+        feature(&mut hasher);
+        let hash = hasher.finish();
+        let sign = if hash & 1 == 0 { 1.0 } else { -1.0 };
+        vector[(hash >> 1) as usize % EMBEDDING_DIMENSIONS] += sign;
+    };
+    for token in &tokens {
+        add(&|h| token.hash(h));
+    }
+    for pair in tokens.windows(2) {
+        add(&|h| pair.hash(h));
+    }
+
+    let norm = vector.iter().map(|v| v * v).sum::<f32>().sqrt();
+    if norm > 0.0 {
+        vector.iter_mut().for_each(|v| *v /= norm);
+    }
+    vector
+}
+
+/// Cosine similarity of two unit vectors
+fn cosine(a: &[f32], b: &[f32]) -> f32 {
+    a.iter().zip(b).map(|(x, y)| x * y).sum()
+}
+
+/// Collapses near-duplicate function bodies across a repository
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SemanticCompressor {
+    threshold: Option<f32>,
+}
+
+/// What semantic compression did
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SemanticReport {
+    /// Function bodies embedded
+    pub chunks: usize,
+    /// Clusters of near-duplicates with more than one body
+    pub clusters: usize,
+    /// Bodies replaced by a pointer to their cluster's representative
+    pub removed: usize,
+    /// Files with at least one body replaced
+    pub files: usize,
+}
+
+/// A function body considered for clustering
+struct Chunk {
+    file: usize,
+    start: usize,
+    end: usize,
+    declared_at: usize,
+    embedding: Vec<f32>,
+}
+
+impl SemanticCompressor {
+    /// Create a compressor with the default similarity threshold
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the cosine similarity above which bodies are near-duplicates
+    pub fn with_threshold(mut self, threshold: f32) -> Self {
+        self.threshold = Some(threshold);
+        self
+    }
+
+    /// Replace near-duplicate bodies in `repo`
+    ///
+    /// Files earlier in the repository win, so with files sorted by
+    /// importance the representative is the most important copy. Bodies are
+    /// found from the files' symbols, whose line numbers refer to the
+    /// original content, so this works after other transforms too.
+    pub fn compress(&self, repo: &mut Repository) -> SemanticReport {
+        self.collapse(repo).0
+    }
+
+    /// Replace near-duplicate bodies, also returning the indices of the
+    /// files that changed
+    pub(crate) fn collapse(&self, repo: &mut Repository) -> (SemanticReport, BTreeSet<usize>) {
+        let threshold = self.threshold.unwrap_or(DEFAULT_SIMILARITY_THRESHOLD);
+        let chunks = chunks(repo);
+
+        // Greedy clustering: each body joins the first representative it is
+        // close enough to, or becomes a representative itself
+        let mut representatives: Vec<usize> = Vec::new();
+        let mut cluster_sizes: HashMap<usize, usize> = HashMap::new();
+        let mut replaced: HashMap<usize, Vec<(usize, usize, String)>> = HashMap::new();
+        for (i, chunk) in chunks.iter().enumerate() {
+            let twin = representatives
+                .iter()
+                .copied()
+                .find(|&r| cosine(&chunks[r].embedding, &chunk.embedding) >= threshold);
+            let Some(r) = twin else {
+                representatives.push(i);
+                continue;
+            };
+            *cluster_sizes.entry(r).or_default() += 1;
+            let file = &repo.files[chunks[r].file];
+            let note = format!("... same as {}:{}", file.relative_path, chunks[r].declared_at);
+            replaced
+                .entry(chunk.file)
+                .or_default()
+                .push((chunk.start, chunk.end, note));
+        }
+
+        let mut changed = BTreeSet::new();
+        for (index, bodies) in replaced {
+            let file = &mut repo.files[index];
+            let comment = file
+                .language
+                .as_deref()
+                .and_then(languages::lookup)
+                .and_then(|info| info.line_comment)
+                .unwrap_or("//");
+            let mut placed = HashSet::new();
+            let plan: Vec<Option<Option<String>>> = file
+                .numbered_lines()
+                .map(|(number, line)| {
+                    let (start, _, note) = bodies
+                        .iter()
+                        .find(|(s, e, _)| (*s..=*e).contains(&number))?;
+                    let indent = &line[..line.len() - line.trim_start().len()];
+                    Some(
+                        placed
+                            .insert(*start)
+                            .then(|| format!("{}{} {}", indent, comment, note)),
+                    )
+                })
+                .collect();
+            let mut plan = plan.into_iter();
+            if rewrite_lines(file, |line| match plan.next().flatten() {
+                None => Some(Cow::Borrowed(line)),
+                Some(placeholder) => placeholder.map(Cow::Owned),
+            }) {
+                changed.insert(index);
+            }
+        }
+
+        let report = SemanticReport {
+            chunks: chunks.len(),
+            clusters: cluster_sizes.len(),
+            removed: cluster_sizes.values().sum(),
+            files: changed.len(),
+        };
+        (report, changed)
+    }
+}
+
+/// Every multi-line function body in the repository, with its embedding
+fn chunks(repo: &Repository) -> Vec<Chunk> {
+    let mut chunks = Vec::new();
+    for (index, file) in repo.files.iter().enumerate() {
+        let language = file.language.as_deref().unwrap_or_default();
+        let lines: HashMap<usize, &str> = file.numbered_lines().collect();
+        let mut bodies: Vec<(usize, usize, usize)> = file
+            .symbols
+            .iter()
+            .filter(|s| matches!(s.kind, SymbolKind::Function | SymbolKind::Method))
+            .filter(|s| s.start_line > 0 && s.end_line > s.start_line)
+            .filter_map(|s| {
+                let (start, end) = body_range(&lines, language, s)?;
+                Some((start, end, s.start_line as usize))
+            })
+            .collect();
+        bodies.sort_unstable();
+
+        let mut covered = 0;
+        for (start, end, declared_at) in bodies {
+            if start <= covered {
+                continue;
+            }
+            covered = end;
+            let body: Vec<&str> = (start..=end)
+                .filter_map(|n| lines.get(&n).copied())
+                .collect();
+            if body.len() < MIN_BODY_LINES {
+                continue;
+            }
+            let embedding = hashed_embedding(&body.join("\n"));
+            chunks.push(Chunk { file: index, start, end, declared_at, embedding });
+        }
+    }
+    chunks
+}
+
 #[cfg(test)]
+#[allow(clippy::str_to_string)]
 mod tests {
     use super::*;
+    use crate::types::{RepoFile, Symbol};
 
     #[test]
     fn test_analyzer_creation() {
         let analyzer = SemanticAnalyzer::new();
-        assert!(analyzer._model_path.is_none());
+        assert!(analyzer.model_path.is_none());
+    }
+
+    #[test]
+    fn test_similarity() {
+        let analyzer = SemanticAnalyzer::new();
+        let a = "let total = items.iter().map(|i| i.price).sum();\nreturn total;";
+        let b = "let total = items.iter().map(|i| i.price).sum();\nreturn total ;";
+        let c = "connect(host, port)?;\nsend(request)";
+        assert!(analyzer.similarity(a, b).unwrap() > 0.99);
+        assert!(analyzer.similarity(a, c).unwrap() < 0.5);
+        assert!(SemanticAnalyzer::with_model("model.bin").embed(a).is_err());
+    }
+
+    fn file(path: &str, content: &str) -> RepoFile {
+        let mut file = RepoFile::new(path, path);
+        file.language = Some("python".to_string());
+        file.content = Some(content.to_string());
+        let mut symbol = Symbol::new("total", SymbolKind::Function);
+        symbol.start_line = 1;
+        symbol.end_line = content.lines().count() as u32;
+        file.symbols = vec![symbol];
+        file
+    }
+
+    #[test]
+    fn test_keeps_one_representative() {
+        let body = "def total(items):\n    result = 0\n    for item in items:\n        result += item.price\n    return result\n";
+        let other = "def fetch(url):\n    session = open_session()\n    response = session.get(url)\n    return response.json()\n";
+        let mut repo = Repository::new("test", "/tmp/test");
+        repo.files.push(file("a.py", body));
+        repo.files.push(file("b.py", body));
+        repo.files.push(file("c.py", other));
+
+        let report = SemanticCompressor::new().compress(&mut repo);
+        assert_eq!(report, SemanticReport { chunks: 3, clusters: 1, removed: 1, files: 1 });
+        assert_eq!(repo.files[0].content.as_deref(), Some(body));
+        assert_eq!(
+            repo.files[1].content.as_deref(),
+            Some("def total(items):\n    # ... same as a.py:1\n")
+        );
+        assert_eq!(repo.files[2].content.as_deref(), Some(other));
     }
 }
@@ -2,6 +2,22 @@
 //!
 //! This module provides accurate token counts using tiktoken for OpenAI models
 //! and estimation-based counting for other models.
+//!
+//! # Estimation accuracy
+//!
+//! Where a file's tokens are estimated from its size, the characters per
+//! token come from [`LANGUAGE_RATIOS`], tuned against cl100k_base and
+//! o200k_base on the `tune/` half of `engine/tests/token_corpus` (code in ten
+//! languages, several files each). On the held-out `holdout/` half, a
+//! language's files together are estimated within 15% of the exact count,
+//! any single file within 20%, and 20-line chunks within 20% on average. The
+//! `token_accuracy` test reports the held-out errors and fails when they
+//! exceed these bounds; run it with `--ignored --nocapture` to print a table
+//! freshly tuned on `tune/`.
+//!
+//! Claude, Gemini and Llama have no tokenizer available offline, so their
+//! estimates scale the model's own ratio by how dense each language is for
+//! cl100k_base; the bound above is only checked for the GPT encodings.
 
 use std::sync::OnceLock;
 use tiktoken_rs::{cl100k_base, o200k_base, CoreBPE};
@@ -48,12 +64,63 @@ impl TokenModel {
         }
     }
 
+    /// Average characters per token for source code in `language`
+    ///
+    /// Falls back to [`chars_per_token`](Self::chars_per_token) for languages
+    /// without a calibrated ratio.
+    pub fn chars_per_token_for(&self, language: Option<&str>) -> f32 {
+        let Some(ratio) = language.and_then(LanguageRatio::find) else {
+            return self.chars_per_token();
+        };
+        match self {
+            Self::Gpt4 => ratio.cl100k,
+            Self::Gpt4o => ratio.o200k,
+            _ => self.chars_per_token() * ratio.cl100k / CORPUS_CL100K_RATIO,
+        }
+    }
+
     /// Whether this model has an exact tokenizer available
     pub fn has_exact_tokenizer(&self) -> bool {
         matches!(self, Self::Gpt4o | Self::Gpt4)
     }
 }
 
+/// Characters per token of one language's source code
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LanguageRatio {
+    /// Language name as detected by the scanner
+    pub language: &'static str,
+    /// Characters per cl100k_base (GPT-4) token
+    pub cl100k: f32,
+    /// Characters per o200k_base (GPT-4o) token
+    pub o200k: f32,
+}
+
+impl LanguageRatio {
+    /// The calibrated ratio for `language`, if there is one
+    pub fn find(language: &str) -> Option<&'static Self> {
+        LANGUAGE_RATIOS.iter().find(|r| r.language == language)
+    }
+}
+
+/// Characters per token by language, tuned on `engine/tests/token_corpus/tune`
+pub const LANGUAGE_RATIOS: &[LanguageRatio] = &[
+    LanguageRatio { language: "c", cl100k: 3.38, o200k: 3.36 },
+    LanguageRatio { language: "cpp", cl100k: 3.98, o200k: 3.96 },
+    LanguageRatio { language: "csharp", cl100k: 5.43, o200k: 5.19 },
+    LanguageRatio { language: "go", cl100k: 3.76, o200k: 3.76 },
+    LanguageRatio { language: "java", cl100k: 4.61, o200k: 4.50 },
+    LanguageRatio { language: "javascript", cl100k: 3.88, o200k: 3.84 },
+    LanguageRatio { language: "python", cl100k: 4.13, o200k: 4.11 },
+    LanguageRatio { language: "ruby", cl100k: 3.67, o200k: 3.64 },
+    LanguageRatio { language: "rust", cl100k: 4.05, o200k: 4.05 },
+    LanguageRatio { language: "typescript", cl100k: 3.78, o200k: 3.73 },
+];
+
+/// Characters per cl100k_base token over the whole corpus, the baseline
+/// language ratios are compared with for estimated models
+pub const CORPUS_CL100K_RATIO: f32 = 4.07;
+
 /// Global tokenizer instances (lazy initialized, thread-safe)
 static GPT4O_TOKENIZER: OnceLock<CoreBPE> = OnceLock::new();
 static GPT4_TOKENIZER: OnceLock<CoreBPE> = OnceLock::new();
@@ -325,6 +392,48 @@ impl std::iter::Sum for TokenCounts {
     }
 }
 
+impl From<TokenCounts> for crate::types::TokenCounts {
+    fn from(counts: TokenCounts) -> Self {
+        Self {
+            claude: counts.claude,
+            gpt4o: counts.gpt4o,
+            gpt4: counts.gpt4,
+            gemini: counts.gemini,
+            llama: counts.llama,
+        }
+    }
+}
+
+impl From<crate::types::TokenCounts> for TokenCounts {
+    fn from(counts: crate::types::TokenCounts) -> Self {
+        Self {
+            claude: counts.claude,
+            gpt4o: counts.gpt4o,
+            gpt4: counts.gpt4,
+            gemini: counts.gemini,
+            llama: counts.llama,
+        }
+    }
+}
+
+impl From<crate::types::TokenizerModel> for TokenModel {
+    fn from(model: crate::types::TokenizerModel) -> Self {
+        use crate::types::TokenizerModel;
+        match model {
+            TokenizerModel::Claude => Self::Claude,
+            TokenizerModel::Gpt4o => Self::Gpt4o,
+            TokenizerModel::Gpt4 => Self::Gpt4,
+            TokenizerModel::Gemini => Self::Gemini,
+            TokenizerModel::Llama => Self::Llama,
+        }
+    }
+}
+
+/// Estimate the tokens of `bytes` bytes of `language` source code
+pub fn estimate_source(bytes: u64, model: TokenModel, language: Option<&str>) -> u32 {
+    (bytes as f32 / model.chars_per_token_for(language)) as u32
+}
+
 /// Quick estimation without creating a Tokenizer instance
 pub fn quick_estimate(text: &str, model: TokenModel) -> u32 {
     if text.is_empty() {
@@ -429,6 +538,6 @@ mod tests {
 
         // GPT-4o with o200k should usually be most efficient
         assert!(count > 0);
-        println!("Most efficient: {:?} with {} tokens", model, count);
+        assert_eq!(tokenizer.count(text, model), count);
     }
 }
@@ -0,0 +1,517 @@
+//! Repository topic inference
+//!
+//! A cheap first look at what kind of project a repository is (a web backend,
+//! a CLI tool, ML training code, infrastructure as code, a mobile app, ...),
+//! so packing defaults and config conditionals can be routed by it.
+//!
+//! [`infer_topics`] walks the tree once without reading source files. It
+//! collects three kinds of evidence and scores them against the [`RULES`]
+//! table:
+//!
+//! - **dependencies** declared in manifests (`package.json`, `requirements*.txt`,
+//!   `pyproject.toml`, `Cargo.toml`, `go.mod`, `Gemfile`, `pom.xml`,
+//!   `build.gradle`, `pubspec.yaml`)
+//! - **paths**: file and directory names such as `manage.py`, `Chart.yaml`
+//!   or `AndroidManifest.xml`
+//! - **extensions** such as `.tf` or `.ipynb`
+//!
+//! Every matching rule counts once; a topic is reported when its score
+//! reaches [`THRESHOLD`].
+
+use ignore::WalkBuilder;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use std::fmt;
+use std::path::Path;
+
+/// Score a topic needs to be reported
+pub const THRESHOLD: u32 = 3;
+
+/// Entries looked at before the walk stops
+const MAX_ENTRIES: usize = 20_000;
+
+/// Kind of project a repository is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Topic {
+    /// HTTP APIs and server-rendered web applications
+    WebBackend,
+    /// Browser applications
+    WebFrontend,
+    /// Command-line tools
+    CliTool,
+    /// Model training and ML research code
+    MachineLearning,
+    /// Terraform, Helm, Kubernetes, Pulumi, Ansible and the like
+    InfraAsCode,
+    /// Android, iOS and cross-platform mobile apps
+    MobileApp,
+}
+
+impl Topic {
+    /// Every topic, in declaration order
+    pub const ALL: [Topic; 6] = [
+        Self::WebBackend,
+        Self::WebFrontend,
+        Self::CliTool,
+        Self::MachineLearning,
+        Self::InfraAsCode,
+        Self::MobileApp,
+    ];
+
+    /// Kebab-case id, as used in config files
+    pub fn id(&self) -> &'static str {
+        match self {
+            Self::WebBackend => "web-backend",
+            Self::WebFrontend => "web-frontend",
+            Self::CliTool => "cli-tool",
+            Self::MachineLearning => "machine-learning",
+            Self::InfraAsCode => "infra-as-code",
+            Self::MobileApp => "mobile-app",
+        }
+    }
+
+    /// Parse a kebab-case id
+    pub fn from_id(id: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|t| t.id() == id)
+    }
+}
+
+impl fmt::Display for Topic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.id())
+    }
+}
+
+/// Evidence a rule looks for
+#[derive(Debug, Clone, Copy)]
+pub enum Signal {
+    /// A declared dependency, by package name or last module path segment
+    Dependency(&'static str),
+    /// A file or directory with this name anywhere in the tree
+    Path(&'static str),
+    /// A file with this extension
+    Extension(&'static str),
+}
+
+/// One row of the rule table
+#[derive(Debug, Clone, Copy)]
+pub struct Rule {
+    /// Topic the evidence points to
+    pub topic: Topic,
+    /// Evidence to look for
+    pub signal: Signal,
+    /// Points added when the evidence is present
+    pub weight: u32,
+}
+
+const fn rule(topic: Topic, signal: Signal, weight: u32) -> Rule {
+    Rule { topic, signal, weight }
+}
+
+use Signal::{Dependency as Dep, Extension as Ext, Path as P};
+use Topic::*;
+
+/// The rule table; a framework dependency alone is enough for its topic,
+/// structural hints need to add up
+pub const RULES: &[Rule] = &[
+    // Web backends
+    rule(WebBackend, Dep("express"), 3),
+    rule(WebBackend, Dep("fastify"), 3),
+    rule(WebBackend, Dep("koa"), 3),
+    rule(WebBackend, Dep("@nestjs/core"), 3),
+    rule(WebBackend, Dep("django"), 3),
+    rule(WebBackend, Dep("flask"), 3),
+    rule(WebBackend, Dep("fastapi"), 3),
+    rule(WebBackend, Dep("rails"), 3),
+    rule(WebBackend, Dep("sinatra"), 3),
+    rule(WebBackend, Dep("spring-boot-starter-web"), 3),
+    rule(WebBackend, Dep("actix-web"), 3),
+    rule(WebBackend, Dep("axum"), 3),
+    rule(WebBackend, Dep("rocket"), 3),
+    rule(WebBackend, Dep("warp"), 3),
+    rule(WebBackend, Dep("gin"), 3),
+    rule(WebBackend, Dep("echo"), 3),
+    rule(WebBackend, Dep("fiber"), 3),
+    rule(WebBackend, P("manage.py"), 2),
+    rule(WebBackend, P("migrations"), 1),
+    rule(WebBackend, P("controllers"), 1),
+    rule(WebBackend, P("routes"), 1),
+    // Web frontends
+    rule(WebFrontend, Dep("react"), 3),
+    rule(WebFrontend, Dep("vue"), 3),
+    rule(WebFrontend, Dep("svelte"), 3),
+    rule(WebFrontend, Dep("@angular/core"), 3),
+    rule(WebFrontend, Dep("next"), 3),
+    rule(WebFrontend, Dep("nuxt"), 3),
+    rule(WebFrontend, Dep("vite"), 2),
+    rule(WebFrontend, Ext("tsx"), 1),
+    rule(WebFrontend, Ext("jsx"), 1),
+    rule(WebFrontend, Ext("vue"), 2),
+    rule(WebFrontend, Ext("svelte"), 2),
+    rule(WebFrontend, P("index.html"), 1),
+    // CLI tools
+    rule(CliTool, Dep("clap"), 3),
+    rule(CliTool, Dep("structopt"), 3),
+    rule(CliTool, Dep("argh"), 3),
+    rule(CliTool, Dep("cobra"), 3),
+    rule(CliTool, Dep("urfave/cli"), 3),
+    rule(CliTool, Dep("click"), 3),
+    rule(CliTool, Dep("typer"), 3),
+    rule(CliTool, Dep("commander"), 3),
+    rule(CliTool, Dep("yargs"), 3),
+    rule(CliTool, Dep("@oclif/core"), 3),
+    rule(CliTool, Dep("thor"), 3),
+    rule(CliTool, P("__main__.py"), 1),
+    rule(CliTool, P("bin"), 1),
+    rule(CliTool, P("cmd"), 1),
+    // Machine learning
+    rule(MachineLearning, Dep("torch"), 3),
+    rule(MachineLearning, Dep("tensorflow"), 3),
+    rule(MachineLearning, Dep("keras"), 3),
+    rule(MachineLearning, Dep("jax"), 3),
+    rule(MachineLearning, Dep("scikit-learn"), 3),
+    rule(MachineLearning, Dep("transformers"), 3),
+    rule(MachineLearning, Dep("xgboost"), 3),
+    rule(MachineLearning, Dep("lightgbm"), 3),
+    rule(MachineLearning, Dep("pytorch-lightning"), 3),
+    rule(MachineLearning, Dep("tch"), 3),
+    rule(MachineLearning, Dep("candle-core"), 3),
+    rule(MachineLearning, Ext("ipynb"), 2),
+    rule(MachineLearning, P("train.py"), 2),
+    rule(MachineLearning, P("notebooks"), 1),
+    rule(MachineLearning, P("checkpoints"), 1),
+    // Infrastructure as code
+    rule(InfraAsCode, Ext("tf"), 3),
+    rule(InfraAsCode, Ext("tfvars"), 1),
+    rule(InfraAsCode, P("Chart.yaml"), 3),
+    rule(InfraAsCode, P("kustomization.yaml"), 3),
+    rule(InfraAsCode, P("Pulumi.yaml"), 3),
+    rule(InfraAsCode, P("ansible.cfg"), 3),
+    rule(InfraAsCode, P("cdk.json"), 3),
+    rule(InfraAsCode, P("serverless.yml"), 2),
+    rule(InfraAsCode, P("playbooks"), 2),
+    rule(InfraAsCode, P("terraform"), 1),
+    rule(InfraAsCode, P("k8s"), 1),
+    rule(InfraAsCode, Dep("aws-cdk-lib"), 3),
+    rule(InfraAsCode, Dep("pulumi"), 3),
+    // Mobile apps
+    rule(MobileApp, P("AndroidManifest.xml"), 3),
+    rule(MobileApp, Ext("xcodeproj"), 3),
+    rule(MobileApp, P("Podfile"), 2),
+    rule(MobileApp, P("Info.plist"), 1),
+    rule(MobileApp, Dep("react-native"), 3),
+    rule(MobileApp, Dep("expo"), 3),
+    rule(MobileApp, Dep("flutter"), 3),
+    rule(MobileApp, Ext("swift"), 1),
+    rule(MobileApp, Ext("kt"), 1),
+];
+
+/// Evidence collected from a repository
+#[derive(Debug, Default)]
+pub struct Evidence {
+    /// Declared dependency names, lowercased
+    pub dependencies: HashSet<String>,
+    /// File and directory names
+    pub names: HashSet<String>,
+    /// File and directory extensions, lowercased
+    pub extensions: HashSet<String>,
+}
+
+impl Evidence {
+    /// Walk `root` (respecting `.gitignore`) and read its manifests
+    pub fn collect(root: &Path) -> Self {
+        let mut evidence = Self::default();
+        let walker = WalkBuilder::new(root)
+            .hidden(true)
+            .max_depth(Some(8))
+            .build();
+        for entry in walker.filter_map(Result::ok).take(MAX_ENTRIES) {
+            let Some(name) = entry.file_name().to_str() else {
+                continue;
+            };
+            if let Some((_, ext)) = name.rsplit_once('.') {
+                evidence.extensions.insert(ext.to_lowercase());
+            }
+            if is_manifest(name) && entry.file_type().is_some_and(|t| t.is_file()) {
+                if let Ok(content) = std::fs::read_to_string(entry.path()) {
+                    evidence.read_manifest(name, &content);
+                }
+            }
+            evidence.names.insert(name.to_owned());
+        }
+        evidence
+    }
+
+    /// Add the dependencies a manifest declares; other files are ignored
+    pub fn read_manifest(&mut self, name: &str, content: &str) {
+        let deps = match name {
+            "package.json" => package_json_deps(content),
+            "Cargo.toml" => toml_table_keys(content, &["dependencies", "dev-dependencies"]),
+            "pyproject.toml" => pyproject_deps(content),
+            "go.mod" => go_mod_deps(content),
+            "Gemfile" => gemfile_deps(content),
+            "pom.xml" => xml_tag_values(content, "artifactId"),
+            "build.gradle" | "build.gradle.kts" => gradle_deps(content),
+            "pubspec.yaml" => pubspec_deps(content),
+            _ if name.starts_with("requirements") && name.ends_with(".txt") => {
+                requirements_deps(content)
+            },
+            _ => return,
+        };
+        self.dependencies
+            .extend(deps.into_iter().map(|d| d.to_lowercase()));
+    }
+
+    fn matches(&self, signal: Signal) -> bool {
+        match signal {
+            Signal::Dependency(dep) => self.dependencies.iter().any(|d| {
+                d == dep || d.ends_with(&format!("/{}", dep)) || d.contains(&format!("/{}/", dep))
+            }),
+            Signal::Path(name) => self.names.contains(name),
+            Signal::Extension(ext) => self.extensions.contains(ext),
+        }
+    }
+
+    /// Score of every topic with at least one matching rule
+    pub fn scores(&self) -> BTreeMap<Topic, u32> {
+        let mut scores = BTreeMap::new();
+        for rule in RULES.iter().filter(|r| self.matches(r.signal)) {
+            *scores.entry(rule.topic).or_insert(0) += rule.weight;
+        }
+        scores
+    }
+
+    /// Topics at or above [`THRESHOLD`], highest score first
+    pub fn topics(&self) -> Vec<Topic> {
+        let mut scored: Vec<(Topic, u32)> = self
+            .scores()
+            .into_iter()
+            .filter(|&(_, s)| s >= THRESHOLD)
+            .collect();
+        scored.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+        scored.into_iter().map(|(topic, _)| topic).collect()
+    }
+}
+
+fn is_manifest(name: &str) -> bool {
+    matches!(
+        name,
+        "package.json"
+            | "Cargo.toml"
+            | "pyproject.toml"
+            | "go.mod"
+            | "Gemfile"
+            | "pom.xml"
+            | "build.gradle"
+            | "build.gradle.kts"
+            | "pubspec.yaml"
+    ) || (name.starts_with("requirements") && name.ends_with(".txt"))
+}
+
+/// Infer the topics of the repository at `root`
+pub fn infer_topics(root: &Path) -> Vec<Topic> {
+    Evidence::collect(root).topics()
+}
+
+pub(crate) fn package_json_deps(content: &str) -> Vec<String> {
+    let Ok(value) = serde_json::from_str::<serde_json::Value>(content) else {
+        return Vec::new();
+    };
+    ["dependencies", "devDependencies", "peerDependencies"]
+        .iter()
+        .filter_map(|key| value.get(key).and_then(|v| v.as_object()))
+        .flat_map(|deps| deps.keys().cloned())
+        .collect()
+}
+
+/// Keys of `[<section>]`, `[workspace.<section>]` and `[target.*.<section>]` tables
+pub(crate) fn toml_table_keys(content: &str, sections: &[&str]) -> Vec<String> {
+    let Ok(value) = content.parse::<toml::Table>() else {
+        return Vec::new();
+    };
+    let mut keys = Vec::new();
+    let mut collect = |table: &toml::Table| {
+        for section in sections {
+            if let Some(deps) = table.get(*section).and_then(|d| d.as_table()) {
+                keys.extend(deps.keys().cloned());
+            }
+        }
+    };
+    collect(&value);
+    if let Some(workspace) = value.get("workspace").and_then(|w| w.as_table()) {
+        collect(workspace);
+    }
+    if let Some(targets) = value.get("target").and_then(|t| t.as_table()) {
+        targets
+            .values()
+            .filter_map(|t| t.as_table())
+            .for_each(&mut collect);
+    }
+    keys
+}
+
+fn pyproject_deps(content: &str) -> Vec<String> {
+    let Ok(value) = content.parse::<toml::Table>() else {
+        return Vec::new();
+    };
+    let mut deps: Vec<String> = value
+        .get("project")
+        .and_then(|p| p.get("dependencies"))
+        .and_then(|d| d.as_array())
+        .into_iter()
+        .flatten()
+        .filter_map(|d| d.as_str())
+        .map(requirement_name)
+        .collect();
+    if let Some(poetry) = value.get("tool").and_then(|t| t.get("poetry")) {
+        for section in ["dependencies", "dev-dependencies"] {
+            if let Some(table) = poetry.get(section).and_then(|d| d.as_table()) {
+                deps.extend(table.keys().cloned());
+            }
+        }
+    }
+    deps
+}
+
+fn requirements_deps(content: &str) -> Vec<String> {
+    content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with(['#', '-']))
+        .map(requirement_name)
+        .collect()
+}
+
+/// Package name of a PEP 508 requirement such as `torch>=2.0; python_version > "3.8"`
+fn requirement_name(requirement: &str) -> String {
+    requirement
+        .split(|c: char| !(c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.')))
+        .next()
+        .unwrap_or_default()
+        .to_owned()
+}
+
+pub(crate) fn go_mod_deps(content: &str) -> Vec<String> {
+    let mut deps = Vec::new();
+    let mut in_block = false;
+    for line in content.lines().map(str::trim) {
+        if line.starts_with("require (") {
+            in_block = true;
+        } else if in_block && line.starts_with(')') {
+            in_block = false;
+        } else if let Some(module) = line.strip_prefix("require ") {
+            deps.extend(module.split_whitespace().next().map(str::to_owned));
+        } else if in_block {
+            deps.extend(line.split_whitespace().next().map(str::to_owned));
+        }
+    }
+    deps
+}
+
+fn gemfile_deps(content: &str) -> Vec<String> {
+    content
+        .lines()
+        .filter_map(|line| line.trim().strip_prefix("gem "))
+        .filter_map(|rest| rest.split(['\'', '"']).nth(1))
+        .map(str::to_owned)
+        .collect()
+}
+
+fn xml_tag_values(content: &str, tag: &str) -> Vec<String> {
+    let open = format!("<{}>", tag);
+    let close = format!("</{}>", tag);
+    content
+        .split(&open)
+        .skip(1)
+        .filter_map(|rest| {
+            rest.split_once(&close)
+                .map(|(value, _)| value.trim().to_owned())
+        })
+        .collect()
+}
+
+/// Artifact names of `group:artifact:version` coordinates
+fn gradle_deps(content: &str) -> Vec<String> {
+    content
+        .split(['"', '\''])
+        .filter_map(|s| {
+            let mut parts = s.split(':');
+            let (_group, artifact) = (parts.next()?, parts.next()?);
+            (!artifact.is_empty() && !artifact.contains(' ')).then(|| artifact.to_owned())
+        })
+        .collect()
+}
+
+fn pubspec_deps(content: &str) -> Vec<String> {
+    let Ok(value) = serde_yaml::from_str::<serde_yaml::Value>(content) else {
+        return Vec::new();
+    };
+    ["dependencies", "dev_dependencies"]
+        .iter()
+        .filter_map(|key| value.get(key).and_then(|v| v.as_mapping()))
+        .flat_map(|deps| deps.keys().filter_map(|k| k.as_str().map(str::to_owned)))
+        .collect()
+}
+
+#[cfg(test)]
+#[allow(clippy::str_to_string)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn write(root: &Path, path: &str, content: &str) {
+        let path = root.join(path);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, content).unwrap();
+    }
+
+    #[test]
+    fn test_manifest_dependencies() {
+        let mut evidence = Evidence::default();
+        evidence.read_manifest("requirements-dev.txt", "torch>=2.0\n# comment\n-e .\nnumpy\n");
+        evidence.read_manifest("Cargo.toml", "[dependencies]\nclap = \"4\"\n");
+        evidence
+            .read_manifest("go.mod", "module x\nrequire (\n\tgithub.com/gin-gonic/gin v1.9.1\n)\n");
+        evidence
+            .read_manifest("pyproject.toml", "[project]\ndependencies = [\"FastAPI>=0.100\"]\n");
+        for dep in ["torch", "numpy", "clap", "github.com/gin-gonic/gin", "fastapi"] {
+            assert!(evidence.dependencies.contains(dep), "missing {}", dep);
+        }
+        assert!(evidence.matches(Signal::Dependency("gin")));
+        assert!(!evidence.matches(Signal::Dependency("-e")));
+    }
+
+    #[test]
+    fn test_infer_topics() {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path();
+        write(root, "requirements.txt", "torch\nflask\n");
+        write(root, "notebooks/explore.ipynb", "{}");
+        write(root, "infra/main.tf", "resource \"aws_s3_bucket\" \"b\" {}");
+        write(root, "src/routes/index.py", "");
+
+        let evidence = Evidence::collect(root);
+        let scores = evidence.scores();
+        assert_eq!(scores[&MachineLearning], 6);
+        assert_eq!(scores[&WebBackend], 4);
+        assert_eq!(evidence.topics(), vec![MachineLearning, WebBackend, InfraAsCode]);
+    }
+
+    #[test]
+    fn test_weak_hints_are_not_enough() {
+        let dir = TempDir::new().unwrap();
+        write(dir.path(), "bin/run.sh", "");
+        write(dir.path(), "index.html", "");
+        assert!(infer_topics(dir.path()).is_empty());
+    }
+
+    #[test]
+    fn test_topic_ids() {
+        for topic in Topic::ALL {
+            assert_eq!(Topic::from_id(topic.id()), Some(topic));
+            let json = serde_json::to_string(&topic).unwrap();
+            assert_eq!(json, format!("\"{}\"", topic.id()));
+        }
+    }
+}
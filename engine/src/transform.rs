@@ -0,0 +1,268 @@
+//! Content transforms applied before formatting
+//!
+//! Transforms run on file contents in the [`Repository`] so every output
+//! format sees the same result.
+
+use crate::types::{RepoFile, Repository, TokenCounts};
+use std::borrow::Cow;
+use std::fmt::Write;
+
+/// How to handle lines longer than the limit
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LongLineMode {
+    /// Cut the line and append a `[LINE TRUNCATED: N chars]` marker
+    #[default]
+    Truncate,
+    /// Split the line into several lines of at most the limit
+    ///
+    /// The pieces all keep the wrapped line's original line number.
+    Wrap,
+}
+
+/// Limits line length in file contents
+///
+/// Minified or data-bearing files can contain single lines of 100k+
+/// characters, which bloat output and skew token estimates.
+#[derive(Debug, Clone)]
+pub struct LineLimiter {
+    /// Maximum line length in characters
+    max_line_length: usize,
+    /// What to do with longer lines
+    mode: LongLineMode,
+}
+
+impl LineLimiter {
+    /// Create a limiter that truncates lines longer than `max_line_length` characters
+    pub fn new(max_line_length: usize) -> Self {
+        Self { max_line_length: max_line_length.max(1), mode: LongLineMode::default() }
+    }
+
+    /// Set how long lines are handled
+    pub fn with_mode(mut self, mode: LongLineMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Apply the limit to one file's content, borrowing it when nothing changes
+    pub fn apply<'a>(&self, content: &'a str) -> Cow<'a, str> {
+        // Byte length bounds char count, so short contents need no scan
+        if content.len() <= self.max_line_length
+            || !content
+                .lines()
+                .any(|line| line.chars().count() > self.max_line_length)
+        {
+            return Cow::Borrowed(content);
+        }
+
+        let mut result = String::with_capacity(content.len().min(1 << 20));
+        for segment in content.split_inclusive('\n') {
+            let (line, newline) = match segment.strip_suffix('\n') {
+                Some(line) => (line, "\n"),
+                None => (segment, ""),
+            };
+            let chars = line.chars().count();
+
+            if chars <= self.max_line_length {
+                result.push_str(segment);
+                continue;
+            }
+
+            match self.mode {
+                LongLineMode::Truncate => {
+                    let cut = byte_offset(line, self.max_line_length);
+                    write!(result, "{} [LINE TRUNCATED: {} chars]", &line[..cut], chars).unwrap();
+                },
+                LongLineMode::Wrap => {
+                    let mut rest = line;
+                    while !rest.is_empty() {
+                        let cut = byte_offset(rest, self.max_line_length);
+                        result.push_str(&rest[..cut]);
+                        rest = &rest[cut..];
+                        if !rest.is_empty() {
+                            result.push('\n');
+                        }
+                    }
+                },
+            }
+            result.push_str(newline);
+        }
+
+        Cow::Owned(result)
+    }
+
+    /// Apply the limit to every file in a repository
+    ///
+    /// Token counts of changed files are scaled to the new content size.
+    /// Returns the number of files that changed.
+    pub fn apply_to_repo(&self, repo: &mut Repository) -> usize {
+        let mut changed = 0;
+
+        for file in &mut repo.files {
+            let Some(content) = &file.content else {
+                continue;
+            };
+            let Cow::Owned(limited) = self.apply(content) else {
+                continue;
+            };
+
+            let ratio = limited.len() as f64 / content.len().max(1) as f64;
+            file.token_count = scale_counts(&file.token_count, ratio);
+            if self.mode == LongLineMode::Wrap {
+                file.line_numbers = Some(self.wrapped_line_numbers(file));
+            }
+            file.content = Some(limited);
+            changed += 1;
+        }
+
+        changed
+    }
+
+    /// Original line numbers of the content once long lines are wrapped
+    fn wrapped_line_numbers(&self, file: &RepoFile) -> Vec<u32> {
+        let mut numbers = Vec::new();
+        for (number, line) in file.numbered_lines() {
+            let pieces = line.chars().count().div_ceil(self.max_line_length).max(1);
+            numbers.extend(std::iter::repeat_n(number as u32, pieces));
+        }
+        numbers
+    }
+}
+
+/// Rewrite a file's content line by line, keeping its original line numbers
+///
+/// `f` returns the replacement for each line, or `None` to drop it. Once a
+/// line is dropped the file records the original number of every remaining
+/// line in [`RepoFile::line_numbers`], so line-numbered output still points
+/// into the real file. Numbers recorded by an earlier rewrite carry through.
+/// Returns whether the content changed.
+pub fn rewrite_lines<F>(file: &mut RepoFile, mut f: F) -> bool
+where
+    F: for<'a> FnMut(&'a str) -> Option<Cow<'a, str>>,
+{
+    let Some(content) = &file.content else {
+        return false;
+    };
+
+    let mut result = String::with_capacity(content.len());
+    let mut numbers = Vec::new();
+    let mut changed = false;
+    let mut dropped = false;
+    for (number, line) in file.numbered_lines() {
+        match f(line) {
+            Some(new) => {
+                changed |= new != line;
+                result.push_str(&new);
+                result.push('\n');
+                numbers.push(number as u32);
+            },
+            None => dropped = true,
+        }
+    }
+    if !changed && !dropped {
+        return false;
+    }
+    if !content.ends_with('\n') {
+        result.pop();
+    }
+
+    if dropped || file.line_numbers.is_some() {
+        file.line_numbers = Some(numbers);
+    }
+    file.content = Some(result);
+    true
+}
+
+/// Byte offset of the `n`th character (or the end of `s`)
+fn byte_offset(s: &str, n: usize) -> usize {
+    s.char_indices().nth(n).map_or(s.len(), |(i, _)| i)
+}
+
+pub(crate) fn scale_counts(counts: &TokenCounts, ratio: f64) -> TokenCounts {
+    let scale = |n: u32| (n as f64 * ratio).ceil() as u32;
+    TokenCounts {
+        claude: scale(counts.claude),
+        gpt4o: scale(counts.gpt4o),
+        gpt4: scale(counts.gpt4),
+        gemini: scale(counts.gemini),
+        llama: scale(counts.llama),
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::str_to_string)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_short_lines_are_borrowed() {
+        let limiter = LineLimiter::new(10);
+        assert!(matches!(limiter.apply("short\nlines\n"), Cow::Borrowed(_)));
+    }
+
+    #[test]
+    fn test_truncate_long_line() {
+        let content = format!("fn main() {{}}\n{}\nend\n", "x".repeat(50));
+        let limited = LineLimiter::new(20).apply(&content);
+        assert_eq!(
+            limited,
+            format!("fn main() {{}}\n{} [LINE TRUNCATED: 50 chars]\nend\n", "x".repeat(20))
+        );
+    }
+
+    #[test]
+    fn test_wrap_long_line_on_char_boundaries() {
+        let content = "ééééé";
+        let limited = LineLimiter::new(2)
+            .with_mode(LongLineMode::Wrap)
+            .apply(content);
+        assert_eq!(limited, "éé\néé\né");
+    }
+
+    #[test]
+    fn test_apply_to_repo_scales_tokens() {
+        let mut repo = Repository::new("test", "/tmp/test");
+        let mut file = RepoFile::new("/tmp/test/data.js", "data.js");
+        file.content = Some("a".repeat(1000));
+        file.token_count.claude = 300;
+        repo.files.push(file);
+        repo.files
+            .push(RepoFile::new("/tmp/test/empty.js", "empty.js"));
+
+        assert_eq!(LineLimiter::new(100).apply_to_repo(&mut repo), 1);
+        let file = &repo.files[0];
+        assert!(file
+            .content
+            .as_ref()
+            .unwrap()
+            .ends_with("[LINE TRUNCATED: 1000 chars]"));
+        assert!(file.token_count.claude < 50);
+    }
+
+    #[test]
+    fn test_rewrite_lines_keeps_original_numbers() {
+        let mut file = RepoFile::new("/tmp/test/main.rs", "main.rs");
+        file.content = Some("// header\nfn main() {\n\n    run(); // go\n}\n".to_string());
+
+        assert!(rewrite_lines(&mut file, |line| {
+            (!line.trim().is_empty()).then_some(Cow::Borrowed(line))
+        }));
+        assert!(rewrite_lines(&mut file, |line| match line.find("//") {
+            Some(0) => None,
+            Some(i) => Some(Cow::Borrowed(line[..i].trim_end())),
+            None => Some(Cow::Borrowed(line)),
+        }));
+        assert_eq!(file.content.as_deref(), Some("fn main() {\n    run();\n}\n"));
+        let numbers: Vec<usize> = file.numbered_lines().map(|(n, _)| n).collect();
+        assert_eq!(numbers, vec![2, 4, 5]);
+
+        assert!(!rewrite_lines(&mut file, |line| Some(Cow::Borrowed(line))));
+
+        let mut repo = Repository::new("test", "/tmp/test");
+        repo.files.push(file);
+        LineLimiter::new(4)
+            .with_mode(LongLineMode::Wrap)
+            .apply_to_repo(&mut repo);
+        let numbers: Vec<usize> = repo.files[0].numbered_lines().map(|(n, _)| n).collect();
+        assert_eq!(numbers, vec![2, 2, 2, 4, 4, 4, 5]);
+    }
+}
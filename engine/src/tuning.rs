@@ -0,0 +1,464 @@
+//! Configuration suggestions from a log of past queries
+//!
+//! Given the questions or tasks users actually brought to packs, scores every
+//! file against each query's vocabulary and reports what a tuned config
+//! would keep:
+//!
+//! - **focus files**: the files that answer the most queries
+//! - **include patterns**: the directories those files live in
+//! - **budget split**: tokens for focus files, the rest of the included
+//!   directories and the repository map
+//!
+//! Scoring weighs query terms by how rare they are across the repository
+//! (inverse document frequency), and symbol names over path components.
+//! Each query contributes its best few files, so one vague query can't
+//! claim the whole repository.
+
+use crate::types::{RepoFile, Repository, Symbol, SymbolKind, TokenizerModel};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+/// Files each query contributes to the report
+const FILES_PER_QUERY: usize = 5;
+
+/// Tokens reserved for the repository map
+const MAP_TOKENS: u32 = 2000;
+
+/// Words too common in queries to say anything about a file
+const STOP_WORDS: &[&str] = &[
+    "about", "add", "all", "and", "are", "can", "does", "fix", "for", "from", "get", "has", "have",
+    "how", "into", "its", "make", "not", "should", "that", "the", "then", "this", "use", "was",
+    "what", "when", "where", "which", "why", "will", "with", "work", "works", "you",
+];
+
+/// A file that answers some of the queries
+#[derive(Debug, Clone, Serialize)]
+pub struct FileSuggestion {
+    /// Relative file path
+    pub path: String,
+    /// Number of queries ranking this file among their best
+    pub queries: usize,
+    /// Summed relevance score across queries
+    pub score: f64,
+    /// Token count for the target model
+    pub tokens: u32,
+    /// Symbols whose names matched query terms
+    pub symbols: Vec<String>,
+}
+
+/// An include pattern covering matched files
+#[derive(Debug, Clone, Serialize)]
+pub struct PatternSuggestion {
+    /// Glob pattern, `dir/**` or a root-level file
+    pub pattern: String,
+    /// Number of query matches in files under the pattern
+    pub queries: usize,
+    /// Tokens of all files under the pattern
+    pub tokens: u32,
+}
+
+/// Suggested token budget, split by what it pays for
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct BudgetSplit {
+    /// Focus files in full
+    pub focus: u32,
+    /// Other files under the include patterns
+    pub context: u32,
+    /// Repository map
+    pub map: u32,
+}
+
+impl BudgetSplit {
+    /// Sum of all parts
+    pub fn total(&self) -> u32 {
+        self.focus + self.context + self.map
+    }
+}
+
+/// Tuning suggestions for one repository and query log
+#[derive(Debug, Clone, Serialize)]
+pub struct TuningReport {
+    /// Number of queries read
+    pub queries: usize,
+    /// Queries no file matched
+    pub unmatched: Vec<String>,
+    /// Files to pass as `--focus`, most useful first
+    pub focus: Vec<FileSuggestion>,
+    /// Patterns for `scan.include`, most useful first
+    pub include: Vec<PatternSuggestion>,
+    /// Suggested budget
+    pub budget: BudgetSplit,
+}
+
+impl TuningReport {
+    /// `.infiniloom.yaml` snippet applying the suggestions
+    pub fn config_snippet(&self) -> String {
+        let mut out = String::new();
+        if !self.include.is_empty() {
+            out.push_str("scan:\n  include:\n");
+            for pattern in &self.include {
+                out.push_str(&format!("    - \"{}\"\n", pattern.pattern));
+            }
+        }
+        out.push_str(&format!("output:\n  token_budget: {}\n", self.budget.total()));
+        out
+    }
+}
+
+/// Scores files against a query log and suggests config
+#[derive(Debug, Clone)]
+pub struct TuningAdvisor {
+    /// Model whose token counts size the budget
+    model: TokenizerModel,
+    /// Most focus files to suggest
+    max_focus: usize,
+    /// Most include patterns to suggest
+    max_patterns: usize,
+    /// Budget to fit the split into, if any
+    budget: Option<u32>,
+}
+
+impl Default for TuningAdvisor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TuningAdvisor {
+    /// Create an advisor suggesting up to 10 focus files and 5 patterns
+    pub fn new() -> Self {
+        Self { model: TokenizerModel::Claude, max_focus: 10, max_patterns: 5, budget: None }
+    }
+
+    /// Set the model whose token counts are used
+    pub fn with_model(mut self, model: TokenizerModel) -> Self {
+        self.model = model;
+        self
+    }
+
+    /// Set the most focus files to suggest
+    pub fn with_max_focus(mut self, max: usize) -> Self {
+        self.max_focus = max;
+        self
+    }
+
+    /// Fit the suggested split into this many tokens
+    pub fn with_budget(mut self, tokens: u32) -> Self {
+        self.budget = Some(tokens);
+        self
+    }
+
+    /// Score `repo` against `queries` and build the report
+    pub fn advise(&self, repo: &Repository, queries: &[String]) -> TuningReport {
+        let docs: Vec<FileTerms<'_>> = repo.files.iter().map(FileTerms::new).collect();
+
+        // Document frequency of each term
+        let mut df: HashMap<&str, usize> = HashMap::new();
+        for doc in &docs {
+            for term in doc.path.union(&doc.symbols) {
+                *df.entry(term.as_str()).or_default() += 1;
+            }
+        }
+        let idf = |term: &str| {
+            let n = df.get(term).copied().unwrap_or(0);
+            (1.0 + docs.len() as f64 / (n.max(1)) as f64).ln()
+        };
+
+        let mut hits: HashMap<usize, (usize, f64, HashSet<String>)> = HashMap::new();
+        let mut unmatched = Vec::new();
+        for query in queries {
+            let terms = terms(query);
+            let mut scored: Vec<(usize, f64)> = docs
+                .iter()
+                .enumerate()
+                .map(|(i, doc)| {
+                    let score: f64 = terms
+                        .iter()
+                        .map(|t| {
+                            let weight = idf(t);
+                            let symbol = if doc.symbols.contains(t) {
+                                2.0 * weight
+                            } else {
+                                0.0
+                            };
+                            let path = if doc.path.contains(t) { weight } else { 0.0 };
+                            symbol + path
+                        })
+                        .sum();
+                    (i, score)
+                })
+                .filter(|(_, score)| *score > 0.0)
+                .collect();
+            if scored.is_empty() {
+                unmatched.push(query.clone());
+                continue;
+            }
+            scored.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.0.cmp(&b.0)));
+
+            for (i, score) in scored.into_iter().take(FILES_PER_QUERY) {
+                let entry = hits.entry(i).or_default();
+                entry.0 += 1;
+                entry.1 += score;
+                entry.2.extend(docs[i].matched_symbols(&terms));
+            }
+        }
+
+        let mut matched: Vec<FileSuggestion> = hits
+            .into_iter()
+            .map(|(i, (queries, score, symbols))| {
+                let file = &repo.files[i];
+                let mut symbols: Vec<String> = symbols.into_iter().collect();
+                symbols.sort();
+                FileSuggestion {
+                    path: file.relative_path.clone(),
+                    queries,
+                    score,
+                    tokens: file.token_count.get(self.model),
+                    symbols,
+                }
+            })
+            .collect();
+        matched.sort_by(|a, b| {
+            b.queries
+                .cmp(&a.queries)
+                .then(b.score.total_cmp(&a.score))
+                .then(a.path.cmp(&b.path))
+        });
+
+        let include = self.patterns(repo, &matched);
+        let mut focus = matched;
+        focus.truncate(self.max_focus);
+        let budget = self.split(repo, &focus, &include);
+
+        TuningReport { queries: queries.len(), unmatched, focus, include, budget }
+    }
+
+    /// Directories of matched files, by query matches
+    fn patterns(&self, repo: &Repository, matched: &[FileSuggestion]) -> Vec<PatternSuggestion> {
+        let mut by_pattern: BTreeMap<String, usize> = BTreeMap::new();
+        for file in matched {
+            *by_pattern.entry(pattern_for(&file.path)).or_default() += file.queries;
+        }
+
+        // Fold each directory into the outermost suggested one around it
+        let dirs: Vec<String> = by_pattern.keys().cloned().collect();
+        let mut folded: BTreeMap<String, usize> = BTreeMap::new();
+        for (pattern, queries) in by_pattern {
+            let outer = dirs
+                .iter()
+                .filter(|d| covers(d, pattern.trim_end_matches("/**")))
+                .min_by_key(|d| d.len())
+                .cloned()
+                .unwrap_or(pattern);
+            *folded.entry(outer).or_default() += queries;
+        }
+
+        let mut patterns: Vec<PatternSuggestion> = folded
+            .into_iter()
+            .map(|(pattern, queries)| {
+                let tokens = repo
+                    .files
+                    .iter()
+                    .filter(|f| covers(&pattern, &f.relative_path))
+                    .map(|f| f.token_count.get(self.model))
+                    .sum();
+                PatternSuggestion { pattern, queries, tokens }
+            })
+            .collect();
+        patterns.sort_by(|a, b| b.queries.cmp(&a.queries).then(a.pattern.cmp(&b.pattern)));
+        patterns.truncate(self.max_patterns);
+        patterns
+    }
+
+    /// Tokens needed for the suggestions, scaled down to the budget if set
+    fn split(
+        &self,
+        repo: &Repository,
+        focus: &[FileSuggestion],
+        include: &[PatternSuggestion],
+    ) -> BudgetSplit {
+        let focus_paths: HashSet<&str> = focus.iter().map(|f| f.path.as_str()).collect();
+        let focus_tokens: u32 = focus.iter().map(|f| f.tokens).sum();
+        let context: u32 = repo
+            .files
+            .iter()
+            .filter(|f| !focus_paths.contains(f.relative_path.as_str()))
+            .filter(|f| include.iter().any(|p| covers(&p.pattern, &f.relative_path)))
+            .map(|f| f.token_count.get(self.model))
+            .sum();
+        let split = BudgetSplit { focus: focus_tokens, context, map: MAP_TOKENS };
+
+        match self.budget {
+            Some(budget) if split.total() > budget => {
+                // The map is fixed; focus files come before the rest
+                let map = MAP_TOKENS.min(budget / 10);
+                let focus = split.focus.min(budget - map);
+                BudgetSplit { focus, context: budget - map - focus, map }
+            },
+            _ => split,
+        }
+    }
+}
+
+/// Read a query log: one query per line, `#` comments and blank lines skipped
+pub fn parse_queries(text: &str) -> Vec<String> {
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(str::to_owned)
+        .collect()
+}
+
+/// Terms of a file: from its path and from its symbol names
+struct FileTerms<'a> {
+    file: &'a RepoFile,
+    path: HashSet<String>,
+    symbols: HashSet<String>,
+}
+
+impl<'a> FileTerms<'a> {
+    fn new(file: &'a RepoFile) -> Self {
+        let path = terms(&file.relative_path);
+        let symbols = Self::defined(file).flat_map(|s| terms(&s.name)).collect();
+        Self { file, path, symbols }
+    }
+
+    /// Symbols the file defines, leaving out imports
+    fn defined(file: &RepoFile) -> impl Iterator<Item = &Symbol> {
+        file.symbols.iter().filter(|s| s.kind != SymbolKind::Import)
+    }
+
+    /// Names of symbols containing any of `query`
+    fn matched_symbols<'q>(
+        &'q self,
+        query: &'q HashSet<String>,
+    ) -> impl Iterator<Item = String> + 'q {
+        Self::defined(self.file)
+            .filter(|s| terms(&s.name).iter().any(|t| query.contains(t)))
+            .map(|s| s.name.clone())
+    }
+}
+
+/// Lowercase words of `text`, split at punctuation and camelCase humps,
+/// without stop words and words under three letters
+fn terms(text: &str) -> HashSet<String> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut prev_lower = false;
+    for c in text.chars() {
+        if !c.is_alphanumeric() {
+            words.push(std::mem::take(&mut word));
+            prev_lower = false;
+            continue;
+        }
+        if c.is_uppercase() && prev_lower {
+            words.push(std::mem::take(&mut word));
+        }
+        prev_lower = c.is_lowercase() || c.is_ascii_digit();
+        word.extend(c.to_lowercase());
+    }
+    words.push(word);
+
+    words
+        .into_iter()
+        .filter(|w| w.len() >= 3 && !STOP_WORDS.contains(&w.as_str()))
+        .collect()
+}
+
+/// Include pattern for a file: its directory, or the file itself at the root
+fn pattern_for(path: &str) -> String {
+    match path.rsplit_once('/') {
+        Some((dir, _)) => format!("{}/**", dir),
+        None => path.to_owned(),
+    }
+}
+
+/// Whether a pattern from [`pattern_for`] covers `path`
+fn covers(pattern: &str, path: &str) -> bool {
+    match pattern.strip_suffix("/**") {
+        Some(dir) => path.starts_with(dir) && path[dir.len()..].starts_with('/'),
+        None => pattern == path,
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::str_to_string)]
+mod tests {
+    use super::*;
+    use crate::types::{RepoMetadata, TokenCounts};
+
+    fn file(path: &str, tokens: u32, symbols: &[&str]) -> RepoFile {
+        RepoFile {
+            path: path.into(),
+            relative_path: path.to_string(),
+            language: Some("rust".to_string()),
+            size_bytes: 100,
+            token_count: TokenCounts { claude: tokens, ..Default::default() },
+            symbols: symbols
+                .iter()
+                .map(|s| Symbol::new(*s, SymbolKind::Function))
+                .collect(),
+            importance: 0.5,
+            ..Default::default()
+        }
+    }
+
+    fn repo() -> Repository {
+        Repository {
+            name: "app".to_string(),
+            path: "/app".into(),
+            files: vec![
+                file("src/auth/session.rs", 400, &["refresh_token", "SessionStore"]),
+                file("src/auth/login.rs", 300, &["login", "verify_password"]),
+                file("src/auth/oauth/google.rs", 200, &["exchange_token"]),
+                file("src/billing/invoice.rs", 500, &["render_invoice"]),
+                file("src/main.rs", 100, &["main"]),
+                file("README.md", 50, &[]),
+            ],
+            metadata: RepoMetadata::default(),
+        }
+    }
+
+    #[test]
+    fn test_terms() {
+        let t = terms("How does refreshToken expire in SessionStore?");
+        for word in ["refresh", "token", "expire", "session", "store"] {
+            assert!(t.contains(word), "{:?}", t);
+        }
+        assert!(!t.contains("how") && !t.contains("does") && !t.contains("in"));
+    }
+
+    #[test]
+    fn test_advise() {
+        let queries = parse_queries(
+            "# from last week\nwhy does the session token refresh fail?\n\nlogin with password\nwhere is the invoice rendered\nkubernetes helm charts\n",
+        );
+        assert_eq!(queries.len(), 4);
+
+        let report = TuningAdvisor::new().advise(&repo(), &queries);
+        assert_eq!(report.queries, 4);
+        assert_eq!(report.unmatched, vec!["kubernetes helm charts".to_string()]);
+        assert_eq!(report.focus[0].path, "src/auth/session.rs");
+        assert!(report.focus[0]
+            .symbols
+            .contains(&"refresh_token".to_string()));
+        assert!(!report.focus.iter().any(|f| f.path == "src/main.rs"));
+
+        assert_eq!(report.include[0].pattern, "src/auth/**");
+        // Nested directories fold into the outer one
+        assert_eq!(report.include[0].tokens, 900);
+        assert!(!report
+            .include
+            .iter()
+            .any(|p| p.pattern == "src/auth/oauth/**"));
+        assert_eq!(report.budget.map, MAP_TOKENS);
+        assert_eq!(report.budget.total(), report.budget.focus + report.budget.context + 2000);
+        assert!(report.config_snippet().contains("    - \"src/auth/**\"\n"));
+
+        // Fitted into a budget, focus files keep their share first
+        let report = TuningAdvisor::new()
+            .with_budget(1000)
+            .advise(&repo(), &queries);
+        assert_eq!(report.budget, BudgetSplit { focus: 900, context: 0, map: 100 });
+    }
+}
@@ -1,7 +1,10 @@
 //! Core type definitions for Infiniloom
 
+use crate::commands::ProjectCommand;
+use crate::topics::Topic;
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
 
 /// A scanned repository
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -40,16 +43,39 @@ impl Repository {
             .collect()
     }
 
+    /// Members of the Cargo, npm, yarn, pnpm or Go workspaces in the repository
+    pub fn packages(&self) -> Vec<crate::packages::Package> {
+        crate::packages::detect_packages(self)
+    }
+
     /// Get files sorted by importance
     pub fn files_by_importance(&self) -> Vec<&RepoFile> {
         let mut files: Vec<_> = self.files.iter().collect();
         files.sort_by(|a, b| b.importance.partial_cmp(&a.importance).unwrap());
         files
     }
+
+    /// File by its path relative to the repository root, or its absolute path
+    pub fn file(&self, path: impl AsRef<Path>) -> Option<&RepoFile> {
+        let path = path.as_ref();
+        self.files
+            .iter()
+            .find(|f| Path::new(&f.relative_path) == path || f.path == path)
+    }
+
+    /// First symbol in `path` called `name` and the region of the file it covers
+    ///
+    /// `name` may be qualified with its parent, as in `Parser.parse` or
+    /// `Parser::parse`.
+    pub fn find_symbol(&self, path: impl AsRef<Path>, name: &str) -> Option<SymbolSpan<'_>> {
+        let file = self.file(path)?;
+        let symbol = file.symbols.iter().find(|s| s.matches_name(name))?;
+        Some(file.span(symbol))
+    }
 }
 
 /// A single file in the repository
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct RepoFile {
     /// Absolute path to file
     pub path: PathBuf,
@@ -67,6 +93,25 @@ pub struct RepoFile {
     pub importance: f32,
     /// File content (may be None to save memory)
     pub content: Option<String>,
+    /// Uncommitted git change to this file, when diffs were requested
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub git_change: Option<GitFileChange>,
+    /// Main reasons for this file's rank, when an explanation was requested
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub rank_factors: Vec<String>,
+    /// Original line number of each content line, when transforms removed lines
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub line_numbers: Option<Vec<u32>>,
+    /// Content was left out for the token budget; the file is represented by
+    /// its symbols and their line ranges instead, see [`RepoFile::outline`]
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub outline_only: bool,
+    /// Ranked first because of an `infiniloom:pin` directive
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub pinned: bool,
+    /// One-line summary from an `infiniloom:summary:` directive
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub summary: Option<String>,
 }
 
 impl RepoFile {
@@ -75,12 +120,8 @@ impl RepoFile {
         Self {
             path: path.into(),
             relative_path: relative_path.into(),
-            language: None,
-            size_bytes: 0,
-            token_count: TokenCounts::default(),
-            symbols: Vec::new(),
             importance: 0.5,
-            content: None,
+            ..Default::default()
         }
     }
 
@@ -93,6 +134,111 @@ impl RepoFile {
     pub fn filename(&self) -> &str {
         self.path.file_name().and_then(|n| n.to_str()).unwrap_or("")
     }
+
+    /// Lines of the content paired with their line numbers in the original file
+    pub fn numbered_lines(&self) -> impl Iterator<Item = (usize, &str)> {
+        let numbers = self.line_numbers.as_deref().unwrap_or(&[]);
+        self.content
+            .as_deref()
+            .unwrap_or("")
+            .lines()
+            .enumerate()
+            .map(move |(i, line)| (numbers.get(i).map_or(i + 1, |&n| n as usize), line))
+    }
+
+    /// One `start-end signature` line per symbol, imports left out
+    ///
+    /// Symbols without a signature show their kind and name instead.
+    pub fn outline(&self) -> Vec<String> {
+        self.symbols
+            .iter()
+            .filter(|s| s.kind != SymbolKind::Import)
+            .map(|s| {
+                let text = match &s.signature {
+                    Some(signature) => signature.trim().to_owned(),
+                    None => format!("{} {}", s.kind.name(), s.name),
+                };
+                format!("{}-{} {}", s.start_line, s.end_line, text)
+            })
+            .collect()
+    }
+
+    /// Drop the content and keep only the outline, if the file has one
+    ///
+    /// Returns whether the file can still be shown.
+    pub fn reduce_to_outline(&mut self) -> bool {
+        self.content = None;
+        self.line_numbers = None;
+        self.outline_only = self.symbols.iter().any(|s| s.kind != SymbolKind::Import);
+        self.outline_only
+    }
+
+    /// Innermost symbol whose line range contains `line` (1-indexed)
+    pub fn symbol_at(&self, line: u32) -> Option<SymbolSpan<'_>> {
+        self.symbols
+            .iter()
+            .filter(|s| s.start_line <= line && line <= s.end_line)
+            .min_by_key(|s| s.line_count())
+            .map(|s| self.span(s))
+    }
+
+    /// The region of this file covered by `symbol`
+    pub fn span<'a>(&'a self, symbol: &'a Symbol) -> SymbolSpan<'a> {
+        SymbolSpan {
+            file: self,
+            symbol,
+            start_line: symbol.start_line,
+            end_line: symbol.end_line,
+            bytes: self.byte_range(symbol.start_line, symbol.end_line),
+        }
+    }
+
+    /// Byte offsets in the content of the lines from `start_line` to
+    /// `end_line`, inclusive, without the last line's line break
+    ///
+    /// Lines are numbered as in the original file, so lines removed by
+    /// transforms are skipped. `None` without content or if none of the lines
+    /// are left.
+    pub fn byte_range(&self, start_line: u32, end_line: u32) -> Option<Range<usize>> {
+        let content = self.content.as_deref()?;
+        let numbers = self.line_numbers.as_deref().unwrap_or(&[]);
+        let mut range: Option<Range<usize>> = None;
+        let mut offset = 0;
+        for (i, line) in content.split_inclusive('\n').enumerate() {
+            let number = numbers.get(i).map_or(i as u32 + 1, |&n| n);
+            if (start_line..=end_line).contains(&number) {
+                let end = offset + line.trim_end_matches(['\n', '\r']).len();
+                range = Some(range.map_or(offset, |r| r.start)..end);
+            }
+            offset += line.len();
+        }
+        range
+    }
+}
+
+/// A symbol and the exact region of its file it covers, for highlighting or
+/// extracting the same lines Infiniloom packs and chunks by
+#[derive(Debug, Clone)]
+pub struct SymbolSpan<'a> {
+    /// File containing the symbol
+    pub file: &'a RepoFile,
+    /// The symbol
+    pub symbol: &'a Symbol,
+    /// First line (1-indexed)
+    pub start_line: u32,
+    /// Last line (1-indexed, inclusive)
+    pub end_line: u32,
+    /// Byte offsets of those lines in the file's content, see
+    /// [`RepoFile::byte_range`]
+    pub bytes: Option<Range<usize>>,
+}
+
+impl<'a> SymbolSpan<'a> {
+    /// Text of the symbol's lines, if the file has content
+    pub fn text(&self) -> Option<&'a str> {
+        let content = self.file.content.as_deref()?;
+        content.get(self.bytes.clone()?)
+    }
 }
 
 /// Token counts for multiple models
@@ -199,6 +345,22 @@ impl Symbol {
             1
         }
     }
+
+    /// Whether this symbol is called `name`, or `Parent.name` / `Parent::name`
+    /// when it has a parent
+    pub fn matches_name(&self, name: &str) -> bool {
+        if self.name == name {
+            return true;
+        }
+        let Some(parent) = self.parent.as_deref() else {
+            return false;
+        };
+        [".", "::"].iter().any(|sep| {
+            name.strip_prefix(parent)
+                .and_then(|rest| rest.strip_prefix(sep))
+                .is_some_and(|rest| rest == self.name)
+        })
+    }
 }
 
 /// Kind of code symbol
@@ -221,6 +383,27 @@ pub enum SymbolKind {
 }
 
 impl SymbolKind {
+    /// Parse a name produced by [`SymbolKind::name`]
+    pub fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "function" => Self::Function,
+            "method" => Self::Method,
+            "class" => Self::Class,
+            "interface" => Self::Interface,
+            "struct" => Self::Struct,
+            "enum" => Self::Enum,
+            "constant" => Self::Constant,
+            "variable" => Self::Variable,
+            "import" => Self::Import,
+            "export" => Self::Export,
+            "type" => Self::TypeAlias,
+            "module" => Self::Module,
+            "trait" => Self::Trait,
+            "macro" => Self::Macro,
+            _ => return None,
+        })
+    }
+
     /// Get human-readable name
     pub fn name(&self) -> &'static str {
         match self {
@@ -265,6 +448,12 @@ pub struct RepoMetadata {
     pub directory_structure: Option<String>,
     /// External dependencies (packages/libraries)
     pub external_dependencies: Vec<String>,
+    /// Build, test and run commands declared by the repository
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub commands: Vec<ProjectCommand>,
+    /// Inferred kinds of project (web backend, CLI tool, ...)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub topics: Vec<Topic>,
     /// Git history (commits and changes) - for structured output
     pub git_history: Option<GitHistory>,
 }
@@ -291,10 +480,13 @@ pub struct GitCommitInfo {
     pub short_hash: String,
     /// Author name
     pub author: String,
-    /// Commit date (YYYY-MM-DD)
+    /// Commit date, ISO-8601 UTC unless local time is configured
     pub date: String,
     /// Commit message
     pub message: String,
+    /// Symbols the commit added, modified or removed
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub symbols: Vec<SymbolChange>,
 }
 
 /// Git history information for structured output
@@ -315,8 +507,76 @@ pub struct GitChangedFile {
     pub status: String,
 }
 
-/// Compression level for output
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+/// Uncommitted change to a single file, with a compact diff stat
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GitFileChange {
+    /// Change status (A=Added, M=Modified, D=Deleted, R=Renamed)
+    pub status: String,
+    /// Lines added
+    pub additions: u32,
+    /// Lines removed
+    pub deletions: u32,
+    /// Symbols the change added, modified or removed
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub symbols: Vec<SymbolChange>,
+}
+
+impl GitFileChange {
+    /// Compact summary such as `M +12 -3`
+    pub fn summary(&self) -> String {
+        format!("{} +{} -{}", self.status, self.additions, self.deletions)
+    }
+
+    /// Summary followed by the touched symbols, such as
+    /// ``M +12 -3 (modified: `Auth.refresh`)``
+    pub fn describe(&self) -> String {
+        if self.symbols.is_empty() {
+            return self.summary();
+        }
+        let symbols: Vec<String> = self.symbols.iter().map(ToString::to_string).collect();
+        format!("{} ({})", self.summary(), symbols.join(", "))
+    }
+}
+
+/// How a diff touched a symbol
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SymbolChangeKind {
+    Added,
+    Modified,
+    Removed,
+}
+
+impl SymbolChangeKind {
+    /// Lowercase name used in output
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Added => "added",
+            Self::Modified => "modified",
+            Self::Removed => "removed",
+        }
+    }
+}
+
+/// A symbol touched by a diff, found by mapping hunks onto parsed symbol ranges
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SymbolChange {
+    /// File the symbol is defined in
+    pub path: String,
+    /// Symbol name, qualified with its parent (`AuthService.refresh_token`)
+    pub name: String,
+    /// Whether the symbol was added, modified or removed
+    pub change: SymbolChangeKind,
+}
+
+impl std::fmt::Display for SymbolChange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: `{}`", self.change.name(), self.name)
+    }
+}
+
+/// Compression level for output, ordered by how much it removes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default, Serialize, Deserialize)]
 pub enum CompressionLevel {
     /// No compression
     None,
@@ -325,7 +585,7 @@ pub enum CompressionLevel {
     /// Remove comments, normalize whitespace
     #[default]
     Balanced,
-    /// Remove docstrings, keep signatures only
+    /// Replace function bodies with `...`, remove docstrings
     Aggressive,
     /// Key symbols only
     Extreme,
@@ -348,6 +608,7 @@ impl CompressionLevel {
 }
 
 #[cfg(test)]
+#[allow(clippy::str_to_string)]
 mod tests {
     use super::*;
 
@@ -372,4 +633,68 @@ mod tests {
         sym.end_line = 20;
         assert_eq!(sym.line_count(), 11);
     }
+
+    fn symbol(name: &str, kind: SymbolKind, lines: (u32, u32), parent: Option<&str>) -> Symbol {
+        let mut sym = Symbol::new(name, kind);
+        sym.start_line = lines.0;
+        sym.end_line = lines.1;
+        sym.parent = parent.map(str::to_owned);
+        sym
+    }
+
+    fn parser_file() -> RepoFile {
+        let mut file = RepoFile::new("/repo/src/parser.py", "src/parser.py");
+        file.content = Some(
+            "import re\r\n\nclass Parser:\n    def parse(self):\n        return 1\n".to_string(),
+        );
+        file.symbols = vec![
+            symbol("re", SymbolKind::Import, (1, 1), None),
+            symbol("Parser", SymbolKind::Class, (3, 5), None),
+            symbol("parse", SymbolKind::Method, (4, 5), Some("Parser")),
+        ];
+        file
+    }
+
+    #[test]
+    fn test_symbol_at_picks_innermost_symbol() {
+        let file = parser_file();
+
+        let span = file.symbol_at(5).unwrap();
+        assert_eq!(span.symbol.name, "parse");
+        assert_eq!((span.start_line, span.end_line), (4, 5));
+        assert_eq!(span.text(), Some("    def parse(self):\n        return 1"));
+
+        assert_eq!(file.symbol_at(3).unwrap().symbol.name, "Parser");
+        assert_eq!(file.symbol_at(1).unwrap().text(), Some("import re"));
+        assert!(file.symbol_at(2).is_none());
+    }
+
+    #[test]
+    fn test_find_symbol_by_path_and_qualified_name() {
+        let mut repo = Repository::new("repo", "/repo");
+        repo.files.push(parser_file());
+
+        let span = repo.find_symbol("src/parser.py", "Parser::parse").unwrap();
+        assert_eq!(span.symbol.name, "parse");
+        assert_eq!(span.bytes, Some(26..63));
+        assert!(repo
+            .find_symbol("/repo/src/parser.py", "Parser.parse")
+            .is_some());
+        assert!(repo.find_symbol("src/parser.py", "Lexer.parse").is_none());
+        assert!(repo.find_symbol("src/other.py", "Parser").is_none());
+    }
+
+    #[test]
+    fn test_byte_range_follows_original_line_numbers() {
+        let mut file = parser_file();
+        file.content = Some("class Parser:\n        return 1\n".to_string());
+        file.line_numbers = Some(vec![3, 5]);
+
+        let span = file.symbol_at(4).unwrap();
+        assert_eq!(span.text(), Some("        return 1"));
+        assert_eq!(file.span(&file.symbols[1]).bytes, Some(0..30));
+
+        file.reduce_to_outline();
+        assert_eq!(file.symbol_at(4).unwrap().bytes, None);
+    }
 }
@@ -0,0 +1,371 @@
+//! Filtered, coalescing file watching for watch mode
+//!
+//! Raw file system notifications are noisy: builds write to `target/`, git
+//! rewrites `.git/`, and watch mode itself writes the output file, each of
+//! which would otherwise trigger another rebuild. This module provides:
+//!
+//! - [`WatchFilter`]: drops events for paths the scanner would skip anyway
+//!   (`.git`, `.gitignore`d paths, hidden files, default ignores) and for
+//!   explicitly ignored paths such as output files
+//! - [`EventBatcher`]: coalesces bursts of events into one batch, flushed after
+//!   a quiet period or a maximum delay
+//! - `DebouncedWatcher` (feature `watch`): both of the above on top of `notify`
+
+use crate::default_ignores::{is_default_ignored, HiddenPolicy};
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use std::collections::BTreeSet;
+use std::path::{Component, Path, PathBuf};
+use std::time::{Duration, Instant};
+
+/// Decides which changed paths are relevant to a pack
+pub struct WatchFilter {
+    /// Canonical repository root
+    root: PathBuf,
+    /// Root `.gitignore` and `.git/info/exclude` rules
+    gitignore: Option<Gitignore>,
+    /// Which hidden files and directories to watch
+    hidden: HiddenPolicy,
+    /// Apply the built-in default ignore patterns
+    use_default_ignores: bool,
+    /// Exact paths to ignore (e.g. output files)
+    ignored_paths: Vec<PathBuf>,
+}
+
+impl WatchFilter {
+    /// Create a filter for a repository root, honouring its `.gitignore`
+    pub fn new(root: &Path) -> Self {
+        let root = root.canonicalize().unwrap_or_else(|_| root.to_path_buf());
+        let mut builder = GitignoreBuilder::new(&root);
+        // Missing files are fine; they just contribute no rules
+        let _missing = builder.add(root.join(".gitignore"));
+        let _missing = builder.add(root.join(".git").join("info").join("exclude"));
+        let gitignore = builder.build().ok().filter(|g| !g.is_empty());
+
+        Self {
+            root,
+            gitignore,
+            hidden: HiddenPolicy::default(),
+            use_default_ignores: true,
+            ignored_paths: Vec::new(),
+        }
+    }
+
+    /// Watch all hidden files and directories, not just the default allowlist
+    pub fn with_hidden(mut self, include_hidden: bool) -> Self {
+        self.hidden = HiddenPolicy::new(include_hidden);
+        self
+    }
+
+    /// Watch the hidden paths a scan with `policy` includes
+    pub fn with_hidden_policy(mut self, policy: HiddenPolicy) -> Self {
+        self.hidden = policy;
+        self
+    }
+
+    /// Honour `.gitignore` rules (enabled by default)
+    pub fn with_gitignore(mut self, respect_gitignore: bool) -> Self {
+        if !respect_gitignore {
+            self.gitignore = None;
+        }
+        self
+    }
+
+    /// Apply the built-in default ignore patterns (enabled by default)
+    pub fn with_default_ignores(mut self, use_default_ignores: bool) -> Self {
+        self.use_default_ignores = use_default_ignores;
+        self
+    }
+
+    /// Ignore an exact path, such as a file written by watch mode itself
+    pub fn ignore_path(mut self, path: &Path) -> Self {
+        let absolute = if path.is_absolute() {
+            path.to_path_buf()
+        } else {
+            std::env::current_dir()
+                .map(|cwd| cwd.join(path))
+                .unwrap_or_else(|_| path.to_path_buf())
+        };
+        // The file may not exist yet; canonicalize its parent instead
+        let resolved = match (absolute.parent(), absolute.file_name()) {
+            (Some(parent), Some(name)) => parent
+                .canonicalize()
+                .map(|p| p.join(name))
+                .unwrap_or(absolute),
+            _ => absolute,
+        };
+        self.ignored_paths.push(resolved);
+        self
+    }
+
+    /// Whether a change to `path` should trigger a rebuild
+    pub fn is_relevant(&self, path: &Path) -> bool {
+        if self.ignored_paths.iter().any(|p| p == path) {
+            return false;
+        }
+
+        let Ok(relative) = path.strip_prefix(&self.root) else {
+            return false;
+        };
+        if relative.as_os_str().is_empty() {
+            return false;
+        }
+
+        for component in relative.components() {
+            let Component::Normal(name) = component else {
+                continue;
+            };
+            if name == ".git" {
+                return false;
+            }
+        }
+        if !self.hidden.allows(relative, path.is_dir()) {
+            return false;
+        }
+
+        if let Some(gitignore) = &self.gitignore {
+            // Deleted paths can't be stat'ed, so treat directories by trailing match
+            let is_dir = path.is_dir();
+            if gitignore
+                .matched_path_or_any_parents(relative, is_dir)
+                .is_ignore()
+            {
+                return false;
+            }
+        }
+
+        if self.use_default_ignores {
+            let relative = relative.to_string_lossy().replace('\\', "/");
+            if is_default_ignored(&relative) {
+                return false;
+            }
+        }
+
+        true
+    }
+}
+
+/// Coalesces bursts of change events into batches
+///
+/// A batch is released once no new event has arrived for `quiet`, or once
+/// `max_delay` has passed since the first pending event, whichever is first.
+/// The latter keeps continuous writes (e.g. a long build) from starving
+/// rebuilds forever.
+pub struct EventBatcher {
+    quiet: Duration,
+    max_delay: Duration,
+    pending: BTreeSet<PathBuf>,
+    first_at: Option<Instant>,
+    last_at: Option<Instant>,
+}
+
+impl EventBatcher {
+    /// Create a batcher with a quiet period; max delay defaults to 10x quiet
+    pub fn new(quiet: Duration) -> Self {
+        Self {
+            quiet,
+            max_delay: quiet * 10,
+            pending: BTreeSet::new(),
+            first_at: None,
+            last_at: None,
+        }
+    }
+
+    /// Set the maximum time a pending batch may be held back
+    pub fn with_max_delay(mut self, max_delay: Duration) -> Self {
+        self.max_delay = max_delay;
+        self
+    }
+
+    /// Record a changed path observed at `now`
+    pub fn push(&mut self, path: PathBuf, now: Instant) {
+        self.pending.insert(path);
+        self.first_at.get_or_insert(now);
+        self.last_at = Some(now);
+    }
+
+    /// Whether any changes are waiting to be flushed
+    pub fn is_pending(&self) -> bool {
+        !self.pending.is_empty()
+    }
+
+    /// Time until the pending batch becomes ready, if any is pending
+    pub fn time_until_ready(&self, now: Instant) -> Option<Duration> {
+        let (first, last) = (self.first_at?, self.last_at?);
+        let deadline = (last + self.quiet).min(first + self.max_delay);
+        Some(deadline.saturating_duration_since(now))
+    }
+
+    /// Take the pending batch if it is ready at `now`
+    pub fn take_ready(&mut self, now: Instant) -> Option<Vec<PathBuf>> {
+        if !self.time_until_ready(now)?.is_zero() {
+            return None;
+        }
+        self.first_at = None;
+        self.last_at = None;
+        Some(std::mem::take(&mut self.pending).into_iter().collect())
+    }
+}
+
+#[cfg(feature = "watch")]
+pub use self::debounced::DebouncedWatcher;
+
+#[cfg(feature = "watch")]
+mod debounced {
+    use super::{EventBatcher, WatchFilter};
+    use notify::{Config, Event, RecommendedWatcher, RecursiveMode, Watcher};
+    use std::path::{Path, PathBuf};
+    use std::sync::mpsc::{channel, Receiver, RecvTimeoutError};
+    use std::time::{Duration, Instant};
+
+    /// File watcher that yields filtered, coalesced batches of changed paths
+    pub struct DebouncedWatcher {
+        _watcher: RecommendedWatcher,
+        receiver: Receiver<Result<Event, notify::Error>>,
+        filter: WatchFilter,
+        batcher: EventBatcher,
+    }
+
+    impl DebouncedWatcher {
+        /// Watch `root` recursively, releasing batches after `quiet` of inactivity
+        pub fn new(root: &Path, filter: WatchFilter, quiet: Duration) -> notify::Result<Self> {
+            let (tx, rx) = channel();
+            let mut watcher = RecommendedWatcher::new(
+                move |res| {
+                    // The receiver only disappears when the watcher is dropped
+                    tx.send(res).ok();
+                },
+                Config::default(),
+            )?;
+            watcher.watch(root, RecursiveMode::Recursive)?;
+
+            Ok(Self { _watcher: watcher, receiver: rx, filter, batcher: EventBatcher::new(quiet) })
+        }
+
+        /// Block until the next batch of relevant changes is ready
+        ///
+        /// Returns `None` once the underlying watcher has shut down.
+        pub fn next_batch(&mut self) -> Option<Vec<PathBuf>> {
+            loop {
+                let now = Instant::now();
+                if let Some(batch) = self.batcher.take_ready(now) {
+                    return Some(batch);
+                }
+
+                let received = match self.batcher.time_until_ready(now) {
+                    Some(wait) => self.receiver.recv_timeout(wait),
+                    None => self
+                        .receiver
+                        .recv()
+                        .map_err(|_| RecvTimeoutError::Disconnected),
+                };
+
+                match received {
+                    Ok(Ok(event)) => {
+                        if !(event.kind.is_create()
+                            || event.kind.is_modify()
+                            || event.kind.is_remove())
+                        {
+                            continue;
+                        }
+                        let now = Instant::now();
+                        for path in event.paths {
+                            if self.filter.is_relevant(&path) {
+                                self.batcher.push(path, now);
+                            }
+                        }
+                    },
+                    Ok(Err(e)) => log::warn!("File watcher error: {}", e),
+                    Err(RecvTimeoutError::Timeout) => {},
+                    Err(RecvTimeoutError::Disconnected) => return None,
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::str_to_string)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn setup() -> (TempDir, PathBuf) {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path().canonicalize().unwrap();
+        fs::write(root.join(".gitignore"), "*.log\ngenerated/\n").unwrap();
+        fs::create_dir_all(root.join("src")).unwrap();
+        (dir, root)
+    }
+
+    #[test]
+    fn test_filter_skips_ignored_paths() {
+        let (_dir, root) = setup();
+        let filter = WatchFilter::new(&root).ignore_path(&root.join("out.xml"));
+
+        assert!(filter.is_relevant(&root.join("src/main.rs")));
+        assert!(!filter.is_relevant(&root.join(".git/index")));
+        assert!(!filter.is_relevant(&root.join("target/debug/app")));
+        assert!(!filter.is_relevant(&root.join("node_modules/x/index.js")));
+        assert!(!filter.is_relevant(&root.join("debug.log")));
+        assert!(!filter.is_relevant(&root.join("generated/types.rs")));
+        assert!(!filter.is_relevant(&root.join(".env")));
+        assert!(!filter.is_relevant(&root.join("out.xml")));
+        assert!(!filter.is_relevant(Path::new("/elsewhere/file.rs")));
+    }
+
+    #[test]
+    fn test_filter_options() {
+        let (_dir, root) = setup();
+        let filter = WatchFilter::new(&root)
+            .with_hidden(true)
+            .with_gitignore(false)
+            .with_default_ignores(false);
+
+        assert!(filter.is_relevant(&root.join(".env")));
+        assert!(filter.is_relevant(&root.join("debug.log")));
+        assert!(filter.is_relevant(&root.join("target/debug/app")));
+        // .git is never relevant
+        assert!(!filter.is_relevant(&root.join(".git/HEAD")));
+    }
+
+    #[test]
+    fn test_batcher_waits_for_quiet_period() {
+        let start = Instant::now();
+        let quiet = Duration::from_millis(100);
+        let mut batcher = EventBatcher::new(quiet);
+        assert!(batcher.take_ready(start).is_none());
+
+        batcher.push(PathBuf::from("a.rs"), start);
+        batcher.push(PathBuf::from("b.rs"), start + Duration::from_millis(50));
+        batcher.push(PathBuf::from("a.rs"), start + Duration::from_millis(60));
+
+        assert!(batcher
+            .take_ready(start + Duration::from_millis(120))
+            .is_none());
+        let batch = batcher
+            .take_ready(start + Duration::from_millis(160))
+            .unwrap();
+        assert_eq!(batch, vec![PathBuf::from("a.rs"), PathBuf::from("b.rs")]);
+        assert!(!batcher.is_pending());
+    }
+
+    #[test]
+    fn test_batcher_max_delay() {
+        let start = Instant::now();
+        let mut batcher = EventBatcher::new(Duration::from_millis(100))
+            .with_max_delay(Duration::from_millis(250));
+
+        // Continuous events every 50ms never leave a quiet gap
+        for i in 0..10 {
+            batcher.push(PathBuf::from("a.rs"), start + Duration::from_millis(i * 50));
+        }
+        assert_eq!(
+            batcher.time_until_ready(start + Duration::from_millis(200)),
+            Some(Duration::from_millis(50))
+        );
+        assert!(batcher
+            .take_ready(start + Duration::from_millis(250))
+            .is_some());
+    }
+}
@@ -0,0 +1,189 @@
+//! Token-count accuracy on the bundled corpus
+//!
+//! The corpus in `tests/token_corpus` holds code in ten languages, split in
+//! two: `LANGUAGE_RATIOS` in `src/tokenizer.rs` is tuned on `tune/` only, and
+//! the size-based estimates are checked against exact cl100k_base (GPT-4) and
+//! o200k_base (GPT-4o) counts on the held-out files in `holdout/`. The test
+//! prints the error for each language and fails when it leaves the bounds
+//! documented in `src/tokenizer.rs`. Run with `--nocapture` to see the report.
+
+#![allow(clippy::print_stdout)]
+
+use infiniloom_engine::tokenizer::{estimate_source, LanguageRatio, CORPUS_CL100K_RATIO};
+use infiniloom_engine::{TokenModel, Tokenizer};
+use std::path::Path;
+
+/// Corpus languages, as the scanner names them; each is a directory in both
+/// splits
+const LANGUAGES: &[&str] =
+    &["c", "cpp", "csharp", "go", "java", "javascript", "python", "ruby", "rust", "typescript"];
+
+/// Largest error allowed for the estimate of all of a language's held-out
+/// files together, in percent
+const MAX_LANGUAGE_ERROR: f64 = 15.0;
+
+/// Largest error allowed for the estimate of one held-out file, in percent
+const MAX_FILE_ERROR: f64 = 20.0;
+
+/// Largest mean error allowed over a language's held-out chunks, in percent
+const MAX_CHUNK_ERROR: f64 = 20.0;
+
+/// Lines per chunk, about the size of a function
+const CHUNK_LINES: usize = 20;
+
+const MODELS: [TokenModel; 2] = [TokenModel::Gpt4, TokenModel::Gpt4o];
+
+/// Files of each language in `split`, in name order
+fn corpus(split: &str) -> Vec<(&'static str, Vec<String>)> {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/token_corpus")
+        .join(split);
+    LANGUAGES
+        .iter()
+        .map(|language| {
+            let mut paths: Vec<_> = std::fs::read_dir(root.join(language))
+                .unwrap()
+                .map(|entry| entry.unwrap().path())
+                .collect();
+            paths.sort();
+            let texts = paths
+                .iter()
+                .map(|path| std::fs::read_to_string(path).unwrap())
+                .collect();
+            (*language, texts)
+        })
+        .collect()
+}
+
+fn chunks(text: &str) -> Vec<String> {
+    let lines: Vec<&str> = text.split_inclusive('\n').collect();
+    lines
+        .chunks(CHUNK_LINES)
+        .map(|chunk| chunk.concat())
+        .collect()
+}
+
+fn error_percent(estimate: u32, exact: u32) -> f64 {
+    (f64::from(estimate) - f64::from(exact)) / f64::from(exact) * 100.0
+}
+
+#[test]
+fn test_corpus_has_several_files_per_language() {
+    for split in ["tune", "holdout"] {
+        for (language, texts) in corpus(split) {
+            assert!(texts.len() >= 2, "{}/{} needs at least two files", split, language);
+        }
+    }
+}
+
+#[test]
+fn test_estimation_error_within_bounds() {
+    let tokenizer = Tokenizer::new();
+    let mut failures = Vec::new();
+
+    println!(
+        "{:<8} {:<12} {:>7} {:>9} {:>8} {:>10} {:>11}",
+        "model", "language", "exact", "estimate", "error", "worst file", "chunk error"
+    );
+    for model in MODELS {
+        for (language, texts) in corpus("holdout") {
+            let (mut exact, mut estimate) = (0, 0);
+            let mut worst_file: f64 = 0.0;
+            let mut chunk_errors = Vec::new();
+            for text in &texts {
+                let file_exact = tokenizer.count(text, model);
+                let file_estimate = estimate_source(text.len() as u64, model, Some(language));
+                exact += file_exact;
+                estimate += file_estimate;
+                worst_file = worst_file.max(error_percent(file_estimate, file_exact).abs());
+                chunk_errors.extend(chunks(text).iter().map(|chunk| {
+                    let estimate = estimate_source(chunk.len() as u64, model, Some(language));
+                    error_percent(estimate, tokenizer.count(chunk, model)).abs()
+                }));
+            }
+            let error = error_percent(estimate, exact);
+            let chunk_error = chunk_errors.iter().sum::<f64>() / chunk_errors.len() as f64;
+
+            println!(
+                "{:<8} {:<12} {:>7} {:>9} {:>+7.1}% {:>9.1}% {:>10.1}%",
+                model.name(),
+                language,
+                exact,
+                estimate,
+                error,
+                worst_file,
+                chunk_error
+            );
+            if error.abs() > MAX_LANGUAGE_ERROR
+                || worst_file > MAX_FILE_ERROR
+                || chunk_error > MAX_CHUNK_ERROR
+            {
+                failures.push(format!("{} {}", model.name(), language));
+            }
+        }
+    }
+
+    assert!(
+        failures.is_empty(),
+        "estimates out of bounds on the held-out corpus for {}",
+        failures.join(", ")
+    );
+}
+
+/// Characters per token of `texts` together
+fn ratio(tokenizer: &Tokenizer, texts: &[String], model: TokenModel) -> f32 {
+    let bytes: usize = texts.iter().map(String::len).sum();
+    let tokens: u32 = texts.iter().map(|text| tokenizer.count(text, model)).sum();
+    bytes as f32 / tokens as f32
+}
+
+#[test]
+fn test_ratios_are_tuned_on_tune_split() {
+    let tokenizer = Tokenizer::new();
+    let corpus = corpus("tune");
+    for (language, texts) in &corpus {
+        let tuned = LanguageRatio::find(language)
+            .unwrap_or_else(|| panic!("{} is not calibrated", language));
+        let cl100k = ratio(&tokenizer, texts, TokenModel::Gpt4);
+        let o200k = ratio(&tokenizer, texts, TokenModel::Gpt4o);
+        assert!(
+            (cl100k - tuned.cl100k).abs() < 0.01 && (o200k - tuned.o200k).abs() < 0.01,
+            "{} ratios are {:.2} and {:.2} on the tune split; retune LANGUAGE_RATIOS",
+            language,
+            cl100k,
+            o200k
+        );
+    }
+
+    let all: Vec<String> = corpus.into_iter().flat_map(|(_, texts)| texts).collect();
+    let overall = ratio(&tokenizer, &all, TokenModel::Gpt4);
+    assert!((overall - CORPUS_CL100K_RATIO).abs() < 0.01, "corpus ratio is {:.2}", overall);
+
+    // Languages outside the corpus keep the model's own ratio
+    assert_eq!(
+        TokenModel::Claude.chars_per_token_for(Some("cobol")),
+        TokenModel::Claude.chars_per_token()
+    );
+}
+
+/// Print a `LANGUAGE_RATIOS` table tuned on the tune split
+#[test]
+#[ignore]
+fn print_language_ratios() {
+    let tokenizer = Tokenizer::new();
+    let corpus = corpus("tune");
+
+    for (language, texts) in &corpus {
+        println!(
+            "    LanguageRatio {{ language: {:?}, cl100k: {:.2}, o200k: {:.2} }},",
+            language,
+            ratio(&tokenizer, texts, TokenModel::Gpt4),
+            ratio(&tokenizer, texts, TokenModel::Gpt4o)
+        );
+    }
+    let all: Vec<String> = corpus.into_iter().flat_map(|(_, texts)| texts).collect();
+    println!(
+        "pub const CORPUS_CL100K_RATIO: f32 = {:.2};",
+        ratio(&tokenizer, &all, TokenModel::Gpt4)
+    );
+}
@@ -0,0 +1,108 @@
+/* Streaming parser for INI files with sections and comments. */
+
+#include <ctype.h>
+#include <stdio.h>
+#include <string.h>
+
+#define INI_MAX_LINE 512
+#define INI_MAX_SECTION 64
+
+typedef int (*ini_handler)(void *user, const char *section, const char *name,
+                           const char *value);
+
+static char *trim(char *text)
+{
+    while (isspace((unsigned char)*text))
+        text++;
+    char *end = text + strlen(text);
+    while (end > text && isspace((unsigned char)end[-1]))
+        end--;
+    *end = '\0';
+    return text;
+}
+
+static char *strip_comment(char *text)
+{
+    for (char *p = text; *p; p++) {
+        if ((*p == ';' || *p == '#') && (p == text || isspace((unsigned char)p[-1]))) {
+            *p = '\0';
+            break;
+        }
+    }
+    return text;
+}
+
+/*
+ * Call handler for each name = value pair in file. Returns 0 on success, the
+ * number of the first malformed line, or -1 if the handler asked to stop.
+ */
+int ini_parse_file(FILE *file, ini_handler handler, void *user)
+{
+    char line[INI_MAX_LINE];
+    char section[INI_MAX_SECTION] = "";
+    int lineno = 0;
+    int error = 0;
+
+    while (fgets(line, sizeof(line), file) != NULL) {
+        lineno++;
+        char *start = trim(strip_comment(line));
+        if (*start == '\0')
+            continue;
+
+        if (*start == '[') {
+            char *end = strchr(start, ']');
+            if (end == NULL) {
+                if (!error)
+                    error = lineno;
+                continue;
+            }
+            *end = '\0';
+            snprintf(section, sizeof(section), "%s", trim(start + 1));
+            continue;
+        }
+
+        char *equals = strchr(start, '=');
+        if (equals == NULL) {
+            if (!error)
+                error = lineno;
+            continue;
+        }
+        *equals = '\0';
+        char *name = trim(start);
+        char *value = trim(equals + 1);
+        if (!handler(user, section, name, value))
+            return -1;
+    }
+    return error;
+}
+
+int ini_parse(const char *path, ini_handler handler, void *user)
+{
+    FILE *file = fopen(path, "r");
+    if (file == NULL)
+        return -2;
+    int result = ini_parse_file(file, handler, user);
+    fclose(file);
+    return result;
+}
+
+#ifdef INI_DEMO
+static int print_pair(void *user, const char *section, const char *name, const char *value)
+{
+    (void)user;
+    printf("%s.%s = %s\n", section[0] ? section : "(global)", name, value);
+    return 1;
+}
+
+int main(int argc, char **argv)
+{
+    if (argc != 2) {
+        fprintf(stderr, "usage: %s FILE\n", argv[0]);
+        return 2;
+    }
+    int result = ini_parse(argv[1], print_pair, NULL);
+    if (result > 0)
+        fprintf(stderr, "%s:%d: malformed line\n", argv[1], result);
+    return result == 0 ? 0 : 1;
+}
+#endif
@@ -0,0 +1,87 @@
+/* Single-producer, single-consumer ring buffer of fixed-size messages. */
+
+#include <stdatomic.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <string.h>
+
+#define RING_MSG_SIZE 128
+
+struct ring_msg {
+    uint16_t type;
+    uint16_t length;
+    uint8_t payload[RING_MSG_SIZE - 4];
+};
+
+struct ring {
+    struct ring_msg *slots;
+    size_t mask;
+    _Atomic size_t head; /* next slot the producer writes */
+    _Atomic size_t tail; /* next slot the consumer reads */
+};
+
+/* capacity must be a power of two; slots must hold that many messages */
+bool ring_init(struct ring *ring, struct ring_msg *slots, size_t capacity)
+{
+    if (capacity == 0 || (capacity & (capacity - 1)) != 0)
+        return false;
+    ring->slots = slots;
+    ring->mask = capacity - 1;
+    atomic_init(&ring->head, 0);
+    atomic_init(&ring->tail, 0);
+    return true;
+}
+
+size_t ring_len(struct ring *ring)
+{
+    size_t head = atomic_load_explicit(&ring->head, memory_order_acquire);
+    size_t tail = atomic_load_explicit(&ring->tail, memory_order_acquire);
+    return head - tail;
+}
+
+bool ring_push(struct ring *ring, uint16_t type, const void *data, size_t length)
+{
+    if (length > sizeof(ring->slots[0].payload))
+        return false;
+
+    size_t head = atomic_load_explicit(&ring->head, memory_order_relaxed);
+    size_t tail = atomic_load_explicit(&ring->tail, memory_order_acquire);
+    if (head - tail > ring->mask)
+        return false; /* full */
+
+    struct ring_msg *msg = &ring->slots[head & ring->mask];
+    msg->type = type;
+    msg->length = (uint16_t)length;
+    memcpy(msg->payload, data, length);
+    atomic_store_explicit(&ring->head, head + 1, memory_order_release);
+    return true;
+}
+
+bool ring_pop(struct ring *ring, struct ring_msg *out)
+{
+    size_t tail = atomic_load_explicit(&ring->tail, memory_order_relaxed);
+    size_t head = atomic_load_explicit(&ring->head, memory_order_acquire);
+    if (tail == head)
+        return false; /* empty */
+
+    const struct ring_msg *msg = &ring->slots[tail & ring->mask];
+    out->type = msg->type;
+    out->length = msg->length;
+    memcpy(out->payload, msg->payload, msg->length);
+    atomic_store_explicit(&ring->tail, tail + 1, memory_order_release);
+    return true;
+}
+
+/* Drain up to max messages into handler; returns how many were handled. */
+size_t ring_drain(struct ring *ring, size_t max,
+                  void (*handler)(const struct ring_msg *msg, void *ctx), void *ctx)
+{
+    struct ring_msg msg;
+    size_t handled = 0;
+    while (handled < max && ring_pop(ring, &msg)) {
+        handler(&msg, ctx);
+        handled++;
+    }
+    return handled;
+}
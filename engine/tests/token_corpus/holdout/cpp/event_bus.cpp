@@ -0,0 +1,107 @@
+#include <algorithm>
+#include <functional>
+#include <memory>
+#include <mutex>
+#include <string>
+#include <typeindex>
+#include <unordered_map>
+#include <utility>
+#include <vector>
+
+namespace events {
+
+// A subscription stays active until the handle is destroyed or reset.
+class Subscription {
+public:
+    Subscription() = default;
+    explicit Subscription(std::function<void()> cancel) : cancel_(std::move(cancel)) {}
+    Subscription(const Subscription&) = delete;
+    Subscription& operator=(const Subscription&) = delete;
+    Subscription(Subscription&& other) noexcept : cancel_(std::move(other.cancel_)) {
+        other.cancel_ = nullptr;
+    }
+    Subscription& operator=(Subscription&& other) noexcept {
+        if (this != &other) {
+            reset();
+            cancel_ = std::move(other.cancel_);
+            other.cancel_ = nullptr;
+        }
+        return *this;
+    }
+    ~Subscription() { reset(); }
+
+    void reset() {
+        if (cancel_) {
+            cancel_();
+            cancel_ = nullptr;
+        }
+    }
+
+private:
+    std::function<void()> cancel_;
+};
+
+class EventBus : public std::enable_shared_from_this<EventBus> {
+public:
+    template <typename Event>
+    Subscription subscribe(std::function<void(const Event&)> handler) {
+        std::lock_guard<std::mutex> lock(mutex_);
+        const std::size_t id = nextId_++;
+        auto& list = handlers_[std::type_index(typeid(Event))];
+        list.push_back({id, [handler = std::move(handler)](const void* event) {
+                            handler(*static_cast<const Event*>(event));
+                        }});
+        std::weak_ptr<EventBus> weak = weak_from_this();
+        return Subscription([weak, id, type = std::type_index(typeid(Event))] {
+            if (auto bus = weak.lock()) {
+                bus->unsubscribe(type, id);
+            }
+        });
+    }
+
+    template <typename Event>
+    std::size_t publish(const Event& event) {
+        std::vector<Handler> snapshot;
+        {
+            std::lock_guard<std::mutex> lock(mutex_);
+            auto it = handlers_.find(std::type_index(typeid(Event)));
+            if (it == handlers_.end()) {
+                return 0;
+            }
+            snapshot = it->second;
+        }
+        for (const auto& handler : snapshot) {
+            handler.call(&event);
+        }
+        return snapshot.size();
+    }
+
+private:
+    struct Handler {
+        std::size_t id;
+        std::function<void(const void*)> call;
+    };
+
+    void unsubscribe(std::type_index type, std::size_t id) {
+        std::lock_guard<std::mutex> lock(mutex_);
+        auto it = handlers_.find(type);
+        if (it == handlers_.end()) {
+            return;
+        }
+        auto& list = it->second;
+        list.erase(std::remove_if(list.begin(), list.end(),
+                                  [id](const Handler& h) { return h.id == id; }),
+                   list.end());
+    }
+
+    std::mutex mutex_;
+    std::size_t nextId_ = 0;
+    std::unordered_map<std::type_index, std::vector<Handler>> handlers_;
+};
+
+struct UserCreated {
+    std::string id;
+    std::string email;
+};
+
+}  // namespace events
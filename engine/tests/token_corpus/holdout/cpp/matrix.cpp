@@ -0,0 +1,124 @@
+#include <cassert>
+#include <cmath>
+#include <initializer_list>
+#include <ostream>
+#include <stdexcept>
+#include <vector>
+
+namespace linalg {
+
+class Matrix {
+public:
+    Matrix(std::size_t rows, std::size_t cols, double fill = 0.0)
+        : rows_(rows), cols_(cols), data_(rows * cols, fill) {}
+
+    Matrix(std::initializer_list<std::initializer_list<double>> rows)
+        : rows_(rows.size()), cols_(rows.size() ? rows.begin()->size() : 0) {
+        data_.reserve(rows_ * cols_);
+        for (const auto& row : rows) {
+            if (row.size() != cols_) {
+                throw std::invalid_argument("ragged matrix literal");
+            }
+            data_.insert(data_.end(), row.begin(), row.end());
+        }
+    }
+
+    static Matrix identity(std::size_t n) {
+        Matrix m(n, n);
+        for (std::size_t i = 0; i < n; ++i) {
+            m(i, i) = 1.0;
+        }
+        return m;
+    }
+
+    std::size_t rows() const noexcept { return rows_; }
+    std::size_t cols() const noexcept { return cols_; }
+
+    double& operator()(std::size_t r, std::size_t c) {
+        assert(r < rows_ && c < cols_);
+        return data_[r * cols_ + c];
+    }
+
+    double operator()(std::size_t r, std::size_t c) const {
+        assert(r < rows_ && c < cols_);
+        return data_[r * cols_ + c];
+    }
+
+    Matrix operator*(const Matrix& other) const {
+        if (cols_ != other.rows_) {
+            throw std::invalid_argument("dimension mismatch in multiply");
+        }
+        Matrix result(rows_, other.cols_);
+        for (std::size_t i = 0; i < rows_; ++i) {
+            for (std::size_t k = 0; k < cols_; ++k) {
+                const double lhs = (*this)(i, k);
+                for (std::size_t j = 0; j < other.cols_; ++j) {
+                    result(i, j) += lhs * other(k, j);
+                }
+            }
+        }
+        return result;
+    }
+
+    Matrix transposed() const {
+        Matrix result(cols_, rows_);
+        for (std::size_t i = 0; i < rows_; ++i) {
+            for (std::size_t j = 0; j < cols_; ++j) {
+                result(j, i) = (*this)(i, j);
+            }
+        }
+        return result;
+    }
+
+    // Determinant by Gaussian elimination with partial pivoting.
+    double determinant() const {
+        if (rows_ != cols_) {
+            throw std::logic_error("determinant of a non-square matrix");
+        }
+        Matrix m = *this;
+        double det = 1.0;
+        for (std::size_t col = 0; col < cols_; ++col) {
+            std::size_t pivot = col;
+            for (std::size_t r = col + 1; r < rows_; ++r) {
+                if (std::abs(m(r, col)) > std::abs(m(pivot, col))) {
+                    pivot = r;
+                }
+            }
+            if (std::abs(m(pivot, col)) < 1e-12) {
+                return 0.0;
+            }
+            if (pivot != col) {
+                for (std::size_t c = 0; c < cols_; ++c) {
+                    std::swap(m(pivot, c), m(col, c));
+                }
+                det = -det;
+            }
+            det *= m(col, col);
+            for (std::size_t r = col + 1; r < rows_; ++r) {
+                const double factor = m(r, col) / m(col, col);
+                for (std::size_t c = col; c < cols_; ++c) {
+                    m(r, c) -= factor * m(col, c);
+                }
+            }
+        }
+        return det;
+    }
+
+private:
+    std::size_t rows_;
+    std::size_t cols_;
+    std::vector<double> data_;
+};
+
+inline std::ostream& operator<<(std::ostream& out, const Matrix& m) {
+    for (std::size_t i = 0; i < m.rows(); ++i) {
+        out << (i == 0 ? "[[" : " [");
+        for (std::size_t j = 0; j < m.cols(); ++j) {
+            out << (j ? ", " : "") << m(i, j);
+        }
+        out << (i + 1 == m.rows() ? "]]" : "]\n");
+    }
+    return out;
+}
+
+}  // namespace linalg
@@ -0,0 +1,101 @@
+using System;
+using System.Collections.Generic;
+using System.Globalization;
+using System.IO;
+using System.Linq;
+using System.Reflection;
+using System.Text;
+
+namespace Reporting
+{
+    [AttributeUsage(AttributeTargets.Property)]
+    public sealed class CsvColumnAttribute : Attribute
+    {
+        public CsvColumnAttribute(string name) => Name = name;
+
+        public string Name { get; }
+        public int Order { get; set; }
+        public string? Format { get; set; }
+    }
+
+    public sealed class CsvExporter<T>
+    {
+        private readonly IReadOnlyList<(PropertyInfo Property, CsvColumnAttribute Column)> _columns;
+        private readonly char _separator;
+
+        public CsvExporter(char separator = ',')
+        {
+            _separator = separator;
+            _columns = typeof(T)
+                .GetProperties(BindingFlags.Public | BindingFlags.Instance)
+                .Select(p => (Property: p, Column: p.GetCustomAttribute<CsvColumnAttribute>()))
+                .Where(c => c.Column != null)
+                .Select(c => (c.Property, c.Column!))
+                .OrderBy(c => c.Item2.Order)
+                .ThenBy(c => c.Item2.Name, StringComparer.Ordinal)
+                .ToList();
+
+            if (_columns.Count == 0)
+            {
+                throw new InvalidOperationException($"{typeof(T).Name} has no [CsvColumn] properties.");
+            }
+        }
+
+        public void Write(TextWriter writer, IEnumerable<T> rows)
+        {
+            writer.WriteLine(string.Join(_separator, _columns.Select(c => Escape(c.Column.Name))));
+            foreach (var row in rows)
+            {
+                var cells = _columns.Select(c => Escape(FormatValue(c.Property.GetValue(row), c.Column.Format)));
+                writer.WriteLine(string.Join(_separator, cells));
+            }
+        }
+
+        public string ToCsv(IEnumerable<T> rows)
+        {
+            var builder = new StringBuilder();
+            using (var writer = new StringWriter(builder, CultureInfo.InvariantCulture))
+            {
+                Write(writer, rows);
+            }
+            return builder.ToString();
+        }
+
+        private static string FormatValue(object? value, string? format) => value switch
+        {
+            null => string.Empty,
+            IFormattable formattable when format != null => formattable.ToString(format, CultureInfo.InvariantCulture),
+            DateTime date => date.ToString("yyyy-MM-dd HH:mm:ss", CultureInfo.InvariantCulture),
+            bool flag => flag ? "true" : "false",
+            IFormattable formattable => formattable.ToString(null, CultureInfo.InvariantCulture),
+            _ => value.ToString() ?? string.Empty
+        };
+
+        private string Escape(string value)
+        {
+            var needsQuotes = value.IndexOfAny(new[] { _separator, '"', '\n', '\r' }) >= 0;
+            if (!needsQuotes)
+            {
+                return value;
+            }
+            return "\"" + value.Replace("\"", "\"\"") + "\"";
+        }
+    }
+
+    public sealed class SalesRow
+    {
+        [CsvColumn("Region", Order = 0)]
+        public string Region { get; init; } = string.Empty;
+
+        [CsvColumn("Month", Order = 1, Format = "yyyy-MM")]
+        public DateTime Month { get; init; }
+
+        [CsvColumn("Revenue", Order = 2, Format = "F2")]
+        public decimal Revenue { get; init; }
+
+        [CsvColumn("Orders", Order = 3)]
+        public int Orders { get; init; }
+
+        public string InternalNote { get; init; } = string.Empty;
+    }
+}
@@ -0,0 +1,105 @@
+using System;
+using System.Net.Http;
+using System.Threading;
+using System.Threading.Tasks;
+
+namespace Resilience
+{
+    /// <summary>
+    /// Retries transient failures with exponential backoff and jitter.
+    /// </summary>
+    public sealed class RetryPolicy
+    {
+        private readonly int _maxAttempts;
+        private readonly TimeSpan _baseDelay;
+        private readonly TimeSpan _maxDelay;
+        private readonly Func<Exception, bool> _isTransient;
+        private readonly Random _random = new();
+
+        public RetryPolicy(int maxAttempts, TimeSpan baseDelay, TimeSpan maxDelay, Func<Exception, bool>? isTransient = null)
+        {
+            if (maxAttempts < 1)
+            {
+                throw new ArgumentOutOfRangeException(nameof(maxAttempts));
+            }
+            _maxAttempts = maxAttempts;
+            _baseDelay = baseDelay;
+            _maxDelay = maxDelay;
+            _isTransient = isTransient ?? DefaultIsTransient;
+        }
+
+        public event EventHandler<RetryEventArgs>? Retrying;
+
+        public async Task<T> ExecuteAsync<T>(Func<CancellationToken, Task<T>> action, CancellationToken cancellationToken = default)
+        {
+            for (var attempt = 1; ; attempt++)
+            {
+                try
+                {
+                    return await action(cancellationToken).ConfigureAwait(false);
+                }
+                catch (Exception ex) when (attempt < _maxAttempts && _isTransient(ex) && !cancellationToken.IsCancellationRequested)
+                {
+                    var delay = DelayFor(attempt);
+                    Retrying?.Invoke(this, new RetryEventArgs(attempt, delay, ex));
+                    await Task.Delay(delay, cancellationToken).ConfigureAwait(false);
+                }
+            }
+        }
+
+        public Task ExecuteAsync(Func<CancellationToken, Task> action, CancellationToken cancellationToken = default) =>
+            ExecuteAsync(async token =>
+            {
+                await action(token).ConfigureAwait(false);
+                return true;
+            }, cancellationToken);
+
+        private TimeSpan DelayFor(int attempt)
+        {
+            var exponential = _baseDelay.TotalMilliseconds * Math.Pow(2, attempt - 1);
+            double jitter;
+            lock (_random)
+            {
+                jitter = _random.NextDouble() * _baseDelay.TotalMilliseconds;
+            }
+            return TimeSpan.FromMilliseconds(Math.Min(exponential + jitter, _maxDelay.TotalMilliseconds));
+        }
+
+        private static bool DefaultIsTransient(Exception ex) => ex switch
+        {
+            HttpRequestException => true,
+            TimeoutException => true,
+            TaskCanceledException => true,
+            _ => false
+        };
+    }
+
+    public sealed class RetryEventArgs : EventArgs
+    {
+        public RetryEventArgs(int attempt, TimeSpan delay, Exception exception)
+        {
+            Attempt = attempt;
+            Delay = delay;
+            Exception = exception;
+        }
+
+        public int Attempt { get; }
+        public TimeSpan Delay { get; }
+        public Exception Exception { get; }
+    }
+
+    public static class RetryExample
+    {
+        public static async Task<string> FetchAsync(HttpClient client, Uri uri)
+        {
+            var policy = new RetryPolicy(4, TimeSpan.FromMilliseconds(200), TimeSpan.FromSeconds(5));
+            policy.Retrying += (_, e) => Console.Error.WriteLine($"Attempt {e.Attempt} failed ({e.Exception.Message}); retrying in {e.Delay.TotalMilliseconds:F0} ms");
+            return await policy.ExecuteAsync(async token =>
+            {
+                using var response = await client.GetAsync(uri, token).ConfigureAwait(false);
+                response.EnsureSuccessStatusCode();
+                return await response.Content.ReadAsStringAsync(token).ConfigureAwait(false);
+            });
+        }
+    }
+}
@@ -0,0 +1,110 @@
+package config
+
+import (
+	"bufio"
+	"fmt"
+	"io"
+	"os"
+	"strconv"
+	"strings"
+	"time"
+)
+
+// Config holds the service settings read from the environment file.
+type Config struct {
+	ListenAddr      string
+	DatabaseURL     string
+	MaxConnections  int
+	ReadTimeout     time.Duration
+	ShutdownTimeout time.Duration
+	Debug           bool
+	AllowedOrigins  []string
+}
+
+// Default returns the settings used for keys that are not set.
+func Default() Config {
+	return Config{
+		ListenAddr:      ":8080",
+		MaxConnections:  20,
+		ReadTimeout:     15 * time.Second,
+		ShutdownTimeout: 30 * time.Second,
+	}
+}
+
+// LoadFile reads KEY=VALUE lines from path on top of the defaults.
+func LoadFile(path string) (Config, error) {
+	f, err := os.Open(path)
+	if err != nil {
+		return Config{}, err
+	}
+	defer f.Close()
+	return Load(f)
+}
+
+// Load reads KEY=VALUE lines from r; blank lines and # comments are skipped.
+func Load(r io.Reader) (Config, error) {
+	cfg := Default()
+	scanner := bufio.NewScanner(r)
+	line := 0
+	for scanner.Scan() {
+		line++
+		text := strings.TrimSpace(scanner.Text())
+		if text == "" || strings.HasPrefix(text, "#") {
+			continue
+		}
+		key, value, ok := strings.Cut(text, "=")
+		if !ok {
+			return Config{}, fmt.Errorf("line %d: expected KEY=VALUE", line)
+		}
+		key = strings.TrimSpace(key)
+		value = strings.Trim(strings.TrimSpace(value), `"`)
+		if err := cfg.set(key, value); err != nil {
+			return Config{}, fmt.Errorf("line %d: %s: %w", line, key, err)
+		}
+	}
+	if err := scanner.Err(); err != nil {
+		return Config{}, err
+	}
+	return cfg, cfg.validate()
+}
+
+func (c *Config) set(key, value string) error {
+	var err error
+	switch key {
+	case "LISTEN_ADDR":
+		c.ListenAddr = value
+	case "DATABASE_URL":
+		c.DatabaseURL = value
+	case "MAX_CONNECTIONS":
+		c.MaxConnections, err = strconv.Atoi(value)
+	case "READ_TIMEOUT":
+		c.ReadTimeout, err = time.ParseDuration(value)
+	case "SHUTDOWN_TIMEOUT":
+		c.ShutdownTimeout, err = time.ParseDuration(value)
+	case "DEBUG":
+		c.Debug, err = strconv.ParseBool(value)
+	case "ALLOWED_ORIGINS":
+		c.AllowedOrigins = nil
+		for _, origin := range strings.Split(value, ",") {
+			if origin = strings.TrimSpace(origin); origin != "" {
+				c.AllowedOrigins = append(c.AllowedOrigins, origin)
+			}
+		}
+	default:
+		return fmt.Errorf("unknown key")
+	}
+	return err
+}
+
+func (c Config) validate() error {
+	if c.DatabaseURL == "" {
+		return fmt.Errorf("DATABASE_URL is required")
+	}
+	if c.MaxConnections < 1 {
+		return fmt.Errorf("MAX_CONNECTIONS must be at least 1, got %d", c.MaxConnections)
+	}
+	if c.ReadTimeout <= 0 {
+		return fmt.Errorf("READ_TIMEOUT must be positive")
+	}
+	return nil
+}
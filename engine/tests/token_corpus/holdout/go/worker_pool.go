@@ -0,0 +1,119 @@
+package pool
+
+import (
+	"context"
+	"errors"
+	"fmt"
+	"sync"
+	"sync/atomic"
+)
+
+// ErrClosed is returned by Submit after Close.
+var ErrClosed = errors.New("pool: closed")
+
+// Task is a unit of work run by the pool.
+type Task func(ctx context.Context) error
+
+// Result reports the outcome of one submitted task.
+type Result struct {
+	ID  int64
+	Err error
+}
+
+// Pool runs tasks on a fixed number of goroutines.
+type Pool struct {
+	tasks   chan job
+	results chan Result
+	wg      sync.WaitGroup
+	nextID  atomic.Int64
+	closed  atomic.Bool
+	cancel  context.CancelFunc
+}
+
+type job struct {
+	id   int64
+	task Task
+}
+
+// NewPool starts workers goroutines that stop when ctx is cancelled.
+func NewPool(ctx context.Context, workers, queue int) *Pool {
+	if workers < 1 {
+		panic(fmt.Sprintf("pool: need at least one worker, got %d", workers))
+	}
+	ctx, cancel := context.WithCancel(ctx)
+	p := &Pool{
+		tasks:   make(chan job, queue),
+		results: make(chan Result, queue),
+		cancel:  cancel,
+	}
+	for i := 0; i < workers; i++ {
+		p.wg.Add(1)
+		go p.work(ctx)
+	}
+	go func() {
+		p.wg.Wait()
+		close(p.results)
+	}()
+	return p
+}
+
+func (p *Pool) work(ctx context.Context) {
+	defer p.wg.Done()
+	for {
+		select {
+		case <-ctx.Done():
+			return
+		case j, ok := <-p.tasks:
+			if !ok {
+				return
+			}
+			err := runSafely(ctx, j.task)
+			select {
+			case p.results <- Result{ID: j.id, Err: err}:
+			case <-ctx.Done():
+				return
+			}
+		}
+	}
+}
+
+func runSafely(ctx context.Context, task Task) (err error) {
+	defer func() {
+		if r := recover(); r != nil {
+			err = fmt.Errorf("pool: task panicked: %v", r)
+		}
+	}()
+	return task(ctx)
+}
+
+// Submit queues task and returns its ID, blocking while the queue is full.
+func (p *Pool) Submit(ctx context.Context, task Task) (int64, error) {
+	if p.closed.Load() {
+		return 0, ErrClosed
+	}
+	id := p.nextID.Add(1)
+	select {
+	case p.tasks <- job{id: id, task: task}:
+		return id, nil
+	case <-ctx.Done():
+		return 0, ctx.Err()
+	}
+}
+
+// Results delivers one Result per finished task until the pool stops.
+func (p *Pool) Results() <-chan Result {
+	return p.results
+}
+
+// Close stops accepting tasks; queued tasks still run.
+func (p *Pool) Close() {
+	if p.closed.CompareAndSwap(false, true) {
+		close(p.tasks)
+	}
+}
+
+// Stop cancels running tasks and drops queued ones.
+func (p *Pool) Stop() {
+	p.Close()
+	p.cancel()
+}
@@ -0,0 +1,117 @@
+package com.example.audit;
+
+import java.time.Instant;
+import java.time.ZoneOffset;
+import java.time.format.DateTimeFormatter;
+import java.util.ArrayList;
+import java.util.Collections;
+import java.util.List;
+import java.util.Map;
+import java.util.Objects;
+import java.util.concurrent.ConcurrentHashMap;
+import java.util.stream.Collectors;
+
+public class AuditService {
+
+    public enum Action { CREATE, UPDATE, DELETE, LOGIN, EXPORT }
+
+    public static final class AuditEvent {
+        private final String actor;
+        private final Action action;
+        private final String resource;
+        private final Instant at;
+        private final Map<String, String> details;
+
+        public AuditEvent(String actor, Action action, String resource, Instant at, Map<String, String> details) {
+            this.actor = Objects.requireNonNull(actor);
+            this.action = Objects.requireNonNull(action);
+            this.resource = Objects.requireNonNull(resource);
+            this.at = Objects.requireNonNull(at);
+            this.details = Map.copyOf(details);
+        }
+
+        public String getActor() {
+            return actor;
+        }
+
+        public Action getAction() {
+            return action;
+        }
+
+        public String getResource() {
+            return resource;
+        }
+
+        public Instant getAt() {
+            return at;
+        }
+
+        public Map<String, String> getDetails() {
+            return details;
+        }
+
+        @Override
+        public String toString() {
+            return String.format("%s %s %s %s %s", FORMAT.format(at), actor, action, resource, details);
+        }
+    }
+
+    public interface AuditSink {
+        void write(AuditEvent event);
+    }
+
+    private static final DateTimeFormatter FORMAT =
+            DateTimeFormatter.ISO_LOCAL_DATE_TIME.withZone(ZoneOffset.UTC);
+
+    private final List<AuditEvent> events = Collections.synchronizedList(new ArrayList<>());
+    private final Map<String, Integer> failedLogins = new ConcurrentHashMap<>();
+    private final List<AuditSink> sinks = new ArrayList<>();
+    private final int lockoutThreshold;
+
+    public AuditService(int lockoutThreshold) {
+        this.lockoutThreshold = lockoutThreshold;
+    }
+
+    public void addSink(AuditSink sink) {
+        sinks.add(sink);
+    }
+
+    public void record(String actor, Action action, String resource, Map<String, String> details) {
+        AuditEvent event = new AuditEvent(actor, action, resource, Instant.now(), details);
+        events.add(event);
+        for (AuditSink sink : sinks) {
+            try {
+                sink.write(event);
+            } catch (RuntimeException e) {
+                System.err.println("Audit sink failed: " + e.getMessage());
+            }
+        }
+    }
+
+    /** Records a login attempt; returns true when the account should be locked. */
+    public boolean recordLogin(String actor, boolean success) {
+        record(actor, Action.LOGIN, "session", Map.of("success", Boolean.toString(success)));
+        if (success) {
+            failedLogins.remove(actor);
+            return false;
+        }
+        int failures = failedLogins.merge(actor, 1, Integer::sum);
+        return failures >= lockoutThreshold;
+    }
+
+    public List<AuditEvent> eventsFor(String actor) {
+        synchronized (events) {
+            return events.stream()
+                    .filter(event -> event.getActor().equals(actor))
+                    .collect(Collectors.toUnmodifiableList());
+        }
+    }
+
+    public Map<Action, Long> countsByAction(Instant since) {
+        synchronized (events) {
+            return events.stream()
+                    .filter(event -> !event.getAt().isBefore(since))
+                    .collect(Collectors.groupingBy(AuditEvent::getAction, Collectors.counting()));
+        }
+    }
+}
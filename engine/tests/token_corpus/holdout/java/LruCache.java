@@ -0,0 +1,118 @@
+package com.example.cache;
+
+import java.time.Clock;
+import java.time.Duration;
+import java.time.Instant;
+import java.util.LinkedHashMap;
+import java.util.Map;
+import java.util.Optional;
+import java.util.concurrent.locks.ReentrantLock;
+import java.util.function.Function;
+
+/**
+ * A thread-safe least-recently-used cache whose entries also expire after a
+ * fixed time to live.
+ *
+ * @param <K> key type
+ * @param <V> value type
+ */
+public class LruCache<K, V> {
+
+    private record Entry<V>(V value, Instant expiresAt) {}
+
+    private final int capacity;
+    private final Duration ttl;
+    private final Clock clock;
+    private final ReentrantLock lock = new ReentrantLock();
+    private final LinkedHashMap<K, Entry<V>> entries;
+    private long hits;
+    private long misses;
+
+    public LruCache(int capacity, Duration ttl) {
+        this(capacity, ttl, Clock.systemUTC());
+    }
+
+    LruCache(int capacity, Duration ttl, Clock clock) {
+        if (capacity < 1) {
+            throw new IllegalArgumentException("capacity must be at least 1");
+        }
+        this.capacity = capacity;
+        this.ttl = ttl;
+        this.clock = clock;
+        this.entries = new LinkedHashMap<>(16, 0.75f, true) {
+            @Override
+            protected boolean removeEldestEntry(Map.Entry<K, Entry<V>> eldest) {
+                return size() > LruCache.this.capacity;
+            }
+        };
+    }
+
+    public Optional<V> get(K key) {
+        lock.lock();
+        try {
+            Entry<V> entry = entries.get(key);
+            if (entry == null || isExpired(entry)) {
+                if (entry != null) {
+                    entries.remove(key);
+                }
+                misses++;
+                return Optional.empty();
+            }
+            hits++;
+            return Optional.of(entry.value());
+        } finally {
+            lock.unlock();
+        }
+    }
+
+    public void put(K key, V value) {
+        lock.lock();
+        try {
+            entries.put(key, new Entry<>(value, clock.instant().plus(ttl)));
+        } finally {
+            lock.unlock();
+        }
+    }
+
+    /** Returns the cached value, computing and caching it when absent. */
+    public V computeIfAbsent(K key, Function<? super K, ? extends V> loader) {
+        return get(key).orElseGet(() -> {
+            V value = loader.apply(key);
+            put(key, value);
+            return value;
+        });
+    }
+
+    public boolean invalidate(K key) {
+        lock.lock();
+        try {
+            return entries.remove(key) != null;
+        } finally {
+            lock.unlock();
+        }
+    }
+
+    public int size() {
+        lock.lock();
+        try {
+            entries.values().removeIf(this::isExpired);
+            return entries.size();
+        } finally {
+            lock.unlock();
+        }
+    }
+
+    public double hitRate() {
+        lock.lock();
+        try {
+            long total = hits + misses;
+            return total == 0 ? 0.0 : (double) hits / total;
+        } finally {
+            lock.unlock();
+        }
+    }
+
+    private boolean isExpired(Entry<V> entry) {
+        return !clock.instant().isBefore(entry.expiresAt());
+    }
+}
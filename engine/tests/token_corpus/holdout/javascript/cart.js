@@ -0,0 +1,110 @@
+'use strict';
+
+const TAX_RATES = {
+  US: 0.07,
+  DE: 0.19,
+  FR: 0.2,
+  GB: 0.2,
+};
+
+class Cart {
+  constructor({ country = 'US', currency = 'USD' } = {}) {
+    this.country = country;
+    this.currency = currency;
+    this.items = new Map();
+    this.coupons = [];
+  }
+
+  add(product, quantity = 1) {
+    if (!product || !product.id) {
+      throw new TypeError('product must have an id');
+    }
+    if (!Number.isInteger(quantity) || quantity < 1) {
+      throw new RangeError('quantity must be a positive integer');
+    }
+    const existing = this.items.get(product.id);
+    if (existing) {
+      existing.quantity += quantity;
+    } else {
+      this.items.set(product.id, { product, quantity });
+    }
+    return this;
+  }
+
+  remove(productId, quantity = Infinity) {
+    const item = this.items.get(productId);
+    if (!item) return this;
+    item.quantity -= quantity;
+    if (item.quantity <= 0) {
+      this.items.delete(productId);
+    }
+    return this;
+  }
+
+  applyCoupon(coupon) {
+    if (this.coupons.some((c) => c.code === coupon.code)) {
+      return false;
+    }
+    if (coupon.expiresAt && new Date(coupon.expiresAt) < new Date()) {
+      return false;
+    }
+    this.coupons.push(coupon);
+    return true;
+  }
+
+  get subtotal() {
+    let cents = 0;
+    for (const { product, quantity } of this.items.values()) {
+      cents += Math.round(product.price * 100) * quantity;
+    }
+    return cents;
+  }
+
+  get discount() {
+    const subtotal = this.subtotal;
+    let discount = 0;
+    for (const coupon of this.coupons) {
+      if (coupon.minimum && subtotal < coupon.minimum * 100) continue;
+      if (coupon.type === 'percent') {
+        discount += Math.round((subtotal * coupon.value) / 100);
+      } else if (coupon.type === 'fixed') {
+        discount += Math.round(coupon.value * 100);
+      }
+    }
+    return Math.min(discount, subtotal);
+  }
+
+  get tax() {
+    const rate = TAX_RATES[this.country] ?? 0;
+    return Math.round((this.subtotal - this.discount) * rate);
+  }
+
+  get total() {
+    return this.subtotal - this.discount + this.tax;
+  }
+
+  summary() {
+    const format = (cents) =>
+      new Intl.NumberFormat('en-US', { style: 'currency', currency: this.currency }).format(cents / 100);
+    const lines = [...this.items.values()].map(
+      ({ product, quantity }) => `${quantity} x ${product.name.padEnd(20)} ${format(product.price * 100 * quantity)}`,
+    );
+    lines.push(`Subtotal ${format(this.subtotal)}`);
+    if (this.discount > 0) lines.push(`Discount -${format(this.discount)}`);
+    lines.push(`Tax ${format(this.tax)}`);
+    lines.push(`Total ${format(this.total)}`);
+    return lines.join('\n');
+  }
+
+  toJSON() {
+    return {
+      country: this.country,
+      currency: this.currency,
+      items: [...this.items.values()].map(({ product, quantity }) => ({ id: product.id, quantity })),
+      coupons: this.coupons.map((c) => c.code),
+      total: this.total,
+    };
+  }
+}
+
+module.exports = { Cart, TAX_RATES };
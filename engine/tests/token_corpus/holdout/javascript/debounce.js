@@ -0,0 +1,118 @@
+/**
+ * Timing helpers for UI event handlers.
+ */
+
+export function debounce(fn, wait, { leading = false, maxWait } = {}) {
+  let timer = null;
+  let lastArgs = null;
+  let lastThis = null;
+  let firstCallAt = null;
+
+  function invoke() {
+    const args = lastArgs;
+    const context = lastThis;
+    lastArgs = null;
+    lastThis = null;
+    firstCallAt = null;
+    return fn.apply(context, args);
+  }
+
+  function debounced(...args) {
+    const now = Date.now();
+    lastArgs = args;
+    lastThis = this;
+
+    if (timer === null && leading) {
+      timer = setTimeout(() => {
+        timer = null;
+        if (lastArgs) invoke();
+      }, wait);
+      return invoke();
+    }
+
+    if (firstCallAt === null) firstCallAt = now;
+    clearTimeout(timer);
+
+    const overdue = maxWait !== undefined && now - firstCallAt >= maxWait;
+    if (overdue) {
+      timer = null;
+      return invoke();
+    }
+    timer = setTimeout(() => {
+      timer = null;
+      invoke();
+    }, wait);
+    return undefined;
+  }
+
+  debounced.cancel = () => {
+    clearTimeout(timer);
+    timer = null;
+    lastArgs = null;
+    lastThis = null;
+    firstCallAt = null;
+  };
+
+  debounced.flush = () => {
+    if (timer === null || lastArgs === null) return undefined;
+    clearTimeout(timer);
+    timer = null;
+    return invoke();
+  };
+
+  return debounced;
+}
+
+export function throttle(fn, interval) {
+  let last = 0;
+  let pending = null;
+
+  return function throttled(...args) {
+    const now = Date.now();
+    const remaining = interval - (now - last);
+    if (remaining <= 0) {
+      clearTimeout(pending);
+      pending = null;
+      last = now;
+      return fn.apply(this, args);
+    }
+    if (pending === null) {
+      pending = setTimeout(() => {
+        pending = null;
+        last = Date.now();
+        fn.apply(this, args);
+      }, remaining);
+    }
+    return undefined;
+  };
+}
+
+export function once(fn) {
+  let called = false;
+  let result;
+  return function onceWrapper(...args) {
+    if (!called) {
+      called = true;
+      result = fn.apply(this, args);
+    }
+    return result;
+  };
+}
+
+export function sleep(ms, { signal } = {}) {
+  return new Promise((resolve, reject) => {
+    if (signal?.aborted) {
+      reject(signal.reason ?? new Error('Aborted'));
+      return;
+    }
+    const timer = setTimeout(resolve, ms);
+    signal?.addEventListener(
+      'abort',
+      () => {
+        clearTimeout(timer);
+        reject(signal.reason ?? new Error('Aborted'));
+      },
+      { once: true },
+    );
+  });
+}
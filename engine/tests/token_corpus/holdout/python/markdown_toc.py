@@ -0,0 +1,94 @@
+#!/usr/bin/env python3
+"""Generate or update a table of contents in a Markdown file."""
+
+import argparse
+import re
+import sys
+import unicodedata
+from collections import Counter
+
+TOC_START = "<!-- toc -->"
+TOC_END = "<!-- tocstop -->"
+HEADING = re.compile(r"^(#{1,6})\s+(.+?)\s*#*\s*$")
+FENCE = re.compile(r"^(```|~~~)")
+
+
+def slugify(text, seen):
+    """GitHub-style anchor for a heading, unique within the document."""
+    text = re.sub(r"`([^`]*)`", r"\1", text)
+    text = re.sub(r"\[([^\]]*)\]\([^)]*\)", r"\1", text)
+    text = unicodedata.normalize("NFKD", text).lower()
+    slug = re.sub(r"[^\w\- ]", "", text).strip().replace(" ", "-")
+    count = seen[slug]
+    seen[slug] += 1
+    return slug if count == 0 else f"{slug}-{count}"
+
+
+def headings(lines, min_level, max_level):
+    seen = Counter()
+    in_fence = False
+    for line in lines:
+        if FENCE.match(line):
+            in_fence = not in_fence
+            continue
+        if in_fence:
+            continue
+        match = HEADING.match(line)
+        if not match:
+            continue
+        level = len(match.group(1))
+        title = match.group(2)
+        anchor = slugify(title, seen)
+        if min_level <= level <= max_level:
+            yield level, title, anchor
+
+
+def build_toc(lines, min_level=2, max_level=4, bullet="-"):
+    entries = list(headings(lines, min_level, max_level))
+    if not entries:
+        return []
+    base = min(level for level, _, _ in entries)
+    return [
+        f"{'  ' * (level - base)}{bullet} [{title}](#{anchor})"
+        for level, title, anchor in entries
+    ]
+
+
+def update(text, **options):
+    lines = text.splitlines()
+    toc = build_toc(lines, **options)
+    try:
+        start = lines.index(TOC_START)
+        end = lines.index(TOC_END, start)
+    except ValueError:
+        raise SystemExit(f"markers {TOC_START} and {TOC_END} not found")
+    updated = lines[: start + 1] + [""] + toc + [""] + lines[end:]
+    return "\n".join(updated) + ("\n" if text.endswith("\n") else "")
+
+
+def main(argv=None):
+    parser = argparse.ArgumentParser(description=__doc__)
+    parser.add_argument("path", help="Markdown file to update in place")
+    parser.add_argument("--min-level", type=int, default=2)
+    parser.add_argument("--max-level", type=int, default=4)
+    parser.add_argument("--check", action="store_true", help="fail if the TOC is out of date")
+    args = parser.parse_args(argv)
+
+    with open(args.path, encoding="utf-8") as handle:
+        original = handle.read()
+    updated = update(original, min_level=args.min_level, max_level=args.max_level)
+
+    if args.check:
+        if updated != original:
+            print(f"{args.path}: table of contents is out of date", file=sys.stderr)
+            return 1
+        return 0
+    if updated != original:
+        with open(args.path, "w", encoding="utf-8") as handle:
+            handle.write(updated)
+        print(f"updated {args.path}")
+    return 0
+
+
+if __name__ == "__main__":
+    sys.exit(main())
@@ -0,0 +1,126 @@
+"""A tiny cron-like scheduler for periodic background jobs."""
+
+from __future__ import annotations
+
+import heapq
+import threading
+import time
+import traceback
+from dataclasses import dataclass, field
+from datetime import datetime, timedelta
+from typing import Callable, Optional
+
+
+@dataclass(order=True)
+class _Entry:
+    due: float
+    seq: int
+    job: "Job" = field(compare=False)
+
+
+@dataclass
+class Job:
+    name: str
+    func: Callable[[], None]
+    interval: timedelta
+    jitter: float = 0.0
+    runs: int = 0
+    failures: int = 0
+    last_error: Optional[str] = None
+    paused: bool = False
+
+
+class Scheduler:
+    """Runs jobs at fixed intervals on a single worker thread."""
+
+    def __init__(self, clock: Callable[[], float] = time.monotonic) -> None:
+        self._clock = clock
+        self._queue: list[_Entry] = []
+        self._jobs: dict[str, Job] = {}
+        self._seq = 0
+        self._lock = threading.Condition()
+        self._stopped = False
+        self._thread: Optional[threading.Thread] = None
+
+    def every(self, interval: timedelta, name: Optional[str] = None):
+        """Decorator registering the function as a job."""
+
+        def register(func: Callable[[], None]) -> Callable[[], None]:
+            self.add(Job(name or func.__name__, func, interval))
+            return func
+
+        return register
+
+    def add(self, job: Job, run_now: bool = False) -> None:
+        with self._lock:
+            if job.name in self._jobs:
+                raise ValueError(f"job {job.name!r} already exists")
+            self._jobs[job.name] = job
+            delay = 0.0 if run_now else job.interval.total_seconds()
+            self._push(job, self._clock() + delay)
+            self._lock.notify()
+
+    def pause(self, name: str) -> None:
+        with self._lock:
+            self._jobs[name].paused = True
+
+    def resume(self, name: str) -> None:
+        with self._lock:
+            self._jobs[name].paused = False
+
+    def start(self) -> None:
+        if self._thread is not None:
+            raise RuntimeError("scheduler already started")
+        self._thread = threading.Thread(target=self._run, name="scheduler", daemon=True)
+        self._thread.start()
+
+    def stop(self, timeout: float = 5.0) -> None:
+        with self._lock:
+            self._stopped = True
+            self._lock.notify()
+        if self._thread is not None:
+            self._thread.join(timeout)
+
+    def status(self) -> list[dict]:
+        with self._lock:
+            return [
+                {
+                    "name": job.name,
+                    "runs": job.runs,
+                    "failures": job.failures,
+                    "paused": job.paused,
+                    "last_error": job.last_error,
+                }
+                for job in sorted(self._jobs.values(), key=lambda j: j.name)
+            ]
+
+    def _push(self, job: Job, due: float) -> None:
+        self._seq += 1
+        heapq.heappush(self._queue, _Entry(due, self._seq, job))
+
+    def _run(self) -> None:
+        while True:
+            with self._lock:
+                while not self._stopped and (
+                    not self._queue or self._queue[0].due > self._clock()
+                ):
+                    timeout = self._queue[0].due - self._clock() if self._queue else None
+                    self._lock.wait(timeout)
+                if self._stopped:
+                    return
+                entry = heapq.heappop(self._queue)
+                job = entry.job
+                self._push(job, entry.due + job.interval.total_seconds())
+                if job.paused:
+                    continue
+            started = datetime.now()
+            try:
+                job.func()
+                job.last_error = None
+            except Exception:  # noqa: BLE001 - a failing job must not stop the others
+                job.failures += 1
+                job.last_error = traceback.format_exc(limit=3)
+            finally:
+                job.runs += 1
+            if datetime.now() - started > job.interval:
+                print(f"warning: job {job.name} ran longer than its interval")
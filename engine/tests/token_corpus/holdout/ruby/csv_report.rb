@@ -0,0 +1,89 @@
+# frozen_string_literal: true
+
+require "csv"
+require "date"
+require "optparse"
+
+# Summarize a CSV of time entries per person and project.
+class TimesheetReport
+  Entry = Struct.new(:person, :project, :date, :hours)
+
+  attr_reader :entries, :errors
+
+  def initialize(io)
+    @entries = []
+    @errors = []
+    parse(io)
+  end
+
+  def by_person
+    group_hours(&:person)
+  end
+
+  def by_project
+    group_hours(&:project)
+  end
+
+  def weekly(person)
+    entries.select { |e| e.person == person }
+           .group_by { |e| e.date.cweek }
+           .transform_values { |list| list.sum(&:hours).round(2) }
+           .sort.to_h
+  end
+
+  def overtime(limit_per_week = 40)
+    entries.group_by { |e| [e.person, e.date.cweek] }
+           .filter_map do |(person, week), list|
+             hours = list.sum(&:hours)
+             [person, week, (hours - limit_per_week).round(2)] if hours > limit_per_week
+           end
+  end
+
+  def to_table(rows, headers)
+    widths = headers.map(&:length)
+    rows.each do |row|
+      row.each_with_index { |cell, i| widths[i] = [widths[i], cell.to_s.length].max }
+    end
+    line = ->(cells) { cells.each_with_index.map { |c, i| c.to_s.ljust(widths[i]) }.join("  ") }
+    [line.call(headers), widths.map { |w| "-" * w }.join("  "), *rows.map(&line)].join("\n")
+  end
+
+  private
+
+  def parse(io)
+    CSV.new(io, headers: true).each.with_index(2) do |row, lineno|
+      entries << Entry.new(
+        row.fetch("person").strip,
+        row.fetch("project").strip,
+        Date.iso8601(row.fetch("date")),
+        Float(row.fetch("hours"))
+      )
+    rescue KeyError, ArgumentError, TypeError => e
+      errors << "line #{lineno}: #{e.message}"
+    end
+  end
+
+  def group_hours(&key)
+    entries.group_by(&key)
+           .transform_values { |list| list.sum(&:hours).round(2) }
+           .sort_by { |_, hours| -hours }
+  end
+end
+
+if $PROGRAM_NAME == __FILE__
+  options = { group: :person }
+  OptionParser.new do |opts|
+    opts.banner = "Usage: csv_report.rb [options] FILE"
+    opts.on("-p", "--project", "Group by project") { options[:group] = :project }
+    opts.on("-o", "--overtime HOURS", Float, "List weeks over HOURS") { |h| options[:overtime] = h }
+  end.parse!
+
+  report = File.open(ARGV.fetch(0)) { |io| TimesheetReport.new(io) }
+  report.errors.each { |error| warn error }
+  if options[:overtime]
+    puts report.to_table(report.overtime(options[:overtime]), %w[Person Week Over])
+  else
+    rows = options[:group] == :project ? report.by_project : report.by_person
+    puts report.to_table(rows, [options[:group].to_s.capitalize, "Hours"])
+  end
+end
@@ -0,0 +1,98 @@
+# frozen_string_literal: true
+
+require "uri"
+
+module Blog
+  Post = Struct.new(:id, :title, :slug, :published_at, :tags, keyword_init: true) do
+    def published?
+      !published_at.nil? && published_at <= Time.now
+    end
+  end
+
+  class Slugger
+    TRANSLITERATIONS = {
+      "ä" => "ae", "ö" => "oe", "ü" => "ue", "ß" => "ss",
+      "é" => "e", "è" => "e", "ê" => "e", "à" => "a", "ç" => "c"
+    }.freeze
+
+    def initialize(max_length: 60)
+      @max_length = max_length
+      @taken = Hash.new(0)
+    end
+
+    def call(title)
+      base = title.downcase
+                  .gsub(/[#{TRANSLITERATIONS.keys.join}]/, TRANSLITERATIONS)
+                  .gsub(/[^a-z0-9\s-]/, "")
+                  .strip
+                  .gsub(/[\s-]+/, "-")
+      base = base[0, @max_length].sub(/-+\z/, "")
+      base = "post" if base.empty?
+      count = @taken[base]
+      @taken[base] += 1
+      count.zero? ? base : "#{base}-#{count + 1}"
+    end
+  end
+
+  class Router
+    Route = Struct.new(:pattern, :keys, :action)
+
+    def initialize(posts)
+      @posts = posts.each_with_object({}) { |post, index| index[post.slug] = post }
+      @routes = []
+      draw
+    end
+
+    def resolve(path)
+      path = URI.decode_www_form_component(path.chomp("/"))
+      @routes.each do |route|
+        match = route.pattern.match(path)
+        next unless match
+
+        params = route.keys.zip(match.captures).to_h
+        return send(route.action, **params)
+      end
+      [404, "Not found"]
+    end
+
+    private
+
+    def draw
+      get %r{\A\z}, :index
+      get %r{\A/posts/(?<slug>[a-z0-9-]+)\z}, :show
+      get %r{\A/tags/(?<tag>[\w-]+)\z}, :tagged
+      get %r{\A/(?<year>\d{4})/(?<month>\d{2})\z}, :archive
+    end
+
+    def get(pattern, action)
+      @routes << Route.new(pattern, pattern.names.map(&:to_sym), action)
+    end
+
+    def index
+      [200, published.sort_by(&:published_at).reverse.first(10).map(&:title)]
+    end
+
+    def show(slug:)
+      post = @posts[slug]
+      return [404, "No post #{slug}"] unless post&.published?
+
+      [200, post.title]
+    end
+
+    def tagged(tag:)
+      posts = published.select { |post| Array(post.tags).include?(tag) }
+      posts.empty? ? [404, "No posts tagged #{tag}"] : [200, posts.map(&:title)]
+    end
+
+    def archive(year:, month:)
+      posts = published.select do |post|
+        post.published_at.year == year.to_i && post.published_at.month == month.to_i
+      end
+      [200, posts.map(&:title)]
+    end
+
+    def published
+      @posts.values.select(&:published?)
+    end
+  end
+end
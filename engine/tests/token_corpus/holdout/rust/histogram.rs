@@ -0,0 +1,91 @@
+//! Fixed-bucket latency histogram with percentile queries
+
+use std::fmt;
+use std::time::Duration;
+
+/// Bucket upper bounds in microseconds, roughly doubling
+const BOUNDS_US: [u64; 16] = [
+    50, 100, 250, 500, 1_000, 2_500, 5_000, 10_000, 25_000, 50_000, 100_000, 250_000, 500_000,
+    1_000_000, 2_500_000, 5_000_000,
+];
+
+#[derive(Debug, Clone, Default)]
+pub struct Histogram {
+    counts: [u64; BOUNDS_US.len() + 1],
+    total: u64,
+    sum_us: u128,
+    max_us: u64,
+}
+
+impl Histogram {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record(&mut self, latency: Duration) {
+        let us = latency.as_micros().min(u128::from(u64::MAX)) as u64;
+        let bucket = BOUNDS_US.partition_point(|&bound| bound < us);
+        self.counts[bucket] += 1;
+        self.total += 1;
+        self.sum_us += u128::from(us);
+        self.max_us = self.max_us.max(us);
+    }
+
+    pub fn merge(&mut self, other: &Self) {
+        for (mine, theirs) in self.counts.iter_mut().zip(other.counts.iter()) {
+            *mine += theirs;
+        }
+        self.total += other.total;
+        self.sum_us += other.sum_us;
+        self.max_us = self.max_us.max(other.max_us);
+    }
+
+    pub fn count(&self) -> u64 {
+        self.total
+    }
+
+    pub fn mean(&self) -> Option<Duration> {
+        (self.total > 0).then(|| Duration::from_micros((self.sum_us / u128::from(self.total)) as u64))
+    }
+
+    /// Upper bound of the bucket holding the `p`th percentile, `p` in 0..=100
+    pub fn percentile(&self, p: f64) -> Option<Duration> {
+        if self.total == 0 {
+            return None;
+        }
+        let rank = ((p.clamp(0.0, 100.0) / 100.0) * self.total as f64).ceil().max(1.0) as u64;
+        let mut seen = 0;
+        for (bucket, &count) in self.counts.iter().enumerate() {
+            seen += count;
+            if seen >= rank {
+                let bound = BOUNDS_US.get(bucket).copied().unwrap_or(self.max_us);
+                return Some(Duration::from_micros(bound.min(self.max_us)));
+            }
+        }
+        Some(Duration::from_micros(self.max_us))
+    }
+
+    pub fn buckets(&self) -> impl Iterator<Item = (Option<Duration>, u64)> + '_ {
+        self.counts.iter().enumerate().map(|(i, &count)| {
+            (BOUNDS_US.get(i).map(|&us| Duration::from_micros(us)), count)
+        })
+    }
+}
+
+impl fmt::Display for Histogram {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let widest = self.counts.iter().copied().max().unwrap_or(0).max(1);
+        for (bound, count) in self.buckets().filter(|(_, count)| *count > 0) {
+            let label = match bound {
+                Some(bound) => format!("<= {:?}", bound),
+                None => "> 5s".to_owned(),
+            };
+            let bar = "#".repeat((count * 40 / widest) as usize);
+            writeln!(f, "{:>12} {:>8} {}", label, count, bar)?;
+        }
+        if let (Some(p50), Some(p99)) = (self.percentile(50.0), self.percentile(99.0)) {
+            write!(f, "p50 {:?}  p99 {:?}  max {:?}", p50, p99, Duration::from_micros(self.max_us))?;
+        }
+        Ok(())
+    }
+}
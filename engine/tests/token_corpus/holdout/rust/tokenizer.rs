@@ -0,0 +1,112 @@
+//! Lexer for a small expression language
+
+use std::iter::Peekable;
+use std::str::CharIndices;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Token {
+    Number(f64),
+    Ident(String),
+    Str(String),
+    Op(char),
+    LParen,
+    RParen,
+    Comma,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Spanned {
+    pub token: Token,
+    pub start: usize,
+    pub end: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LexError {
+    pub message: String,
+    pub offset: usize,
+}
+
+pub struct Lexer<'a> {
+    source: &'a str,
+    chars: Peekable<CharIndices<'a>>,
+}
+
+impl<'a> Lexer<'a> {
+    pub fn new(source: &'a str) -> Self {
+        Self { source, chars: source.char_indices().peekable() }
+    }
+
+    fn error(&self, message: impl Into<String>, offset: usize) -> LexError {
+        LexError { message: message.into(), offset }
+    }
+
+    fn take_while(&mut self, start: usize, keep: impl Fn(char) -> bool) -> &'a str {
+        let mut end = start;
+        while let Some(&(i, c)) = self.chars.peek() {
+            if !keep(c) {
+                break;
+            }
+            end = i + c.len_utf8();
+            self.chars.next();
+        }
+        &self.source[start..end]
+    }
+
+    fn string(&mut self, start: usize) -> Result<Spanned, LexError> {
+        let mut value = String::new();
+        while let Some((i, c)) = self.chars.next() {
+            match c {
+                '"' => {
+                    return Ok(Spanned { token: Token::Str(value), start, end: i + 1 });
+                },
+                '\\' => match self.chars.next() {
+                    Some((_, 'n')) => value.push('\n'),
+                    Some((_, 't')) => value.push('\t'),
+                    Some((_, escaped @ ('"' | '\\'))) => value.push(escaped),
+                    Some((j, other)) => {
+                        return Err(self.error(format!("unknown escape \\{}", other), j));
+                    },
+                    None => break,
+                },
+                c => value.push(c),
+            }
+        }
+        Err(self.error("unterminated string", start))
+    }
+}
+
+impl Iterator for Lexer<'_> {
+    type Item = Result<Spanned, LexError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.chars.next_if(|(_, c)| c.is_whitespace()).is_some() {}
+        let (start, c) = self.chars.next()?;
+        let single = |token| Some(Ok(Spanned { token, start, end: start + 1 }));
+        match c {
+            '(' => single(Token::LParen),
+            ')' => single(Token::RParen),
+            ',' => single(Token::Comma),
+            '+' | '-' | '*' | '/' | '%' | '<' | '>' | '=' => single(Token::Op(c)),
+            '"' => Some(self.string(start)),
+            '0'..='9' | '.' => {
+                let text = self.take_while(start, |c| c.is_ascii_digit() || c == '.');
+                let token = text
+                    .parse()
+                    .map(Token::Number)
+                    .map_err(|_| self.error(format!("bad number {}", text), start));
+                Some(token.map(|token| Spanned { token, start, end: start + text.len() }))
+            },
+            c if c.is_alphabetic() || c == '_' => {
+                let text = self.take_while(start, |c| c.is_alphanumeric() || c == '_');
+                let end = start + text.len();
+                Some(Ok(Spanned { token: Token::Ident(text.to_owned()), start, end }))
+            },
+            other => Some(Err(self.error(format!("unexpected {:?}", other), start))),
+        }
+    }
+}
+
+pub fn tokenize(source: &str) -> Result<Vec<Spanned>, LexError> {
+    Lexer::new(source).collect()
+}
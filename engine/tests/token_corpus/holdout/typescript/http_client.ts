@@ -0,0 +1,103 @@
+export class HttpError extends Error {
+  constructor(
+    readonly status: number,
+    readonly url: string,
+    readonly body: unknown,
+  ) {
+    super(`Request to ${url} failed with ${status}`);
+    this.name = "HttpError";
+  }
+}
+
+export interface RequestOptions {
+  query?: Record<string, string | number | boolean | undefined>;
+  headers?: Record<string, string>;
+  body?: unknown;
+  timeoutMs?: number;
+  retries?: number;
+  signal?: AbortSignal;
+}
+
+type Method = "GET" | "POST" | "PUT" | "PATCH" | "DELETE";
+
+export class HttpClient {
+  private token?: string;
+
+  constructor(
+    private readonly baseUrl: string,
+    private readonly defaults: Pick<RequestOptions, "timeoutMs" | "retries"> = { timeoutMs: 10_000, retries: 2 },
+  ) {}
+
+  setToken(token: string | undefined): void {
+    this.token = token;
+  }
+
+  get<T>(path: string, options?: RequestOptions): Promise<T> {
+    return this.request<T>("GET", path, options);
+  }
+
+  post<T>(path: string, body: unknown, options?: RequestOptions): Promise<T> {
+    return this.request<T>("POST", path, { ...options, body });
+  }
+
+  delete(path: string, options?: RequestOptions): Promise<void> {
+    return this.request<void>("DELETE", path, options);
+  }
+
+  async request<T>(method: Method, path: string, options: RequestOptions = {}): Promise<T> {
+    const url = this.url(path, options.query);
+    const retries = method === "GET" ? (options.retries ?? this.defaults.retries ?? 0) : 0;
+    let attempt = 0;
+
+    for (;;) {
+      const controller = new AbortController();
+      const timeout = setTimeout(() => controller.abort(), options.timeoutMs ?? this.defaults.timeoutMs);
+      options.signal?.addEventListener("abort", () => controller.abort(), { once: true });
+      try {
+        const response = await fetch(url, {
+          method,
+          headers: this.headers(options),
+          body: options.body === undefined ? undefined : JSON.stringify(options.body),
+          signal: controller.signal,
+        });
+        const data = await this.parse(response);
+        if (!response.ok) {
+          throw new HttpError(response.status, url, data);
+        }
+        return data as T;
+      } catch (error) {
+        const retryable = !(error instanceof HttpError) || error.status >= 500 || error.status === 429;
+        if (!retryable || attempt >= retries || options.signal?.aborted) {
+          throw error;
+        }
+        attempt += 1;
+        await new Promise((resolve) => setTimeout(resolve, 200 * 2 ** attempt));
+      } finally {
+        clearTimeout(timeout);
+      }
+    }
+  }
+
+  private url(path: string, query: RequestOptions["query"]): string {
+    const url = new URL(path.replace(/^\//, ""), this.baseUrl.endsWith("/") ? this.baseUrl : `${this.baseUrl}/`);
+    for (const [key, value] of Object.entries(query ?? {})) {
+      if (value !== undefined) url.searchParams.set(key, String(value));
+    }
+    return url.toString();
+  }
+
+  private headers(options: RequestOptions): Record<string, string> {
+    return {
+      Accept: "application/json",
+      ...(options.body !== undefined ? { "Content-Type": "application/json" } : {}),
+      ...(this.token ? { Authorization: `Bearer ${this.token}` } : {}),
+      ...options.headers,
+    };
+  }
+
+  private async parse(response: Response): Promise<unknown> {
+    if (response.status === 204) return undefined;
+    const type = response.headers.get("content-type") ?? "";
+    return type.includes("json") ? response.json() : response.text();
+  }
+}
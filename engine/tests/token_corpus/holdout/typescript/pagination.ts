@@ -0,0 +1,77 @@
+export interface Page<T> {
+  items: T[];
+  nextCursor: string | null;
+  total?: number;
+}
+
+export type FetchPage<T> = (cursor: string | null, limit: number) => Promise<Page<T>>;
+
+/**
+ * Iterate over every item of a cursor-paginated endpoint, fetching pages
+ * lazily as the caller consumes them.
+ */
+export async function* paginate<T>(fetchPage: FetchPage<T>, limit = 100): AsyncGenerator<T, void, undefined> {
+  let cursor: string | null = null;
+  const seen = new Set<string>();
+  do {
+    const page: Page<T> = await fetchPage(cursor, limit);
+    yield* page.items;
+    cursor = page.nextCursor;
+    if (cursor !== null) {
+      if (seen.has(cursor)) {
+        throw new Error(`Cursor ${cursor} repeated; the endpoint is looping`);
+      }
+      seen.add(cursor);
+    }
+  } while (cursor !== null);
+}
+
+export async function collect<T>(items: AsyncIterable<T>, max = Infinity): Promise<T[]> {
+  const result: T[] = [];
+  for await (const item of items) {
+    if (result.length >= max) break;
+    result.push(item);
+  }
+  return result;
+}
+
+export async function* mapConcurrent<T, R>(
+  items: AsyncIterable<T>,
+  concurrency: number,
+  fn: (item: T) => Promise<R>,
+): AsyncGenerator<R, void, undefined> {
+  if (concurrency < 1) throw new RangeError("concurrency must be at least 1");
+  const running = new Map<number, Promise<[number, R]>>();
+  let index = 0;
+  for await (const item of items) {
+    const id = index++;
+    running.set(id, fn(item).then((value) => [id, value] as [number, R]));
+    if (running.size >= concurrency) {
+      const [done, value] = await Promise.race(running.values());
+      running.delete(done);
+      yield value;
+    }
+  }
+  while (running.size > 0) {
+    const [done, value] = await Promise.race(running.values());
+    running.delete(done);
+    yield value;
+  }
+}
+
+export interface OffsetQuery {
+  page: number;
+  perPage: number;
+}
+
+export function offsetPages(total: number, perPage: number): OffsetQuery[] {
+  if (perPage <= 0) throw new RangeError("perPage must be positive");
+  const count = Math.ceil(total / perPage);
+  return Array.from({ length: count }, (_, i) => ({ page: i + 1, perPage }));
+}
+
+export function pageLabel({ page, perPage }: OffsetQuery, total: number): string {
+  const first = (page - 1) * perPage + 1;
+  const last = Math.min(page * perPage, total);
+  return total === 0 ? "No results" : `${first}-${last} of ${total}`;
+}
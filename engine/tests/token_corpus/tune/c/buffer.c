@@ -0,0 +1,104 @@
+/* Minimal growable byte buffer and a line reader built on it. */
+
+#include <errno.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+
+typedef struct {
+    char *data;
+    size_t len;
+    size_t cap;
+} buffer_t;
+
+static int buffer_reserve(buffer_t *buf, size_t extra)
+{
+    size_t needed = buf->len + extra;
+    if (needed <= buf->cap)
+        return 0;
+
+    size_t cap = buf->cap ? buf->cap : 64;
+    while (cap < needed) {
+        if (cap > SIZE_MAX / 2)
+            return -ENOMEM;
+        cap *= 2;
+    }
+
+    char *data = realloc(buf->data, cap);
+    if (data == NULL)
+        return -ENOMEM;
+    buf->data = data;
+    buf->cap = cap;
+    return 0;
+}
+
+int buffer_append(buffer_t *buf, const char *bytes, size_t n)
+{
+    int err = buffer_reserve(buf, n + 1);
+    if (err)
+        return err;
+    memcpy(buf->data + buf->len, bytes, n);
+    buf->len += n;
+    buf->data[buf->len] = '\0';
+    return 0;
+}
+
+void buffer_free(buffer_t *buf)
+{
+    free(buf->data);
+    buf->data = NULL;
+    buf->len = buf->cap = 0;
+}
+
+/*
+ * Read one line from fp into buf, without the trailing newline.
+ * Returns 1 when a line was read, 0 at end of file and a negative errno on failure.
+ */
+int read_line(FILE *fp, buffer_t *buf)
+{
+    char chunk[256];
+    buf->len = 0;
+
+    while (fgets(chunk, sizeof chunk, fp) != NULL) {
+        size_t n = strlen(chunk);
+        int done = n > 0 && chunk[n - 1] == '\n';
+        if (done)
+            n--;
+        int err = buffer_append(buf, chunk, n);
+        if (err)
+            return err;
+        if (done)
+            return 1;
+    }
+    if (ferror(fp))
+        return -EIO;
+    return buf->len > 0 ? 1 : 0;
+}
+
+int main(int argc, char **argv)
+{
+    FILE *fp = argc > 1 ? fopen(argv[1], "r") : stdin;
+    if (fp == NULL) {
+        fprintf(stderr, "%s: %s\n", argv[1], strerror(errno));
+        return EXIT_FAILURE;
+    }
+
+    buffer_t line = {0};
+    size_t count = 0, longest = 0;
+    int rc;
+    while ((rc = read_line(fp, &line)) > 0) {
+        count++;
+        if (line.len > longest)
+            longest = line.len;
+    }
+
+    buffer_free(&line);
+    if (fp != stdin)
+        fclose(fp);
+    if (rc < 0) {
+        fprintf(stderr, "read failed: %s\n", strerror(-rc));
+        return EXIT_FAILURE;
+    }
+    printf("%zu lines, longest is %zu bytes\n", count, longest);
+    return EXIT_SUCCESS;
+}
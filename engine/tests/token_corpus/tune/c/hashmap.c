@@ -0,0 +1,96 @@
+/* Open-addressing hash map from strings to integers. */
+
+#include <stdint.h>
+#include <stdlib.h>
+#include <string.h>
+
+#define MAP_MIN_CAPACITY 16
+#define MAP_MAX_LOAD 0.75
+
+typedef struct {
+    char *key;
+    long value;
+    uint32_t hash;
+} slot_t;
+
+typedef struct {
+    slot_t *slots;
+    size_t capacity;
+    size_t count;
+} strmap_t;
+
+static uint32_t fnv1a(const char *key)
+{
+    uint32_t hash = 2166136261u;
+    for (const unsigned char *p = (const unsigned char *)key; *p; p++) {
+        hash ^= *p;
+        hash *= 16777619u;
+    }
+    return hash ? hash : 1;
+}
+
+static slot_t *find_slot(slot_t *slots, size_t capacity, const char *key, uint32_t hash)
+{
+    size_t index = hash & (capacity - 1);
+    for (;;) {
+        slot_t *slot = &slots[index];
+        if (slot->hash == 0 || (slot->hash == hash && strcmp(slot->key, key) == 0))
+            return slot;
+        index = (index + 1) & (capacity - 1);
+    }
+}
+
+static int grow(strmap_t *map)
+{
+    size_t capacity = map->capacity ? map->capacity * 2 : MAP_MIN_CAPACITY;
+    slot_t *slots = calloc(capacity, sizeof(*slots));
+    if (slots == NULL)
+        return -1;
+
+    for (size_t i = 0; i < map->capacity; i++) {
+        slot_t *old = &map->slots[i];
+        if (old->hash != 0)
+            *find_slot(slots, capacity, old->key, old->hash) = *old;
+    }
+    free(map->slots);
+    map->slots = slots;
+    map->capacity = capacity;
+    return 0;
+}
+
+int strmap_put(strmap_t *map, const char *key, long value)
+{
+    if (map->count + 1 > map->capacity * MAP_MAX_LOAD && grow(map) != 0)
+        return -1;
+
+    uint32_t hash = fnv1a(key);
+    slot_t *slot = find_slot(map->slots, map->capacity, key, hash);
+    if (slot->hash == 0) {
+        slot->key = strdup(key);
+        if (slot->key == NULL)
+            return -1;
+        slot->hash = hash;
+        map->count++;
+    }
+    slot->value = value;
+    return 0;
+}
+
+int strmap_get(const strmap_t *map, const char *key, long *value)
+{
+    if (map->count == 0)
+        return 0;
+    slot_t *slot = find_slot(map->slots, map->capacity, key, fnv1a(key));
+    if (slot->hash == 0)
+        return 0;
+    *value = slot->value;
+    return 1;
+}
+
+void strmap_free(strmap_t *map)
+{
+    for (size_t i = 0; i < map->capacity; i++)
+        free(map->slots[i].key);
+    free(map->slots);
+    memset(map, 0, sizeof(*map));
+}
@@ -0,0 +1,99 @@
+#include <algorithm>
+#include <cstdint>
+#include <iostream>
+#include <limits>
+#include <optional>
+#include <queue>
+#include <string>
+#include <unordered_map>
+#include <vector>
+
+namespace graph {
+
+struct Edge {
+    std::size_t to;
+    std::uint32_t weight;
+};
+
+class Graph {
+public:
+    explicit Graph(std::size_t nodes) : adjacency_(nodes) {}
+
+    void add_edge(std::size_t from, std::size_t to, std::uint32_t weight) {
+        adjacency_.at(from).push_back(Edge{to, weight});
+        adjacency_.at(to).push_back(Edge{from, weight});
+    }
+
+    [[nodiscard]] std::size_t size() const noexcept { return adjacency_.size(); }
+
+    [[nodiscard]] const std::vector<Edge>& neighbors(std::size_t node) const {
+        return adjacency_.at(node);
+    }
+
+private:
+    std::vector<std::vector<Edge>> adjacency_;
+};
+
+struct Path {
+    std::uint64_t cost = 0;
+    std::vector<std::size_t> nodes;
+};
+
+std::optional<Path> shortest_path(const Graph& g, std::size_t source, std::size_t target) {
+    constexpr auto kInfinity = std::numeric_limits<std::uint64_t>::max();
+    std::vector<std::uint64_t> dist(g.size(), kInfinity);
+    std::vector<std::size_t> previous(g.size(), g.size());
+
+    using Item = std::pair<std::uint64_t, std::size_t>;
+    std::priority_queue<Item, std::vector<Item>, std::greater<>> frontier;
+    dist[source] = 0;
+    frontier.emplace(0, source);
+
+    while (!frontier.empty()) {
+        auto [cost, node] = frontier.top();
+        frontier.pop();
+        if (cost > dist[node]) continue;
+        if (node == target) break;
+        for (const auto& edge : g.neighbors(node)) {
+            const auto next = cost + edge.weight;
+            if (next < dist[edge.to]) {
+                dist[edge.to] = next;
+                previous[edge.to] = node;
+                frontier.emplace(next, edge.to);
+            }
+        }
+    }
+
+    if (dist[target] == kInfinity) return std::nullopt;
+
+    Path path{dist[target], {}};
+    for (auto node = target; node != g.size(); node = previous[node]) {
+        path.nodes.push_back(node);
+    }
+    std::reverse(path.nodes.begin(), path.nodes.end());
+    return path;
+}
+
+}  // namespace graph
+
+int main() {
+    const std::vector<std::string> names{"depot", "north", "east", "south", "harbor"};
+    std::unordered_map<std::string, std::size_t> index;
+    for (std::size_t i = 0; i < names.size(); ++i) index.emplace(names[i], i);
+
+    graph::Graph g(names.size());
+    g.add_edge(index["depot"], index["north"], 7);
+    g.add_edge(index["depot"], index["east"], 9);
+    g.add_edge(index["north"], index["south"], 10);
+    g.add_edge(index["east"], index["south"], 2);
+    g.add_edge(index["south"], index["harbor"], 6);
+
+    if (auto path = graph::shortest_path(g, index["depot"], index["harbor"])) {
+        std::cout << "cost " << path->cost << ':';
+        for (auto node : path->nodes) std::cout << ' ' << names[node];
+        std::cout << '\n';
+    } else {
+        std::cout << "no route\n";
+    }
+    return 0;
+}
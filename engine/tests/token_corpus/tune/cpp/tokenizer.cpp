@@ -0,0 +1,97 @@
+#include <cctype>
+#include <stdexcept>
+#include <string>
+#include <string_view>
+#include <variant>
+#include <vector>
+
+namespace calc {
+
+enum class TokenKind { Number, Identifier, Operator, LeftParen, RightParen, End };
+
+struct Token {
+    TokenKind kind;
+    std::string text;
+    double value = 0.0;
+    std::size_t offset = 0;
+};
+
+class SyntaxError : public std::runtime_error {
+public:
+    SyntaxError(const std::string& message, std::size_t offset)
+        : std::runtime_error(message + " at offset " + std::to_string(offset)),
+          offset_(offset) {}
+
+    std::size_t offset() const noexcept { return offset_; }
+
+private:
+    std::size_t offset_;
+};
+
+class Lexer {
+public:
+    explicit Lexer(std::string_view source) : source_(source) {}
+
+    std::vector<Token> tokenize() {
+        std::vector<Token> tokens;
+        while (true) {
+            skipWhitespace();
+            if (pos_ >= source_.size()) {
+                tokens.push_back({TokenKind::End, "", 0.0, pos_});
+                return tokens;
+            }
+            tokens.push_back(next());
+        }
+    }
+
+private:
+    void skipWhitespace() {
+        while (pos_ < source_.size() && std::isspace(static_cast<unsigned char>(source_[pos_]))) {
+            ++pos_;
+        }
+    }
+
+    Token next() {
+        const std::size_t start = pos_;
+        const char c = source_[pos_];
+        if (std::isdigit(static_cast<unsigned char>(c)) || c == '.') {
+            while (pos_ < source_.size() &&
+                   (std::isdigit(static_cast<unsigned char>(source_[pos_])) || source_[pos_] == '.')) {
+                ++pos_;
+            }
+            std::string text(source_.substr(start, pos_ - start));
+            try {
+                return {TokenKind::Number, text, std::stod(text), start};
+            } catch (const std::exception&) {
+                throw SyntaxError("invalid number '" + text + "'", start);
+            }
+        }
+        if (std::isalpha(static_cast<unsigned char>(c)) || c == '_') {
+            while (pos_ < source_.size() &&
+                   (std::isalnum(static_cast<unsigned char>(source_[pos_])) || source_[pos_] == '_')) {
+                ++pos_;
+            }
+            return {TokenKind::Identifier, std::string(source_.substr(start, pos_ - start)), 0.0, start};
+        }
+        ++pos_;
+        switch (c) {
+        case '(':
+            return {TokenKind::LeftParen, "(", 0.0, start};
+        case ')':
+            return {TokenKind::RightParen, ")", 0.0, start};
+        case '+':
+        case '-':
+        case '*':
+        case '/':
+        case '^':
+            return {TokenKind::Operator, std::string(1, c), 0.0, start};
+        default:
+            throw SyntaxError(std::string("unexpected character '") + c + "'", start);
+        }
+    }
+
+    std::string_view source_;
+    std::size_t pos_ = 0;
+};
+
+}  // namespace calc
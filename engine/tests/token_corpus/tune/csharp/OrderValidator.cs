@@ -0,0 +1,109 @@
+using System;
+using System.Collections.Generic;
+using System.Globalization;
+using System.Linq;
+
+namespace Shop.Orders
+{
+    public enum Severity
+    {
+        Warning,
+        Error
+    }
+
+    public sealed record ValidationIssue(string Field, string Message, Severity Severity);
+
+    public sealed class OrderLine
+    {
+        public string Sku { get; init; } = string.Empty;
+        public int Quantity { get; init; }
+        public decimal UnitPrice { get; init; }
+        public decimal Total => Quantity * UnitPrice;
+    }
+
+    public sealed class Order
+    {
+        public Guid Id { get; init; } = Guid.NewGuid();
+        public string CustomerEmail { get; init; } = string.Empty;
+        public string Currency { get; init; } = "USD";
+        public IReadOnlyList<OrderLine> Lines { get; init; } = Array.Empty<OrderLine>();
+        public DateTimeOffset PlacedAt { get; init; } = DateTimeOffset.UtcNow;
+    }
+
+    public interface IOrderRule
+    {
+        IEnumerable<ValidationIssue> Check(Order order);
+    }
+
+    public sealed class OrderValidator
+    {
+        private static readonly HashSet<string> SupportedCurrencies =
+            new(StringComparer.OrdinalIgnoreCase) { "USD", "EUR", "GBP", "JPY" };
+
+        private readonly List<IOrderRule> _rules = new();
+        private readonly decimal _maxOrderTotal;
+
+        public OrderValidator(decimal maxOrderTotal)
+        {
+            if (maxOrderTotal <= 0)
+            {
+                throw new ArgumentOutOfRangeException(nameof(maxOrderTotal), "Limit must be positive.");
+            }
+            _maxOrderTotal = maxOrderTotal;
+        }
+
+        public OrderValidator AddRule(IOrderRule rule)
+        {
+            _rules.Add(rule ?? throw new ArgumentNullException(nameof(rule)));
+            return this;
+        }
+
+        public IReadOnlyList<ValidationIssue> Validate(Order order)
+        {
+            var issues = new List<ValidationIssue>();
+
+            if (string.IsNullOrWhiteSpace(order.CustomerEmail) || !order.CustomerEmail.Contains('@'))
+            {
+                issues.Add(new ValidationIssue(nameof(order.CustomerEmail), "A valid email is required.", Severity.Error));
+            }
+
+            if (!SupportedCurrencies.Contains(order.Currency))
+            {
+                issues.Add(new ValidationIssue(nameof(order.Currency), $"Currency '{order.Currency}' is not supported.", Severity.Error));
+            }
+
+            if (order.Lines.Count == 0)
+            {
+                issues.Add(new ValidationIssue(nameof(order.Lines), "An order needs at least one line.", Severity.Error));
+            }
+
+            foreach (var (line, index) in order.Lines.Select((line, index) => (line, index)))
+            {
+                if (line.Quantity <= 0)
+                {
+                    issues.Add(new ValidationIssue($"Lines[{index}].Quantity", "Quantity must be positive.", Severity.Error));
+                }
+                if (line.UnitPrice < 0)
+                {
+                    issues.Add(new ValidationIssue($"Lines[{index}].UnitPrice", "Price cannot be negative.", Severity.Error));
+                }
+            }
+
+            var duplicates = order.Lines.GroupBy(l => l.Sku).Where(g => g.Count() > 1).Select(g => g.Key);
+            foreach (var sku in duplicates)
+            {
+                issues.Add(new ValidationIssue(nameof(order.Lines), $"SKU {sku} appears more than once.", Severity.Warning));
+            }
+
+            var total = order.Lines.Sum(l => l.Total);
+            if (total > _maxOrderTotal)
+            {
+                var limit = _maxOrderTotal.ToString("N2", CultureInfo.InvariantCulture);
+                issues.Add(new ValidationIssue("Total", $"Order total exceeds the limit of {limit}.", Severity.Warning));
+            }
+
+            issues.AddRange(_rules.SelectMany(rule => rule.Check(order)));
+            return issues;
+        }
+    }
+}
@@ -0,0 +1,105 @@
+using System;
+using System.Collections.Generic;
+using System.Linq;
+using System.Threading;
+using System.Threading.Tasks;
+
+namespace Scheduling
+{
+    public enum Priority
+    {
+        Low,
+        Normal,
+        High,
+    }
+
+    public sealed record WorkItem(string Name, Priority Priority, TimeSpan Estimate, IReadOnlyList<string> DependsOn);
+
+    public interface IWorker
+    {
+        Task RunAsync(WorkItem item, CancellationToken cancellationToken);
+    }
+
+    /// <summary>
+    /// Orders work items by their dependencies and runs them, highest priority first.
+    /// </summary>
+    public class Scheduler
+    {
+        private readonly Dictionary<string, WorkItem> _items = new(StringComparer.OrdinalIgnoreCase);
+        private readonly IWorker _worker;
+
+        public Scheduler(IWorker worker)
+        {
+            _worker = worker ?? throw new ArgumentNullException(nameof(worker));
+        }
+
+        public event EventHandler<WorkItem>? Started;
+        public event EventHandler<(WorkItem Item, Exception Error)>? Failed;
+
+        public void Add(WorkItem item)
+        {
+            if (!_items.TryAdd(item.Name, item))
+            {
+                throw new InvalidOperationException($"Duplicate work item '{item.Name}'");
+            }
+        }
+
+        public IReadOnlyList<WorkItem> Plan()
+        {
+            var ordered = new List<WorkItem>();
+            var state = new Dictionary<string, bool>(StringComparer.OrdinalIgnoreCase);
+
+            void Visit(WorkItem item)
+            {
+                if (state.TryGetValue(item.Name, out var done))
+                {
+                    if (!done)
+                    {
+                        throw new InvalidOperationException($"Dependency cycle through '{item.Name}'");
+                    }
+                    return;
+                }
+
+                state[item.Name] = false;
+                foreach (var name in item.DependsOn)
+                {
+                    if (!_items.TryGetValue(name, out var dependency))
+                    {
+                        throw new KeyNotFoundException($"'{item.Name}' depends on unknown '{name}'");
+                    }
+                    Visit(dependency);
+                }
+                state[item.Name] = true;
+                ordered.Add(item);
+            }
+
+            foreach (var item in _items.Values.OrderByDescending(i => i.Priority).ThenBy(i => i.Estimate))
+            {
+                Visit(item);
+            }
+            return ordered;
+        }
+
+        public async Task<int> RunAsync(CancellationToken cancellationToken = default)
+        {
+            var failures = 0;
+            foreach (var item in Plan())
+            {
+                cancellationToken.ThrowIfCancellationRequested();
+                Started?.Invoke(this, item);
+                try
+                {
+                    await _worker.RunAsync(item, cancellationToken).ConfigureAwait(false);
+                }
+                catch (Exception ex) when (ex is not OperationCanceledException)
+                {
+                    failures++;
+                    Failed?.Invoke(this, (item, ex));
+                }
+            }
+            return failures;
+        }
+
+        public TimeSpan TotalEstimate => _items.Values.Aggregate(TimeSpan.Zero, (sum, i) => sum + i.Estimate);
+    }
+}
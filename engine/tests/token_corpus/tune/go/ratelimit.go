@@ -0,0 +1,110 @@
+package ratelimit
+
+import (
+	"context"
+	"errors"
+	"sync"
+	"time"
+)
+
+// ErrLimited is returned when a caller would have to wait past its deadline.
+var ErrLimited = errors.New("ratelimit: request would exceed the deadline")
+
+// Bucket is a token bucket refilled at a constant rate.
+type Bucket struct {
+	mu       sync.Mutex
+	capacity float64
+	tokens   float64
+	rate     float64 // tokens per second
+	last     time.Time
+	now      func() time.Time
+}
+
+// NewBucket returns a full bucket holding up to capacity tokens.
+func NewBucket(capacity int, perSecond float64) *Bucket {
+	if capacity <= 0 || perSecond <= 0 {
+		panic("ratelimit: capacity and rate must be positive")
+	}
+	b := &Bucket{
+		capacity: float64(capacity),
+		tokens:   float64(capacity),
+		rate:     perSecond,
+		now:      time.Now,
+	}
+	b.last = b.now()
+	return b
+}
+
+func (b *Bucket) refill() {
+	now := b.now()
+	elapsed := now.Sub(b.last).Seconds()
+	b.last = now
+	b.tokens += elapsed * b.rate
+	if b.tokens > b.capacity {
+		b.tokens = b.capacity
+	}
+}
+
+// Allow takes n tokens if they are available right now.
+func (b *Bucket) Allow(n int) bool {
+	b.mu.Lock()
+	defer b.mu.Unlock()
+	b.refill()
+	if b.tokens < float64(n) {
+		return false
+	}
+	b.tokens -= float64(n)
+	return true
+}
+
+// Wait blocks until n tokens are available or ctx is done.
+func (b *Bucket) Wait(ctx context.Context, n int) error {
+	for {
+		b.mu.Lock()
+		b.refill()
+		if b.tokens >= float64(n) {
+			b.tokens -= float64(n)
+			b.mu.Unlock()
+			return nil
+		}
+		missing := float64(n) - b.tokens
+		delay := time.Duration(missing / b.rate * float64(time.Second))
+		b.mu.Unlock()
+
+		if deadline, ok := ctx.Deadline(); ok && time.Until(deadline) < delay {
+			return ErrLimited
+		}
+		timer := time.NewTimer(delay)
+		select {
+		case <-ctx.Done():
+			timer.Stop()
+			return ctx.Err()
+		case <-timer.C:
+		}
+	}
+}
+
+// Limiter keeps one bucket per key, such as a client IP address.
+type Limiter struct {
+	mu        sync.Mutex
+	buckets   map[string]*Bucket
+	capacity  int
+	perSecond float64
+}
+
+// NewLimiter creates a keyed limiter with the given bucket settings.
+func NewLimiter(capacity int, perSecond float64) *Limiter {
+	return &Limiter{buckets: make(map[string]*Bucket), capacity: capacity, perSecond: perSecond}
+}
+
+// Allow reports whether the request for key may proceed.
+func (l *Limiter) Allow(key string) bool {
+	l.mu.Lock()
+	b, ok := l.buckets[key]
+	if !ok {
+		b = NewBucket(l.capacity, l.perSecond)
+		l.buckets[key] = b
+	}
+	l.mu.Unlock()
+	return b.Allow(1)
+}
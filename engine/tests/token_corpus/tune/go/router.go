@@ -0,0 +1,100 @@
+package router
+
+import (
+	"context"
+	"net/http"
+	"strings"
+)
+
+type paramsKey struct{}
+
+// Params returns the path parameters matched for the request.
+func Params(r *http.Request) map[string]string {
+	params, _ := r.Context().Value(paramsKey{}).(map[string]string)
+	return params
+}
+
+type route struct {
+	method   string
+	segments []string
+	handler  http.Handler
+}
+
+// Router matches requests by method and path pattern, such as
+// "/users/:id/posts".
+type Router struct {
+	routes   []route
+	NotFound http.Handler
+}
+
+// New returns an empty router.
+func New() *Router {
+	return &Router{NotFound: http.NotFoundHandler()}
+}
+
+// Handle registers handler for method and pattern.
+func (rt *Router) Handle(method, pattern string, handler http.Handler) {
+	rt.routes = append(rt.routes, route{
+		method:   strings.ToUpper(method),
+		segments: split(pattern),
+		handler:  handler,
+	})
+}
+
+// HandleFunc registers a handler function for method and pattern.
+func (rt *Router) HandleFunc(method, pattern string, fn func(http.ResponseWriter, *http.Request)) {
+	rt.Handle(method, pattern, http.HandlerFunc(fn))
+}
+
+func (rt *Router) ServeHTTP(w http.ResponseWriter, r *http.Request) {
+	path := split(r.URL.Path)
+	allowed := make([]string, 0, 2)
+	for _, route := range rt.routes {
+		params, ok := match(route.segments, path)
+		if !ok {
+			continue
+		}
+		if route.method != r.Method {
+			allowed = append(allowed, route.method)
+			continue
+		}
+		ctx := context.WithValue(r.Context(), paramsKey{}, params)
+		route.handler.ServeHTTP(w, r.WithContext(ctx))
+		return
+	}
+	if len(allowed) > 0 {
+		w.Header().Set("Allow", strings.Join(allowed, ", "))
+		http.Error(w, http.StatusText(http.StatusMethodNotAllowed), http.StatusMethodNotAllowed)
+		return
+	}
+	rt.NotFound.ServeHTTP(w, r)
+}
+
+func split(path string) []string {
+	trimmed := strings.Trim(path, "/")
+	if trimmed == "" {
+		return nil
+	}
+	return strings.Split(trimmed, "/")
+}
+
+func match(pattern, path []string) (map[string]string, bool) {
+	params := map[string]string{}
+	for i, segment := range pattern {
+		if segment == "*" {
+			params["*"] = strings.Join(path[i:], "/")
+			return params, true
+		}
+		if i >= len(path) {
+			return nil, false
+		}
+		if strings.HasPrefix(segment, ":") {
+			params[segment[1:]] = path[i]
+			continue
+		}
+		if segment != path[i] {
+			return nil, false
+		}
+	}
+	return params, len(pattern) == len(path)
+}
@@ -0,0 +1,115 @@
+package com.example.inventory;
+
+import java.math.BigDecimal;
+import java.math.RoundingMode;
+import java.util.ArrayList;
+import java.util.Collections;
+import java.util.HashMap;
+import java.util.List;
+import java.util.Map;
+import java.util.Objects;
+import java.util.Optional;
+
+/**
+ * Tracks stock levels per warehouse and reserves items for orders.
+ */
+public final class Inventory {
+
+    public record Sku(String code) {
+        public Sku {
+            Objects.requireNonNull(code, "code");
+            if (code.isBlank()) {
+                throw new IllegalArgumentException("SKU code must not be blank");
+            }
+        }
+    }
+
+    public static final class InsufficientStockException extends RuntimeException {
+        private final Sku sku;
+        private final int requested;
+        private final int available;
+
+        InsufficientStockException(Sku sku, int requested, int available) {
+            super(String.format("SKU %s: requested %d, only %d available", sku.code(), requested, available));
+            this.sku = sku;
+            this.requested = requested;
+            this.available = available;
+        }
+
+        public Sku getSku() {
+            return sku;
+        }
+
+        public int getShortfall() {
+            return requested - available;
+        }
+    }
+
+    private final Map<String, Map<Sku, Integer>> stock = new HashMap<>();
+    private final Map<Sku, BigDecimal> prices = new HashMap<>();
+
+    public void receive(String warehouse, Sku sku, int quantity) {
+        if (quantity <= 0) {
+            throw new IllegalArgumentException("quantity must be positive: " + quantity);
+        }
+        stock.computeIfAbsent(warehouse, w -> new HashMap<>()).merge(sku, quantity, Integer::sum);
+    }
+
+    public int available(Sku sku) {
+        int total = 0;
+        for (Map<Sku, Integer> levels : stock.values()) {
+            total += levels.getOrDefault(sku, 0);
+        }
+        return total;
+    }
+
+    public Optional<String> bestWarehouse(Sku sku, int quantity) {
+        String best = null;
+        int bestLevel = -1;
+        for (Map.Entry<String, Map<Sku, Integer>> entry : stock.entrySet()) {
+            int level = entry.getValue().getOrDefault(sku, 0);
+            if (level >= quantity && level > bestLevel) {
+                best = entry.getKey();
+                bestLevel = level;
+            }
+        }
+        return Optional.ofNullable(best);
+    }
+
+    public synchronized List<String> reserve(Sku sku, int quantity) {
+        int available = available(sku);
+        if (available < quantity) {
+            throw new InsufficientStockException(sku, quantity, available);
+        }
+        List<String> sources = new ArrayList<>();
+        int remaining = quantity;
+        for (Map.Entry<String, Map<Sku, Integer>> entry : stock.entrySet()) {
+            Map<Sku, Integer> levels = entry.getValue();
+            int take = Math.min(remaining, levels.getOrDefault(sku, 0));
+            if (take > 0) {
+                levels.put(sku, levels.get(sku) - take);
+                sources.add(entry.getKey());
+                remaining -= take;
+            }
+            if (remaining == 0) {
+                break;
+            }
+        }
+        return Collections.unmodifiableList(sources);
+    }
+
+    public void setPrice(Sku sku, BigDecimal price) {
+        prices.put(sku, price.setScale(2, RoundingMode.HALF_EVEN));
+    }
+
+    public BigDecimal stockValue() {
+        BigDecimal total = BigDecimal.ZERO;
+        for (Map<Sku, Integer> levels : stock.values()) {
+            for (Map.Entry<Sku, Integer> level : levels.entrySet()) {
+                BigDecimal price = prices.getOrDefault(level.getKey(), BigDecimal.ZERO);
+                total = total.add(price.multiply(BigDecimal.valueOf(level.getValue())));
+            }
+        }
+        return total;
+    }
+}
@@ -0,0 +1,90 @@
+package com.example.shipping;
+
+import java.math.BigDecimal;
+import java.math.RoundingMode;
+import java.util.Comparator;
+import java.util.EnumMap;
+import java.util.List;
+import java.util.Map;
+import java.util.Objects;
+import java.util.Optional;
+
+/**
+ * Quotes shipping rates for parcels from a table of carrier tariffs.
+ */
+public final class RateCalculator {
+
+    public enum Zone { DOMESTIC, CONTINENTAL, INTERNATIONAL }
+
+    public record Parcel(double weightKg, double lengthCm, double widthCm, double heightCm) {
+        public Parcel {
+            if (weightKg <= 0) {
+                throw new IllegalArgumentException("weight must be positive");
+            }
+        }
+
+        /** Volumetric weight with the usual 5000 cm3/kg divisor. */
+        public double volumetricWeightKg() {
+            return lengthCm * widthCm * heightCm / 5000.0;
+        }
+
+        public double chargeableWeightKg() {
+            return Math.max(weightKg, volumetricWeightKg());
+        }
+    }
+
+    public record Tariff(String carrier, BigDecimal baseFee, BigDecimal perKg, double maxWeightKg) {}
+
+    public record Quote(String carrier, BigDecimal price) {}
+
+    private final Map<Zone, List<Tariff>> tariffs = new EnumMap<>(Zone.class);
+    private final BigDecimal fuelSurcharge;
+
+    public RateCalculator(BigDecimal fuelSurcharge) {
+        this.fuelSurcharge = Objects.requireNonNull(fuelSurcharge, "fuelSurcharge");
+    }
+
+    public RateCalculator addTariffs(Zone zone, List<Tariff> zoneTariffs) {
+        tariffs.put(zone, List.copyOf(zoneTariffs));
+        return this;
+    }
+
+    public List<Quote> quotes(Parcel parcel, Zone zone) {
+        double weight = parcel.chargeableWeightKg();
+        return tariffs.getOrDefault(zone, List.of()).stream()
+                .filter(tariff -> weight <= tariff.maxWeightKg())
+                .map(tariff -> new Quote(tariff.carrier(), price(tariff, weight)))
+                .sorted(Comparator.comparing(Quote::price).thenComparing(Quote::carrier))
+                .toList();
+    }
+
+    public Optional<Quote> cheapest(Parcel parcel, Zone zone) {
+        return quotes(parcel, zone).stream().findFirst();
+    }
+
+    private BigDecimal price(Tariff tariff, double weightKg) {
+        // Carriers bill per started half kilogram
+        BigDecimal billedKg = BigDecimal.valueOf(Math.ceil(weightKg * 2) / 2);
+        BigDecimal net = tariff.baseFee().add(tariff.perKg().multiply(billedKg));
+        BigDecimal surcharge = net.multiply(fuelSurcharge);
+        return net.add(surcharge).setScale(2, RoundingMode.HALF_UP);
+    }
+
+    public static void main(String[] args) {
+        RateCalculator calculator = new RateCalculator(new BigDecimal("0.12"))
+                .addTariffs(Zone.DOMESTIC, List.of(
+                        new Tariff("Parcelo", new BigDecimal("4.90"), new BigDecimal("0.80"), 30),
+                        new Tariff("SwiftShip", new BigDecimal("3.50"), new BigDecimal("1.10"), 20)))
+                .addTariffs(Zone.INTERNATIONAL, List.of(
+                        new Tariff("GlobeFreight", new BigDecimal("18.00"), new BigDecimal("4.25"), 50)));
+
+        Parcel parcel = new Parcel(2.3, 40, 30, 20);
+        for (Quote quote : calculator.quotes(parcel, Zone.DOMESTIC)) {
+            System.out.printf("%-12s %8s%n", quote.carrier(), quote.price());
+        }
+        calculator.cheapest(parcel, Zone.CONTINENTAL)
+                .ifPresentOrElse(
+                        quote -> System.out.println("Cheapest: " + quote),
+                        () -> System.out.println("No carrier serves that zone"));
+    }
+}
@@ -0,0 +1,105 @@
+'use strict';
+
+const { EventEmitter } = require('events');
+
+const DEFAULT_OPTIONS = {
+  concurrency: 4,
+  retries: 2,
+  retryDelayMs: 250,
+  timeoutMs: 10_000,
+};
+
+/**
+ * A promise queue running at most `concurrency` jobs at once.
+ * Failed jobs are retried with a linear backoff before being rejected.
+ */
+class JobQueue extends EventEmitter {
+  constructor(options = {}) {
+    super();
+    this.options = { ...DEFAULT_OPTIONS, ...options };
+    this.pending = [];
+    this.running = 0;
+    this.completed = 0;
+    this.failed = 0;
+  }
+
+  /**
+   * Queue `task`, a function returning a promise.
+   * @returns {Promise<*>} settles with the task's final outcome
+   */
+  push(task, label = `job-${this.pending.length + this.running + 1}`) {
+    return new Promise((resolve, reject) => {
+      this.pending.push({ task, label, attempt: 0, resolve, reject });
+      this.#drain();
+    });
+  }
+
+  get size() {
+    return this.pending.length + this.running;
+  }
+
+  async idle() {
+    if (this.size === 0) return;
+    await new Promise((resolve) => this.once('idle', resolve));
+  }
+
+  #drain() {
+    while (this.running < this.options.concurrency && this.pending.length > 0) {
+      const job = this.pending.shift();
+      this.running += 1;
+      this.#run(job).finally(() => {
+        this.running -= 1;
+        if (this.size === 0) this.emit('idle');
+        this.#drain();
+      });
+    }
+  }
+
+  async #run(job) {
+    const { retries, retryDelayMs, timeoutMs } = this.options;
+    for (;;) {
+      job.attempt += 1;
+      try {
+        const result = await withTimeout(job.task(), timeoutMs, job.label);
+        this.completed += 1;
+        this.emit('done', { label: job.label, attempt: job.attempt });
+        job.resolve(result);
+        return;
+      } catch (error) {
+        if (job.attempt > retries) {
+          this.failed += 1;
+          this.emit('failed', { label: job.label, error });
+          job.reject(error);
+          return;
+        }
+        this.emit('retry', { label: job.label, attempt: job.attempt, error });
+        await sleep(retryDelayMs * job.attempt);
+      }
+    }
+  }
+}
+
+function sleep(ms) {
+  return new Promise((resolve) => setTimeout(resolve, ms));
+}
+
+function withTimeout(promise, ms, label) {
+  let timer;
+  const timeout = new Promise((_, reject) => {
+    timer = setTimeout(() => reject(new Error(`${label} timed out after ${ms}ms`)), ms);
+  });
+  return Promise.race([promise, timeout]).finally(() => clearTimeout(timer));
+}
+
+module.exports = { JobQueue, sleep, withTimeout };
+
+if (require.main === module) {
+  const queue = new JobQueue({ concurrency: 2 });
+  queue.on('retry', ({ label, attempt }) => console.warn(`${label}: retry #${attempt}`));
+  for (let i = 0; i < 5; i++) {
+    queue.push(() => sleep(Math.random() * 100).then(() => i * i)).then((value) => {
+      console.log(`job ${i} -> ${value}`);
+    });
+  }
+  queue.idle().then(() => console.log(`done: ${queue.completed} ok, ${queue.failed} failed`));
+}
@@ -0,0 +1,106 @@
+'use strict';
+
+class ValidationError extends Error {
+  constructor(errors) {
+    super(`Validation failed: ${errors.map((e) => `${e.path} ${e.message}`).join('; ')}`);
+    this.name = 'ValidationError';
+    this.errors = errors;
+  }
+}
+
+const checks = {
+  string(value) {
+    return typeof value === 'string' ? null : 'must be a string';
+  },
+  number(value) {
+    return typeof value === 'number' && Number.isFinite(value) ? null : 'must be a number';
+  },
+  boolean(value) {
+    return typeof value === 'boolean' ? null : 'must be a boolean';
+  },
+  array(value) {
+    return Array.isArray(value) ? null : 'must be an array';
+  },
+  object(value) {
+    return value !== null && typeof value === 'object' && !Array.isArray(value)
+      ? null
+      : 'must be an object';
+  },
+};
+
+function validateValue(schema, value, path, errors) {
+  if (value === undefined || value === null) {
+    if (schema.required) {
+      errors.push({ path, message: 'is required' });
+    }
+    return;
+  }
+
+  const typeError = checks[schema.type] ? checks[schema.type](value) : null;
+  if (typeError) {
+    errors.push({ path, message: typeError });
+    return;
+  }
+
+  if (schema.type === 'string') {
+    if (schema.minLength !== undefined && value.length < schema.minLength) {
+      errors.push({ path, message: `must be at least ${schema.minLength} characters` });
+    }
+    if (schema.pattern && !schema.pattern.test(value)) {
+      errors.push({ path, message: `must match ${schema.pattern}` });
+    }
+    if (schema.enum && !schema.enum.includes(value)) {
+      errors.push({ path, message: `must be one of ${schema.enum.join(', ')}` });
+    }
+  }
+
+  if (schema.type === 'number') {
+    if (schema.min !== undefined && value < schema.min) {
+      errors.push({ path, message: `must be >= ${schema.min}` });
+    }
+    if (schema.max !== undefined && value > schema.max) {
+      errors.push({ path, message: `must be <= ${schema.max}` });
+    }
+  }
+
+  if (schema.type === 'array' && schema.items) {
+    value.forEach((item, index) => validateValue(schema.items, item, `${path}[${index}]`, errors));
+  }
+
+  if (schema.type === 'object' && schema.properties) {
+    for (const [key, child] of Object.entries(schema.properties)) {
+      validateValue(child, value[key], path ? `${path}.${key}` : key, errors);
+    }
+    if (schema.additionalProperties === false) {
+      for (const key of Object.keys(value)) {
+        if (!(key in schema.properties)) {
+          errors.push({ path: path ? `${path}.${key}` : key, message: 'is not allowed' });
+        }
+      }
+    }
+  }
+}
+
+function validate(schema, value) {
+  const errors = [];
+  validateValue(schema, value, '', errors);
+  if (errors.length > 0) {
+    throw new ValidationError(errors);
+  }
+  return value;
+}
+
+const userSchema = {
+  type: 'object',
+  required: true,
+  additionalProperties: false,
+  properties: {
+    name: { type: 'string', required: true, minLength: 1 },
+    email: { type: 'string', required: true, pattern: /^[^@\s]+@[^@\s]+$/ },
+    age: { type: 'number', min: 0, max: 150 },
+    role: { type: 'string', enum: ['admin', 'editor', 'viewer'] },
+    tags: { type: 'array', items: { type: 'string' } },
+  },
+};
+
+module.exports = { validate, ValidationError, userSchema };
@@ -0,0 +1,108 @@
+"""Parse and summarize web server access logs."""
+
+from __future__ import annotations
+
+import argparse
+import re
+import sys
+from collections import Counter, defaultdict
+from dataclasses import dataclass, field
+from datetime import datetime
+from typing import Iterable, Iterator
+
+LINE_RE = re.compile(
+    r'(?P<ip>\S+) \S+ \S+ \[(?P<time>[^\]]+)\] '
+    r'"(?P<method>[A-Z]+) (?P<path>\S+) [^"]*" (?P<status>\d{3}) (?P<size>\d+|-)'
+)
+TIME_FORMAT = "%d/%b/%Y:%H:%M:%S %z"
+
+
+@dataclass
+class Request:
+    ip: str
+    time: datetime
+    method: str
+    path: str
+    status: int
+    size: int
+
+
+@dataclass
+class Summary:
+    total: int = 0
+    bytes_sent: int = 0
+    statuses: Counter = field(default_factory=Counter)
+    paths: Counter = field(default_factory=Counter)
+    per_hour: dict = field(default_factory=lambda: defaultdict(int))
+
+    def add(self, request: Request) -> None:
+        self.total += 1
+        self.bytes_sent += request.size
+        self.statuses[request.status] += 1
+        self.paths[request.path.split("?", 1)[0]] += 1
+        self.per_hour[request.time.strftime("%Y-%m-%d %H:00")] += 1
+
+    @property
+    def error_rate(self) -> float:
+        if not self.total:
+            return 0.0
+        errors = sum(n for status, n in self.statuses.items() if status >= 500)
+        return errors / self.total
+
+
+def parse_lines(lines: Iterable[str]) -> Iterator[Request]:
+    """Yield requests, skipping lines that do not match the log format."""
+    for number, line in enumerate(lines, start=1):
+        match = LINE_RE.match(line)
+        if match is None:
+            print(f"warning: line {number} is malformed", file=sys.stderr)
+            continue
+        size = match["size"]
+        yield Request(
+            ip=match["ip"],
+            time=datetime.strptime(match["time"], TIME_FORMAT),
+            method=match["method"],
+            path=match["path"],
+            status=int(match["status"]),
+            size=0 if size == "-" else int(size),
+        )
+
+
+def summarize(requests: Iterable[Request]) -> Summary:
+    summary = Summary()
+    for request in requests:
+        summary.add(request)
+    return summary
+
+
+def render(summary: Summary, top: int = 10) -> str:
+    lines = [
+        f"requests:   {summary.total}",
+        f"bytes sent: {summary.bytes_sent:,}",
+        f"error rate: {summary.error_rate:.2%}",
+        "",
+        "top paths:",
+    ]
+    for path, count in summary.paths.most_common(top):
+        lines.append(f"  {count:>8}  {path}")
+    lines.append("")
+    lines.append("status codes:")
+    for status, count in sorted(summary.statuses.items()):
+        lines.append(f"  {status}: {count}")
+    return "\n".join(lines)
+
+
+def main(argv: list[str] | None = None) -> int:
+    parser = argparse.ArgumentParser(description=__doc__)
+    parser.add_argument("logfile", nargs="?", type=argparse.FileType("r"), default=sys.stdin)
+    parser.add_argument("--top", type=int, default=10, help="number of paths to show")
+    args = parser.parse_args(argv)
+
+    with args.logfile as handle:
+        summary = summarize(parse_lines(handle))
+    print(render(summary, top=args.top))
+    return 1 if summary.error_rate > 0.05 else 0
+
+
+if __name__ == "__main__":
+    sys.exit(main())
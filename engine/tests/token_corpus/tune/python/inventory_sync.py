@@ -0,0 +1,98 @@
+"""Reconcile warehouse stock counts with the store's product catalog."""
+
+from __future__ import annotations
+
+import csv
+import logging
+from dataclasses import dataclass, field
+from decimal import Decimal
+from pathlib import Path
+from typing import Iterable, Iterator
+
+log = logging.getLogger(__name__)
+
+
+@dataclass(frozen=True)
+class StockRecord:
+    sku: str
+    warehouse: str
+    on_hand: int
+    reserved: int = 0
+
+    @property
+    def available(self) -> int:
+        return max(self.on_hand - self.reserved, 0)
+
+
+@dataclass
+class Product:
+    sku: str
+    title: str
+    price: Decimal
+    stock: int = 0
+    active: bool = True
+    warehouses: dict[str, int] = field(default_factory=dict)
+
+
+@dataclass
+class SyncReport:
+    updated: list[str] = field(default_factory=list)
+    deactivated: list[str] = field(default_factory=list)
+    unknown: list[str] = field(default_factory=list)
+
+    def __str__(self) -> str:
+        return (
+            f"{len(self.updated)} updated, {len(self.deactivated)} deactivated, "
+            f"{len(self.unknown)} unknown SKUs"
+        )
+
+
+def read_stock(path: Path) -> Iterator[StockRecord]:
+    with path.open(newline="", encoding="utf-8") as handle:
+        for row_number, row in enumerate(csv.DictReader(handle), start=2):
+            try:
+                yield StockRecord(
+                    sku=row["sku"].strip().upper(),
+                    warehouse=row["warehouse"].strip(),
+                    on_hand=int(row["on_hand"]),
+                    reserved=int(row.get("reserved") or 0),
+                )
+            except (KeyError, ValueError) as error:
+                log.warning("%s:%d: skipping malformed row (%s)", path, row_number, error)
+
+
+def sync(catalog: dict[str, Product], records: Iterable[StockRecord]) -> SyncReport:
+    report = SyncReport()
+    totals: dict[str, dict[str, int]] = {}
+    for record in records:
+        if record.sku not in catalog:
+            report.unknown.append(record.sku)
+            continue
+        totals.setdefault(record.sku, {})[record.warehouse] = record.available
+
+    for sku, product in catalog.items():
+        by_warehouse = totals.get(sku, {})
+        stock = sum(by_warehouse.values())
+        if stock != product.stock or by_warehouse != product.warehouses:
+            product.stock = stock
+            product.warehouses = by_warehouse
+            report.updated.append(sku)
+        if stock == 0 and product.active:
+            product.active = False
+            report.deactivated.append(sku)
+        elif stock > 0 and not product.active:
+            product.active = True
+
+    return report
+
+
+if __name__ == "__main__":
+    import sys
+
+    logging.basicConfig(level=logging.INFO, format="%(levelname)s %(message)s")
+    catalog = {
+        "MUG-01": Product("MUG-01", "Enamel mug", Decimal("12.50")),
+        "TEE-M": Product("TEE-M", "T-shirt, medium", Decimal("24.00"), stock=3),
+    }
+    result = sync(catalog, read_stock(Path(sys.argv[1])))
+    print(result)
@@ -0,0 +1,91 @@
+# frozen_string_literal: true
+
+require "json"
+require "time"
+
+module Billing
+  class InvalidPlan < StandardError; end
+
+  Plan = Struct.new(:name, :monthly_cents, :seats_included, :extra_seat_cents, keyword_init: true) do
+    def price_for(seats)
+      extra = [seats - seats_included, 0].max
+      monthly_cents + extra * extra_seat_cents
+    end
+  end
+
+  PLANS = {
+    "starter" => Plan.new(name: "Starter", monthly_cents: 0, seats_included: 3, extra_seat_cents: 0),
+    "team" => Plan.new(name: "Team", monthly_cents: 4_900, seats_included: 10, extra_seat_cents: 500),
+    "business" => Plan.new(name: "Business", monthly_cents: 19_900, seats_included: 50, extra_seat_cents: 400)
+  }.freeze
+
+  class Invoice
+    attr_reader :customer, :lines, :issued_at
+
+    def initialize(customer:, issued_at: Time.now.utc)
+      @customer = customer
+      @issued_at = issued_at
+      @lines = []
+    end
+
+    def add_line(description, cents, quantity: 1)
+      raise ArgumentError, "quantity must be positive" unless quantity.positive?
+
+      @lines << { description: description, cents: cents, quantity: quantity }
+      self
+    end
+
+    def subtotal_cents
+      lines.sum { |line| line[:cents] * line[:quantity] }
+    end
+
+    def tax_cents(rate)
+      (subtotal_cents * rate).round
+    end
+
+    def total_cents(tax_rate: 0.0)
+      subtotal_cents + tax_cents(tax_rate)
+    end
+
+    def to_h(tax_rate: 0.0)
+      {
+        customer: customer,
+        issued_at: issued_at.iso8601,
+        lines: lines,
+        subtotal: format_cents(subtotal_cents),
+        tax: format_cents(tax_cents(tax_rate)),
+        total: format_cents(total_cents(tax_rate: tax_rate))
+      }
+    end
+
+    def to_json(*args)
+      to_h.to_json(*args)
+    end
+
+    private
+
+    def format_cents(cents)
+      format("$%<dollars>d.%<cents>02d", dollars: cents / 100, cents: cents % 100)
+    end
+  end
+
+  module_function
+
+  def invoice_for(customer, plan_key, seats:, coupons: [])
+    plan = PLANS.fetch(plan_key) { raise InvalidPlan, "unknown plan #{plan_key.inspect}" }
+    invoice = Invoice.new(customer: customer)
+    invoice.add_line("#{plan.name} plan", plan.monthly_cents)
+    extra = seats - plan.seats_included
+    invoice.add_line("Extra seats", plan.extra_seat_cents, quantity: extra) if extra.positive?
+    coupons.each do |coupon|
+      discount = (invoice.subtotal_cents * coupon.fetch(:percent) / 100.0).round
+      invoice.add_line("Coupon #{coupon.fetch(:code)}", -discount)
+    end
+    invoice
+  end
+end
+
+if $PROGRAM_NAME == __FILE__
+  invoice = Billing.invoice_for("acme", "team", seats: 14, coupons: [{ code: "WELCOME", percent: 10 }])
+  puts JSON.pretty_generate(invoice.to_h(tax_rate: 0.2))
+end
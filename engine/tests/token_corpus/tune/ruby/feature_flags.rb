@@ -0,0 +1,101 @@
+# frozen_string_literal: true
+
+require "digest"
+require "set"
+
+module Flags
+  class UnknownFlag < KeyError; end
+
+  # A feature flag rolled out to a percentage of actors, with explicit
+  # allow and deny lists that win over the percentage.
+  class Flag
+    attr_reader :name, :percentage, :allowed, :denied
+
+    def initialize(name, percentage: 0, allowed: [], denied: [])
+      raise ArgumentError, "percentage must be 0..100" unless (0..100).cover?(percentage)
+
+      @name = name.to_sym
+      @percentage = percentage
+      @allowed = Set.new(allowed.map(&:to_s))
+      @denied = Set.new(denied.map(&:to_s))
+    end
+
+    def enabled_for?(actor_id)
+      id = actor_id.to_s
+      return false if denied.include?(id)
+      return true if allowed.include?(id)
+
+      bucket(id) < percentage
+    end
+
+    def with_percentage(value)
+      self.class.new(name, percentage: value, allowed: allowed.to_a, denied: denied.to_a)
+    end
+
+    private
+
+    # Stable bucket in 0...100 so an actor keeps its answer as the
+    # percentage grows.
+    def bucket(id)
+      Digest::SHA256.hexdigest("#{name}:#{id}")[0, 8].to_i(16) % 100
+    end
+  end
+
+  class Registry
+    include Enumerable
+
+    def initialize
+      @flags = {}
+      @mutex = Mutex.new
+      @listeners = []
+    end
+
+    def define(name, **options)
+      flag = Flag.new(name, **options)
+      @mutex.synchronize { @flags[flag.name] = flag }
+      notify(flag)
+      flag
+    end
+
+    def fetch(name)
+      @mutex.synchronize { @flags.fetch(name.to_sym) }
+    rescue KeyError
+      raise UnknownFlag, "no flag named #{name}"
+    end
+
+    def enabled?(name, actor_id)
+      fetch(name).enabled_for?(actor_id)
+    end
+
+    def rollout(name, percentage)
+      flag = @mutex.synchronize do
+        @flags[name.to_sym] = fetch_unlocked(name).with_percentage(percentage)
+      end
+      notify(flag)
+      flag
+    end
+
+    def on_change(&block)
+      @listeners << block
+      self
+    end
+
+    def each(&block)
+      @mutex.synchronize { @flags.values.dup }.each(&block)
+    end
+
+    def to_h
+      map { |flag| [flag.name, flag.percentage] }.to_h
+    end
+
+    private
+
+    def fetch_unlocked(name)
+      @flags.fetch(name.to_sym) { raise UnknownFlag, "no flag named #{name}" }
+    end
+
+    def notify(flag)
+      @listeners.each { |listener| listener.call(flag) }
+    end
+  end
+end
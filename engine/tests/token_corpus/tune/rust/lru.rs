@@ -0,0 +1,110 @@
+//! A small LRU cache with time-based expiry
+
+use std::collections::{HashMap, VecDeque};
+use std::hash::Hash;
+use std::time::{Duration, Instant};
+
+/// Entry stored alongside the moment it was inserted
+#[derive(Debug, Clone)]
+struct Entry<V> {
+    value: V,
+    inserted: Instant,
+}
+
+/// Least-recently-used cache whose entries expire after `ttl`
+pub struct LruCache<K, V> {
+    capacity: usize,
+    ttl: Duration,
+    entries: HashMap<K, Entry<V>>,
+    order: VecDeque<K>,
+}
+
+impl<K: Hash + Eq + Clone, V: Clone> LruCache<K, V> {
+    /// Create a cache holding at most `capacity` entries
+    pub fn new(capacity: usize, ttl: Duration) -> Self {
+        assert!(capacity > 0, "capacity must be positive");
+        Self { capacity, ttl, entries: HashMap::with_capacity(capacity), order: VecDeque::new() }
+    }
+
+    /// Look up `key`, refreshing its position if it is still fresh
+    pub fn get(&mut self, key: &K) -> Option<V> {
+        let expired = match self.entries.get(key) {
+            Some(entry) => entry.inserted.elapsed() > self.ttl,
+            None => return None,
+        };
+        if expired {
+            self.remove(key);
+            return None;
+        }
+        self.touch(key);
+        self.entries.get(key).map(|entry| entry.value.clone())
+    }
+
+    /// Insert `value`, evicting the least recently used entry when full
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        let previous = self.entries.insert(
+            key.clone(),
+            Entry { value, inserted: Instant::now() },
+        );
+        if previous.is_some() {
+            self.touch(&key);
+        } else {
+            self.order.push_back(key);
+            while self.order.len() > self.capacity {
+                if let Some(oldest) = self.order.pop_front() {
+                    self.entries.remove(&oldest);
+                }
+            }
+        }
+        previous.map(|entry| entry.value)
+    }
+
+    /// Drop `key` from the cache
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        self.order.retain(|k| k != key);
+        self.entries.remove(key).map(|entry| entry.value)
+    }
+
+    /// Drop every entry older than the time to live
+    pub fn purge_expired(&mut self) -> usize {
+        let ttl = self.ttl;
+        let before = self.entries.len();
+        self.entries.retain(|_, entry| entry.inserted.elapsed() <= ttl);
+        let entries = &self.entries;
+        self.order.retain(|k| entries.contains_key(k));
+        before - self.entries.len()
+    }
+
+    fn touch(&mut self, key: &K) {
+        if let Some(pos) = self.order.iter().position(|k| k == key) {
+            if let Some(k) = self.order.remove(pos) {
+                self.order.push_back(k);
+            }
+        }
+    }
+
+    /// Number of entries, expired ones included
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn evicts_least_recently_used() {
+        let mut cache = LruCache::new(2, Duration::from_secs(60));
+        cache.insert("a", 1);
+        cache.insert("b", 2);
+        assert_eq!(cache.get(&"a"), Some(1));
+        cache.insert("c", 3);
+        assert_eq!(cache.get(&"b"), None);
+        assert_eq!(cache.len(), 2);
+    }
+}
@@ -0,0 +1,117 @@
+//! Semantic version parsing and comparison
+
+use std::cmp::Ordering;
+use std::fmt;
+use std::str::FromStr;
+
+/// A `MAJOR.MINOR.PATCH[-PRERELEASE]` version
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Version {
+    pub major: u64,
+    pub minor: u64,
+    pub patch: u64,
+    pub pre: Vec<Identifier>,
+}
+
+/// One dot-separated part of a pre-release
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Identifier {
+    Numeric(u64),
+    Alpha(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseError {
+    Empty,
+    MissingPart(&'static str),
+    InvalidNumber(String),
+    LeadingZero(String),
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Empty => write!(f, "empty version"),
+            Self::MissingPart(part) => write!(f, "missing {} version", part),
+            Self::InvalidNumber(text) => write!(f, "invalid number: {}", text),
+            Self::LeadingZero(text) => write!(f, "leading zero in {}", text),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+fn number(text: &str) -> Result<u64, ParseError> {
+    if text.len() > 1 && text.starts_with('0') {
+        return Err(ParseError::LeadingZero(text.to_owned()));
+    }
+    text.parse()
+        .map_err(|_| ParseError::InvalidNumber(text.to_owned()))
+}
+
+impl FromStr for Version {
+    type Err = ParseError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let text = text.trim().trim_start_matches('v');
+        if text.is_empty() {
+            return Err(ParseError::Empty);
+        }
+        let text = text.split('+').next().unwrap_or(text);
+        let (core, pre) = match text.split_once('-') {
+            Some((core, pre)) => (core, Some(pre)),
+            None => (text, None),
+        };
+        let mut parts = core.split('.');
+        let mut next = |name| parts.next().ok_or(ParseError::MissingPart(name)).and_then(number);
+        let (major, minor, patch) = (next("major")?, next("minor")?, next("patch")?);
+        let pre = pre
+            .map(|pre| {
+                pre.split('.')
+                    .map(|part| match part.parse() {
+                        Ok(n) => Identifier::Numeric(n),
+                        Err(_) => Identifier::Alpha(part.to_owned()),
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        Ok(Self { major, minor, patch, pre })
+    }
+}
+
+impl Ord for Identifier {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (Self::Numeric(a), Self::Numeric(b)) => a.cmp(b),
+            (Self::Numeric(_), Self::Alpha(_)) => Ordering::Less,
+            (Self::Alpha(_), Self::Numeric(_)) => Ordering::Greater,
+            (Self::Alpha(a), Self::Alpha(b)) => a.cmp(b),
+        }
+    }
+}
+
+impl PartialOrd for Identifier {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Version {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.major, self.minor, self.patch)
+            .cmp(&(other.major, other.minor, other.patch))
+            .then_with(|| match (self.pre.is_empty(), other.pre.is_empty()) {
+                // A release sorts after its pre-releases
+                (true, true) => Ordering::Equal,
+                (true, false) => Ordering::Greater,
+                (false, true) => Ordering::Less,
+                (false, false) => self.pre.cmp(&other.pre),
+            })
+    }
+}
+
+impl PartialOrd for Version {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
@@ -0,0 +1,101 @@
+export type Listener<S> = (state: S, previous: S) => void;
+export type Reducer<S, A> = (state: S, action: A) => S;
+export type Middleware<S, A> = (store: Store<S, A>) => (next: (action: A) => void) => (action: A) => void;
+
+export interface Store<S, A> {
+  getState(): S;
+  dispatch(action: A): void;
+  subscribe(listener: Listener<S>): () => void;
+}
+
+export function createStore<S, A>(
+  reducer: Reducer<S, A>,
+  initial: S,
+  middleware: Middleware<S, A>[] = [],
+): Store<S, A> {
+  let state = initial;
+  let dispatching = false;
+  const listeners = new Set<Listener<S>>();
+
+  const store: Store<S, A> = {
+    getState: () => state,
+    dispatch: (action) => baseDispatch(action),
+    subscribe(listener) {
+      listeners.add(listener);
+      return () => listeners.delete(listener);
+    },
+  };
+
+  function baseDispatch(action: A): void {
+    if (dispatching) {
+      throw new Error("Reducers may not dispatch actions");
+    }
+    const previous = state;
+    try {
+      dispatching = true;
+      state = reducer(state, action);
+    } finally {
+      dispatching = false;
+    }
+    if (state !== previous) {
+      for (const listener of [...listeners]) {
+        listener(state, previous);
+      }
+    }
+  }
+
+  const chain = middleware.map((m) => m(store));
+  const dispatch = chain.reduceRight<(action: A) => void>((next, m) => m(next), baseDispatch);
+  store.dispatch = dispatch;
+  return store;
+}
+
+export interface Todo {
+  id: number;
+  title: string;
+  done: boolean;
+}
+
+export interface TodoState {
+  todos: Todo[];
+  filter: "all" | "open" | "done";
+  nextId: number;
+}
+
+export type TodoAction =
+  | { type: "add"; title: string }
+  | { type: "toggle"; id: number }
+  | { type: "remove"; id: number }
+  | { type: "filter"; filter: TodoState["filter"] };
+
+export const todoReducer: Reducer<TodoState, TodoAction> = (state, action) => {
+  switch (action.type) {
+    case "add":
+      if (!action.title.trim()) return state;
+      return {
+        ...state,
+        todos: [...state.todos, { id: state.nextId, title: action.title.trim(), done: false }],
+        nextId: state.nextId + 1,
+      };
+    case "toggle":
+      return {
+        ...state,
+        todos: state.todos.map((t) => (t.id === action.id ? { ...t, done: !t.done } : t)),
+      };
+    case "remove":
+      return { ...state, todos: state.todos.filter((t) => t.id !== action.id) };
+    case "filter":
+      return state.filter === action.filter ? state : { ...state, filter: action.filter };
+  }
+};
+
+export const logger: Middleware<TodoState, TodoAction> = (store) => (next) => (action) => {
+  console.debug("dispatch", action.type, action);
+  next(action);
+  console.debug("state", store.getState());
+};
+
+export function visibleTodos(state: TodoState): Todo[] {
+  if (state.filter === "all") return state.todos;
+  return state.todos.filter((t) => t.done === (state.filter === "done"));
+}
@@ -0,0 +1,91 @@
+import { createHash } from "crypto";
+
+export type Role = "owner" | "admin" | "member" | "guest";
+
+export interface User {
+  id: string;
+  email: string;
+  displayName?: string;
+  roles: Role[];
+  createdAt: Date;
+}
+
+export interface Page<T> {
+  items: T[];
+  nextCursor: string | null;
+}
+
+export class NotFoundError extends Error {
+  constructor(public readonly resource: string, public readonly id: string) {
+    super(`${resource} ${id} not found`);
+    this.name = "NotFoundError";
+  }
+}
+
+const ROLE_RANK: Record<Role, number> = { owner: 3, admin: 2, member: 1, guest: 0 };
+
+export function highestRole(user: Pick<User, "roles">): Role {
+  return user.roles.reduce<Role>(
+    (best, role) => (ROLE_RANK[role] > ROLE_RANK[best] ? role : best),
+    "guest",
+  );
+}
+
+export function can(user: User, action: "read" | "write" | "delete"): boolean {
+  const rank = ROLE_RANK[highestRole(user)];
+  switch (action) {
+    case "read":
+      return rank >= ROLE_RANK.guest;
+    case "write":
+      return rank >= ROLE_RANK.member;
+    case "delete":
+      return rank >= ROLE_RANK.admin;
+  }
+}
+
+export class UserStore {
+  private readonly users = new Map<string, User>();
+
+  add(email: string, roles: Role[] = ["member"]): User {
+    const id = createHash("sha256").update(email.toLowerCase()).digest("hex").slice(0, 12);
+    if (this.users.has(id)) {
+      throw new Error(`user with email ${email} already exists`);
+    }
+    const user: User = { id, email, roles, createdAt: new Date() };
+    this.users.set(id, user);
+    return user;
+  }
+
+  get(id: string): User {
+    const user = this.users.get(id);
+    if (!user) throw new NotFoundError("user", id);
+    return user;
+  }
+
+  update(id: string, patch: Partial<Omit<User, "id" | "createdAt">>): User {
+    const updated = { ...this.get(id), ...patch };
+    this.users.set(id, updated);
+    return updated;
+  }
+
+  list(limit = 20, cursor: string | null = null): Page<User> {
+    const sorted = [...this.users.values()].sort((a, b) => a.id.localeCompare(b.id));
+    const start = cursor ? sorted.findIndex((u) => u.id > cursor) : 0;
+    if (start < 0) return { items: [], nextCursor: null };
+    const items = sorted.slice(start, start + limit);
+    const last = items[items.length - 1];
+    return {
+      items,
+      nextCursor: items.length === limit && last ? last.id : null,
+    };
+  }
+
+  async *all(batch = 50): AsyncGenerator<User> {
+    let cursor: string | null = null;
+    do {
+      const page = this.list(batch, cursor);
+      yield* page.items;
+      cursor = page.nextCursor;
+    } while (cursor !== null);
+  }
+}